@@ -2,55 +2,94 @@ mod modules;
 
 use modules::config;
 use modules::cli;
-use modules::database::{storage, comments, analytics};
-use modules::content::{compiler, scanner, markdown, templates, theme};
-use modules::web::{admin, routes, recommender};
+use modules::error;
+use modules::database::{storage, comments, analytics, migrate, geoip};
+use modules::content::{compiler, scanner, markdown, templates, theme, seo, highlight, tags, series, i18n, images, slug, git_sync};
+use modules::web::{admin, routes, recommender, redirects, ip_filter, notifications, openapi, render_cache, live_config};
 
 use actix_web::{middleware, web, App, HttpServer};
 use compiler::Compiler;
 use config::Config;
 use log::info;
+use std::path::PathBuf;
 
-fn main()  -> std::io::Result<()> {
-    // Check for CLI mode
-    if let Some(first_arg) = std::env::args().nth(1) {
-        match first_arg.as_str() {
-            "client" | "server" => {
-                cli::run();
-                return Ok(());
-            }
-            _ => {
-                // Unknown argument, print help
-                cli::run();
-                return Ok(());
+/// Longest the scheduler sleeps between checks for due posts
+const SCHEDULER_MAX_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
 
-            }
-        }
+/// How often visits past `analytics.retention_days` are rolled up
+const ANALYTICS_ROLLUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+fn main() -> std::io::Result<()> {
+    // Any argument goes through the CLI, which starts the server for `server`
+    if std::env::args_os().len() > 1 {
+        cli::run();
+        return Ok(());
     }
 
-    // Web server mode (original behavior)
-    async_web_server()?;
-    Ok(())
+    // No arguments: web server with the default config file
+    async_web_server(PathBuf::from(config::DEFAULT_CONFIG_PATH), Vec::new(), false)
 }
 
+/// Run the web server with the config at `config_path`; `extra_admin_allowlist`
+/// is appended to the configured admin allowlist for this run only, and
+/// `force_full` rebuilds every page on startup even right after a compile
 #[actix_web::main]
-async fn async_web_server() -> std::io::Result<()> {
+async fn async_web_server(
+    config_path: PathBuf,
+    extra_admin_allowlist: Vec<String>,
+    force_full: bool,
+) -> std::io::Result<()> {
     // Initialize logger
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("debug"));
 
     info!("Starting LF Blog...");
 
     // Load configuration
-    let config = Config::load().unwrap_or_else(|e| {
-        eprintln!("Failed to load config, using defaults: {}", e);
+    let mut config = Config::load_file(&config_path).unwrap_or_else(|e| {
+        eprintln!("Failed to load {}, using defaults: {}", config_path.display(), e);
         Config::default()
     });
 
     // Save default config if it doesn't exist
-    if let Err(e) = config.save() {
+    if let Err(e) = config.save_to(&config_path) {
         info!("Failed to save config file: {}", e);
     }
 
+    // LFB_* environment variables win over the file but are never written back to it
+    config.apply_env_overrides();
+
+    match config.validate() {
+        Ok(warnings) => {
+            for warning in &warnings {
+                log::warn!("Config: {}", warning);
+            }
+        }
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("Config error: {}", error);
+            }
+            eprintln!(
+                "Fix {} and restart (check it with `lf_blog server --config {} --validate`)",
+                config_path.display(),
+                config_path.display()
+            );
+            std::process::exit(1);
+        }
+    }
+
+    // Move data out of the locations used by older config keys
+    match migrate::migrate_legacy_databases(&config.paths) {
+        Ok(migrated) => {
+            for step in &migrated {
+                info!("Database migration: {}", step);
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to migrate databases: {}", e);
+            std::process::exit(1);
+        }
+    }
+
     info!("Configuration loaded");
     info!(
         "Server will listen on {}:{}",
@@ -61,10 +100,13 @@ async fn async_web_server() -> std::io::Result<()> {
         "Generated directory: {}",
         config.paths.generated_dir.display()
     );
+    info!("Storage database (pages, attachments, admin sessions): {}", config.paths.storage_db.display());
+    info!("Comments database: {}", config.paths.comments_db.display());
+    info!("Analytics database (page visits): {}", config.paths.analytics_db.display());
 
     // Initial compilation
     let mut compiler = match Compiler::new(config.clone()) {
-        Ok(c) => c,
+        Ok(c) => c.with_force_full(force_full),
         Err(e) => {
             eprintln!("Failed to create compiler: {}", e);
             std::process::exit(1);
@@ -72,6 +114,7 @@ async fn async_web_server() -> std::io::Result<()> {
     };
 
     info!("Performing initial compilation...");
+    let initial_compile_at = chrono::Utc::now();
     match compiler.compile() {
         Ok(result) => {
             info!(
@@ -85,18 +128,182 @@ async fn async_web_server() -> std::io::Result<()> {
         }
     }
 
+    // Admin IP allowlist (CLI entries are not written back to the config file)
+    let mut config = config;
+    config.server.admin_ip_allowlist.extend(extra_admin_allowlist);
+    let admin_allowlist = match ip_filter::parse_allowlist(&config.server.admin_ip_allowlist) {
+        Ok(allowlist) => allowlist,
+        Err(e) => {
+            eprintln!("Invalid admin allowlist entry: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if !admin_allowlist.is_empty() {
+        info!(
+            "Admin access restricted to: {}",
+            config.server.admin_ip_allowlist.join(", ")
+        );
+    }
+    // Forwarded-for headers are only believed from these peers (validated above)
+    let trusted_proxies = ip_filter::parse_allowlist(&config.server.trusted_proxies).unwrap_or_default();
+    let trusted_proxies = web::Data::new(ip_filter::TrustedProxies::new(trusted_proxies));
+
+    // site.*, analytics.* and the listen address are re-read on SIGHUP
+    let live_config = web::Data::new(live_config::LiveConfig::new(config_path.clone(), config.clone()));
+
+    // Load redirect rules
+    let redirect_store = redirects::RedirectStore::load(&config.paths.redirects_file)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load redirects, starting with none: {}", e);
+            redirects::RedirectStore::empty(&config.paths.redirects_file)
+        });
+    let redirect_store = web::Data::new(redirect_store);
+    let login_throttle = web::Data::new(admin::LoginThrottle::new());
+    let admin_credentials = web::Data::new(admin::AdminCredentials::new(config_path.clone(), &config));
+    let comment_rate_limiter = web::Data::new(comments::CommentRateLimiter::new(config.comments.rate_limit_per_hour));
+    let compiler_state = web::Data::new(std::sync::Mutex::new(admin::CompilerState::new()));
+    let render_cache = web::Data::new(std::sync::Mutex::new(render_cache::RenderCache::new(
+        config.server.render_cache_size,
+    )));
+    // Loaded on the first recorded visit; without analytics.geoip_db_path no lookups happen
+    let geoip = web::Data::new(geoip::GeoIp::new(&config));
+
+    #[cfg(unix)]
+    if let Err(e) = live_config::spawn_reload_on_hangup(live_config.clone(), compiler_state.clone()) {
+        eprintln!("Failed to listen for SIGHUP, config reload is unavailable: {}", e);
+    }
+
+    // Publish scheduled articles once their time comes: wake shortly after the
+    // next pending publish time, and at least once a minute to notice new posts
+    let scheduler_config = live_config.clone();
+    actix_web::rt::spawn(async move {
+        let mut since = initial_compile_at;
+        let mut wait = SCHEDULER_MAX_WAIT;
+        loop {
+            actix_web::rt::time::sleep(wait).await;
+            let config = Config::clone(&scheduler_config.current());
+            let now = chrono::Utc::now();
+            let result = actix_web::rt::task::spawn_blocking(move || {
+                Compiler::new(config)
+                    .and_then(|mut compiler| compiler.compile_scheduled(since, now))
+                    .map_err(|e| e.to_string())
+            })
+            .await;
+            wait = SCHEDULER_MAX_WAIT;
+            match result {
+                Ok(Ok(scheduled)) => {
+                    if scheduled.released > 0 {
+                        info!("Published {} scheduled items", scheduled.released);
+                    }
+                    since = now;
+                    if let Some(next) = scheduled.next_publish_at {
+                        let until_next = (next - chrono::Utc::now()).to_std().unwrap_or_default();
+                        wait = wait.min(until_next + std::time::Duration::from_secs(1));
+                    }
+                }
+                Ok(Err(e)) => eprintln!("Scheduled publish failed: {}", e),
+                Err(e) => eprintln!("Scheduled publish task panicked: {}", e),
+            }
+        }
+    });
+
+    // Fold the WAL back into the main database every 5 minutes, and vacuum
+    // it once the weekly interval has passed
+    let checkpoint_db_path = config.paths.storage_db.to_string_lossy().to_string();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(5 * 60));
+        loop {
+            interval.tick().await;
+            let db_path = checkpoint_db_path.clone();
+            let result = actix_web::rt::task::spawn_blocking(move || {
+                storage::StorageDB::new(&db_path)
+                    .and_then(|db| {
+                        db.checkpoint()?;
+                        db.vacuum_if_due(chrono::Utc::now())
+                    })
+                    .map_err(|e| e.to_string())
+            })
+            .await;
+            match result {
+                Ok(Ok(Some(freed))) => info!("Weekly database vacuum freed {} bytes", freed),
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => eprintln!("Database checkpoint failed: {}", e),
+                Err(e) => eprintln!("Database checkpoint task panicked: {}", e),
+            }
+        }
+    });
+
+    // Once a day, fold visits older than the retention period into per-day
+    // counts and drop the raw rows; the first run happens right at startup
+    if config.analytics.rollup_enabled {
+        let analytics_db_path = config.paths.analytics_db.to_string_lossy().to_string();
+        let retention_days = config.analytics.retention_days;
+        actix_web::rt::spawn(async move {
+            let mut interval = actix_web::rt::time::interval(ANALYTICS_ROLLUP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let db_path = analytics_db_path.clone();
+                let result = actix_web::rt::task::spawn_blocking(move || {
+                    let cutoff = chrono::Utc::now().date_naive() - chrono::Duration::days(i64::from(retention_days));
+                    analytics::AnalyticsDB::new(&db_path)
+                        .and_then(|db| db.rollup_visits_before(cutoff))
+                        .map_err(|e| e.to_string())
+                })
+                .await;
+                match result {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(rolled)) => info!("Rolled up {} visits older than {} days", rolled, retention_days),
+                    Ok(Err(e)) => eprintln!("Analytics rollup failed: {}", e),
+                    Err(e) => eprintln!("Analytics rollup task panicked: {}", e),
+                }
+            }
+        });
+    }
+
     // Start HTTP server
     let bind_address = format!("{}:{}", config.server.host, config.server.port);
     info!("Server starting on http://{}", bind_address);
 
+    let shutdown_timeout = std::time::Duration::from_secs(config.server.shutdown_timeout_secs);
+    let shutdown_compiler_state = compiler_state.clone();
+
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(config.clone()))
+            .app_data(redirect_store.clone())
+            .app_data(login_throttle.clone())
+            .app_data(admin_credentials.clone())
+            .app_data(comment_rate_limiter.clone())
+            .app_data(compiler_state.clone())
+            .app_data(render_cache.clone())
+            .app_data(geoip.clone())
+            .app_data(trusted_proxies.clone())
+            .wrap(live_config::CurrentConfig::new(live_config.clone()))
             .wrap(middleware::Logger::default())
+            .wrap(ip_filter::AdminIpFilter::new(admin_allowlist.clone()))
+            .wrap(modules::web::middleware::SecurityHeaders::new(&config))
             .wrap(middleware::Compress::default())
             .configure(routes::configure_routes)
     })
+    .shutdown_timeout(shutdown_timeout.as_secs())
     .bind(&bind_address)?
     .run()
-    .await
+    .await?;
+
+    // Let a running compile finish; an interrupted one is rolled back, so
+    // giving up after the timeout still leaves the previous site intact
+    let compiling = shutdown_compiler_state.lock().map(|state| state.is_compiling).unwrap_or(false);
+    if compiling {
+        info!(
+            "Shutdown delayed: waiting up to {}s for the running compile to finish",
+            shutdown_timeout.as_secs()
+        );
+        if admin::wait_for_compile(&shutdown_compiler_state, shutdown_timeout).await {
+            info!("Compile finished, shutting down");
+        } else {
+            eprintln!("Compile still running after {}s, exiting without it", shutdown_timeout.as_secs());
+            std::process::exit(1);
+        }
+    }
+    Ok(())
 }
\ No newline at end of file
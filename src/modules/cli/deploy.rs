@@ -0,0 +1,363 @@
+//! `client deploy`: package local content, upload it to a running server
+//! through the admin API and recompile it there.
+
+use crate::scanner::Scanner;
+use serde_json::Value;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How often the remote compile status is polled
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Give up waiting for the remote compile after this long
+const COMPILE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// The part of the content directory to deploy
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeployScope {
+    /// Every category; `merge` keeps remote files the package does not contain
+    All { merge: bool },
+    Category(String),
+    Item { category: String, item: String },
+}
+
+#[derive(Debug)]
+pub struct DeployOptions {
+    pub server: String,
+    pub password: Option<String>,
+    pub token: Option<String>,
+    pub scope: DeployScope,
+}
+
+pub fn deploy(base_dir: &Path, options: &DeployOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let server = options.server.trim_end_matches('/');
+    let scope = &options.scope;
+    // Local mistakes should fail before anything is sent
+    let urls = published_urls(base_dir, scope)?;
+
+    // No overall timeout: large packages take a while to upload
+    let client = reqwest::blocking::Client::builder().timeout(None).build()?;
+    let token = match &options.token {
+        Some(token) => token.clone(),
+        None => login(&client, server, &read_password(options.password.as_deref())?)?,
+    };
+    println!("✓ Logged in to {}", server);
+
+    let archive = std::env::temp_dir().join(format!("lf_blog_deploy_{}.zip", uuid::Uuid::new_v4()));
+    let result = package(base_dir, scope, &archive).and_then(|_| {
+        let size = fs::metadata(&archive)?.len();
+        println!("✓ Packaged {} ({})", describe(scope), format_size(size));
+        upload(&client, server, &token, scope, &archive, size)
+    });
+    let _ = fs::remove_file(&archive);
+    let upload_response = result?;
+    println!("✓ {}", message(&upload_response).unwrap_or("Uploaded"));
+
+    // A full import starts its own compile
+    let job_id = match upload_response.get("job_id").and_then(Value::as_str) {
+        Some(job_id) => job_id.to_string(),
+        None => start_compile(&client, server, &token)?,
+    };
+    wait_for_compile(&client, server, &token, &job_id)?;
+
+    if !urls.is_empty() {
+        println!();
+        println!("Published pages:");
+        for url in urls {
+            println!("  {}/{}", server, url);
+        }
+    }
+    Ok(())
+}
+
+fn read_password(password: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let password = match password {
+        Some(p) => p.to_string(),
+        None => {
+            print!("Admin password: ");
+            std::io::stdout().flush()?;
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+    if password.is_empty() {
+        return Err("password must not be empty".into());
+    }
+    Ok(password)
+}
+
+fn login(client: &reqwest::blocking::Client, server: &str, password: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let response = client
+        .post(format!("{}/api/admin/login", server))
+        .json(&serde_json::json!({ "password": password }))
+        .send()?;
+    let status = response.status();
+    let body: Value = response.json().unwrap_or(Value::Null);
+    let reason = message(&body).unwrap_or("no message");
+    match body.get("token").and_then(Value::as_str) {
+        Some(token) if status.is_success() => Ok(token.to_string()),
+        _ => Err(format!("authentication failed ({}): {}", status, reason).into()),
+    }
+}
+
+/// Zip the deployed directories with the layout the matching upload endpoint
+/// expects: `{category}/...` for full and category uploads, `{item}/...` for items
+fn package(base_dir: &Path, scope: &DeployScope, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = zip::ZipWriter::new(fs::File::create(dest)?);
+    let options = zip::write::FileOptions::default().large_file(true);
+
+    let (source_dir, root) = match scope {
+        DeployScope::All { .. } => (base_dir.to_path_buf(), base_dir.to_path_buf()),
+        DeployScope::Category(category) => {
+            zip.add_directory(format!("{}/", category), options)?;
+            (base_dir.join(category), base_dir.to_path_buf())
+        }
+        DeployScope::Item { category, item } => {
+            zip.add_directory(format!("{}/", item), options)?;
+            (base_dir.join(category).join(item), base_dir.join(category))
+        }
+    };
+    crate::admin::add_dir_to_zip(&mut zip, &source_dir, &root, options)?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Stream the package as a chunked multipart body, printing progress
+fn upload(
+    client: &reqwest::blocking::Client,
+    server: &str,
+    token: &str,
+    scope: &DeployScope,
+    archive: &Path,
+    size: u64,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let endpoint = match scope {
+        DeployScope::All { merge } => format!(
+            "{}/api/admin/upload/full?mode={}",
+            server,
+            if *merge { "merge" } else { "replace" }
+        ),
+        DeployScope::Category(_) => format!("{}/api/admin/upload/category", server),
+        DeployScope::Item { category, .. } => {
+            format!("{}/api/admin/upload/item/{}", server, urlencoding::encode(category))
+        }
+    };
+
+    let reader = ProgressReader {
+        inner: fs::File::open(archive)?,
+        sent: 0,
+        total: size,
+    };
+    let part = reqwest::blocking::multipart::Part::reader(reader)
+        .file_name("deploy.zip")
+        .mime_str("application/zip")?;
+    let form = reqwest::blocking::multipart::Form::new().part("file", part);
+
+    let response = client.post(endpoint).bearer_auth(token).multipart(form).send()?;
+    println!();
+    json_result(response)
+}
+
+/// Ask the server to recompile, waiting out a compile that was already running
+fn start_compile(client: &reqwest::blocking::Client, server: &str, token: &str) -> Result<String, Box<dyn std::error::Error>> {
+    loop {
+        let response = client
+            .post(format!("{}/api/recompile", server))
+            .bearer_auth(token)
+            .send()?;
+        let already_running = response.status() == reqwest::StatusCode::ACCEPTED;
+        let body = json_result(response)?;
+        let job_id = body
+            .get("job_id")
+            .and_then(Value::as_str)
+            .ok_or("recompile response has no job_id")?
+            .to_string();
+        if !already_running {
+            return Ok(job_id);
+        }
+        // That compile may have started before the upload finished
+        println!("  A compile is already running, waiting for it first");
+        wait_for_compile(client, server, token, &job_id)?;
+    }
+}
+
+fn wait_for_compile(
+    client: &reqwest::blocking::Client,
+    server: &str,
+    token: &str,
+    job_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    loop {
+        let status = json_result(
+            client
+                .get(format!("{}/api/admin/compile/status", server))
+                .bearer_auth(token)
+                .send()?,
+        )?;
+        let same_job = status.get("job_id").and_then(Value::as_str) == Some(job_id);
+        let compiling = status.get("is_compiling").and_then(Value::as_bool).unwrap_or(false);
+
+        if same_job && !compiling {
+            println!();
+            let result = status.get("last_result").and_then(Value::as_str).unwrap_or("");
+            return match status.get("last_success").and_then(Value::as_bool) {
+                Some(false) => Err(format!("remote compile failed: {}", result).into()),
+                _ => {
+                    println!("✓ Compiled: {}", result);
+                    Ok(())
+                }
+            };
+        }
+
+        if let (Some(phase), Some(done), Some(total)) = (
+            status.get("phase").and_then(Value::as_str),
+            status.get("done").and_then(Value::as_u64),
+            status.get("total").and_then(Value::as_u64),
+        ) {
+            print!("\r  Compiling: {} {}/{}    ", phase, done, total);
+            std::io::stdout().flush()?;
+        }
+
+        if started.elapsed() > COMPILE_TIMEOUT {
+            return Err(format!("remote compile {} did not finish within {}s", job_id, COMPILE_TIMEOUT.as_secs()).into());
+        }
+        std::thread::sleep(STATUS_POLL_INTERVAL);
+    }
+}
+
+/// The JSON body of a successful response, or its `message` as the error
+fn json_result(response: reqwest::blocking::Response) -> Result<Value, Box<dyn std::error::Error>> {
+    let status = response.status();
+    let body: Value = response.json().unwrap_or(Value::Null);
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("authentication failed: the session or token was rejected".into());
+    }
+    if !status.is_success() || body.get("status").and_then(Value::as_str) == Some("error") {
+        return Err(format!("server returned {}: {}", status, message(&body).unwrap_or("no message")).into());
+    }
+    Ok(body)
+}
+
+fn message(body: &Value) -> Option<&str> {
+    body.get("message").and_then(Value::as_str)
+}
+
+/// Site-relative URLs of the pages the deploy publishes (drafts and
+/// scheduled posts excluded); errors when the scope does not exist locally
+fn published_urls(base_dir: &Path, scope: &DeployScope) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let scanner = Scanner::new(base_dir.to_path_buf());
+    let now = chrono::Utc::now();
+    let categories = match scope {
+        DeployScope::All { .. } => scanner.scan_listing()?.published(now).categories,
+        DeployScope::Category(category) | DeployScope::Item { category, .. } => {
+            let listing = scanner
+                .scan_category_listing(category)?
+                .ok_or_else(|| format!("Category '{}' not found in '{}'", category, base_dir.display()))?;
+            crate::scanner::SiteContent { categories: vec![listing] }.published(now).categories
+        }
+    };
+
+    if let DeployScope::Item { category, item } = scope {
+        if !base_dir.join(category).join(item).is_dir() {
+            return Err(format!("Page '{}/{}' not found in '{}'", category, item, base_dir.display()).into());
+        }
+        return Ok(categories
+            .iter()
+            .flat_map(|c| &c.items)
+            .filter(|i| i.dir_name == *item)
+            .map(|i| i.url.clone())
+            .collect());
+    }
+
+    let mut urls = Vec::new();
+    for category in categories {
+        urls.push(category.url.clone());
+        urls.extend(category.items.iter().map(|item| item.url.clone()));
+    }
+    Ok(urls)
+}
+
+fn describe(scope: &DeployScope) -> String {
+    match scope {
+        DeployScope::All { .. } => "all content".to_string(),
+        DeployScope::Category(category) => format!("category '{}'", category),
+        DeployScope::Item { category, item } => format!("page '{}/{}'", category, item),
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+/// Reports upload progress as the request body is read
+struct ProgressReader {
+    inner: fs::File,
+    sent: u64,
+    total: u64,
+}
+
+impl Read for ProgressReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sent += n as u64;
+        let percent = if self.total == 0 { 100 } else { self.sent * 100 / self.total };
+        print!("\r  Uploading: {} / {} ({}%)", format_size(self.sent), format_size(self.total), percent);
+        std::io::stdout().flush()?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn zip_names(path: &PathBuf) -> Vec<String> {
+        let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+        let mut names: Vec<String> = (0..archive.len()).map(|i| archive.by_index(i).unwrap().name().to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_package_and_urls() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_deploy_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("content/fruit/apple/attachment")).unwrap();
+        fs::create_dir_all(root.join("content/fruit/pear")).unwrap();
+        fs::write(root.join("content/fruit/index.md"), "# Fruit\n").unwrap();
+        fs::write(root.join("content/fruit/apple/apple.md"), "---\ntitle: Apple\n---\n# Apple\n").unwrap();
+        fs::write(root.join("content/fruit/apple/attachment/a.txt"), "a").unwrap();
+        fs::write(root.join("content/fruit/pear/pear.md"), "---\ntitle: Pear\ndraft: true\n---\n# Pear\n").unwrap();
+        let content = root.join("content");
+        let archive = root.join("deploy.zip");
+
+        let item = DeployScope::Item { category: "fruit".to_string(), item: "apple".to_string() };
+        package(&content, &item, &archive).unwrap();
+        assert_eq!(zip_names(&archive), vec!["apple/", "apple/apple.md", "apple/attachment/", "apple/attachment/a.txt"]);
+        assert_eq!(published_urls(&content, &item).unwrap(), vec!["fruit-apple"]);
+
+        let category = DeployScope::Category("fruit".to_string());
+        package(&content, &category, &archive).unwrap();
+        assert!(zip_names(&archive).contains(&"fruit/pear/pear.md".to_string()));
+        // Drafts are uploaded but not published
+        assert_eq!(published_urls(&content, &category).unwrap(), vec!["fruit", "fruit-apple"]);
+
+        package(&content, &DeployScope::All { merge: false }, &archive).unwrap();
+        assert!(zip_names(&archive).contains(&"fruit/index.md".to_string()));
+
+        assert!(published_urls(&content, &DeployScope::Category("veg".to_string())).is_err());
+        let missing = DeployScope::Item { category: "fruit".to_string(), item: "plum".to_string() };
+        assert!(published_urls(&content, &missing).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,7 +1,15 @@
-use clap::{Parser, Subcommand, Command};
-use std::path::PathBuf;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand, Command};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::Write;
 use chrono::Utc;
+use serde::Serialize;
+use crate::scanner::{item_url, Scanner};
+use crate::slug::slugify;
+
+mod deploy;
 
 #[derive(Parser, Debug)]
 #[command(name = "lfb")]
@@ -28,6 +36,22 @@ pub enum Commands {
     #[command(name = "server")]
     #[command(about = "Start the blog server")]
     Server(ServerArgs),
+
+    #[command(name = "init")]
+    #[command(about = "Scaffold a new blog project")]
+    Init {
+        #[arg(help = "Directory to create the project in")]
+        path: PathBuf,
+
+        #[arg(long, help = "Site title")]
+        title: String,
+
+        #[arg(long, help = "Site author")]
+        author: String,
+
+        #[arg(long, help = "Write into a non-empty directory, overwriting scaffold files")]
+        force: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -45,16 +69,158 @@ pub enum ClientActions {
         #[arg(help = "Title of the new article")]
         title: String,
     },
+
+    #[command(name = "lint")]
+    #[command(about = "Check content for common problems")]
+    Lint,
+
+    #[command(name = "check")]
+    #[command(about = "Validate content: headings, frontmatter, links, slugs and attachment sizes")]
+    Check {
+        #[arg(long, help = "Create missing attachment/ directories and normalize frontmatter")]
+        fix: bool,
+
+        #[arg(long, value_name = "MB", help = "Flag attachments larger than this (default: server.max_attachment_size_mb)")]
+        max_attachment_size: Option<u64>,
+    },
+
+    #[command(name = "list")]
+    #[command(about = "List categories and their items")]
+    List {
+        #[arg(short, long, help = "Only list items in this category")]
+        category: Option<String>,
+
+        #[arg(long, help = "Print machine-readable JSON")]
+        json: bool,
+    },
+
+    #[command(name = "pages")]
+    #[command(about = "List the pages of a category with their dates and draft status")]
+    Pages {
+        #[arg(help = "Category directory name")]
+        category: String,
+    },
+
+    #[command(name = "delete")]
+    #[command(about = "Delete a page directory and its attachments")]
+    Delete {
+        #[arg(help = "Category directory name")]
+        category: String,
+
+        #[arg(help = "Item directory name")]
+        item: String,
+
+        #[arg(short, long, help = "Delete without asking for confirmation")]
+        yes: bool,
+    },
+
+    #[command(name = "deploy")]
+    #[command(about = "Upload content to a running server and recompile it there")]
+    Deploy {
+        #[arg(long, help = "Server base URL, e.g. https://myblog.example")]
+        server: String,
+
+        #[arg(long, conflicts_with = "token", help = "Admin password (read from stdin if neither it nor --token is given)")]
+        password: Option<String>,
+
+        #[arg(long, help = "Existing admin session token")]
+        token: Option<String>,
+
+        #[arg(long, help = "Only deploy this category")]
+        category: Option<String>,
+
+        #[arg(long, requires = "category", help = "Only deploy this item of --category")]
+        item: Option<String>,
+
+        #[arg(long, conflicts_with = "category", help = "Keep remote files missing locally instead of replacing all content")]
+        merge: bool,
+    },
+
+    #[command(name = "publish")]
+    #[command(about = "Publish a draft by setting `draft: false` in its frontmatter")]
+    Publish {
+        #[arg(help = "Category directory name")]
+        category: String,
+
+        #[arg(help = "Item directory name")]
+        item: String,
+    },
+
+    #[command(name = "migrate-slugs")]
+    #[command(about = "Rename categories, item directories and markdown files to URL slugs")]
+    MigrateSlugs {
+        #[arg(long, help = "Rename the files (default: only show what would change)")]
+        apply: bool,
+    },
 }
 
 #[derive(Parser, Debug)]
 pub struct ServerArgs {
     #[arg(short, long, default_value = "lf_blog.toml")]
     pub config: PathBuf,
+
+    #[arg(long = "admin-allowlist", value_name = "CIDR", help = "Allow admin access from this IP or CIDR range for this run (repeatable)")]
+    pub admin_allowlist: Vec<String>,
+
+    #[arg(long, help = "Validate the configuration and exit without starting the server")]
+    pub validate: bool,
+
+    #[arg(long, help = "Compare the content directory with the compiled database, print the report and exit")]
+    pub check_integrity: bool,
+
+    #[arg(long, help = "Recompile every page on startup instead of only content changed since the last compile")]
+    pub force_full: bool,
+
+    #[command(subcommand)]
+    pub action: Option<ServerActions>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ServerActions {
+    #[command(name = "hash-password")]
+    #[command(about = "Print an argon2 hash to use as admin_password_hash")]
+    HashPassword {
+        #[arg(help = "Password to hash (read from stdin if omitted)")]
+        password: Option<String>,
+    },
+
+    #[command(name = "set-password")]
+    #[command(about = "Set a new admin password (prompted) and sign out all admin sessions")]
+    SetPassword,
+
+    #[command(name = "api-key")]
+    #[command(about = "Manage API keys for the admin API")]
+    ApiKey {
+        #[command(subcommand)]
+        action: ApiKeyActions,
+    },
+
+    #[command(name = "migrate-attachments")]
+    #[command(about = "Move attachment blobs out of the database into generated/attachments")]
+    MigrateAttachments,
+
+    #[command(about = "Pull the content directory from [content.git] and recompile what changed")]
+    Sync,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ApiKeyActions {
+    #[command(about = "Generate a new API key, print it once and store only its hash")]
+    Generate,
 }
 
 pub fn run() {
-    let cli = Cli::parse();
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // An unknown subcommand shows the full help rather than just the usage line
+        Err(e) if e.kind() == ErrorKind::InvalidSubcommand => {
+            let _ = e.print();
+            eprintln!();
+            let _ = Cli::command().print_help();
+            std::process::exit(2);
+        }
+        Err(e) => e.exit(),
+    };
 
     match &cli.command {
         Commands::Client { action, dir } => {
@@ -79,18 +245,408 @@ pub fn run() {
                         std::process::exit(1);
                     }
                 }
+                ClientActions::Lint => {
+                    if let Err(e) = lint_content(&base_dir) {
+                        eprintln!("Error linting content: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::Check { fix, max_attachment_size } => {
+                    let max_mb = max_attachment_size
+                        .unwrap_or_else(|| crate::config::Config::default().server.max_attachment_size_mb);
+                    match check_content(&base_dir, max_mb * 1024 * 1024, *fix) {
+                        Ok(report) => {
+                            report.print();
+                            if report.error_count() > 0 {
+                                std::process::exit(1);
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Error checking content: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                ClientActions::List { category, json } => {
+                    if let Err(e) = list_content(&base_dir, category.as_deref(), *json) {
+                        eprintln!("Error listing content: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::Pages { category } => {
+                    if let Err(e) = list_pages(&base_dir, category) {
+                        eprintln!("Error listing pages: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::Delete { category, item, yes } => {
+                    if let Err(e) = delete_page(&base_dir, category, item, *yes) {
+                        eprintln!("Error deleting page: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::Deploy { server, password, token, category, item, merge } => {
+                    let scope = match (category, item) {
+                        (Some(category), Some(item)) => deploy::DeployScope::Item {
+                            category: category.clone(),
+                            item: item.clone(),
+                        },
+                        (Some(category), None) => deploy::DeployScope::Category(category.clone()),
+                        _ => deploy::DeployScope::All { merge: *merge },
+                    };
+                    let options = deploy::DeployOptions {
+                        server: server.clone(),
+                        password: password.clone(),
+                        token: token.clone(),
+                        scope,
+                    };
+                    if let Err(e) = deploy::deploy(&base_dir, &options) {
+                        eprintln!("Error deploying: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::Publish { category, item } => {
+                    if let Err(e) = publish_page(&base_dir, category, item) {
+                        eprintln!("Error publishing page: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                ClientActions::MigrateSlugs { apply } => {
+                    if let Err(e) = migrate_slugs(&base_dir, *apply) {
+                        eprintln!("Error migrating slugs: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        }
+        Commands::Server(ServerArgs { action: Some(action), config, .. }) => match action {
+            ServerActions::HashPassword { password } => {
+                if let Err(e) = print_password_hash(password.as_deref()) {
+                    eprintln!("Error hashing password: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            ServerActions::SetPassword => {
+                if let Err(e) = set_password(config) {
+                    eprintln!("Error setting password: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            ServerActions::ApiKey { action: ApiKeyActions::Generate } => {
+                if let Err(e) = generate_api_key(config) {
+                    eprintln!("Error generating API key: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            ServerActions::MigrateAttachments => {
+                if let Err(e) = migrate_attachments(config) {
+                    eprintln!("Error migrating attachments: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            ServerActions::Sync => match sync_content(config) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("Error syncing content: {}", e);
+                    std::process::exit(1);
+                }
+            },
+        },
+        Commands::Server(ServerArgs { validate: true, config, .. }) => {
+            if !validate_config(config) {
+                std::process::exit(1);
             }
         }
+        Commands::Server(ServerArgs { check_integrity: true, config, .. }) => match check_integrity(config) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error checking integrity: {}", e);
+                std::process::exit(1);
+            }
+        },
         Commands::Server(args) => {
             println!("Starting server with config: {}", args.config.display());
-            println!("Note: Server mode is typically run via 'cargo run' or the binary directly.");
-            println!("To start the server, run: lfg_server --config {}", args.config.display());
+            if let Err(e) = crate::async_web_server(args.config.clone(), args.admin_allowlist.clone(), args.force_full) {
+                eprintln!("Server error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Init { path, title, author, force } => {
+            if let Err(e) = init_project(path, title, author, *force) {
+                eprintln!("Error initializing project: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Create `path` with a config file, the content/templates/generated
+/// directories and a sample category holding one item
+fn init_project(path: &Path, title: &str, author: &str, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if path.exists() {
+        if !path.is_dir() {
+            return Err(format!("'{}' exists and is not a directory", path.display()).into());
+        }
+        if !force && fs::read_dir(path)?.next().is_some() {
+            return Err(format!("'{}' is not empty (use --force to initialize anyway)", path.display()).into());
+        }
+    }
+
+    let mut config = crate::config::Config::default();
+    config.site.title = title.to_string();
+    config.site.author = author.to_string();
+
+    for dir in [&config.paths.content_dir, &config.paths.templates_dir, &config.paths.generated_dir, &config.paths.data_dir] {
+        fs::create_dir_all(path.join(dir))?;
+    }
+    fs::write(path.join("lf_blog.toml"), toml::to_string_pretty(&config)?)?;
+
+    let category_dir = path.join(&config.paths.content_dir).join("welcome");
+    let item_dir = category_dir.join("hello-world");
+    fs::create_dir_all(item_dir.join("attachment"))?;
+    fs::write(
+        category_dir.join("index.md"),
+        r#"---
+title: Welcome
+description: Getting started with your new blog
+---
+
+# Welcome
+
+Each directory under `content/` is a category; this file is its introduction.
+"#,
+    )?;
+    fs::write(
+        item_dir.join("hello-world.md"),
+        format!(
+            r#"---
+title: Hello, World
+date: {}
+author: {}
+tags: [welcome]
+description: The first post on {}
+---
+
+# Hello, World
+
+Each item lives in its own directory under a category, next to an
+`attachment/` folder for its images and files.
+
+Edit this post or add new ones with `lf_blog client new_page`, then
+recompile from the admin panel.
+"#,
+            Utc::now().format("%Y-%m-%d"),
+            author,
+            title
+        ),
+    )?;
+
+    let gitignore = format!(
+        "/{}\n/{}/\n",
+        config.paths.storage_db.display(),
+        config.paths.generated_dir.display()
+    );
+    fs::write(path.join(".gitignore"), gitignore)?;
+
+    println!("✓ Blog '{}' initialized at: {}", title, path.display());
+    println!();
+    println!("Next steps:");
+    println!("  cd {}", path.display());
+    println!("  lf_blog server hash-password      # then set admin_password_hash in lf_blog.toml");
+    println!("  lf_blog                           # start the server on http://{}:{}", config.server.host, config.server.port);
+    println!("  open /admin and click 编译发布 to build the site");
+    Ok(())
+}
+
+/// Print the result of `Config::validate` for the config at `config_path`;
+/// returns false when there are errors
+fn validate_config(config_path: &Path) -> bool {
+    let config = match crate::config::Config::load_from(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Error loading config: {}", e);
+            return false;
+        }
+    };
+
+    match config.validate() {
+        Ok(warnings) => {
+            for warning in &warnings {
+                println!("warning: {}", warning);
+            }
+            println!("✓ Configuration is valid");
+            true
+        }
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("error: {}", error);
+            }
+            eprintln!("Configuration has {} error(s)", errors.len());
+            false
+        }
+    }
+}
+
+/// Print the integrity report; returns whether nothing was found
+fn check_integrity(config_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load_from(config_path)?;
+    let report = crate::compiler::Compiler::check_integrity(&config)?;
+
+    for filename in &report.orphaned_attachments {
+        println!("orphaned attachment: {}", filename);
+    }
+    for slug in &report.ghost_pages {
+        println!("ghost page: /{} (no source on disk)", slug);
+    }
+    for dir in &report.uncompiled_items {
+        println!("uncompiled item: {}", dir.display());
+    }
+    if report.is_clean() {
+        println!("✓ Content and database are in sync");
+    } else {
+        println!(
+            "{} orphaned attachments, {} ghost pages, {} uncompiled items",
+            report.orphaned_attachments.len(),
+            report.ghost_pages.len(),
+            report.uncompiled_items.len()
+        );
+    }
+    Ok(report.is_clean())
+}
+
+fn print_password_hash(password: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let password = match password {
+        Some(p) => p.to_string(),
+        None => {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    if password.is_empty() {
+        return Err("password must not be empty".into());
+    }
+
+    let hash = crate::admin::hash_admin_password(&password)?;
+    println!("{}", hash);
+    println!();
+    println!("Add this to the [server] section of your config:");
+    println!("admin_password_hash = \"{}\"", hash);
+    Ok(())
+}
+
+/// Prompt for a new admin password twice and store its hash in the config
+fn set_password(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let prompt = |label: &str| -> std::io::Result<String> {
+        print!("{}: ", label);
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+    let password = prompt("New admin password")?;
+    let confirm = prompt("Confirm password")?;
+
+    crate::admin::validate_new_password(&password, &confirm)?;
+    crate::admin::set_admin_password(config_path, &password)?;
+    println!("Saved admin_password_hash to {}", config_path.display());
+    println!("All admin sessions were signed out; a running server picks up the new password after a restart");
+    Ok(())
+}
+
+/// Add a new API key to the config and print the plaintext key once
+fn generate_api_key(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let (key, hash) = crate::admin::add_api_key(config_path)?;
+    println!("{}", key);
+    println!();
+    println!("Saved the key hash ({}...) to {}", &hash[..12], config_path.display());
+    println!("Store the key now, it cannot be shown again; a running server accepts it after a restart");
+    Ok(())
+}
+
+/// Pull and recompile like `POST /api/admin/sync`; returns false on conflicts
+fn sync_content(config_path: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load_from(config_path)?;
+    let (report, compiled) = crate::git_sync::sync_and_compile(&config)?;
+
+    for path in &report.conflicts {
+        eprintln!("conflict: {}", path.display());
+    }
+    if !report.is_applied() {
+        eprintln!(
+            "{} locally modified files also changed upstream in {}; commit or discard them and sync again",
+            report.conflicts.len(),
+            report.commit
+        );
+        return Ok(false);
+    }
+    for path in &report.changed_files {
+        println!("changed: {}", path.display());
+    }
+    match compiled {
+        Some(result) => println!(
+            "✓ Synced to {} and compiled {} items, {} attachments",
+            report.commit, result.total_items, result.total_attachments
+        ),
+        None => println!("✓ Already up to date at {}", report.commit),
+    }
+    Ok(true)
+}
+
+fn migrate_attachments(config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config = crate::config::Config::load_from(config_path)?;
+    for migrated in crate::migrate::migrate_legacy_databases(&config.paths)? {
+        println!("{}", migrated);
+    }
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = crate::storage::StorageDB::new(&storage_path)?;
+
+    let pending = storage.get_db_stored_attachments()?;
+    println!("Moving {} attachments out of {}", pending.len(), storage_path);
+
+    let mut buf = vec![0u8; 256 * 1024];
+    for meta in &pending {
+        let dir = config.attachments_dir().join(&meta.slug);
+        fs::create_dir_all(&dir)?;
+        let dest = dir.join(&meta.filename);
+
+        // Copy the blob in chunks so large files are never held in memory
+        let mut file = fs::File::create(&dest)?;
+        let mut offset = 0;
+        while offset < meta.file_size {
+            let read = storage.read_attachment_chunk(meta.rowid, offset, &mut buf)?;
+            if read == 0 {
+                return Err(format!("attachment '{}' is truncated", meta.filename).into());
+            }
+            std::io::Write::write_all(&mut file, &buf[..read])?;
+            offset += read;
         }
+        file.sync_all()?;
+
+        storage.move_attachment_to_file(meta.rowid, &dest.to_string_lossy())?;
+        println!("  - {} -> {}", meta.filename, dest.display());
+    }
+
+    storage.vacuum()?;
+
+    // Keep later compiles writing to disk as well
+    if config.storage.attachment_backend != crate::config::AttachmentBackend::Filesystem {
+        // Rewrite the file itself so LFB_* environment overrides are not persisted
+        let mut file_config = crate::config::Config::load_file(config_path)?;
+        file_config.storage.attachment_backend = crate::config::AttachmentBackend::Filesystem;
+        file_config.save_to(config_path)?;
+        println!("Set storage.attachment_backend = \"filesystem\" in {}", config_path.display());
     }
+
+    println!("✓ Migrated {} attachments", pending.len());
+    Ok(())
 }
 
 fn create_category(base_dir: &PathBuf, name: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let slug = to_slug(name);
+    let slug = slugify(name);
     let category_dir = base_dir.join(&slug);
 
     if category_dir.exists() {
@@ -127,7 +683,7 @@ date: {}
 }
 
 fn create_page(base_dir: &PathBuf, title: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let slug = to_slug(title);
+    let slug = slugify(title);
     let page_dir = base_dir.join(&slug);
 
     if page_dir.exists() {
@@ -147,6 +703,7 @@ date: {}
 author: 
 tags: []
 description: 
+draft: true
 ---
 
 # {}
@@ -176,15 +733,909 @@ fn main() {{
     println!("  - Created: {}", page_path.display());
     println!("  - Attachment dir: {}/attachment", page_dir.display());
     println!("  - Slug: {}", slug);
+    println!("  - Draft: run `client publish` when it is ready");
 
     Ok(())
 }
 
-fn to_slug(name: &str) -> String {
-    name.to_lowercase()
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-        .collect::<String>()
-        .chars()
-        .collect::<String>()
+fn lint_content(base_dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let scanner = Scanner::new(base_dir.clone());
+    let site_content = scanner.scan()?;
+    let total_items: usize = site_content.categories.iter().map(|c| c.items.len()).sum();
+    println!("Checked {} categories, {} items", site_content.categories.len(), total_items);
+
+    let duplicate_groups = scanner.duplicate_groups();
+    if duplicate_groups.is_empty() {
+        println!("✓ No problems found");
+        return Ok(());
+    }
+
+    for group in &duplicate_groups {
+        println!(
+            "⚠ duplicate-attachment: {} is copied into {} other item(s)",
+            group.first_path.display(),
+            group.copies.len()
+        );
+        for copy in &group.copies {
+            println!("  - {}", copy.display());
+        }
+        println!(
+            "  Consider consolidating into a single file ({} bytes could be saved)",
+            group.file_size * group.copies.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Top-level paths served by routes rather than compiled pages
+const RESERVED_PATHS: &[&str] = &["admin", "api", "attachment", "feed", "health", "search", "static", "tags"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found by `client check`; `line` is 1-based and absent for
+/// directories and attachment files
+#[derive(Debug)]
+struct CheckIssue {
+    severity: Severity,
+    kind: &'static str,
+    path: PathBuf,
+    line: Option<usize>,
+    message: String,
+}
+
+impl std::fmt::Display for CheckIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, ": {}[{}]: {}", severity, self.kind, self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+struct CheckReport {
+    files_checked: usize,
+    issues: Vec<CheckIssue>,
+    fixed: Vec<String>,
+}
+
+impl CheckReport {
+    fn issue(&mut self, severity: Severity, kind: &'static str, path: &Path, line: Option<usize>, message: String) {
+        self.issues.push(CheckIssue {
+            severity,
+            kind,
+            path: path.to_path_buf(),
+            line,
+            message,
+        });
+    }
+
+    fn error_count(&self) -> usize {
+        self.issues.iter().filter(|i| i.severity == Severity::Error).count()
+    }
+
+    fn print(&self) {
+        for fixed in &self.fixed {
+            println!("fixed: {}", fixed);
+        }
+        for issue in &self.issues {
+            println!("{}", issue);
+        }
+        let errors = self.error_count();
+        let warnings = self.issues.len() - errors;
+        if self.issues.is_empty() {
+            println!("✓ Checked {} files, no problems found", self.files_checked);
+        } else {
+            println!("Checked {} files: {} error(s), {} warning(s)", self.files_checked, errors, warnings);
+        }
+    }
+}
+
+/// An internal link found in a markdown file
+struct InternalLink {
+    path: PathBuf,
+    line: usize,
+    target: String,
+}
+
+/// Validate the content tree under `base_dir`. With `fix`, missing
+/// `attachment/` directories are created and frontmatter is normalized
+/// before the remaining checks run.
+fn check_content(base_dir: &Path, max_attachment_bytes: u64, fix: bool) -> Result<CheckReport, Box<dyn std::error::Error>> {
+    let mut report = CheckReport::default();
+    let mut slugs: Vec<(String, PathBuf)> = Vec::new();
+    let mut links = Vec::new();
+
+    for category in sorted_subdirs(base_dir)? {
+        let category_dir = base_dir.join(&category);
+        let index_path = category_dir.join("index.md");
+        if index_path.is_file() {
+            check_markdown_file(&index_path, fix, &mut report, &mut links)?;
+        } else {
+            report.issue(Severity::Warning, "missing-index", &category_dir, None, "category has no index.md".to_string());
+        }
+        slugs.push((category.clone(), category_dir.clone()));
+
+        for item in sorted_subdirs(&category_dir)? {
+            if item == "attachment" {
+                continue;
+            }
+            let item_dir = category_dir.join(&item);
+            let mut md_files: Vec<PathBuf> = fs::read_dir(&item_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+                .collect();
+            if md_files.is_empty() {
+                continue;
+            }
+            md_files.sort();
+
+            for md_path in &md_files {
+                check_markdown_file(md_path, fix, &mut report, &mut links)?;
+                if let Some(stem) = md_path.file_stem().and_then(|s| s.to_str()) {
+                    let slug_override = fs::read_to_string(md_path)
+                        .ok()
+                        .and_then(|content| crate::markdown::parse_markdown(&content).ok())
+                        .and_then(|parsed| parsed.frontmatter.slug);
+                    slugs.push((item_url(&category, stem, slug_override.as_deref()), md_path.clone()));
+                }
+            }
+
+            let attachment_dir = item_dir.join("attachment");
+            if !attachment_dir.is_dir() {
+                if fix {
+                    fs::create_dir_all(&attachment_dir)?;
+                    report.fixed.push(format!("{}: created attachment/", item_dir.display()));
+                } else {
+                    report.issue(Severity::Warning, "missing-attachment-dir", &item_dir, None, "item has no attachment/ directory".to_string());
+                }
+                continue;
+            }
+            for entry in fs::read_dir(&attachment_dir)? {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let size = fs::metadata(&path)?.len();
+                if size > max_attachment_bytes {
+                    report.issue(
+                        Severity::Error,
+                        "attachment-size",
+                        &path,
+                        None,
+                        format!("{} bytes exceeds the {} byte limit", size, max_attachment_bytes),
+                    );
+                }
+            }
+        }
+    }
+
+    // Two files compiling to the same URL overwrite each other's page
+    for (i, (slug, path)) in slugs.iter().enumerate() {
+        if let Some((_, first)) = slugs[..i].iter().find(|(other, _)| other == slug) {
+            report.issue(
+                Severity::Error,
+                "duplicate-slug",
+                path,
+                None,
+                format!("compiles to /{}, already used by {}", slug, first.display()),
+            );
+        }
+    }
+
+    for link in links {
+        let target = link.target.split(['#', '?']).next().unwrap_or_default().trim_matches('/');
+        let target = urlencoding::decode(target).map(|t| t.into_owned()).unwrap_or_else(|_| target.to_string());
+        let first_segment = target.split('/').next().unwrap_or_default();
+        if target.is_empty() || RESERVED_PATHS.contains(&first_segment) || slugs.iter().any(|(slug, _)| *slug == target) {
+            continue;
+        }
+        report.issue(
+            Severity::Error,
+            "broken-link",
+            &link.path,
+            Some(link.line),
+            format!("{} does not match any category or item", link.target),
+        );
+    }
+
+    report.issues.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    Ok(report)
+}
+
+/// Check one markdown file for frontmatter errors and a missing H1, and
+/// collect its internal links
+fn check_markdown_file(
+    path: &Path,
+    fix: bool,
+    report: &mut CheckReport,
+    links: &mut Vec<InternalLink>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    report.files_checked += 1;
+    let mut content = fs::read_to_string(path)?;
+
+    if let Some(normalized) = normalize_frontmatter(&content).filter(|n| *n != content) {
+        if fix {
+            fs::write(path, &normalized)?;
+            report.fixed.push(format!("{}: normalized frontmatter", path.display()));
+            content = normalized;
+        } else {
+            report.issue(
+                Severity::Warning,
+                "frontmatter-format",
+                path,
+                Some(1),
+                "frontmatter has tabs, CRLF line endings or stray whitespace (fixable with --fix)".to_string(),
+            );
+        }
+    }
+
+    let parsed = match crate::markdown::parse_markdown(&content) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            let line = e
+                .downcast_ref::<serde_yaml::Error>()
+                .and_then(|e| e.location())
+                .map_or(1, |location| location.line());
+            report.issue(Severity::Error, "frontmatter", path, Some(line), e.to_string());
+            return Ok(());
+        }
+    };
+
+    // `raw_content` is the tail of the file after the frontmatter
+    let body_offset = content.len() - parsed.raw_content.len();
+    let line_at = |offset: usize| content[..body_offset + offset].matches('\n').count() + 1;
+
+    let mut has_h1 = false;
+    let options = pulldown_cmark::Options::ENABLE_TABLES | pulldown_cmark::Options::ENABLE_FOOTNOTES;
+    for (event, range) in pulldown_cmark::Parser::new_ext(&parsed.raw_content, options).into_offset_iter() {
+        match event {
+            pulldown_cmark::Event::Start(pulldown_cmark::Tag::Heading { level: pulldown_cmark::HeadingLevel::H1, .. }) => {
+                has_h1 = true;
+            }
+            pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link { dest_url, .. })
+            | pulldown_cmark::Event::Start(pulldown_cmark::Tag::Image { dest_url, .. }) => {
+                if dest_url.starts_with('/') && !dest_url.starts_with("//") {
+                    links.push(InternalLink {
+                        path: path.to_path_buf(),
+                        line: line_at(range.start),
+                        target: dest_url.to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    if !has_h1 {
+        report.issue(Severity::Warning, "missing-h1", path, Some(1), "no top-level `# ` heading".to_string());
+    }
+
+    Ok(())
+}
+
+/// Frontmatter rewritten with LF line endings, spaces instead of leading
+/// tabs, no trailing whitespace and one blank line before the body;
+/// `None` when the file has no complete frontmatter block
+fn normalize_frontmatter(content: &str) -> Option<String> {
+    let content = content.replace("\r\n", "\n");
+    let rest = content.strip_prefix("---")?;
+    let end = rest.find("\n---")?;
+
+    let yaml: Vec<String> = rest[..end]
+        .lines()
+        .map(|line| {
+            let indent = line.len() - line.trim_start_matches('\t').len();
+            format!("{}{}", "  ".repeat(indent), &line[indent..]).trim_end().to_string()
+        })
+        .collect();
+    let yaml = yaml.join("\n");
+    let body = rest[end + 4..].trim_start();
+
+    let frontmatter = format!("---\n{}\n---\n", yaml.trim_matches('\n'));
+    if body.is_empty() {
+        Some(frontmatter)
+    } else {
+        Some(format!("{}\n{}", frontmatter, body))
+    }
+}
+
+/// A category directory and the items inside it
+#[derive(Debug, Serialize)]
+struct CategoryListing {
+    name: String,
+    items: Vec<ItemListing>,
+}
+
+#[derive(Debug, Serialize)]
+struct ItemListing {
+    name: String,
+    slug: String,
+    has_attachments: bool,
+    compiled: Option<bool>, // None when no storage database was found
+}
+
+fn list_content(base_dir: &PathBuf, category: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // The storage database normally sits in the data directory next to the
+    // content directory (directly next to it in older layouts)
+    let default_db = crate::config::Config::default().paths.storage_db;
+    let db_path = base_dir.parent().and_then(|parent| {
+        [parent.join(&default_db), parent.join("storage.db")].into_iter().find(|path| path.is_file())
+    });
+    let compiled = match db_path {
+        Some(path) => {
+            let storage = crate::storage::StorageDB::new(&path.to_string_lossy())?;
+            let pages = storage.get_pages_by_type(crate::storage::PageType::Item)?;
+            Some(pages.into_iter().map(|page| page.slug).collect::<HashSet<_>>())
+        }
+        None => None,
+    };
+
+    let listing = collect_listing(base_dir, category, compiled.as_ref())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&listing)?);
+        return Ok(());
+    }
+
+    // Without a category filter, one summary row per category
+    if category.is_none() {
+        print_category_table(&listing);
+    } else {
+        for category in &listing {
+            println!("{}/ ({} items)", category.name, category.items.len());
+            for item in &category.items {
+                let mut flags = Vec::new();
+                if item.has_attachments {
+                    flags.push("attachment/");
+                }
+                match item.compiled {
+                    Some(true) => flags.push("compiled"),
+                    Some(false) => flags.push("uncompiled"),
+                    None => {}
+                }
+                if flags.is_empty() {
+                    println!("  - {}", item.name);
+                } else {
+                    println!("  - {}  [{}]", item.name, flags.join(", "));
+                }
+            }
+        }
+    }
+    if compiled.is_none() {
+        println!();
+        println!("(storage.db not found next to {}, compile status unknown)", base_dir.display());
+    }
+
+    Ok(())
+}
+
+fn print_category_table(listing: &[CategoryListing]) {
+    let width = listing.iter().map(|c| c.name.chars().count()).max().unwrap_or(0).max("CATEGORY".len());
+    println!("{:<width$}  {:>5}  COMPILED", "CATEGORY", "ITEMS", width = width);
+    for category in listing {
+        let compiled: Vec<bool> = category.items.iter().filter_map(|item| item.compiled).collect();
+        let compiled = if compiled.len() == category.items.len() && !compiled.is_empty() {
+            format!("{}/{}", compiled.iter().filter(|c| **c).count(), compiled.len())
+        } else {
+            "-".to_string()
+        };
+        println!("{:<width$}  {:>5}  {}", category.name, category.items.len(), compiled, width = width);
+    }
+}
+
+/// A page of a category as shown by `client pages`
+#[derive(Debug)]
+struct PageListing {
+    dir_name: String,
+    title: String,
+    date: Option<String>,
+    draft: bool,
+}
+
+fn list_pages(base_dir: &Path, category: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let pages = collect_pages(base_dir, category)?;
+    if pages.is_empty() {
+        println!("No pages in '{}'", category);
+        return Ok(());
+    }
+
+    let title_width = pages.iter().map(|p| p.title.chars().count()).max().unwrap_or(0).max("TITLE".len());
+    let dir_width = pages.iter().map(|p| p.dir_name.chars().count()).max().unwrap_or(0).max("ITEM".len());
+    println!("{:<dir_width$}  {:<title_width$}  {:<10}  STATUS", "ITEM", "TITLE", "DATE");
+    for page in &pages {
+        println!(
+            "{:<dir_width$}  {:<title_width$}  {:<10}  {}",
+            page.dir_name,
+            page.title,
+            page.date.as_deref().unwrap_or("-"),
+            if page.draft { "draft" } else { "published" },
+        );
+    }
+    Ok(())
+}
+
+/// Items of `category` in scanner order (newest first), with the draft flag
+/// read back from each item's frontmatter
+fn collect_pages(base_dir: &Path, category: &str) -> Result<Vec<PageListing>, Box<dyn std::error::Error>> {
+    let listing = Scanner::new(base_dir.to_path_buf())
+        .with_drafts(true)
+        .scan_category_listing(category)?
+        .ok_or_else(|| format!("Category '{}' not found in '{}'", category, base_dir.display()))?;
+
+    let mut pages = Vec::new();
+    for item in listing.items {
+        let content = fs::read_to_string(&item.file_path)?;
+        let parsed = crate::markdown::parse_markdown(&content)
+            .map_err(|e| format!("{}: {}", item.file_path.display(), e))?;
+        pages.push(PageListing {
+            dir_name: item.dir_name,
+            title: item.title,
+            date: item.date,
+            draft: parsed.frontmatter.draft,
+        });
+    }
+    Ok(pages)
+}
+
+/// Remove an item directory, asking on stdin unless `yes` is set
+fn delete_page(base_dir: &Path, category: &str, item: &str, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let content = Scanner::new(base_dir.to_path_buf())
+        .with_drafts(true)
+        .scan_item_dir(category, item)?
+        .ok_or_else(|| format!("Page '{}/{}' not found in '{}'", category, item, base_dir.display()))?;
+    let item_dir = base_dir.join(category).join(item);
+
+    if !yes {
+        use std::io::Write;
+        print!("Delete '{}' ({}) and its attachments? [y/N] ", content.title, item_dir.display());
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Err("Cancelled (pass --yes to skip the prompt)".into());
+        }
+    }
+
+    fs::remove_dir_all(&item_dir)?;
+    println!("✓ Deleted '{}' ({})", content.title, item_dir.display());
+    println!("  - Recompile to remove it from the site");
+    Ok(())
+}
+
+fn publish_page(base_dir: &Path, category: &str, item: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let content = Scanner::new(base_dir.to_path_buf())
+        .with_drafts(true)
+        .scan_item_dir(category, item)?
+        .ok_or_else(|| format!("Page '{}/{}' not found in '{}'", category, item, base_dir.display()))?;
+
+    let markdown = fs::read_to_string(&content.file_path)?;
+    match clear_draft_flag(&markdown) {
+        Some(published) => {
+            fs::write(&content.file_path, published)?;
+            println!("✓ Published '{}'", content.title);
+            println!("  - Updated: {}", content.file_path.display());
+        }
+        None => println!("'{}' is not a draft, nothing to do", content.title),
+    }
+    Ok(())
+}
+
+/// Rewrite `draft: true` to `draft: false` inside the frontmatter block,
+/// leaving everything else byte-for-byte; `None` when the page is no draft
+fn clear_draft_flag(content: &str) -> Option<String> {
+    let body = content.strip_prefix("---")?;
+    let end = body.find("\n---")?;
+    let frontmatter = &body[..end];
+
+    let mut offset = 0;
+    for line in frontmatter.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if let Some(value) = trimmed.strip_prefix("draft:") {
+            if value.trim() != "true" {
+                return None;
+            }
+            let start = 3 + offset;
+            let mut published = String::with_capacity(content.len());
+            published.push_str(&content[..start]);
+            published.push_str("draft: false");
+            published.push_str(&content[start + trimmed.len()..]);
+            return Some(published);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Walk `base_dir` the way the scanner does: categories are top-level
+/// directories, items are their subdirectories holding a markdown file
+fn collect_listing(
+    base_dir: &Path,
+    category: Option<&str>,
+    compiled: Option<&HashSet<String>>,
+) -> Result<Vec<CategoryListing>, Box<dyn std::error::Error>> {
+    let category_names = match category {
+        Some(name) => {
+            if !base_dir.join(name).is_dir() {
+                return Err(format!("Category '{}' not found in '{}'", name, base_dir.display()).into());
+            }
+            vec![name.to_string()]
+        }
+        None => sorted_subdirs(base_dir)?,
+    };
+
+    let mut listing = Vec::new();
+    for category_name in category_names {
+        let category_dir = base_dir.join(&category_name);
+        let mut items = Vec::new();
+        for item_name in sorted_subdirs(&category_dir)? {
+            if item_name == "attachment" {
+                continue;
+            }
+            let item_dir = category_dir.join(&item_name);
+
+            // Slugs come from the markdown file name, preferring `{dir}.md`
+            let Some(stem) = item_markdown_stem(&item_dir, &item_name)? else {
+                continue;
+            };
+            let slug = format!("{}-{}", category_name, stem);
+
+            items.push(ItemListing {
+                has_attachments: item_dir.join("attachment").is_dir(),
+                compiled: compiled.map(|slugs| slugs.contains(&slug)),
+                name: item_name,
+                slug,
+            });
+        }
+        listing.push(CategoryListing {
+            name: category_name,
+            items,
+        });
+    }
+    Ok(listing)
+}
+
+/// Non-hidden subdirectory names, sorted
+/// Renames that give every category, item directory and markdown file a
+/// slug name, with the URLs that change as a result
+#[derive(Debug, Default)]
+struct SlugMigration {
+    renames: Vec<(PathBuf, PathBuf)>, // Deepest paths first, so parents are renamed last
+    moved_urls: Vec<(String, String)>,
+}
+
+fn plan_slug_migration(base_dir: &Path) -> Result<SlugMigration, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    let mut item_dirs = Vec::new();
+    let mut categories = Vec::new();
+    let mut moved_urls = Vec::new();
+
+    for category in sorted_subdirs(base_dir)? {
+        let category_dir = base_dir.join(&category);
+        let new_category = slugify(&category);
+        if new_category != category {
+            categories.push((category_dir.clone(), base_dir.join(&new_category)));
+            moved_urls.push((category.clone(), new_category.clone()));
+        }
+
+        for item in sorted_subdirs(&category_dir)? {
+            if item == "attachment" {
+                continue;
+            }
+            let item_dir = category_dir.join(&item);
+            let new_item = slugify(&item);
+            if new_item != item {
+                item_dirs.push((item_dir.clone(), category_dir.join(&new_item)));
+            }
+
+            let mut md_files: Vec<PathBuf> = fs::read_dir(&item_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+                .collect();
+            md_files.sort();
+            for md_path in md_files {
+                let Some(stem) = md_path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                let new_stem = slugify(stem);
+                if new_stem != stem {
+                    files.push((md_path.clone(), item_dir.join(format!("{}.md", new_stem))));
+                }
+                let slug_override = crate::markdown::parse_markdown(&fs::read_to_string(&md_path)?)
+                    .ok()
+                    .and_then(|parsed| parsed.frontmatter.slug);
+                let old_url = item_url(&category, stem, slug_override.as_deref());
+                let new_url = item_url(&new_category, &new_stem, slug_override.as_deref());
+                if old_url != new_url {
+                    moved_urls.push((old_url, new_url));
+                }
+            }
+        }
+    }
+
+    let renames: Vec<(PathBuf, PathBuf)> = files.into_iter().chain(item_dirs).chain(categories).collect();
+    for (i, (from, to)) in renames.iter().enumerate() {
+        if to.exists() || renames[..i].iter().any(|(_, other)| other == to) {
+            return Err(format!(
+                "'{}' would be renamed to '{}', which is already taken; rename one of them by hand first",
+                from.display(),
+                to.display()
+            )
+            .into());
+        }
+    }
+
+    Ok(SlugMigration { renames, moved_urls })
+}
+
+/// Show (or with `apply`, perform) the slug renames, printing redirect rules
+/// that keep the old URLs working
+fn migrate_slugs(base_dir: &Path, apply: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let migration = plan_slug_migration(base_dir)?;
+    if migration.renames.is_empty() {
+        println!("✓ All names in '{}' are already slugs", base_dir.display());
+        return Ok(());
+    }
+
+    for (from, to) in &migration.renames {
+        if apply {
+            fs::rename(from, to)?;
+        }
+        println!("  {} -> {}", from.display(), to.display());
+    }
+
+    if !migration.moved_urls.is_empty() {
+        println!();
+        println!("# Add to redirects.toml so old links keep working:");
+        for (from, to) in &migration.moved_urls {
+            println!("[[redirect]]\nfrom = \"/{}\"\nto = \"/{}\"\npermanent = true\n", from, to);
+        }
+    }
+
+    if apply {
+        println!("✓ Renamed {} paths; recompile the site to publish the new URLs", migration.renames.len());
+    } else {
+        println!("Nothing renamed yet; run again with --apply");
+    }
+    Ok(())
+}
+
+fn sorted_subdirs(dir: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with('.') {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn item_markdown_stem(item_dir: &Path, dir_name: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if item_dir.join(format!("{}.md", dir_name)).is_file() {
+        return Ok(Some(dir_name.to_string()));
+    }
+    let mut stems: Vec<String> = fs::read_dir(item_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_string))
+        .collect();
+    stems.sort();
+    Ok(stems.into_iter().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_listing() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_list_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("fruit/apple/attachment")).unwrap();
+        fs::create_dir_all(root.join("fruit/pear")).unwrap();
+        fs::create_dir_all(root.join("fruit/empty")).unwrap();
+        fs::create_dir_all(root.join("tools/.hidden")).unwrap();
+        fs::write(root.join("fruit/apple/apple.md"), "# Apple").unwrap();
+        fs::write(root.join("fruit/pear/notes.md"), "# Pear").unwrap();
+
+        let compiled: HashSet<String> = ["fruit-apple".to_string()].into_iter().collect();
+        let listing = collect_listing(&root, None, Some(&compiled)).unwrap();
+        assert_eq!(listing.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["fruit", "tools"]);
+
+        let items = &listing[0].items;
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].slug.as_str(), items[0].has_attachments, items[0].compiled), ("fruit-apple", true, Some(true)));
+        assert_eq!((items[1].slug.as_str(), items[1].has_attachments, items[1].compiled), ("fruit-notes", false, Some(false)));
+        assert!(listing[1].items.is_empty());
+
+        let only = collect_listing(&root, Some("fruit"), None).unwrap();
+        assert_eq!(only.len(), 1);
+        assert_eq!(only[0].items[0].compiled, None);
+        assert!(collect_listing(&root, Some("missing"), None).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_server_args() {
+        let cli = Cli::try_parse_from(["lfb", "server", "--config", "my.toml"]).unwrap();
+        match cli.command {
+            Commands::Server(args) => {
+                assert_eq!(args.config, PathBuf::from("my.toml"));
+                assert!(!args.validate && args.action.is_none());
+            }
+            other => panic!("expected server, got {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["lfb", "server"]).unwrap();
+        assert!(matches!(cli.command, Commands::Server(args) if args.config == PathBuf::from("lf_blog.toml")));
+
+        let err = Cli::try_parse_from(["lfb", "serve"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidSubcommand);
+    }
+
+    #[test]
+    fn test_migrate_slugs() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_migrate_slugs_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("葡萄/提子/attachment")).unwrap();
+        fs::create_dir_all(root.join("葡萄/Red Globe")).unwrap();
+        fs::create_dir_all(root.join("tools/knife")).unwrap();
+        fs::write(root.join("葡萄/index.md"), "# 葡萄").unwrap();
+        fs::write(root.join("葡萄/提子/提子.md"), "# 提子").unwrap();
+        fs::write(root.join("葡萄/Red Globe/Red Globe.md"), "---\nslug: globe\n---\n# Red Globe").unwrap();
+        fs::write(root.join("tools/knife/knife.md"), "# Knife").unwrap();
+
+        let migration = plan_slug_migration(&root).unwrap();
+        let renames: Vec<(String, String)> = migration
+            .renames
+            .iter()
+            .map(|(from, to)| {
+                let rel = |p: &PathBuf| p.strip_prefix(&root).unwrap().display().to_string();
+                (rel(from), rel(to))
+            })
+            .collect();
+        let expected = [
+            ("葡萄/Red Globe/Red Globe.md", "葡萄/Red Globe/red-globe.md"),
+            ("葡萄/提子/提子.md", "葡萄/提子/ti-zi.md"),
+            ("葡萄/Red Globe", "葡萄/red-globe"),
+            ("葡萄/提子", "葡萄/ti-zi"),
+            ("葡萄", "pu-tao"),
+        ];
+        assert_eq!(renames, expected.map(|(a, b)| (a.to_string(), b.to_string())));
+        assert_eq!(
+            migration.moved_urls,
+            vec![
+                ("葡萄".to_string(), "pu-tao".to_string()),
+                ("葡萄-globe".to_string(), "pu-tao-globe".to_string()),
+                ("葡萄-提子".to_string(), "pu-tao-ti-zi".to_string()),
+            ]
+        );
+
+        // Dry run leaves everything in place
+        migrate_slugs(&root, false).unwrap();
+        assert!(root.join("葡萄/提子/提子.md").is_file());
+
+        migrate_slugs(&root, true).unwrap();
+        assert!(root.join("pu-tao/ti-zi/ti-zi.md").is_file());
+        assert!(root.join("pu-tao/ti-zi/attachment").is_dir());
+        assert!(root.join("pu-tao/index.md").is_file());
+        assert!(plan_slug_migration(&root).unwrap().renames.is_empty());
+
+        // Two names that collapse to the same slug are left for the user
+        fs::create_dir_all(root.join("Tools")).unwrap();
+        assert!(plan_slug_migration(&root).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_pages_and_publish() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_publish_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("fruit/apple")).unwrap();
+        fs::create_dir_all(root.join("fruit/pear")).unwrap();
+        fs::write(root.join("fruit/apple/apple.md"), "---\r\ntitle: Apple\r\ndate: 2024-05-02\r\ndraft: true\r\n---\r\n# Apple\r\n").unwrap();
+        fs::write(root.join("fruit/pear/pear.md"), "---\ntitle: Pear\ndate: 2024-05-01\n---\n# Pear\ndraft: true\n").unwrap();
+
+        // Drafts stay off the site until published
+        assert_eq!(Scanner::new(root.clone()).scan().unwrap().categories[0].items.len(), 1);
+        let pages = collect_pages(&root, "fruit").unwrap();
+        let summary: Vec<(&str, bool)> = pages.iter().map(|p| (p.title.as_str(), p.draft)).collect();
+        assert_eq!(summary, vec![("Apple", true), ("Pear", false)]);
+        assert!(collect_pages(&root, "missing").is_err());
+
+        // Only the frontmatter flag changes
+        publish_page(&root, "fruit", "apple").unwrap();
+        let apple = fs::read_to_string(root.join("fruit/apple/apple.md")).unwrap();
+        assert_eq!(apple, "---\r\ntitle: Apple\r\ndate: 2024-05-02\r\ndraft: false\r\n---\r\n# Apple\r\n");
+        assert!(!collect_pages(&root, "fruit").unwrap()[0].draft);
+        assert_eq!(clear_draft_flag(&fs::read_to_string(root.join("fruit/pear/pear.md")).unwrap()), None);
+        assert!(publish_page(&root, "fruit", "plum").is_err());
+        assert_eq!(Scanner::new(root.clone()).scan().unwrap().categories[0].items.len(), 2);
+
+        delete_page(&root, "fruit", "pear", true).unwrap();
+        assert!(!root.join("fruit/pear").exists());
+        assert!(delete_page(&root, "fruit", "pear", true).is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_check_content() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_check_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("fruit/apple/attachment")).unwrap();
+        fs::create_dir_all(root.join("fruit/pear")).unwrap();
+        fs::create_dir_all(root.join("fruit-apple")).unwrap();
+        fs::write(root.join("fruit/index.md"), "# Fruit\n").unwrap();
+        fs::write(
+            root.join("fruit/apple/apple.md"),
+            "---\r\ntitle: Apple  \r\ntags:\r\n  - red\r\n---\r\n# Apple\r\n\r\nSee [pear](/fruit-pear), [tags](/tags?tag=red) and [gone](/fruit-gone#top).\r\n",
+        )
+        .unwrap();
+        fs::write(root.join("fruit/apple/attachment/big.bin"), vec![0u8; 2048]).unwrap();
+        fs::write(root.join("fruit/pear/pear.md"), "---\ntitle: [oops\n---\n\n# Pear\n").unwrap();
+        fs::write(root.join("fruit-apple/index.md"), "No heading here.\n").unwrap();
+
+        let report = check_content(&root, 1024, false).unwrap();
+        let kinds: Vec<(&str, Option<usize>)> = report.issues.iter().map(|i| (i.kind, i.line)).collect();
+        assert!(kinds.contains(&("frontmatter-format", Some(1))));
+        assert!(kinds.contains(&("frontmatter", Some(2))));
+        assert!(kinds.contains(&("broken-link", Some(8))));
+        assert!(kinds.contains(&("attachment-size", None)));
+        assert!(kinds.contains(&("missing-attachment-dir", None)));
+        assert!(kinds.contains(&("missing-h1", Some(1))));
+        assert!(kinds.contains(&("duplicate-slug", None)));
+        assert_eq!(report.issues.iter().filter(|i| i.kind == "broken-link").count(), 1);
+        assert_eq!(report.error_count(), 4);
+
+        // --fix 规范化 frontmatter 并补齐附件目录
+        let report = check_content(&root, 1024, true).unwrap();
+        assert_eq!(report.fixed.len(), 2);
+        assert!(root.join("fruit/pear/attachment").is_dir());
+        let apple = fs::read_to_string(root.join("fruit/apple/apple.md")).unwrap();
+        assert!(apple.starts_with("---\ntitle: Apple\ntags:\n  - red\n---\n\n# Apple\n"));
+        assert!(!report.issues.iter().any(|i| i.kind == "frontmatter-format" || i.kind == "missing-attachment-dir"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_init_project() {
+        let root = PathBuf::from("/tmp/lf_blog_cli_init_test");
+        let _ = fs::remove_dir_all(&root);
+        init_project(&root, "Notes", "lin", false).unwrap();
+
+        let config: crate::config::Config = toml::from_str(&fs::read_to_string(root.join("lf_blog.toml")).unwrap()).unwrap();
+        assert_eq!((config.site.title.as_str(), config.site.author.as_str()), ("Notes", "lin"));
+        assert!(root.join("templates").is_dir() && root.join("generated").is_dir());
+        assert!(fs::read_to_string(root.join(".gitignore")).unwrap().contains("/storage.db"));
+
+        let site = Scanner::new(root.join("content")).scan().unwrap();
+        assert_eq!(site.categories.len(), 1);
+        assert_eq!(site.categories[0].items.len(), 1);
+        assert_eq!(site.categories[0].items[0].title, "Hello, World");
+
+        // 非空目录需要 --force
+        assert!(init_project(&root, "Notes", "lin", false).is_err());
+        init_project(&root, "Notes", "lin", true).unwrap();
+
+        let _ = fs::remove_dir_all(&root);
+    }
 }
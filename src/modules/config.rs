@@ -1,18 +1,85 @@
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+/// 默认配置文件路径（不带参数启动时使用）
+pub const DEFAULT_CONFIG_PATH: &str = "lf_blog.toml";
+
+/// 站点配置
+///
+/// 服务运行中收到 SIGHUP 时会重新读取配置文件，但只有下列配置项会在运行时更新
+/// （见 `Config::apply_reload`）：
+/// - `site.*`：内容有变化时在后台重新编译全站
+/// - `analytics.*`：按请求读取的部分（如 IP 匿名化）立即生效，启动时创建的后台任务不变
+/// - `server.host`、`server.port`：只更新配置中的值，监听地址要重启后才改变
+///
+/// 其余配置项只在启动时读取。管理员会话保存在存储数据库中，重新加载不会使其失效。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub paths: PathConfig,
-    pub site: SiteConfig,
+    pub site: SiteConfig, // 可热加载
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub markdown: MarkdownConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+    #[serde(default)]
+    pub comments: CommentsConfig,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig, // 可热加载
+    #[serde(default)]
+    pub attachments: AttachmentsConfig,
+    #[serde(default)]
+    pub seo: SeoConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+    #[serde(default)]
+    pub content: ContentConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    #[serde(default)]
     pub admin_password: String,
+    #[serde(default)]
+    pub admin_password_hash: Option<String>, // argon2 哈希，设置后优先于明文密码
+    #[serde(default)]
+    pub api_keys: Vec<String>, // API 密钥的 SHA-256（十六进制），通过 Authorization: Bearer 访问管理接口
+    #[serde(default = "default_session_lifetime_hours")]
+    pub session_lifetime_hours: i64, // 管理员会话有效期（小时）
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize,         // 同时有效的管理员会话上限
+    #[serde(default)]
+    pub admin_ip_allowlist: Vec<String>, // 管理后台 IP 白名单（支持 CIDR），为空时不限制
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>, // 可信反向代理（支持 CIDR），只有来自这些地址的请求才采用 X-Forwarded-For / X-Real-IP
+    #[serde(default = "default_max_attachment_size_mb")]
+    pub max_attachment_size_mb: u64, // 单个附件大小上限（MB）
+    #[serde(default = "default_cors_origins")]
+    pub cors_origins: Vec<String>,   // 允许跨域访问公开 API 的来源，"*" 表示任意来源，为空时不发送 CORS 头
+    #[serde(default)]
+    pub autoreload_templates: bool,  // 开发模式：每次页面请求检查模板是否改动，改动后自动重新编译
+    #[serde(default = "default_render_cache_size")]
+    pub render_cache_size: usize,    // 内存中缓存的页面数，0 表示不缓存
+    #[serde(default = "default_attachment_cache_max_bytes")]
+    pub attachment_cache_max_bytes: usize, // 不超过此大小（字节）的数据库附件会缓存在内存中
+    #[serde(default)]
+    pub page_cache_seconds: u64,     // HTML 页面 Cache-Control 的 max-age（秒），0 表示浏览器每次都要重新验证
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: usize,     // 管理后台上传压缩包的大小上限（字节）
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,  // 关闭服务时等待进行中的编译和请求完成的最长秒数
+    #[serde(default)]
+    pub use_https: bool,             // 站点通过 HTTPS 访问时开启，响应带 Strict-Transport-Security
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,16 +88,287 @@ pub struct PathConfig {
     pub generated_dir: PathBuf,
     pub static_dir: PathBuf,
     pub templates_dir: PathBuf,
-    pub database_path: PathBuf,         // 评论数据库路径
-    pub storage_database_path: PathBuf, // 统一存储数据库路径
+    #[serde(default = "default_data_dir")]
+    pub data_dir: PathBuf,              // 数据库文件的默认目录
+    #[serde(default)]
+    pub storage_db: PathBuf,            // 页面、附件、会话，为空时为 {data_dir}/storage.db
+    #[serde(default)]
+    pub comments_db: PathBuf,           // 评论，为空时为 {data_dir}/comments.db
+    #[serde(default)]
+    pub analytics_db: PathBuf,          // 访问统计，为空时为 {data_dir}/analytics.db
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database_path: Option<PathBuf>, // 旧配置项：访问统计（更早版本还有评论），启动时迁移
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_database_path: Option<PathBuf>, // 旧配置项：存储数据库（含评论），启动时迁移
+    #[serde(default = "default_redirects_file")]
+    pub redirects_file: PathBuf,        // 重定向规则文件路径
+    #[serde(default = "default_themes_dir")]
+    pub themes_dir: PathBuf,            // 主题目录，每个主题位于 {themes_dir}/{name}/theme.toml
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SiteConfig {
     pub title: String,
     pub description: String,
     pub url: String,
     pub author: String,
+    #[serde(default)]
+    pub default_og_image: Option<String>, // 默认分享图片
+    #[serde(default)]
+    pub twitter_site: Option<String>,     // 站点 Twitter 账号，如 @example
+    #[serde(default = "default_feed_max_items")]
+    pub feed_max_items: usize,            // RSS 订阅中的最大条目数
+    #[serde(default)]
+    pub future_posts: bool,               // 为 false 时，日期在未来的文章到期后才发布
+    #[serde(default = "default_timezone")]
+    pub timezone: String,                 // 解释文章日期所用的时区，如 Asia/Shanghai
+    #[serde(default)]
+    pub theme: Option<String>,            // 使用的主题名，未设置时使用内置主题
+    #[serde(default = "default_language")]
+    pub default_language: String,         // 访客未选择语言时使用的语言（zh-CN 或 en）
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminConfig {
+    #[serde(default = "default_max_versions")]
+    pub max_versions: usize, // 每个页面保留的历史版本数
+    #[serde(default = "default_max_extract_size_mb")]
+    pub max_extract_size_mb: u64, // 上传压缩包解压后的总大小上限（MB）
+    #[serde(default = "default_max_extract_files")]
+    pub max_extract_files: usize, // 上传压缩包的条目数上限
+    #[serde(default = "default_attachment_extensions")]
+    pub attachment_extensions: Vec<String>, // 后台编辑器允许上传的附件扩展名
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            max_versions: default_max_versions(),
+            max_extract_size_mb: default_max_extract_size_mb(),
+            max_extract_files: default_max_extract_files(),
+            attachment_extensions: default_attachment_extensions(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    #[serde(default)]
+    pub webhook_url: Option<String>,    // 有新评论时 POST 通知的地址，为空时不发送
+    #[serde(default)]
+    pub webhook_secret: Option<String>, // 计算 X-Signature（HMAC-SHA256）所用的密钥
+    #[serde(default)]
+    pub retry: bool,                    // 发送失败后重试一次
+    #[serde(default)]
+    pub muted_slugs: Vec<String>,       // 这些文章的新评论不发送通知
+}
+
+/// 编译成功后通知的 webhook（如 CDN 刷新、聊天机器人）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebhooksConfig {
+    #[serde(default)]
+    pub urls: Vec<String>,      // 每次编译成功后 POST 编译结果的地址
+    #[serde(default)]
+    pub secret: Option<String>, // 计算 X-Signature（HMAC-SHA256）所用的密钥
+}
+
+/// 内容目录的来源
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentConfig {
+    #[serde(default)]
+    pub git: GitContentConfig, // [content.git]
+}
+
+/// 从 git 仓库同步内容目录（`POST /api/admin/sync`、`lfb server sync`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitContentConfig {
+    #[serde(default)]
+    pub url: String,                 // 远程仓库地址，为空时不启用同步
+    #[serde(default = "default_git_branch")]
+    pub branch: String,              // 同步的分支
+    #[serde(default)]
+    pub deploy_key: Option<PathBuf>, // 通过 SSH 访问私有仓库时使用的私钥（部署密钥）
+}
+
+impl Default for GitContentConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            branch: default_git_branch(),
+            deploy_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentsConfig {
+    #[serde(default = "default_comment_rate_limit_per_hour")]
+    pub rate_limit_per_hour: u32, // 每个 IP 每小时最多提交的评论数，0 表示不限制
+}
+
+impl Default for CommentsConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_per_hour: default_comment_rate_limit_per_hour(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    #[serde(default = "default_anonymize_ip")]
+    pub anonymize_ip: bool, // 保存访问记录前抹去 IP 末段（IPv4 末 8 位，IPv6 末 80 位）
+    #[serde(default)]
+    pub rollup_enabled: bool, // 每天把超过保留期的访问记录汇总为按天、按页面的计数，再删除原始记录
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32, // 原始访问记录的保留天数
+    #[serde(default)]
+    pub geoip_db_path: Option<PathBuf>, // MaxMind GeoLite2 数据库（.mmdb），设置后按 IP 记录访客的国家和城市
+    #[serde(default)]
+    pub bot_user_agents: Vec<String>, // 内置特征之外，用户代理包含这些字符串（不区分大小写）的访问也视为爬虫
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            anonymize_ip: default_anonymize_ip(),
+            rollup_enabled: false,
+            retention_days: default_retention_days(),
+            geoip_db_path: None,
+            bot_user_agents: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentsConfig {
+    #[serde(default = "default_optimize_images")]
+    pub optimize_images: bool,    // 上传内容包时优化其中的 JPEG/PNG/WebP 图片
+    #[serde(default = "default_image_max_width")]
+    pub max_width: u32,           // 图片最大宽度（像素），更宽的图片按比例缩小
+    #[serde(default = "default_png_to_webp_kb")]
+    pub png_to_webp_kb: u64,      // 超过该大小（KB）的 PNG 转为 WebP，0 表示不转换
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            optimize_images: default_optimize_images(),
+            max_width: default_image_max_width(),
+            png_to_webp_kb: default_png_to_webp_kb(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeoConfig {
+    #[serde(default = "default_robots_disallow")]
+    pub robots_disallow: Vec<String>,    // robots.txt 中禁止抓取的路径前缀
+    #[serde(default)]
+    pub robots_custom: Option<String>,   // 完整的自定义 robots.txt 内容，设置后忽略其他选项
+    #[serde(default)]
+    pub crawl_delay: Option<u32>,        // robots.txt 中的 Crawl-delay（秒）
+}
+
+impl Default for SeoConfig {
+    fn default() -> Self {
+        Self {
+            robots_disallow: default_robots_disallow(),
+            robots_custom: None,
+            crawl_delay: None,
+        }
+    }
+}
+
+/// 安全相关的响应头
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    #[serde(default = "default_csp", serialize_with = "serialize_sorted")]
+    pub csp: HashMap<String, Vec<String>>, // Content-Security-Policy：指令 -> 来源（原样写入，如 "'self'"），为空时不发送
+    #[serde(default = "default_allow_inline_scripts")]
+    pub allow_inline_scripts: bool,        // 内置模板使用内联脚本；关闭后 script-src 不含 'unsafe-inline'
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            csp: default_csp(),
+            allow_inline_scripts: default_allow_inline_scripts(),
+        }
+    }
+}
+
+impl SecurityConfig {
+    /// Content-Security-Policy 头的值，指令按名称排序；未配置任何指令时为 None
+    pub fn csp_header(&self) -> Option<String> {
+        let mut directives: Vec<(&String, &Vec<String>)> = self.csp.iter().collect();
+        directives.sort();
+        let header = directives
+            .into_iter()
+            .map(|(directive, sources)| {
+                let mut sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                if directive == "script-src" && self.allow_inline_scripts && !sources.contains(&"'unsafe-inline'") {
+                    sources.push("'unsafe-inline'");
+                }
+                if sources.is_empty() {
+                    directive.clone()
+                } else {
+                    format!("{} {}", directive, sources.join(" "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        (!header.is_empty()).then_some(header)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub attachment_backend: AttachmentBackend, // 附件存储位置
+}
+
+/// 附件存储后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentBackend {
+    /// 附件内容存入数据库 BLOB
+    #[default]
+    Db,
+    /// 附件复制到 generated_dir/attachments，数据库只存元信息
+    Filesystem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownConfig {
+    #[serde(default = "default_highlight")]
+    pub highlight: bool, // 编译时代码高亮（会增加编译时间）
+    #[serde(default = "default_markdown_extension")]
+    pub tables: bool,    // GFM 表格
+    #[serde(default = "default_markdown_extension")]
+    pub footnotes: bool, // 脚注（带返回正文的链接）
+    #[serde(default = "default_markdown_extension")]
+    pub tasklists: bool, // 任务列表 `- [ ]`
+    #[serde(default = "default_markdown_extension")]
+    pub strikethrough: bool, // 删除线 `~~text~~`
+    #[serde(default = "default_markdown_extension")]
+    pub smart_punctuation: bool, // 直引号转为弯引号，`--`/`---` 转为破折号
+    #[serde(default = "default_markdown_extension")]
+    pub enable_callouts: bool, // `::: warning` 等提示块
+}
+
+impl Default for MarkdownConfig {
+    fn default() -> Self {
+        Self {
+            highlight: default_highlight(),
+            tables: default_markdown_extension(),
+            footnotes: default_markdown_extension(),
+            tasklists: default_markdown_extension(),
+            strikethrough: default_markdown_extension(),
+            smart_punctuation: default_markdown_extension(),
+            enable_callouts: default_markdown_extension(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -40,41 +378,742 @@ impl Default for Config {
                 host: "127.0.0.1".to_string(),
                 port: 8080,
                 admin_password: "admin".to_string(),
+                admin_password_hash: None,
+                api_keys: Vec::new(),
+                session_lifetime_hours: default_session_lifetime_hours(),
+                max_sessions: default_max_sessions(),
+                admin_ip_allowlist: Vec::new(),
+                trusted_proxies: Vec::new(),
+                max_attachment_size_mb: default_max_attachment_size_mb(),
+                cors_origins: default_cors_origins(),
+                autoreload_templates: false,
+                render_cache_size: default_render_cache_size(),
+                attachment_cache_max_bytes: default_attachment_cache_max_bytes(),
+                page_cache_seconds: 0,
+                max_upload_bytes: default_max_upload_bytes(),
+                shutdown_timeout_secs: default_shutdown_timeout_secs(),
+                use_https: false,
             },
             paths: PathConfig {
                 content_dir: PathBuf::from("content"),
                 generated_dir: PathBuf::from("generated"),
                 static_dir: PathBuf::from("static"),
                 templates_dir: PathBuf::from("templates"),
-                database_path: PathBuf::from("comments.db"),
-                storage_database_path: PathBuf::from("storage.db"),
+                data_dir: default_data_dir(),
+                storage_db: default_data_dir().join("storage.db"),
+                comments_db: default_data_dir().join("comments.db"),
+                analytics_db: default_data_dir().join("analytics.db"),
+                database_path: None,
+                storage_database_path: None,
+                redirects_file: default_redirects_file(),
+                themes_dir: default_themes_dir(),
             },
             site: SiteConfig {
                 title: "My Collections".to_string(),
                 description: "A blog about my collections".to_string(),
                 url: "http://localhost:8080".to_string(),
                 author: "xiaolinfeng".to_string(),
+                default_og_image: None,
+                twitter_site: None,
+                feed_max_items: default_feed_max_items(),
+                future_posts: false,
+                timezone: default_timezone(),
+                theme: None,
+                default_language: default_language(),
             },
+            storage: StorageConfig::default(),
+            markdown: MarkdownConfig::default(),
+            admin: AdminConfig::default(),
+            notifications: NotificationConfig::default(),
+            comments: CommentsConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            attachments: AttachmentsConfig::default(),
+            seo: SeoConfig::default(),
+            security: SecurityConfig::default(),
+            webhooks: WebhooksConfig::default(),
+            content: ContentConfig::default(),
+        }
+    }
+}
+
+fn default_session_lifetime_hours() -> i64 {
+    24
+}
+
+fn default_max_sessions() -> usize {
+    5
+}
+
+fn default_render_cache_size() -> usize {
+    200
+}
+
+fn default_attachment_cache_max_bytes() -> usize {
+    256 * 1024
+}
+
+fn default_max_upload_bytes() -> usize {
+    50 * 1024 * 1024
+}
+
+fn default_data_dir() -> PathBuf {
+    PathBuf::from("data")
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    60
+}
+
+fn default_max_attachment_size_mb() -> u64 {
+    1024
+}
+
+fn default_cors_origins() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn default_max_versions() -> usize {
+    10
+}
+
+fn default_max_extract_size_mb() -> u64 {
+    512
+}
+
+fn default_max_extract_files() -> usize {
+    10000
+}
+
+fn default_attachment_extensions() -> Vec<String> {
+    ["jpg", "jpeg", "png", "gif", "webp", "svg", "pdf", "zip", "txt", "mp3", "mp4"]
+        .iter()
+        .map(|ext| ext.to_string())
+        .collect()
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_feed_max_items() -> usize {
+    20
+}
+
+fn default_highlight() -> bool {
+    true
+}
+
+fn default_markdown_extension() -> bool {
+    true
+}
+
+fn default_redirects_file() -> PathBuf {
+    PathBuf::from("redirects.toml")
+}
+
+fn default_comment_rate_limit_per_hour() -> u32 {
+    5
+}
+
+fn default_anonymize_ip() -> bool {
+    true
+}
+
+fn default_retention_days() -> u32 {
+    90
+}
+
+fn default_optimize_images() -> bool {
+    true
+}
+
+fn default_image_max_width() -> u32 {
+    1920
+}
+
+fn default_png_to_webp_kb() -> u64 {
+    512
+}
+
+fn default_robots_disallow() -> Vec<String> {
+    vec!["/admin".to_string(), "/api".to_string()]
+}
+
+/// 默认 CSP：只允许本站资源，另外放行 KaTeX 所在的 CDN 和任意 HTTPS 图片；
+/// 模板中的 style 属性需要 'unsafe-inline'
+fn default_csp() -> HashMap<String, Vec<String>> {
+    const KATEX_CDN: &str = "https://cdn.jsdelivr.net";
+    [
+        ("default-src", vec!["'self'"]),
+        ("script-src", vec!["'self'", KATEX_CDN]),
+        ("style-src", vec!["'self'", "'unsafe-inline'", KATEX_CDN]),
+        ("font-src", vec!["'self'", "data:", KATEX_CDN]),
+        ("img-src", vec!["'self'", "data:", "https:"]),
+        ("connect-src", vec!["'self'"]),
+        ("object-src", vec!["'none'"]),
+        ("base-uri", vec!["'self'"]),
+        ("frame-ancestors", vec!["'self'"]),
+    ]
+    .into_iter()
+    .map(|(directive, sources)| (directive.to_string(), sources.into_iter().map(String::from).collect()))
+    .collect()
+}
+
+fn default_allow_inline_scripts() -> bool {
+    true
+}
+
+/// 按键排序写出 HashMap，保存的配置文件不会每次顺序不同
+fn serialize_sorted<S: Serializer>(map: &HashMap<String, Vec<String>>, serializer: S) -> Result<S::Ok, S::Error> {
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+fn default_language() -> String {
+    "zh-CN".to_string()
+}
+
+fn default_git_branch() -> String {
+    "main".to_string()
+}
+
+fn default_themes_dir() -> PathBuf {
+    PathBuf::from("themes")
+}
+
+impl PathConfig {
+    /// 未单独配置的数据库放在 data_dir 下
+    pub fn resolve_database_paths(&mut self) {
+        for (path, file_name) in [
+            (&mut self.storage_db, "storage.db"),
+            (&mut self.comments_db, "comments.db"),
+            (&mut self.analytics_db, "analytics.db"),
+        ] {
+            if path.as_os_str().is_empty() {
+                *path = self.data_dir.join(file_name);
+            }
         }
     }
 }
 
 impl Config {
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        // Try to load from config file, fall back to default
-        let config_path = PathBuf::from("lf_blog.toml");
+    /// 文件系统模式下附件的存放目录
+    pub fn attachments_dir(&self) -> PathBuf {
+        self.paths.generated_dir.join("attachments")
+    }
+
+    /// 站点时区，配置无效时回退到 UTC
+    pub fn site_timezone(&self) -> Tz {
+        self.site.timezone.parse().unwrap_or_else(|_| {
+            log::warn!("Unknown site.timezone '{}', using UTC", self.site.timezone);
+            Tz::UTC
+        })
+    }
+
+    /// 从指定文件加载配置并用 `LFB_` 环境变量覆盖（见 `ENV_OVERRIDES`），
+    /// 文件不存在时等同于 `from_env`
+    pub fn load_from(config_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        if !config_path.exists() {
+            return Ok(Self::from_env());
+        }
+        let mut config = Self::load_file(config_path)?;
+        config.apply_env_overrides();
+        Ok(config)
+    }
+
+    /// 只从配置文件加载，不读取环境变量；修改后要写回文件时使用，
+    /// 以免把环境变量中的值（如密码）写进文件
+    pub fn load_file(config_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         if config_path.exists() {
-            let content = std::fs::read_to_string(&config_path)?;
-            let config: Config = toml::from_str(&content)?;
+            let content = std::fs::read_to_string(config_path)?;
+            let mut config: Config = toml::from_str(&content)?;
+            config.paths.resolve_database_paths();
             Ok(config)
         } else {
             Ok(Self::default())
         }
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+    /// 不使用配置文件，在默认配置上应用 `LFB_` 环境变量，适合容器部署
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        config.apply_env_overrides();
+        config
+    }
+
+    /// 用进程环境变量覆盖配置项
+    pub fn apply_env_overrides(&mut self) -> Vec<&'static str> {
+        self.apply_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// 用 `lookup` 返回的值覆盖 `ENV_OVERRIDES` 中的配置项，返回被覆盖的配置项。
+    /// 无法解析的值记录警告后忽略，保留原来的值
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Vec<&'static str> {
+        let mut overridden = Vec::new();
+        for &(var, field) in ENV_OVERRIDES {
+            let Some(value) = lookup(var) else {
+                continue;
+            };
+            match self.set_field(field, &value) {
+                Ok(()) => {
+                    let shown = if is_secret_field(field) { "******" } else { value.as_str() };
+                    log::info!("Config: {} = {} (from {})", field, shown, var);
+                    overridden.push(field);
+                }
+                Err(e) => log::warn!("Config: ignoring {}: {}", var, e),
+            }
+        }
+        overridden
+    }
+
+    /// 按配置项名设置 `ENV_OVERRIDES` 中的配置项
+    fn set_field(&mut self, field: &str, value: &str) -> Result<(), String> {
+        let optional = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+        match field {
+            "server.host" => self.server.host = value.to_string(),
+            "server.port" => self.server.port = parse_env_value(value)?,
+            "server.admin_password" => self.server.admin_password = value.to_string(),
+            "server.admin_password_hash" => self.server.admin_password_hash = optional(value),
+            "server.cors_origins" => self.server.cors_origins = split_env_list(value),
+            "server.session_lifetime_hours" => self.server.session_lifetime_hours = parse_env_value(value)?,
+            "server.render_cache_size" => self.server.render_cache_size = parse_env_value(value)?,
+            "server.page_cache_seconds" => self.server.page_cache_seconds = parse_env_value(value)?,
+            "server.autoreload_templates" => self.server.autoreload_templates = parse_env_bool(value)?,
+            "site.title" => self.site.title = value.to_string(),
+            "site.description" => self.site.description = value.to_string(),
+            "site.url" => self.site.url = value.to_string(),
+            "site.author" => self.site.author = value.to_string(),
+            "site.timezone" => self.site.timezone = value.to_string(),
+            "site.default_language" => self.site.default_language = value.to_string(),
+            "paths.content_dir" => self.paths.content_dir = PathBuf::from(value),
+            "paths.generated_dir" => self.paths.generated_dir = PathBuf::from(value),
+            "paths.static_dir" => self.paths.static_dir = PathBuf::from(value),
+            "paths.templates_dir" => self.paths.templates_dir = PathBuf::from(value),
+            "paths.data_dir" => {
+                // 仍在原数据目录默认位置的数据库跟着移动
+                let previous = std::mem::replace(&mut self.paths.data_dir, PathBuf::from(value));
+                for (path, file_name) in [
+                    (&mut self.paths.storage_db, "storage.db"),
+                    (&mut self.paths.comments_db, "comments.db"),
+                    (&mut self.paths.analytics_db, "analytics.db"),
+                ] {
+                    if *path == previous.join(file_name) {
+                        *path = self.paths.data_dir.join(file_name);
+                    }
+                }
+            }
+            "paths.storage_db" => self.paths.storage_db = PathBuf::from(value),
+            "paths.comments_db" => self.paths.comments_db = PathBuf::from(value),
+            "paths.analytics_db" => self.paths.analytics_db = PathBuf::from(value),
+            "analytics.anonymize_ip" => self.analytics.anonymize_ip = parse_env_bool(value)?,
+            "analytics.geoip_db_path" => self.analytics.geoip_db_path = optional(value).map(PathBuf::from),
+            "notifications.webhook_url" => self.notifications.webhook_url = optional(value),
+            "notifications.webhook_secret" => self.notifications.webhook_secret = optional(value),
+            "webhooks.urls" => self.webhooks.urls = split_env_list(value),
+            "webhooks.secret" => self.webhooks.secret = optional(value),
+            _ => return Err(format!("unknown config field {}", field)),
+        }
+        Ok(())
+    }
+
+    /// 把重新读取的配置中可以在运行时修改的部分（`site`、`analytics`、`server.host`、
+    /// `server.port`）合并进来，其他配置项保持不变；返回需要提醒管理员的变化
+    pub fn apply_reload(&mut self, reloaded: Config) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if reloaded.server.host != self.server.host || reloaded.server.port != self.server.port {
+            warnings.push(format!(
+                "server.host/server.port changed from {}:{} to {}:{}; restart the server to listen on the new address",
+                self.server.host, self.server.port, reloaded.server.host, reloaded.server.port
+            ));
+        }
+        self.server.host = reloaded.server.host;
+        self.server.port = reloaded.server.port;
+        self.site = reloaded.site;
+        self.analytics = reloaded.analytics;
+        warnings
+    }
+
+    /// 将配置写入指定文件
+    pub fn save_to(&self, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         let content = toml::to_string_pretty(self)?;
-        std::fs::write("lf_blog.toml", content)?;
+        std::fs::write(config_path, content)?;
         Ok(())
     }
 }
+
+/// 可以用环境变量覆盖的配置项：(环境变量, 配置项)。列表类配置项用逗号分隔，
+/// 可选配置项设为空字符串时清除
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("LFB_SERVER_HOST", "server.host"),
+    ("LFB_SERVER_PORT", "server.port"),
+    ("LFB_SERVER_ADMIN_PASSWORD", "server.admin_password"),
+    ("LFB_SERVER_ADMIN_PASSWORD_HASH", "server.admin_password_hash"),
+    ("LFB_SERVER_CORS_ORIGINS", "server.cors_origins"),
+    ("LFB_SERVER_SESSION_LIFETIME_HOURS", "server.session_lifetime_hours"),
+    ("LFB_SERVER_RENDER_CACHE_SIZE", "server.render_cache_size"),
+    ("LFB_SERVER_PAGE_CACHE_SECONDS", "server.page_cache_seconds"),
+    ("LFB_SERVER_AUTORELOAD_TEMPLATES", "server.autoreload_templates"),
+    ("LFB_SITE_TITLE", "site.title"),
+    ("LFB_SITE_DESCRIPTION", "site.description"),
+    ("LFB_SITE_BASE_URL", "site.url"),
+    ("LFB_SITE_AUTHOR", "site.author"),
+    ("LFB_SITE_TIMEZONE", "site.timezone"),
+    ("LFB_SITE_DEFAULT_LANGUAGE", "site.default_language"),
+    ("LFB_PATHS_CONTENT_DIR", "paths.content_dir"),
+    ("LFB_PATHS_GENERATED_DIR", "paths.generated_dir"),
+    ("LFB_PATHS_STATIC_DIR", "paths.static_dir"),
+    ("LFB_PATHS_TEMPLATES_DIR", "paths.templates_dir"),
+    ("LFB_PATHS_DATA_DIR", "paths.data_dir"),
+    ("LFB_PATHS_STORAGE_DB", "paths.storage_db"),
+    ("LFB_PATHS_COMMENTS_DB", "paths.comments_db"),
+    ("LFB_PATHS_ANALYTICS_DB", "paths.analytics_db"),
+    ("LFB_ANALYTICS_ANONYMIZE_IP", "analytics.anonymize_ip"),
+    ("LFB_ANALYTICS_GEOIP_DB_PATH", "analytics.geoip_db_path"),
+    ("LFB_NOTIFICATIONS_WEBHOOK_URL", "notifications.webhook_url"),
+    ("LFB_NOTIFICATIONS_WEBHOOK_SECRET", "notifications.webhook_secret"),
+    ("LFB_WEBHOOKS_URLS", "webhooks.urls"),
+    ("LFB_WEBHOOKS_SECRET", "webhooks.secret"),
+];
+
+/// 日志中需要隐藏值的配置项
+fn is_secret_field(field: &str) -> bool {
+    field.contains("password") || field.contains("secret")
+}
+
+fn parse_env_value<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("'{}' is not a valid value", value))
+}
+
+fn parse_env_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{}' is not a boolean", value)),
+    }
+}
+
+fn split_env_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 配置错误：存在时拒绝启动
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    ContentDirMissing(PathBuf),
+    ContentDirUnreadable(PathBuf, String),
+    TemplatesDirUnavailable(PathBuf, String),
+    InvalidPort(u16),
+    AdminPasswordEmpty,
+    AdminPasswordTooShort(usize),
+    InvalidSiteUrl(String, String),
+    ZeroRetentionDays,
+    InvalidIpList(&'static str, String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::ContentDirMissing(path) => {
+                write!(f, "paths.content_dir '{}' does not exist", path.display())
+            }
+            ConfigError::ContentDirUnreadable(path, e) => {
+                write!(f, "paths.content_dir '{}' cannot be read: {}", path.display(), e)
+            }
+            ConfigError::TemplatesDirUnavailable(path, reason) => {
+                write!(f, "paths.templates_dir '{}' does not exist and cannot be created: {}", path.display(), reason)
+            }
+            ConfigError::InvalidPort(port) => write!(f, "server.port {} is not in the range 1-65535", port),
+            ConfigError::AdminPasswordEmpty => {
+                write!(f, "server.admin_password is empty and no server.admin_password_hash is set")
+            }
+            ConfigError::AdminPasswordTooShort(len) => write!(
+                f,
+                "server.admin_password has {} characters, at least {} are required (or set server.admin_password_hash)",
+                len, MIN_ADMIN_PASSWORD_LEN
+            ),
+            ConfigError::InvalidSiteUrl(url, e) => write!(f, "site.url '{}' is not a valid http(s) URL: {}", url, e),
+            ConfigError::ZeroRetentionDays => {
+                write!(f, "analytics.retention_days must be at least 1 when analytics.rollup_enabled is set")
+            }
+            ConfigError::InvalidIpList(key, e) => write!(f, "{}: {}", key, e),
+        }
+    }
+}
+
+/// 配置警告：打印后继续启动
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigWarning {
+    TemplatesDirWillBeCreated(PathBuf),
+    UnknownTimezone(String),
+    UnknownLanguage(String),
+    PlaintextPasswordIgnored,
+    GeoIpDatabaseMissing(PathBuf),
+    GitDeployKeyMissing(PathBuf),
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigWarning::TemplatesDirWillBeCreated(path) => {
+                write!(f, "paths.templates_dir '{}' does not exist and will be created", path.display())
+            }
+            ConfigWarning::UnknownTimezone(tz) => write!(f, "site.timezone '{}' is unknown, UTC will be used", tz),
+            ConfigWarning::UnknownLanguage(lang) => write!(
+                f,
+                "site.default_language '{}' has no bundled translations, {} will be used",
+                lang,
+                crate::i18n::FALLBACK_LANGUAGE
+            ),
+            ConfigWarning::PlaintextPasswordIgnored => {
+                write!(f, "server.admin_password is ignored because server.admin_password_hash is set")
+            }
+            ConfigWarning::GeoIpDatabaseMissing(path) => write!(
+                f,
+                "analytics.geoip_db_path '{}' does not exist, visitor locations will not be recorded",
+                path.display()
+            ),
+            ConfigWarning::GitDeployKeyMissing(path) => write!(
+                f,
+                "content.git.deploy_key '{}' does not exist, syncing over SSH will fail",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// 明文管理员密码的最短长度
+pub const MIN_ADMIN_PASSWORD_LEN: usize = 8;
+
+impl Config {
+    /// 启动前检查配置；有错误时返回全部错误，否则返回警告
+    pub fn validate(&self) -> Result<Vec<ConfigWarning>, Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let content_dir = &self.paths.content_dir;
+        if !content_dir.is_dir() {
+            errors.push(ConfigError::ContentDirMissing(content_dir.clone()));
+        } else if let Err(e) = std::fs::read_dir(content_dir) {
+            errors.push(ConfigError::ContentDirUnreadable(content_dir.clone(), e.to_string()));
+        }
+
+        // 模板目录不存在时由 TemplateRenderer 创建，只需最近的已有上级目录可写
+        let templates_dir = &self.paths.templates_dir;
+        if !templates_dir.is_dir() {
+            let ancestor = templates_dir
+                .ancestors()
+                .skip(1)
+                .find(|dir| dir.as_os_str().is_empty() || dir.exists());
+            let writable = match ancestor {
+                Some(dir) if dir.as_os_str().is_empty() => Ok(()),
+                Some(dir) if !dir.is_dir() => Err(format!("'{}' is not a directory", dir.display())),
+                Some(dir) => match std::fs::metadata(dir) {
+                    Ok(meta) if meta.permissions().readonly() => Err(format!("'{}' is read-only", dir.display())),
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                },
+                None => Err("no existing parent directory".to_string()),
+            };
+            match writable {
+                Ok(()) => warnings.push(ConfigWarning::TemplatesDirWillBeCreated(templates_dir.clone())),
+                Err(reason) => errors.push(ConfigError::TemplatesDirUnavailable(templates_dir.clone(), reason)),
+            }
+        }
+
+        if self.server.port == 0 {
+            errors.push(ConfigError::InvalidPort(self.server.port));
+        }
+
+        let has_hash = self.server.admin_password_hash.as_deref().is_some_and(|h| !h.is_empty());
+        if has_hash {
+            if !self.server.admin_password.is_empty() {
+                warnings.push(ConfigWarning::PlaintextPasswordIgnored);
+            }
+        } else if self.server.admin_password.is_empty() {
+            errors.push(ConfigError::AdminPasswordEmpty);
+        } else if self.server.admin_password.chars().count() < MIN_ADMIN_PASSWORD_LEN {
+            errors.push(ConfigError::AdminPasswordTooShort(self.server.admin_password.chars().count()));
+        }
+
+        for (key, entries) in [
+            ("server.admin_ip_allowlist", &self.server.admin_ip_allowlist),
+            ("server.trusted_proxies", &self.server.trusted_proxies),
+        ] {
+            if let Err(e) = crate::ip_filter::parse_allowlist(entries) {
+                errors.push(ConfigError::InvalidIpList(key, e));
+            }
+        }
+
+        let site_url = self.site.url.trim();
+        if !site_url.is_empty() {
+            match reqwest::Url::parse(site_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(url) => errors.push(ConfigError::InvalidSiteUrl(
+                    site_url.to_string(),
+                    format!("unsupported scheme '{}'", url.scheme()),
+                )),
+                Err(e) => errors.push(ConfigError::InvalidSiteUrl(site_url.to_string(), e.to_string())),
+            }
+        }
+
+        if self.analytics.rollup_enabled && self.analytics.retention_days == 0 {
+            errors.push(ConfigError::ZeroRetentionDays);
+        }
+
+        if let Some(geoip_db_path) = &self.analytics.geoip_db_path {
+            if !geoip_db_path.is_file() {
+                warnings.push(ConfigWarning::GeoIpDatabaseMissing(geoip_db_path.clone()));
+            }
+        }
+
+        if let Some(deploy_key) = &self.content.git.deploy_key {
+            if !self.content.git.url.is_empty() && !deploy_key.is_file() {
+                warnings.push(ConfigWarning::GitDeployKeyMissing(deploy_key.clone()));
+            }
+        }
+
+        if self.site.timezone.parse::<Tz>().is_err() {
+            warnings.push(ConfigWarning::UnknownTimezone(self.site.timezone.clone()));
+        }
+
+        if crate::i18n::supported_language(&self.site.default_language).is_none() {
+            warnings.push(ConfigWarning::UnknownLanguage(self.site.default_language.clone()));
+        }
+
+        if errors.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let root = PathBuf::from("/tmp/lf_blog_config_validate_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("content")).unwrap();
+
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.server.admin_password = "correct horse".to_string();
+        assert_eq!(
+            config.validate(),
+            Ok(vec![ConfigWarning::TemplatesDirWillBeCreated(root.join("templates"))])
+        );
+
+        // 默认密码过短、端口为 0、站点地址无效、内容目录缺失都会报错
+        config.server.admin_password = "admin".to_string();
+        config.server.port = 0;
+        config.site.url = "localhost:8080".to_string();
+        config.paths.content_dir = root.join("missing");
+        config.analytics.rollup_enabled = true;
+        config.analytics.retention_days = 0;
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 5);
+        assert!(errors.contains(&ConfigError::ZeroRetentionDays));
+        assert!(errors.contains(&ConfigError::AdminPasswordTooShort(5)));
+        assert!(errors.contains(&ConfigError::InvalidPort(0)));
+        assert!(errors.contains(&ConfigError::ContentDirMissing(root.join("missing"))));
+
+        // 设置了哈希后不再检查明文密码
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("content");
+        config.server.admin_password_hash = Some("$argon2id$...".to_string());
+        config.site.timezone = "Mars/Olympus".to_string();
+        config.analytics.geoip_db_path = Some(root.join("GeoLite2-City.mmdb"));
+        config.content.git.url = "git@example.com:blog/content.git".to_string();
+        config.content.git.deploy_key = Some(root.join("deploy_key"));
+        assert_eq!(
+            config.validate(),
+            Ok(vec![
+                ConfigWarning::PlaintextPasswordIgnored,
+                ConfigWarning::GeoIpDatabaseMissing(root.join("GeoLite2-City.mmdb")),
+                ConfigWarning::GitDeployKeyMissing(root.join("deploy_key")),
+                ConfigWarning::UnknownTimezone("Mars/Olympus".to_string())
+            ])
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        // 用变量表代替进程环境变量，避免影响并行运行的其他测试
+        let env: std::collections::HashMap<&str, &str> = [
+            ("LFB_SERVER_HOST", "0.0.0.0"),
+            ("LFB_SERVER_PORT", "9000"),
+            ("LFB_SERVER_ADMIN_PASSWORD", "from the environment"),
+            ("LFB_SERVER_CORS_ORIGINS", "https://a.example, https://b.example"),
+            ("LFB_SERVER_SESSION_LIFETIME_HOURS", "not a number"),
+            ("LFB_SERVER_AUTORELOAD_TEMPLATES", "yes"),
+            ("LFB_SITE_TITLE", "Container Blog"),
+            ("LFB_SITE_BASE_URL", "https://blog.example"),
+            ("LFB_SITE_TIMEZONE", "Asia/Shanghai"),
+            ("LFB_PATHS_CONTENT_DIR", "/srv/content"),
+            ("LFB_PATHS_DATA_DIR", "/srv/data"),
+            ("LFB_PATHS_ANALYTICS_DB", "/var/lib/analytics.db"),
+            ("LFB_ANALYTICS_ANONYMIZE_IP", "false"),
+            ("LFB_NOTIFICATIONS_WEBHOOK_URL", "https://hooks.example/new-comment"),
+            ("LFB_NOTIFICATIONS_WEBHOOK_SECRET", "s3cret"),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut config = Config::default();
+        config.analytics.geoip_db_path = Some(PathBuf::from("GeoLite2-City.mmdb"));
+        let overridden = config.apply_overrides(|name| env.get(name).map(|value| value.to_string()));
+
+        // 无法解析的值被忽略
+        assert_eq!(overridden.len(), env.len() - 1);
+        assert!(!overridden.contains(&"server.session_lifetime_hours"));
+        assert_eq!(config.server.session_lifetime_hours, Config::default().server.session_lifetime_hours);
+
+        assert_eq!(config.server.host, "0.0.0.0");
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.server.admin_password, "from the environment");
+        assert_eq!(config.server.cors_origins, vec!["https://a.example", "https://b.example"]);
+        assert!(config.server.autoreload_templates);
+        assert_eq!(config.site.title, "Container Blog");
+        assert_eq!(config.site.url, "https://blog.example");
+        assert_eq!(config.site.timezone, "Asia/Shanghai");
+        assert_eq!(config.paths.content_dir, PathBuf::from("/srv/content"));
+        // 默认位置的数据库随数据目录移动，单独设置的不受影响
+        assert_eq!(config.paths.storage_db, PathBuf::from("/srv/data/storage.db"));
+        assert_eq!(config.paths.comments_db, PathBuf::from("/srv/data/comments.db"));
+        assert_eq!(config.paths.analytics_db, PathBuf::from("/var/lib/analytics.db"));
+        assert!(!config.analytics.anonymize_ip);
+        assert_eq!(config.analytics.geoip_db_path, Some(PathBuf::from("GeoLite2-City.mmdb")));
+        assert_eq!(config.notifications.webhook_url.as_deref(), Some("https://hooks.example/new-comment"));
+        assert_eq!(config.notifications.webhook_secret.as_deref(), Some("s3cret"));
+
+        // 空字符串清除可选配置项
+        let overridden = config.apply_overrides(|name| (name == "LFB_ANALYTICS_GEOIP_DB_PATH").then(String::new));
+        assert_eq!(overridden, vec!["analytics.geoip_db_path"]);
+        assert_eq!(config.analytics.geoip_db_path, None);
+
+        assert!(is_secret_field("server.admin_password"));
+        assert!(is_secret_field("notifications.webhook_secret"));
+        assert!(!is_secret_field("site.title"));
+
+        // 每个配置项都能设置
+        for &(var, field) in ENV_OVERRIDES {
+            let value = match field {
+                "server.port" | "server.session_lifetime_hours" | "server.render_cache_size" | "server.page_cache_seconds" => "1",
+                "server.autoreload_templates" | "analytics.anonymize_ip" => "true",
+                _ => "value",
+            };
+            assert_eq!(Config::default().set_field(field, value), Ok(()), "{}", var);
+        }
+    }
+}
@@ -1,132 +1,444 @@
-use crate::config::Config;
-use crate::scanner::{Attachment, Scanner, SiteContent};
-use crate::storage::{Page, PageType, StoredAttachment, StorageDB};
+use crate::error::BlogError;
+use crate::config::{AttachmentBackend, Config};
+use crate::scanner::{BrokenLink, Category, ContentItem, ItemLink, Scanner, SiteContent};
+use crate::storage::{AttachmentRef, Page, PageType, StoredAttachment, StorageDB};
+use crate::series::SeriesIndex;
+use crate::tags::{TagFilter, TaggedItem};
 use crate::templates::TemplateRenderer;
-use crate::markdown::replace_attachment_links;
+use crate::markdown::rewrite_attachment_links;
+use crate::i18n;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Slug the compiled 404 page is stored under
+pub const NOT_FOUND_SLUG: &str = "404";
+
+/// How many recent items the 404 page suggests
+const NOT_FOUND_RECENT_ITEMS: usize = 5;
+
+/// A compile within this long of the previous one only rescans what changed
+const INCREMENTAL_WINDOW_SECS: i64 = 60 * 60;
+
+/// Stage of a full compile, as reported to progress observers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompilePhase {
+    Scanning,
+    Rendering,
+    Saving,
+}
+
+/// `done` of `total` units finished in the current phase
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompileProgress {
+    pub phase: CompilePhase,
+    pub done: usize,
+    pub total: usize,
+}
 
 pub struct Compiler {
     config: Config,
     scanner: Scanner,
     renderer: TemplateRenderer,
     storage: StorageDB,
+    series: SeriesIndex, // Published series parts, set before rendering items
+    progress: Option<Box<dyn Fn(CompileProgress) + Send + Sync>>,
+    force_full: bool, // Never take the incremental path in `compile`
 }
 
 impl Compiler {
-    pub fn new(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
-        let scanner = Scanner::new(config.paths.content_dir.clone());
+    pub fn new(config: Config) -> Result<Self, BlogError> {
+        let scanner = Scanner::new(config.paths.content_dir.clone())
+            .with_max_attachment_size(config.server.max_attachment_size_mb * 1024 * 1024)
+            .with_markdown(config.markdown.clone())
+            .with_publish_dates(config.site.future_posts, config.site_timezone());
         let renderer = TemplateRenderer::new(&config)?;
 
         // Initialize storage database
-        let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
-        let storage = StorageDB::new(&storage_path)?;
+        let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+        let storage = StorageDB::new(&storage_path)?.with_max_versions(config.admin.max_versions);
 
         Ok(Self {
             config,
             scanner,
             renderer,
             storage,
+            series: SeriesIndex::default(),
+            progress: None,
+            force_full: false,
+        })
+    }
+
+    /// Call `observer` as a full compile moves through its phases
+    pub fn with_progress(mut self, observer: impl Fn(CompileProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Box::new(observer));
+        self
+    }
+
+    /// Always rebuild every page, even right after a previous compile
+    pub fn with_force_full(mut self, force_full: bool) -> Self {
+        self.force_full = force_full;
+        self
+    }
+
+    /// Compare the content directory with the compiled pages and attachments.
+    /// Drafts are skipped as in a compile, so the stored page of an item
+    /// since marked as a draft is reported as a ghost page
+    pub fn check_integrity(config: &Config) -> Result<IntegrityReport, BlogError> {
+        let scanner = Scanner::new(config.paths.content_dir.clone())
+            .with_markdown(config.markdown.clone())
+            .with_publish_dates(config.site.future_posts, config.site_timezone());
+        let site_content = scanner.scan_listing()?;
+        let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+        let storage = StorageDB::new(&storage_path)?;
+
+        let on_disk: HashSet<&str> = site_content
+            .categories
+            .iter()
+            .flat_map(|category| {
+                std::iter::once(category.url.as_str()).chain(category.items.iter().map(|item| item.url.as_str()))
+            })
+            .collect();
+        let mut stored = HashSet::new();
+        let mut ghost_pages = Vec::new();
+        for page_type in [PageType::Category, PageType::Item] {
+            for page in storage.get_pages_by_type(page_type)? {
+                // Admin drafts are stored next to the item they belong to
+                if !page.slug.ends_with("_draft") && !on_disk.contains(page.slug.as_str()) {
+                    ghost_pages.push(page.slug.clone());
+                }
+                stored.insert(page.slug);
+            }
+        }
+        ghost_pages.sort();
+
+        let uncompiled_items = site_content
+            .categories
+            .iter()
+            .flat_map(|category| &category.items)
+            .filter(|item| !stored.contains(&item.url))
+            .filter_map(|item| item.file_path.parent().map(PathBuf::from))
+            .collect();
+
+        Ok(IntegrityReport {
+            orphaned_attachments: storage.get_orphaned_attachments()?,
+            ghost_pages,
+            uncompiled_items,
         })
     }
 
+    fn report(&self, phase: CompilePhase, done: usize, total: usize) {
+        if let Some(observer) = &self.progress {
+            observer(CompileProgress { phase, done, total });
+        }
+    }
+
+    /// Save pages along with gzip and brotli copies of each body in the default
+    /// language, so `serve_page` does not compress the same HTML on every request
+    fn save_pages(&mut self, pages: &[Page]) -> Result<(), BlogError> {
+        self.storage.save_pages_batch(pages)?;
+
+        let lang = i18n::supported_language(&self.config.site.default_language).unwrap_or(i18n::FALLBACK_LANGUAGE);
+        let mut bodies = Vec::with_capacity(pages.len());
+        for page in pages {
+            let html = i18n::localize(&page.content, lang, lang);
+            bodies.push((page.slug.clone(), gzip_html(&html)?, brotli_html(&html)?));
+        }
+        self.storage.save_compressed_pages(lang, &bodies)?;
+        Ok(())
+    }
+
+    /// Compile the site into the database. Within an hour of the last compile,
+    /// and with the same templates, only content changed since then is rebuilt
+    pub fn compile(&mut self) -> Result<CompileResult, BlogError> {
+        match self.incremental_since() {
+            Some(since) => self.compile_incremental(since),
+            None => self.compile_full(),
+        }
+    }
+
+    /// Time of the last compile when it is recent enough to build on
+    fn incremental_since(&self) -> Option<DateTime<Utc>> {
+        if self.force_full {
+            return None;
+        }
+        // Changed templates affect every page
+        let fingerprint = self.storage.get_template_fingerprint().ok().flatten();
+        if fingerprint.as_deref() != Some(self.renderer.fingerprint()) {
+            return None;
+        }
+        let last_compiled = self.storage.get_last_compiled().ok().flatten()?;
+        let since = DateTime::parse_from_rfc3339(&last_compiled).ok()?.with_timezone(&Utc);
+        let age = Utc::now().signed_duration_since(since).num_seconds();
+        (0..INCREMENTAL_WINDOW_SECS).contains(&age).then_some(since)
+    }
+
+    /// Rebuild the categories and items changed since `since`, and drop the
+    /// pages of items that are gone from a changed category
+    fn compile_incremental(&mut self, since: DateTime<Utc>) -> Result<CompileResult, BlogError> {
+        log::info!("Starting incremental compilation of changes since {}...", since.to_rfc3339());
+
+        self.report(CompilePhase::Scanning, 0, 0);
+        let changed = self.scanner.scan_incremental(since)?;
+        let total_items: usize = changed.categories.iter().map(|c| c.items.len()).sum();
+        log::info!("Found {} changed categories, {} changed items", changed.categories.len(), total_items);
+
+        let mut done = 0;
+        let mut total_attachments = 0;
+        let mut changed_slugs = Vec::new();
+        self.report(CompilePhase::Rendering, 0, total_items);
+        for category in &changed.categories {
+            let listed: Vec<String> = self
+                .scanner
+                .scan_category_listing(&category.name)?
+                .map(|listing| listing.items.into_iter().map(|item| item.url).collect())
+                .unwrap_or_default();
+            let removed: Vec<String> = self
+                .storage
+                .get_items_by_category(&category.name)?
+                .into_iter()
+                .map(|page| page.slug)
+                .filter(|slug| !slug.ends_with("_draft") && !listed.contains(slug))
+                .collect();
+            for slug in &removed {
+                self.compile_removed_item(&category.name, slug)?;
+            }
+
+            // Item compiles refresh the category page; otherwise only index.md changed
+            if category.items.is_empty() && removed.is_empty() {
+                self.compile_category(&category.name)?;
+            }
+            changed_slugs.extend(removed);
+            for item in &category.items {
+                let compiled = self.compile_item(&category.name, &item.dir_name)?;
+                total_attachments += compiled.total_attachments;
+                changed_slugs.push(compiled.slug);
+                done += 1;
+                self.report(CompilePhase::Rendering, done, total_items);
+            }
+        }
+        self.storage.update_compile_time()?;
+
+        log::info!("Incremental compilation complete. {} items, {} attachments.", total_items, total_attachments);
+
+        Ok(CompileResult {
+            success: true,
+            generated_files: Vec::new(),
+            total_categories: changed.categories.len(),
+            total_items,
+            total_attachments,
+            broken_links: Vec::new(),
+            changed_slugs: Some(changed_slugs),
+        })
+    }
+
+    /// Rebuild what the files at `changed` (relative to the content directory)
+    /// belong to, as after pulling new commits: a file under
+    /// `{category}/{item}/` rebuilds or removes that item, any other file in a
+    /// category the category page. Files outside categories are not content;
+    /// a removed category falls back to a full compile
+    pub fn compile_paths(&mut self, changed: &[PathBuf]) -> Result<CompileResult, BlogError> {
+        let content_dir = self.config.paths.content_dir.clone();
+        let mut items: Vec<(String, String)> = Vec::new();
+        let mut categories: Vec<String> = Vec::new();
+        for path in changed {
+            let parts: Vec<String> = path.iter().map(|part| part.to_string_lossy().into_owned()).collect();
+            let (category, rest) = match parts.split_first() {
+                Some((category, rest)) if !rest.is_empty() && !category.starts_with('.') => (category, rest),
+                _ => continue,
+            };
+            if !content_dir.join(category).is_dir() {
+                log::info!("Category {} was removed, compiling everything", category);
+                return self.compile_full();
+            }
+            let target = (category.clone(), rest[0].clone());
+            if rest.len() > 1 && !items.contains(&target) {
+                items.push(target);
+            } else if rest.len() == 1 && !categories.contains(category) {
+                categories.push(category.clone());
+            }
+        }
+
+        let mut changed_slugs = Vec::new();
+        let mut refreshed: Vec<String> = Vec::new();
+        let mut total_attachments = 0;
+        self.report(CompilePhase::Rendering, 0, items.len());
+        for (done, (category, dir_name)) in items.iter().enumerate() {
+            if content_dir.join(category).join(dir_name).is_dir() {
+                match self.compile_item(category, dir_name) {
+                    Ok(compiled) => {
+                        total_attachments += compiled.total_attachments;
+                        changed_slugs.push(compiled.slug);
+                        refreshed.push(category.clone());
+                    }
+                    // A directory without markdown, such as shared attachments
+                    Err(BlogError::NotFound(_)) if !categories.contains(category) => categories.push(category.clone()),
+                    Err(BlogError::NotFound(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            } else {
+                let removed: Vec<String> = self
+                    .storage
+                    .get_items_by_category(category)?
+                    .into_iter()
+                    .filter(|page| page.item_name.as_deref() == Some(dir_name.as_str()))
+                    .map(|page| page.slug)
+                    .collect();
+                for slug in removed {
+                    self.compile_removed_item(category, &slug)?;
+                    changed_slugs.push(slug);
+                    refreshed.push(category.clone());
+                }
+            }
+            self.report(CompilePhase::Rendering, done + 1, items.len());
+        }
+        // Item compiles already refresh their category page
+        for category in categories.iter().filter(|category| !refreshed.contains(category)) {
+            self.compile_category(category)?;
+        }
+        self.storage.update_compile_time()?;
+
+        log::info!("Compiled {} changed files: {} items, {} categories", changed.len(), items.len(), categories.len());
+
+        Ok(CompileResult {
+            success: true,
+            generated_files: Vec::new(),
+            total_categories: categories.len(),
+            total_items: items.len(),
+            total_attachments,
+            broken_links: Vec::new(),
+            changed_slugs: Some(changed_slugs),
+        })
+    }
+
+    /// Re-render a category page and the index, for a changed `index.md`
+    fn compile_category(&mut self, category: &str) -> Result<(), BlogError> {
+        let now = chrono::Utc::now();
+        let updated_at = now.to_rfc3339();
+        let published = self.scanner.scan_listing()?.published(now);
+
+        let stored_items = self.storage.get_pages_by_type(PageType::Item)?;
+        self.renderer.set_tag_filter(TagFilter::from_pages(&stored_items));
+        self.series = SeriesIndex::from_pages(&stored_items);
+
+        let mut pages_to_save = vec![self.index_page(&published, &updated_at)?];
+        pages_to_save.extend(self.not_found_page(&published, &updated_at));
+        if let Some(listed) = published.categories.iter().find(|c| c.name == category) {
+            pages_to_save.push(self.category_page(listed, &updated_at)?);
+        }
+        self.save_pages(&pages_to_save)?;
+        log::info!("Compiled category: {}", category);
+        Ok(())
+    }
+
     /// Compile the entire site - stores everything in database
-    pub fn compile(&mut self) -> Result<CompileResult, Box<dyn std::error::Error>> {
+    fn compile_full(&mut self) -> Result<CompileResult, BlogError> {
         log::info!("Starting compilation...");
 
         // Scan content
+        self.report(CompilePhase::Scanning, 0, 0);
         let site_content = self.scanner.scan()?;
-        log::info!("Found {} categories", site_content.categories.len());
+        log::info!(
+            "Found {} categories ({} attachment files read)",
+            site_content.categories.len(),
+            self.scanner.files_read()
+        );
+        let broken_links = self.scanner.check_broken_links(&site_content);
+        for link in &broken_links {
+            log::warn!("Broken link {} in {}:{}", link.url, link.source_file.display(), link.line);
+        }
+        // Index and 404 pages, then every category with its items
+        let total_pages = 2 + site_content
+            .categories
+            .iter()
+            .map(|c| 1 + c.items.len())
+            .sum::<usize>();
 
         // Collect pages and attachments for storage
         let mut pages_to_save: Vec<Page> = Vec::new();
-        let mut attachments_to_save: Vec<StoredAttachment> = Vec::new();
+        let mut attachments_to_save: Vec<(StoredAttachment, PathBuf)> = Vec::new();
+        let mut attachment_refs: Vec<AttachmentRef> = Vec::new();
         let mut total_attachments_count = 0;
 
-        // Render index page
-        let index_html = self.renderer.render_index(&site_content)?;
+        // Scheduled items get their own page but stay out of listings until due
+        let published = site_content.published(chrono::Utc::now());
+        self.renderer.set_tag_filter(TagFilter::from_site_content(&published));
+        self.series = SeriesIndex::from_site_content(&published);
 
-        // Save index page to storage
+        // Render index page
         let now = chrono::Utc::now().to_rfc3339();
-        pages_to_save.push(Page {
-            id: "index".to_string(),
-            slug: "index".to_string(),
-            page_type: PageType::Index,
-            title: self.config.site.title.clone(),
-            content: index_html,
-            category: None,
-            updated_at: now.clone(),
-        });
+        self.report(CompilePhase::Rendering, 0, total_pages);
+        pages_to_save.push(self.index_page(&published, &now)?);
         log::info!("Compiled index page");
+        pages_to_save.extend(self.not_found_page(&published, &now));
 
-        // Render category pages
-        for category in &site_content.categories {
-            let category_html = self.renderer.render_category(category)?;
-
-            // Save category page to storage
-            pages_to_save.push(Page {
-                id: format!("category-{}", category.url),
-                slug: category.url.clone(),
-                page_type: PageType::Category,
-                title: category.name.clone(),
-                content: category_html,
-                category: None,
-                updated_at: now.clone(),
-            });
-            log::info!("Compiled category: {}", category.name);
-
-            // Render item pages and collect attachments
-            for item in &category.items {
-                // Build attachment map for this item
-                let attachment_map: Vec<(String, String)> = item.attachments.iter()
-                    .map(|a| (a.original_name.clone(), a.new_name.clone()))
-                    .collect();
-
-                // Render item HTML and replace attachment links
-                let raw_item_html = self.renderer.render_item(item)?;
-                let item_html = replace_attachment_links(&raw_item_html, &attachment_map);
-
-                // Save item page to storage
-                pages_to_save.push(Page {
-                    id: format!("item-{}", item.url),
-                    slug: item.url.clone(),
-                    page_type: PageType::Item,
-                    title: item.title.clone(),
-                    content: item_html,
-                    category: Some(item.category.clone()),
-                    updated_at: now.clone(),
-                });
-
-                // Collect attachments for this item
-                for attachment in &item.attachments {
-                    let stored_attachment = StoredAttachment {
-                        id: format!("attachment-{}", attachment.new_name),
-                        slug: item.url.clone(),
-                        filename: attachment.new_name.clone(),
-                        original_name: attachment.original_name.clone(),
-                        mime_type: attachment.mime_type.clone(),
-                        file_data: attachment.file_data.clone(),
-                        file_size: attachment.file_size,
-                        updated_at: now.clone(),
-                    };
-                    attachments_to_save.push(stored_attachment);
-                    total_attachments_count += 1;
+        // Item pages don't depend on each other, so they render in parallel;
+        // the results come back in listing order
+        let jobs: Vec<(&ContentItem, &Category)> = site_content
+            .categories
+            .iter()
+            .zip(&published.categories)
+            .flat_map(|(category, listed)| category.items.iter().map(move |item| (item, listed)))
+            .collect();
+        let rendered_first = pages_to_save.len();
+        let (renderer, series, site_url) = (&self.renderer, &self.series, self.config.site.url.as_str());
+        let progress = self.progress.as_deref();
+        let rendered = AtomicUsize::new(0);
+        let item_pages: Vec<Result<Page, BlogError>> = jobs
+            .par_iter()
+            .map(|(item, listed)| {
+                let page = render_item_page(renderer, series, site_url, item, listed, &now);
+                let done = rendered.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(observer) = progress {
+                    observer(CompileProgress { phase: CompilePhase::Rendering, done: rendered_first + done, total: total_pages });
                 }
-                log::info!("Compiled item: {} ({} attachments)", item.title, item.attachments.len());
+                page
+            })
+            .collect();
+        let mut item_pages = item_pages.into_iter();
+
+        // Category pages, then each category's items with their attachments
+        for (category, listed) in site_content.categories.iter().zip(&published.categories) {
+            pages_to_save.push(self.category_page(listed, &now)?);
+            let mut category_attachments = 0;
+            for item in &category.items {
+                pages_to_save.push(item_pages.next().expect("one rendered page per item")?);
+                category_attachments +=
+                    collect_attachments(item, &now, &mut attachment_refs, &mut attachments_to_save);
             }
+            total_attachments_count += category_attachments;
+            log::info!(
+                "Compiled category: {} ({} items, {} attachments)",
+                category.name,
+                category.items.len(),
+                category_attachments
+            );
         }
+        self.report(CompilePhase::Rendering, total_pages, total_pages);
 
-        // Save all pages to storage database
-        self.storage.save_pages_batch(&pages_to_save)?;
-        log::info!("Saved {} pages to storage database", pages_to_save.len());
-
-        // Save all attachments to storage database
-        if !attachments_to_save.is_empty() {
-            self.storage.save_attachments_batch(&attachments_to_save)?;
-            log::info!("Saved {} attachments to storage database", attachments_to_save.len());
+        // Write everything in one transaction so an interrupted compile
+        // leaves the previously compiled site in place
+        self.storage.begin_transaction()?;
+        if let Err(e) = self.save_compiled(&pages_to_save, &attachments_to_save, &attachment_refs) {
+            self.storage.rollback_transaction();
+            return Err(e);
         }
+        self.storage.commit_transaction()?;
 
-        // Update compile time
-        self.storage.update_compile_time()?;
+        let duplicate_groups = self.scanner.duplicate_groups();
+        if !duplicate_groups.is_empty() {
+            log::info!(
+                "Skipped {} duplicated attachment files",
+                duplicate_groups.iter().map(|g| g.copies.len()).sum::<usize>()
+            );
+        }
 
         log::info!("Compilation complete. {} pages, {} attachments.", pages_to_save.len(), total_attachments_count);
 
@@ -137,10 +449,441 @@ impl Compiler {
             total_categories: site_content.categories.len(),
             total_items: site_content.categories.iter().map(|c| c.items.len()).sum(),
             total_attachments: total_attachments_count,
+            broken_links,
+            changed_slugs: None,
         })
     }
 
-    fn generate_sitemap(&self, site_content: &SiteContent, generated_files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    /// Save the pages, attachments and compile metadata of a full compile
+    fn save_compiled(
+        &mut self,
+        pages: &[Page],
+        attachments: &[(StoredAttachment, PathBuf)],
+        attachment_refs: &[AttachmentRef],
+    ) -> Result<(), BlogError> {
+        let total_saved = pages.len() + attachments.len();
+        self.report(CompilePhase::Saving, 0, total_saved);
+        self.save_pages(pages)?;
+        log::info!("Saved {} pages to storage database", pages.len());
+        self.report(CompilePhase::Saving, pages.len(), total_saved);
+
+        self.save_attachments(attachments)?;
+        self.storage.replace_attachment_refs(attachment_refs)?;
+        self.report(CompilePhase::Saving, total_saved, total_saved);
+
+        // Update compile time and the templates every page now reflects
+        self.storage.update_compile_time()?;
+        self.storage.update_template_fingerprint(self.renderer.fingerprint())?;
+        Ok(())
+    }
+
+    /// Recompile a single item along with the category and index pages that
+    /// list it, without a full rebuild
+    pub fn compile_item(
+        &mut self,
+        category: &str,
+        item_name: &str,
+    ) -> Result<CompileItemResult, BlogError> {
+        let started = std::time::Instant::now();
+
+        let item = self
+            .scanner
+            .scan_item_dir(category, item_name)?
+            .ok_or_else(|| BlogError::NotFound(format!("Item not found: {}/{}", category, item_name)))?;
+
+        let now = chrono::Utc::now();
+        let updated_at = now.to_rfc3339();
+        let site_listing = self.scanner.scan_listing()?.published(now);
+        let listed = site_listing
+            .categories
+            .iter()
+            .find(|c| c.name == category)
+            .cloned()
+            .ok_or_else(|| BlogError::NotFound(format!("Category not found: {}", category)))?;
+
+        // Other items' tags and series come from the stored pages
+        let stored_items = self.storage.get_pages_by_type(PageType::Item)?;
+        let previous_series = stored_items
+            .iter()
+            .find(|page| page.slug == item.url)
+            .and_then(|page| page.series.clone());
+        let mut tag_filter = TagFilter::from_pages(&stored_items);
+        let mut series = SeriesIndex::from_pages(&stored_items).without(&item.url);
+        if !item.is_scheduled(now) {
+            tag_filter = tag_filter.with_item(TaggedItem::from(&item));
+            series = series.with_item(&item);
+        }
+        self.renderer.set_tag_filter(tag_filter);
+        self.series = series;
+        let item_page = self.item_page(&item, &listed, &updated_at)?;
+
+        let mut attachment_refs = Vec::new();
+        let mut attachments_to_save = Vec::new();
+        collect_attachments(&item, &updated_at, &mut attachment_refs, &mut attachments_to_save);
+        let total_attachments = attachments_to_save.len();
+
+        let page_size = item_page.content.len();
+        let mut pages_to_save = vec![
+            item_page,
+            self.category_page(&listed, &updated_at)?,
+            self.index_page(&site_listing, &updated_at)?,
+        ];
+        pages_to_save.extend(self.not_found_page(&site_listing, &updated_at));
+        let mut refs_by_item = vec![(item.url.clone(), attachment_refs)];
+
+        // Neighbours link to this item by title, so a new or renamed item changes their pages too
+        let (prev, next) = listed.neighbours(&item.url);
+        for link in [prev, next].into_iter().flatten() {
+            let Some(listed_item) = listed.items.iter().find(|i| i.url == link.url) else {
+                continue;
+            };
+            let Some(neighbour) = self.scanner.scan_item_dir(category, &listed_item.dir_name)? else {
+                continue;
+            };
+            pages_to_save.push(self.item_page(&neighbour, &listed, &updated_at)?);
+            let mut neighbour_refs = Vec::new();
+            collect_attachments(&neighbour, &updated_at, &mut neighbour_refs, &mut attachments_to_save);
+            refs_by_item.push((neighbour.url, neighbour_refs));
+        }
+
+        // Other parts of the series it joined or left list it in their series box
+        let mut siblings: Vec<(String, String)> = Vec::new();
+        for name in [previous_series.as_deref(), item.series.as_deref()].into_iter().flatten() {
+            for (url, sibling_category) in self.series.parts(name) {
+                let Some(sibling_category) = sibling_category else {
+                    continue;
+                };
+                let rendered = refs_by_item.iter().any(|(slug, _)| *slug == url);
+                if !rendered && !siblings.iter().any(|(u, _)| *u == url) {
+                    siblings.push((url, sibling_category));
+                }
+            }
+        }
+        for (url, sibling_category) in siblings {
+            let Some(sibling_listing) = self.scanner.scan_category_listing(&sibling_category)? else {
+                continue;
+            };
+            let Some(dir_name) = sibling_listing.items.iter().find(|i| i.url == url).map(|i| i.dir_name.clone()) else {
+                continue;
+            };
+            let Some(sibling) = self.scanner.scan_item_dir(&sibling_category, &dir_name)? else {
+                continue;
+            };
+            let sibling_listed = SiteContent { categories: vec![sibling_listing] }
+                .published(now)
+                .categories
+                .remove(0);
+            pages_to_save.push(self.item_page(&sibling, &sibling_listed, &updated_at)?);
+            let mut sibling_refs = Vec::new();
+            collect_attachments(&sibling, &updated_at, &mut sibling_refs, &mut attachments_to_save);
+            refs_by_item.push((sibling.url, sibling_refs));
+        }
+
+        self.save_pages(&pages_to_save)?;
+        self.save_attachments(&attachments_to_save)?;
+        for (slug, refs) in &refs_by_item {
+            self.storage.replace_item_attachment_refs(slug, refs)?;
+        }
+        self.storage.update_compile_time()?;
+
+        let duration = started.elapsed();
+        log::info!(
+            "Compiled item {} in {:?} ({} pages, {} bytes, {} attachments)",
+            item.url,
+            duration,
+            pages_to_save.len(),
+            page_size,
+            total_attachments
+        );
+
+        Ok(CompileItemResult {
+            slug: item.url,
+            duration_ms: duration.as_millis(),
+            page_size,
+            total_attachments,
+            total_pages: pages_to_save.len(),
+        })
+    }
+
+    /// Refresh the pages affected by deleting the item at `slug` from
+    /// `category`: its category and the index, plus every item page that
+    /// still links to it (former neighbours and series parts)
+    pub fn compile_removed_item(
+        &mut self,
+        category: &str,
+        slug: &str,
+    ) -> Result<CompileItemResult, BlogError> {
+        let started = std::time::Instant::now();
+        self.storage.delete_page(slug)?;
+
+        let now = chrono::Utc::now();
+        let updated_at = now.to_rfc3339();
+        let site_content = self.scanner.scan_listing()?;
+        let published = site_content.published(now);
+
+        let stored_items = self.storage.get_pages_by_type(PageType::Item)?;
+        self.renderer.set_tag_filter(TagFilter::from_pages(&stored_items));
+        self.series = SeriesIndex::from_pages(&stored_items);
+
+        let mut pages_to_save = vec![self.index_page(&published, &updated_at)?];
+        pages_to_save.extend(self.not_found_page(&published, &updated_at));
+        if let Some(listed) = published.categories.iter().find(|c| c.name == category) {
+            pages_to_save.push(self.category_page(listed, &updated_at)?);
+        }
+
+        // Item pages link to each other as `/{slug}`
+        let link = format!("href=\"/{}\"", slug);
+        let linking: Vec<&str> = stored_items
+            .iter()
+            .filter(|page| page.content.contains(&link))
+            .map(|page| page.slug.as_str())
+            .collect();
+        let mut attachments_to_save = Vec::new();
+        let mut refs_by_item = Vec::new();
+        for (listing, listed) in site_content.categories.iter().zip(&published.categories) {
+            for listed_item in listing.items.iter().filter(|i| linking.contains(&i.url.as_str())) {
+                let Some(item) = self.scanner.scan_item_dir(&listing.name, &listed_item.dir_name)? else {
+                    continue;
+                };
+                pages_to_save.push(self.item_page(&item, listed, &updated_at)?);
+                let mut refs = Vec::new();
+                collect_attachments(&item, &updated_at, &mut refs, &mut attachments_to_save);
+                refs_by_item.push((item.url, refs));
+            }
+        }
+
+        self.save_pages(&pages_to_save)?;
+        self.save_attachments(&attachments_to_save)?;
+        for (item_slug, refs) in &refs_by_item {
+            self.storage.replace_item_attachment_refs(item_slug, refs)?;
+        }
+        self.storage.update_compile_time()?;
+
+        let duration = started.elapsed();
+        log::info!("Removed item {} in {:?} ({} pages re-rendered)", slug, duration, pages_to_save.len());
+
+        Ok(CompileItemResult {
+            slug: slug.to_string(),
+            duration_ms: duration.as_millis(),
+            page_size: 0,
+            total_attachments: attachments_to_save.len(),
+            total_pages: pages_to_save.len(),
+        })
+    }
+
+    /// Re-render items whose `publish_at` falls in `(since, now]`, along with
+    /// the index and category pages that now list them
+    pub fn compile_scheduled(
+        &mut self,
+        since: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<ScheduledResult, BlogError> {
+        let site_content = self.scanner.scan()?;
+        let published = site_content.published(now);
+        self.renderer.set_tag_filter(TagFilter::from_site_content(&published));
+        self.series = SeriesIndex::from_site_content(&published);
+        let updated_at = now.to_rfc3339();
+        let mut pages_to_save = Vec::new();
+        let mut released = 0;
+
+        for (category, listed) in site_content.categories.iter().zip(&published.categories) {
+            let due: Vec<_> = category
+                .items
+                .iter()
+                .filter(|item| item.publish_at.is_some_and(|at| at > since && at <= now))
+                .collect();
+            if due.is_empty() {
+                continue;
+            }
+
+            for item in &due {
+                log::info!("Published scheduled item: {}", item.title);
+            }
+
+            // Released items now sit between two others, whose navigation changes
+            let mut to_render: Vec<&str> = Vec::new();
+            for item in &due {
+                to_render.push(&item.url);
+                let (prev, next) = listed.neighbours(&item.url);
+                for link in [prev, next].into_iter().flatten() {
+                    if let Some(neighbour) = category.items.iter().find(|i| i.url == link.url) {
+                        to_render.push(&neighbour.url);
+                    }
+                }
+            }
+            for item in category.items.iter().filter(|i| to_render.contains(&i.url.as_str())) {
+                pages_to_save.push(self.item_page(item, listed, &updated_at)?);
+            }
+            pages_to_save.push(self.category_page(listed, &updated_at)?);
+            released += due.len();
+        }
+
+        // Released items also join the series boxes of their other parts
+        let released_series: Vec<&str> = site_content
+            .categories
+            .iter()
+            .flat_map(|category| &category.items)
+            .filter(|item| item.publish_at.is_some_and(|at| at > since && at <= now))
+            .filter_map(|item| item.series.as_deref())
+            .collect();
+        if !released_series.is_empty() {
+            for (category, listed) in site_content.categories.iter().zip(&published.categories) {
+                for item in &category.items {
+                    let in_series = item.series.as_deref().is_some_and(|name| released_series.contains(&name));
+                    if in_series && !pages_to_save.iter().any(|page| page.slug == item.url) {
+                        pages_to_save.push(self.item_page(item, listed, &updated_at)?);
+                    }
+                }
+            }
+        }
+
+        if released > 0 {
+            pages_to_save.push(self.index_page(&published, &updated_at)?);
+            self.save_pages(&pages_to_save)?;
+            self.storage.update_compile_time()?;
+        }
+
+        let next_publish_at = site_content
+            .categories
+            .iter()
+            .flat_map(|category| &category.items)
+            .filter_map(|item| item.publish_at)
+            .filter(|at| *at > now)
+            .min();
+
+        Ok(ScheduledResult {
+            released,
+            next_publish_at,
+        })
+    }
+
+    /// Store attachment contents according to the configured backend
+    fn save_attachments(
+        &mut self,
+        attachments: &[(StoredAttachment, PathBuf)],
+    ) -> Result<(), BlogError> {
+        if attachments.is_empty() {
+            return Ok(());
+        }
+
+        match self.config.storage.attachment_backend {
+            AttachmentBackend::Db => {
+                self.storage
+                    .save_attachments_from_files(attachments)
+                    .map_err(|e| BlogError::Internal(e.to_string()))?;
+                log::info!("Saved {} attachments to storage database", attachments.len());
+            }
+            AttachmentBackend::Filesystem => {
+                let records = self.copy_attachments_to_disk(attachments)?;
+                self.storage.save_attachment_records(&records)?;
+                log::info!(
+                    "Copied {} attachments to {}",
+                    records.len(),
+                    self.config.attachments_dir().display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy attachments into `generated_dir/attachments/{slug}/{new_name}`,
+    /// returning each record paired with its on-disk location
+    fn copy_attachments_to_disk(
+        &self,
+        attachments: &[(StoredAttachment, PathBuf)],
+    ) -> Result<Vec<(StoredAttachment, PathBuf)>, BlogError> {
+        let mut records = Vec::with_capacity(attachments.len());
+        for (attachment, source_path) in attachments {
+            let dir = self.config.attachments_dir().join(&attachment.slug);
+            std::fs::create_dir_all(&dir)?;
+            let dest = dir.join(&attachment.filename);
+            std::fs::copy(source_path, &dest)?;
+            records.push((attachment.clone(), dest));
+        }
+        Ok(records)
+    }
+
+    fn index_page(&self, site_content: &SiteContent, now: &str) -> Result<Page, BlogError> {
+        Ok(Page {
+            id: "index".to_string(),
+            slug: "index".to_string(),
+            page_type: PageType::Index,
+            title: self.config.site.title.clone(),
+            content: self.renderer.render_index(site_content)?,
+            category: None,
+            updated_at: now.to_string(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        })
+    }
+
+    /// The 404 page suggesting the newest published items. Custom template
+    /// sets without a `404.html` simply go without one.
+    fn not_found_page(&self, published: &SiteContent, now: &str) -> Option<Page> {
+        let mut items: Vec<&ContentItem> = published.categories.iter().flat_map(|c| &c.items).collect();
+        items.sort_by(|a, b| b.date.cmp(&a.date));
+        let recent: Vec<ItemLink> = items
+            .into_iter()
+            .take(NOT_FOUND_RECENT_ITEMS)
+            .map(ItemLink::from)
+            .collect();
+
+        let content = match self.renderer.render_not_found(&recent) {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("Skipping 404 page: {}", e);
+                return None;
+            }
+        };
+        Some(Page {
+            id: NOT_FOUND_SLUG.to_string(),
+            slug: NOT_FOUND_SLUG.to_string(),
+            page_type: PageType::Index,
+            title: self.config.site.title.clone(),
+            content,
+            category: None,
+            updated_at: now.to_string(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        })
+    }
+
+    fn category_page(&self, category: &Category, now: &str) -> Result<Page, BlogError> {
+        Ok(Page {
+            id: format!("category-{}", category.url),
+            slug: category.url.clone(),
+            page_type: PageType::Category,
+            title: category.title.clone(),
+            content: self.renderer.render_category(category)?,
+            category: None,
+            updated_at: now.to_string(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        })
+    }
+
+    /// Render an item page; `listed` is the published listing of its category,
+    /// used for previous/next links, and series links come from `self.series`
+    fn item_page(&self, item: &ContentItem, listed: &Category, now: &str) -> Result<Page, BlogError> {
+        render_item_page(&self.renderer, &self.series, &self.config.site.url, item, listed, now)
+    }
+
+    fn generate_sitemap(&self, site_content: &SiteContent, generated_files: &mut Vec<PathBuf>) -> Result<(), BlogError> {
         let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
         sitemap.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
 
@@ -176,14 +919,8 @@ impl Compiler {
         Ok(())
     }
 
-    fn generate_robots_txt(&self, generated_files: &mut Vec<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
-        let robots_txt = format!(
-            r#"User-agent: *
-Allow: /
-Sitemap: {}/sitemap.xml
-"#,
-            self.config.site.url
-        );
+    fn generate_robots_txt(&self, generated_files: &mut Vec<PathBuf>) -> Result<(), BlogError> {
+        let robots_txt = crate::seo::render_robots_txt(&self.config.seo, &self.config.site.url);
 
         let robots_path = self.config.paths.generated_dir.join("robots.txt");
         std::fs::write(&robots_path, robots_txt)?;
@@ -194,6 +931,167 @@ Sitemap: {}/sitemap.xml
     }
 }
 
+/// Absolute URL of an item's frontmatter `cover`, which names one of its
+/// attachments (`cover.jpg` or `attachment/cover.jpg`), a site path or a full URL.
+/// A cover naming a missing attachment is ignored, so the default image is used.
+pub fn resolve_cover(item: &ContentItem, site_url: &str) -> Option<String> {
+    let cover = item.cover.as_deref().map(str::trim).filter(|cover| !cover.is_empty())?;
+    if cover.starts_with("http://") || cover.starts_with("https://") {
+        return Some(cover.to_string());
+    }
+
+    let site_url = site_url.trim_end_matches('/');
+    let name = cover.strip_prefix("./").unwrap_or(cover);
+    let name = name.strip_prefix("attachment/").unwrap_or(name);
+    if let Some(attachment) = item.attachments.iter().find(|a| a.original_name == name) {
+        return Some(format!("{}/attachment/{}", site_url, attachment.stored_name()));
+    }
+    if cover.starts_with('/') {
+        return Some(format!("{}{}", site_url, cover));
+    }
+
+    log::warn!("Item {} has a cover that is not an attachment: {}", item.file_path.display(), cover);
+    None
+}
+
+/// Render an item page. Needs only shared references, so item pages can be
+/// rendered on several threads at once
+fn render_item_page(
+    renderer: &TemplateRenderer,
+    series: &SeriesIndex,
+    site_url: &str,
+    item: &ContentItem,
+    listed: &Category,
+    now: &str,
+) -> Result<Page, BlogError> {
+    // Build attachment map for this item
+    let attachment_map: Vec<(String, String)> = item.attachments.iter()
+        .map(|a| (a.original_name.clone(), a.stored_name().to_string()))
+        .collect();
+
+    // Render item HTML and replace attachment links
+    let (prev, next) = listed.neighbours(&item.url);
+    let series_nav = series.nav_for(item);
+    let cover = resolve_cover(item, site_url);
+    let raw_item_html = renderer.render_item(
+        item,
+        cover.as_deref(),
+        prev.as_ref(),
+        next.as_ref(),
+        series_nav.as_ref(),
+    )?;
+    let (item_html, missing) = rewrite_attachment_links(&raw_item_html, &attachment_map);
+    if !missing.is_empty() {
+        log::warn!(
+            "Item {} links to missing attachments: {}",
+            item.file_path.display(),
+            missing.join(", ")
+        );
+    }
+
+    Ok(Page {
+        id: format!("item-{}", item.url),
+        slug: item.url.clone(),
+        page_type: PageType::Item,
+        title: item.title.clone(),
+        content: item_html,
+        category: Some(item.category.clone()),
+        updated_at: now.to_string(),
+        publish_at: item.publish_at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+        tags: item.tags.clone(),
+        raw_content: Some(item.raw_content.clone()),
+        series: item.series.clone(),
+        series_order: item.series_order,
+        item_name: Some(item.dir_name.clone()),
+        excerpt: item.excerpt.clone(),
+    })
+}
+
+/// Gzip `html` at the best level; it is compressed once and served many times
+fn gzip_html(html: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(html.as_bytes())?;
+    encoder.finish()
+}
+
+/// Brotli `html` at the best quality, for clients that accept `br`
+fn brotli_html(html: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    encoder.write_all(html.as_bytes())?;
+    encoder.flush()?;
+    Ok(encoder.into_inner())
+}
+
+/// Record an item's attachment references and queue its non-duplicate
+/// attachments for storage; returns how many were queued
+fn collect_attachments(
+    item: &ContentItem,
+    now: &str,
+    refs: &mut Vec<AttachmentRef>,
+    to_save: &mut Vec<(StoredAttachment, PathBuf)>,
+) -> usize {
+    let mut queued = 0;
+    for attachment in &item.attachments {
+        refs.push(AttachmentRef {
+            slug: item.url.clone(),
+            filename: attachment.stored_name().to_string(),
+            source_path: item.file_path.with_file_name("attachment")
+                .join(&attachment.original_name)
+                .to_string_lossy()
+                .to_string(),
+        });
+
+        // Identical copies reference the attachment stored first
+        if attachment.duplicate_of.is_some() {
+            continue;
+        }
+
+        let stored_attachment = StoredAttachment {
+            id: format!("attachment-{}", attachment.new_name),
+            slug: item.url.clone(),
+            filename: attachment.new_name.clone(),
+            original_name: attachment.original_name.clone(),
+            mime_type: attachment.mime_type.clone(),
+            file_data: Vec::new(),
+            file_size: attachment.file_size,
+            updated_at: now.to_string(),
+            content_hash: attachment.content_hash.clone(),
+        };
+        to_save.push((stored_attachment, attachment.source_path.clone()));
+        queued += 1;
+    }
+    queued
+}
+
+/// Differences between the content directory and the storage database
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub orphaned_attachments: Vec<String>, // Stored attachments whose page is gone
+    pub ghost_pages: Vec<String>,          // Stored category and item pages with nothing on disk
+    pub uncompiled_items: Vec<PathBuf>,    // Item directories never compiled
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphaned_attachments.is_empty() && self.ghost_pages.is_empty() && self.uncompiled_items.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledResult {
+    pub released: usize,
+    pub next_publish_at: Option<DateTime<Utc>>, // earliest item still waiting
+}
+
+#[derive(Debug, Clone)]
+pub struct CompileItemResult {
+    pub slug: String,
+    pub duration_ms: u128,
+    pub page_size: usize,
+    pub total_attachments: usize,
+    pub total_pages: usize, // item page plus the listings and neighbours re-rendered with it
+}
+
 #[derive(Debug, Clone)]
 pub struct CompileResult {
     pub success: bool,
@@ -201,4 +1099,6 @@ pub struct CompileResult {
     pub total_categories: usize,
     pub total_items: usize,
     pub total_attachments: usize,
+    pub broken_links: Vec<BrokenLink>, // Only checked by full compiles
+    pub changed_slugs: Option<Vec<String>>, // Items rebuilt or removed by an incremental compile; None for full compiles
 }
\ No newline at end of file
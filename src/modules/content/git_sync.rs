@@ -0,0 +1,342 @@
+//! Pull the content directory from the git repository configured in
+//! `[content.git]`, for `POST /api/admin/sync` and `lfb server sync`.
+//!
+//! The first sync clones into an empty (or missing) content directory. Later
+//! syncs fetch the branch and move the checkout to it, touching only the files
+//! that changed upstream. Files edited locally (e.g. in the admin editor) that
+//! the new commits also change are reported as conflicts and nothing is
+//! updated, so local edits are never silently overwritten.
+use crate::compiler::{CompileResult, Compiler};
+use crate::config::{Config, GitContentConfig};
+use crate::error::BlogError;
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{Cred, CredentialType, FetchOptions, Oid, RemoteCallbacks, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Outcome of a sync
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SyncReport {
+    pub previous_commit: Option<String>, // None when the repository was just cloned
+    pub commit: String,                  // Fetched commit; checked out unless there are conflicts
+    pub changed_files: Vec<PathBuf>,     // Relative to the content directory
+    pub conflicts: Vec<PathBuf>,         // Locally modified files the update would overwrite
+}
+
+impl SyncReport {
+    /// Whether the content directory now matches the fetched commit
+    pub fn is_applied(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Bring `content_dir` up to date with `git.branch` of `git.url`
+pub fn sync(content_dir: &Path, git: &GitContentConfig) -> Result<SyncReport, BlogError> {
+    if git.url.trim().is_empty() {
+        return Err(BlogError::Validation("content.git.url is not set".to_string()));
+    }
+
+    if !content_dir.join(".git").exists() {
+        let is_empty = match std::fs::read_dir(content_dir) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+            Err(e) => return Err(e.into()),
+        };
+        if !is_empty {
+            return Err(BlogError::Validation(format!(
+                "{} is not a git repository and is not empty; move its files away before the first sync",
+                content_dir.display()
+            )));
+        }
+        return clone(content_dir, git);
+    }
+
+    let repo = Repository::open(content_dir).map_err(git_error)?;
+    let target = fetch(&repo, git)?;
+    let head = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let previous_commit = head.as_ref().map(|commit| commit.id().to_string());
+    if head.as_ref().is_some_and(|commit| commit.id() == target) {
+        return Ok(SyncReport {
+            previous_commit,
+            commit: target.to_string(),
+            changed_files: Vec::new(),
+            conflicts: Vec::new(),
+        });
+    }
+
+    let target_commit = repo.find_commit(target).map_err(git_error)?;
+    let head_tree = head.as_ref().map(|commit| commit.tree()).transpose().map_err(git_error)?;
+    let diff = repo
+        .diff_tree_to_tree(head_tree.as_ref(), Some(&target_commit.tree().map_err(git_error)?), None)
+        .map_err(git_error)?;
+    let mut changed_files: Vec<PathBuf> = Vec::new();
+    for delta in diff.deltas() {
+        for path in [delta.old_file().path(), delta.new_file().path()].into_iter().flatten() {
+            if !changed_files.iter().any(|changed| changed == path) {
+                changed_files.push(path.to_path_buf());
+            }
+        }
+    }
+
+    let modified = local_changes(&repo)?;
+    let conflicts: Vec<PathBuf> = changed_files.iter().filter(|path| modified.contains(path)).cloned().collect();
+    if !conflicts.is_empty() {
+        log::warn!("Not syncing {}: {} locally modified files changed upstream", content_dir.display(), conflicts.len());
+        return Ok(SyncReport {
+            previous_commit,
+            commit: target.to_string(),
+            changed_files,
+            conflicts,
+        });
+    }
+
+    // A safe checkout only writes files that differ between HEAD and the
+    // target, so local edits to other files stay as they are
+    repo.checkout_tree(target_commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(git_error)?;
+    let branch_ref = format!("refs/heads/{}", git.branch);
+    repo.reference(&branch_ref, target, true, &format!("sync: {}", git.url))
+        .map_err(git_error)?;
+    repo.set_head(&branch_ref).map_err(git_error)?;
+
+    log::info!(
+        "Synced {} to {} ({} changed files)",
+        content_dir.display(),
+        target,
+        changed_files.len()
+    );
+    Ok(SyncReport {
+        previous_commit,
+        commit: target.to_string(),
+        changed_files,
+        conflicts: Vec::new(),
+    })
+}
+
+/// Sync the content directory, then rebuild only what the new commits changed
+/// (everything after a clone). The compile result is `None` when nothing was
+/// compiled: already up to date, or not applied because of conflicts
+pub fn sync_and_compile(config: &Config) -> Result<(SyncReport, Option<CompileResult>), BlogError> {
+    let report = sync(&config.paths.content_dir, &config.content.git)?;
+    if !report.is_applied() || report.changed_files.is_empty() {
+        return Ok((report, None));
+    }
+    let result = if report.previous_commit.is_none() {
+        Compiler::new(config.clone())?.with_force_full(true).compile()?
+    } else {
+        Compiler::new(config.clone())?.compile_paths(&report.changed_files)?
+    };
+    Ok((report, Some(result)))
+}
+
+/// First sync: clone the branch and report every file in it as changed
+fn clone(content_dir: &Path, git: &GitContentConfig) -> Result<SyncReport, BlogError> {
+    let repo = RepoBuilder::new()
+        .branch(&git.branch)
+        .fetch_options(fetch_options(git))
+        .clone(&git.url, content_dir)
+        .map_err(git_error)?;
+    let commit = repo.head().and_then(|head| head.peel_to_commit()).map_err(git_error)?;
+
+    let mut changed_files = Vec::new();
+    commit
+        .tree()
+        .map_err(git_error)?
+        .walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    changed_files.push(Path::new(dir).join(name));
+                }
+            }
+            git2::TreeWalkResult::Ok
+        })
+        .map_err(git_error)?;
+
+    log::info!("Cloned {} into {} at {}", git.url, content_dir.display(), commit.id());
+    Ok(SyncReport {
+        previous_commit: None,
+        commit: commit.id().to_string(),
+        changed_files,
+        conflicts: Vec::new(),
+    })
+}
+
+/// Fetch the configured branch and return the commit it points to
+fn fetch(repo: &Repository, git: &GitContentConfig) -> Result<Oid, BlogError> {
+    let tracking_ref = format!("refs/remotes/origin/{}", git.branch);
+    let refspec = format!("+refs/heads/{}:{}", git.branch, tracking_ref);
+    let mut remote = repo.remote_anonymous(&git.url).map_err(git_error)?;
+    remote
+        .fetch(&[refspec.as_str()], Some(&mut fetch_options(git)), None)
+        .map_err(git_error)?;
+    repo.refname_to_id(&tracking_ref).map_err(git_error)
+}
+
+/// Tracked files with uncommitted changes and untracked files
+fn local_changes(repo: &Repository) -> Result<Vec<PathBuf>, BlogError> {
+    let mut options = StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options)).map_err(git_error)?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| entry.status() != Status::CURRENT)
+        .filter_map(|entry| entry.path().map(PathBuf::from))
+        .collect())
+}
+
+/// Credentials: the deploy key for SSH remotes when configured, otherwise the
+/// SSH agent or git's default credential helpers
+fn fetch_options(git: &GitContentConfig) -> FetchOptions<'static> {
+    let deploy_key = git.deploy_key.clone();
+    let mut attempts = 0;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        // libgit2 asks again after a rejected credential; give up instead of looping
+        attempts += 1;
+        if attempts > 3 {
+            return Err(git2::Error::from_str(&format!("authentication failed for {}", url)));
+        }
+        let username = username.unwrap_or("git");
+        if allowed.contains(CredentialType::SSH_KEY) {
+            return match &deploy_key {
+                Some(key) => Cred::ssh_key(username, None, key, None),
+                None => Cred::ssh_key_from_agent(username),
+            };
+        }
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+        Cred::default()
+    });
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    options
+}
+
+fn git_error(e: git2::Error) -> BlogError {
+    BlogError::Internal(format!("git: {}", e.message()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    /// Write `files` into the work tree of `repo` and commit them
+    fn commit(repo: &Repository, files: &[(&str, Option<&str>)], message: &str) -> Oid {
+        let root = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, content) in files {
+            match content {
+                Some(content) => {
+                    std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+                    std::fs::write(root.join(path), content).unwrap();
+                    index.add_path(Path::new(path)).unwrap();
+                }
+                None => {
+                    std::fs::remove_file(root.join(path)).unwrap();
+                    index.remove_path(Path::new(path)).unwrap();
+                }
+            }
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    #[test]
+    fn test_sync_clones_then_pulls_changes() {
+        let root = std::env::temp_dir().join("test_git_sync_pull");
+        let _ = std::fs::remove_dir_all(&root);
+        let upstream = Repository::init(root.join("upstream")).unwrap();
+        upstream.set_head("refs/heads/main").unwrap();
+        let first = commit(
+            &upstream,
+            &[("wine/index.md", Some("---\ntitle: Wine\n---\n")), ("wine/merlot/merlot.md", Some("Soft"))],
+            "initial",
+        );
+        let git = GitContentConfig {
+            url: root.join("upstream").to_string_lossy().into_owned(),
+            ..GitContentConfig::default()
+        };
+        let content_dir = root.join("content");
+
+        // The first sync clones
+        let report = sync(&content_dir, &git).unwrap();
+        assert_eq!(report.previous_commit, None);
+        assert_eq!(report.commit, first.to_string());
+        assert_eq!(report.changed_files.len(), 2);
+        assert_eq!(std::fs::read_to_string(content_dir.join("wine/merlot/merlot.md")).unwrap(), "Soft");
+
+        // Nothing new upstream
+        let report = sync(&content_dir, &git).unwrap();
+        assert!(report.changed_files.is_empty() && report.is_applied());
+
+        // A local edit to a file that did not change upstream is kept
+        std::fs::write(content_dir.join("wine/index.md"), "---\ntitle: Wines\n---\n").unwrap();
+        let second = commit(
+            &upstream,
+            &[("wine/merlot/merlot.md", None), ("wine/malbec/malbec.md", Some("Dark"))],
+            "replace merlot",
+        );
+        let report = sync(&content_dir, &git).unwrap();
+        assert!(report.is_applied());
+        assert_eq!(report.previous_commit, Some(first.to_string()));
+        assert_eq!(report.commit, second.to_string());
+        assert_eq!(
+            report.changed_files,
+            vec![PathBuf::from("wine/malbec/malbec.md"), PathBuf::from("wine/merlot/merlot.md")]
+        );
+        assert!(!content_dir.join("wine/merlot/merlot.md").exists());
+        assert_eq!(std::fs::read_to_string(content_dir.join("wine/malbec/malbec.md")).unwrap(), "Dark");
+        assert_eq!(std::fs::read_to_string(content_dir.join("wine/index.md")).unwrap(), "---\ntitle: Wines\n---\n");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_sync_reports_conflicts_without_overwriting() {
+        let root = std::env::temp_dir().join("test_git_sync_conflict");
+        let _ = std::fs::remove_dir_all(&root);
+        let upstream = Repository::init(root.join("upstream")).unwrap();
+        upstream.set_head("refs/heads/main").unwrap();
+        let first = commit(&upstream, &[("wine/merlot/merlot.md", Some("Soft"))], "initial");
+        let git = GitContentConfig {
+            url: root.join("upstream").to_string_lossy().into_owned(),
+            ..GitContentConfig::default()
+        };
+        let content_dir = root.join("content");
+        sync(&content_dir, &git).unwrap();
+
+        // Edited in the admin editor and changed upstream
+        std::fs::write(content_dir.join("wine/merlot/merlot.md"), "Edited locally").unwrap();
+        let second = commit(
+            &upstream,
+            &[("wine/merlot/merlot.md", Some("Soft and round")), ("wine/malbec/malbec.md", Some("Dark"))],
+            "update",
+        );
+        let report = sync(&content_dir, &git).unwrap();
+        assert!(!report.is_applied());
+        assert_eq!(report.commit, second.to_string());
+        assert_eq!(report.conflicts, vec![PathBuf::from("wine/merlot/merlot.md")]);
+        assert_eq!(std::fs::read_to_string(content_dir.join("wine/merlot/merlot.md")).unwrap(), "Edited locally");
+        assert!(!content_dir.join("wine/malbec").exists());
+        let head = Repository::open(&content_dir).unwrap().head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(head, first);
+
+        // A non-empty directory that is not a repository is left alone
+        let plain = root.join("plain");
+        std::fs::create_dir_all(&plain).unwrap();
+        std::fs::write(plain.join("notes.md"), "mine").unwrap();
+        assert!(matches!(sync(&plain, &git), Err(BlogError::Validation(_))));
+        assert!(matches!(
+            sync(&content_dir, &GitContentConfig::default()),
+            Err(BlogError::Validation(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::OnceLock;
+use syntect::highlighting::ThemeSet;
+use syntect::html::{css_for_theme_with_class_style, ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+/// Prefix for the generated token classes, keeps them clear of site styles
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// Loading the bundled syntax definitions is slow, so do it once per process
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Highlight `code` as `lang`, returning a complete `<pre><code>` block.
+/// Returns `None` for languages syntect does not know.
+pub fn highlight_code(code: &str, lang: &str) -> Option<String> {
+    let syntaxes = syntax_set();
+    let syntax = syntaxes
+        .find_syntax_by_token(lang)
+        .or_else(|| syntaxes.find_syntax_by_name(lang))?;
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, syntaxes, CLASS_STYLE);
+    for line in LinesWithEndings::from(code) {
+        generator.parse_html_for_line_which_includes_newline(line).ok()?;
+    }
+
+    Some(format!(
+        "<pre class=\"highlight\"><code class=\"language-{}\">{}</code></pre>\n",
+        escape_class(lang),
+        generator.finalize()
+    ))
+}
+
+/// Stylesheet for highlighted blocks: the light palette by default and the
+/// dark one under `[data-theme="dark"]`
+pub fn highlight_css() -> String {
+    let themes = ThemeSet::load_defaults();
+    let light = css_for_theme_with_class_style(&themes.themes[LIGHT_THEME], CLASS_STYLE)
+        .unwrap_or_default();
+    let dark = css_for_theme_with_class_style(&themes.themes[DARK_THEME], CLASS_STYLE)
+        .unwrap_or_default();
+
+    format!(
+        "{}\n{}",
+        scope_css(&light, ".content"),
+        scope_css(&dark, "[data-theme=\"dark\"] .content")
+    )
+}
+
+/// Prefix every selector of the generated stylesheet with `scope`
+fn scope_css(css: &str, scope: &str) -> String {
+    let mut output = String::with_capacity(css.len() * 2);
+    for line in css.lines() {
+        let trimmed = line.trim();
+        if trimmed.ends_with('{') || trimmed.ends_with(',') {
+            // Selector line; comment and declaration lines never end this way
+            let selectors = trimmed.trim_end_matches(['{', ',']).trim_end();
+            let scoped: Vec<String> = selectors
+                .split(',')
+                .map(|selector| format!("{} {}", scope, selector.trim()))
+                .collect();
+            output.push_str(&scoped.join(", "));
+            output.push_str(if trimmed.ends_with('{') { " {" } else { "," });
+        } else {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Languages tags come from user content; keep them safe inside an attribute
+fn escape_class(lang: &str) -> String {
+    lang.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '+' | '#'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_known_language() {
+        let html = highlight_code("fn main() {}\n", "rust").unwrap();
+        assert!(html.starts_with("<pre class=\"highlight\"><code class=\"language-rust\">"));
+        assert!(html.contains("hl-"));
+        assert!(html.contains("main"));
+    }
+
+    #[test]
+    fn test_highlight_unknown_language() {
+        assert!(highlight_code("whatever", "not-a-language").is_none());
+    }
+
+    #[test]
+    fn test_highlight_css_has_dark_palette() {
+        let css = highlight_css();
+        assert!(css.contains(".content .hl-"));
+        assert!(css.contains("[data-theme=\"dark\"] .content .hl-"));
+    }
+}
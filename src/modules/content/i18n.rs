@@ -0,0 +1,189 @@
+//! String catalog for the built-in templates.
+//!
+//! Pages are compiled once but served in whichever language the visitor
+//! picked, so the `t()` template function does not translate while rendering.
+//! It leaves a marker in the page, and [`localize`] replaces the markers with
+//! the visitor's language when the page is served.
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Language used when neither the request nor `site.default_language` names a bundled one
+pub const FALLBACK_LANGUAGE: &str = "zh-CN";
+
+/// Bundled catalogs, `locales/{lang}.toml`
+const BUNDLED: &[(&str, &str)] = &[
+    ("zh-CN", include_str!("../../../locales/zh-CN.toml")),
+    ("en", include_str!("../../../locales/en.toml")),
+];
+
+// Private-use characters never appear in real content
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+const ARG_SEP: char = '\u{E002}';
+
+/// Translations per language, keyed by dotted key (`nav.home`).
+/// Catalog text is trusted markup, like the templates themselves.
+struct Catalog {
+    languages: HashMap<&'static str, HashMap<String, String>>,
+}
+
+impl Catalog {
+    fn bundled() -> &'static Catalog {
+        static CATALOG: OnceLock<Catalog> = OnceLock::new();
+        CATALOG.get_or_init(|| Catalog {
+            languages: BUNDLED
+                .iter()
+                .map(|(lang, source)| {
+                    let table: toml::Table = toml::from_str(source)
+                        .unwrap_or_else(|e| panic!("invalid bundled catalog locales/{}.toml: {}", lang, e));
+                    let mut strings = HashMap::new();
+                    flatten("", &table, &mut strings);
+                    (*lang, strings)
+                })
+                .collect(),
+        })
+    }
+
+    /// Text for `key` in `lang`, falling back to `default_lang`
+    fn lookup(&self, lang: &str, default_lang: &str, key: &str) -> Option<&str> {
+        let find = |lang: &str| self.languages.get(lang).and_then(|strings| strings.get(key)).map(String::as_str);
+        find(lang).or_else(|| {
+            log::debug!("Missing translation '{}' for {}, using {}", key, lang, default_lang);
+            find(default_lang)
+        })
+    }
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            toml::Value::Table(table) => flatten(&key, table, out),
+            toml::Value::String(text) => {
+                out.insert(key, text.clone());
+            }
+            other => {
+                out.insert(key, other.to_string());
+            }
+        }
+    }
+}
+
+/// The bundled language matching `requested`, exactly or by its primary
+/// subtag (`en-US` -> `en`), ignoring case
+pub fn supported_language(requested: &str) -> Option<&'static str> {
+    let requested = requested.trim();
+    let primary = requested.split(['-', '_']).next().unwrap_or_default();
+    BUNDLED
+        .iter()
+        .map(|(lang, _)| *lang)
+        .find(|lang| lang.eq_ignore_ascii_case(requested))
+        .or_else(|| {
+            BUNDLED
+                .iter()
+                .map(|(lang, _)| *lang)
+                .find(|lang| lang.split('-').next().is_some_and(|p| p.eq_ignore_ascii_case(primary)))
+        })
+}
+
+/// Tera function `t(key="nav.home", name=value...)`: a marker for `key`
+/// whose `{name}` placeholders are filled from the other arguments
+pub fn t_function(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let key = args
+        .get("key")
+        .and_then(Value::as_str)
+        .ok_or_else(|| tera::Error::msg("t: `key` must be a string"))?;
+
+    let mut marker = String::new();
+    marker.push(MARK_START);
+    marker.push_str(key);
+    let mut names: Vec<&String> = args.keys().filter(|name| name.as_str() != "key").collect();
+    names.sort();
+    for name in names {
+        let value = match &args[name] {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        marker.push(ARG_SEP);
+        marker.push_str(name);
+        marker.push('=');
+        marker.extend(value.chars().filter(|c| ![MARK_START, MARK_END, ARG_SEP].contains(c)));
+    }
+    marker.push(MARK_END);
+    Ok(Value::String(marker))
+}
+
+/// Replace every `t()` marker in `html` with its text in `lang`.
+/// Keys missing from `lang` use `default_lang`, and unknown keys show the key.
+pub fn localize(html: &str, lang: &str, default_lang: &str) -> String {
+    let catalog = Catalog::bundled();
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(MARK_START) {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + MARK_START.len_utf8()..];
+        let Some(end) = after.find(MARK_END) else {
+            rest = after;
+            break;
+        };
+
+        let mut parts = after[..end].split(ARG_SEP);
+        let key = parts.next().unwrap_or_default();
+        let mut text = catalog.lookup(lang, default_lang, key).unwrap_or(key).to_string();
+        for arg in parts {
+            let (name, value) = arg.split_once('=').unwrap_or((arg, ""));
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        out.push_str(&text);
+        rest = &after[end + MARK_END.len_utf8()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(key: &str, args: &[(&str, Value)]) -> String {
+        let mut map: HashMap<String, Value> = args.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        map.insert("key".to_string(), Value::String(key.to_string()));
+        t_function(&map).unwrap().as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_localize() {
+        let html = format!(
+            "<a>{}</a> <h2>{}</h2> {}",
+            marker("nav.home", &[]),
+            marker("item.series", &[("name", Value::from("Vines")), ("count", Value::from(2))]),
+            marker("no.such.key", &[])
+        );
+        assert_eq!(localize(&html, "zh-CN", "zh-CN"), "<a>首页</a> <h2>系列：Vines（共 2 篇）</h2> no.such.key");
+        assert_eq!(localize(&html, "en", "zh-CN"), "<a>Home</a> <h2>Series: Vines (2 parts)</h2> no.such.key");
+        assert_eq!(localize("plain", "en", "zh-CN"), "plain");
+
+        assert_eq!(supported_language("en-US"), Some("en"));
+        assert_eq!(supported_language("zh-cn"), Some("zh-CN"));
+        assert_eq!(supported_language("zh"), Some("zh-CN"));
+        assert_eq!(supported_language("fr"), None);
+    }
+
+    #[test]
+    fn test_bundled_catalogs_match() {
+        let catalog = Catalog::bundled();
+        let mut keys: Vec<Vec<&String>> = catalog
+            .languages
+            .values()
+            .map(|strings| {
+                let mut keys: Vec<&String> = strings.keys().collect();
+                keys.sort();
+                keys
+            })
+            .collect();
+        let first = keys.pop().unwrap();
+        assert!(keys.iter().all(|other| *other == first));
+    }
+}
@@ -0,0 +1,459 @@
+use crate::config::AttachmentsConfig;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+/// Record of optimized images kept in each `attachment/` directory, keyed by
+/// the file name after optimization. Hidden, so the scanner skips it.
+pub const OPTIMIZED_MANIFEST: &str = ".optimized.json";
+
+/// Quality used when a JPEG has to be re-encoded
+const JPEG_QUALITY: u8 = 85;
+
+/// An image rewritten by [`optimize_attachments`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OptimizedImage {
+    pub file: String, // File name after optimization
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub converted_from: Option<String>, // Original name of a PNG converted to WebP
+    pub original_size: usize,
+    pub optimized_size: usize,
+}
+
+/// Optimize the JPEG, PNG and WebP files in every `attachment/` directory
+/// under `dir` (an extracted item or category). Images wider than
+/// `max_width` are scaled down, EXIF metadata is stripped and large PNGs are
+/// converted to WebP, with markdown links updated to the new name. An image
+/// that fails to optimize is left as uploaded.
+pub fn optimize_attachments(dir: &Path, config: &AttachmentsConfig) -> Vec<OptimizedImage> {
+    if !config.optimize_images {
+        return Vec::new();
+    }
+
+    let attachment_dirs: Vec<PathBuf> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir() && entry.file_name() == "attachment")
+        .map(|entry| entry.into_path())
+        .collect();
+
+    let mut optimized = Vec::new();
+    for attachment_dir in attachment_dirs {
+        let results = optimize_attachment_dir(&attachment_dir, config);
+        if let Some(item_dir) = attachment_dir.parent() {
+            rewrite_converted_links(item_dir, &results);
+        }
+        optimized.extend(results);
+    }
+    optimized
+}
+
+/// Optimization records of an `attachment/` directory
+pub fn read_manifest(attachment_dir: &Path) -> HashMap<String, OptimizedImage> {
+    std::fs::read_to_string(attachment_dir.join(OPTIMIZED_MANIFEST))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn optimize_attachment_dir(attachment_dir: &Path, config: &AttachmentsConfig) -> Vec<OptimizedImage> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(attachment_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    let mut optimized = Vec::new();
+    for path in files {
+        match optimize_file(&path, config) {
+            Ok(Some(result)) => {
+                log::info!(
+                    "Optimized {}: {} -> {} bytes",
+                    path.display(),
+                    result.original_size,
+                    result.optimized_size
+                );
+                optimized.push(result);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to optimize image {}: {}", path.display(), e),
+        }
+    }
+
+    if !optimized.is_empty() {
+        let mut manifest = read_manifest(attachment_dir);
+        for result in &optimized {
+            if let Some(old_name) = &result.converted_from {
+                manifest.remove(old_name);
+            }
+            // An image optimized before keeps its first original size
+            let original_size = manifest
+                .get(&result.file)
+                .map_or(result.original_size, |previous| previous.original_size);
+            manifest.insert(
+                result.file.clone(),
+                OptimizedImage { original_size, ..result.clone() },
+            );
+        }
+        match serde_json::to_string_pretty(&manifest) {
+            Ok(json) => {
+                if let Err(e) = std::fs::write(attachment_dir.join(OPTIMIZED_MANIFEST), json) {
+                    log::warn!("Failed to write {} in {}: {}", OPTIMIZED_MANIFEST, attachment_dir.display(), e);
+                }
+            }
+            Err(e) => log::warn!("Failed to serialize {}: {}", OPTIMIZED_MANIFEST, e),
+        }
+    }
+    optimized
+}
+
+/// Optimize one file in place; `None` when it is not an image or is already optimal
+fn optimize_file(
+    path: &Path,
+    config: &AttachmentsConfig,
+) -> Result<Option<OptimizedImage>, Box<dyn std::error::Error>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let format = match extension.as_str() {
+        "jpg" | "jpeg" => ImageFormat::Jpeg,
+        "png" => ImageFormat::Png,
+        "webp" => ImageFormat::WebP,
+        _ => return Ok(None),
+    };
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid file name")?
+        .to_string();
+
+    let original = std::fs::read(path)?;
+    let mut decoder = ImageReader::with_format(Cursor::new(&original), format).into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let (width, height) = decoder.dimensions();
+    let upright_width = if orientation_swaps_axes(orientation) { height } else { width };
+
+    // Re-encode only when the pixels change; dropping the EXIF block alone is lossless
+    let mut decoded = None;
+    let mut bytes = if upright_width > config.max_width || orientation != Orientation::NoTransforms {
+        let mut image = DynamicImage::from_decoder(decoder)?;
+        image.apply_orientation(orientation);
+        if image.width() > config.max_width {
+            image = image.resize(config.max_width, u32::MAX, FilterType::Lanczos3);
+        }
+        let bytes = encode(&image, format)?;
+        decoded = Some(image);
+        bytes
+    } else {
+        strip_metadata(&original, format)
+    };
+
+    let mut new_name = file_name.clone();
+    let webp_threshold = config.png_to_webp_kb * 1024;
+    if format == ImageFormat::Png && webp_threshold > 0 && bytes.len() as u64 > webp_threshold {
+        let webp_name = format!("{}.webp", path.file_stem().and_then(|s| s.to_str()).unwrap_or("image"));
+        if !path.with_file_name(&webp_name).exists() {
+            let image = match decoded {
+                Some(image) => image,
+                None => image::load_from_memory_with_format(&bytes, ImageFormat::Png)?,
+            };
+            let webp = encode(&image, ImageFormat::WebP)?;
+            if webp.len() < bytes.len() {
+                bytes = webp;
+                new_name = webp_name;
+            }
+        }
+    }
+
+    if new_name == file_name && bytes == original {
+        return Ok(None);
+    }
+
+    let new_path = path.with_file_name(&new_name);
+    std::fs::write(&new_path, &bytes)?;
+    if new_path != path {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(Some(OptimizedImage {
+        converted_from: (new_name != file_name).then_some(file_name),
+        file: new_name,
+        original_size: original.len(),
+        optimized_size: bytes.len(),
+    }))
+}
+
+fn orientation_swaps_axes(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat) -> image::ImageResult<Vec<u8>> {
+    let mut out = Vec::new();
+    match format {
+        ImageFormat::Jpeg => DynamicImage::from(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))?,
+        ImageFormat::WebP if image.color().has_alpha() => DynamicImage::from(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+        ImageFormat::WebP => DynamicImage::from(image.to_rgb8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut out))?,
+        _ => image.write_with_encoder(PngEncoder::new_with_quality(
+            &mut out,
+            CompressionType::Best,
+            PngFilterType::Adaptive,
+        ))?,
+    }
+    Ok(out)
+}
+
+/// Drop EXIF (and XMP/text) metadata without touching the image data.
+/// Malformed files are returned unchanged.
+fn strip_metadata(data: &[u8], format: ImageFormat) -> Vec<u8> {
+    let stripped = match format {
+        ImageFormat::Jpeg => strip_jpeg(data),
+        ImageFormat::Png => strip_png(data),
+        ImageFormat::WebP => strip_webp(data),
+        _ => None,
+    };
+    stripped.unwrap_or_else(|| data.to_vec())
+}
+
+/// Remove APP1 segments (EXIF and XMP) from a JPEG
+fn strip_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut out = data[..2].to_vec();
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        // Start of scan: the rest is entropy-coded image data
+        if marker == 0xDA {
+            out.extend_from_slice(&data[pos..]);
+            return Some(out);
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            out.extend_from_slice(&data[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+        let len = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > data.len() {
+            return None;
+        }
+        if marker != 0xE1 {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+}
+
+/// Remove `eXIf` and text chunks from a PNG
+fn strip_png(data: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !data.starts_with(SIGNATURE) {
+        return None;
+    }
+    let mut out = SIGNATURE.to_vec();
+    let mut pos = SIGNATURE.len();
+    while pos < data.len() {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let end = pos.checked_add(12 + len)?;
+        let chunk_type = data.get(pos + 4..pos + 8)?;
+        if end > data.len() {
+            return None;
+        }
+        if !matches!(chunk_type, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt") {
+            out.extend_from_slice(&data[pos..end]);
+        }
+        pos = end;
+    }
+    Some(out)
+}
+
+/// Remove `EXIF` and `XMP ` chunks from a WebP and clear their VP8X flags
+fn strip_webp(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return None;
+    }
+    let mut out = data[..12].to_vec();
+    let mut pos = 12;
+    while pos < data.len() {
+        let fourcc = data.get(pos..pos + 4)?;
+        let len = u32::from_le_bytes(data.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        let end = (pos + 8).checked_add(len + len % 2)?.min(data.len());
+        if pos + 8 + len > data.len() {
+            return None;
+        }
+        match fourcc {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(&data[pos..end]);
+                // Flags byte: bit 3 is EXIF, bit 2 is XMP
+                if let Some(flags) = out.get_mut(start + 8) {
+                    *flags &= !0x0C;
+                }
+            }
+            _ => out.extend_from_slice(&data[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+/// Point markdown links and `cover:` at PNGs that were converted to WebP
+fn rewrite_converted_links(item_dir: &Path, optimized: &[OptimizedImage]) {
+    let renames: Vec<(&str, &str)> = optimized
+        .iter()
+        .filter_map(|result| Some((result.converted_from.as_deref()?, result.file.as_str())))
+        .collect();
+    if renames.is_empty() {
+        return;
+    }
+
+    let Ok(entries) = std::fs::read_dir(item_dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let mut updated = content.clone();
+        for (old_name, new_name) in &renames {
+            updated = updated
+                .replace(&format!("attachment/{}", old_name), &format!("attachment/{}", new_name))
+                .replace(
+                    &format!("attachment/{}", urlencoding::encode(old_name)),
+                    &format!("attachment/{}", urlencoding::encode(new_name)),
+                )
+                .replace(&format!("cover: {}\n", old_name), &format!("cover: {}\n", new_name));
+        }
+        if updated != content {
+            if let Err(e) = std::fs::write(&path, updated) {
+                log::warn!("Failed to update links in {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage, Rgba, RgbaImage};
+
+    /// A minimal EXIF APP1 segment
+    const EXIF_SEGMENT: &[u8] = b"\xFF\xE1\x00\x10Exif\x00\x00MM\x00\x2A\x00\x00\x00\x08";
+
+    fn jpeg_with_exif(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+        let mut jpeg = Vec::new();
+        DynamicImage::from(image)
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut jpeg, 90))
+            .unwrap();
+        // Insert the EXIF block right after SOI, where cameras put it
+        [&jpeg[..2], EXIF_SEGMENT, &jpeg[2..]].concat()
+    }
+
+    fn config() -> AttachmentsConfig {
+        AttachmentsConfig {
+            optimize_images: true,
+            max_width: 1920,
+            png_to_webp_kb: 1,
+        }
+    }
+
+    #[test]
+    fn test_strip_jpeg_exif() {
+        let jpeg = jpeg_with_exif(64, 32);
+        let stripped = strip_metadata(&jpeg, ImageFormat::Jpeg);
+        assert_eq!(stripped.len(), jpeg.len() - EXIF_SEGMENT.len());
+        assert!(!stripped.windows(4).any(|w| w == b"Exif"));
+        assert_eq!(image::load_from_memory(&stripped).unwrap().width(), 64);
+
+        // Malformed data is kept as is
+        assert_eq!(strip_metadata(b"not a jpeg", ImageFormat::Jpeg), b"not a jpeg");
+    }
+
+    #[test]
+    fn test_optimize_attachments() {
+        let item_dir = std::env::temp_dir().join("lf_blog_test_optimize_images/grape/tizi");
+        let _ = std::fs::remove_dir_all(item_dir.parent().unwrap());
+        let attachment_dir = item_dir.join("attachment");
+        std::fs::create_dir_all(&attachment_dir).unwrap();
+
+        std::fs::write(attachment_dir.join("wide.jpg"), jpeg_with_exif(2000, 1000)).unwrap();
+        std::fs::write(attachment_dir.join("small.jpg"), jpeg_with_exif(64, 32)).unwrap();
+        let png = RgbaImage::from_fn(256, 256, |x, y| Rgba([x as u8, y as u8, 200, 255]));
+        png.save(attachment_dir.join("chart.png")).unwrap();
+        std::fs::write(attachment_dir.join("notes.txt"), "not an image").unwrap();
+        std::fs::write(
+            item_dir.join("tizi.md"),
+            "---\ntitle: Tizi\ncover: chart.png\n---\n\n![chart](attachment/chart.png)\n",
+        )
+        .unwrap();
+
+        let mut results = optimize_attachments(item_dir.parent().unwrap(), &config());
+        results.sort_by(|a, b| a.file.cmp(&b.file));
+        let files: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+        assert_eq!(files, vec!["chart.webp", "small.jpg", "wide.jpg"]);
+        assert!(results.iter().all(|r| r.optimized_size < r.original_size));
+
+        // Wide images are scaled down keeping the aspect ratio, without EXIF
+        let wide = std::fs::read(attachment_dir.join("wide.jpg")).unwrap();
+        let wide_image = image::load_from_memory(&wide).unwrap();
+        assert_eq!((wide_image.width(), wide_image.height()), (1920, 960));
+        assert!(!wide.windows(4).any(|w| w == b"Exif"));
+
+        // Large PNGs become WebP and links follow
+        assert!(!attachment_dir.join("chart.png").exists());
+        assert_eq!(results[0].converted_from.as_deref(), Some("chart.png"));
+        let webp = image::open(attachment_dir.join("chart.webp")).unwrap();
+        assert_eq!(webp.width(), 256);
+        let markdown = std::fs::read_to_string(item_dir.join("tizi.md")).unwrap();
+        assert!(markdown.contains("cover: chart.webp\n"));
+        assert!(markdown.contains("(attachment/chart.webp)"));
+
+        let manifest = read_manifest(&attachment_dir);
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest["wide.jpg"], results[2]);
+
+        // A second pass finds nothing left to do and keeps the original sizes
+        assert!(optimize_attachments(&item_dir, &config()).is_empty());
+        assert_eq!(read_manifest(&attachment_dir)["wide.jpg"].original_size, results[2].original_size);
+
+        // Opting out leaves uploads untouched
+        std::fs::write(attachment_dir.join("small.jpg"), jpeg_with_exif(64, 32)).unwrap();
+        let disabled = AttachmentsConfig { optimize_images: false, ..config() };
+        assert!(optimize_attachments(&item_dir, &disabled).is_empty());
+
+        std::fs::remove_dir_all(item_dir.parent().unwrap()).ok();
+    }
+}
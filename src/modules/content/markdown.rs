@@ -1,7 +1,13 @@
-use chrono::{DateTime, NaiveDateTime};
-use pulldown_cmark::{html, Event, Parser, Tag};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::config::MarkdownConfig;
+use crate::highlight::highlight_code;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Kinds accepted by `::: kind` callout containers
+pub const CALLOUT_TYPES: &[&str] = &["info", "tip", "warning", "danger"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Frontmatter {
@@ -18,6 +24,18 @@ pub struct Frontmatter {
     #[serde(rename = "time")]
     #[serde(default)]
     pub time: Option<String>, // Support both 'date' and 'time'
+    #[serde(default)]
+    pub publish_at: Option<chrono::DateTime<chrono::Utc>>, // Hidden until this time
+    #[serde(default)]
+    pub series: Option<String>, // Name of the series the article belongs to
+    #[serde(default)]
+    pub series_order: Option<u32>, // Position within the series
+    #[serde(default)]
+    pub cover: Option<String>, // Share image: an attachment name, a site path or a full URL
+    #[serde(default)]
+    pub draft: bool, // Set by `new_page`, cleared by `client publish`
+    #[serde(default)]
+    pub slug: Option<String>, // Replaces the file name in the item URL
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
@@ -69,22 +87,76 @@ impl ParsedMarkdown {
         DateTime::parse_from_rfc3339(date_str)
             .ok()
             .map(|dt| dt.naive_utc())
-            .or_else(|| {
-                // Try other formats
-                NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d").ok()
-                // Can add more formats here
-            })
+            .or_else(|| parse_local_date(date_str))
+    }
+
+    /// Point in time the post goes live. RFC 3339 dates carry their own
+    /// offset; plain dates and date-times are local to `tz`.
+    pub fn get_publish_time(&self, tz: Tz) -> Option<DateTime<Utc>> {
+        let date_str = self
+            .frontmatter
+            .time
+            .as_ref()
+            .or(self.frontmatter.date.as_ref())?;
+        if let Ok(dt) = DateTime::parse_from_rfc3339(date_str) {
+            return Some(dt.with_timezone(&Utc));
+        }
+
+        let local = parse_local_date(date_str)?;
+        // A time skipped by a DST change has no local match; read it as UTC
+        Some(
+            tz.from_local_datetime(&local)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|| Utc.from_utc_datetime(&local)),
+        )
     }
 }
 
+/// Parse a date without offset; a bare date means midnight
+fn parse_local_date(date_str: &str) -> Option<NaiveDateTime> {
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(date_str, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Parse with every markdown extension enabled and no code highlighting
 pub fn parse_markdown(content: &str) -> Result<ParsedMarkdown, Box<dyn std::error::Error>> {
+    let options = MarkdownConfig {
+        highlight: false,
+        ..MarkdownConfig::default()
+    };
+    parse_markdown_with_options(content, &options)
+}
+
+/// Like `parse_markdown`, with extensions and code highlighting taken from `options`
+pub fn parse_markdown_with_options(
+    content: &str,
+    options: &MarkdownConfig,
+) -> Result<ParsedMarkdown, Box<dyn std::error::Error>> {
     // Parse frontmatter
     let (frontmatter, markdown_content) = parse_frontmatter(content)?;
 
     // Convert markdown to HTML
-    let parser = Parser::new(markdown_content);
+    let source = if options.enable_callouts {
+        expand_callouts(markdown_content)
+    } else {
+        markdown_content.to_string()
+    };
+    let mut events: Vec<Event> = Parser::new_ext(&source, parser_options(options)).collect();
+    if options.highlight {
+        events = highlight_code_blocks(events);
+    }
+    if options.footnotes {
+        events = link_footnotes(events);
+    }
     let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
+    html::push_html(&mut html_output, events.into_iter());
 
     Ok(ParsedMarkdown {
         frontmatter,
@@ -93,6 +165,253 @@ pub fn parse_markdown(content: &str) -> Result<ParsedMarkdown, Box<dyn std::erro
     })
 }
 
+fn parser_options(config: &MarkdownConfig) -> Options {
+    let mut options = Options::empty();
+    options.set(Options::ENABLE_TABLES, config.tables);
+    options.set(Options::ENABLE_FOOTNOTES, config.footnotes);
+    options.set(Options::ENABLE_TASKLISTS, config.tasklists);
+    options.set(Options::ENABLE_STRIKETHROUGH, config.strikethrough);
+    options.set(Options::ENABLE_SMART_PUNCTUATION, config.smart_punctuation);
+    options
+}
+
+/// Turn `::: kind [title]` ... `:::` containers into callout divs. Blank
+/// lines keep the div tags apart from the content so it is still parsed as
+/// markdown, and lines inside fenced code blocks are left alone.
+fn expand_callouts(markdown: &str) -> String {
+    let mut output = String::with_capacity(markdown.len());
+    let mut fence: Option<&str> = None;
+    let mut open = 0;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        match fence {
+            Some(marker) => {
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                }
+            }
+            None if trimmed.starts_with("```") || trimmed.starts_with("~~~") => fence = Some(&trimmed[..3]),
+            None => {
+                if let Some(rest) = trimmed.strip_prefix(":::").map(str::trim) {
+                    if rest.is_empty() && open > 0 {
+                        output.push_str("\n</div>\n\n");
+                        open -= 1;
+                        continue;
+                    }
+                    let (kind, title) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    if CALLOUT_TYPES.contains(&kind) {
+                        output.push_str(&format!("\n<div class=\"callout callout-{}\">\n", kind));
+                        if !title.trim().is_empty() {
+                            let escaped = title.trim().replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                            output.push_str(&format!("<p class=\"callout-title\">{}</p>\n", escaped));
+                        }
+                        output.push('\n');
+                        open += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    // An unclosed callout runs to the end of the document
+    for _ in 0..open {
+        output.push_str("\n</div>\n");
+    }
+    output
+}
+
+/// Replace fenced code blocks with highlighted HTML; unknown languages keep the plain events
+fn highlight_code_blocks(input: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut events = Vec::new();
+    let mut block: Option<(CowStr, Vec<Event>)> = None;
+
+    for event in input {
+        match (&mut block, event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang)))) if !lang.is_empty() => {
+                let start = Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang.clone())));
+                block = Some((lang, vec![start]));
+            }
+            (Some((lang, buffered)), Event::End(TagEnd::CodeBlock)) => {
+                let code: String = buffered
+                    .iter()
+                    .filter_map(|e| match e {
+                        Event::Text(text) => Some(text.as_ref()),
+                        _ => None,
+                    })
+                    .collect();
+                // The info string may carry extra attributes after the language
+                let lang = lang.split_whitespace().next().unwrap_or_default();
+                match highlight_code(&code, lang) {
+                    Some(html) => events.push(Event::Html(html.into())),
+                    None => {
+                        events.append(buffered);
+                        events.push(Event::End(TagEnd::CodeBlock));
+                    }
+                }
+                block = None;
+            }
+            (Some((_, buffered)), event) => buffered.push(event),
+            (None, event) => events.push(event),
+        }
+    }
+
+    events
+}
+
+/// Render footnotes with ids on both ends so definitions can link back to
+/// where they were referenced. Footnotes are numbered in order of first
+/// appearance, like pulldown-cmark's own renderer; the numbers double as
+/// ids so footnote labels never end up in attributes. Definitions are moved
+/// into a `<section class="footnotes">` after the rest of the content.
+fn link_footnotes(input: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut numbers: HashMap<String, usize> = HashMap::new();
+    let mut references: HashMap<usize, usize> = HashMap::new();
+    let mut definitions: Vec<usize> = Vec::new();
+
+    let mut number_for = |name: &str| {
+        let next = numbers.len() + 1;
+        *numbers.entry(name.to_string()).or_insert(next)
+    };
+
+    // Definitions may come before their references, so collect those first
+    let referenced: HashSet<String> = input
+        .iter()
+        .filter_map(|event| match event {
+            Event::FootnoteReference(name) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut events = Vec::new();
+    let mut footnotes = Vec::new();
+    for event in input {
+        let in_definition = !definitions.is_empty();
+        match event {
+            Event::FootnoteReference(name) => {
+                let number = number_for(&name);
+                let count = references.entry(number).or_insert(0);
+                *count += 1;
+                let id = if *count == 1 {
+                    format!("fnref-{}", number)
+                } else {
+                    format!("fnref-{}-{}", number, count)
+                };
+                let reference = Event::InlineHtml(
+                    format!(
+                        "<sup class=\"footnote-reference\" id=\"{}\"><a href=\"#fn-{}\">{}</a></sup>",
+                        id, number, number
+                    )
+                    .into(),
+                );
+                if in_definition {
+                    footnotes.push(reference);
+                } else {
+                    events.push(reference);
+                }
+            }
+            Event::Start(Tag::FootnoteDefinition(name)) => {
+                let number = number_for(&name);
+                definitions.push(if referenced.contains(name.as_ref()) { number } else { 0 });
+                footnotes.push(Event::Html(
+                    format!(
+                        "<div class=\"footnote-definition\" id=\"fn-{}\"><sup class=\"footnote-definition-label\">{}</sup>\n",
+                        number, number
+                    )
+                    .into(),
+                ));
+            }
+            Event::End(TagEnd::FootnoteDefinition) => footnotes.push(match definitions.pop() {
+                Some(number) if number > 0 => Event::Html(
+                    format!(
+                        "<a href=\"#fnref-{}\" class=\"footnote-backref\">↩</a></div>\n",
+                        number
+                    )
+                    .into(),
+                ),
+                _ => Event::Html("</div>\n".into()),
+            }),
+            event if in_definition => footnotes.push(event),
+            event => events.push(event),
+        }
+    }
+
+    if !footnotes.is_empty() {
+        events.push(Event::Html("<section class=\"footnotes\">\n".into()));
+        events.append(&mut footnotes);
+        events.push(Event::Html("</section>\n".into()));
+    }
+    events
+}
+
+/// Inline elements that must not split the surrounding words when stripped
+const INLINE_TAGS: &[&str] = &["a", "abbr", "b", "code", "del", "em", "i", "kbd", "mark", "span", "strong", "sub", "sup"];
+
+/// Strip tags from compiled HTML and collapse whitespace
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = "";
+            break;
+        };
+        let name: String = rest[start + 1..start + end]
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        if !INLINE_TAGS.contains(&name.to_ascii_lowercase().as_str()) {
+            text.push(' ');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Longest excerpt taken from the first paragraph, in characters
+const MAX_EXCERPT_CHARS: usize = 250;
+
+/// Summary shown in listings: the `description` frontmatter, otherwise the
+/// text before a `<!--more-->` marker, otherwise the first paragraph cut to
+/// 250 characters
+pub fn extract_excerpt(parsed: &ParsedMarkdown) -> Option<String> {
+    if let Some(description) = parsed.frontmatter.description.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        return Some(description.to_string());
+    }
+
+    // The marker passes through as raw HTML; inside code it is escaped and never matches
+    if let Some((before, _)) = parsed.html_content.split_once("<!--more-->") {
+        return Some(html_to_text(before)).filter(|text| !text.is_empty());
+    }
+
+    let start = parsed.html_content.find("<p>")?;
+    let end = parsed.html_content[start..].find("</p>").map_or(parsed.html_content.len(), |end| start + end);
+    let text = html_to_text(&parsed.html_content[start..end]);
+    if text.chars().count() <= MAX_EXCERPT_CHARS {
+        return Some(text).filter(|text| !text.is_empty());
+    }
+    let cut: String = text.chars().take(MAX_EXCERPT_CHARS).collect();
+    // Prefer ending on a word boundary when the text has spaces
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > cut.len() / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    Some(format!("{}…", cut.trim_end()))
+}
+
 fn parse_frontmatter(content: &str) -> Result<(Frontmatter, &str), Box<dyn std::error::Error>> {
     if !content.starts_with("---") {
         // No frontmatter
@@ -104,6 +423,12 @@ fn parse_frontmatter(content: &str) -> Result<(Frontmatter, &str), Box<dyn std::
                 tags: None,
                 description: None,
                 time: None,
+                publish_at: None,
+                series: None,
+                series_order: None,
+                cover: None,
+                draft: false,
+                slug: None,
                 extra: HashMap::new(),
             },
             content,
@@ -134,30 +459,94 @@ pub fn process_image_links(html: &str, base_path: &str) -> String {
     result
 }
 
-/// Replace attachment links in HTML content with their new names
+/// Rewrite `src`/`href` attributes that point into the item's `attachment/`
+/// directory to the stored attachment under `/attachment/`.
 /// Original format: ./attachment/filename.ext or attachment/filename.ext
-/// New format: attachment/new_filename.ext
-pub fn replace_attachment_links(html: &str, attachment_map: &[(String, String)]) -> String {
-    let mut result = html.to_string();
-
-    // Sort by length descending to avoid partial replacements
-    // e.g., replace "image.png" before replacing "image_small.png"
-    let mut sorted_attachments: Vec<_> = attachment_map.iter().collect();
-    sorted_attachments.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
-
-    for (original_name, new_name) in sorted_attachments {
-        // Match patterns: ./attachment/filename or attachment/filename
-        let patterns = [
-            format!("./attachment/{}", original_name),
-            format!("attachment/{}", original_name),
-        ];
+/// New format: /attachment/new_filename.ext
+///
+/// Covers both markdown images/links and raw `<img>` tags, since both end up
+/// as attributes in the rendered HTML. Returns the rewritten HTML together
+/// with the referenced filenames that have no stored attachment.
+pub fn rewrite_attachment_links(
+    html: &str,
+    attachment_map: &[(String, String)],
+) -> (String, Vec<String>) {
+    let mut result = String::with_capacity(html.len());
+    let mut missing = Vec::new();
+    let mut rest = html;
+
+    while let Some((value_start, quote)) = find_link_attribute(rest) {
+        let value_len = rest[value_start..].find(quote).unwrap_or(rest.len() - value_start);
+        let value = &rest[value_start..value_start + value_len];
+        result.push_str(&rest[..value_start]);
+
+        match rewrite_attachment_url(value, attachment_map) {
+            Some(Ok(url)) => result.push_str(&url),
+            Some(Err(name)) => {
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+                result.push_str(value);
+            }
+            None => result.push_str(value),
+        }
+
+        rest = &rest[value_start + value_len..];
+    }
+    result.push_str(rest);
+
+    (result, missing)
+}
 
-        for pattern in &patterns {
-            result = result.replace(pattern, &format!("attachment/{}", new_name));
+/// Find the next ` src=` / ` href=` attribute, returning the start of its
+/// quoted value and the quote character
+fn find_link_attribute(html: &str) -> Option<(usize, char)> {
+    let mut offset = 0;
+    while offset < html.len() {
+        let remaining = &html[offset..];
+        let (pos, attr_len) = match (remaining.find("src="), remaining.find("href=")) {
+            (Some(s), Some(h)) if h < s => (h, 5),
+            (Some(s), _) => (s, 4),
+            (None, Some(h)) => (h, 5),
+            (None, None) => return None,
+        };
+        let attr_start = offset + pos;
+        let value_quote = html[attr_start + attr_len..].chars().next();
+        let at_boundary = html[..attr_start].ends_with(char::is_whitespace);
+
+        match value_quote {
+            Some(quote @ ('"' | '\'')) if at_boundary => {
+                return Some((attr_start + attr_len + 1, quote));
+            }
+            _ => offset = attr_start + attr_len,
         }
     }
+    None
+}
 
-    result
+/// `Some(Ok(url))` for a known attachment, `Some(Err(name))` for an unknown
+/// one, `None` when the URL does not point into `attachment/`
+fn rewrite_attachment_url(
+    value: &str,
+    attachment_map: &[(String, String)],
+) -> Option<Result<String, String>> {
+    let path = value.strip_prefix("./").unwrap_or(value);
+    let name_and_suffix = path.strip_prefix("attachment/")?;
+
+    // Keep any query string or fragment, e.g. `attachment/doc.pdf#page=2`
+    let split = name_and_suffix.find(['?', '#']).unwrap_or(name_and_suffix.len());
+    let (name, suffix) = name_and_suffix.split_at(split);
+    let name = urlencoding::decode(name)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or_else(|_| name.to_string());
+
+    Some(
+        attachment_map
+            .iter()
+            .find(|(original, _)| *original == name)
+            .map(|(_, new_name)| format!("/attachment/{}{}", urlencoding::encode(new_name), suffix))
+            .ok_or(name),
+    )
 }
 
 #[cfg(test)]
@@ -182,6 +571,240 @@ This is a test post."#;
         assert!(parsed.html_content.contains("<h1>Hello World</h1>"));
     }
 
+    #[test]
+    fn test_publish_time_in_timezone() {
+        let parsed = parse_markdown("---\ndate: 2025-12-01\n---\n\nSoon.").unwrap();
+        assert_eq!(parsed.get_date().unwrap().to_string(), "2025-12-01 00:00:00");
+
+        let shanghai: Tz = "Asia/Shanghai".parse().unwrap();
+        let publish_time = parsed.get_publish_time(shanghai).unwrap();
+        assert_eq!(publish_time.to_rfc3339(), "2025-11-30T16:00:00+00:00");
+        assert_eq!(
+            parsed.get_publish_time(Tz::UTC).unwrap().to_rfc3339(),
+            "2025-12-01T00:00:00+00:00"
+        );
+
+        // An explicit offset wins over the site timezone
+        let parsed = parse_markdown("---\ndate: 2025-12-01T09:30:00+02:00\n---\n").unwrap();
+        assert_eq!(
+            parsed.get_publish_time(shanghai).unwrap().to_rfc3339(),
+            "2025-12-01T07:30:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_parse_publish_at() {
+        let content = "---\ntitle: Later\npublish_at: 2030-06-01T08:00:00Z\n---\n\nSoon.";
+        let parsed = parse_markdown(content).unwrap();
+        let publish_at = parsed.frontmatter.publish_at.unwrap();
+        assert_eq!(publish_at.to_rfc3339(), "2030-06-01T08:00:00+00:00");
+        assert!(!parsed.frontmatter.extra.contains_key("publish_at"));
+    }
+
+    #[test]
+    fn test_highlight_code_blocks() {
+        let content = "```rust\nlet x = 1;\n```\n\n```nosuchlang\n<raw>\n```\n";
+
+        let plain = parse_markdown(content).unwrap();
+        assert!(plain.html_content.contains("<pre><code class=\"language-rust\">"));
+
+        let highlighted = parse_markdown_with_options(content, &MarkdownConfig::default()).unwrap();
+        assert!(highlighted.html_content.contains("<pre class=\"highlight\"><code class=\"language-rust\">"));
+        assert!(highlighted.html_content.contains("hl-"));
+        // Unknown languages fall back to the plain, escaped block
+        assert!(highlighted.html_content.contains("<code class=\"language-nosuchlang\">&lt;raw&gt;"));
+    }
+
+    const EXTENSIONS_FIXTURE: &str = "\
+> Quarterly numbers:
+>
+> | Fruit | Count |
+> |:------|------:|
+> | apple | 3 |
+> | plum  | 5 |
+
+- [x] pick apples
+- [ ] pick plums
+
+~~Pears~~ \"Plums\" are 'the' best -- ask anyone --- really.
+
+Plums keep well[^keep], dried plums even longer[^dried]. See also[^keep].
+
+[^dried]: Dried plums last a year.
+
+[^keep]: About two weeks in the fridge.
+";
+
+    #[test]
+    fn test_tables_in_blockquote() {
+        let html = parse_markdown(EXTENSIONS_FIXTURE).unwrap().html_content;
+        let quote_start = html.find("<blockquote>").unwrap();
+        let quote_end = html.find("</blockquote>").unwrap();
+        let quote = &html[quote_start..quote_end];
+        assert!(quote.contains("<table>"));
+        assert!(quote.contains("<th style=\"text-align: left\">Fruit</th>"));
+        assert!(quote.contains("<td style=\"text-align: right\">5</td>"));
+    }
+
+    #[test]
+    fn test_tasklists() {
+        let html = parse_markdown(EXTENSIONS_FIXTURE).unwrap().html_content;
+        assert!(html.contains("<input disabled=\"\" type=\"checkbox\" checked=\"\"/>\npick apples"));
+        assert!(html.contains("<input disabled=\"\" type=\"checkbox\"/>\npick plums"));
+    }
+
+    #[test]
+    fn test_footnote_back_references() {
+        let html = parse_markdown(EXTENSIONS_FIXTURE).unwrap().html_content;
+        // Numbered by first reference, not by definition order
+        assert!(html.contains("<sup class=\"footnote-reference\" id=\"fnref-1\"><a href=\"#fn-1\">1</a></sup>"));
+        assert!(html.contains("<sup class=\"footnote-reference\" id=\"fnref-2\"><a href=\"#fn-2\">2</a></sup>"));
+        // A second reference gets its own id
+        assert!(html.contains("id=\"fnref-1-2\"><a href=\"#fn-1\">1</a>"));
+
+        let keep = html.find("<div class=\"footnote-definition\" id=\"fn-1\">").unwrap();
+        assert!(html[keep..].contains("About two weeks in the fridge."));
+        assert!(html[keep..].contains("<a href=\"#fnref-1\" class=\"footnote-backref\">↩</a></div>"));
+        assert!(html.contains("<a href=\"#fnref-2\" class=\"footnote-backref\">↩</a></div>"));
+
+        // Definitions end up in one section after the rest of the content
+        let section = html.find("<section class=\"footnotes\">").unwrap();
+        assert!(section > html.find("See also").unwrap());
+        assert!(section < keep);
+        assert!(html.trim_end().ends_with("</section>"));
+
+        // Unreferenced definitions have nothing to link back to
+        let html = parse_markdown("[^lonely]: Never cited.\n").unwrap().html_content;
+        assert!(html.contains("id=\"fn-1\""));
+        assert!(!html.contains("footnote-backref"));
+    }
+
+    #[test]
+    fn test_strikethrough_and_smart_punctuation() {
+        let html = parse_markdown(EXTENSIONS_FIXTURE).unwrap().html_content;
+        assert!(html.contains("<del>Pears</del>"));
+        assert!(html.contains("“Plums” are ‘the’ best – ask anyone — really."));
+        // Code keeps its straight quotes and dashes
+        let html = parse_markdown("`\"a\" -- b`").unwrap().html_content;
+        assert!(html.contains("<code>\"a\" -- b</code>"));
+    }
+
+    #[test]
+    fn test_callouts() {
+        for kind in CALLOUT_TYPES {
+            let html = parse_markdown(&format!("::: {}\nMind the **gap**.\n:::\n", kind)).unwrap().html_content;
+            assert!(html.contains(&format!("<div class=\"callout callout-{}\">", kind)), "{}", html);
+            assert!(html.contains("<p>Mind the <strong>gap</strong>.</p>\n</div>"), "{}", html);
+        }
+
+        let content = "\
+Before
+
+::: warning Hot <plate>
+- one
+- two
+
+```sh
+:::
+```
+
+::: tip
+Nested `tip`
+:::
+:::
+
+::: note
+Not a callout type
+:::
+";
+        let html = parse_markdown(content).unwrap().html_content;
+        assert!(html.contains("<p>Before</p>"));
+        assert!(html.contains("<p class=\"callout-title\">Hot &lt;plate&gt;</p>"));
+        assert!(html.contains("<li>one</li>"));
+        // Markers inside code blocks are content
+        assert!(html.contains("<code class=\"language-sh\">:::\n</code>"));
+        let warning = html.find("callout-warning").unwrap();
+        let tip = html.find("callout-tip").unwrap();
+        assert!(warning < tip && html[tip..].contains("<code>tip</code>"));
+        assert_eq!(html.matches("<div class=\"callout").count(), 2);
+        assert_eq!(html.matches("</div>").count(), 2);
+        assert!(html.contains("::: note"));
+
+        // Unclosed callouts end with the document
+        let html = parse_markdown("::: danger\nStill open").unwrap().html_content;
+        assert!(html.trim_end().ends_with("<p>Still open</p>\n</div>"), "{}", html);
+    }
+
+    #[test]
+    fn test_extensions_can_be_disabled() {
+        let options = MarkdownConfig {
+            highlight: false,
+            tables: false,
+            footnotes: false,
+            tasklists: false,
+            strikethrough: false,
+            smart_punctuation: false,
+            enable_callouts: false,
+        };
+        let html = parse_markdown_with_options(EXTENSIONS_FIXTURE, &options).unwrap().html_content;
+        assert!(!html.contains("<table>"));
+        assert!(!html.contains("checkbox"));
+        assert!(!html.contains("footnote"));
+        assert!(html.contains("| apple | 3 |"));
+        assert!(html.contains("[ ] pick plums"));
+        assert!(html.contains("~~Pears~~ \"Plums\" are 'the' best -- ask anyone --- really."));
+        let html = parse_markdown_with_options("::: tip\nHi\n:::", &options).unwrap().html_content;
+        assert!(html.contains("::: tip") && !html.contains("callout"));
+    }
+
+    #[test]
+    fn test_rewrite_attachment_links() {
+        let content = "![pic](attachment/photo.jpg)\n\n\
+            <img src=\"./attachment/my photo.png\" alt=\"raw\">\n\n\
+            [doc](./attachment/guide.pdf#page=2) [gone](attachment/missing.zip) \
+            [site](https://example.com/attachment/photo.jpg)\n\n\
+            Text mentioning attachment/photo.jpg stays as is.";
+        let parsed = parse_markdown(content).unwrap();
+        let map = vec![
+            ("photo.jpg".to_string(), "item_1.jpg".to_string()),
+            ("my photo.png".to_string(), "item_2.png".to_string()),
+            ("guide.pdf".to_string(), "item_3.pdf".to_string()),
+        ];
+
+        let (html, missing) = rewrite_attachment_links(&parsed.html_content, &map);
+        assert!(html.contains("src=\"/attachment/item_1.jpg\""));
+        assert!(html.contains("src=\"/attachment/item_2.png\""));
+        assert!(html.contains("href=\"/attachment/item_3.pdf#page=2\""));
+        assert!(html.contains("href=\"https://example.com/attachment/photo.jpg\""));
+        assert!(html.contains("mentioning attachment/photo.jpg stays"));
+        assert_eq!(missing, vec!["missing.zip".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_excerpt() {
+        let excerpt = |content: &str| extract_excerpt(&parse_markdown(content).unwrap());
+
+        // Frontmatter description wins
+        assert_eq!(
+            excerpt("---\ndescription: Short summary\n---\n\nFirst paragraph.").as_deref(),
+            Some("Short summary")
+        );
+        // Text before the more marker, tags stripped
+        assert_eq!(
+            excerpt("# Title\n\nIntro with **bold** &amp; more.\n\n<!--more-->\n\nRest.").as_deref(),
+            Some("Title Intro with bold & more.")
+        );
+        // The marker inside code does not count
+        assert_eq!(
+            excerpt("# Title\n\nFirst *paragraph*.\n\n```\n<!--more-->\n```").as_deref(),
+            Some("First paragraph.")
+        );
+        // Long first paragraphs are cut at a word boundary
+        let long = excerpt(&"word ".repeat(100)).unwrap();
+        assert!(long.ends_with("word…") && long.chars().count() <= MAX_EXCERPT_CHARS + 1);
+        assert_eq!(excerpt("# Only a heading"), None);
+    }
+
     #[test]
     fn test_parse_without_frontmatter() {
         let content = "# Hello World\n\nThis is a test post.";
@@ -2,4 +2,9 @@ pub mod scanner;
 pub mod compiler;
 pub mod markdown;
 pub mod templates;
-pub mod theme;
\ No newline at end of file
+pub mod theme;
+pub mod seo;
+pub mod highlight;
+pub mod tags;
+pub mod series;
+pub mod i18n;
\ No newline at end of file
@@ -1,6 +1,16 @@
-use crate::markdown::parse_markdown;
+use crate::error::BlogError;
+use crate::config::MarkdownConfig;
+use crate::markdown::{extract_excerpt, parse_markdown, parse_markdown_with_options, ParsedMarkdown};
+use crate::slug::slugify;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// Attachment file information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,9 +19,22 @@ pub struct Attachment {
     pub new_name: String,      // Generated unique filename
     pub file_type: String,     // File extension (e.g., "jpg", "png")
     pub path: String,          // Relative path in generated folder
-    pub file_data: Vec<u8>,    // File binary content
+    pub source_path: PathBuf,  // Source file on disk, streamed into storage at compile time
     pub file_size: usize,      // File size in bytes
     pub mime_type: String,     // MIME type
+    pub content_hash: String,  // SHA-256 of the file content
+    pub duplicate_of: Option<String>, // new_name of the identical attachment stored first
+    #[serde(default)]
+    pub was_optimized: bool,   // Resized or re-encoded when the package was uploaded
+    #[serde(default)]
+    pub original_size: usize,  // Size as uploaded; equals file_size unless optimized
+}
+
+impl Attachment {
+    /// Name under which this attachment is actually stored
+    pub fn stored_name(&self) -> &str {
+        self.duplicate_of.as_deref().unwrap_or(&self.new_name)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,38 +48,249 @@ pub struct ContentItem {
     pub date: Option<String>, // Formatted date string
     pub author: Option<String>,
     pub description: Option<String>,
+    #[serde(default)]
+    pub excerpt: Option<String>, // Summary for listings, see markdown::extract_excerpt
     pub html_content: String,
     pub attachments: Vec<Attachment>, // List of attachments
     pub tags: Vec<String>,            // Tags from frontmatter
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>, // Scheduled publish time
+    #[serde(default)]
+    pub raw_content: String, // Markdown body without frontmatter
+    #[serde(default)]
+    pub series: Option<String>, // Series name from frontmatter
+    #[serde(default)]
+    pub series_order: Option<u32>, // Position within the series
+    #[serde(default)]
+    pub cover: Option<String>, // Cover image as written in frontmatter
+}
+
+impl ContentItem {
+    /// Whether the item is scheduled to appear after `now`
+    pub fn is_scheduled(&self, now: DateTime<Utc>) -> bool {
+        self.publish_at.is_some_and(|at| at > now)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Category {
     pub name: String,        // e.g., "grape"
+    #[serde(default)]
+    pub title: String,       // Display name from index.md `title:`, e.g. "葡萄"; defaults to name
     pub url: String,         // e.g., "grape"
     pub index_path: PathBuf, // e.g., "content/grape/index.md"
     pub items: Vec<ContentItem>,
     pub description: Option<String>,
 }
 
+impl Category {
+    /// Older (`prev`) and newer (`next`) neighbours of the item at `url`.
+    /// Items are listed newest first, so the older one comes after it.
+    pub fn neighbours(&self, url: &str) -> (Option<ItemLink>, Option<ItemLink>) {
+        let Some(index) = self.items.iter().position(|item| item.url == url) else {
+            return (None, None);
+        };
+        let prev = self.items.get(index + 1).map(ItemLink::from);
+        let next = index
+            .checked_sub(1)
+            .and_then(|i| self.items.get(i))
+            .map(ItemLink::from);
+        (prev, next)
+    }
+}
+
+/// Title and URL of another item, for previous/next navigation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemLink {
+    pub title: String,
+    pub url: String,
+}
+
+impl From<&ContentItem> for ItemLink {
+    fn from(item: &ContentItem) -> Self {
+        ItemLink {
+            title: item.title.clone(),
+            url: item.url.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteContent {
     pub categories: Vec<Category>,
 }
 
+impl SiteContent {
+    /// Copy of the site without items scheduled after `now`, for listing pages
+    pub fn published(&self, now: DateTime<Utc>) -> SiteContent {
+        let categories = self
+            .categories
+            .iter()
+            .map(|category| Category {
+                items: category
+                    .items
+                    .iter()
+                    .filter(|item| !item.is_scheduled(now))
+                    .cloned()
+                    .collect(),
+                ..category.clone()
+            })
+            .collect();
+        SiteContent { categories }
+    }
+}
+
+/// Identical attachment files found in several item directories
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub stored_name: String,
+    pub first_path: PathBuf,
+    pub copies: Vec<PathBuf>,
+    pub file_size: usize,
+}
+
+/// First occurrence of an attachment content hash during a scan
+struct SeenAttachment {
+    new_name: String,
+    path: PathBuf,
+    file_size: usize,
+    copies: Vec<PathBuf>,
+}
+
+/// Markdown link to a page of the site that does not exist
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenLink {
+    pub source_file: PathBuf,
+    pub url: String,
+    pub line: usize, // 1-based line in the source file
+}
+
+/// Single-segment paths served by routes rather than compiled pages
+const ROUTE_PATHS: &[&str] = &["search", "tags", "robots.txt", "health"];
+
+/// Size and mtime of a file when it was last hashed; while both are unchanged
+/// the hash of that same path is reused instead of reading the file again
+type Fingerprint = (u64, SystemTime);
+
 pub struct Scanner {
     content_dir: PathBuf,
+    /// content hash -> first attachment with that content (per scan)
+    seen_attachments: RefCell<HashMap<String, SeenAttachment>>,
+    /// path -> fingerprint and content hash, kept across scans so unchanged
+    /// files are not read again
+    fingerprints: RefCell<HashMap<PathBuf, (Fingerprint, String)>>,
+    /// number of attachment files read from disk during the last scan
+    files_read: Cell<usize>,
+    /// largest accepted attachment in bytes
+    max_attachment_size: Option<u64>,
+    /// markdown extensions and code highlighting used when rendering items
+    markdown: MarkdownConfig,
+    /// publish items dated in the future right away instead of scheduling them
+    future_posts: bool,
+    /// timezone for dates written without an offset
+    timezone: Tz,
+    /// include items marked `draft: true`
+    include_drafts: bool,
+    /// (source file, url, line) of every `/`-rooted markdown link (per scan)
+    internal_links: RefCell<Vec<(PathBuf, String, usize)>>,
 }
 
 impl Scanner {
     pub fn new(content_dir: PathBuf) -> Self {
-        Self { content_dir }
+        Self {
+            content_dir,
+            seen_attachments: RefCell::new(HashMap::new()),
+            fingerprints: RefCell::new(HashMap::new()),
+            files_read: Cell::new(0),
+            max_attachment_size: None,
+            markdown: MarkdownConfig {
+                highlight: false,
+                ..MarkdownConfig::default()
+            },
+            future_posts: true,
+            timezone: Tz::UTC,
+            include_drafts: false,
+            internal_links: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Markdown extensions and code highlighting for item content
+    pub fn with_markdown(mut self, markdown: MarkdownConfig) -> Self {
+        self.markdown = markdown;
+        self
+    }
+
+    /// Schedule items dated in the future for that date unless `future_posts`
+    /// is set; dates without an offset are read in `timezone`
+    pub fn with_publish_dates(mut self, future_posts: bool, timezone: Tz) -> Self {
+        self.future_posts = future_posts;
+        self.timezone = timezone;
+        self
+    }
+
+    /// Also return items marked `draft: true`, which are skipped by default
+    pub fn with_drafts(mut self, include_drafts: bool) -> Self {
+        self.include_drafts = include_drafts;
+        self
+    }
+
+    /// Reject attachments larger than `bytes` during scanning
+    pub fn with_max_attachment_size(mut self, bytes: u64) -> Self {
+        self.max_attachment_size = Some(bytes);
+        self
+    }
+
+    /// Number of attachment files read from disk during the last scan
+    pub fn files_read(&self) -> usize {
+        self.files_read.get()
+    }
+
+    /// Attachments whose content appears in more than one place in the last scan
+    pub fn duplicate_groups(&self) -> Vec<DuplicateGroup> {
+        let mut groups: Vec<DuplicateGroup> = self
+            .seen_attachments
+            .borrow()
+            .iter()
+            .filter(|(_, seen)| !seen.copies.is_empty())
+            .map(|(hash, seen)| DuplicateGroup {
+                content_hash: hash.clone(),
+                stored_name: seen.new_name.clone(),
+                first_path: seen.path.clone(),
+                copies: seen.copies.clone(),
+                file_size: seen.file_size,
+            })
+            .collect();
+        groups.sort_by(|a, b| a.first_path.cmp(&b.first_path));
+        groups
     }
 
     /// Scan the content directory and build the site structure
-    pub fn scan(&self) -> Result<SiteContent, Box<dyn std::error::Error>> {
+    pub fn scan(&self) -> Result<SiteContent, BlogError> {
+        self.scan_tree(true, None)
+    }
+
+    /// Scan every category for its item listing only; attachments are not read
+    pub fn scan_listing(&self) -> Result<SiteContent, BlogError> {
+        self.scan_tree(false, None)
+    }
+
+    /// Scan only what was modified after `since`. A category is returned when
+    /// its directory, its `index.md` or any of its item directories changed,
+    /// and holds just the items whose directory has a file modified since then.
+    pub fn scan_incremental(&self, since: DateTime<Utc>) -> Result<SiteContent, BlogError> {
+        self.scan_tree(true, Some(since))
+    }
+
+    fn scan_tree(
+        &self,
+        read_attachments: bool,
+        changed_since: Option<DateTime<Utc>>,
+    ) -> Result<SiteContent, BlogError> {
         let mut categories = Vec::new();
 
+        self.reset_scan_state();
+
         // Check if content directory exists
         if !self.content_dir.exists() {
             log::warn!("Content directory does not exist: {:?}", self.content_dir);
@@ -77,7 +311,7 @@ impl Scanner {
             let category_name = path
                 .file_name()
                 .and_then(|n| n.to_str())
-                .ok_or("Invalid category name")?;
+                .ok_or_else(|| BlogError::Validation("Invalid category name".to_string()))?;
 
             // Skip hidden directories
             if category_name.starts_with('.') {
@@ -85,7 +319,14 @@ impl Scanner {
             }
 
             // Scan this category
-            if let Some(category) = self.scan_category(&path, category_name)? {
+            let Some(category) = self.scan_category(&path, category_name, read_attachments, changed_since)? else {
+                continue;
+            };
+            // Items were added, removed or renamed, or the index changed
+            let category_changed = changed_since.map_or(true, |since| {
+                modified_after(&path, since) || modified_after(&category.index_path, since)
+            });
+            if category_changed || !category.items.is_empty() {
                 categories.push(category);
             }
         }
@@ -96,24 +337,106 @@ impl Scanner {
         Ok(SiteContent { categories })
     }
 
+    /// Scan a single item directory (`content_dir/{category}/{dir_name}`)
+    pub fn scan_item_dir(
+        &self,
+        category_name: &str,
+        dir_name: &str,
+    ) -> Result<Option<ContentItem>, BlogError> {
+        self.reset_scan_state();
+
+        let item_dir = self.content_dir.join(category_name).join(dir_name);
+        if !item_dir.is_dir() {
+            return Ok(None);
+        }
+
+        // Prefer `{dir_name}.md`, otherwise the first markdown file in the directory
+        let mut md_path = item_dir.join(format!("{}.md", dir_name));
+        if !md_path.exists() {
+            let first = std::fs::read_dir(&item_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .find(|path| path.extension().and_then(|e| e.to_str()) == Some("md"));
+            match first {
+                Some(path) => md_path = path,
+                None => return Ok(None),
+            }
+        }
+
+        let item_name = md_path
+            .file_stem()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| BlogError::Validation("Invalid item name".to_string()))?;
+        self.scan_item(&md_path, category_name, dir_name, item_name, &item_dir, true)
+    }
+
+    /// Scan a category for its item listing only; attachments are not read
+    pub fn scan_category_listing(
+        &self,
+        category_name: &str,
+    ) -> Result<Option<Category>, BlogError> {
+        let category_path = self.content_dir.join(category_name);
+        if !category_path.is_dir() {
+            return Ok(None);
+        }
+        self.scan_category(&category_path, category_name, false, None)
+    }
+
+    /// Internal links found in the last scan that point at no category or
+    /// item of `site_content`. Only single-segment paths are checked, since
+    /// deeper paths (`/tags/...`, `/attachment/...`) belong to routes.
+    pub fn check_broken_links(&self, site_content: &SiteContent) -> Vec<BrokenLink> {
+        let mut known: HashSet<&str> = ROUTE_PATHS.iter().copied().collect();
+        for category in &site_content.categories {
+            known.insert(&category.url);
+            known.extend(category.items.iter().map(|item| item.url.as_str()));
+        }
+
+        self.internal_links
+            .borrow()
+            .iter()
+            .filter(|(_, url, _)| {
+                let path = url.split(['#', '?']).next().unwrap_or("");
+                let path = path.trim_matches('/');
+                !path.is_empty() && !path.contains('/') && !known.contains(path)
+            })
+            .map(|(source_file, url, line)| BrokenLink {
+                source_file: source_file.clone(),
+                url: url.clone(),
+                line: *line,
+            })
+            .collect()
+    }
+
+    /// Duplicate and link tracking is per scan
+    fn reset_scan_state(&self) {
+        self.seen_attachments.borrow_mut().clear();
+        self.files_read.set(0);
+        self.internal_links.borrow_mut().clear();
+    }
+
     fn scan_category(
         &self,
         category_path: &Path,
         category_name: &str,
-    ) -> Result<Option<Category>, Box<dyn std::error::Error>> {
+        read_attachments: bool,
+        changed_since: Option<DateTime<Utc>>,
+    ) -> Result<Option<Category>, BlogError> {
         let index_path = category_path.join("index.md");
         let mut items = Vec::new();
         let mut description = None;
+        let mut title = None;
 
         // Parse index.md if exists
         if index_path.exists() {
             let content = std::fs::read_to_string(&index_path)?;
             if let Ok(parsed) = parse_markdown(&content) {
                 description = parsed.frontmatter.description.clone();
+                title = parsed.frontmatter.title.clone();
             }
         }
 
-        // Scan subdirectories for items
+        // Collect the item markdown files first, then parse them in parallel
+        let mut files: Vec<ItemFile> = Vec::new();
         for entry in std::fs::read_dir(category_path)? {
             let entry = entry?;
             let path = entry.path();
@@ -126,13 +449,24 @@ impl Scanner {
             let dir_name = path
                 .file_name()
                 .and_then(|n| n.to_str())
-                .ok_or("Invalid item name")?;
+                .ok_or_else(|| BlogError::Validation("Invalid item name".to_string()))?;
 
             // Skip hidden directories and index files
             if dir_name.starts_with('.') || dir_name == "attachment" {
                 continue;
             }
 
+            // Incremental scans skip items with nothing modified inside
+            if let Some(since) = changed_since {
+                let changed = walkdir::WalkDir::new(&path)
+                    .into_iter()
+                    .filter_map(|entry| entry.ok())
+                    .any(|entry| modified_after(entry.path(), since));
+                if !changed {
+                    continue;
+                }
+            }
+
             // Try to find any markdown file in the directory
             for entry in std::fs::read_dir(&path)? {
                 let entry = entry?;
@@ -147,13 +481,23 @@ impl Scanner {
                 let item_name = md_path
                     .file_stem()
                     .and_then(|n| n.to_str())
-                    .ok_or("Invalid item name")?;
+                    .ok_or_else(|| BlogError::Validation("Invalid item name".to_string()))?;
 
-                if let Some(item) =
-                    self.scan_item(&md_path, category_name, dir_name, item_name, &path)?
-                {
-                    items.push(item);
-                }
+                files.push(ItemFile {
+                    md_path: md_path.clone(),
+                    dir_name: dir_name.to_string(),
+                    item_name: item_name.to_string(),
+                    item_dir: path.clone(),
+                });
+            }
+        }
+
+        // Attachments are scanned in directory order so duplicates resolve the same way every time
+        let markdown = &self.markdown;
+        let parsed: Vec<_> = files.par_iter().map(|file| parse_item_file(&file.md_path, markdown)).collect();
+        for (file, parsed) in files.iter().zip(parsed) {
+            if let Some(item) = self.build_item(file, parsed?, category_name, read_attachments)? {
+                items.push(item);
             }
         }
 
@@ -170,8 +514,19 @@ impl Scanner {
             (None, None) => a.item_name.cmp(&b.item_name),
         });
 
+        if slugify(category_name) != category_name {
+            log::warn!(
+                "Category directory '{}' is not a URL slug; `client migrate-slugs` can rename it",
+                category_name
+            );
+        }
+
         Ok(Some(Category {
             name: category_name.to_string(),
+            title: title
+                .map(|title| title.trim().to_string())
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| category_name.to_string()),
             url: category_name.to_string(),
             index_path,
             items,
@@ -186,22 +541,64 @@ impl Scanner {
         dir_name: &str,
         item_name: &str,
         item_dir: &Path,
-    ) -> Result<Option<ContentItem>, Box<dyn std::error::Error>> {
-        let content = std::fs::read_to_string(md_path)?;
-        let parsed = parse_markdown(&content)?;
+        read_attachments: bool,
+    ) -> Result<Option<ContentItem>, BlogError> {
+        let parsed = parse_item_file(md_path, &self.markdown)?;
+        let file = ItemFile {
+            md_path: md_path.to_path_buf(),
+            dir_name: dir_name.to_string(),
+            item_name: item_name.to_string(),
+            item_dir: item_dir.to_path_buf(),
+        };
+        self.build_item(&file, parsed, category_name, read_attachments)
+    }
+
+    /// Turn a parsed item file into a content item, reading its attachments
+    fn build_item(
+        &self,
+        file: &ItemFile,
+        (content, parsed): (String, ParsedMarkdown),
+        category_name: &str,
+        read_attachments: bool,
+    ) -> Result<Option<ContentItem>, BlogError> {
+        let ItemFile { md_path, dir_name, item_name, item_dir } = file;
+        if parsed.frontmatter.draft && !self.include_drafts {
+            log::debug!("Skipping draft {}", md_path.display());
+            return Ok(None);
+        }
+
+        self.internal_links.borrow_mut().extend(
+            internal_links(&content)
+                .into_iter()
+                .map(|(url, line)| (md_path.to_path_buf(), url, line)),
+        );
 
         // Scan attachments
-        let attachments = self.scan_attachments(item_dir)?;
+        let attachments = if read_attachments {
+            self.scan_attachments(item_dir)?
+        } else {
+            Vec::new()
+        };
 
-        // Generate URL: category-itemname (use item_name for URL)
-        let url = format!("{}-{}", category_name, item_name);
+        let url = item_url(category_name, item_name, parsed.frontmatter.slug.as_deref());
 
         // Extract title
         let title = parsed.get_title();
 
+        let excerpt = extract_excerpt(&parsed);
+
         // Format date if available
         let date = parsed.get_date().map(|d| d.format("%Y-%m-%d").to_string());
 
+        // An explicit publish_at wins; otherwise the post goes live on its date
+        let publish_at = parsed.frontmatter.publish_at.or_else(|| {
+            if self.future_posts {
+                None
+            } else {
+                parsed.get_publish_time(self.timezone)
+            }
+        });
+
         Ok(Some(ContentItem {
             category: category_name.to_string(),
             item_name: item_name.to_string(),
@@ -212,9 +609,21 @@ impl Scanner {
             date,
             author: parsed.frontmatter.author.clone(),
             description: parsed.frontmatter.description.clone(),
+            excerpt,
             html_content: parsed.html_content,
             attachments,
             tags: parsed.frontmatter.tags.clone().unwrap_or_default(),
+            publish_at,
+            series: parsed
+                .frontmatter
+                .series
+                .as_deref()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string),
+            series_order: parsed.frontmatter.series_order,
+            cover: parsed.frontmatter.cover.clone(),
+            raw_content: parsed.raw_content,
         }))
     }
 
@@ -222,7 +631,7 @@ impl Scanner {
     fn scan_attachments(
         &self,
         item_dir: &Path,
-    ) -> Result<Vec<Attachment>, Box<dyn std::error::Error>> {
+    ) -> Result<Vec<Attachment>, BlogError> {
         let attachment_dir = item_dir.join("attachment");
 
         if !attachment_dir.exists() || !attachment_dir.is_dir() {
@@ -231,6 +640,7 @@ impl Scanner {
 
         let mut attachments = Vec::new();
         let mut counter: usize = 0;
+        let optimized = crate::images::read_manifest(&attachment_dir);
 
         // Track used names to avoid duplicates
         let mut used_names = std::collections::HashSet::new();
@@ -247,7 +657,7 @@ impl Scanner {
             let file_name = path
                 .file_name()
                 .and_then(|n| n.to_str())
-                .ok_or("Invalid file name")?;
+                .ok_or_else(|| BlogError::Validation("Invalid file name".to_string()))?;
 
             // Skip hidden files
             if file_name.starts_with('.') {
@@ -277,19 +687,83 @@ impl Scanner {
             }
             used_names.insert(new_name.clone());
 
-            // Read file content
-            let file_data = std::fs::read(&path)?;
-            let file_size = file_data.len();
-            let mime_type = get_mime_type(&extension);
+            let metadata = entry.metadata()?;
+            if let Some(max) = self.max_attachment_size {
+                if metadata.len() > max {
+                    return Err(BlogError::Validation(format!(
+                        "Attachment '{}' is {} bytes, exceeding the configured max_attachment_size_mb ({} bytes)",
+                        path.display(),
+                        metadata.len(),
+                        max
+                    )));
+                }
+            }
+            let file_size = metadata.len() as usize;
+            // Files are identified by content, so every file is hashed once; the
+            // fingerprint only spares re-reading a path that has not changed
+            let fingerprint = metadata.modified().ok().map(|mtime| (metadata.len(), mtime));
+            let known_hash = fingerprint.and_then(|fp| {
+                self.fingerprints
+                    .borrow()
+                    .get(&path)
+                    .filter(|(known, _)| *known == fp)
+                    .map(|(_, hash)| hash.clone())
+            });
+
+            let content_hash = match known_hash {
+                Some(hash) => hash,
+                None => {
+                    let hash = hash_file(&path)?;
+                    self.files_read.set(self.files_read.get() + 1);
+                    if let Some(fp) = fingerprint {
+                        self.fingerprints.borrow_mut().insert(path.clone(), (fp, hash.clone()));
+                    }
+                    hash
+                }
+            };
+
+            // Subsequent copies reference the attachment stored first
+            let mut seen_attachments = self.seen_attachments.borrow_mut();
+            let duplicate_of = match seen_attachments.get_mut(&content_hash) {
+                Some(seen) => {
+                    seen.copies.push(path.clone());
+                    Some(seen.new_name.clone())
+                }
+                None => {
+                    seen_attachments.insert(
+                        content_hash.clone(),
+                        SeenAttachment {
+                            new_name: new_name.clone(),
+                            path: path.clone(),
+                            file_size,
+                            copies: Vec::new(),
+                        },
+                    );
+                    None
+                }
+            };
+            let mut mime_type = get_mime_type(&extension);
+            // Types this list lacks are identified by extension or leading bytes
+            if mime_type == "application/octet-stream" {
+                mime_type = crate::storage::detect_mime_type(&path).to_string();
+            }
+            // Ignore stale records of files replaced since the upload
+            let optimized = optimized
+                .get(file_name)
+                .filter(|record| record.optimized_size == file_size);
 
             attachments.push(Attachment {
                 original_name: file_name.to_string(),
                 new_name: new_name.clone(),
                 file_type: extension,
                 path: format!("attachment/{}", new_name),
-                file_data,
+                source_path: path.clone(),
                 file_size,
                 mime_type,
+                content_hash,
+                duplicate_of,
+                was_optimized: optimized.is_some(),
+                original_size: optimized.map_or(file_size, |record| record.original_size),
             });
         }
 
@@ -297,8 +771,78 @@ impl Scanner {
     }
 }
 
+/// An item's markdown file and the directory it sits in
+struct ItemFile {
+    md_path: PathBuf,
+    dir_name: String,
+    item_name: String, // File stem of the markdown file
+    item_dir: PathBuf,
+}
+
+/// Read and parse an item's markdown file; independent of scanner state so
+/// files can be parsed on several threads
+fn parse_item_file(md_path: &Path, markdown: &MarkdownConfig) -> Result<(String, ParsedMarkdown), BlogError> {
+    let content = std::fs::read_to_string(md_path)?;
+    let parsed = parse_markdown_with_options(&content, markdown)
+        .map_err(|e| BlogError::Markdown(format!("{}: {}", md_path.display(), e)))?;
+    Ok((content, parsed))
+}
+
+/// URL of an item: `{category}-{name}`, where the name is the markdown file
+/// stem unless the frontmatter sets `slug:`
+pub fn item_url(category: &str, file_stem: &str, slug_override: Option<&str>) -> String {
+    match slug_override.map(str::trim).filter(|slug| !slug.is_empty()) {
+        Some(slug) => format!("{}-{}", category, slugify(slug)),
+        None => format!("{}-{}", category, file_stem),
+    }
+}
+
+/// `[text](/url)` links in markdown with their 1-based line, skipping fenced
+/// code blocks and protocol-relative `//host` URLs
+fn internal_links(markdown: &str) -> Vec<(String, usize)> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in markdown.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            rest = &rest[start + 2..];
+            let end = rest.find(')').unwrap_or(rest.len());
+            // Drop an optional title: [text](/url "title")
+            let url = rest[..end].split_whitespace().next().unwrap_or("");
+            if url.starts_with('/') && !url.starts_with("//") {
+                links.push((url.to_string(), index + 1));
+            }
+            rest = &rest[end..];
+        }
+    }
+    links
+}
+
+/// Whether `path` exists and was last modified after `since`
+fn modified_after(path: &Path, since: DateTime<Utc>) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| DateTime::<Utc>::from(modified) > since)
+        .unwrap_or(false)
+}
+
+/// Hex-encoded SHA-256 of a file, read in chunks rather than loaded whole
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 /// Get MIME type based on file extension
-fn get_mime_type(extension: &str) -> String {
+pub fn get_mime_type(extension: &str) -> String {
     match extension.to_lowercase().as_str() {
         "jpg" | "jpeg" => "image/jpeg".to_string(),
         "png" => "image/png".to_string(),
@@ -323,6 +867,276 @@ mod tests {
 
     #[test]
     fn test_url_generation() {
-        assert_eq!(format!("{}-{}", "grape", "tizi"), "grape-tizi");
+        assert_eq!(item_url("grape", "tizi", None), "grape-tizi");
+        assert_eq!(item_url("grape", "tizi", Some("  ")), "grape-tizi");
+        // A frontmatter slug replaces the file name and is normalized
+        assert_eq!(item_url("grape", "tizi", Some("Red Globe 提子")), "grape-red-globe-ti-zi");
+    }
+
+    #[test]
+    fn test_duplicate_attachments_stored_once() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_duplicates");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        let category_dir = content_dir.join("tutorials");
+        let image = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+
+        for i in 0..50 {
+            let item_name = format!("part{}", i);
+            let attachment_dir = category_dir.join(&item_name).join("attachment");
+            std::fs::create_dir_all(&attachment_dir).unwrap();
+            std::fs::write(
+                category_dir.join(&item_name).join(format!("{}.md", item_name)),
+                "# Part\n\n![diagram](./attachment/diagram.png)\n",
+            )
+            .unwrap();
+            let image_path = attachment_dir.join("diagram.png");
+            std::fs::write(&image_path, &image).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&image_path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+
+        let scanner = Scanner::new(content_dir.clone());
+        let site_content = scanner.scan().unwrap();
+        assert_eq!(scanner.files_read(), 50);
+
+        // Unchanged files are not read again by the next scan
+        let site_content_again = scanner.scan().unwrap();
+        assert_eq!(scanner.files_read(), 0);
+        assert_eq!(site_content_again.categories[0].items.len(), 50);
+
+        let groups = scanner.duplicate_groups();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].copies.len(), 49);
+
+        let items = &site_content.categories[0].items;
+        assert_eq!(items.len(), 50);
+        for item in items {
+            let attachment = &item.attachments[0];
+            assert_eq!(attachment.stored_name(), groups[0].stored_name);
+
+            let attachment_map = vec![(
+                attachment.original_name.clone(),
+                attachment.stored_name().to_string(),
+            )];
+            let (html, missing) = crate::markdown::rewrite_attachment_links(&item.html_content, &attachment_map);
+            assert!(html.contains(&format!("src=\"/attachment/{}\"", groups[0].stored_name)));
+            assert!(missing.is_empty());
+        }
+
+        std::fs::remove_dir_all(&content_dir).ok();
+    }
+
+    #[test]
+    fn test_same_size_and_mtime_is_not_same_content() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_same_fingerprint");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        let mtime = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for (item_name, content) in [("apple", "red!"), ("pear", "green")] {
+            let item_dir = content_dir.join("fruit").join(item_name);
+            std::fs::create_dir_all(item_dir.join("attachment")).unwrap();
+            std::fs::write(item_dir.join(format!("{}.md", item_name)), format!("# {}\n", item_name)).unwrap();
+            let image_path = item_dir.join("attachment").join("photo.png");
+            // Same name, size and mtime, different bytes
+            std::fs::write(&image_path, format!("{:5}", content)).unwrap();
+            std::fs::File::options().write(true).open(&image_path).unwrap().set_modified(mtime).unwrap();
+        }
+
+        let scanner = Scanner::new(content_dir.clone());
+        let site_content = scanner.scan().unwrap();
+        assert_eq!(scanner.files_read(), 2);
+        assert!(scanner.duplicate_groups().is_empty());
+
+        let items = &site_content.categories[0].items;
+        let (apple, pear) = (&items[0].attachments[0], &items[1].attachments[0]);
+        assert_ne!(apple.content_hash, pear.content_hash);
+        assert_eq!(apple.duplicate_of, None);
+        assert_eq!(pear.duplicate_of, None);
+        assert_ne!(apple.stored_name(), pear.stored_name());
+
+        std::fs::remove_dir_all(&content_dir).ok();
+    }
+
+    #[test]
+    fn test_scan_single_item() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_single_item");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        for item_name in ["apple", "cherry"] {
+            let item_dir = content_dir.join("fruit").join(item_name);
+            std::fs::create_dir_all(item_dir.join("attachment")).unwrap();
+            std::fs::write(item_dir.join(format!("{}.md", item_name)), format!("# {}\n", item_name)).unwrap();
+            std::fs::write(item_dir.join("attachment").join("photo.png"), item_name).unwrap();
+        }
+
+        let scanner = Scanner::new(content_dir.clone());
+        let item = scanner.scan_item_dir("fruit", "cherry").unwrap().unwrap();
+        assert_eq!(item.url, "fruit-cherry");
+        assert_eq!(item.attachments.len(), 1);
+        assert_eq!(scanner.files_read(), 1);
+
+        // The listing has every item but never touches attachments
+        let listing = scanner.scan_category_listing("fruit").unwrap().unwrap();
+        assert_eq!(listing.items.len(), 2);
+        assert!(listing.items.iter().all(|i| i.attachments.is_empty()));
+        assert_eq!(scanner.files_read(), 1);
+
+        assert!(scanner.scan_item_dir("fruit", "plum").unwrap().is_none());
+
+        std::fs::remove_dir_all(&content_dir).ok();
+    }
+
+    #[test]
+    fn test_scan_incremental() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_incremental");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        let old = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let set_old = |path: &Path| {
+            std::fs::File::open(path).unwrap().set_modified(old).unwrap();
+        };
+        for (category, item) in [("fruit", "apple"), ("fruit", "cherry"), ("veg", "leek")] {
+            let item_dir = content_dir.join(category).join(item);
+            std::fs::create_dir_all(item_dir.join("attachment")).unwrap();
+            std::fs::write(item_dir.join(format!("{}.md", item)), format!("# {}\n", item)).unwrap();
+            std::fs::write(item_dir.join("attachment/photo.png"), item).unwrap();
+        }
+        std::fs::write(content_dir.join("veg/index.md"), "---\ntitle: Vegetables\n---\n").unwrap();
+        for entry in walkdir::WalkDir::new(&content_dir).contents_first(true) {
+            set_old(entry.unwrap().path());
+        }
+        let since = DateTime::<Utc>::from(old + std::time::Duration::from_secs(60));
+
+        let scanner = Scanner::new(content_dir.clone());
+        assert!(scanner.scan_incremental(since).unwrap().categories.is_empty());
+
+        // An attachment edited in place marks only its own item
+        std::fs::write(content_dir.join("fruit/cherry/attachment/photo.png"), "new").unwrap();
+        let changed = scanner.scan_incremental(since).unwrap();
+        assert_eq!(changed.categories.len(), 1);
+        let urls: Vec<&str> = changed.categories[0].items.iter().map(|i| i.url.as_str()).collect();
+        assert_eq!(urls, vec!["fruit-cherry"]);
+        assert_eq!(changed.categories[0].items[0].attachments.len(), 1);
+
+        // A changed index.md rescans the category even with no item changed
+        std::fs::write(content_dir.join("veg/index.md"), "---\ntitle: Greens\n---\n").unwrap();
+        let changed = scanner.scan_incremental(since).unwrap();
+        let veg = changed.categories.iter().find(|c| c.name == "veg").unwrap();
+        assert_eq!(veg.title, "Greens");
+        assert!(veg.items.is_empty());
+
+        // A full scan still sees everything
+        assert_eq!(scanner.scan().unwrap().categories.iter().map(|c| c.items.len()).sum::<usize>(), 3);
+
+        std::fs::remove_dir_all(&content_dir).ok();
+    }
+
+    #[test]
+    fn test_check_broken_links() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_broken_links");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        let apple = "---\ntitle: Apple\n---\n\nSee [cherry](/fruit-cherry) and [fruit](/fruit/ \"All\").\n\
+            Gone: [plum](/fruit-plum#top), [tag](/tags/red), [site](https://example.com/x), [cdn](//cdn.example.com/a.js)\n\
+            ```\n[in code](/nowhere)\n```\n[search](/search?q=apple) [draft](/fruit-durian)\n";
+        for (name, body) in [("apple", apple), ("cherry", "# Cherry\n"), ("durian", "---\ndraft: true\n---\n")] {
+            let item_dir = content_dir.join("fruit").join(name);
+            std::fs::create_dir_all(&item_dir).unwrap();
+            std::fs::write(item_dir.join(format!("{}.md", name)), body).unwrap();
+        }
+
+        let scanner = Scanner::new(content_dir.clone());
+        let site_content = scanner.scan().unwrap();
+        let broken = scanner.check_broken_links(&site_content);
+        let found: Vec<(&str, usize)> = broken.iter().map(|link| (link.url.as_str(), link.line)).collect();
+        assert_eq!(found, vec![("/fruit-plum#top", 6), ("/fruit-durian", 10)]);
+        assert_eq!(broken[0].source_file, content_dir.join("fruit/apple/apple.md"));
+
+        std::fs::remove_dir_all(&content_dir).ok();
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_category_neighbours() {
+        let item = |url: &str| ContentItem {
+            category: "fruit".to_string(),
+            item_name: url.to_string(),
+            dir_name: url.to_string(),
+            url: url.to_string(),
+            file_path: PathBuf::new(),
+            title: url.to_uppercase(),
+            date: None,
+            author: None,
+            description: None,
+            excerpt: None,
+            html_content: String::new(),
+            attachments: Vec::new(),
+            tags: Vec::new(),
+            publish_at: None,
+            raw_content: String::new(),
+            series: None,
+            series_order: None,
+            cover: None,
+        };
+        // Newest first
+        let category = Category {
+            name: "fruit".to_string(),
+            title: "Fruit".to_string(),
+            url: "fruit".to_string(),
+            index_path: PathBuf::new(),
+            items: vec![item("c"), item("b"), item("a")],
+            description: None,
+        };
+
+        let (prev, next) = category.neighbours("b");
+        assert_eq!(prev.unwrap().url, "a");
+        assert_eq!(next.unwrap().title, "C");
+
+        assert_eq!(category.neighbours("c").1, None);
+        assert_eq!(category.neighbours("a").0, None);
+        assert_eq!(category.neighbours("missing"), (None, None));
+    }
+
+    #[test]
+    fn test_future_dated_items_are_scheduled() {
+        let content_dir = PathBuf::from("/tmp/test_scanner_future_dates");
+        let _ = std::fs::remove_dir_all(&content_dir);
+
+        for (item_name, date) in [("old", "2020-01-01"), ("new", "2999-12-01")] {
+            let item_dir = content_dir.join("news").join(item_name);
+            std::fs::create_dir_all(&item_dir).unwrap();
+            std::fs::write(
+                item_dir.join(format!("{}.md", item_name)),
+                format!("---\ndate: {}\n---\n\nPosted on {}.\n", date, date),
+            )
+            .unwrap();
+        }
+
+        let now = Utc::now();
+        let tz: Tz = "Asia/Tokyo".parse().unwrap();
+        let site = Scanner::new(content_dir.clone())
+            .with_publish_dates(false, tz)
+            .scan()
+            .unwrap();
+        let published = site.published(now);
+        let names: Vec<_> = published.categories[0].items.iter().map(|i| i.item_name.as_str()).collect();
+        assert_eq!(names, vec!["old"]);
+
+        let new = site.categories[0].items.iter().find(|i| i.item_name == "new").unwrap();
+        assert_eq!(new.publish_at.unwrap().to_rfc3339(), "2999-11-30T15:00:00+00:00");
+
+        // future_posts publishes everything immediately
+        let site = Scanner::new(content_dir.clone())
+            .with_publish_dates(true, tz)
+            .scan()
+            .unwrap();
+        assert_eq!(site.published(now).categories[0].items.len(), 2);
+
+        std::fs::remove_dir_all(&content_dir).ok();
+    }
+}
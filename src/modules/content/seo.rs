@@ -0,0 +1,284 @@
+use crate::config::SeoConfig;
+use crate::scanner::{Category, ContentItem};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Build a schema.org `BlogPosting` JSON-LD document for an article;
+/// `site_title` (`site.title`) names the publisher
+pub fn render_json_ld(item: &ContentItem, base_url: &str, site_title: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    let publisher = site_name(site_title, base_url);
+    let url = format!("{}/{}", base_url, item.url);
+
+    let date_modified = std::fs::metadata(&item.file_path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339())
+        .or_else(|| item.date.clone());
+
+    let mut doc = json!({
+        "@context": "https://schema.org",
+        "@type": "BlogPosting",
+        "headline": item.title,
+        "url": url,
+        "mainEntityOfPage": { "@type": "WebPage", "@id": url },
+        "author": {
+            "@type": "Person",
+            "name": item.author.clone().unwrap_or_else(|| publisher.clone()),
+        },
+        "publisher": {
+            "@type": "Organization",
+            "name": publisher,
+            "url": base_url,
+        },
+    });
+
+    if let Some(date) = &item.date {
+        doc["datePublished"] = json!(date);
+    }
+    if let Some(date) = date_modified {
+        doc["dateModified"] = json!(date);
+    }
+    if let Some(description) = &item.description {
+        doc["description"] = json!(description);
+    }
+    if !item.tags.is_empty() {
+        doc["keywords"] = json!(item.tags.join(", "));
+    }
+    if let Some(image) = item
+        .attachments
+        .iter()
+        .find(|a| a.mime_type.starts_with("image/"))
+    {
+        doc["image"] = json!(format!("{}/attachment/{}", base_url, image.stored_name()));
+    }
+
+    to_script_json(&doc)
+}
+
+/// Build a schema.org `CollectionPage` JSON-LD document for a category
+pub fn render_category_json_ld(category: &Category, base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+
+    let parts: Vec<Value> = category
+        .items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            json!({
+                "@type": "ListItem",
+                "position": index + 1,
+                "url": format!("{}/{}", base_url, item.url),
+                "name": item.title,
+            })
+        })
+        .collect();
+
+    let mut doc = json!({
+        "@context": "https://schema.org",
+        "@type": "CollectionPage",
+        "name": category.title,
+        "url": format!("{}/{}", base_url, category.url),
+        "mainEntity": {
+            "@type": "ItemList",
+            "numberOfItems": parts.len(),
+            "itemListElement": parts,
+        },
+    });
+
+    if let Some(description) = &category.description {
+        doc["description"] = json!(description);
+    }
+
+    to_script_json(&doc)
+}
+
+/// Tera function `json_ld(item=..., base_url=..., site_title=...)` /
+/// `json_ld(category=..., base_url=...)`
+pub fn json_ld_function(args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let base_url = args
+        .get("base_url")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let site_title = args
+        .get("site_title")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    if let Some(item) = args.get("item") {
+        let item: ContentItem = serde_json::from_value(item.clone())
+            .map_err(|e| tera::Error::msg(format!("json_ld: invalid item: {}", e)))?;
+        return Ok(Value::String(render_json_ld(&item, base_url, site_title)));
+    }
+
+    if let Some(category) = args.get("category") {
+        let category: Category = serde_json::from_value(category.clone())
+            .map_err(|e| tera::Error::msg(format!("json_ld: invalid category: {}", e)))?;
+        return Ok(Value::String(render_category_json_ld(&category, base_url)));
+    }
+
+    Err(tera::Error::msg("json_ld: expected an `item` or `category` argument"))
+}
+
+/// Build robots.txt: `robots_custom` verbatim when set, otherwise one
+/// `Disallow` line per configured prefix, an optional `Crawl-delay` and the sitemap
+pub fn render_robots_txt(seo: &SeoConfig, base_url: &str) -> String {
+    if let Some(custom) = &seo.robots_custom {
+        return custom.clone();
+    }
+
+    let mut robots = String::from("User-agent: *\n");
+    for path in &seo.robots_disallow {
+        robots.push_str(&format!("Disallow: {}\n", path));
+    }
+    if let Some(delay) = seo.crawl_delay {
+        robots.push_str(&format!("Crawl-delay: {}\n", delay));
+    }
+    robots.push_str(&format!("Sitemap: {}/sitemap.xml\n", base_url.trim_end_matches('/')));
+    robots
+}
+
+/// Site name used for the publisher: the site title, or the host of the base
+/// URL for custom templates that do not pass `site_title`
+fn site_name(site_title: &str, base_url: &str) -> String {
+    let site_title = site_title.trim();
+    if !site_title.is_empty() {
+        return site_title.to_string();
+    }
+    base_url
+        .split("://")
+        .last()
+        .unwrap_or(base_url)
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Serialize for embedding in a `<script>` element without letting content close it
+fn to_script_json(doc: &Value) -> String {
+    doc.to_string().replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample_item() -> ContentItem {
+        ContentItem {
+            category: "grape".to_string(),
+            item_name: "tizi".to_string(),
+            dir_name: "提子".to_string(),
+            url: "grape-tizi".to_string(),
+            file_path: PathBuf::from("/nonexistent/tizi.md"),
+            title: "Grapes </script><b>".to_string(),
+            date: Some("2024-05-01".to_string()),
+            author: Some("lin".to_string()),
+            description: Some("Sweet".to_string()),
+            excerpt: Some("Sweet".to_string()),
+            html_content: String::new(),
+            attachments: Vec::new(),
+            tags: vec!["fruit".to_string()],
+            publish_at: None,
+            raw_content: String::new(),
+            series: None,
+            series_order: None,
+            cover: None,
+        }
+    }
+
+    /// Checks the properties schema.org / Google rich results require for the type
+    fn assert_valid(doc: &Value, expected_type: &str, required: &[&str]) {
+        assert_eq!(doc["@context"], "https://schema.org");
+        assert_eq!(doc["@type"], expected_type);
+        for key in required {
+            assert!(!doc[*key].is_null(), "missing required property `{}`", key);
+        }
+    }
+
+    #[test]
+    fn test_blog_posting_json_ld() {
+        let output = render_json_ld(&sample_item(), "https://example.com/", "Fruit Notes");
+        assert!(!output.contains("</script>"));
+
+        let doc: Value = serde_json::from_str(&output).unwrap();
+        assert_valid(
+            &doc,
+            "BlogPosting",
+            &["headline", "datePublished", "dateModified", "author", "publisher", "url"],
+        );
+        assert_eq!(doc["headline"], "Grapes </script><b>");
+        assert_eq!(doc["url"], "https://example.com/grape-tizi");
+        assert_eq!(doc["author"]["@type"], "Person");
+        assert_eq!(doc["author"]["name"], "lin");
+        assert_eq!(doc["publisher"]["@type"], "Organization");
+        assert_eq!(doc["publisher"]["name"], "Fruit Notes");
+        // No file on disk: modification date falls back to the publish date
+        assert_eq!(doc["dateModified"], "2024-05-01");
+    }
+
+    #[test]
+    fn test_collection_page_json_ld() {
+        let category = Category {
+            name: "grape".to_string(),
+            title: "Grapes".to_string(),
+            url: "grape".to_string(),
+            index_path: PathBuf::from("content/grape/index.md"),
+            items: vec![sample_item()],
+            description: None,
+        };
+
+        let doc: Value =
+            serde_json::from_str(&render_category_json_ld(&category, "https://example.com")).unwrap();
+        assert_valid(&doc, "CollectionPage", &["name", "url", "mainEntity"]);
+        assert_eq!(doc["mainEntity"]["numberOfItems"], 1);
+        assert_eq!(doc["mainEntity"]["itemListElement"][0]["position"], 1);
+        assert_eq!(
+            doc["mainEntity"]["itemListElement"][0]["url"],
+            "https://example.com/grape-tizi"
+        );
+        assert_eq!(doc["name"], "Grapes");
+    }
+
+    #[test]
+    fn test_render_robots_txt() {
+        let mut seo = SeoConfig::default();
+        assert_eq!(
+            render_robots_txt(&seo, "https://example.com/"),
+            "User-agent: *\nDisallow: /admin\nDisallow: /api\nSitemap: https://example.com/sitemap.xml\n"
+        );
+
+        seo.robots_disallow = Vec::new();
+        seo.crawl_delay = Some(10);
+        assert_eq!(
+            render_robots_txt(&seo, "https://example.com"),
+            "User-agent: *\nCrawl-delay: 10\nSitemap: https://example.com/sitemap.xml\n"
+        );
+
+        seo.robots_custom = Some("User-agent: *\nDisallow: /\n".to_string());
+        assert_eq!(render_robots_txt(&seo, "https://example.com"), "User-agent: *\nDisallow: /\n");
+    }
+
+    #[test]
+    fn test_json_ld_function_requires_argument() {
+        assert!(json_ld_function(&HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_publisher_name_from_site_title() {
+        let mut args = HashMap::new();
+        args.insert("item".to_string(), serde_json::to_value(sample_item()).unwrap());
+        args.insert("base_url".to_string(), json!("https://example.com"));
+        args.insert("site_title".to_string(), json!("Fruit Notes"));
+        let output = json_ld_function(&args).unwrap();
+        let doc: Value = serde_json::from_str(output.as_str().unwrap()).unwrap();
+        assert_eq!(doc["publisher"]["name"], "Fruit Notes");
+
+        // Older custom templates without `site_title` fall back to the host
+        args.remove("site_title");
+        let output = json_ld_function(&args).unwrap();
+        let doc: Value = serde_json::from_str(output.as_str().unwrap()).unwrap();
+        assert_eq!(doc["publisher"]["name"], "example.com");
+    }
+}
@@ -0,0 +1,189 @@
+use crate::scanner::{ContentItem, SiteContent};
+use crate::storage::{Page, PageType};
+use serde::Serialize;
+
+/// An item reduced to what series navigation needs
+#[derive(Debug, Clone)]
+struct SeriesEntry {
+    series: String,
+    order: Option<u32>,
+    title: String,
+    url: String,
+    category: Option<String>,
+}
+
+impl SeriesEntry {
+    fn from_item(item: &ContentItem) -> Option<Self> {
+        Some(SeriesEntry {
+            series: item.series.clone()?,
+            order: item.series_order,
+            title: item.title.clone(),
+            url: item.url.clone(),
+            category: Some(item.category.clone()),
+        })
+    }
+}
+
+/// Series box on an item page: `(title, url)` links to the neighbouring parts
+/// and every part as `(title, url, part number)`, numbered from 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeriesNav {
+    pub series_name: String,
+    pub prev: Option<(String, String)>,
+    pub next: Option<(String, String)>,
+    pub all_parts: Vec<(String, String, u32)>,
+}
+
+/// Published items grouped by the `series` frontmatter field
+#[derive(Debug, Clone, Default)]
+pub struct SeriesIndex {
+    entries: Vec<SeriesEntry>,
+}
+
+impl SeriesIndex {
+    /// Build from stored pages, skipping non-items and scheduled items
+    pub fn from_pages(pages: &[Page]) -> Self {
+        let entries = pages
+            .iter()
+            .filter(|page| page.page_type == PageType::Item && !page.is_scheduled())
+            .filter_map(|page| {
+                Some(SeriesEntry {
+                    series: page.series.clone()?,
+                    order: page.series_order,
+                    title: page.title.clone(),
+                    url: page.slug.clone(),
+                    category: page.category.clone(),
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Build from scanned content
+    pub fn from_site_content(site_content: &SiteContent) -> Self {
+        let entries = site_content
+            .categories
+            .iter()
+            .flat_map(|category| &category.items)
+            .filter_map(SeriesEntry::from_item)
+            .collect();
+        Self { entries }
+    }
+
+    /// Add or replace the entry for `item`
+    pub fn with_item(self, item: &ContentItem) -> Self {
+        let mut index = self.without(&item.url);
+        index.entries.extend(SeriesEntry::from_item(item));
+        index
+    }
+
+    /// Drop the entry for `url`, if any
+    pub fn without(mut self, url: &str) -> Self {
+        self.entries.retain(|entry| entry.url != url);
+        self
+    }
+
+    /// `(url, category)` of every part of `series`, in reading order
+    pub fn parts(&self, series: &str) -> Vec<(String, Option<String>)> {
+        self.sorted(series)
+            .into_iter()
+            .map(|entry| (entry.url.clone(), entry.category.clone()))
+            .collect()
+    }
+
+    /// Navigation for `item`, which is always listed even when the index
+    /// leaves it out (e.g. it is still scheduled)
+    pub fn nav_for(&self, item: &ContentItem) -> Option<SeriesNav> {
+        let own = SeriesEntry::from_item(item)?;
+        let mut parts: Vec<SeriesEntry> = self
+            .sorted(&own.series)
+            .into_iter()
+            .filter(|entry| entry.url != own.url)
+            .cloned()
+            .collect();
+        parts.push(own.clone());
+        parts.sort_by(compare_parts);
+
+        let position = parts.iter().position(|entry| entry.url == own.url)?;
+        let link = |entry: &SeriesEntry| (entry.title.clone(), entry.url.clone());
+        Some(SeriesNav {
+            series_name: own.series,
+            prev: position.checked_sub(1).map(|i| link(&parts[i])),
+            next: parts.get(position + 1).map(link),
+            all_parts: parts
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| (entry.title.clone(), entry.url.clone(), i as u32 + 1))
+                .collect(),
+        })
+    }
+
+    fn sorted(&self, series: &str) -> Vec<&SeriesEntry> {
+        let mut parts: Vec<&SeriesEntry> = self.entries.iter().filter(|entry| entry.series == series).collect();
+        parts.sort_by(|a, b| compare_parts(a, b));
+        parts
+    }
+}
+
+/// Explicit `series_order` first, unnumbered parts after, ties broken by URL
+fn compare_parts(a: &SeriesEntry, b: &SeriesEntry) -> std::cmp::Ordering {
+    a.order
+        .is_none()
+        .cmp(&b.order.is_none())
+        .then_with(|| a.order.cmp(&b.order))
+        .then_with(|| a.url.cmp(&b.url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn item(url: &str, series: Option<&str>, order: Option<u32>) -> ContentItem {
+        ContentItem {
+            category: "rust".to_string(),
+            item_name: url.to_string(),
+            dir_name: url.to_string(),
+            url: url.to_string(),
+            file_path: PathBuf::new(),
+            title: url.to_uppercase(),
+            date: None,
+            author: None,
+            description: None,
+            excerpt: None,
+            html_content: String::new(),
+            attachments: Vec::new(),
+            tags: Vec::new(),
+            publish_at: None,
+            raw_content: String::new(),
+            series: series.map(str::to_string),
+            series_order: order,
+            cover: None,
+        }
+    }
+
+    #[test]
+    fn test_series_nav_order() {
+        let index = SeriesIndex::default()
+            .with_item(&item("b", Some("intro"), Some(2)))
+            .with_item(&item("z", Some("intro"), None))
+            .with_item(&item("a", Some("intro"), Some(1)))
+            .with_item(&item("other", Some("advanced"), Some(1)))
+            .with_item(&item("loose", None, None));
+
+        let nav = index.nav_for(&item("b", Some("intro"), Some(2))).unwrap();
+        assert_eq!(nav.series_name, "intro");
+        assert_eq!(nav.prev, Some(("A".to_string(), "a".to_string())));
+        assert_eq!(nav.next, Some(("Z".to_string(), "z".to_string())));
+        let urls: Vec<_> = nav.all_parts.iter().map(|(_, url, n)| (url.as_str(), *n)).collect();
+        assert_eq!(urls, vec![("a", 1), ("b", 2), ("z", 3)]);
+
+        // An item missing from the index still finds its place
+        let nav = index.nav_for(&item("c", Some("intro"), Some(3))).unwrap();
+        assert_eq!(nav.prev.unwrap().1, "b");
+        assert_eq!(nav.next.unwrap().1, "z");
+
+        assert!(index.nav_for(&item("loose", None, None)).is_none());
+        assert_eq!(index.without("a").parts("intro").len(), 2);
+    }
+}
@@ -0,0 +1,94 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest slug produced by [`slugify`], cut at a word boundary
+const MAX_SLUG_LEN: usize = 80;
+
+/// Turn a title or name into a URL slug: the text is NFKC-normalized (so
+/// fullwidth letters and ligatures become plain ones), CJK becomes pinyin and
+/// other scripts are transliterated to ASCII, letters are lowercased, and any
+/// run of spaces or punctuation becomes a single hyphen. Never returns an
+/// empty string.
+///
+/// "My First Post" -> "my-first-post", "葡萄酒" -> "pu-tao-jiu"
+pub fn slugify(text: &str) -> String {
+    let normalized: String = text.nfkc().collect();
+    let mut slug = String::new();
+    let mut separator = false;
+    for c in deunicode::deunicode(&normalized).chars() {
+        match c {
+            // "Don't" reads better as "dont" than "don-t"
+            '\'' => {}
+            c if c.is_ascii_alphanumeric() => {
+                if separator && !slug.is_empty() {
+                    slug.push('-');
+                }
+                separator = false;
+                slug.push(c.to_ascii_lowercase());
+            }
+            _ => separator = true,
+        }
+    }
+
+    if slug.len() > MAX_SLUG_LEN {
+        slug.truncate(MAX_SLUG_LEN + 1);
+        match slug.rfind('-') {
+            Some(cut) if cut > 0 => slug.truncate(cut),
+            _ => slug.truncate(MAX_SLUG_LEN),
+        }
+    }
+
+    if !slug.is_empty() {
+        return slug;
+    }
+    // Nothing transliterable: hex-encode the UTF-8 bytes so distinct inputs
+    // keep distinct slugs
+    let normalized = normalized.trim();
+    if normalized.is_empty() {
+        return "untitled".to_string();
+    }
+    let mut slug = String::from("page-");
+    for byte in normalized.bytes() {
+        if slug.len() + 2 > MAX_SLUG_LEN {
+            break;
+        }
+        slug.push_str(&format!("{:02x}", byte));
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("My First Post"), "my-first-post");
+        assert_eq!(slugify("葡萄酒"), "pu-tao-jiu");
+        assert_eq!(slugify("葡萄 Grapes 101"), "pu-tao-grapes-101");
+        assert_eq!(slugify("  Rust & Go:  a -- comparison!! "), "rust-go-a-comparison");
+        assert_eq!(slugify("Don't panic"), "dont-panic");
+        assert_eq!(slugify("Crème brûlée"), "creme-brulee");
+        assert_eq!(slugify("snake_case_name"), "snake-case-name");
+        assert_eq!(slugify("grape-tizi"), "grape-tizi");
+        // Other scripts, mixed input and compatibility forms (fullwidth, ligatures)
+        assert_eq!(slugify("東京タワー"), "dong-jing-tawa");
+        assert_eq!(slugify("한국어"), "hangugeo");
+        assert_eq!(slugify("مرحبا بالعالم"), "mrhb-bl-lm");
+        assert_eq!(slugify("Rust 入門 ガイド"), "rust-ru-men-gaido");
+        assert_eq!(slugify("ＡＢＣ１２３"), "abc123");
+        assert_eq!(slugify("ﬁle"), "file");
+
+        let long = slugify(&"word ".repeat(40));
+        assert!(long.len() <= MAX_SLUG_LEN && long.ends_with("word"));
+
+        assert_eq!(slugify(""), "untitled");
+        assert_eq!(slugify("   "), "untitled");
+        assert_eq!(slugify("\u{3000}"), "untitled");
+        // Nothing to transliterate: hex of the normalized UTF-8 bytes
+        assert_eq!(slugify("???"), "page-3f3f3f");
+        assert_eq!(slugify("！！"), "page-2121");
+        assert_ne!(slugify("???"), slugify("!!!"));
+        let many = slugify(&"?".repeat(100));
+        assert!(many.starts_with("page-3f3f") && many.len() <= MAX_SLUG_LEN);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::scanner::{ContentItem, SiteContent};
+use crate::storage::{Page, PageType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+
+/// An item reduced to what tag listings need
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaggedItem {
+    pub slug: String,
+    pub title: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub excerpt: Option<String>,
+}
+
+impl From<&ContentItem> for TaggedItem {
+    fn from(item: &ContentItem) -> Self {
+        TaggedItem {
+            slug: item.url.clone(),
+            title: item.title.clone(),
+            category: Some(item.category.clone()),
+            tags: item.tags.clone(),
+            excerpt: item.excerpt.clone(),
+        }
+    }
+}
+
+/// Number of items carrying a tag
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Tag count with a 0.0–1.0 weight for font-size scaling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightedTag {
+    pub tag: String,
+    pub count: usize,
+    pub weight: f32,
+}
+
+/// Tag index over published items, also exposed to templates as `tag_filter()`:
+/// without arguments it returns all tag counts, with `tag=...` the matching items
+#[derive(Debug, Clone, Default)]
+pub struct TagFilter {
+    items: Vec<TaggedItem>,
+}
+
+impl TagFilter {
+    pub fn new(items: Vec<TaggedItem>) -> Self {
+        Self { items }
+    }
+
+    /// Build from stored pages, skipping non-items and scheduled items
+    pub fn from_pages(pages: &[Page]) -> Self {
+        Self::new(
+            pages
+                .iter()
+                .filter(|page| page.page_type == PageType::Item && !page.is_scheduled())
+                .map(|page| TaggedItem {
+                    slug: page.slug.clone(),
+                    title: page.title.clone(),
+                    category: page.category.clone(),
+                    tags: page.tags.clone(),
+                    excerpt: page.excerpt.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Build from scanned content
+    pub fn from_site_content(site_content: &SiteContent) -> Self {
+        Self::new(
+            site_content
+                .categories
+                .iter()
+                .flat_map(|category| &category.items)
+                .map(TaggedItem::from)
+                .collect(),
+        )
+    }
+
+    /// Add or replace the entry for `item`
+    pub fn with_item(mut self, item: TaggedItem) -> Self {
+        self.items.retain(|existing| existing.slug != item.slug);
+        self.items.push(item);
+        self
+    }
+
+    /// All tags sorted by count (descending), then name
+    pub fn tag_counts(&self) -> Vec<TagCount> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for item in &self.items {
+            // An item listing the same tag twice still counts once
+            let unique: BTreeSet<&str> = item.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()).collect();
+            for tag in unique {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+
+        let mut counts: Vec<TagCount> = counts
+            .into_iter()
+            .map(|(tag, count)| TagCount {
+                tag: tag.to_string(),
+                count,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        counts
+    }
+
+    /// Tag counts with weights normalized between the rarest and most used tag
+    pub fn tag_cloud(&self) -> Vec<WeightedTag> {
+        let counts = self.tag_counts();
+        let max = counts.first().map(|t| t.count).unwrap_or(0);
+        let min = counts.last().map(|t| t.count).unwrap_or(0);
+
+        counts
+            .into_iter()
+            .map(|t| {
+                let weight = if max == min {
+                    1.0
+                } else {
+                    (t.count - min) as f32 / (max - min) as f32
+                };
+                WeightedTag {
+                    tag: t.tag,
+                    count: t.count,
+                    weight,
+                }
+            })
+            .collect()
+    }
+
+    /// Items carrying `tag`, in index order
+    pub fn items_with_tag(&self, tag: &str) -> Vec<&TaggedItem> {
+        let tag = tag.trim();
+        self.items
+            .iter()
+            .filter(|item| item.tags.iter().any(|t| t.trim() == tag))
+            .collect()
+    }
+}
+
+impl tera::Function for TagFilter {
+    fn call(&self, args: &HashMap<String, Value>) -> tera::Result<Value> {
+        let result = match args.get("tag") {
+            Some(tag) => {
+                let tag = tag
+                    .as_str()
+                    .ok_or_else(|| tera::Error::msg("tag_filter: `tag` must be a string"))?;
+                serde_json::to_value(self.items_with_tag(tag))
+            }
+            None => serde_json::to_value(self.tag_counts()),
+        };
+        result.map_err(|e| tera::Error::msg(format!("tag_filter: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(slug: &str, tags: &[&str]) -> TaggedItem {
+        TaggedItem {
+            slug: slug.to_string(),
+            title: slug.to_string(),
+            category: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            excerpt: None,
+        }
+    }
+
+    #[test]
+    fn test_tag_counts_and_cloud() {
+        let filter = TagFilter::new(vec![
+            item("a", &["rust", "web", "rust"]),
+            item("b", &["rust"]),
+            item("c", &["rust", "db", "web"]),
+        ]);
+
+        let counts = filter.tag_counts();
+        assert_eq!(
+            counts.iter().map(|t| (t.tag.as_str(), t.count)).collect::<Vec<_>>(),
+            vec![("rust", 3), ("web", 2), ("db", 1)]
+        );
+
+        let cloud = filter.tag_cloud();
+        assert_eq!(cloud[0].weight, 1.0);
+        assert_eq!(cloud[1].weight, 0.5);
+        assert_eq!(cloud[2].weight, 0.0);
+
+        let slugs: Vec<_> = filter.items_with_tag("web").iter().map(|i| i.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_tag_filter_function() {
+        let filter = TagFilter::new(vec![item("a", &["rust"])]);
+        let mut args = HashMap::new();
+        assert_eq!(tera::Function::call(&filter, &args).unwrap()[0]["count"], 1);
+
+        args.insert("tag".to_string(), Value::from("rust"));
+        assert_eq!(tera::Function::call(&filter, &args).unwrap()[0]["slug"], "a");
+    }
+}
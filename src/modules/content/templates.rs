@@ -1,14 +1,40 @@
+use crate::analytics::PageAnalytics;
+use crate::error::BlogError;
 use crate::config::Config;
-use crate::scanner::{Category, ContentItem, SiteContent};
+use crate::scanner::{Category, ContentItem, ItemLink, SiteContent};
+use crate::series::SeriesNav;
+use crate::storage::AttachmentMetadata;
+use crate::tags::TagFilter;
+use crate::theme::Theme;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
 use tera::{Context, Tera};
 
+/// Where the live templates were loaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateSource {
+    /// The templates compiled into the binary
+    Builtin,
+    /// `paths.templates_dir`
+    Directory,
+}
+
 pub struct TemplateRenderer {
     tera: Tera,
+    source: TemplateSource,
+    /// `template_fingerprint` of the templates directory at load time
+    fingerprint: String,
     config: Config,
+    /// Stylesheet for highlighted code blocks, when highlighting is enabled
+    highlight_css: Option<String>,
+    /// Theme selected by `site.theme`, available to templates as `theme`
+    theme: Theme,
 }
 
 impl TemplateRenderer {
-    pub fn new(config: &Config) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(config: &Config) -> Result<Self, BlogError> {
         // Create templates directory if it doesn't exist
         if !config.paths.templates_dir.exists() {
             std::fs::create_dir_all(&config.paths.templates_dir)?;
@@ -18,36 +44,47 @@ impl TemplateRenderer {
         let pattern = format!("{}/**/*.html", config.paths.templates_dir.display());
         log::debug!("Looking for templates with pattern: {}", pattern);
 
-        let tera = match Tera::new(&pattern) {
+        let (tera, source) = match Tera::new(&pattern) {
             Ok(mut t) => {
                 log::info!("Loaded templates from directory");
                 // Check if any templates were actually loaded
                 if t.get_template_names().count() == 0 {
                     log::warn!("No templates found in directory, using built-in templates");
-                    t = Self::create_builtin_templates()?;
+                    (Self::create_builtin_templates()?, TemplateSource::Builtin)
+                } else {
+                    t.register_function("json_ld", crate::seo::json_ld_function);
+                    t.register_function("tag_filter", TagFilter::default());
+                    t.register_function("t", crate::i18n::t_function);
+                    (t, TemplateSource::Directory)
                 }
-                t
             },
             Err(e) => {
                 log::warn!("Failed to load templates: {}", e);
                 log::info!("Using built-in templates");
-                Self::create_builtin_templates()?
+                (Self::create_builtin_templates()?, TemplateSource::Builtin)
             }
         };
 
         Ok(Self {
             tera,
+            source,
+            fingerprint: template_fingerprint(&config.paths.templates_dir),
             config: config.clone(),
+            highlight_css: config.markdown.highlight.then(crate::highlight::highlight_css),
+            theme: Theme::from_config(config),
         })
     }
 
-    fn create_builtin_templates() -> Result<Tera, Box<dyn std::error::Error>> {
+    fn create_builtin_templates() -> Result<Tera, BlogError> {
         let mut tera = Tera::default();
+        tera.register_function("json_ld", crate::seo::json_ld_function);
+        tera.register_function("tag_filter", TagFilter::default());
+        tera.register_function("t", crate::i18n::t_function);
 
         // Base template with lazy loading, KaTeX, and i18n support
         let base_template = [
             r#"<!DOCTYPE html>
-<html lang="zh-CN">
+<html lang="{{ t(key="meta.lang") }}">
 <head>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
@@ -56,6 +93,8 @@ impl TemplateRenderer {
             r#"{{ config.site.title }}"#,
             r#"{% endblock title %}</title>
     <meta name="description" content="{% block description %}{{ config.site.description }}{% endblock description %}">
+    {% block head_meta %}{% if meta_tags %}{{ meta_tags | safe }}{% endif %}{% endblock head_meta %}
+    {% block head %}{% endblock head %}
     <!-- KaTeX CSS for math formula rendering -->
     <link rel="stylesheet" href="https://cdn.jsdelivr.net/npm/katex@0.16.9/dist/katex.min.css">
     <style>
@@ -112,7 +151,43 @@ impl TemplateRenderer {
         .content pre { background: var(--code-bg); padding: 15px; border-radius: 8px; overflow-x: auto; margin: 20px 0; }
         .content code { background: var(--code-bg); padding: 2px 6px; border-radius: 4px; font-family: 'Fira Code', 'Consolas', monospace; font-size: 0.9em; }
         .content pre code { background: none; padding: 0; }
+        .content table { width: 100%; border-collapse: collapse; margin: 20px 0; display: block; overflow-x: auto; }
+        .content th, .content td { border: 1px solid var(--border-color); padding: 8px 12px; }
+        .content th { background: var(--code-bg); font-weight: 600; }
+        .content tr:nth-child(even) td { background: var(--footer-bg); }
+        .content blockquote { border-left: 4px solid var(--border-color); padding-left: 16px; margin: 20px 0; color: var(--muted-color); }
+        .content li:has(> input[type="checkbox"]) { list-style: none; }
+        .content li > input[type="checkbox"] { margin: 0 0.5em 0 -1.4em; vertical-align: middle; }
+        .content .footnote-reference { font-size: 0.75em; }
+        .content .footnote-reference a, .content .footnote-backref { color: var(--primary-color); text-decoration: none; }
+        .content .footnote-definition { font-size: 0.9em; color: var(--muted-color); margin-top: 10px; }
+        .content .footnote-definition p { display: inline; }
+        .content .footnote-definition-label { margin-right: 6px; }
+        .content .footnotes { margin-top: 40px; padding-top: 10px; border-top: 1px solid var(--border-color); }
+        .content del { color: var(--muted-color); }
+        .content .callout { position: relative; margin: 20px 0; padding: 12px 16px 12px 44px; border-left: 4px solid var(--callout-color); border-radius: 4px; background: var(--code-bg); }
+        .content .callout::before { content: ""; position: absolute; left: 14px; top: 15px; width: 18px; height: 18px; background-color: var(--callout-color); -webkit-mask: var(--callout-icon) no-repeat center / contain; mask: var(--callout-icon) no-repeat center / contain; }
+        .content .callout > :first-child { margin-top: 0; }
+        .content .callout > :last-child { margin-bottom: 0; }
+        .content .callout-title { font-weight: 600; }
+        .content .callout-info { --callout-color: #3b82f6; --callout-icon: url("data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 24 24' fill='none' stroke='black' stroke-width='2' stroke-linecap='round' stroke-linejoin='round'><circle cx='12' cy='12' r='10'/><path d='M12 11v6M12 7h.01'/></svg>"); }
+        .content .callout-tip { --callout-color: #10b981; --callout-icon: url("data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 24 24' fill='none' stroke='black' stroke-width='2' stroke-linecap='round' stroke-linejoin='round'><path d='M9 18h6M10 22h4M12 2a7 7 0 0 0-4 12.7V16h8v-1.3A7 7 0 0 0 12 2z'/></svg>"); }
+        .content .callout-warning { --callout-color: #f59e0b; --callout-icon: url("data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 24 24' fill='none' stroke='black' stroke-width='2' stroke-linecap='round' stroke-linejoin='round'><path d='M12 3 2 21h20L12 3zM12 10v4M12 17h.01'/></svg>"); }
+        .content .callout-danger { --callout-color: #ef4444; --callout-icon: url("data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 24 24' fill='none' stroke='black' stroke-width='2' stroke-linecap='round' stroke-linejoin='round'><path d='M8 2h8l6 6v8l-6 6H8l-6-6V8zM9 9l6 6M15 9l-6 6'/></svg>"); }
         .meta { color: var(--muted-color); font-size: 0.9em; margin-bottom: 20px; }
+        .post-nav { display: grid; grid-template-columns: 1fr 1fr; gap: 20px; margin-top: 40px; padding-top: 20px; border-top: 1px solid var(--border-color); }
+        .post-nav span { display: block; color: var(--muted-color); font-size: 0.85em; margin-bottom: 4px; }
+        .post-nav a { color: var(--primary-color); text-decoration: none; }
+        .post-nav a:hover { text-decoration: underline; }
+        .post-nav-next { text-align: right; }
+        .series-nav { background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px; padding: 16px 20px; margin-bottom: 30px; }
+        .series-nav h2 { font-size: 1em; margin-bottom: 10px; }
+        .series-nav ol { padding-left: 1.5em; margin-bottom: 10px; }
+        .series-nav li { margin: 4px 0; }
+        .series-nav a { color: var(--primary-color); text-decoration: none; }
+        .series-nav a:hover { text-decoration: underline; }
+        .series-nav .current { font-weight: bold; }
+        .series-nav-links { display: flex; justify-content: space-between; gap: 20px; font-size: 0.9em; }
         .category-list { display: grid; grid-template-columns: repeat(auto-fill, minmax(250px, 1fr)); gap: 20px; }
         .category-card { background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px; padding: 20px; transition: background-color 0.3s, border-color 0.3s; }
         .category-card h3 { margin-bottom: 10px; }
@@ -187,6 +262,14 @@ impl TemplateRenderer {
             .content h2 { font-size: 1.5em; }
         }
     </style>
+    {% if theme %}<style>
+        :root { --bg-color: {{ theme.light_mode.background }}; --fg-color: {{ theme.light_mode.foreground }}; --primary-color: {{ theme.light_mode.primary }}; --secondary-color: {{ theme.light_mode.secondary }}; --accent-color: {{ theme.light_mode.accent }}; --border-color: {{ theme.light_mode.border }}; --muted-color: {{ theme.light_mode.muted }}; --code-bg: {{ theme.light_mode.code_bg }}; --card-bg: {{ theme.light_mode.card_bg }}; --header-bg: {{ theme.light_mode.header_bg }}; --footer-bg: {{ theme.light_mode.footer_bg }}; }
+        [data-theme="dark"] { --bg-color: {{ theme.dark_mode.background }}; --fg-color: {{ theme.dark_mode.foreground }}; --primary-color: {{ theme.dark_mode.primary }}; --secondary-color: {{ theme.dark_mode.secondary }}; --accent-color: {{ theme.dark_mode.accent }}; --border-color: {{ theme.dark_mode.border }}; --muted-color: {{ theme.dark_mode.muted }}; --code-bg: {{ theme.dark_mode.code_bg }}; --card-bg: {{ theme.dark_mode.card_bg }}; --header-bg: {{ theme.dark_mode.header_bg }}; --footer-bg: {{ theme.dark_mode.footer_bg }}; }
+        body { font-family: {{ theme.fonts.body | safe }}; }
+        .content code { font-family: {{ theme.fonts.code | safe }}; }
+    </style>
+    {% if theme.custom_css %}<style>{{ theme.custom_css | safe }}</style>{% endif %}{% endif %}
+    {% if highlight_css %}<style>{{ highlight_css | safe }}</style>{% endif %}
 </head>
 <body>
     <header>
@@ -195,20 +278,21 @@ impl TemplateRenderer {
             r#"{{ config.site.title }}"#,
             r#"</h1>
             <nav>
-                <a href="/">首页</a>
-                <a href="/archive">归档</a>
-                <a href="/search" class="search-link" title="搜索" style="display: flex; align-items: center; gap: 5px;">
+                <a href="/">{{ t(key="nav.home") }}</a>
+                <a href="/archive">{{ t(key="nav.archive") }}</a>
+                <a href="/tags">{{ t(key="nav.tags") }}</a>
+                <a href="/search" class="search-link" title="{{ t(key="nav.search") }}" style="display: flex; align-items: center; gap: 5px;">
                     <svg viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" width="18" height="18">
                         <circle cx="11" cy="11" r="8"></circle>
                         <line x1="21" y1="21" x2="16.65" y2="16.65"></line>
                     </svg>
-                    搜索
+                    {{ t(key="nav.search") }}
                 </a>
-                <select class="lang-switcher" id="langSwitcher" onchange="changeLanguage(this.value)" title="切换语言">
+                <select class="lang-switcher" id="langSwitcher" onchange="changeLanguage(this.value)" title="{{ t(key="nav.switch_language") }}">
                     <option value="zh-CN">中文</option>
                     <option value="en">English</option>
                 </select>
-                <button class="theme-toggle" id="themeToggle" title="切换主题">
+                <button class="theme-toggle" id="themeToggle" title="{{ t(key="nav.switch_theme") }}">
                     <svg class="sun-icon" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" style="display:none;">
                         <circle cx="12" cy="12" r="5"></circle>
                         <line x1="12" y1="1" x2="12" y2="3"></line>
@@ -223,7 +307,7 @@ impl TemplateRenderer {
                     <svg class="moon-icon" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
                         <path d="M21 12.79A9 9 0 1 1 11.21 3 7 7 0 0 0 21 12.79z"></path>
                     </svg>
-                    <span class="theme-text">深色</span>
+                    <span class="theme-text">{{ t(key="theme.dark") }}</span>
                 </button>
             </nav>
         </div>
@@ -233,7 +317,7 @@ impl TemplateRenderer {
     </main>
     <footer>
         <div class="container">
-            <p>&copy; 2026 {{ config.site.author }}. 版权所有.</p>
+            <p>&copy; 2026 {{ config.site.author }}. {{ t(key="footer.rights") }}</p>
         </div>
     </footer>
     
@@ -362,11 +446,11 @@ impl TemplateRenderer {
             if (isDark) {
                 sunIcon.style.display = 'block';
                 moonIcon.style.display = 'none';
-                themeText.textContent = '浅色';
+                themeText.textContent = '{{ t(key="theme.light") }}';
             } else {
                 sunIcon.style.display = 'none';
                 moonIcon.style.display = 'block';
-                themeText.textContent = '深色';
+                themeText.textContent = '{{ t(key="theme.dark") }}';
             }
         }
         
@@ -375,14 +459,18 @@ impl TemplateRenderer {
             var langSwitcher = document.getElementById('langSwitcher');
             if (!langSwitcher) return;
             
+            // The page is served in the language the server picked
+            langSwitcher.value = document.documentElement.lang;
             var savedLang = localStorage.getItem('language');
-            if (savedLang) {
-                langSwitcher.value = savedLang;
+            if (savedLang && savedLang !== document.documentElement.lang && document.cookie.indexOf('language=') === -1) {
+                changeLanguage(savedLang);
             }
         }
         
         function changeLanguage(lang) {
             localStorage.setItem('language', lang);
+            // The server reads the cookie to choose the page language
+            document.cookie = 'language=' + encodeURIComponent(lang) + '; path=/; max-age=31536000; SameSite=Lax';
             window.location.reload();
         }
         
@@ -411,34 +499,34 @@ impl TemplateRenderer {
         tera.add_raw_template(
             "index.html",
             r#"{% extends "base.html" %}
-{% block title %}{{ config.site.title }} - 首页{% endblock title %}
+{% block title %}{{ config.site.title }} - {{ t(key="nav.home") }}{% endblock title %}
 {% block content %}
 <div class="content">
     <h2>{{ config.site.description }}</h2>
     
     <!-- Popular Content Section -->
     <div id="popular-content" style="margin-top: 60px;">
-        <h2 style="margin-bottom: 30px;">热门文章</h2>
+        <h2 style="margin-bottom: 30px;">{{ t(key="index.popular") }}</h2>
         <div id="popular-list">
-            <p style="color: #888; text-align: center; padding: 40px;">加载热门内容中...</p>
+            <p style="color: #888; text-align: center; padding: 40px;">{{ t(key="index.loading_popular") }}</p>
         </div>
     </div>
     
     <!-- Latest Content Section -->
     <div id="latest-content" style="margin-top: 60px;">
-        <h2 style="margin-bottom: 30px;">最新文章</h2>
+        <h2 style="margin-bottom: 30px;">{{ t(key="index.latest") }}</h2>
         <div id="latest-list">
-            <p style="color: #888; text-align: center; padding: 40px;">加载最新内容中...</p>
+            <p style="color: #888; text-align: center; padding: 40px;">{{ t(key="index.loading_latest") }}</p>
         </div>
     </div>
     
-    <h2 style="margin-top: 60px;">分类</h2>
+    <h2 style="margin-top: 60px;">{{ t(key="index.categories") }}</h2>
     <div class="category-list">
         {% for category in site_content.categories %}
         <div class="category-card">
-            <h3><a href="/{{ category.url }}">{{ category.name }}</a></h3>
+            <h3><a href="/{{ category.url }}">{{ category.title }}</a></h3>
             <p>{{ category.description | default(value="") }}</p>
-            <p>共 {{ category.items | length }} 篇</p>
+            <p>{{ t(key="common.post_count", count=category.items | length) }}</p>
         </div>
         {% endfor %}
     </div>
@@ -467,9 +555,10 @@ document.addEventListener('DOMContentLoaded', function() {
                                 <div class="post-item">
                                     <h3><a href="/${item.url}">${item.title}</a></h3>
                                     <div class="meta">
-                                        <span>分类: <a href="/${item.category}">${item.category_name}</a></span>
+                                        <span>{{ t(key="common.category") }} <a href="/${item.category}">${item.category_name}</a></span>
+                                        ${item.comment_count > 0 ? `<span>{{ t(key="common.comment_count", count="${item.comment_count}") }}</span>` : ''}
                                     </div>
-                                    ${item.description ? `<p>${item.description.length > 120 ? item.description.substring(0, 120) + '...' : item.description}</p>` : ''}
+                                    ${item.excerpt ? `<p>${item.excerpt.length > 120 ? item.excerpt.substring(0, 120) + '...' : item.excerpt}</p>` : ''}
                                 </div>
                             `).join('')}
                         </div>
@@ -477,13 +566,13 @@ document.addEventListener('DOMContentLoaded', function() {
                     
                     popularList.innerHTML = html;
                 } else {
-                    popularList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">暂无热门内容</p>';
+                    popularList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">{{ t(key="index.no_popular") }}</p>';
                 }
             }
         } catch (error) {
             console.error('加载热门内容失败:', error);
             const popularList = document.getElementById('popular-list');
-            popularList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">加载失败</p>';
+            popularList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">{{ t(key="common.load_failed") }}</p>';
         }
     }
     
@@ -502,9 +591,10 @@ document.addEventListener('DOMContentLoaded', function() {
                                 <div class="post-item">
                                     <h3><a href="/${item.url}">${item.title}</a></h3>
                                     <div class="meta">
-                                        <span>分类: <a href="/${item.category}">${item.category_name}</a></span>
+                                        <span>{{ t(key="common.category") }} <a href="/${item.category}">${item.category_name}</a></span>
+                                        ${item.comment_count > 0 ? `<span>{{ t(key="common.comment_count", count="${item.comment_count}") }}</span>` : ''}
                                     </div>
-                                    ${item.description ? `<p>${item.description.length > 120 ? item.description.substring(0, 120) + '...' : item.description}</p>` : ''}
+                                    ${item.excerpt ? `<p>${item.excerpt.length > 120 ? item.excerpt.substring(0, 120) + '...' : item.excerpt}</p>` : ''}
                                 </div>
                             `).join('')}
                         </div>
@@ -512,13 +602,13 @@ document.addEventListener('DOMContentLoaded', function() {
                     
                     latestList.innerHTML = html;
                 } else {
-                    latestList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">暂无最新内容</p>';
+                    latestList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">{{ t(key="index.no_latest") }}</p>';
                 }
             }
         } catch (error) {
             console.error('加载最新内容失败:', error);
             const latestList = document.getElementById('latest-list');
-            latestList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">加载失败</p>';
+            latestList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">{{ t(key="common.load_failed") }}</p>';
         }
     }
 });
@@ -530,11 +620,15 @@ document.addEventListener('DOMContentLoaded', function() {
         tera.add_raw_template(
             "category.html",
             r#"{% extends "base.html" %}
-{% block title %}{{ category.name }} - {{ config.site.title }}{% endblock title %}
-{% block description %}{{ category.description | default(value=category.name) }}{% endblock description %}
+{% block title %}{{ category.title }} - {{ config.site.title }}{% endblock title %}
+{% block description %}{{ category.description | default(value=category.title) }}{% endblock description %}
+{% block head %}{{ super() }}
+    <link rel="alternate" type="application/rss+xml" title="{{ category.title }}" href="/feed/{{ category.url }}.xml">
+    <script type="application/ld+json">{{ json_ld(category=category, base_url=config.site.url) | safe }}</script>
+{% endblock head %}
 {% block content %}
 <div class="content">
-    <h1>{{ category.name }}</h1>
+    <h1>{{ category.title }}</h1>
     {% if category.description %}
     <p>{{ category.description }}</p>
     {% endif %}
@@ -547,7 +641,7 @@ document.addEventListener('DOMContentLoaded', function() {
                 <span>{{ item.date }}</span>
                 {% endif %}
             </div>
-            <p>{{ item.description | default(value="") }}</p>
+            <p>{{ item.excerpt | default(value="") }}</p>
         </div>
         {% endfor %}
     </div>
@@ -561,63 +655,106 @@ document.addEventListener('DOMContentLoaded', function() {
             r#"{% extends "base.html" %}
 {% block title %}{{ item.title }} - {{ config.site.title }}{% endblock title %}
 {% block description %}{{ item.description | default(value=item.title) }}{% endblock description %}
+{% block head %}{{ super() }}
+    <script type="application/ld+json">{{ json_ld(item=item, base_url=config.site.url, site_title=config.site.title) | safe }}</script>
+{% endblock head %}
 {% block content %}
 <div class="content">
     <h1>{{ item.title }}</h1>
     <div class="meta">
         {% if item.date %}
-        <span>发布于 {{ item.date }}</span>
+        <span>{{ t(key="item.published", date=item.date) }}</span>
         {% endif %}
         {% if item.author %}
-        <span>作者: {{ item.author }}</span>
+        <span>{{ t(key="item.author", author=item.author) }}</span>
+        {% endif %}
+        <span id="view-count" style="display: none;">{{ t(key="item.views") }}</span>
+        {% if item.tags %}
+        <span class="tags">
+            {% for tag in item.tags %}<a href="/tags?tag={{ tag | urlencode }}" style="color: var(--primary-color); margin-right: 6px;">#{{ tag }}</a>{% endfor %}
+        </span>
         {% endif %}
     </div>
+    {% if series_nav %}
+    <nav class="series-nav">
+        <h2>{{ t(key="item.series", name=series_nav.series_name, count=series_nav.all_parts | length) }}</h2>
+        <ol>
+            {% for part in series_nav.all_parts %}
+            <li value="{{ part.2 }}">{% if part.1 == item.url %}<span class="current">{{ part.0 }}</span>{% else %}<a href="/{{ part.1 }}">{{ part.0 }}</a>{% endif %}</li>
+            {% endfor %}
+        </ol>
+        <div class="series-nav-links">
+            <div>{% if series_nav.prev %}← <a href="/{{ series_nav.prev.1 }}">{{ series_nav.prev.0 }}</a>{% endif %}</div>
+            <div>{% if series_nav.next %}<a href="/{{ series_nav.next.1 }}">{{ series_nav.next.0 }}</a> →{% endif %}</div>
+        </div>
+    </nav>
+    {% endif %}
     <article>
         {{ item.html_content | safe }}
     </article>
+
+    {% if prev or next %}
+    <nav class="post-nav">
+        <div class="post-nav-prev">
+            {% if prev %}<span>{{ t(key="item.prev") }}</span><a href="/{{ prev.url }}">{{ prev.title }}</a>{% endif %}
+        </div>
+        <div class="post-nav-next">
+            {% if next %}<span>{{ t(key="item.next") }}</span><a href="/{{ next.url }}">{{ next.title }}</a>{% endif %}
+        </div>
+    </nav>
+    {% endif %}
     
     <!-- Comments Section -->
     <div id="comments" style="margin-top: 60px; border-top: 1px solid var(--border-color); padding-top: 40px;">
-        <h2 style="margin-bottom: 30px;">评论 <span id="comment-count"></span></h2>
+        <h2 style="margin-bottom: 30px;">{{ t(key="comments.title") }} <span id="comment-count"></span></h2>
         
         <!-- Add Comment Form -->
         <div class="comment-form" style="margin-bottom: 40px;">
-            <h3 style="margin-bottom: 20px;">发表评论</h3>
+            <h3 style="margin-bottom: 20px;">{{ t(key="comments.post") }}</h3>
             <form id="commentForm">
-                <div class="form-group" style="display: grid; grid-template-columns: 1fr 1fr; gap: 15px; margin-bottom: 15px;">
+                <input type="hidden" id="parent_id" name="parent_id" value="">
+                <div id="reply-to" style="display: none; margin-bottom: 15px; color: var(--muted-color);">
+                    {{ t(key="comments.replying_to") }} <strong></strong>
+                    <a href="javascript:void(0)" id="cancel-reply" style="margin-left: 10px; color: var(--primary-color);">{{ t(key="comments.cancel_reply") }}</a>
+                </div>
+                <div class="form-group" style="display: grid; grid-template-columns: repeat(auto-fit, minmax(180px, 1fr)); gap: 15px; margin-bottom: 15px;">
                     <div>
-                        <label for="author" style="display: block; margin-bottom: 8px; font-weight: 500;">昵称 *</label>
+                        <label for="author" style="display: block; margin-bottom: 8px; font-weight: 500;">{{ t(key="comments.name") }}</label>
                         <input type="text" id="author" name="author" required style="width: 100%; padding: 10px; border: 1px solid var(--border-color); border-radius: 4px;">
                     </div>
                     <div>
-                        <label for="website" style="display: block; margin-bottom: 8px; font-weight: 500;">网站 (可选)</label>
+                        <label for="website" style="display: block; margin-bottom: 8px; font-weight: 500;">{{ t(key="comments.website") }}</label>
                         <input type="url" id="website" name="website" style="width: 100%; padding: 10px; border: 1px solid var(--border-color); border-radius: 4px;">
                     </div>
+                    <div>
+                        <label for="email" style="display: block; margin-bottom: 8px; font-weight: 500;">{{ t(key="comments.email") }}</label>
+                        <input type="email" id="email" name="email" style="width: 100%; padding: 10px; border: 1px solid var(--border-color); border-radius: 4px;">
+                    </div>
                 </div>
                 <div class="form-group" style="margin-bottom: 20px;">
-                    <label for="content" style="display: block; margin-bottom: 8px; font-weight: 500;">评论内容 *</label>
+                    <label for="content" style="display: block; margin-bottom: 8px; font-weight: 500;">{{ t(key="comments.content") }}</label>
                     <textarea id="content" name="content" required rows="4" style="width: 100%; padding: 10px; border: 1px solid var(--border-color); border-radius: 4px;"></textarea>
                 </div>
-                <button type="submit" class="btn" style="background: var(--primary-color);">发表评论</button>
+                <button type="submit" class="btn" style="background: var(--primary-color);">{{ t(key="comments.submit") }}</button>
             </form>
             <div id="comment-message" style="margin-top: 15px; display: none;"></div>
         </div>
         
         <!-- Comments List -->
         <div id="comments-list">
-            <p style="color: #888; text-align: center; padding: 40px;">加载评论中...</p>
+            <p style="color: #888; text-align: center; padding: 40px;">{{ t(key="comments.loading") }}</p>
         </div>
     </div>
     
     <!-- Recommendations Section -->
     <div id="recommendations" style="margin-top: 60px; border-top: 1px solid var(--border-color); padding-top: 40px;">
-        <h2 style="margin-bottom: 30px;">相关推荐</h2>
+        <h2 style="margin-bottom: 30px;">{{ t(key="item.related") }}</h2>
         <div id="recommendations-list">
-            <p style="color: #888; text-align: center; padding: 40px;">加载推荐内容中...</p>
+            <p style="color: #888; text-align: center; padding: 40px;">{{ t(key="item.loading_related") }}</p>
         </div>
     </div>
     
-    <p style="margin-top: 40px;"><a href="/{{ item.category }}">← 返回 {{ item.category }}</a></p>
+    <p style="margin-top: 40px;"><a href="/{{ item.category }}">{{ t(key="item.back_to", category=item.category) }}</a></p>
 </div>
 
 <script>
@@ -631,6 +768,9 @@ document.addEventListener('DOMContentLoaded', function() {
     // Load recommendations
     loadRecommendations(currentSlug);
     
+    // Load view count
+    loadViewCount(currentSlug);
+    
     // Handle comment form submission
     const commentForm = document.getElementById('commentForm');
     if (commentForm) {
@@ -639,14 +779,16 @@ document.addEventListener('DOMContentLoaded', function() {
             
             const submitBtn = commentForm.querySelector('button[type="submit"]');
             const originalText = submitBtn.textContent;
-            submitBtn.textContent = '提交中...';
+            submitBtn.textContent = '{{ t(key="comments.submitting") }}';
             submitBtn.disabled = true;
             
             const formData = {
                 slug: currentSlug,
                 author: document.getElementById('author').value.trim(),
                 content: document.getElementById('content').value.trim(),
-                website: document.getElementById('website').value.trim() || null
+                website: document.getElementById('website').value.trim() || null,
+                email: document.getElementById('email').value.trim() || null,
+                parent_id: document.getElementById('parent_id').value || null
             };
             
             const messageDiv = document.getElementById('comment-message');
@@ -669,8 +811,9 @@ document.addEventListener('DOMContentLoaded', function() {
                     messageDiv.style.background = '#d4edda';
                     messageDiv.style.padding = '15px';
                     messageDiv.style.borderRadius = '4px';
-                    messageDiv.textContent = '评论发表成功！刷新页面查看您的评论。';
+                    messageDiv.textContent = '{{ t(key="comments.success") }}';
                     commentForm.reset();
+                    cancelReply();
                     
                     // Reload comments after a short delay
                     setTimeout(() => {
@@ -682,7 +825,7 @@ document.addEventListener('DOMContentLoaded', function() {
                     messageDiv.style.background = '#f8d7da';
                     messageDiv.style.padding = '15px';
                     messageDiv.style.borderRadius = '4px';
-                    messageDiv.textContent = '评论发表失败: ' + (data.message || '未知错误');
+                    messageDiv.textContent = '{{ t(key="comments.failed") }}' + (data.message || '{{ t(key="common.unknown_error") }}');
                 }
             } catch (error) {
                 messageDiv.style.display = 'block';
@@ -690,7 +833,7 @@ document.addEventListener('DOMContentLoaded', function() {
                 messageDiv.style.background = '#f8d7da';
                 messageDiv.style.padding = '15px';
                 messageDiv.style.borderRadius = '4px';
-                messageDiv.textContent = '请求失败: ' + error.message;
+                messageDiv.textContent = '{{ t(key="common.request_failed") }}' + error.message;
             }
             
             submitBtn.textContent = originalText;
@@ -698,6 +841,74 @@ document.addEventListener('DOMContentLoaded', function() {
         });
     }
     
+    function escapeHtml(text) {
+        return String(text)
+            .replace(/&/g, '&amp;')
+            .replace(/</g, '&lt;')
+            .replace(/>/g, '&gt;')
+            .replace(/"/g, '&quot;')
+            .replace(/'/g, '&#39;');
+    }
+
+    function renderComment(comment, isReply) {
+        // The server only accepts http(s) websites; check again before linking
+        const author = comment.website && /^https?:\/\//i.test(comment.website)
+            ? `<a href="${escapeHtml(comment.website)}" target="_blank" rel="nofollow ugc noopener" style="color: var(--primary-color);">${escapeHtml(comment.author)}</a>`
+            : escapeHtml(comment.author);
+        const avatar = comment.avatar_url
+            ? `<img class="comment-avatar" src="${escapeHtml(comment.avatar_url)}" alt="" width="40" height="40" loading="lazy" style="border-radius: 50%; flex-shrink: 0;">`
+            : '';
+        // 回复只有一层，回复下不再显示回复按钮
+        const replyButton = isReply
+            ? ''
+            : `<a href="javascript:void(0)" class="reply-link" data-id="${escapeHtml(comment.id)}" data-author="${escapeHtml(comment.author)}" style="display: inline-block; margin-top: 10px; color: var(--primary-color); font-size: 0.9em;">{{ t(key="comments.reply") }}</a>`;
+        return `
+            <div class="comment-header" style="display: flex; justify-content: space-between; align-items: center; margin-bottom: 15px;">
+                <div style="display: flex; align-items: center; gap: 12px;">
+                    ${avatar}
+                    <strong style="color: var(--primary-color);">${author}</strong>
+                </div>
+                <span style="color: #888; font-size: 0.9em;">${new Date(comment.created_at).toLocaleDateString(document.documentElement.lang)}</span>
+            </div>
+            <div class="comment-content" style="line-height: 1.6;">${escapeHtml(comment.content).replace(/\n/g, '<br>')}</div>
+            ${replyButton}
+        `;
+    }
+
+    function renderThread(thread) {
+        const replies = thread.replies.map(reply => `
+            <div class="comment comment-reply" style="margin-top: 20px; margin-left: 30px; padding-left: 20px; border-left: 3px solid var(--border-color);">
+                ${renderComment(reply, true)}
+            </div>
+        `).join('');
+        return `
+            <div class="comment" style="margin-bottom: 30px; padding-bottom: 30px; border-bottom: 1px solid var(--border-color);">
+                ${renderComment(thread.comment, false)}
+                ${replies}
+            </div>
+        `;
+    }
+
+    function cancelReply() {
+        document.getElementById('parent_id').value = '';
+        document.getElementById('reply-to').style.display = 'none';
+    }
+
+    document.getElementById('cancel-reply').addEventListener('click', function(e) {
+        e.preventDefault();
+        cancelReply();
+    });
+
+    document.getElementById('comments-list').addEventListener('click', function(e) {
+        const link = e.target.closest('.reply-link');
+        if (!link) return;
+        document.getElementById('parent_id').value = link.dataset.id;
+        const replyTo = document.getElementById('reply-to');
+        replyTo.querySelector('strong').textContent = link.dataset.author;
+        replyTo.style.display = 'block';
+        document.getElementById('content').focus();
+    });
+
     async function loadComments(slug) {
         try {
             const response = await fetch('/api/comments/' + slug);
@@ -710,27 +921,31 @@ document.addEventListener('DOMContentLoaded', function() {
                 if (data.comments && data.comments.length > 0) {
                     commentCount.textContent = '(' + data.count + ')';
                     
-                    const html = data.comments.map(comment => `
-                        <div class="comment" style="margin-bottom: 30px; padding-bottom: 30px; border-bottom: 1px solid var(--border-color);">
-                            <div class="comment-header" style="display: flex; justify-content: space-between; margin-bottom: 15px;">
-                                <strong style="color: var(--primary-color);">${comment.author}</strong>
-                                <span style="color: #888; font-size: 0.9em;">${new Date(comment.created_at).toLocaleDateString('zh-CN')}</span>
-                            </div>
-                            <div class="comment-content" style="line-height: 1.6;">${comment.content.replace(/\n/g, '<br>')}</div>
-                            ${comment.website ? `<div style="margin-top: 10px;"><a href="${comment.website}" target="_blank" style="color: #888; font-size: 0.9em;">${comment.website}</a></div>` : ''}
-                        </div>
-                    `).join('');
-                    
-                    commentsList.innerHTML = html;
+                    commentsList.innerHTML = data.comments.map(renderThread).join('');
                 } else {
                     commentCount.textContent = '(0)';
-                    commentsList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">暂无评论，快来发表第一条评论吧！</p>';
+                    commentsList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">{{ t(key="comments.empty") }}</p>';
                 }
             }
         } catch (error) {
             console.error('加载评论失败:', error);
             const commentsList = document.getElementById('comments-list');
-            commentsList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">加载评论失败</p>';
+            commentsList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">{{ t(key="comments.load_failed") }}</p>';
+        }
+    }
+    
+    async function loadViewCount(slug) {
+        try {
+            const response = await fetch('/api/stats/views/' + slug);
+            const data = await response.json();
+            
+            if (data.status === 'success') {
+                const viewCount = document.getElementById('view-count');
+                viewCount.querySelector('span').textContent = data.views;
+                viewCount.style.display = '';
+            }
+        } catch (error) {
+            console.error('加载浏览量失败:', error);
         }
     }
     
@@ -749,9 +964,9 @@ document.addEventListener('DOMContentLoaded', function() {
                                 <div class="recommendation-item" style="background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px; padding: 20px; transition: all 0.3s;">
                                     <h3 style="margin-bottom: 10px;"><a href="/${item.url}" style="color: var(--primary-color); text-decoration: none;">${item.title}</a></h3>
                                     <div class="meta" style="color: #888; font-size: 0.9em; margin-bottom: 10px;">
-                                        分类: <a href="/${item.category}" style="color: #888;">${item.category_name}</a>
+                                        {{ t(key="common.category") }} <a href="/${item.category}" style="color: #888;">${item.category_name}</a>
                                     </div>
-                                    ${item.description ? `<p style="color: var(--muted-color); font-size: 0.95em; line-height: 1.5;">${item.description.length > 100 ? item.description.substring(0, 100) + '...' : item.description}</p>` : ''}
+                                    ${item.excerpt ? `<p style="color: var(--muted-color); font-size: 0.95em; line-height: 1.5;">${item.excerpt.length > 100 ? item.excerpt.substring(0, 100) + '...' : item.excerpt}</p>` : ''}
                                 </div>
                             `).join('')}
                         </div>
@@ -759,13 +974,13 @@ document.addEventListener('DOMContentLoaded', function() {
                     
                     recommendationsList.innerHTML = html;
                 } else {
-                    recommendationsList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">暂无推荐内容</p>';
+                    recommendationsList.innerHTML = '<p style="color: #888; text-align: center; padding: 40px;">{{ t(key="item.no_related") }}</p>';
                 }
             }
         } catch (error) {
             console.error('加载推荐失败:', error);
             const recommendationsList = document.getElementById('recommendations-list');
-            recommendationsList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">加载推荐失败</p>';
+            recommendationsList.innerHTML = '<p style="color: #c00; text-align: center; padding: 40px;">{{ t(key="item.related_failed") }}</p>';
         }
     }
 });
@@ -777,11 +992,11 @@ document.addEventListener('DOMContentLoaded', function() {
         tera.add_raw_template(
             "archive.html",
             r#"{% extends "base.html" %}
-{% block title %}文章归档 - {{ config.site.title }}{% endblock title %}
+{% block title %}{{ t(key="archive.title") }} - {{ config.site.title }}{% endblock title %}
 {% block content %}
 <div class="content">
-    <h1>文章归档</h1>
-    <p>共 {{ total_items }} 篇文章</p>
+    <h1>{{ t(key="archive.title") }}</h1>
+    <p>{{ t(key="archive.total", count=total_items) }}</p>
     <div class="post-list" style="margin-top: 30px;">
         {% for item in all_items %}
         <div class="post-item">
@@ -790,12 +1005,97 @@ document.addEventListener('DOMContentLoaded', function() {
                 {% if item.date %}
                 <span>{{ item.date }}</span>
                 {% endif %}
-                <span>分类: {{ item.category_name }}</span>
+                <span>{{ t(key="common.category") }} {{ item.category_name }}</span>
+                {% if item.comment_count %}
+                <span>{{ t(key="common.comment_count", count=item.comment_count) }}</span>
+                {% endif %}
             </div>
-            <p>{{ item.description | default(value="") }}</p>
+            <p>{{ item.excerpt | default(value="") }}</p>
+        </div>
+        {% endfor %}
+    </div>
+</div>
+{% endblock content %}"#,
+        )?;
+
+        // Not found template, compiled and served with status 404
+        tera.add_raw_template(
+            "404.html",
+            r#"{% extends "base.html" %}
+{% block title %}{{ t(key="not_found.title") }} - {{ config.site.title }}{% endblock title %}
+{% block head %}<meta name="robots" content="noindex">{% endblock head %}
+{% block content %}
+<div class="content">
+    <h1>{{ t(key="not_found.title") }}</h1>
+    <p style="color: var(--muted-color);">{{ t(key="not_found.hint") }}</p>
+
+    <div class="search-form" style="margin: 30px 0; padding: 20px; background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px;">
+        <form action="/search" method="GET" style="display: flex; gap: 10px; flex-wrap: wrap;">
+            <input type="text"
+                   name="q"
+                   placeholder="{{ t(key="common.search_placeholder") }}"
+                   style="flex: 1; min-width: 200px; padding: 12px 15px; border: 2px solid var(--border-color); border-radius: 6px; font-size: 1em;"
+                   required>
+            <button type="submit" class="btn" style="padding: 12px 25px; background: var(--primary-color); color: #fff; border: none; border-radius: 6px; cursor: pointer; font-size: 1em;">
+                {{ t(key="nav.search") }}
+            </button>
+        </form>
+    </div>
+
+    {% if recent_items | length > 0 %}
+    <h2>{{ t(key="not_found.recent") }}</h2>
+    <ul class="recent-list" style="margin: 15px 0 30px 20px; line-height: 2;">
+        {% for item in recent_items %}
+        <li><a href="/{{ item.url }}">{{ item.title }}</a></li>
+        {% endfor %}
+    </ul>
+    {% endif %}
+
+    <p><a href="/">{{ t(key="not_found.back_home") }}</a></p>
+</div>
+{% endblock content %}"#,
+        )?;
+
+        // Tag cloud template
+        tera.add_raw_template(
+            "tags.html",
+            r#"{% extends "base.html" %}
+{% block title %}{% if tag %}{{ t(key="tags.tag_title", tag=tag) }}{% else %}{{ t(key="tags.title") }}{% endif %} - {{ config.site.title }}{% endblock title %}
+{% block description %}{% if tag %}{{ t(key="tags.tag_description", tag=tag) }}{% else %}{{ t(key="tags.all") }}{% endif %}{% endblock description %}
+{% block content %}
+<div class="content">
+    <h1>{{ t(key="tags.title") }}</h1>
+
+    {% if cloud | length > 0 %}
+    <div class="tag-cloud" style="margin: 30px 0; padding: 20px; background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px; line-height: 2.2;">
+        {% for entry in cloud %}
+        <a href="/tags?tag={{ entry.tag | urlencode }}"
+           title="{{ t(key="tags.posts", count=entry.count) }}"
+           style="font-size: {{ 0.9 + entry.weight * 1.4 }}em; margin-right: 14px; color: var(--primary-color); text-decoration: none;{% if entry.tag == tag %} font-weight: bold;{% endif %}">
+            {{ entry.tag }}
+        </a>
+        {% endfor %}
+    </div>
+    {% else %}
+    <p style="color: var(--muted-color);">{{ t(key="tags.none") }}</p>
+    {% endif %}
+
+    {% if tag %}
+    {% set tagged = tag_filter(tag=tag) %}
+    <h2>#{{ tag }} <small style="color: var(--muted-color); font-size: 0.6em;">{{ t(key="common.post_count", count=tagged | length) }}</small></h2>
+    <div class="post-list" style="margin-top: 20px;">
+        {% for item in tagged %}
+        <div class="post-item">
+            <h2><a href="/{{ item.slug }}">{{ item.title }}</a></h2>
+            {% if item.category %}
+            <div class="meta">{{ t(key="common.category") }} <a href="/{{ item.category }}">{{ item.category }}</a></div>
+            {% endif %}
         </div>
+        {% else %}
+        <p style="color: var(--muted-color);">{{ t(key="tags.no_items") }}</p>
         {% endfor %}
     </div>
+    {% endif %}
 </div>
 {% endblock content %}"#,
         )?;
@@ -804,11 +1104,11 @@ document.addEventListener('DOMContentLoaded', function() {
         tera.add_raw_template(
             "search.html",
             r#"{% extends "base.html" %}
-{% block title %}搜索: {{ search_query }} - {{ config.site.title }}{% endblock title %}
-{% block description %}搜索 {{ search_query }} 的结果{% endblock description %}
+{% block title %}{{ t(key="search.title", query=search_query) }} - {{ config.site.title }}{% endblock title %}
+{% block description %}{{ t(key="search.description", query=search_query) }}{% endblock description %}
 {% block content %}
 <div class="content">
-    <h1>搜索结果</h1>
+    <h1>{{ t(key="search.results") }}</h1>
     
     <!-- Search Form -->
     <div class="search-form" style="margin: 30px 0; padding: 20px; background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px;">
@@ -816,30 +1116,43 @@ document.addEventListener('DOMContentLoaded', function() {
             <input type="text" 
                    name="q" 
                    value="{{ search_query }}" 
-                   placeholder="输入关键词搜索..." 
+                   placeholder="{{ t(key="common.search_placeholder") }}" 
                    style="flex: 1; min-width: 200px; padding: 12px 15px; border: 2px solid var(--border-color); border-radius: 6px; font-size: 1em;"
-                   required
                    autofocus>
             <button type="submit" class="btn" style="padding: 12px 25px; background: var(--primary-color); color: #fff; border: none; border-radius: 6px; cursor: pointer; font-size: 1em;">
-                搜索
+                {{ t(key="nav.search") }}
             </button>
+            <div class="search-filters" style="display: flex; gap: 10px; flex-wrap: wrap; align-items: center; width: 100%; color: var(--muted-color); font-size: 0.9em;">
+                <select name="category" style="padding: 8px 10px; border: 1px solid var(--border-color); border-radius: 6px;">
+                    <option value="">{{ t(key="search.all_categories") }}</option>
+                    {% for category in site_content.categories %}
+                    <option value="{{ category.name }}"{% if search_category and search_category == category.name %} selected{% endif %}>{{ category.title }}</option>
+                    {% endfor %}
+                </select>
+                <label>{{ t(key="search.from") }}
+                    <input type="date" name="start" value="{{ search_start | default(value="") }}" style="padding: 7px 10px; border: 1px solid var(--border-color); border-radius: 6px;">
+                </label>
+                <label>{{ t(key="search.to") }}
+                    <input type="date" name="end" value="{{ search_end | default(value="") }}" style="padding: 7px 10px; border: 1px solid var(--border-color); border-radius: 6px;">
+                </label>
+            </div>
         </form>
     </div>
     
     <!-- Search Results Summary -->
     <div class="search-summary" style="margin-bottom: 30px; padding-bottom: 20px; border-bottom: 1px solid var(--border-color);">
-        {% if search_query %}
+        {% if search_query or search_category or search_start or search_end %}
             {% if search_results | length > 0 %}
                 <p style="color: var(--muted-color);">
-                    关于 "<strong>{{ search_query }}</strong>" 的搜索结果，共找到 <strong>{{ total_count }}</strong> 条结果
+                    {{ t(key="search.found", query=search_query, count=total_count) }}
                 </p>
             {% else %}
                 <p style="color: var(--muted-color);">
-                    没有找到与 "<strong>{{ search_query }}</strong>" 相关的内容
+                    {{ t(key="search.nothing_found", query=search_query) }}
                 </p>
             {% endif %}
         {% else %}
-            <p style="color: var(--muted-color);">请输入关键词进行搜索</p>
+            <p style="color: var(--muted-color);">{{ t(key="search.prompt") }}</p>
         {% endif %}
     </div>
     
@@ -856,7 +1169,7 @@ document.addEventListener('DOMContentLoaded', function() {
             <div class="meta" style="margin-bottom: 10px; font-size: 0.9em;">
                 {% if result.category %}
                 <span style="color: var(--muted-color);">
-                    分类: <a href="/{{ result.category }}" style="color: var(--primary-color);">{{ result.category }}</a>
+                    {{ t(key="common.category") }} <a href="/{{ result.category }}" style="color: var(--primary-color);">{{ result.category }}</a>
                 </span>
                 {% endif %}
             </div>
@@ -865,28 +1178,35 @@ document.addEventListener('DOMContentLoaded', function() {
                 {{ result.snippet | safe }}
             </p>
             {% endif %}
-            <a href="/{{ result.slug }}" style="color: var(--primary-color); font-size: 0.9em;">查看详情 &rarr;</a>
+            <a href="/{{ result.slug }}" style="color: var(--primary-color); font-size: 0.9em;">{{ t(key="search.view_details") }}</a>
         </div>
         {% endfor %}
     </div>
+    {% if pagination.total_pages > 1 %}
+    <nav class="search-pagination" style="display: flex; justify-content: space-between; align-items: center; margin: 30px 0; color: var(--muted-color);">
+        <div>{% if pagination.has_prev %}<a href="{{ search_page_url }}{{ pagination.current_page - 1 }}" rel="prev" style="color: var(--primary-color);">{{ t(key="search.prev_page") }}</a>{% endif %}</div>
+        <span>{{ t(key="search.page_of", page=pagination.current_page, total=pagination.total_pages) }}</span>
+        <div>{% if pagination.has_next %}<a href="{{ search_page_url }}{{ pagination.current_page + 1 }}" rel="next" style="color: var(--primary-color);">{{ t(key="search.next_page") }}</a>{% endif %}</div>
+    </nav>
+    {% endif %}
     {% endif %}
     
     <!-- Quick Links -->
-    {% if search_query == "" %}
+    {% if search_query == "" and not search_category and not search_start and not search_end %}
     <div class="search-tips" style="margin-top: 40px; padding: 30px; background: var(--card-bg); border: 1px solid var(--border-color); border-radius: 8px;">
-        <h2 style="margin-bottom: 20px;">搜索提示</h2>
+        <h2 style="margin-bottom: 20px;">{{ t(key="search.tips") }}</h2>
         <ul style="list-style: none; padding: 0;">
             <li style="margin-bottom: 15px; padding-left: 20px; position: relative;">
                 <span style="position: absolute; left: 0; color: var(--primary-color);">•</span>
-                输入准确的关键词可以获得更精确的结果
+                {{ t(key="search.tip_exact") }}
             </li>
             <li style="margin-bottom: 15px; padding-left: 20px; position: relative;">
                 <span style="position: absolute; left: 0; color: var(--primary-color);">•</span>
-                支持按标题和内容进行搜索
+                {{ t(key="search.tip_fields") }}
             </li>
             <li style="margin-bottom: 15px; padding-left: 20px; position: relative;">
                 <span style="position: absolute; left: 0; color: var(--primary-color);">•</span>
-                尝试使用更短或更简单的关键词
+                {{ t(key="search.tip_shorter") }}
             </li>
         </ul>
     </div>
@@ -1187,6 +1507,10 @@ document.addEventListener('DOMContentLoaded', function() {
             background: #fff3cd;
             color: #856404;
         }
+        .badge-info {
+            background: #d1ecf1;
+            color: #0c5460;
+        }
         .action-btns {
             display: flex;
             gap: 8px;
@@ -1226,6 +1550,37 @@ document.addEventListener('DOMContentLoaded', function() {
             grid-template-columns: 1fr 1fr;
             gap: 20px;
         }
+        .editor-split {
+            display: grid;
+            grid-template-columns: 1fr 1fr;
+            gap: 20px;
+        }
+        .editor-split textarea {
+            min-height: 480px;
+            font-family: SFMono-Regular, Consolas, 'Liberation Mono', Menlo, monospace;
+        }
+        .editor-preview {
+            min-height: 480px;
+            max-height: 720px;
+            overflow-y: auto;
+            padding: 12px 16px;
+            border: 1px solid #ddd;
+            border-radius: 6px;
+            background: #fff;
+            line-height: 1.7;
+        }
+        .editor-preview h1, .editor-preview h2, .editor-preview h3 { margin: 0.8em 0 0.4em; }
+        .editor-preview p, .editor-preview ul, .editor-preview ol, .editor-preview table, .editor-preview pre { margin-bottom: 1em; }
+        .editor-preview ul, .editor-preview ol { padding-left: 1.5em; }
+        .editor-preview img { max-width: 100%; }
+        .editor-preview pre { padding: 12px; background: #f6f8fa; overflow-x: auto; border-radius: 4px; }
+        .editor-preview table { border-collapse: collapse; }
+        .editor-preview th, .editor-preview td { border: 1px solid #ddd; padding: 4px 8px; }
+        .editor-preview-status {
+            color: #7f8c8d;
+            font-size: 0.85em;
+            margin-bottom: 6px;
+        }
         @media (max-width: 768px) {
             .admin-sidebar {
                 display: none;
@@ -1233,8 +1588,12 @@ document.addEventListener('DOMContentLoaded', function() {
             .grid-2 {
                 grid-template-columns: 1fr;
             }
+            .editor-split {
+                grid-template-columns: 1fr;
+            }
         }
     </style>
+    {% if highlight_css %}<style>{{ highlight_css | safe }}</style>{% endif %}
 </head>
 <body>
     <header class="admin-header">
@@ -1249,14 +1608,73 @@ document.addEventListener('DOMContentLoaded', function() {
             <a href="/admin" class="{% if active == 'overview' %}active{% endif %}">仪表盘</a>
             <a href="/admin/categories" class="{% if active == 'categories' %}active{% endif %}">分类管理</a>
             <a href="/admin/items" class="{% if active == 'items' %}active{% endif %}">文章管理</a>
+            <a href="/admin/attachments" class="{% if active == 'attachments' %}active{% endif %}">附件管理</a>
+            <a href="/admin/comments" class="{% if active == 'comments' %}active{% endif %}">评论管理</a>
             <a href="/admin/analytics" class="{% if active == 'analytics' %}active{% endif %}">访问统计</a>
-            <a href="/admin/compile" class="{% if active == 'compile' %}active{% endif %}">编译发布</a>
+            <a href="/admin/compile" class="{% if active == 'compile' %}active{% endif %}">编译发布 <span id="broken-links-badge" class="badge badge-warning" style="display: none;"></span></a>
         </aside>
         <main class="admin-content">
             {% block content %}{% endblock %}
         </main>
     </div>
     <script>
+        // Count of internal links pointing at missing pages
+        (function() {
+            var badge = document.getElementById('broken-links-badge');
+            fetch('/api/admin/content/broken-links')
+                .then(function(response) { return response.json(); })
+                .then(function(data) {
+                    if (data.status !== 'success' || data.count === 0) return;
+                    badge.textContent = data.count;
+                    badge.title = '失效的站内链接：\n' + data.broken_links.map(function(link) {
+                        return link.source_file + ':' + link.line + ' → ' + link.url;
+                    }).join('\n');
+                    badge.style.display = 'inline-block';
+                })
+                .catch(function() {});
+        })();
+
+        // Live Markdown preview for the item editor
+        (function() {
+            var editor = document.getElementById('content');
+            var preview = document.getElementById('markdown-preview');
+            if (!editor || !preview) return;
+            var status = document.getElementById('markdown-preview-status');
+            var timer = null;
+            var latest = 0;
+
+            function render() {
+                var request = ++latest;
+                fetch('/api/admin/preview', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ content: editor.value, slug: preview.dataset.slug || null })
+                })
+                    .then(function(response) { return response.json(); })
+                    .then(function(data) {
+                        // Ignore responses overtaken by a newer request
+                        if (request !== latest) return;
+                        if (data.status === 'success') {
+                            preview.innerHTML = data.html;
+                            status.textContent = data.missing_attachments.length
+                                ? '缺少附件: ' + data.missing_attachments.join(', ')
+                                : '预览';
+                        } else {
+                            status.textContent = '预览失败: ' + data.message;
+                        }
+                    })
+                    .catch(function(error) {
+                        if (request === latest) status.textContent = '预览失败: ' + error.message;
+                    });
+            }
+
+            editor.addEventListener('input', function() {
+                clearTimeout(timer);
+                timer = setTimeout(render, 300);
+            });
+            render();
+        })();
+
         // Auto-hide messages after 3 seconds
         setTimeout(function() {
             var messages = document.querySelectorAll('.message');
@@ -1303,6 +1721,17 @@ document.addEventListener('DOMContentLoaded', function() {
     </div>
 </div>
 
+<div class="card">
+    <h2>模板</h2>
+    <p>当前使用：{% if overview.template_source == "directory" %}模板目录 <code>{{ config.paths.templates_dir }}</code>{% else %}内置模板{% endif %}</p>
+    <p>模板指纹：<code title="{{ overview.template_fingerprint }}">{{ overview.template_fingerprint | truncate(length=12, end="") }}</code></p>
+    {% if overview.templates_stale %}
+    <p style="color: #e67e22;">模板在上次全量编译后有改动，页面仍是旧模板渲染的结果，<a href="/admin/compile">重新编译</a>后生效。</p>
+    {% else %}
+    <p style="color: #27ae60;">已编译的页面与当前模板一致。</p>
+    {% endif %}
+</div>
+
 <div class="card">
     <h2>快捷操作</h2>
     <div style="display: flex; gap: 15px; flex-wrap: wrap;">
@@ -1312,6 +1741,160 @@ document.addEventListener('DOMContentLoaded', function() {
     </div>
 </div>
 
+<div class="card">
+    <h2>备份</h2>
+    <p style="color: #888; margin-bottom: 15px;">下载内容目录的 zip 压缩包。单个分类的备份可直接通过分类上传重新导入。</p>
+    <form method="get" action="/api/admin/export" style="display: flex; gap: 15px; flex-wrap: wrap; align-items: center;">
+        <input type="text" name="category" placeholder="分类（留空导出全部）" style="max-width: 240px;">
+        <label style="display: flex; align-items: center; gap: 6px;">
+            <input type="checkbox" name="include" value="generated"> 包含数据库（完整备份）
+        </label>
+        <button type="submit" class="btn">下载备份</button>
+    </form>
+
+    <h3 style="margin: 25px 0 15px;">导入全站</h3>
+    <p style="color: #888; margin-bottom: 15px;">上传包含多个分类目录的 .zip 或 .tar.gz 压缩包，导入后自动重新编译。</p>
+    <form id="importSiteForm" enctype="multipart/form-data" style="display: flex; gap: 15px; flex-wrap: wrap; align-items: center;">
+        <input type="file" name="file" accept=".zip,.tar.gz,.tgz" required>
+        <select name="mode">
+            <option value="replace">替换全部内容</option>
+            <option value="merge">合并到现有内容</option>
+        </select>
+        <button type="submit" class="btn">上传并导入</button>
+    </form>
+    <div id="importResult" style="margin-top: 15px; display: none;"></div>
+</div>
+
+<script>
+document.getElementById('importSiteForm').addEventListener('submit', async function(e) {
+    e.preventDefault();
+
+    var mode = this.querySelector('select[name="mode"]').value;
+    if (mode === 'replace' && !confirm('将用压缩包内容替换整个内容目录，确定继续吗？')) {
+        return;
+    }
+
+    var formData = new FormData();
+    formData.append('file', this.querySelector('input[name="file"]').files[0]);
+    var resultDiv = document.getElementById('importResult');
+    var submitBtn = this.querySelector('button[type="submit"]');
+
+    submitBtn.disabled = true;
+    submitBtn.textContent = '导入中...';
+    resultDiv.style.display = 'none';
+
+    try {
+        var response = await fetch('/api/admin/upload/full?mode=' + mode, {
+            method: 'POST',
+            body: formData
+        });
+        var data = await response.json();
+
+        resultDiv.style.display = 'block';
+        if (response.ok) {
+            resultDiv.innerHTML = '<div style="color: #155724; padding: 10px; background: #d4edda; border-radius: 6px;">' + data.message + '</div>';
+        } else {
+            resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">错误: ' + data.message + '</div>';
+        }
+    } catch (error) {
+        resultDiv.style.display = 'block';
+        resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">请求失败: ' + error.message + '</div>';
+    }
+
+    submitBtn.disabled = false;
+    submitBtn.textContent = '上传并导入';
+});
+</script>
+
+<div class="card">
+    <h2>数据库维护</h2>
+    <p style="color: #888; margin-bottom: 15px;">上次整理: {% if overview.last_vacuum_at %}{{ overview.last_vacuum_at }}{% else %}从未{% endif %}（每周自动整理一次）</p>
+    <div style="display: flex; gap: 15px; flex-wrap: wrap;">
+        <button type="button" class="btn" data-db-action="vacuum" data-method="POST">整理数据库 (VACUUM)</button>
+        <button type="button" class="btn" data-db-action="integrity" data-method="GET">完整性检查</button>
+        <button type="button" class="btn" data-db-action="analyze" data-method="POST">更新统计信息 (ANALYZE)</button>
+    </div>
+    <div id="dbMaintenanceResult" style="margin-top: 15px; display: none;"></div>
+</div>
+
+<script>
+document.querySelectorAll('[data-db-action]').forEach(function(button) {
+    button.addEventListener('click', async function() {
+        var resultDiv = document.getElementById('dbMaintenanceResult');
+        var label = button.textContent;
+        button.disabled = true;
+        button.textContent = '执行中...';
+        resultDiv.style.display = 'none';
+
+        try {
+            var response = await fetch('/api/admin/db/' + button.dataset.dbAction, { method: button.dataset.method });
+            var data = await response.json();
+            var ok = response.ok && data.ok !== false;
+            resultDiv.style.display = 'block';
+            resultDiv.innerHTML = ok
+                ? '<div style="color: #155724; padding: 10px; background: #d4edda; border-radius: 6px;">' + data.message + '</div>'
+                : '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">错误: ' + data.message + '</div>';
+        } catch (error) {
+            resultDiv.style.display = 'block';
+            resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">请求失败: ' + error.message + '</div>';
+        }
+
+        button.disabled = false;
+        button.textContent = label;
+    });
+});
+</script>
+
+<div class="card">
+    <h2>修改密码</h2>
+    <p style="color: #888; margin-bottom: 15px;">新密码至少 8 个字符，以哈希形式写入配置文件。修改后所有已登录的会话都会失效。</p>
+    <form id="changePasswordForm" style="display: flex; gap: 15px; flex-wrap: wrap; align-items: center;">
+        <input type="password" name="current_password" placeholder="当前密码" autocomplete="current-password" required style="max-width: 200px;">
+        <input type="password" name="new_password" placeholder="新密码" autocomplete="new-password" minlength="8" required style="max-width: 200px;">
+        <input type="password" name="confirm_password" placeholder="确认新密码" autocomplete="new-password" minlength="8" required style="max-width: 200px;">
+        <button type="submit" class="btn">修改密码</button>
+    </form>
+    <div id="changePasswordResult" style="margin-top: 15px; display: none;"></div>
+</div>
+
+<script>
+document.getElementById('changePasswordForm').addEventListener('submit', async function(e) {
+    e.preventDefault();
+
+    var form = this;
+    var resultDiv = document.getElementById('changePasswordResult');
+    var body = {
+        current_password: form.current_password.value,
+        new_password: form.new_password.value,
+        confirm_password: form.confirm_password.value
+    };
+    if (body.new_password !== body.confirm_password) {
+        resultDiv.style.display = 'block';
+        resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">两次输入的新密码不一致</div>';
+        return;
+    }
+
+    try {
+        var response = await fetch('/api/admin/password', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(body)
+        });
+        var data = await response.json();
+        resultDiv.style.display = 'block';
+        if (response.ok) {
+            resultDiv.innerHTML = '<div style="color: #155724; padding: 10px; background: #d4edda; border-radius: 6px;">' + data.message + '</div>';
+            setTimeout(function() { window.location.href = '/admin/login'; }, 1500);
+        } else {
+            resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">错误: ' + data.message + '</div>';
+        }
+    } catch (error) {
+        resultDiv.style.display = 'block';
+        resultDiv.innerHTML = '<div style="color: #721c24; padding: 10px; background: #f8d7da; border-radius: 6px;">请求失败: ' + error.message + '</div>';
+    }
+});
+</script>
+
 {% if last_compiled %}
 <div class="card">
     <h2>编译信息</h2>
@@ -1484,31 +2067,48 @@ document.getElementById('uploadCategoryForm').addEventListener('submit', async f
         {% endif %}
     </p>
     {% else %}
+    <div id="bulkBar" style="display: none; align-items: center; gap: 10px; padding: 10px; margin-bottom: 10px; background: #f8f9fa; border-radius: 6px;">
+        <span>已选择 <strong id="bulkCount">0</strong> 篇</span>
+        {% if show_drafts == 'true' %}
+        <button type="button" class="btn btn-sm btn-success" onclick="bulkAction('publish')">批量发布</button>
+        {% else %}
+        <button type="button" class="btn btn-sm" onclick="bulkAction('unpublish')">批量撤回为草稿</button>
+        {% endif %}
+        <button type="button" class="btn btn-sm btn-danger" onclick="bulkAction('delete')">批量删除</button>
+    </div>
     <table class="table">
         <thead>
             <tr>
+                <th><input type="checkbox" id="bulkSelectAll" title="全选"></th>
                 <th>标题</th>
                 <th>分类</th>
                 <th>状态</th>
+                <th>浏览量</th>
                 <th>操作</th>
             </tr>
         </thead>
         <tbody>
             {% for item in items %}
             <tr>
+                <td><input type="checkbox" class="bulk-select" value="{{ item.slug }}"></td>
                 <td>{{ item.title }}</td>
                 <td>{{ item.category }}</td>
                 <td>
                     {% if item.is_draft %}
                     <span class="badge badge-warning">草稿</span>
+                    {% elif item.is_scheduled %}
+                    <span class="badge badge-info" title="{{ item.publish_at }} UTC">定时</span>
+                    {% if item.publish_at_local %}<div style="font-size: 12px; color: #666; margin-top: 4px;">{{ item.publish_at_local }} 上线</div>{% endif %}
                     {% else %}
                     <span class="badge badge-success">已发布</span>
                     {% endif %}
                 </td>
+                <td>{{ item.view_count | default(value=0) }}</td>
                 <td>
                     <div class="action-btns">
                         <a href="/{{ item.slug }}" class="btn btn-sm" target="_blank">查看</a>
                         <a href="/admin/items/{{ item.slug }}/edit" class="btn btn-sm">编辑</a>
+                        <a href="/admin/items/{{ item.slug }}/stats" class="btn btn-sm">统计</a>
                         {% if item.is_draft %}
                         <form action="/admin/items/{{ item.slug }}/publish" method="POST" style="display: inline;">
                             <button type="submit" class="btn btn-sm btn-success">发布</button>
@@ -1525,6 +2125,68 @@ document.getElementById('uploadCategoryForm').addEventListener('submit', async f
     </table>
     {% endif %}
 </div>
+
+<script>
+(function() {
+    var selectAll = document.getElementById('bulkSelectAll');
+    var boxes = document.querySelectorAll('.bulk-select');
+    var bar = document.getElementById('bulkBar');
+    if (!bar) return;
+
+    function selectedSlugs() {
+        return Array.prototype.filter.call(boxes, function(box) { return box.checked; })
+            .map(function(box) { return box.value; });
+    }
+
+    function updateBar() {
+        var count = selectedSlugs().length;
+        document.getElementById('bulkCount').textContent = count;
+        bar.style.display = count > 0 ? 'flex' : 'none';
+        selectAll.checked = count > 0 && count === boxes.length;
+    }
+
+    selectAll.addEventListener('change', function() {
+        boxes.forEach(function(box) { box.checked = selectAll.checked; });
+        updateBar();
+    });
+    boxes.forEach(function(box) { box.addEventListener('change', updateBar); });
+
+    var labels = { delete: '删除', publish: '发布', unpublish: '撤回为草稿' };
+    window.bulkAction = async function(action) {
+        var slugs = selectedSlugs();
+        if (slugs.length === 0) return;
+        if (!confirm('确定要' + labels[action] + '选中的 ' + slugs.length + ' 篇文章吗？')) return;
+
+        var message;
+        var success = false;
+        try {
+            var response = await fetch('/api/admin/items/bulk', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ action: action, slugs: slugs })
+            });
+            var data = await response.json();
+            if (response.ok) {
+                message = labels[action] + '成功 ' + data.succeeded.length + ' 篇';
+                if (data.failed.length > 0) {
+                    message += '，失败 ' + data.failed.length + ' 篇：' + data.failed.map(function(f) {
+                        return f.slug + '（' + f.error + '）';
+                    }).join('；');
+                }
+                success = data.failed.length === 0;
+            } else {
+                message = '错误: ' + data.message;
+            }
+        } catch (error) {
+            message = '请求失败: ' + error.message;
+        }
+        var params = new URLSearchParams(window.location.search);
+        params.set('message', message);
+        params.set('success', success);
+        window.location.search = params.toString();
+    };
+})();
+</script>
 {% endblock content %}"#,
         )?;
 
@@ -1613,7 +2275,13 @@ document.getElementById('uploadCategoryForm').addEventListener('submit', async f
         </div>
         <div class="form-group">
             <label for="content">文章内容 (Markdown)</label>
-            <textarea id="content" name="content" placeholder="使用 Markdown 格式编写文章内容"></textarea>
+            <div class="editor-split">
+                <textarea id="content" name="content" placeholder="使用 Markdown 格式编写文章内容"></textarea>
+                <div>
+                    <div class="editor-preview-status" id="markdown-preview-status">预览</div>
+                    <div class="editor-preview content" id="markdown-preview"></div>
+                </div>
+            </div>
         </div>
         <div style="display: flex; gap: 15px;">
             <button type="submit" class="btn">保存文章</button>
@@ -1721,7 +2389,18 @@ document.getElementById('uploadItemForm').addEventListener('submit', async funct
         </div>
         <div class="form-group">
             <label for="content">文章内容 (Markdown)</label>
-            <textarea id="content" name="content">{{ item.content }}</textarea>
+            <div style="display: flex; gap: 10px; align-items: center; margin-bottom: 8px;">
+                <input type="file" id="attachment-file" style="display: none;">
+                <button type="button" class="btn" id="attachment-button" style="padding: 6px 12px;">插入附件</button>
+                <span id="attachment-status" style="color: #7f8c8d; font-size: 0.9em;"></span>
+            </div>
+            <div class="editor-split">
+                <textarea id="content" name="content">{{ item.content }}</textarea>
+                <div>
+                    <div class="editor-preview-status" id="markdown-preview-status">预览</div>
+                    <div class="editor-preview content" id="markdown-preview" data-slug="{{ item.slug }}"></div>
+                </div>
+            </div>
         </div>
         <div style="display: flex; gap: 15px;">
             <button type="submit" class="btn">保存修改</button>
@@ -1729,6 +2408,50 @@ document.getElementById('uploadItemForm').addEventListener('submit', async funct
         </div>
     </form>
 </div>
+<script>
+(function() {
+    const fileInput = document.getElementById('attachment-file');
+    const status = document.getElementById('attachment-status');
+    const editor = document.getElementById('content');
+
+    document.getElementById('attachment-button').addEventListener('click', function() {
+        fileInput.click();
+    });
+
+    fileInput.addEventListener('change', async function() {
+        const file = fileInput.files[0];
+        if (!file) return;
+        const formData = new FormData();
+        formData.append('file', file);
+        status.textContent = '正在上传 ' + file.name + '...';
+
+        try {
+            const response = await fetch('/api/admin/items/{{ item.slug | urlencode }}/attachments', {
+                method: 'POST',
+                body: formData
+            });
+            const data = await response.json();
+            if (data.status !== 'success') {
+                status.textContent = '上传失败: ' + data.message;
+                return;
+            }
+
+            // 在光标处插入 Markdown 片段
+            const snippet = data.attachment.markdown;
+            const start = editor.selectionStart;
+            const end = editor.selectionEnd;
+            editor.value = editor.value.slice(0, start) + snippet + editor.value.slice(end);
+            editor.focus();
+            editor.selectionStart = editor.selectionEnd = start + snippet.length;
+            status.textContent = '已插入 ' + data.attachment.filename;
+        } catch (e) {
+            status.textContent = '上传失败: ' + e;
+        } finally {
+            fileInput.value = '';
+        }
+    });
+})();
+</script>
 {% endblock content %}"#,
         )?;
 
@@ -1742,6 +2465,17 @@ document.getElementById('uploadItemForm').addEventListener('submit', async funct
 <div class="message message-{% if success %}success{% else %}error{% endif %}">{{ message }}</div>
 {% endif %}
 
+<div class="card">
+    <h2>实时访客</h2>
+    <div class="stats-grid">
+        <div class="stat-card">
+            <h3 id="liveVisitors">-</h3>
+            <p>最近 <span id="liveMinutes">5</span> 分钟内的访客</p>
+        </div>
+    </div>
+    <div id="livePages"></div>
+</div>
+
 <div class="card">
     <h2>访问概览</h2>
     <div class="stats-grid">
@@ -1773,14 +2507,28 @@ document.getElementById('uploadItemForm').addEventListener('submit', async funct
 </div>
 
 <div class="card">
-    <h2>搜索关键词</h2>
-    <div id="searchKeywords">
+    <h2>访问设备</h2>
+    <div id="deviceStats">
+        <p style="color: #888; text-align: center; padding: 20px;">加载中...</p>
+    </div>
+</div>
+
+<div class="card" id="countryStatsCard" style="display: none;">
+    <h2>访客国家</h2>
+    <div id="countryStats">
         <p style="color: #888; text-align: center; padding: 20px;">加载中...</p>
     </div>
 </div>
 
 <div class="card">
-    <h2>每日访问统计</h2>
+    <h2>搜索关键词</h2>
+    <div id="searchKeywords">
+        <p style="color: #888; text-align: center; padding: 20px;">加载中...</p>
+    </div>
+</div>
+
+<div class="card">
+    <h2>每日访问统计</h2>
     <div style="margin-bottom: 15px;">
         <label for="dateRange" style="margin-right: 10px;">时间范围：</label>
         <select id="dateRange" onchange="loadDailyStats()">
@@ -1792,9 +2540,126 @@ document.getElementById('uploadItemForm').addEventListener('submit', async funct
     <div id="dailyStats">
         <p style="color: #888; text-align: center; padding: 20px;">加载中...</p>
     </div>
+    <div id="hourlyBreakdown" style="display: none; margin-top: 15px;">
+        <h3 id="hourlyTitle"></h3>
+        <div id="hourlyChart" style="display: flex; align-items: flex-end; gap: 2px; height: 120px;"></div>
+    </div>
+</div>
+
+<div class="card">
+    <h2>访问时段分布</h2>
+    <div style="margin-bottom: 15px;">
+        <label for="heatmapRange" style="margin-right: 10px;">时间范围：</label>
+        <select id="heatmapRange" onchange="loadHeatmap()">
+            <option value="7">最近7天</option>
+            <option value="30" selected>最近30天</option>
+            <option value="90">最近90天</option>
+        </select>
+        <span style="color: #888; margin-left: 10px;">时间为 UTC</span>
+    </div>
+    <div id="heatmap" style="overflow-x: auto;">
+        <p style="color: #888; text-align: center; padding: 20px;">加载中...</p>
+    </div>
 </div>
 
 <script>
+const weekdayNames = ['周日', '周一', '周二', '周三', '周四', '周五', '周六'];
+
+// 按最大值计算格子颜色深浅
+function heatColor(count, max) {
+    const alpha = max > 0 ? count / max : 0;
+    return `rgba(40, 167, 69, ${alpha.toFixed(2)})`;
+}
+
+async function loadHeatmap() {
+    const days = document.getElementById('heatmapRange').value;
+    try {
+        const response = await fetch(`/api/analytics/heatmap?days=${days}`);
+        const data = await response.json();
+
+        if (data.status === 'success' && data.max_count > 0) {
+            const hours = Array.from({ length: 24 }, (_, hour) => hour);
+            const html = `
+                <table class="table" style="font-size: 12px; text-align: center;">
+                    <thead>
+                        <tr><th></th>${hours.map(hour => `<th>${hour}</th>`).join('')}</tr>
+                    </thead>
+                    <tbody>
+                        ${weekdayNames.map((name, weekday) => `
+                            <tr>
+                                <th>${name}</th>
+                                ${data.cells.filter(cell => cell.weekday === weekday).map(cell => `
+                                    <td title="${name} ${cell.hour}:00 - ${cell.visit_count} 次访问"
+                                        style="background: ${heatColor(cell.visit_count, data.max_count)}; min-width: 20px;">${cell.visit_count || ''}</td>
+                                `).join('')}
+                            </tr>
+                        `).join('')}
+                    </tbody>
+                </table>
+            `;
+            document.getElementById('heatmap').innerHTML = html;
+        } else {
+            document.getElementById('heatmap').innerHTML = '<p style="color: #888; text-align: center; padding: 20px;">暂无数据</p>';
+        }
+    } catch (error) {
+        console.error('加载访问时段分布失败:', error);
+        document.getElementById('heatmap').innerHTML = '<p style="color: #c00; text-align: center; padding: 20px;">加载失败</p>';
+    }
+}
+
+// 每日统计中点击日期后显示当天每小时的访问量
+async function loadHourlyBreakdown(date) {
+    try {
+        const response = await fetch(`/api/analytics/hourly?date=${date}`);
+        const data = await response.json();
+        if (data.status !== 'success') {
+            return;
+        }
+
+        const max = Math.max(1, ...data.hours.map(hour => hour.visit_count));
+        document.getElementById('hourlyTitle').textContent = `${data.date} 每小时访问量（UTC）`;
+        document.getElementById('hourlyChart').innerHTML = data.hours.map(hour => `
+            <div title="${hour.hour}:00 - ${hour.visit_count} 次访问"
+                 style="flex: 1; background: #28a745; height: ${Math.round(hour.visit_count * 100 / max)}%; min-height: 1px;"></div>
+        `).join('');
+        document.getElementById('hourlyBreakdown').style.display = 'block';
+    } catch (error) {
+        console.error('加载每小时访问量失败:', error);
+    }
+}
+
+async function loadRealtime() {
+    try {
+        const response = await fetch('/api/analytics/realtime');
+        const data = await response.json();
+
+        if (data.status === 'success') {
+            document.getElementById('liveVisitors').textContent = data.active_visitors;
+            document.getElementById('liveMinutes').textContent = data.within_minutes;
+            document.getElementById('livePages').innerHTML = data.top_pages.length > 0 ? `
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>正在访问的页面</th>
+                            <th>访问量</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        ${data.top_pages.map(page => `
+                            <tr>
+                                <td><a href="/${page.slug}" target="_blank">${page.slug}</a></td>
+                                <td>${page.count}</td>
+                            </tr>
+                        `).join('')}
+                    </tbody>
+                </table>
+            ` : '';
+        }
+    } catch (error) {
+        console.error('加载实时访客失败:', error);
+    }
+}
+
 async function loadTotalStats() {
     try {
         const response = await fetch('/api/analytics/total-stats');
@@ -1879,6 +2744,83 @@ async function loadPopularPages() {
     }
 }
 
+async function loadDeviceStats() {
+    const labels = { mobile: '手机', tablet: '平板', desktop: '桌面', bot: '爬虫', unknown: '未知' };
+    try {
+        const response = await fetch('/api/analytics/device-stats');
+        const data = await response.json();
+        
+        if (data.status === 'success' && data.devices.length > 0) {
+            const html = `
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>设备类型</th>
+                            <th>访问量</th>
+                            <th>占比</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        ${data.devices.map(device => `
+                            <tr>
+                                <td>${labels[device.device_type] || device.device_type}</td>
+                                <td>${device.count}</td>
+                                <td>${device.percentage.toFixed(1)}%</td>
+                            </tr>
+                        `).join('')}
+                    </tbody>
+                </table>
+            `;
+            document.getElementById('deviceStats').innerHTML = html;
+        } else {
+            document.getElementById('deviceStats').innerHTML = '<p style="color: #888; text-align: center; padding: 20px;">暂无数据</p>';
+        }
+    } catch (error) {
+        console.error('加载设备统计失败:', error);
+        document.getElementById('deviceStats').innerHTML = '<p style="color: #c00; text-align: center; padding: 20px;">加载失败</p>';
+    }
+}
+
+async function loadCountryStats() {
+    try {
+        const response = await fetch('/api/analytics/countries?limit=10');
+        const data = await response.json();
+        // 未配置 GeoIP 数据库时不显示
+        if (data.status !== 'success' || (!data.geoip_enabled && data.countries.length === 0)) {
+            return;
+        }
+        document.getElementById('countryStatsCard').style.display = '';
+
+        if (data.countries.length > 0) {
+            const html = `
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>国家</th>
+                            <th>访问量</th>
+                            <th>占比</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        ${data.countries.map(country => `
+                            <tr>
+                                <td>${country.country}</td>
+                                <td>${country.count}</td>
+                                <td>${country.percentage.toFixed(1)}%</td>
+                            </tr>
+                        `).join('')}
+                    </tbody>
+                </table>
+            `;
+            document.getElementById('countryStats').innerHTML = html;
+        } else {
+            document.getElementById('countryStats').innerHTML = '<p style="color: #888; text-align: center; padding: 20px;">暂无数据</p>';
+        }
+    } catch (error) {
+        console.error('加载国家统计失败:', error);
+    }
+}
+
 async function loadSearchKeywords() {
     try {
         const response = await fetch('/api/analytics/search-keywords?limit=15');
@@ -1937,7 +2879,7 @@ async function loadDailyStats() {
                     <tbody>
                         ${data.stats.map(stat => `
                             <tr>
-                                <td>${stat.date}</td>
+                                <td><a href="javascript:void(0)" onclick="loadHourlyBreakdown('${stat.date}')">${stat.date}</a></td>
                                 <td>${stat.total_visits}</td>
                                 <td>${stat.unique_visitors}</td>
                                 <td>${stat.page_views}</td>
@@ -1956,16 +2898,315 @@ async function loadDailyStats() {
     }
 }
 
-// 页面加载时初始化所有数据
+// 页面加载时初始化所有数据，实时访客每 30 秒刷新
+loadRealtime();
+setInterval(loadRealtime, 30000);
 loadTotalStats();
 loadSourceStats();
 loadPopularPages();
+loadDeviceStats();
+loadCountryStats();
 loadSearchKeywords();
 loadDailyStats();
+loadHeatmap();
 </script>
 {% endblock content %}"#,
         )?;
 
+        // Admin per-item analytics template
+        tera.add_raw_template(
+            "admin_item_stats.html",
+            r#"{% extends "admin_base.html" %}
+{% block title %}访问统计 - {{ stats.title }}{% endblock %}
+{% block content %}
+<div class="card">
+    <h2>{{ stats.title }}</h2>
+    <form method="GET" style="margin-bottom: 20px;">
+        <label for="days" style="margin-right: 10px;">时间范围：</label>
+        <select id="days" name="days" onchange="this.form.submit()">
+            {% for option in [7, 30, 90, 365] %}
+            <option value="{{ option }}"{% if option == days %} selected{% endif %}>最近{{ option }}天</option>
+            {% endfor %}
+        </select>
+        <span style="margin-left: 10px; color: #888;">{{ stats.start_date }} ~ {{ stats.end_date }}</span>
+        <a href="/{{ stats.slug }}" class="btn btn-sm" target="_blank" style="float: right;">查看文章</a>
+    </form>
+    <div class="stats-grid">
+        <div class="stat-card">
+            <h3>{{ stats.total_visits }}</h3>
+            <p>访问量</p>
+        </div>
+        <div class="stat-card">
+            <h3>{{ stats.unique_visitors }}</h3>
+            <p>独立访客</p>
+        </div>
+    </div>
+    <svg viewBox="0 0 {{ sparkline_width }} {{ sparkline_height }}" preserveAspectRatio="none" style="width: 100%; height: 80px; margin-top: 20px;" role="img" aria-label="每日访问趋势">
+        <polyline points="{{ sparkline }}" fill="none" style="stroke: #667eea;" stroke-width="2" vector-effect="non-scaling-stroke"/>
+    </svg>
+</div>
+
+<div class="grid-2">
+    <div class="card">
+        <h2>访问来源</h2>
+        {% if stats.sources %}
+        <table class="table">
+            <thead>
+                <tr>
+                    <th>来源类型</th>
+                    <th>访问量</th>
+                    <th>占比</th>
+                </tr>
+            </thead>
+            <tbody>
+                {% for source in stats.sources %}
+                <tr>
+                    <td>{{ source.source }}</td>
+                    <td>{{ source.count }}</td>
+                    <td>{{ source.percentage | round(precision=1) }}%</td>
+                </tr>
+                {% endfor %}
+            </tbody>
+        </table>
+        {% else %}
+        <p style="color: #888; text-align: center; padding: 20px;">暂无数据</p>
+        {% endif %}
+    </div>
+
+    <div class="card">
+        <h2>搜索关键词</h2>
+        {% if stats.keywords %}
+        <table class="table">
+            <thead>
+                <tr>
+                    <th>关键词</th>
+                    <th>搜索次数</th>
+                </tr>
+            </thead>
+            <tbody>
+                {% for keyword in stats.keywords %}
+                <tr>
+                    <td>{{ keyword.0 }}</td>
+                    <td>{{ keyword.1 }}</td>
+                </tr>
+                {% endfor %}
+            </tbody>
+        </table>
+        {% else %}
+        <p style="color: #888; text-align: center; padding: 20px;">暂无数据</p>
+        {% endif %}
+    </div>
+</div>
+
+<div class="card">
+    <h2>每日访问</h2>
+    <table class="table">
+        <thead>
+            <tr>
+                <th>日期</th>
+                <th>访问量</th>
+                <th>独立访客</th>
+            </tr>
+        </thead>
+        <tbody>
+            {% for day in stats.daily | reverse %}
+            <tr>
+                <td>{{ day.date }}</td>
+                <td>{{ day.visits }}</td>
+                <td>{{ day.unique_visitors }}</td>
+            </tr>
+            {% endfor %}
+        </tbody>
+    </table>
+</div>
+{% endblock content %}"#,
+        )?;
+
+        // Admin attachments template
+        tera.add_raw_template(
+            "admin_attachments.html",
+            r#"{% extends "admin_base.html" %}
+{% block title %}附件管理{% endblock %}
+{% block content %}
+<div class="card">
+    <h2>附件管理</h2>
+    <form method="GET" action="/admin/attachments" style="margin-bottom: 20px; display: flex; gap: 10px; align-items: center;">
+        <label for="slug">文章</label>
+        <select id="slug" name="slug" style="padding: 8px 10px; border: 1px solid #ddd; border-radius: 6px; max-width: 320px;" onchange="this.form.submit()">
+            <option value="">全部</option>
+            {% for s in slugs %}
+            <option value="{{ s }}" {% if s == slug %}selected{% endif %}>{{ s }}</option>
+            {% endfor %}
+        </select>
+        <span style="color: #888;">共 {{ attachments | length }} 个附件</span>
+    </form>
+
+    {% if attachments | length > 0 %}
+    <div class="attachment-grid">
+        {% for attachment in attachments %}
+        <div class="attachment-card" id="attachment-{{ loop.index }}">
+            <a class="attachment-thumb" href="/attachment/{{ attachment.filename | urlencode }}" target="_blank">
+                {% if attachment.mime_type is starting_with("image/") %}
+                <img src="/attachment/{{ attachment.filename | urlencode }}" alt="{{ attachment.original_name }}" loading="lazy">
+                {% else %}
+                <span>{{ attachment.mime_type }}</span>
+                {% endif %}
+            </a>
+            <div class="attachment-info">
+                <strong title="{{ attachment.filename }}">{{ attachment.original_name }}</strong>
+                <small>{{ attachment.slug }}</small>
+                <small>{{ attachment.file_size | filesizeformat }} · {{ attachment.updated_at | truncate(length=10, end="") }}</small>
+            </div>
+            <button type="button" class="btn btn-sm btn-danger" data-filename="{{ attachment.filename }}" data-card="attachment-{{ loop.index }}">删除</button>
+        </div>
+        {% endfor %}
+    </div>
+    {% else %}
+    <p style="color: #888; text-align: center; padding: 20px;">暂无附件</p>
+    {% endif %}
+</div>
+
+<style>
+    .attachment-grid {
+        display: grid;
+        grid-template-columns: repeat(auto-fill, minmax(180px, 1fr));
+        gap: 16px;
+    }
+    .attachment-card {
+        border: 1px solid #eee;
+        border-radius: 8px;
+        overflow: hidden;
+        display: flex;
+        flex-direction: column;
+    }
+    .attachment-thumb {
+        height: 140px;
+        background: #f8f9fa;
+        display: flex;
+        align-items: center;
+        justify-content: center;
+        color: #888;
+        text-decoration: none;
+        font-size: 0.85em;
+    }
+    .attachment-thumb img {
+        width: 100%;
+        height: 100%;
+        object-fit: cover;
+    }
+    .attachment-info {
+        padding: 8px 10px;
+        display: flex;
+        flex-direction: column;
+        gap: 2px;
+        flex: 1;
+    }
+    .attachment-info strong {
+        overflow: hidden;
+        text-overflow: ellipsis;
+        white-space: nowrap;
+    }
+    .attachment-info small {
+        color: #888;
+    }
+    .attachment-card .btn {
+        margin: 0 10px 10px;
+    }
+</style>
+
+<script>
+    document.querySelectorAll('.attachment-card button[data-filename]').forEach(function(button) {
+        button.addEventListener('click', async function() {
+            var filename = button.dataset.filename;
+            if (!confirm('确定删除附件 ' + filename + '？内容目录中的源文件也会被删除。')) {
+                return;
+            }
+            button.disabled = true;
+            try {
+                var response = await fetch('/api/admin/attachments/' + encodeURIComponent(filename), { method: 'DELETE' });
+                var data = await response.json();
+                if (!response.ok) {
+                    throw new Error(data.message || ('HTTP ' + response.status));
+                }
+                document.getElementById(button.dataset.card).remove();
+            } catch (error) {
+                button.disabled = false;
+                alert('删除失败: ' + error.message);
+            }
+        });
+    });
+</script>
+{% endblock content %}"#,
+        )?;
+
+        // Admin comments template
+        tera.add_raw_template(
+            "admin_comments.html",
+            r#"{% extends "admin_base.html" %}
+{% block title %}评论管理{% endblock %}
+{% block content %}
+{% if message %}
+<div class="message message-{% if success %}success{% else %}error{% endif %}">{{ message }}</div>
+{% endif %}
+
+<div class="card">
+    <h2>评论管理</h2>
+    <form method="GET" action="/admin/comments" style="margin-bottom: 20px; display: flex; gap: 10px; align-items: center;">
+        <label for="slug">文章</label>
+        <select id="slug" name="slug" style="padding: 8px 10px; border: 1px solid #ddd; border-radius: 6px; max-width: 320px;" onchange="this.form.submit()">
+            <option value="">全部</option>
+            {% for s in slugs %}
+            <option value="{{ s }}" {% if s == slug %}selected{% endif %}>{{ s }}</option>
+            {% endfor %}
+        </select>
+        <span style="color: #888;">共 {{ comments | length }} 条评论</span>
+    </form>
+
+    {% if comments | length > 0 %}
+    <table class="table">
+        <thead>
+            <tr>
+                <th>文章</th>
+                <th>作者</th>
+                <th>内容</th>
+                <th>时间</th>
+                <th>操作</th>
+            </tr>
+        </thead>
+        <tbody>
+            {% for entry in comments %}
+            <tr id="comment-{{ entry.comment.id }}">
+                <td>
+                    <a href="/{{ entry.comment.slug }}" target="_blank">{{ entry.title }}</a>
+                    {% if entry.muted %}<span class="badge badge-warning" title="该文章的新评论不发送通知">已静音</span>{% endif %}
+                </td>
+                <td>
+                    {% if entry.comment.website %}<a href="{{ entry.comment.website }}" target="_blank" rel="nofollow ugc noopener">{{ entry.comment.author }}</a>{% else %}{{ entry.comment.author }}{% endif %}
+                    {% if entry.email %}<br><small style="color: #888;">{{ entry.email }}</small>{% endif %}
+                    {% if entry.comment.parent_id %}<br><small style="color: #888;">回复</small>{% endif %}
+                </td>
+                <td style="white-space: pre-wrap; max-width: 420px;">{{ entry.comment.content }}</td>
+                <td>{{ entry.comment.created_at | truncate(length=16, end="") | replace(from="T", to=" ") }}</td>
+                <td>
+                    <form action="/admin/comments/{{ entry.comment.id }}/delete" method="POST" style="display: inline;" onsubmit="return confirm('确定要删除这条评论吗？它的回复也会被删除。')">
+                        <button type="submit" class="btn btn-sm btn-danger">删除</button>
+                    </form>
+                </td>
+            </tr>
+            {% endfor %}
+        </tbody>
+    </table>
+    {% else %}
+    <p style="color: #888; text-align: center; padding: 20px;">暂无评论</p>
+    {% endif %}
+</div>
+
+<style>
+    tr:target { background: #fff3cd; }
+</style>
+{% endblock content %}"#,
+        )?;
+
         // Admin compile template
         tera.add_raw_template(
             "admin_compile.html",
@@ -1986,6 +3227,12 @@ loadDailyStats();
             开始编译
         </button>
     </form>
+    <div id="compileProgress" style="margin-top: 20px; display: none;">
+        <div style="height: 6px; background: #e9ecef; border-radius: 3px; overflow: hidden;">
+            <div class="compile-progress-bar" id="progressBar"></div>
+        </div>
+        <p id="progressText" style="margin-top: 8px; color: #666;">编译中...</p>
+    </div>
     <div id="compileResult" style="margin-top: 20px; display: none;">
         <div class="card" style="background: #f8f9fa;">
             <h3>编译结果</h3>
@@ -1994,17 +3241,100 @@ loadDailyStats();
     </div>
 </div>
 
+<div class="card">
+    <h2>编译 Webhook</h2>
+    <p style="margin-bottom: 20px; color: #666;">
+        每次编译成功后会向配置文件 <code>[webhooks]</code> 中的地址发送编译结果，可用于刷新 CDN 或发送聊天通知。
+    </p>
+    <button type="button" class="btn" id="webhookTestBtn">发送测试通知</button>
+    <pre id="webhookTestResult" style="margin-top: 15px; white-space: pre-wrap; display: none;"></pre>
+</div>
+
+<style>
+    .compile-progress-bar {
+        width: 30%;
+        height: 100%;
+        background: #28a745;
+        animation: compile-progress 1.2s ease-in-out infinite;
+    }
+    @keyframes compile-progress {
+        0% { margin-left: -30%; }
+        100% { margin-left: 100%; }
+    }
+    .compile-progress-bar.determinate {
+        animation: none;
+        margin-left: 0;
+        transition: width 0.3s;
+    }
+</style>
+
 <script>
+    var btn = document.getElementById('compileBtn');
+    var progressDiv = document.getElementById('compileProgress');
+    var progressText = document.getElementById('progressText');
+    var progressBar = document.getElementById('progressBar');
+    var phaseNames = { scanning: '扫描内容', rendering: '渲染页面', saving: '保存结果' };
+    var resultDiv = document.getElementById('compileResult');
+    var resultContent = document.getElementById('resultContent');
+    var pollTimer = null;
+
+    function showResult(text, success) {
+        resultDiv.style.display = 'block';
+        resultContent.textContent = text;
+        resultContent.style.color = success ? '#155724' : '#721c24';
+    }
+
+    function setRunning(running, startedAt, status) {
+        btn.disabled = running;
+        btn.textContent = running ? '编译中...' : '开始编译';
+        progressDiv.style.display = running ? 'block' : 'none';
+        if (!running) {
+            return;
+        }
+
+        var text = '编译中...';
+        var determinate = status && status.phase && status.total > 0;
+        if (status && status.phase) {
+            text = (phaseNames[status.phase] || status.phase) + '...';
+            if (status.total > 0) {
+                text += ' ' + status.done + ' / ' + status.total;
+            }
+        }
+        progressBar.classList.toggle('determinate', !!determinate);
+        progressBar.style.width = determinate ? Math.round(status.done * 100 / status.total) + '%' : '';
+        if (startedAt) {
+            var seconds = Math.max(0, Math.round((Date.now() - Date.parse(startedAt)) / 1000));
+            text += '，已用时 ' + seconds + ' 秒';
+        }
+        progressText.textContent = text;
+    }
+
+    // 轮询编译状态，直到编译结束
+    async function pollStatus(showLast) {
+        try {
+            var response = await fetch('/api/admin/compile/status');
+            if (!response.ok) {
+                throw new Error('HTTP ' + response.status);
+            }
+            var data = await response.json();
+            setRunning(data.is_compiling, data.started_at, data);
+            if (data.is_compiling) {
+                pollTimer = setTimeout(function() { pollStatus(true); }, 1000);
+            } else if (showLast && data.last_result) {
+                showResult((data.last_success ? '编译成功！\n\n' : '编译失败:\n') + data.last_result, data.last_success);
+            }
+        } catch (error) {
+            setRunning(false);
+            showResult('获取编译状态失败: ' + error.message, false);
+        }
+    }
+
     document.getElementById('compileForm').addEventListener('submit', async function(e) {
         e.preventDefault();
-        var btn = document.getElementById('compileBtn');
-        var resultDiv = document.getElementById('compileResult');
-        var resultContent = document.getElementById('resultContent');
-        
-        btn.disabled = true;
-        btn.textContent = '编译中...';
+        clearTimeout(pollTimer);
         resultDiv.style.display = 'none';
-        
+        setRunning(true, new Date().toISOString());
+
         try {
             var response = await fetch('/api/recompile', {
                 method: 'POST',
@@ -2013,27 +3343,41 @@ loadDailyStats();
                 }
             });
             var data = await response.json();
-            
-            resultDiv.style.display = 'block';
-            if (response.ok) {
-                resultContent.textContent = '编译成功！\n\n' +
-                    '分类数量: ' + data.categories + '\n' +
-                    '文章数量: ' + data.items + '\n' +
-                    '附件数量: ' + data.attachments + '\n\n' +
-                    data.message;
-                resultContent.style.color = '#155724';
-            } else {
-                resultContent.textContent = '编译失败:\n' + data.message;
-                resultContent.style.color = '#721c24';
+            if (!response.ok) {
+                setRunning(false);
+                showResult('编译失败:\n' + data.message, false);
+                return;
             }
+            pollStatus(true);
         } catch (error) {
-            resultDiv.style.display = 'block';
-            resultContent.textContent = '请求失败: ' + error.message;
-            resultContent.style.color = '#721c24';
+            setRunning(false);
+            showResult('请求失败: ' + error.message, false);
+        }
+    });
+
+    // 打开页面时若已有编译在进行，继续显示进度
+    pollStatus(false);
+
+    document.getElementById('webhookTestBtn').addEventListener('click', async function() {
+        var output = document.getElementById('webhookTestResult');
+        this.disabled = true;
+        output.style.display = 'block';
+        output.style.color = '#666';
+        output.textContent = '发送中...';
+        try {
+            var response = await fetch('/api/admin/webhooks/test', { method: 'POST' });
+            var data = await response.json();
+            var lines = [data.message].concat((data.results || []).map(function(result) {
+                return (result.success ? '✓ ' : '✗ ') + result.url + ' - ' + result.message;
+            }));
+            output.textContent = lines.join('\n');
+            output.style.color = response.ok ? '#155724' : '#721c24';
+        } catch (error) {
+            output.textContent = '请求失败: ' + error.message;
+            output.style.color = '#721c24';
+        } finally {
+            this.disabled = false;
         }
-        
-        btn.disabled = false;
-        btn.textContent = '开始编译';
     });
 </script>
 {% endblock content %}"#,
@@ -2042,54 +3386,93 @@ loadDailyStats();
         Ok(tera)
     }
 
-    pub fn render_index(&self, site_content: &SiteContent) -> Result<String, Box<dyn std::error::Error>> {
+    pub fn source(&self) -> TemplateSource {
+        self.source
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    /// Make `tag_filter()` in templates answer from `filter`
+    pub fn set_tag_filter(&mut self, filter: TagFilter) {
+        self.tera.register_function("tag_filter", filter);
+    }
+
+    /// Context every page starts from: `config`, `theme` and the
+    /// `callout_types` usable in `::: kind` containers
+    fn context(&self) -> Context {
         let mut context = Context::new();
         context.insert("config", &self.config);
+        context.insert("theme", &self.theme);
+        context.insert("callout_types", crate::markdown::CALLOUT_TYPES);
+        context
+    }
+
+    pub fn render_index(&self, site_content: &SiteContent) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("site_content", site_content);
+        context.insert("meta_tags", &render_meta_tags(None, None, None, &self.config));
         Ok(self.tera.render("index.html", &context)?)
     }
 
-    pub fn render_category(&self, category: &Category) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    /// Render the 404 page; `recent_items` are linked as suggestions
+    pub fn render_not_found(&self, recent_items: &[ItemLink]) -> Result<String, BlogError> {
+        let mut context = self.context();
+        context.insert("recent_items", recent_items);
+        Ok(self.tera.render("404.html", &context)?)
+    }
+
+    pub fn render_category(&self, category: &Category) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("category", category);
+        context.insert("meta_tags", &render_meta_tags(None, Some(category), None, &self.config));
         Ok(self.tera.render("category.html", &context)?)
     }
 
-    pub fn render_item(&self, item: &ContentItem) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_item(
+        &self,
+        item: &ContentItem,
+        cover: Option<&str>,
+        prev: Option<&ItemLink>,
+        next: Option<&ItemLink>,
+        series_nav: Option<&SeriesNav>,
+    ) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("item", item);
+        context.insert("prev", &prev);
+        context.insert("next", &next);
+        context.insert("series_nav", &series_nav);
+        context.insert("meta_tags", &render_meta_tags(Some(item), None, cover, &self.config));
+        if let Some(css) = &self.highlight_css {
+            context.insert("highlight_css", css);
+        }
         Ok(self.tera.render("item.html", &context)?)
     }
 
-    pub fn render_archive(&self, all_items: &Vec<(String, String, String, Option<String>)>, total_items: usize) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_archive(&self, all_items: &Vec<(String, String, String, Option<String>)>, total_items: usize) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("all_items", all_items);
         context.insert("total_items", &total_items);
         Ok(self.tera.render("archive.html", &context)?)
     }
 
-    pub fn render_admin_login(&self, error: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_login(&self, error: Option<&str>) -> Result<String, BlogError> {
+        let mut context = self.context();
         if let Some(err) = error {
             context.insert("error", err);
         }
         Ok(self.tera.render("admin_login.html", &context)?)
     }
 
-    pub fn render_admin_base(&self, active: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_base(&self, active: &str) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("active", active);
         Ok(self.tera.render("admin_base.html", &context)?)
     }
 
-    pub fn render_admin_overview(&self, overview: &serde_json::Value, last_compiled: Option<&str>, message: Option<&str>, success: bool) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_overview(&self, overview: &serde_json::Value, last_compiled: Option<&str>, message: Option<&str>, success: bool) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("overview", overview);
         context.insert("last_compiled", &last_compiled);
         if let Some(msg) = message {
@@ -2100,9 +3483,8 @@ loadDailyStats();
         Ok(self.tera.render("admin_overview.html", &context)?)
     }
 
-    pub fn render_admin_categories(&self, categories: &[serde_json::Value], message: Option<&str>, success: bool) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_categories(&self, categories: &[serde_json::Value], message: Option<&str>, success: bool) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("categories", categories);
         if let Some(msg) = message {
             context.insert("message", msg);
@@ -2112,16 +3494,14 @@ loadDailyStats();
         Ok(self.tera.render("admin_categories.html", &context)?)
     }
 
-    pub fn render_admin_new_category(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_new_category(&self) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("active", "categories");
         Ok(self.tera.render("admin_new_category.html", &context)?)
     }
 
-    pub fn render_admin_items(&self, items: &[serde_json::Value], show_drafts: bool, message: Option<&str>, success: bool) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_items(&self, items: &[serde_json::Value], show_drafts: bool, message: Option<&str>, success: bool) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("items", items);
         context.insert("show_drafts", &show_drafts.to_string());
         if let Some(msg) = message {
@@ -2132,26 +3512,29 @@ loadDailyStats();
         Ok(self.tera.render("admin_items.html", &context)?)
     }
 
-    pub fn render_admin_new_item(&self, categories: &[serde_json::Value]) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_new_item(&self, categories: &[serde_json::Value]) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("categories", categories);
         context.insert("active", "items");
+        if let Some(css) = &self.highlight_css {
+            context.insert("highlight_css", css);
+        }
         Ok(self.tera.render("admin_new_item.html", &context)?)
     }
 
-    pub fn render_admin_edit_item(&self, item: &serde_json::Value, categories: &[serde_json::Value]) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_edit_item(&self, item: &serde_json::Value, categories: &[serde_json::Value]) -> Result<String, BlogError> {
+        let mut context = self.context();
         context.insert("item", item);
         context.insert("categories", categories);
         context.insert("active", "items");
+        if let Some(css) = &self.highlight_css {
+            context.insert("highlight_css", css);
+        }
         Ok(self.tera.render("admin_edit_item.html", &context)?)
     }
 
-    pub fn render_admin_analytics(&self, message: Option<&str>, success: bool) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_analytics(&self, message: Option<&str>, success: bool) -> Result<String, BlogError> {
+        let mut context = self.context();
         if let Some(msg) = message {
             context.insert("message", msg);
             context.insert("success", &success);
@@ -2160,9 +3543,54 @@ loadDailyStats();
         Ok(self.tera.render("admin_analytics.html", &context)?)
     }
 
-    pub fn render_admin_compile(&self, message: Option<&str>, success: bool) -> Result<String, Box<dyn std::error::Error>> {
-        let mut context = Context::new();
-        context.insert("config", &self.config);
+    pub fn render_admin_item_stats(&self, stats: &PageAnalytics) -> Result<String, BlogError> {
+        let mut context = self.context();
+        let visits: Vec<i64> = stats.daily.iter().map(|day| day.visits).collect();
+        context.insert("stats", stats);
+        context.insert("days", &stats.daily.len());
+        context.insert("sparkline", &sparkline_points(&visits, SPARKLINE_WIDTH, SPARKLINE_HEIGHT));
+        context.insert("sparkline_width", &SPARKLINE_WIDTH);
+        context.insert("sparkline_height", &SPARKLINE_HEIGHT);
+        context.insert("active", "items");
+        Ok(self.tera.render("admin_item_stats.html", &context)?)
+    }
+
+    pub fn render_admin_attachments(
+        &self,
+        attachments: &[AttachmentMetadata],
+        slugs: &[String],
+        slug: Option<&str>,
+    ) -> Result<String, BlogError> {
+        let mut context = self.context();
+        context.insert("attachments", attachments);
+        context.insert("slugs", slugs);
+        context.insert("slug", slug.unwrap_or_default());
+        context.insert("active", "attachments");
+        Ok(self.tera.render("admin_attachments.html", &context)?)
+    }
+
+    pub fn render_admin_comments(
+        &self,
+        comments: &[serde_json::Value],
+        slugs: &[String],
+        slug: Option<&str>,
+        message: Option<&str>,
+        success: bool,
+    ) -> Result<String, BlogError> {
+        let mut context = self.context();
+        context.insert("comments", comments);
+        context.insert("slugs", slugs);
+        context.insert("slug", slug.unwrap_or_default());
+        if let Some(msg) = message {
+            context.insert("message", msg);
+            context.insert("success", &success);
+        }
+        context.insert("active", "comments");
+        Ok(self.tera.render("admin_comments.html", &context)?)
+    }
+
+    pub fn render_admin_compile(&self, message: Option<&str>, success: bool) -> Result<String, BlogError> {
+        let mut context = self.context();
         if let Some(msg) = message {
             context.insert("message", msg);
             context.insert("success", &success);
@@ -2171,7 +3599,393 @@ loadDailyStats();
         Ok(self.tera.render("admin_compile.html", &context)?)
     }
 
-    pub fn render_with_context(&self, template: &str, context: &Context) -> Result<String, Box<dyn std::error::Error>> {
-        Ok(self.tera.render(template, context)?)
+    pub fn render_with_context(&self, template: &str, context: &Context) -> Result<String, BlogError> {
+        let mut context = context.clone();
+        context.insert("theme", &self.theme);
+        Ok(self.tera.render(template, &context)?)
+    }
+}
+
+/// Size of the SVG coordinate space for the admin visits sparkline
+const SPARKLINE_WIDTH: u32 = 300;
+const SPARKLINE_HEIGHT: u32 = 60;
+
+/// `points` for an SVG polyline spreading `values` across `width`, scaled so the
+/// largest value touches the top edge; all-zero series sit on the bottom edge
+fn sparkline_points(values: &[i64], width: u32, height: u32) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let step = if values.len() > 1 { f64::from(width) / (values.len() - 1) as f64 } else { 0.0 };
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let y = f64::from(height) * (1.0 - *value as f64 / max);
+            format!("{:.1},{:.1}", i as f64 * step, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// SHA-256 over the relative path and contents of every file under
+/// `templates_dir`, so any edit, addition or removal changes it.
+/// A missing directory hashes like an empty one.
+pub fn template_fingerprint(templates_dir: &Path) -> String {
+    let mut hasher = Sha256::new();
+    for entry in walkdir::WalkDir::new(templates_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let relative = entry.path().strip_prefix(templates_dir).unwrap_or(entry.path());
+        hasher.update(relative.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        match std::fs::read(entry.path()) {
+            Ok(contents) => hasher.update(&contents),
+            Err(e) => log::warn!("Failed to read template {}: {}", entry.path().display(), e),
+        }
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Build OpenGraph / Twitter Card meta tags for a page.
+///
+/// `item` takes precedence over `category`; with neither, the tags describe the site itself.
+/// `cover` is the item's resolved cover URL (see [`crate::compiler::resolve_cover`]).
+pub fn render_meta_tags(
+    item: Option<&ContentItem>,
+    category: Option<&Category>,
+    cover: Option<&str>,
+    config: &Config,
+) -> String {
+    let site_url = config.site.url.trim_end_matches('/');
+
+    let (title, description, url, og_type) = match (item, category) {
+        (Some(item), _) => (
+            item.title.clone(),
+            item.description.clone().unwrap_or_else(|| item.title.clone()),
+            format!("{}/{}", site_url, item.url),
+            "article",
+        ),
+        (None, Some(category)) => (
+            category.title.clone(),
+            category.description.clone().unwrap_or_else(|| category.title.clone()),
+            format!("{}/{}", site_url, category.url),
+            "website",
+        ),
+        (None, None) => (
+            config.site.title.clone(),
+            config.site.description.clone(),
+            format!("{}/", site_url),
+            "website",
+        ),
+    };
+
+    // The cover, else the item's first image attachment, else the configured default
+    let image = cover
+        .map(str::to_string)
+        .or_else(|| {
+            item.and_then(|item| item.attachments.iter().find(|a| a.mime_type.starts_with("image/")))
+                .map(|a| format!("{}/attachment/{}", site_url, a.stored_name()))
+        })
+        .or_else(|| {
+            config.site.default_og_image.as_ref().map(|img| {
+                if img.starts_with("http://") || img.starts_with("https://") {
+                    img.clone()
+                } else {
+                    format!("{}/{}", site_url, img.trim_start_matches('/'))
+                }
+            })
+        });
+
+    let creator = item
+        .and_then(|item| item.author.clone())
+        .unwrap_or_else(|| config.site.author.clone());
+
+    let mut tags = vec![
+        meta_property("og:site_name", &config.site.title),
+        meta_property("og:title", &title),
+        meta_property("og:description", &description),
+        meta_property("og:url", &url),
+        meta_property("og:type", og_type),
+    ];
+    if let Some(image) = &image {
+        tags.push(meta_property("og:image", image));
+    }
+
+    let card = if image.is_some() { "summary_large_image" } else { "summary" };
+    tags.push(meta_name("twitter:card", card));
+    if let Some(site) = &config.site.twitter_site {
+        tags.push(meta_name("twitter:site", site));
     }
-}
\ No newline at end of file
+    tags.push(meta_name("twitter:creator", &creator));
+    tags.push(meta_name("twitter:title", &title));
+    tags.push(meta_name("twitter:description", &description));
+
+    if let Some(item) = item {
+        if let Some(date) = &item.date {
+            tags.push(meta_property("article:published_time", date));
+        }
+        tags.push(meta_property("article:author", &creator));
+        for tag in &item.tags {
+            tags.push(meta_property("article:tag", tag));
+        }
+    }
+
+    tags.join("\n    ")
+}
+
+fn meta_property(property: &str, content: &str) -> String {
+    format!(r#"<meta property="{}" content="{}">"#, property, escape_attr(content))
+}
+
+fn meta_name(name: &str, content: &str) -> String {
+    format!(r#"<meta name="{}" content="{}">"#, name, escape_attr(content))
+}
+
+/// Escape a value for use inside a double-quoted HTML attribute
+fn escape_attr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Attachment;
+    use std::path::PathBuf;
+
+    fn sample_item() -> ContentItem {
+        ContentItem {
+            category: "grape".to_string(),
+            item_name: "tizi".to_string(),
+            dir_name: "提子".to_string(),
+            url: "grape-tizi".to_string(),
+            file_path: PathBuf::from("content/grape/tizi/tizi.md"),
+            title: "Red \"Globe\" <Grapes>".to_string(),
+            date: Some("2024-05-01".to_string()),
+            author: Some("lin".to_string()),
+            description: Some("Sweet & crunchy".to_string()),
+            excerpt: Some("Sweet & crunchy".to_string()),
+            html_content: String::new(),
+            attachments: vec![Attachment {
+                original_name: "cover.jpg".to_string(),
+                new_name: "abc.jpg".to_string(),
+                file_type: "jpg".to_string(),
+                path: "attachment/abc.jpg".to_string(),
+                source_path: PathBuf::from("content/grape/tizi/attachment/cover.jpg"),
+                file_size: 0,
+                mime_type: "image/jpeg".to_string(),
+                content_hash: String::new(),
+                duplicate_of: None,
+                was_optimized: false,
+                original_size: 0,
+            }],
+            tags: vec!["fruit".to_string(), "summer".to_string()],
+            publish_at: None,
+            raw_content: String::new(),
+            series: None,
+            series_order: None,
+            cover: None,
+        }
+    }
+
+    /// Parse `<meta (property|name)="key" content="value">` lines into pairs,
+    /// failing on anything that is not well-formed.
+    fn parse_meta(html: &str) -> Vec<(String, String)> {
+        html.lines()
+            .map(|line| {
+                let line = line.trim();
+                let rest = line
+                    .strip_prefix("<meta ")
+                    .and_then(|l| l.strip_suffix('>'))
+                    .expect("meta tag");
+                let (attr, rest) = rest.split_once("=\"").expect("key attribute");
+                assert!(attr == "property" || attr == "name");
+                let (key, rest) = rest.split_once("\" content=\"").expect("content attribute");
+                let value = rest.strip_suffix('"').expect("closing quote");
+                assert!(!value.contains('"') && !value.contains('<'));
+                (key.to_string(), value.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_meta_tags_for_item() {
+        let mut config = Config::default();
+        config.site.twitter_site = Some("@collections".to_string());
+        let html = render_meta_tags(Some(&sample_item()), None, None, &config);
+        let tags = parse_meta(&html);
+        let get = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+        assert_eq!(get("og:type"), Some("article"));
+        assert_eq!(get("og:title"), Some("Red &quot;Globe&quot; &lt;Grapes&gt;"));
+        assert_eq!(get("og:description"), Some("Sweet &amp; crunchy"));
+        assert_eq!(get("og:url"), Some("http://localhost:8080/grape-tizi"));
+        assert_eq!(get("og:image"), Some("http://localhost:8080/attachment/abc.jpg"));
+        assert_eq!(get("twitter:card"), Some("summary_large_image"));
+        assert_eq!(get("twitter:site"), Some("@collections"));
+        assert_eq!(get("twitter:creator"), Some("lin"));
+        assert_eq!(get("article:published_time"), Some("2024-05-01"));
+        assert_eq!(tags.iter().filter(|(k, _)| k == "article:tag").count(), 2);
+
+        // The tags end up inside <head> of the built-in item page
+        let tera = TemplateRenderer::create_builtin_templates().unwrap();
+        let mut context = Context::new();
+        context.insert("config", &config);
+        context.insert("item", &sample_item());
+        context.insert("meta_tags", &html);
+        let page = tera.render("item.html", &context).unwrap();
+        let head = &page[..page.find("</head>").unwrap()];
+        assert!(head.contains(r#"<meta property="og:type" content="article">"#));
+        assert!(head.contains(r#"<script type="application/ld+json">{"@context":"https://schema.org""#));
+    }
+
+    #[test]
+    fn test_theme_in_head() {
+        let tera = TemplateRenderer::create_builtin_templates().unwrap();
+        let mut context = Context::new();
+        context.insert("config", &Config::default());
+        context.insert("recent_items", &Vec::<ItemLink>::new());
+        let page = tera.render("404.html", &context).unwrap();
+        assert!(!page.contains("--primary-color: #0a7d5a"));
+
+        let mut theme = Theme::default();
+        theme.light_mode.primary = "#0a7d5a".to_string();
+        theme.custom_css = Some(".post { border: 0; }".to_string());
+        context.insert("theme", &theme);
+        let page = tera.render("404.html", &context).unwrap();
+        let head = &page[..page.find("</head>").unwrap()];
+        assert!(head.contains("--primary-color: #0a7d5a;"));
+        assert!(head.contains("<style>.post { border: 0; }</style>"));
+    }
+
+    #[test]
+    fn test_template_fingerprint() {
+        let dir = std::path::PathBuf::from("/tmp/test_template_fingerprint");
+        let _ = std::fs::remove_dir_all(&dir);
+        let empty = template_fingerprint(&dir);
+        std::fs::create_dir_all(dir.join("partials")).unwrap();
+        assert_eq!(template_fingerprint(&dir), empty);
+
+        std::fs::write(dir.join("index.html"), "<h1>{{ config.site.title }}</h1>").unwrap();
+        let first = template_fingerprint(&dir);
+        assert_ne!(first, empty);
+        assert_eq!(template_fingerprint(&dir), first);
+
+        std::fs::write(dir.join("index.html"), "<h2>{{ config.site.title }}</h2>").unwrap();
+        let edited = template_fingerprint(&dir);
+        assert_ne!(edited, first);
+
+        std::fs::write(dir.join("partials/nav.html"), "").unwrap();
+        assert_ne!(template_fingerprint(&dir), edited);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_item_prev_next_nav() {
+        let tera = TemplateRenderer::create_builtin_templates().unwrap();
+        let mut context = Context::new();
+        context.insert("config", &Config::default());
+        context.insert("item", &sample_item());
+        let older = ItemLink {
+            title: "Older <post>".to_string(),
+            url: "grape-old".to_string(),
+        };
+
+        // The oldest item has no previous link
+        context.insert("prev", &None::<ItemLink>);
+        context.insert("next", &None::<ItemLink>);
+        assert!(!tera.render("item.html", &context).unwrap().contains("<nav class=\"post-nav\">"));
+
+        context.insert("prev", &Some(&older));
+        let page = crate::i18n::localize(&tera.render("item.html", &context).unwrap(), "zh-CN", "zh-CN");
+        assert!(page.contains(r#"<a href="/grape-old">Older &lt;post&gt;</a>"#));
+        assert!(page.contains("上一篇"));
+        assert!(!page.contains("下一篇"));
+    }
+
+    #[test]
+    fn test_item_series_nav() {
+        let tera = TemplateRenderer::create_builtin_templates().unwrap();
+        let item = sample_item();
+        let mut context = Context::new();
+        context.insert("config", &Config::default());
+        context.insert("item", &item);
+        context.insert("prev", &None::<ItemLink>);
+        context.insert("next", &None::<ItemLink>);
+        context.insert("series_nav", &None::<SeriesNav>);
+        assert!(!tera.render("item.html", &context).unwrap().contains("<nav class=\"series-nav\">"));
+
+        let series_nav = SeriesNav {
+            series_name: "Vines".to_string(),
+            prev: Some(("Planting <soil>".to_string(), "grape-planting".to_string())),
+            next: None,
+            all_parts: vec![
+                ("Planting <soil>".to_string(), "grape-planting".to_string(), 1),
+                (item.title.clone(), item.url.clone(), 2),
+            ],
+        };
+        context.insert("series_nav", &Some(&series_nav));
+        let page = tera.render("item.html", &context).unwrap();
+        assert!(crate::i18n::localize(&page, "zh-CN", "zh-CN").contains("系列：Vines（共 2 篇）"));
+        assert!(crate::i18n::localize(&page, "en", "zh-CN").contains("Series: Vines (2 parts)"));
+        assert!(page.contains(r#"<li value="1"><a href="/grape-planting">Planting &lt;soil&gt;</a></li>"#));
+        assert!(page.contains(r#"<li value="2"><span class="current">Red &quot;Globe&quot; &lt;Grapes&gt;</span></li>"#));
+        assert!(page.contains(r#"← <a href="/grape-planting">"#));
+    }
+
+    #[test]
+    fn test_render_meta_tags_for_site() {
+        let mut config = Config::default();
+        config.site.default_og_image = Some("/static/og.png".to_string());
+        let html = render_meta_tags(None, None, None, &config);
+        let tags = parse_meta(&html);
+        let get = |key: &str| tags.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+
+        assert_eq!(get("og:type"), Some("website"));
+        assert_eq!(get("og:image"), Some("http://localhost:8080/static/og.png"));
+        assert!(get("twitter:site").is_none());
+        assert!(get("article:author").is_none());
+    }
+
+    #[test]
+    fn test_cover_image() {
+        use crate::compiler::resolve_cover;
+
+        let mut config = Config::default();
+        config.site.default_og_image = Some("/static/og.png".to_string());
+        let og_image = |item: &ContentItem| {
+            let cover = resolve_cover(item, &config.site.url);
+            let html = render_meta_tags(Some(item), None, cover.as_deref(), &config);
+            parse_meta(&html).into_iter().find(|(k, _)| k == "og:image").map(|(_, v)| v)
+        };
+
+        let mut item = sample_item();
+        item.cover = Some("attachment/cover.jpg".to_string());
+        assert_eq!(og_image(&item).as_deref(), Some("http://localhost:8080/attachment/abc.jpg"));
+        item.cover = Some("https://cdn.example.com/grapes.png".to_string());
+        assert_eq!(og_image(&item).as_deref(), Some("https://cdn.example.com/grapes.png"));
+
+        // A missing cover falls back to the site-wide image
+        item.attachments.clear();
+        item.cover = Some("missing.jpg".to_string());
+        assert_eq!(resolve_cover(&item, &config.site.url), None);
+        assert_eq!(og_image(&item).as_deref(), Some("http://localhost:8080/static/og.png"));
+    }
+}
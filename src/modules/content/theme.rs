@@ -1,10 +1,45 @@
+use crate::config::Config;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 
+/// Definition file inside every theme directory
+pub const THEME_FILE: &str = "theme.toml";
+
+/// Site theme, exposed to templates as `theme.*`.
+///
+/// A user theme lives in `{paths.themes_dir}/{name}/theme.toml` and is selected
+/// with `site.theme`:
+///
+/// ```toml
+/// custom_css = "custom.css"   # relative to the theme directory
+///
+/// [fonts]
+/// body = "Georgia, serif"
+/// code = "'JetBrains Mono', monospace"
+///
+/// [light_mode]
+/// primary = "#0a7d5a"
+///
+/// [dark_mode]
+/// primary = "#3ecf9a"
+/// ```
+///
+/// Any key left out keeps its built-in value.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Theme {
     pub name: String,
     pub light_mode: ColorPalette,
     pub dark_mode: ColorPalette,
+    pub fonts: ThemeFonts,
+    /// Contents of the theme's custom stylesheet, injected after the built-in CSS
+    #[serde(default)]
+    pub custom_css: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeFonts {
+    pub body: String,
+    pub code: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +51,28 @@ pub struct ColorPalette {
     pub accent: String,
     pub border: String,
     pub muted: String,
+    pub code_bg: String,
+    pub card_bg: String,
+    pub header_bg: String,
+    pub footer_bg: String,
+}
+
+impl ColorPalette {
+    fn fields_mut(&mut self) -> [(&'static str, &mut String); 11] {
+        [
+            ("background", &mut self.background),
+            ("foreground", &mut self.foreground),
+            ("primary", &mut self.primary),
+            ("secondary", &mut self.secondary),
+            ("accent", &mut self.accent),
+            ("border", &mut self.border),
+            ("muted", &mut self.muted),
+            ("code_bg", &mut self.code_bg),
+            ("card_bg", &mut self.card_bg),
+            ("header_bg", &mut self.header_bg),
+            ("footer_bg", &mut self.footer_bg),
+        ]
+    }
 }
 
 impl Default for Theme {
@@ -30,6 +87,10 @@ impl Default for Theme {
                 accent: "#e74c3c".to_string(),
                 border: "#e0e0e0".to_string(),
                 muted: "#666666".to_string(),
+                code_bg: "#f5f5f5".to_string(),
+                card_bg: "#ffffff".to_string(),
+                header_bg: "#2c3e50".to_string(),
+                footer_bg: "#f8f9fa".to_string(),
             },
             dark_mode: ColorPalette {
                 background: "#1a1a1a".to_string(),
@@ -39,12 +100,79 @@ impl Default for Theme {
                 accent: "#ec7063".to_string(),
                 border: "#333333".to_string(),
                 muted: "#999999".to_string(),
+                code_bg: "#2d2d2d".to_string(),
+                card_bg: "#242424".to_string(),
+                header_bg: "#1a1a2e".to_string(),
+                footer_bg: "#1a1a1a".to_string(),
             },
+            fonts: ThemeFonts {
+                body: "-apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif".to_string(),
+                code: "'Fira Code', 'Consolas', monospace".to_string(),
+            },
+            custom_css: None,
         }
     }
 }
 
 impl Theme {
+    /// Load the theme `name` from `themes_dir` on top of the built-in theme.
+    ///
+    /// Also returns the keys the file did not set (or whose custom CSS could
+    /// not be read), which kept their built-in values.
+    pub fn load(themes_dir: &Path, name: &str) -> Result<(Self, Vec<String>), Box<dyn std::error::Error>> {
+        let dir = themes_dir.join(name);
+        let table: toml::Table = toml::from_str(&std::fs::read_to_string(dir.join(THEME_FILE))?)?;
+
+        let mut theme = Theme::default();
+        theme.name = name.to_string();
+        let mut fallbacks = Vec::new();
+        let mut settings: Vec<(String, &mut String)> = Vec::new();
+        for (section, palette) in [("light_mode", &mut theme.light_mode), ("dark_mode", &mut theme.dark_mode)] {
+            settings.extend(palette.fields_mut().into_iter().map(|(key, value)| (format!("{}.{}", section, key), value)));
+        }
+        settings.push(("fonts.body".to_string(), &mut theme.fonts.body));
+        settings.push(("fonts.code".to_string(), &mut theme.fonts.code));
+        for (key, value) in settings {
+            match lookup(&table, &key) {
+                Some(found) => *value = found,
+                None => fallbacks.push(key),
+            }
+        }
+
+        if let Some(file) = lookup(&table, "custom_css") {
+            match std::fs::read_to_string(dir.join(&file)) {
+                Ok(css) => theme.custom_css = Some(css),
+                Err(e) => {
+                    log::warn!("Failed to read custom CSS {} of theme '{}': {}", file, name, e);
+                    fallbacks.push("custom_css".to_string());
+                }
+            }
+        }
+
+        Ok((theme, fallbacks))
+    }
+
+    /// The theme selected by `site.theme`, or the built-in theme when none is
+    /// set or it cannot be loaded
+    pub fn from_config(config: &Config) -> Self {
+        let Some(name) = config.site.theme.as_deref().filter(|name| !name.trim().is_empty()) else {
+            return Self::default();
+        };
+        match Self::load(&config.paths.themes_dir, name) {
+            Ok((theme, fallbacks)) => {
+                if !fallbacks.is_empty() {
+                    log::warn!("Theme '{}' does not set {}; using built-in values", name, fallbacks.join(", "));
+                }
+                log::info!("Using theme '{}'", name);
+                theme
+            }
+            Err(e) => {
+                log::warn!("Failed to load theme '{}' from {}: {}; using the built-in theme", name, config.paths.themes_dir.display(), e);
+                Self::default()
+            }
+        }
+    }
+
     pub fn to_css(&self) -> String {
         format!(
             r#"
@@ -93,6 +221,15 @@ body {{
     }
 }
 
+/// String value of `key`, either top-level or `section.field`
+fn lookup(table: &toml::Table, key: &str) -> Option<String> {
+    let value = match key.split_once('.') {
+        Some((section, field)) => table.get(section)?.get(field)?,
+        None => table.get(key)?,
+    };
+    value.as_str().map(str::to_string)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,4 +247,40 @@ mod tests {
         assert!(css.contains("var(--bg-color)"));
         assert!(css.contains("var(--fg-color)"));
     }
+
+    #[test]
+    fn test_theme_load() {
+        let themes_dir = std::path::PathBuf::from("/tmp/test_theme_load");
+        let _ = std::fs::remove_dir_all(&themes_dir);
+        let dir = themes_dir.join("ocean");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(THEME_FILE),
+            "custom_css = \"custom.css\"\n\n[fonts]\nbody = \"Georgia, serif\"\n\n[light_mode]\nprimary = \"#0a7d5a\"\nbackground = \"#f0f8ff\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("custom.css"), ".post { border: 0; }").unwrap();
+
+        let (theme, fallbacks) = Theme::load(&themes_dir, "ocean").unwrap();
+        assert_eq!(theme.name, "ocean");
+        assert_eq!(theme.light_mode.primary, "#0a7d5a");
+        assert_eq!(theme.light_mode.background, "#f0f8ff");
+        assert_eq!(theme.light_mode.accent, Theme::default().light_mode.accent);
+        assert_eq!(theme.fonts.body, "Georgia, serif");
+        assert_eq!(theme.custom_css.as_deref(), Some(".post { border: 0; }"));
+        assert!(fallbacks.contains(&"fonts.code".to_string()));
+        assert!(fallbacks.contains(&"dark_mode.primary".to_string()));
+        assert!(!fallbacks.contains(&"light_mode.primary".to_string()));
+        assert_eq!(fallbacks.len(), 21);
+
+        // A missing theme falls back to the built-in one
+        let mut config = Config::default();
+        config.paths.themes_dir = themes_dir.clone();
+        config.site.theme = Some("missing".to_string());
+        assert_eq!(Theme::from_config(&config).name, "default");
+        config.site.theme = Some("ocean".to_string());
+        assert_eq!(Theme::from_config(&config).fonts.body, "Georgia, serif");
+
+        let _ = std::fs::remove_dir_all(&themes_dir);
+    }
 }
@@ -1,12 +1,17 @@
-use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::path::PathBuf;
 use uuid::Uuid;
 
 use crate::config::Config;
+use crate::error::BlogError;
+use crate::geoip::GeoIp;
+use crate::storage::StorageDB;
 
 /// 访问来源类型
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 pub enum VisitSource {
     Direct,          // 直接访问
     SearchEngine,    // 搜索引擎
@@ -46,8 +51,46 @@ impl ToString for VisitSource {
     }
 }
 
+/// 访问设备类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub enum DeviceType {
+    Mobile,  // 手机
+    Tablet,  // 平板
+    Desktop, // 桌面
+    Bot,     // 爬虫
+}
+
+impl Default for DeviceType {
+    fn default() -> Self {
+        DeviceType::Desktop
+    }
+}
+
+impl std::str::FromStr for DeviceType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mobile" => Ok(DeviceType::Mobile),
+            "tablet" => Ok(DeviceType::Tablet),
+            "bot" => Ok(DeviceType::Bot),
+            _ => Ok(DeviceType::Desktop),
+        }
+    }
+}
+
+impl ToString for DeviceType {
+    fn to_string(&self) -> String {
+        match self {
+            DeviceType::Mobile => "mobile".to_string(),
+            DeviceType::Tablet => "tablet".to_string(),
+            DeviceType::Desktop => "desktop".to_string(),
+            DeviceType::Bot => "bot".to_string(),
+        }
+    }
+}
+
 /// 访问记录
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct VisitRecord {
     pub id: String,
     pub page_slug: String,      // 访问的页面 slug
@@ -60,6 +103,11 @@ pub struct VisitRecord {
     pub visit_time: String,     // 访问时间
     pub country: Option<String>, // 国家（可选）
     pub city: Option<String>,    // 城市（可选）
+    pub redirected_from: Option<String>, // 重定向来源路径（如有）
+    #[serde(default)]
+    pub device_type: DeviceType, // 设备类型
+    #[serde(default)]
+    pub is_bot: bool,            // 爬虫或监控程序，默认不计入统计
 }
 
 /// 每日统计
@@ -79,6 +127,22 @@ pub struct SourceStats {
     pub percentage: f64,
 }
 
+/// 设备类型统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceStats {
+    pub device_type: String,
+    pub count: i64,
+    pub percentage: f64,
+}
+
+/// 国家统计
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountryStats {
+    pub country: String, // ISO 3166-1 国家代码
+    pub count: i64,
+    pub percentage: f64, // 占有国家信息的访问的比例
+}
+
 /// 热门页面
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PopularPage {
@@ -87,6 +151,64 @@ pub struct PopularPage {
     pub visits: i64,
 }
 
+/// 访问热力图中的一格：星期几（0 为周日）的某个小时
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourlyCell {
+    pub weekday: u8,
+    pub hour: u8,
+    pub visit_count: i64,
+}
+
+/// 最近几分钟内被访问的页面
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RealtimePage {
+    pub slug: String,
+    pub count: i64,
+}
+
+/// 实时访客统计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RealtimeStats {
+    pub active_visitors: i64,
+    pub within_minutes: u64,
+    pub top_pages: Vec<RealtimePage>,
+}
+
+/// 实时统计的时间条件：?1 为起始日期（按字符串比较可以使用 visit_time 索引），
+/// ?2 为起始时间；datetime() 同时兼容 RFC 3339 和 "YYYY-MM-DD HH:MM:SS" 两种格式
+const REALTIME_RANGE: &str = "visit_time >= ?1 AND datetime(visit_time) >= ?2 AND is_bot = 0";
+
+fn realtime_cutoff(within_minutes: u64) -> (String, String) {
+    let minutes = i64::try_from(within_minutes).unwrap_or(i64::MAX).min(MAX_REALTIME_MINUTES as i64);
+    let since = chrono::Utc::now() - chrono::Duration::minutes(minutes);
+    (since.format("%Y-%m-%d").to_string(), since.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// 单个页面某一天的访问量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageDailyVisits {
+    pub date: String,
+    pub visits: i64,
+    pub unique_visitors: i64,
+}
+
+/// 单个页面在一段时间内的访问统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageAnalytics {
+    pub slug: String,
+    pub title: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub total_visits: i64,
+    pub unique_visitors: i64,
+    pub daily: Vec<PageDailyVisits>, // 按日期升序，没有访问的日期计为 0
+    pub sources: Vec<SourceStats>,
+    pub keywords: Vec<(String, i64)>,
+}
+
+/// 每日统计视图的版本标记，视图定义变化时递增以重建旧数据库中的视图
+const DAILY_STATS_VIEW_VERSION: &str = "view-v2";
+
 /// 访问统计数据库
 pub struct AnalyticsDB {
     conn: Connection,
@@ -115,6 +237,21 @@ impl AnalyticsDB {
             [],
         )?;
 
+        // 旧数据库补充新列（列已存在时忽略错误）
+        conn.execute("ALTER TABLE visits ADD COLUMN redirected_from TEXT", []).ok();
+        conn.execute("ALTER TABLE visits ADD COLUMN device_type TEXT", []).ok();
+        let added_is_bot = conn
+            .execute("ALTER TABLE visits ADD COLUMN is_bot INTEGER NOT NULL DEFAULT 0", [])
+            .is_ok();
+        if added_is_bot {
+            // 旧数据库迁移：按已识别的设备类型标记爬虫，旧触发器会计入爬虫，需要重建
+            conn.execute_batch(
+                "UPDATE visits SET is_bot = 1 WHERE device_type = 'bot';
+                 DROP TRIGGER IF EXISTS visits_count_insert;
+                 DROP TRIGGER IF EXISTS visits_count_delete;",
+            )?;
+        }
+
         // 创建索引
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_visits_page ON visits(page_slug)",
@@ -128,20 +265,93 @@ impl AnalyticsDB {
             "CREATE INDEX IF NOT EXISTS idx_visits_ip ON visits(ip_address)",
             [],
         )?;
+        // 按页面和时间筛选访问记录
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_visits_page_time ON visits(page_slug, visit_time)",
+            [],
+        )?;
+
+        // 每个页面的访问数缓存（不含爬虫），热门页面不必扫描全部访问记录；由触发器维护
+        let has_counts: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'page_visit_counts'",
+            [],
+            |row| row.get(0),
+        )?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS page_visit_counts (
+                page_slug TEXT PRIMARY KEY,
+                visits INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_page_visit_counts ON page_visit_counts(visits);
+             CREATE TRIGGER IF NOT EXISTS visits_count_insert AFTER INSERT ON visits WHEN NEW.is_bot = 0 BEGIN
+                INSERT INTO page_visit_counts (page_slug, visits) VALUES (NEW.page_slug, 1)
+                ON CONFLICT(page_slug) DO UPDATE SET visits = visits + 1;
+             END;
+             CREATE TRIGGER IF NOT EXISTS visits_count_delete AFTER DELETE ON visits WHEN OLD.is_bot = 0 BEGIN
+                UPDATE page_visit_counts SET visits = visits - 1 WHERE page_slug = OLD.page_slug;
+             END;",
+        )?;
+        // 旧数据库迁移：首次创建缓存表时按已有记录填充
+        if !has_counts {
+            conn.execute(
+                "INSERT OR REPLACE INTO page_visit_counts (page_slug, visits)
+                 SELECT page_slug, COUNT(*) FROM visits GROUP BY page_slug",
+                [],
+            )?;
+        }
 
-        // 创建每日统计视图
+        // 超过保留期的访问记录（不含爬虫）按天、按页面汇总到这里，原始记录随后删除
         conn.execute(
-            "CREATE VIEW IF NOT EXISTS daily_stats AS
-             SELECT
-                date(visit_time) as date,
-                COUNT(*) as total_visits,
-                COUNT(DISTINCT ip_address) as unique_visitors,
-                COUNT(*) as page_views
-             FROM visits
-             GROUP BY date(visit_time)",
+            "CREATE TABLE IF NOT EXISTS daily_stats_agg (
+                date TEXT NOT NULL,
+                page_slug TEXT NOT NULL,
+                visits INTEGER NOT NULL,
+                unique_visitors INTEGER NOT NULL,
+                PRIMARY KEY (date, page_slug)
+            )",
             [],
         )?;
 
+        // 每日统计视图合并原始记录和汇总数据。汇总后的独立访客按页面相加，
+        // 同一访客当天看过多个页面时会重复计数。daily_stats 不含爬虫，daily_stats_all 包含
+        for (view, filter) in [("daily_stats", "WHERE is_bot = 0"), ("daily_stats_all", "")] {
+            let view_sql: Option<String> = conn
+                .query_row("SELECT sql FROM sqlite_master WHERE type = 'view' AND name = ?", [view], |row| row.get(0))
+                .optional()?;
+            // 旧数据库迁移：视图只统计原始记录或未区分爬虫时重建
+            if view_sql.is_some_and(|sql| sql.contains(DAILY_STATS_VIEW_VERSION)) {
+                continue;
+            }
+            conn.execute_batch(&format!(
+                "DROP VIEW IF EXISTS {view};
+                 CREATE VIEW {view} AS /* {DAILY_STATS_VIEW_VERSION} */
+                 SELECT date, SUM(total_visits) AS total_visits, SUM(unique_visitors) AS unique_visitors, SUM(page_views) AS page_views
+                 FROM (
+                    SELECT date(visit_time) AS date, COUNT(*) AS total_visits,
+                           COUNT(DISTINCT ip_address) AS unique_visitors, COUNT(*) AS page_views
+                    FROM visits {filter} GROUP BY date(visit_time)
+                    UNION ALL
+                    SELECT date, SUM(visits), SUM(unique_visitors), SUM(visits)
+                    FROM daily_stats_agg GROUP BY date
+                 )
+                 GROUP BY date;"
+            ))?;
+        }
+
+        // 旧数据库迁移：去掉已标记为爬虫的访问后重算页面访问数
+        if added_is_bot {
+            conn.execute_batch(
+                "DELETE FROM page_visit_counts;
+                 INSERT INTO page_visit_counts (page_slug, visits)
+                 SELECT page_slug, SUM(visits) FROM (
+                    SELECT page_slug, COUNT(*) AS visits FROM visits WHERE is_bot = 0 GROUP BY page_slug
+                    UNION ALL
+                    SELECT page_slug, SUM(visits) FROM daily_stats_agg GROUP BY page_slug
+                 )
+                 GROUP BY page_slug;",
+            )?;
+        }
+
         Ok(Self { conn })
     }
 
@@ -149,8 +359,8 @@ impl AnalyticsDB {
     pub fn record_visit(&self, record: &VisitRecord) -> Result<()> {
         self.conn.execute(
             "INSERT INTO visits
-             (id, page_slug, ip_address, user_agent, referer, source, search_engine, search_keyword, visit_time, country, city)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+             (id, page_slug, ip_address, user_agent, referer, source, search_engine, search_keyword, visit_time, country, city, redirected_from, device_type, is_bot)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 record.id,
                 record.page_slug,
@@ -162,48 +372,46 @@ impl AnalyticsDB {
                 record.search_keyword,
                 record.visit_time,
                 record.country,
-                record.city
+                record.city,
+                record.redirected_from,
+                record.device_type.to_string(),
+                record.is_bot
             ],
         )?;
         Ok(())
     }
 
-    /// 获取每日统计
+    /// 获取每日统计，`include_bots` 为 false 时不含爬虫
     pub fn get_daily_stats(
         &self,
         start_date: Option<&str>,
         end_date: Option<&str>,
+        include_bots: bool,
     ) -> Result<Vec<DailyStats>> {
-        let query = match (start_date, end_date) {
+        let columns = if include_bots {
+            "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats_all"
+        } else {
+            "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats"
+        };
+        let mut stmt;
+        let mut rows = match (start_date, end_date) {
             (Some(start), Some(end)) => {
-                format!(
-                    "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats
-                     WHERE date BETWEEN '{}' AND '{}' ORDER BY date DESC",
-                    start, end
-                )
+                stmt = self.conn.prepare(&format!("{} WHERE date BETWEEN ? AND ? ORDER BY date DESC", columns))?;
+                stmt.query(params![start, end])?
             }
             (Some(start), None) => {
-                format!(
-                    "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats
-                     WHERE date >= '{}' ORDER BY date DESC",
-                    start
-                )
+                stmt = self.conn.prepare(&format!("{} WHERE date >= ? ORDER BY date DESC", columns))?;
+                stmt.query(params![start])?
             }
             (None, Some(end)) => {
-                format!(
-                    "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats
-                     WHERE date <= '{}' ORDER BY date DESC",
-                    end
-                )
+                stmt = self.conn.prepare(&format!("{} WHERE date <= ? ORDER BY date DESC", columns))?;
+                stmt.query(params![end])?
             }
-            _ => {
-                "SELECT date, total_visits, unique_visitors, page_views FROM daily_stats
-                 ORDER BY date DESC LIMIT 30".to_string()
+            (None, None) => {
+                stmt = self.conn.prepare(&format!("{} ORDER BY date DESC LIMIT 30", columns))?;
+                stmt.query([])?
             }
         };
-
-        let mut stmt = self.conn.prepare(&query)?;
-        let mut rows = stmt.query([])?;
         let mut stats = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -219,13 +427,12 @@ impl AnalyticsDB {
 
     /// 获取来源统计
     pub fn get_source_stats(&self, limit: Option<usize>) -> Result<Vec<SourceStats>> {
-        let limit = limit.unwrap_or(10);
-        let mut stmt = self.conn.prepare(&format!(
-            "SELECT source, COUNT(*) as count FROM visits GROUP BY source ORDER BY count DESC LIMIT {}",
-            limit
-        ))?;
+        let limit = limit.unwrap_or(10).min(MAX_STATS_LIMIT);
+        let mut stmt = self.conn.prepare(
+            "SELECT source, COUNT(*) as count FROM visits GROUP BY source ORDER BY count DESC LIMIT ?",
+        )?;
 
-        let mut rows = stmt.query([])?;
+        let mut rows = stmt.query(params![limit as i64])?;
         let mut stats = Vec::new();
         let total: i64 = {
             let mut total_stmt = self.conn.prepare("SELECT COUNT(*) FROM visits")?;
@@ -243,15 +450,75 @@ impl AnalyticsDB {
         Ok(stats)
     }
 
-    /// 获取热门页面
-    pub fn get_popular_pages(&self, limit: Option<usize>) -> Result<Vec<PopularPage>> {
-        let limit = limit.unwrap_or(10);
-        let mut stmt = self.conn.prepare(&format!(
-            "SELECT page_slug, COUNT(*) as visits FROM visits GROUP BY page_slug ORDER BY visits DESC LIMIT {}",
-            limit
-        ))?;
+    /// 获取设备类型统计（新增该列之前的记录计为未知）
+    pub fn get_device_stats(&self) -> Result<Vec<DeviceStats>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(device_type, 'unknown') as device, COUNT(*) as count
+             FROM visits GROUP BY device ORDER BY count DESC",
+        )?;
 
         let mut rows = stmt.query([])?;
+        let mut stats = Vec::new();
+        let total = self.get_total_visits(true)?;
+
+        while let Some(row) = rows.next()? {
+            let count: i64 = row.get(1)?;
+            stats.push(DeviceStats {
+                device_type: row.get(0)?,
+                count,
+                percentage: if total > 0 { (count as f64 / total as f64) * 100.0 } else { 0.0 },
+            });
+        }
+        Ok(stats)
+    }
+
+    /// 获取访问量最多的国家（没有国家信息的访问不计入）
+    pub fn get_country_stats(&self, limit: Option<usize>) -> Result<Vec<CountryStats>> {
+        let limit = limit.unwrap_or(10).min(MAX_STATS_LIMIT);
+        let total: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM visits WHERE country IS NOT NULL AND country != ''",
+            [],
+            |row| row.get(0),
+        )?;
+        let mut stmt = self.conn.prepare(
+            "SELECT country, COUNT(*) as count FROM visits
+             WHERE country IS NOT NULL AND country != ''
+             GROUP BY country ORDER BY count DESC LIMIT ?",
+        )?;
+
+        let mut rows = stmt.query(params![limit as i64])?;
+        let mut stats = Vec::new();
+        while let Some(row) = rows.next()? {
+            let count: i64 = row.get(1)?;
+            stats.push(CountryStats {
+                country: row.get(0)?,
+                count,
+                percentage: if total > 0 { (count as f64 / total as f64) * 100.0 } else { 0.0 },
+            });
+        }
+        Ok(stats)
+    }
+
+    /// 获取热门页面，`include_bots` 为 false 时不含爬虫
+    pub fn get_popular_pages(&self, limit: Option<usize>, include_bots: bool) -> Result<Vec<PopularPage>> {
+        let limit = limit.unwrap_or(10).min(MAX_STATS_LIMIT);
+        // 访问数缓存不含爬虫，包含爬虫时从原始记录统计
+        let mut stmt = if include_bots {
+            self.conn.prepare(
+                "SELECT page_slug, SUM(visits) AS visits FROM (
+                    SELECT page_slug, COUNT(*) AS visits FROM visits GROUP BY page_slug
+                    UNION ALL
+                    SELECT page_slug, SUM(visits) FROM daily_stats_agg GROUP BY page_slug
+                 )
+                 GROUP BY page_slug HAVING visits > 0 ORDER BY visits DESC LIMIT ?",
+            )?
+        } else {
+            self.conn.prepare(
+                "SELECT page_slug, visits FROM page_visit_counts WHERE visits > 0 ORDER BY visits DESC LIMIT ?",
+            )?
+        };
+
+        let mut rows = stmt.query(params![limit as i64])?;
         let mut pages = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -264,29 +531,110 @@ impl AnalyticsDB {
         Ok(pages)
     }
 
-    /// 获取总访问量
-    pub fn get_total_visits(&self) -> Result<i64> {
-        self.conn
-            .query_row("SELECT COUNT(*) FROM visits", [], |row| row.get(0))
+    /// 获取总访问量（包括已汇总的访问），`include_bots` 为 false 时不含爬虫
+    pub fn get_total_visits(&self, include_bots: bool) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT (SELECT COUNT(*) FROM visits WHERE ?1 OR is_bot = 0)
+                  + (SELECT COALESCE(SUM(visits), 0) FROM daily_stats_agg)",
+            params![include_bots],
+            |row| row.get(0),
+        )
+    }
+
+    /// 获取独立访客数，`include_bots` 为 false 时不含爬虫
+    pub fn get_unique_visitors(&self, include_bots: bool) -> Result<i64> {
+        self.conn.query_row(
+            "SELECT COUNT(DISTINCT ip_address) FROM visits WHERE ?1 OR is_bot = 0",
+            params![include_bots],
+            |row| row.get(0),
+        )
+    }
+
+    /// 获取最近 `within_minutes` 分钟内的访客数（按 IP 去重，不含爬虫）
+    pub fn get_realtime_visitors(&self, within_minutes: u64) -> Result<i64> {
+        let (day, since) = realtime_cutoff(within_minutes);
+        self.conn.query_row(
+            &format!("SELECT COUNT(DISTINCT ip_address) FROM visits WHERE {REALTIME_RANGE}"),
+            params![day, since],
+            |row| row.get(0),
+        )
+    }
+
+    /// 获取最近 `within_minutes` 分钟内访问最多的页面（不含爬虫）
+    pub fn get_realtime_pages(&self, within_minutes: u64, limit: usize) -> Result<Vec<RealtimePage>> {
+        let (day, since) = realtime_cutoff(within_minutes);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT page_slug, COUNT(*) AS count FROM visits WHERE {REALTIME_RANGE}
+             GROUP BY page_slug ORDER BY count DESC, page_slug LIMIT ?3"
+        ))?;
+        let rows = stmt.query_map(params![day, since, limit as i64], |row| {
+            Ok(RealtimePage {
+                slug: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        rows.collect()
     }
 
-    /// 获取独立访客数
-    pub fn get_unique_visitors(&self) -> Result<i64> {
-        self.conn
-            .query_row("SELECT COUNT(DISTINCT ip_address) FROM visits", [], |row| row.get(0))
+    /// 获取最近 `days` 天（含今天，UTC）按星期和小时统计的访问量，不含爬虫。
+    /// 返回完整的 7×24 个格子，按星期（0 为周日）再按小时排列
+    pub fn get_hourly_heatmap(&self, days: u32) -> Result<Vec<HourlyCell>> {
+        let today = chrono::Utc::now().date_naive();
+        let start = today - chrono::Duration::days(i64::from(days.max(1)) - 1);
+        let mut stmt = self.conn.prepare(
+            "SELECT CAST(strftime('%w', visit_time) AS INTEGER) AS wd,
+                    CAST(strftime('%H', visit_time) AS INTEGER) AS hr, COUNT(*)
+             FROM visits WHERE visit_time >= ?1 AND is_bot = 0
+             GROUP BY wd, hr",
+        )?;
+        let mut counts = [[0i64; 24]; 7];
+        let mut rows = stmt.query(params![start.format("%Y-%m-%d").to_string()])?;
+        while let Some(row) = rows.next()? {
+            // 无法解析的访问时间得到 NULL
+            if let (Some(weekday), Some(hour)) = (row.get::<_, Option<usize>>(0)?, row.get::<_, Option<usize>>(1)?) {
+                if weekday < 7 && hour < 24 {
+                    counts[weekday][hour] += row.get::<_, i64>(2)?;
+                }
+            }
+        }
+
+        Ok((0..7u8)
+            .flat_map(|weekday| (0..24u8).map(move |hour| (weekday, hour)))
+            .map(|(weekday, hour)| HourlyCell {
+                weekday,
+                hour,
+                visit_count: counts[usize::from(weekday)][usize::from(hour)],
+            })
+            .collect())
+    }
+
+    /// 获取某一天（YYYY-MM-DD，UTC）每小时的访问量，不含爬虫；返回 24 个 (小时, 访问量)
+    pub fn get_hourly_breakdown(&self, date: &str) -> Result<Vec<(u8, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT CAST(strftime('%H', visit_time) AS INTEGER) AS hr, COUNT(*) FROM visits
+             WHERE visit_time >= ?1 AND visit_time < date(?1, '+1 day') AND is_bot = 0
+             GROUP BY hr",
+        )?;
+        let mut counts = [0i64; 24];
+        let mut rows = stmt.query(params![date])?;
+        while let Some(row) = rows.next()? {
+            if let Some(hour) = row.get::<_, Option<usize>>(0)?.filter(|hour| *hour < 24) {
+                counts[hour] += row.get::<_, i64>(1)?;
+            }
+        }
+        Ok((0..24u8).zip(counts).collect())
     }
 
     /// 获取搜索引擎关键词统计
     pub fn get_search_keywords(&self, limit: Option<usize>) -> Result<Vec<(String, i64)>> {
         let limit = limit.unwrap_or(20);
-        let mut stmt = self.conn.prepare(&format!(
+        let mut stmt = self.conn.prepare(
             "SELECT search_keyword, COUNT(*) as count FROM visits
              WHERE search_keyword IS NOT NULL AND search_keyword != ''
-             GROUP BY search_keyword ORDER BY count DESC LIMIT {}",
-            limit
-        ))?;
+             GROUP BY search_keyword ORDER BY count DESC LIMIT ?",
+        )?;
 
-        let mut rows = stmt.query([])?;
+        let mut rows = stmt.query(params![limit as i64])?;
         let mut keywords = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -295,6 +643,129 @@ impl AnalyticsDB {
         Ok(keywords)
     }
 
+    /// 获取日期范围内的访问记录（按访问时间升序，日期格式 YYYY-MM-DD，含首尾两天）
+    pub fn get_visits_in_range(&self, start: &str, end: &str, limit: usize) -> Result<Vec<VisitRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, page_slug, ip_address, user_agent, referer, source, search_engine,
+                    search_keyword, visit_time, country, city, redirected_from, device_type, is_bot
+             FROM visits
+             WHERE date(visit_time) BETWEEN ?1 AND ?2
+             ORDER BY visit_time ASC
+             LIMIT ?3",
+        )?;
+
+        let rows = stmt.query_map(params![start, end, limit as i64], |row| {
+            let source: String = row.get(5)?;
+            Ok(VisitRecord {
+                id: row.get(0)?,
+                page_slug: row.get(1)?,
+                ip_address: row.get(2)?,
+                user_agent: row.get(3)?,
+                referer: row.get(4)?,
+                source: source.parse().unwrap_or_default(),
+                search_engine: row.get(6)?,
+                search_keyword: row.get(7)?,
+                visit_time: row.get(8)?,
+                country: row.get(9)?,
+                city: row.get(10)?,
+                redirected_from: row.get(11)?,
+                device_type: row
+                    .get::<_, Option<String>>(12)?
+                    .and_then(|d| d.parse().ok())
+                    .unwrap_or_default(),
+                is_bot: row.get(13)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 获取单个页面在 `start` 到 `end`（含首尾两天）之间的每日访问、来源和搜索关键词
+    pub fn get_page_analytics(&self, page_slug: &str, start: NaiveDate, end: NaiveDate) -> Result<PageAnalytics> {
+        // visit_time 以日期开头，按字符串比较即可使用 (page_slug, visit_time) 索引
+        let from = start.format("%Y-%m-%d").to_string();
+        let until = end.succ_opt().unwrap_or(end).format("%Y-%m-%d").to_string();
+        let range = "page_slug = ?1 AND visit_time >= ?2 AND visit_time < ?3";
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT date(visit_time) AS day, COUNT(*), COUNT(DISTINCT ip_address) FROM visits
+             WHERE {range} GROUP BY day"
+        ))?;
+        let mut counts = std::collections::HashMap::new();
+        let mut rows = stmt.query(params![page_slug, from, until])?;
+        while let Some(row) = rows.next()? {
+            let day: Option<String> = row.get(0)?;
+            counts.insert(day.unwrap_or_default(), (row.get::<_, i64>(1)?, row.get::<_, i64>(2)?));
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT date, visits, unique_visitors FROM daily_stats_agg
+             WHERE page_slug = ?1 AND date >= ?2 AND date < ?3",
+        )?;
+        let mut rows = stmt.query(params![page_slug, from, until])?;
+        let (mut rolled_visits, mut rolled_visitors) = (0, 0);
+        while let Some(row) = rows.next()? {
+            let (visits, visitors): (i64, i64) = (row.get(1)?, row.get(2)?);
+            let entry = counts.entry(row.get::<_, String>(0)?).or_insert((0, 0));
+            entry.0 += visits;
+            entry.1 += visitors;
+            rolled_visits += visits;
+            rolled_visitors += visitors;
+        }
+        let daily: Vec<PageDailyVisits> = start
+            .iter_days()
+            .take_while(|day| *day <= end)
+            .map(|day| {
+                let date = day.format("%Y-%m-%d").to_string();
+                let (visits, unique_visitors) = counts.get(&date).copied().unwrap_or((0, 0));
+                PageDailyVisits { date, visits, unique_visitors }
+            })
+            .collect();
+
+        let (raw_visits, raw_visitors): (i64, i64) = self.conn.query_row(
+            &format!("SELECT COUNT(*), COUNT(DISTINCT ip_address) FROM visits WHERE {range}"),
+            params![page_slug, from, until],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        // 来源和关键词只有原始记录里才有
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT source, COUNT(*) AS count FROM visits WHERE {range}
+             GROUP BY source ORDER BY count DESC"
+        ))?;
+        let sources = stmt
+            .query_map(params![page_slug, from, until], |row| {
+                let count: i64 = row.get(1)?;
+                Ok(SourceStats {
+                    source: row.get(0)?,
+                    count,
+                    percentage: if raw_visits > 0 { (count as f64 / raw_visits as f64) * 100.0 } else { 0.0 },
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT search_keyword, COUNT(*) AS count FROM visits
+             WHERE {range} AND search_keyword IS NOT NULL AND search_keyword != ''
+             GROUP BY search_keyword ORDER BY count DESC LIMIT ?4"
+        ))?;
+        let keywords = stmt
+            .query_map(params![page_slug, from, until, PAGE_KEYWORDS_LIMIT as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(PageAnalytics {
+            slug: page_slug.to_string(),
+            title: page_slug.to_string(), // 由调用方从 storage 填充真实标题
+            start_date: from,
+            end_date: end.format("%Y-%m-%d").to_string(),
+            total_visits: raw_visits + rolled_visits,
+            unique_visitors: raw_visitors + rolled_visitors,
+            daily,
+            sources,
+            keywords,
+        })
+    }
+
     /// 获取特定页面的访问量
     pub fn get_page_visits(&self, page_slug: &str) -> Result<i64> {
         self.conn.query_row(
@@ -304,6 +775,39 @@ impl AnalyticsDB {
         )
     }
 
+    /// 把 `cutoff` 之前的访问记录汇总到 `daily_stats_agg` 后删除，返回删除的记录数。
+    /// 爬虫的访问不汇总，直接删除。页面访问数缓存保持不变，热门页面仍包含已汇总的访问
+    pub fn rollup_visits_before(&self, cutoff: NaiveDate) -> Result<usize> {
+        let cutoff = cutoff.format("%Y-%m-%d").to_string();
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "CREATE TEMP TABLE rollup AS
+             SELECT date(visit_time) AS date, page_slug, COUNT(*) AS visits, COUNT(DISTINCT ip_address) AS unique_visitors
+             FROM visits WHERE date(visit_time) < ?1 AND is_bot = 0 GROUP BY date(visit_time), page_slug",
+            params![cutoff],
+        )?;
+        // 同一天可能分几次汇总（例如保留天数调小后），计数累加
+        tx.execute(
+            "INSERT INTO daily_stats_agg (date, page_slug, visits, unique_visitors)
+             SELECT date, page_slug, visits, unique_visitors FROM rollup WHERE true
+             ON CONFLICT(date, page_slug) DO UPDATE SET
+                visits = visits + excluded.visits,
+                unique_visitors = unique_visitors + excluded.unique_visitors",
+            [],
+        )?;
+        let deleted = tx.execute("DELETE FROM visits WHERE date(visit_time) < ?1", params![cutoff])?;
+        // 删除触发器减掉的访问数加回来
+        tx.execute(
+            "INSERT INTO page_visit_counts (page_slug, visits)
+             SELECT page_slug, SUM(visits) FROM rollup GROUP BY page_slug
+             ON CONFLICT(page_slug) DO UPDATE SET visits = visits + excluded.visits",
+            [],
+        )?;
+        tx.execute("DROP TABLE temp.rollup", [])?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
     /// 清理旧记录（保留最近 N 天）
     pub fn cleanup_old_records(&self, days_to_keep: i64) -> Result<usize> {
         let cutoff_date = chrono::Utc::now()
@@ -320,8 +824,55 @@ impl AnalyticsDB {
     }
 }
 
-/// 解析用户代理，提取浏览器和操作系统信息
-pub fn parse_user_agent(ua: &str) -> (String, String) {
+/// 已知爬虫和监控程序的用户代理特征（小写）
+const BOT_SIGNATURES: &[&str] = &[
+    "bot",
+    "crawl",
+    "spider",
+    "slurp",
+    "facebookexternalhit",
+    "bingpreview",
+    "headless",
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "okhttp",
+    "java/",
+    "libwww-perl",
+    "httpclient",
+    "axios/",
+    "node-fetch",
+    "uptime",
+    "pingdom",
+    "statuscake",
+    "site24x7",
+    "monitor",
+    "lighthouse",
+    "feedfetcher",
+    "mediapartners-google",
+    "yahoo! slurp",
+    "yandex",
+    "ia_archiver",
+    "petalsearch",
+    "scrapy",
+];
+
+/// 用户代理是否为爬虫或监控程序：匹配内置特征或配置中 `analytics.bot_user_agents`
+/// 的任意一项（不区分大小写）；空的用户代理也视为程序访问
+pub fn is_bot(user_agent: &str, extra_signatures: &[String]) -> bool {
+    let ua = user_agent.trim().to_lowercase();
+    ua.is_empty()
+        || BOT_SIGNATURES.iter().any(|sig| ua.contains(sig))
+        || extra_signatures
+            .iter()
+            .map(|sig| sig.trim().to_lowercase())
+            .any(|sig| !sig.is_empty() && ua.contains(&sig))
+}
+
+/// 解析用户代理，提取浏览器、操作系统和设备类型
+pub fn parse_user_agent(ua: &str) -> (String, String, DeviceType) {
     let ua = ua.to_lowercase();
 
     // 检测浏览器
@@ -358,7 +909,18 @@ pub fn parse_user_agent(ua: &str) -> (String, String) {
     }
     .to_string();
 
-    (browser, os)
+    // 检测设备类型：Android 平板的用户代理不带 "mobile"
+    let device = if is_bot(&ua, &[]) {
+        DeviceType::Bot
+    } else if ua.contains("ipad") || ua.contains("tablet") || (ua.contains("android") && !ua.contains("mobi")) {
+        DeviceType::Tablet
+    } else if ua.contains("mobi") {
+        DeviceType::Mobile
+    } else {
+        DeviceType::Desktop
+    };
+
+    (browser, os, device)
 }
 
 /// 检测访问来源
@@ -443,11 +1005,9 @@ fn extract_query_param(url: &str, param_name: &str) -> Option<String> {
     None
 }
 
-/// 从 IP 地址获取位置信息（简化版本，实际可以使用 GeoIP 数据库）
-pub fn get_location_from_ip(ip: &str) -> (Option<String>, Option<String>) {
-    // 简化实现：返回 None
-    // 实际使用中，可以使用 MaxMind GeoIP 或其他服务
-    (None, None)
+/// 从 IP 地址获取国家代码和城市名，未配置 GeoIP 数据库时返回 None
+pub fn get_location_from_ip(ip: &str, geoip: Option<&GeoIp>) -> (Option<String>, Option<String>) {
+    geoip.map_or((None, None), |geoip| geoip.locate(ip))
 }
 
 // ==================== API Handler Functions ====================
@@ -460,20 +1020,39 @@ pub struct DailyStatsQuery {
     pub start_date: Option<String>,
     pub end_date: Option<String>,
     pub limit: Option<usize>,
+    #[serde(default)]
+    pub include_bots: bool, // 包含爬虫的访问
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/analytics/daily-stats",
+    tag = "analytics",
+    summary = "每日访问统计",
+    params(("start_date" = Option<String>, Query, description = "开始日期（YYYY-MM-DD）"), ("end_date" = Option<String>, Query, description = "结束日期（YYYY-MM-DD）"), ("limit" = Option<u32>, Query, description = "最多返回天数"), ("include_bots" = Option<bool>, Query, description = "包含爬虫的访问")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "stats": [{"date": "2024-05-01", "total_visits": 30, "unique_visitors": 12, "page_views": 30}], "count": 1})))
+)]
 pub async fn get_daily_stats(
     query: web::Query<DailyStatsQuery>,
     config: web::Data<Config>,
 ) -> impl Responder {
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+    let (start, end) = match (
+        parse_date_param(query.start_date.as_deref()),
+        parse_date_param(query.end_date.as_deref()),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "日期格式错误，应为 YYYY-MM-DD"
+            }))
+        }
+    };
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
-            let stats = db.get_daily_stats(
-                query.start_date.as_deref(),
-                query.end_date.as_deref(),
-            );
+            let stats = db.get_daily_stats(start.as_deref(), end.as_deref(), query.include_bots);
             match stats {
                 Ok(stats) => HttpResponse::Ok().json(serde_json::json!({
                     "status": "success",
@@ -499,11 +1078,19 @@ pub struct SourceStatsQuery {
     pub limit: Option<usize>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/analytics/source-stats",
+    tag = "analytics",
+    summary = "访问来源统计",
+    params(("limit" = Option<u32>, Query, description = "最多返回条数")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "sources": [{"source": "search", "count": 20, "percentage": 66.7}], "count": 1})))
+)]
 pub async fn get_source_stats(
     query: web::Query<SourceStatsQuery>,
     config: web::Data<Config>,
 ) -> impl Responder {
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
@@ -527,21 +1114,118 @@ pub async fn get_source_stats(
     }
 }
 
+/// 获取设备类型统计（需要管理员登录）
+#[utoipa::path(
+    get,
+    path = "/api/analytics/device-stats",
+    tag = "analytics",
+    summary = "访问设备统计",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "devices": [{"device_type": "desktop", "count": 80, "percentage": 66.7}], "count": 1}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_device_stats(req: HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
+
+    match AnalyticsDB::new(&db_path) {
+        Ok(db) => match db.get_device_stats() {
+            Ok(stats) => HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "devices": stats,
+                "count": stats.len()
+            })),
+            Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            })),
+        },
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 获取国家统计（需要管理员登录）
+#[derive(Deserialize)]
+pub struct CountryStatsQuery {
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/countries",
+    tag = "analytics",
+    summary = "访客国家统计（需要配置 GeoIP 数据库）",
+    params(("limit" = Option<u32>, Query, description = "最多返回条数")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "countries": [{"country": "CN", "count": 30, "percentage": 75.0}], "count": 1, "geoip_enabled": true}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_country_stats(
+    req: HttpRequest,
+    query: web::Query<CountryStatsQuery>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
+
+    match AnalyticsDB::new(&db_path) {
+        Ok(db) => match db.get_country_stats(query.limit) {
+            Ok(stats) => HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "countries": stats,
+                "count": stats.len(),
+                "geoip_enabled": config.analytics.geoip_db_path.is_some()
+            })),
+            Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            })),
+        },
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
 /// 获取热门页面
 #[derive(Deserialize)]
 pub struct PopularPagesQuery {
     pub limit: Option<usize>,
+    #[serde(default)]
+    pub include_bots: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/analytics/popular-pages",
+    tag = "analytics",
+    summary = "热门页面",
+    params(("limit" = Option<u32>, Query, description = "最多返回条数"), ("include_bots" = Option<bool>, Query, description = "包含爬虫的访问")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "pages": [{"slug": "wine-merlot", "title": "wine-merlot", "visits": 42}], "count": 1})))
+)]
 pub async fn get_popular_pages(
     query: web::Query<PopularPagesQuery>,
     config: web::Data<Config>,
 ) -> impl Responder {
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
-            let pages = db.get_popular_pages(query.limit);
+            let pages = db.get_popular_pages(query.limit, query.include_bots);
             match pages {
                 Ok(pages) => HttpResponse::Ok().json(serde_json::json!({
                     "status": "success",
@@ -562,13 +1246,27 @@ pub async fn get_popular_pages(
 }
 
 /// 获取总统计
-pub async fn get_total_stats(config: web::Data<Config>) -> impl Responder {
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+#[derive(Deserialize)]
+pub struct TotalStatsQuery {
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/analytics/total-stats",
+    tag = "analytics",
+    summary = "总访问量和独立访客数",
+    params(("include_bots" = Option<bool>, Query, description = "包含爬虫的访问")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "stats": {"total_visits": 120, "unique_visitors": 45}})))
+)]
+pub async fn get_total_stats(query: web::Query<TotalStatsQuery>, config: web::Data<Config>) -> impl Responder {
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
-            let total_visits = db.get_total_visits().unwrap_or(0);
-            let unique_visitors = db.get_unique_visitors().unwrap_or(0);
+            let total_visits = db.get_total_visits(query.include_bots).unwrap_or(0);
+            let unique_visitors = db.get_unique_visitors(query.include_bots).unwrap_or(0);
             HttpResponse::Ok().json(serde_json::json!({
                 "status": "success",
                 "stats": {
@@ -590,15 +1288,23 @@ pub struct KeywordsQuery {
     pub limit: Option<usize>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/analytics/search-keywords",
+    tag = "analytics",
+    summary = "搜索引擎带来的关键词",
+    params(("limit" = Option<u32>, Query, description = "最多返回条数")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "keywords": [["merlot", 5]], "count": 1})))
+)]
 pub async fn get_search_keywords(
     query: web::Query<KeywordsQuery>,
     config: web::Data<Config>,
 ) -> impl Responder {
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
-            let keywords = db.get_search_keywords(query.limit);
+            let keywords = db.get_search_keywords(Some(query.limit.unwrap_or(20).min(MAX_STATS_LIMIT)));
             match keywords {
                 Ok(keywords) => HttpResponse::Ok().json(serde_json::json!({
                     "status": "success",
@@ -618,16 +1324,487 @@ pub async fn get_search_keywords(
     }
 }
 
-/// 记录页面访问（中间件会调用）
+/// 单页统计
+#[derive(Deserialize)]
+pub struct PageAnalyticsQuery {
+    pub days: Option<u32>,
+}
+
+/// 单页统计默认和最长的天数
+const DEFAULT_PAGE_STATS_DAYS: u32 = 30;
+const MAX_PAGE_STATS_DAYS: u32 = 365;
+/// 单页统计返回的搜索关键词数量
+const PAGE_KEYWORDS_LIMIT: usize = 20;
+
+/// 读取 `slug` 最近 `days` 天（含今天）的访问统计；页面不存在时返回 `NotFound`
+pub fn load_page_analytics(config: &Config, slug: &str, days: Option<u32>) -> std::result::Result<PageAnalytics, BlogError> {
+    let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy())?;
+    let page = storage
+        .get_page(slug)?
+        .ok_or_else(|| BlogError::NotFound(format!("页面不存在: {}", slug)))?;
+
+    let days = days.unwrap_or(DEFAULT_PAGE_STATS_DAYS).clamp(1, MAX_PAGE_STATS_DAYS);
+    let end = chrono::Utc::now().date_naive();
+    let start = end - chrono::Duration::days(i64::from(days) - 1);
+    let db = AnalyticsDB::new(&config.paths.analytics_db.to_string_lossy())?;
+    let mut stats = db.get_page_analytics(slug, start, end)?;
+    stats.title = page.title;
+    Ok(stats)
+}
+
+/// 获取单个页面的每日访问、来源和搜索关键词（需要管理员登录）
+///
+/// `days` 为统计天数，默认 30，最多 365；页面不存在时返回 404
+#[utoipa::path(
+    get,
+    path = "/api/analytics/page/{slug}",
+    tag = "analytics",
+    summary = "单个页面的每日访问、来源和关键词",
+    params(("slug" = String, Path), ("days" = Option<u32>, Query, description = "统计最近多少天，默认 30")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "stats": {"slug": "wine-merlot", "title": "Merlot", "start_date": "2024-04-02", "end_date": "2024-05-01", "total_visits": 42, "unique_visitors": 30, "daily": [{"date": "2024-05-01", "visits": 3}], "sources": [{"source": "search", "count": 20, "percentage": 47.6}], "keywords": [["merlot", 5]]}}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_page_analytics(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<PageAnalyticsQuery>,
+    config: web::Data<Config>,
+) -> std::result::Result<HttpResponse, BlogError> {
+    if !crate::admin::require_auth(&req, &config) {
+        return Err(BlogError::Unauthorized("未授权访问，请先登录".to_string()));
+    }
+
+    let stats = load_page_analytics(&config, &path.into_inner(), query.days)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "stats": stats
+    })))
+}
+
+/// 热力图默认和最长的天数
+const DEFAULT_HEATMAP_DAYS: u32 = 30;
+const MAX_HEATMAP_DAYS: u32 = 365;
+
+/// 访问热力图
+#[derive(Deserialize)]
+pub struct HeatmapQuery {
+    pub days: Option<u32>,
+}
+
+/// 获取最近 `days` 天（默认 30，最多 365）按星期和小时统计的访问量（需要管理员登录）
+#[utoipa::path(
+    get,
+    path = "/api/analytics/heatmap",
+    tag = "analytics",
+    summary = "按星期（0 为周日）和小时（UTC）统计的访问量，共 7×24 格，不含爬虫",
+    params(("days" = Option<u32>, Query, description = "统计最近多少天，默认 30，最多 365")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "days": 30, "max_count": 12, "cells": [{"weekday": 0, "hour": 0, "visit_count": 3}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_hourly_heatmap(
+    req: HttpRequest,
+    query: web::Query<HeatmapQuery>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let days = query.days.unwrap_or(DEFAULT_HEATMAP_DAYS).clamp(1, MAX_HEATMAP_DAYS);
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
+
+    match AnalyticsDB::new(&db_path).and_then(|db| db.get_hourly_heatmap(days)) {
+        Ok(cells) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "days": days,
+            "max_count": cells.iter().map(|cell| cell.visit_count).max().unwrap_or(0),
+            "cells": cells
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 单日每小时访问量
+#[derive(Deserialize)]
+pub struct HourlyBreakdownQuery {
+    pub date: Option<String>,
+}
+
+/// 获取某一天（默认今天）每小时的访问量（需要管理员登录）
+#[utoipa::path(
+    get,
+    path = "/api/analytics/hourly",
+    tag = "analytics",
+    summary = "某一天每小时（UTC）的访问量，不含爬虫",
+    params(("date" = Option<String>, Query, description = "日期 YYYY-MM-DD，默认今天")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "date": "2024-05-01", "hours": [{"hour": 0, "visit_count": 2}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_hourly_breakdown(
+    req: HttpRequest,
+    query: web::Query<HourlyBreakdownQuery>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let date = match parse_date_param(query.date.as_deref()) {
+        Ok(date) => date.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string()),
+        Err(_) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "日期格式错误，应为 YYYY-MM-DD"
+            }))
+        }
+    };
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
+
+    match AnalyticsDB::new(&db_path).and_then(|db| db.get_hourly_breakdown(&date)) {
+        Ok(hours) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "date": date,
+            "hours": hours
+                .iter()
+                .map(|(hour, count)| serde_json::json!({"hour": hour, "visit_count": count}))
+                .collect::<Vec<_>>()
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 实时统计默认和最长的时间窗口（分钟）
+const DEFAULT_REALTIME_MINUTES: u64 = 5;
+const MAX_REALTIME_MINUTES: u64 = 1440;
+/// 实时统计返回的页面数量
+const REALTIME_TOP_PAGES: usize = 10;
+/// 实时统计推送间隔
+const REALTIME_STREAM_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// 实时访客
+#[derive(Deserialize)]
+pub struct RealtimeQuery {
+    pub minutes: Option<u64>,
+}
+
+/// 读取最近 `within_minutes` 分钟（默认 5，最多 1440）内的访客数和热门页面
+pub fn load_realtime_stats(config: &Config, within_minutes: Option<u64>) -> std::result::Result<RealtimeStats, BlogError> {
+    let within_minutes = within_minutes.unwrap_or(DEFAULT_REALTIME_MINUTES).clamp(1, MAX_REALTIME_MINUTES);
+    let db = AnalyticsDB::new(&config.paths.analytics_db.to_string_lossy())?;
+    Ok(RealtimeStats {
+        active_visitors: db.get_realtime_visitors(within_minutes)?,
+        within_minutes,
+        top_pages: db.get_realtime_pages(within_minutes, REALTIME_TOP_PAGES)?,
+    })
+}
+
+/// 获取最近几分钟内的访客数和热门页面（需要管理员登录）
+#[utoipa::path(
+    get,
+    path = "/api/analytics/realtime",
+    tag = "analytics",
+    summary = "最近几分钟内的访客数和热门页面（不含爬虫）",
+    params(("minutes" = Option<u32>, Query, description = "时间窗口（分钟），默认 5，最多 1440")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "active_visitors": 3, "within_minutes": 5, "top_pages": [{"slug": "wine-merlot", "count": 2}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_realtime_visitors(
+    req: HttpRequest,
+    query: web::Query<RealtimeQuery>,
+    config: web::Data<Config>,
+) -> std::result::Result<HttpResponse, BlogError> {
+    if !crate::admin::require_auth(&req, &config) {
+        return Err(BlogError::Unauthorized("未授权访问，请先登录".to_string()));
+    }
+
+    let stats = load_realtime_stats(&config, query.minutes)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "active_visitors": stats.active_visitors,
+        "within_minutes": stats.within_minutes,
+        "top_pages": stats.top_pages
+    })))
+}
+
+/// 以 Server-Sent Events 每 10 秒推送一次实时统计（需要管理员登录）
+///
+/// 每条事件的 data 与 `/api/analytics/realtime` 的统计字段相同；读取失败时发送 `error` 事件
+#[utoipa::path(
+    get,
+    path = "/api/analytics/realtime/stream",
+    tag = "analytics",
+    summary = "以 Server-Sent Events 每 10 秒推送实时访客统计",
+    params(("minutes" = Option<u32>, Query, description = "时间窗口（分钟），默认 5，最多 1440")),
+    responses((status = 200, description = "成功", body = String, content_type = "text/event-stream")),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn stream_realtime_visitors(
+    req: HttpRequest,
+    query: web::Query<RealtimeQuery>,
+    config: web::Data<Config>,
+) -> std::result::Result<HttpResponse, BlogError> {
+    if !crate::admin::require_auth(&req, &config) {
+        return Err(BlogError::Unauthorized("未授权访问，请先登录".to_string()));
+    }
+
+    let minutes = query.minutes;
+    let events = futures_util::stream::unfold((config, true), move |(config, first)| async move {
+        if !first {
+            actix_web::rt::time::sleep(REALTIME_STREAM_INTERVAL).await;
+        }
+        let event = match load_realtime_stats(&config, minutes) {
+            Ok(stats) => format!("data: {}\n\n", serde_json::to_string(&stats).unwrap_or_default()),
+            Err(e) => format!("event: error\ndata: {}\n\n", e),
+        };
+        Some((Ok::<_, actix_web::Error>(web::Bytes::from(event)), (config, false)))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+        // 压缩会缓冲事件，反向代理也不要缓冲
+        .insert_header((actix_web::http::header::CONTENT_ENCODING, "identity"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(events))
+}
+
+/// 单次导出的最大行数
+const MAX_EXPORT_ROWS: usize = 100_000;
+/// 统计接口 `limit` 参数的上限
+const MAX_STATS_LIMIT: usize = 100;
+
+/// 导出统计数据
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(rename = "type")]
+    pub export_type: Option<String>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+/// 导出统计数据为 CSV（需要管理员登录）
+///
+/// `type` 可选 `visits`（默认）、`daily`、`keywords`、`devices`；日期范围对 `keywords` 和 `devices` 不生效
+#[utoipa::path(
+    get,
+    path = "/api/admin/analytics/export",
+    tag = "analytics",
+    summary = "导出统计数据为 CSV",
+    params(("type" = Option<String>, Query, description = "visits（默认）、daily、keywords 或 devices"), ("start_date" = Option<String>, Query, description = "开始日期（YYYY-MM-DD）"), ("end_date" = Option<String>, Query, description = "结束日期（YYYY-MM-DD）")),
+    responses((status = 200, description = "成功", body = String, content_type = "text/csv")),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn export_analytics(
+    req: HttpRequest,
+    query: web::Query<ExportQuery>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let (start, end) = match (
+        parse_export_date(query.start_date.as_deref(), "1970-01-01"),
+        parse_export_date(query.end_date.as_deref(), "9999-12-31"),
+    ) {
+        (Some(start), Some(end)) => (start, end),
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "日期格式错误，应为 YYYY-MM-DD"
+            }))
+        }
+    };
+
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
+    let db = match AnalyticsDB::new(&db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+        }
+    };
+
+    let export_type = query.export_type.as_deref().unwrap_or("visits");
+    let rows: Result<Vec<String>> = match export_type {
+        "visits" => db.get_visits_in_range(&start, &end, MAX_EXPORT_ROWS).map(|visits| {
+            visits
+                .iter()
+                .map(|v| {
+                    csv_row(&[
+                        &v.visit_time,
+                        &v.page_slug,
+                        &v.ip_address,
+                        &v.source.to_string(),
+                        v.search_engine.as_deref().unwrap_or(""),
+                        v.search_keyword.as_deref().unwrap_or(""),
+                        &v.user_agent,
+                        v.country.as_deref().unwrap_or(""),
+                        v.city.as_deref().unwrap_or(""),
+                        &v.device_type.to_string(),
+                    ])
+                })
+                .collect()
+        }),
+        "daily" => db.get_daily_stats(Some(&start), Some(&end), false).map(|stats| {
+            stats
+                .iter()
+                .map(|s| {
+                    csv_row(&[
+                        &s.date,
+                        &s.total_visits.to_string(),
+                        &s.unique_visitors.to_string(),
+                        &s.page_views.to_string(),
+                    ])
+                })
+                .collect()
+        }),
+        "keywords" => db.get_search_keywords(Some(MAX_EXPORT_ROWS)).map(|keywords| {
+            keywords
+                .iter()
+                .map(|(keyword, count)| csv_row(&[keyword, &count.to_string()]))
+                .collect()
+        }),
+        "devices" => db.get_device_stats().map(|stats| {
+            stats
+                .iter()
+                .map(|s| csv_row(&[&s.device_type, &s.count.to_string(), &format!("{:.1}", s.percentage)]))
+                .collect()
+        }),
+        _ => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "不支持的导出类型，可选 visits、daily、keywords、devices"
+            }))
+        }
+    };
+
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }))
+        }
+    };
+
+    let header = match export_type {
+        "visits" => "date,page_slug,ip_address,source,search_engine,search_keyword,user_agent,country,city,device_type",
+        "daily" => "date,total_visits,unique_visitors,page_views",
+        "devices" => "device_type,count,percentage",
+        _ => "keyword,count",
+    };
+    let body = futures_util::stream::iter(
+        std::iter::once(format!("{}\r\n", header))
+            .chain(rows)
+            .map(|line| Ok::<_, actix_web::Error>(web::Bytes::from(line))),
+    );
+
+    let filename = format!(
+        "analytics_{}_{}.csv",
+        export_type,
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            actix_web::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .streaming(body)
+}
+
+/// 校验导出日期，未提供时使用默认值
+fn parse_export_date(date: Option<&str>, default: &str) -> Option<String> {
+    parse_date_param(date)
+        .ok()
+        .map(|date| date.unwrap_or_else(|| default.to_string()))
+}
+
+/// 校验可选的 YYYY-MM-DD 日期参数，返回补齐前导零后的日期；格式错误时返回原值
+fn parse_date_param(date: Option<&str>) -> std::result::Result<Option<String>, String> {
+    match date.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+            .map_err(|_| date.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// 按 RFC 4180 拼接一行 CSV：含逗号、引号或换行的字段加引号
+fn csv_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// 记录页面访问（中间件会调用），客户端发送 `DNT: 1` 时不记录
+#[utoipa::path(
+    post,
+    path = "/api/analytics/record-visit/{page_slug}",
+    tag = "analytics",
+    summary = "记录一次页面访问（客户端发送 DNT: 1 时不记录）",
+    params(("page_slug" = String, Path)),
+    responses((status = 200, description = "成功"))
+)]
 pub async fn record_visit(
     req: HttpRequest,
     path: web::Path<String>,
     config: web::Data<Config>,
 ) -> impl Responder {
-    let page_slug = path.into_inner();
+    if !do_not_track(&req) {
+        let record = build_visit_record(&req, path.into_inner(), None, &config);
+        save_visit_record(&record, &config);
+    }
 
+    HttpResponse::Ok().finish()
+}
+
+/// 根据请求构建访问记录，`analytics.anonymize_ip` 开启时保存匿名化后的 IP
+pub fn build_visit_record(
+    req: &HttpRequest,
+    page_slug: String,
+    redirected_from: Option<String>,
+    config: &Config,
+) -> VisitRecord {
     // 获取请求信息
-    let ip_address = get_client_ip(&req);
+    let ip_address = crate::ip_filter::client_ip(req);
+
+    // 获取位置信息（在匿名化之前用完整的 IP 查询）
+    let geoip = req.app_data::<web::Data<GeoIp>>().map(|geoip| geoip.get_ref());
+    let (country, city) = get_location_from_ip(&ip_address, geoip);
+
+    let ip_address = if config.analytics.anonymize_ip {
+        anonymize_ip(&ip_address)
+    } else {
+        ip_address
+    };
     let user_agent = req
         .headers()
         .get("user-agent")
@@ -644,11 +1821,13 @@ pub async fn record_visit(
     // 检测来源
     let (source, search_engine, search_keyword) = detect_source(&referer, &user_agent);
 
-    // 获取位置信息
-    let (country, city) = get_location_from_ip(&ip_address);
+    // 检测设备类型，配置中补充的爬虫也归为爬虫
+    let is_bot = is_bot(&user_agent, &config.analytics.bot_user_agents);
+    let (_, _, device_type) = parse_user_agent(&user_agent);
+    let device_type = if is_bot { DeviceType::Bot } else { device_type };
 
     // 创建访问记录
-    let record = VisitRecord {
+    VisitRecord {
         id: uuid::Uuid::new_v4().to_string(),
         page_slug,
         ip_address,
@@ -660,48 +1839,48 @@ pub async fn record_visit(
         visit_time: chrono::Utc::now().to_rfc3339(),
         country,
         city,
-    };
+        redirected_from,
+        device_type,
+        is_bot,
+    }
+}
 
-    // 保存到数据库
-    let db_path = config.paths.database_path.to_string_lossy().to_string();
+/// 保存访问记录到数据库
+pub fn save_visit_record(record: &VisitRecord, config: &Config) {
+    let db_path = config.paths.analytics_db.to_string_lossy().to_string();
 
     match AnalyticsDB::new(&db_path) {
         Ok(db) => {
-            db.record_visit(&record).ok();
+            db.record_visit(record).ok();
         }
         Err(_) => {
             // 静默失败，不影响正常请求
         }
     }
+}
 
-    HttpResponse::Ok().finish()
+/// 客户端是否通过 `DNT: 1` 请求不被统计
+pub fn do_not_track(req: &HttpRequest) -> bool {
+    req.headers()
+        .get("dnt")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|v| v.trim() == "1")
 }
 
-/// 从请求中获取客户端 IP
-fn get_client_ip(req: &HttpRequest) -> String {
-    // 检查 X-Forwarded-For 头（代理场景）
-    if let Some(forwarded) = req.headers().get("x-forwarded-for") {
-        if let Ok(forwarded_str) = forwarded.to_str() {
-            // X-Forwarded-For 可能包含多个 IP，取第一个
-            if let Some(ip) = forwarded_str.split(',').next() {
-                return ip.trim().to_string();
-            }
+/// 匿名化 IP：IPv4 末段置 0，IPv6 保留前 48 位、其余 80 位置 0；无法解析时原样返回
+pub fn anonymize_ip(ip: &str) -> String {
+    match ip.trim().parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(v4)) => {
+            let [a, b, c, _] = v4.octets();
+            std::net::Ipv4Addr::new(a, b, c, 0).to_string()
         }
-    }
-
-    // 检查 X-Real-IP 头
-    if let Some(real_ip) = req.headers().get("x-real-ip") {
-        if let Ok(ip) = real_ip.to_str() {
-            return ip.to_string();
+        Ok(std::net::IpAddr::V6(v6)) => {
+            let mut segments = v6.segments();
+            segments[3..].fill(0);
+            std::net::Ipv6Addr::from(segments).to_string()
         }
+        Err(_) => ip.to_string(),
     }
-
-    // 获取连接远程地址
-    if let Some(peer) = req.peer_addr() {
-        return peer.ip().to_string();
-    }
-
-    "0.0.0.0".to_string()
 }
 
 #[cfg(test)]
@@ -710,19 +1889,520 @@ mod tests {
 
     #[test]
     fn test_parse_user_agent() {
-        let (browser, os) = parse_user_agent(
+        let (browser, os, _) = parse_user_agent(
             "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
         );
         assert_eq!(browser, "Chrome");
         assert_eq!(os, "macOS");
 
-        let (browser, os) = parse_user_agent(
+        let (browser, os, _) = parse_user_agent(
             "Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1",
         );
         assert_eq!(browser, "Safari");
         assert_eq!(os, "iOS");
     }
 
+    #[test]
+    fn test_device_type() {
+        let device = |ua| parse_user_agent(ua).2;
+        assert_eq!(
+            device("Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1"),
+            DeviceType::Mobile
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (iPad; CPU OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Safari/604.1"),
+            DeviceType::Tablet
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (Linux; Android 13; SM-X700) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"),
+            DeviceType::Tablet
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36"),
+            DeviceType::Desktop
+        );
+        assert_eq!(
+            device("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"),
+            DeviceType::Bot
+        );
+        assert_eq!(device("Mozilla/5.0 (compatible; Baiduspider/2.0)"), DeviceType::Bot);
+    }
+
+    #[test]
+    fn test_is_bot() {
+        let cases: &[(&str, bool)] = &[
+            ("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)", true),
+            ("Mozilla/5.0 (Linux; Android 6.0.1; Nexus 5X Build/MMB29P) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.216 Mobile Safari/537.36 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)", true),
+            ("Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)", true),
+            ("Mozilla/5.0 (compatible; Baiduspider/2.0; +http://www.baidu.com/search/spider.html)", true),
+            ("Sogou web spider/4.0(+http://www.sogou.com/docs/help/webmasters.htm#07)", true),
+            ("Mozilla/5.0 (compatible; YandexBot/3.0; +http://yandex.com/bots)", true),
+            ("Mozilla/5.0 (compatible; Yahoo! Slurp; http://help.yahoo.com/help/us/ysearch/slurp)", true),
+            ("DuckDuckBot/1.1; (+http://duckduckgo.com/duckduckbot.html)", true),
+            ("Mozilla/5.0 (compatible; AhrefsBot/7.0; +http://ahrefs.com/robot/)", true),
+            ("Mozilla/5.0 (compatible; SemrushBot/7~bl; +http://www.semrush.com/bot.html)", true),
+            ("Mozilla/5.0 (Linux; Android 7.0;) AppleWebKit/537.36 (KHTML, like Gecko) Mobile Safari/537.36 (compatible; PetalBot;+https://webmaster.petalsearch.com/site/petalbot)", true),
+            ("Mozilla/5.0 AppleWebKit/537.36 (KHTML, like Gecko; compatible; GPTBot/1.0; +https://openai.com/gptbot)", true),
+            ("facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)", true),
+            ("Twitterbot/1.0", true),
+            ("Mozilla/5.0 (compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)", true),
+            ("Pingdom.com_bot_version_1.4_(http://www.pingdom.com/)", true),
+            ("Mozilla/5.0 (compatible; StatusCake)", true),
+            ("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36", true),
+            ("Mozilla/5.0 (Linux; Android 11; moto g power (2022)) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Mobile Safari/537.36 Chrome-Lighthouse", true),
+            ("curl/8.4.0", true),
+            ("Wget/1.21.4", true),
+            ("python-requests/2.31.0", true),
+            ("Go-http-client/2.0", true),
+            ("okhttp/4.12.0", true),
+            ("Scrapy/2.11.0 (+https://scrapy.org)", true),
+            ("", true),
+            ("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36", false),
+            ("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Safari/605.1.15", false),
+            ("Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.1 Mobile/15E148 Safari/604.1", false),
+            ("Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0", false),
+            ("Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36", false),
+            ("Mozilla/5.0 (iPhone; CPU iPhone OS 17_1 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148 MicroMessenger/8.0.44", false),
+        ];
+        for (ua, expected) in cases {
+            assert_eq!(is_bot(ua, &[]), *expected, "{}", ua);
+        }
+
+        // 配置中补充的特征不区分大小写
+        let extra = vec!["MyHealthCheck".to_string(), "  ".to_string()];
+        assert!(is_bot("myhealthcheck/1.0", &extra));
+        assert!(!is_bot("Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0", &extra));
+    }
+
+    #[test]
+    fn test_bot_visits_excluded() {
+        let db_path = "/tmp/test_analytics_bots.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        for (id, slug, bot) in [(1, "wine-1", false), (2, "wine-1", true), (3, "wine-2", true), (4, "wine-2", true), (5, "wine-2", true)] {
+            let mut visit = synthetic_visit(id, slug, "2024-05-01 10:00:00");
+            visit.is_bot = bot;
+            db.record_visit(&visit).unwrap();
+        }
+
+        assert_eq!(db.get_total_visits(false).unwrap(), 1);
+        assert_eq!(db.get_total_visits(true).unwrap(), 5);
+        assert_eq!(db.get_unique_visitors(false).unwrap(), 1);
+        assert_eq!(db.get_unique_visitors(true).unwrap(), 5);
+        assert_eq!(db.get_daily_stats(None, None, false).unwrap()[0].total_visits, 1);
+        assert_eq!(db.get_daily_stats(None, None, true).unwrap()[0].total_visits, 5);
+        assert_eq!(popular(&db, false), vec![("wine-1".to_string(), 1)]);
+        assert_eq!(popular(&db, true), vec![("wine-2".to_string(), 3), ("wine-1".to_string(), 2)]);
+
+        // 爬虫的访问在汇总时直接删除
+        let cutoff = NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+        assert_eq!(db.rollup_visits_before(cutoff).unwrap(), 5);
+        assert_eq!(db.get_total_visits(true).unwrap(), 1);
+        assert_eq!(popular(&db, false), vec![("wine-1".to_string(), 1)]);
+
+        // 旧数据库：没有 is_bot 列时按设备类型补标记并重算页面访问数
+        let _ = std::fs::remove_file(db_path);
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE visits (id TEXT PRIMARY KEY, page_slug TEXT NOT NULL, ip_address TEXT NOT NULL,
+                user_agent TEXT NOT NULL, referer TEXT NOT NULL, source TEXT NOT NULL, search_engine TEXT,
+                search_keyword TEXT, visit_time TEXT NOT NULL, country TEXT, city TEXT,
+                redirected_from TEXT, device_type TEXT);
+             INSERT INTO visits VALUES ('1', 'wine-1', '1.1.1.1', 'Googlebot', '', 'direct', NULL, NULL, '2024-05-01 10:00:00', NULL, NULL, NULL, 'bot');
+             INSERT INTO visits VALUES ('2', 'wine-1', '1.1.1.2', 'Firefox', '', 'direct', NULL, NULL, '2024-05-01 11:00:00', NULL, NULL, NULL, 'desktop');
+             CREATE TABLE page_visit_counts (page_slug TEXT PRIMARY KEY, visits INTEGER NOT NULL);
+             INSERT INTO page_visit_counts VALUES ('wine-1', 2);",
+        )
+        .unwrap();
+        drop(conn);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        assert_eq!(db.get_total_visits(false).unwrap(), 1);
+        assert_eq!(popular(&db, false), vec![("wine-1".to_string(), 1)]);
+        let mut visit = synthetic_visit(3, "wine-1", "2024-05-02 10:00:00");
+        visit.is_bot = true;
+        db.record_visit(&visit).unwrap();
+        assert_eq!(popular(&db, false), vec![("wine-1".to_string(), 1)]);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_realtime_visitors() {
+        let db_path = "/tmp/test_analytics_realtime.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        let ago = |minutes: i64| chrono::Utc::now() - chrono::Duration::minutes(minutes);
+        for (id, slug, time, bot) in [
+            (1, "wine-1", ago(1).to_rfc3339(), false),
+            (2, "wine-1", ago(2).format("%Y-%m-%d %H:%M:%S").to_string(), false),
+            (3, "wine-2", ago(3).to_rfc3339(), false),
+            (4, "wine-2", ago(1).to_rfc3339(), true),
+            (5, "wine-3", ago(30).to_rfc3339(), false),
+            (6, "wine-3", "2024-05-01 10:00:00".to_string(), false),
+        ] {
+            let mut visit = synthetic_visit(id, slug, &time);
+            visit.is_bot = bot;
+            db.record_visit(&visit).unwrap();
+        }
+
+        assert_eq!(db.get_realtime_visitors(5).unwrap(), 3);
+        assert_eq!(db.get_realtime_visitors(60).unwrap(), 4);
+        assert_eq!(
+            db.get_realtime_pages(5, 10).unwrap(),
+            vec![
+                RealtimePage { slug: "wine-1".to_string(), count: 2 },
+                RealtimePage { slug: "wine-2".to_string(), count: 1 },
+            ]
+        );
+        assert_eq!(db.get_realtime_pages(60, 1).unwrap().len(), 1);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_hourly_heatmap() {
+        let db_path = "/tmp/test_analytics_heatmap.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        let today = chrono::Utc::now().date_naive();
+        let at = |day: chrono::NaiveDate, time: &str| format!("{} {}", day.format("%Y-%m-%d"), time);
+        for (id, time, bot) in [
+            (1, at(today, "00:10:00"), false),
+            (2, format!("{}T00:50:00+00:00", today.format("%Y-%m-%d")), false),
+            (3, at(today, "23:00:00"), false),
+            (4, at(today, "23:30:00"), true),
+            (5, at(today - chrono::Duration::days(1), "09:00:00"), false),
+            (6, at(today - chrono::Duration::days(40), "09:00:00"), false),
+        ] {
+            let mut visit = synthetic_visit(id, "wine-1", &time);
+            visit.is_bot = bot;
+            db.record_visit(&visit).unwrap();
+        }
+
+        let cells = db.get_hourly_heatmap(30).unwrap();
+        assert_eq!(cells.len(), 7 * 24);
+        let count = |weekday: u32, hour: u8| {
+            cells
+                .iter()
+                .find(|cell| u32::from(cell.weekday) == weekday && cell.hour == hour)
+                .unwrap()
+                .visit_count
+        };
+        let weekday = chrono::Datelike::weekday(&today).num_days_from_sunday();
+        assert_eq!(count(weekday, 0), 2);
+        assert_eq!(count(weekday, 23), 1);
+        assert_eq!(count((weekday + 6) % 7, 9), 1);
+        assert_eq!(cells.iter().map(|cell| cell.visit_count).sum::<i64>(), 4);
+        // 超出天数的访问不计入
+        assert_eq!(db.get_hourly_heatmap(1).unwrap().iter().map(|cell| cell.visit_count).sum::<i64>(), 3);
+
+        let hours = db.get_hourly_breakdown(&today.format("%Y-%m-%d").to_string()).unwrap();
+        assert_eq!(hours.len(), 24);
+        assert_eq!((hours[0], hours[9], hours[23]), ((0, 2), (9, 0), (23, 1)));
+        assert!(db.get_hourly_breakdown("not a date").unwrap().iter().all(|(_, count)| *count == 0));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    fn popular(db: &AnalyticsDB, include_bots: bool) -> Vec<(String, i64)> {
+        db.get_popular_pages(None, include_bots).unwrap().into_iter().map(|p| (p.slug, p.visits)).collect()
+    }
+
+    #[actix_web::test]
+    async fn test_dashboard_stats_require_auth() {
+        use actix_web::{test as actix_test, App};
+
+        let db_path = PathBuf::from("/tmp/test_analytics_dashboard_auth.db");
+        let _ = std::fs::remove_file(&db_path);
+        let (key, hash) = crate::admin::generate_api_key();
+        let mut config = Config::default();
+        config.paths.analytics_db = db_path.clone();
+        config.server.api_keys = vec![hash];
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/api/analytics/heatmap", web::get().to(get_hourly_heatmap))
+                .route("/api/analytics/hourly", web::get().to(get_hourly_breakdown))
+                .route("/api/analytics/countries", web::get().to(get_country_stats))
+                .route("/api/analytics/device-stats", web::get().to(get_device_stats)),
+        )
+        .await;
+
+        for uri in [
+            "/api/analytics/heatmap",
+            "/api/analytics/hourly",
+            "/api/analytics/countries",
+            "/api/analytics/device-stats",
+        ] {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED, "{}", uri);
+
+            let req = actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", key)))
+                .to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{}", uri);
+        }
+
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_anonymize_ip() {
+        assert_eq!(anonymize_ip("203.0.113.57"), "203.0.113.0");
+        assert_eq!(anonymize_ip("2001:db8:85a3:8d3:1319:8a2e:370:7348"), "2001:db8:85a3::");
+        assert_eq!(anonymize_ip("::1"), "::");
+        assert_eq!(anonymize_ip("unknown"), "unknown");
+
+        let req = actix_web::test::TestRequest::default().insert_header(("DNT", "1")).to_http_request();
+        assert!(do_not_track(&req));
+        let req = actix_web::test::TestRequest::default().insert_header(("DNT", "0")).to_http_request();
+        assert!(!do_not_track(&req));
+    }
+
+    #[test]
+    fn test_visits_in_range_and_csv() {
+        let db_path = "/tmp/test_analytics_export.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+
+        for (id, time) in [("a", "2024-05-01 10:00:00"), ("b", "2024-05-02 09:00:00"), ("c", "2024-05-03 08:00:00")] {
+            db.record_visit(&VisitRecord {
+                id: id.to_string(),
+                page_slug: "grape-tizi".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                user_agent: "Mozilla/5.0 (X11, Linux)".to_string(),
+                referer: String::new(),
+                source: VisitSource::Direct,
+                search_engine: None,
+                search_keyword: None,
+                visit_time: time.to_string(),
+                country: None,
+                city: None,
+                redirected_from: None,
+                device_type: DeviceType::Desktop,
+                is_bot: false,
+            })
+            .unwrap();
+        }
+
+        let visits = db.get_visits_in_range("2024-05-01", "2024-05-02", 10).unwrap();
+        assert_eq!(visits.iter().map(|v| v.id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(visits[0].source, VisitSource::Direct);
+        assert_eq!(visits[0].device_type, DeviceType::Desktop);
+
+        let devices = db.get_device_stats().unwrap();
+        assert_eq!(devices[0].device_type, "desktop");
+        assert_eq!(devices[0].count, 3);
+        assert_eq!(db.get_visits_in_range("2024-05-01", "2024-05-03", 1).unwrap().len(), 1);
+
+        assert_eq!(
+            csv_row(&["a", "Mozilla/5.0 (X11, Linux)", "say \"hi\""]),
+            "a,\"Mozilla/5.0 (X11, Linux)\",\"say \"\"hi\"\"\"\r\n"
+        );
+        assert_eq!(parse_export_date(None, "1970-01-01").as_deref(), Some("1970-01-01"));
+        assert_eq!(parse_export_date(Some("2024-5-1"), "").as_deref(), Some("2024-05-01"));
+        assert!(parse_export_date(Some("2024-05-01' OR 1=1"), "").is_none());
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    fn synthetic_visit(id: usize, slug: &str, time: &str) -> VisitRecord {
+        VisitRecord {
+            id: id.to_string(),
+            page_slug: slug.to_string(),
+            ip_address: format!("10.0.{}.{}", id / 256 % 256, id % 256),
+            user_agent: "bench".to_string(),
+            referer: String::new(),
+            source: VisitSource::Direct,
+            search_engine: None,
+            search_keyword: Some(format!("keyword-{}", id % 300)),
+            visit_time: time.to_string(),
+            country: None,
+            city: None,
+            redirected_from: None,
+            device_type: DeviceType::Desktop,
+            is_bot: false,
+        }
+    }
+
+    #[test]
+    fn test_country_stats() {
+        let db_path = "/tmp/test_analytics_countries.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        for (id, country) in [(1, Some("CN")), (2, Some("CN")), (3, Some("US")), (4, None), (5, Some(""))] {
+            let mut visit = synthetic_visit(id, "wine-1", "2024-05-01 10:00:00");
+            visit.country = country.map(str::to_string);
+            db.record_visit(&visit).unwrap();
+        }
+
+        // 没有国家信息的访问不计入
+        let stats = db.get_country_stats(None).unwrap();
+        assert_eq!(
+            stats.iter().map(|s| (s.country.as_str(), s.count)).collect::<Vec<_>>(),
+            vec![("CN", 2), ("US", 1)]
+        );
+        assert!((stats[0].percentage - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(db.get_country_stats(Some(1)).unwrap().len(), 1);
+
+        // 未配置 GeoIP 时不查询位置
+        assert_eq!(get_location_from_ip("8.8.8.8", None), (None, None));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_stats_queries_bind_parameters() {
+        let db_path = "/tmp/test_analytics_params.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        for id in 0..400 {
+            let time = format!("2024-05-0{} 10:00:00", 1 + id % 3);
+            db.record_visit(&synthetic_visit(id, &format!("page-{}", id % 150), &time)).unwrap();
+        }
+
+        let daily = db.get_daily_stats(Some("2024-05-02"), None, false).unwrap();
+        assert_eq!(daily.iter().map(|d| d.date.as_str()).collect::<Vec<_>>(), vec!["2024-05-03", "2024-05-02"]);
+        // 参数按值绑定，注入的 SQL 只是一个不匹配的日期
+        assert!(db.get_daily_stats(Some("2024-05-01' OR '1'='1"), Some("2024-05-01"), false).unwrap().is_empty());
+
+        assert_eq!(db.get_popular_pages(Some(5), false).unwrap().len(), 5);
+        assert_eq!(db.get_popular_pages(Some(10_000), false).unwrap().len(), MAX_STATS_LIMIT);
+        assert_eq!(db.get_source_stats(None).unwrap().len(), 1);
+        // 导出仍可取得全部关键词
+        assert_eq!(db.get_search_keywords(Some(MAX_EXPORT_ROWS)).unwrap().len(), 300);
+
+        // 访问数缓存随插入和清理同步
+        for id in 1000..1005 {
+            db.record_visit(&synthetic_visit(id, "popular", "2024-05-04 10:00:00")).unwrap();
+        }
+        let top = &db.get_popular_pages(Some(1), false).unwrap()[0];
+        assert_eq!((top.slug.as_str(), top.visits), ("popular", 5));
+        db.conn.execute("DELETE FROM visits WHERE id = '1000'", []).unwrap();
+        assert_eq!(db.get_popular_pages(Some(1), false).unwrap()[0].visits, 4);
+
+        assert_eq!(parse_date_param(None), Ok(None));
+        assert_eq!(parse_date_param(Some(" 2024-5-1 ")), Ok(Some("2024-05-01".to_string())));
+        assert!(parse_date_param(Some("yesterday")).is_err());
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_page_analytics() {
+        let db_path = "/tmp/test_analytics_page.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        for (id, slug, time) in [
+            (1, "grape-tizi", "2024-04-30 23:00:00"),
+            (2, "grape-tizi", "2024-05-01 10:00:00"),
+            (3, "grape-tizi", "2024-05-01T12:00:00+00:00"),
+            (4, "grape-tizi", "2024-05-03 08:00:00"),
+            (5, "grape-other", "2024-05-02 08:00:00"),
+        ] {
+            let mut visit = synthetic_visit(id, slug, time);
+            if id == 4 {
+                visit.source = VisitSource::SearchEngine;
+            }
+            db.record_visit(&visit).unwrap();
+        }
+
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let stats = db.get_page_analytics("grape-tizi", day("2024-05-01"), day("2024-05-03")).unwrap();
+        assert_eq!((stats.start_date.as_str(), stats.end_date.as_str()), ("2024-05-01", "2024-05-03"));
+        assert_eq!(stats.total_visits, 3);
+        assert_eq!(stats.unique_visitors, 3);
+        // 没有访问的日期补 0
+        let daily: Vec<(&str, i64)> = stats.daily.iter().map(|d| (d.date.as_str(), d.visits)).collect();
+        assert_eq!(daily, vec![("2024-05-01", 2), ("2024-05-02", 0), ("2024-05-03", 1)]);
+        assert_eq!(stats.sources[0].source, "direct");
+        assert_eq!(stats.sources[0].count, 2);
+        assert_eq!(stats.keywords.len(), 3);
+
+        // slug 按值绑定
+        let injected = db.get_page_analytics("x' OR '1'='1", day("2024-05-01"), day("2024-05-03")).unwrap();
+        assert_eq!(injected.total_visits, 0);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_rollup_visits() {
+        let db_path = "/tmp/test_analytics_rollup.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        for (id, slug, time) in [
+            (1, "wine-1", "2024-05-01 10:00:00"),
+            (2, "wine-1", "2024-05-01 11:00:00"),
+            (3, "wine-2", "2024-05-01 12:00:00"),
+            (4, "wine-1", "2024-05-02 10:00:00"),
+            (5, "wine-2", "2024-05-03 10:00:00"),
+        ] {
+            db.record_visit(&synthetic_visit(id, slug, time)).unwrap();
+        }
+        let daily_before = db.get_daily_stats(None, None, false).unwrap();
+        let popular_before = db.get_popular_pages(None, false).unwrap();
+
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        assert_eq!(db.rollup_visits_before(day("2024-05-03")).unwrap(), 4);
+        assert_eq!(db.conn.query_row("SELECT COUNT(*) FROM visits", [], |row| row.get::<_, i64>(0)).unwrap(), 1);
+        assert_eq!(db.rollup_visits_before(day("2024-05-03")).unwrap(), 0);
+
+        // 汇总前后的统计一致，没有断崖
+        let daily_after = db.get_daily_stats(None, None, false).unwrap();
+        let visits = |stats: &[DailyStats]| stats.iter().map(|d| (d.date.clone(), d.total_visits)).collect::<Vec<_>>();
+        assert_eq!(visits(&daily_after), visits(&daily_before));
+        assert_eq!(
+            db.get_daily_stats(Some("2024-05-01"), Some("2024-05-01"), false).unwrap()[0].total_visits,
+            3
+        );
+        let popular_after = db.get_popular_pages(None, false).unwrap();
+        assert_eq!(
+            popular_after.iter().map(|p| (&p.slug, p.visits)).collect::<Vec<_>>(),
+            popular_before.iter().map(|p| (&p.slug, p.visits)).collect::<Vec<_>>()
+        );
+        assert_eq!(db.get_total_visits(false).unwrap(), 5);
+        let page = db.get_page_analytics("wine-1", day("2024-05-01"), day("2024-05-02")).unwrap();
+        assert_eq!(page.total_visits, 3);
+        assert_eq!(page.daily.iter().map(|d| d.visits).collect::<Vec<_>>(), vec![2, 1]);
+
+        // 旧数据库中只统计原始记录的视图会被重建
+        db.conn
+            .execute_batch("DROP VIEW daily_stats; CREATE VIEW daily_stats AS SELECT date(visit_time) AS date, COUNT(*) AS total_visits, 0 AS unique_visitors, 0 AS page_views FROM visits GROUP BY 1;")
+            .unwrap();
+        let db = AnalyticsDB::new(db_path).unwrap();
+        assert_eq!(visits(&db.get_daily_stats(None, None, false).unwrap()), visits(&daily_before));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    /// 100 万条访问记录下的热门页面查询，`cargo test --release -- --ignored` 运行
+    #[test]
+    #[ignore]
+    fn bench_popular_pages_large_table() {
+        let db_path = "/tmp/bench_analytics_popular.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = AnalyticsDB::new(db_path).unwrap();
+        db.conn.execute_batch("BEGIN").unwrap();
+        for id in 0..1_000_000 {
+            let time = format!("2024-{:02}-{:02} 10:00:00", 1 + id % 12, 1 + id % 28);
+            db.record_visit(&synthetic_visit(id, &format!("page-{}", id % 2_000), &time)).unwrap();
+        }
+        db.conn.execute_batch("COMMIT; ANALYZE").unwrap();
+
+        let started = std::time::Instant::now();
+        let pages = db.get_popular_pages(Some(10), false).unwrap();
+        let elapsed = started.elapsed();
+        println!("popular pages over 1M visits: {:?}", elapsed);
+        assert_eq!(pages.len(), 10);
+        assert_eq!(pages[0].visits, 500);
+        assert!(elapsed < std::time::Duration::from_millis(5));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
     #[test]
     fn test_detect_source() {
         // 直接访问
@@ -1,10 +1,21 @@
 use chrono::Utc;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// 头像图片的边长（像素）
+const AVATAR_SIZE: u32 = 64;
+
+/// 评论限流的时间窗口
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
 /// 评论数据结构
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Comment {
     pub id: String,
     pub slug: String,
@@ -12,15 +23,30 @@ pub struct Comment {
     pub content: String,
     pub created_at: String,
     pub website: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<String>, // 被回复的评论，顶层评论为空
+    #[serde(default, skip_serializing)]
+    pub email: Option<String>, // 只用于生成头像，不对外返回
+    #[serde(default)]
+    pub avatar_url: String,
+}
+
+/// 顶层评论及其回复（最多两层）
+#[derive(Debug, Serialize, Clone)]
+pub struct CommentThread {
+    pub comment: Comment,
+    pub replies: Vec<Comment>,
 }
 
 /// 创建评论请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateCommentRequest {
     pub slug: String,
     pub author: String,
     pub content: String,
     pub website: Option<String>,
+    pub parent_id: Option<String>,
+    pub email: Option<String>,
 }
 
 /// 评论数据库管理器
@@ -32,6 +58,11 @@ impl CommentDB {
     /// 创建新的评论数据库
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)?;
+        let has_counts: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'comment_counts'",
+            [],
+            |row| row.get(0),
+        )?;
 
         // 创建评论表
         conn.execute(
@@ -41,17 +72,46 @@ impl CommentDB {
                 author TEXT NOT NULL,
                 content TEXT NOT NULL,
                 website TEXT,
-                created_at TEXT NOT NULL
+                created_at TEXT NOT NULL,
+                parent_id TEXT REFERENCES comments(id),
+                email TEXT
             )",
             [],
         )?;
 
+        // 旧数据库迁移：添加回复关系列
+        conn.execute(
+            "ALTER TABLE comments ADD COLUMN parent_id TEXT REFERENCES comments(id)",
+            [],
+        )
+        .ok();
+        // 旧数据库迁移：添加邮箱列，已有评论为空
+        conn.execute("ALTER TABLE comments ADD COLUMN email TEXT", []).ok();
+
         // 创建索引以加速查询
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_comments_slug ON comments(slug)",
             [],
         )?;
 
+        // 每篇文章的评论数缓存，列表接口不必逐篇统计
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS comment_counts (
+                slug TEXT PRIMARY KEY,
+                count INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        // 旧数据库迁移：首次创建缓存表时按已有评论填充
+        if !has_counts {
+            conn.execute(
+                "INSERT OR REPLACE INTO comment_counts (slug, count, updated_at)
+                 SELECT slug, COUNT(*), ? FROM comments GROUP BY slug",
+                params![Utc::now().to_rfc3339()],
+            )?;
+        }
+
         Ok(Self { conn })
     }
 
@@ -61,82 +121,174 @@ impl CommentDB {
         let now = Utc::now().to_rfc3339();
 
         self.conn.execute(
-            "INSERT INTO comments (id, slug, author, content, website, created_at)
-             VALUES (?, ?, ?, ?, ?, ?)",
+            "INSERT INTO comments (id, slug, author, content, website, created_at, parent_id, email)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 id,
                 request.slug,
                 request.author,
                 request.content,
                 request.website,
-                now
+                now,
+                request.parent_id,
+                request.email
             ],
         )?;
+        self.increment_comment_count(&request.slug)?;
 
         Ok(Comment {
+            avatar_url: avatar_url(request.email.as_deref(), &request.author),
             id,
             slug: request.slug,
             author: request.author,
             content: request.content,
             created_at: now,
             website: request.website,
+            parent_id: request.parent_id,
+            email: request.email,
         })
     }
 
+    /// 获取单条评论
+    pub fn get_comment(&self, id: &str) -> Result<Option<Comment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, slug, author, content, website, created_at, parent_id, email
+             FROM comments WHERE id = ?",
+        )?;
+        let mut comments = stmt.query_map(params![id], row_to_comment)?;
+        comments.next().transpose()
+    }
+
     /// 获取某篇文章的所有评论
     pub fn get_comments_by_slug(&self, slug: &str) -> Result<Vec<Comment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, author, content, website, created_at
+            "SELECT id, slug, author, content, website, created_at, parent_id, email
              FROM comments WHERE slug = ? ORDER BY created_at DESC",
         )?;
 
         let comments = stmt
-            .query_map(params![slug], |row| {
-                Ok(Comment {
-                    id: row.get(0)?,
-                    slug: row.get(1)?,
-                    author: row.get(2)?,
-                    content: row.get(3)?,
-                    website: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })?
+            .query_map(params![slug], row_to_comment)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(comments)
     }
 
+    /// 获取某篇文章的评论，回复归入所属的顶层评论下。
+    /// 顶层评论从新到旧，回复按时间先后排列；父评论已不存在的回复作为顶层评论显示。
+    pub fn get_comments_threaded(&self, slug: &str) -> Result<Vec<CommentThread>> {
+        let comments = self.get_comments_by_slug(slug)?;
+        let top_level_ids: std::collections::HashSet<String> = comments
+            .iter()
+            .filter(|c| c.parent_id.is_none())
+            .map(|c| c.id.clone())
+            .collect();
+
+        let (replies, top_level): (Vec<Comment>, Vec<Comment>) = comments
+            .into_iter()
+            .partition(|c| c.parent_id.as_ref().is_some_and(|p| top_level_ids.contains(p)));
+
+        let mut threads: Vec<CommentThread> = top_level
+            .into_iter()
+            .map(|comment| CommentThread {
+                comment,
+                replies: Vec::new(),
+            })
+            .collect();
+        // 查询结果从新到旧，倒序遍历使回复按时间先后排列
+        for reply in replies.into_iter().rev() {
+            if let Some(thread) = threads
+                .iter_mut()
+                .find(|t| Some(&t.comment.id) == reply.parent_id.as_ref())
+            {
+                thread.replies.push(reply);
+            }
+        }
+
+        Ok(threads)
+    }
+
     /// 获取所有评论（按文章分组）
     pub fn get_all_comments(&self) -> Result<Vec<Comment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, author, content, website, created_at
+            "SELECT id, slug, author, content, website, created_at, parent_id, email
              FROM comments ORDER BY created_at DESC",
         )?;
 
         let comments = stmt
-            .query_map([], |row| {
-                Ok(Comment {
-                    id: row.get(0)?,
-                    slug: row.get(1)?,
-                    author: row.get(2)?,
-                    content: row.get(3)?,
-                    website: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })?
+            .query_map([], row_to_comment)?
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(comments)
     }
 
-    /// 删除评论
+    /// 删除评论（连同其回复）
     pub fn delete_comment(&self, id: &str) -> Result<bool> {
+        let slug = self.get_comment(id)?.map(|comment| comment.slug);
+        self.conn
+            .execute("DELETE FROM comments WHERE parent_id = ?", params![id])?;
         let rows = self
             .conn
             .execute("DELETE FROM comments WHERE id = ?", params![id])?;
+        if let Some(slug) = slug {
+            self.refresh_comment_count(&slug)?;
+        }
         Ok(rows > 0)
     }
 
+    /// 文章评论数缓存加一
+    pub fn increment_comment_count(&self, slug: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO comment_counts (slug, count, updated_at) VALUES (?1, 1, ?2)
+             ON CONFLICT(slug) DO UPDATE SET count = count + 1, updated_at = ?2",
+            params![slug, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// 按评论表重新统计某篇文章的评论数
+    fn refresh_comment_count(&self, slug: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO comment_counts (slug, count, updated_at)
+             SELECT ?1, COUNT(*), ?2 FROM comments WHERE slug = ?1",
+            params![slug, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// 获取某篇文章的评论数（读取缓存）
+    pub fn get_comment_count(&self, slug: &str) -> Result<i64> {
+        self.conn
+            .query_row(
+                "SELECT count FROM comment_counts WHERE slug = ?",
+                params![slug],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(|count| count.unwrap_or(0))
+    }
+
+    /// 批量获取评论数，没有评论的文章不在结果中
+    pub fn get_comment_counts(&self, slugs: &[&str]) -> Result<HashMap<String, i64>> {
+        if slugs.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders = vec!["?"; slugs.len()].join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT slug, count FROM comment_counts WHERE slug IN ({}) AND count > 0",
+            placeholders
+        ))?;
+        let counts = stmt
+            .query_map(rusqlite::params_from_iter(slugs.iter()), |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        Ok(counts)
+    }
+
+    /// 获取评论总数
+    pub fn get_total_comments(&self) -> Result<i64> {
+        self.conn
+            .query_row("SELECT COUNT(*) FROM comments", [], |row| row.get(0))
+    }
+
     /// 获取评论统计
     pub fn get_comment_stats(&self) -> Result<Vec<(String, i64)>> {
         let mut stmt = self.conn.prepare(
@@ -150,9 +302,281 @@ impl CommentDB {
     }
 }
 
+fn row_to_comment(row: &rusqlite::Row) -> Result<Comment> {
+    let author: String = row.get(2)?;
+    let email: Option<String> = row.get(7)?;
+    Ok(Comment {
+        id: row.get(0)?,
+        slug: row.get(1)?,
+        avatar_url: avatar_url(email.as_deref(), &author),
+        author,
+        content: row.get(3)?,
+        // 早于网址校验写入的评论可能带有 `javascript:` 之类的地址，读取时一并丢弃
+        website: row.get::<_, Option<String>>(4)?.and_then(|website| validate_website(&website)),
+        created_at: row.get(5)?,
+        parent_id: row.get(6)?,
+        email,
+    })
+}
+
+/// Gravatar 头像地址。有邮箱时按邮箱取头像（Gravatar 支持 SHA-256 散列），
+/// 否则按昵称生成，同一昵称得到同一个 identicon 默认头像
+pub fn avatar_url(email: Option<&str>, author: &str) -> String {
+    let key = email
+        .map(|email| email.trim().to_lowercase())
+        .filter(|email| !email.is_empty())
+        .unwrap_or_else(|| format!("author:{}", author.trim()));
+    let hash: String = Sha256::digest(key.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("https://www.gravatar.com/avatar/{}?d=identicon&s={}", hash, AVATAR_SIZE)
+}
+
+/// 检查评论者网站：只接受带主机名的 http/https 地址，返回去掉首尾空白的地址
+pub fn validate_website(website: &str) -> Option<String> {
+    let website = website.trim();
+    let lower = website.to_ascii_lowercase();
+    let rest = lower.strip_prefix("https://").or_else(|| lower.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let valid = !host.is_empty()
+        && !host.contains('@')
+        && !website.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '<' | '>'));
+    valid.then(|| website.to_string())
+}
+
+/// 检查评论者邮箱的基本格式，返回去掉首尾空白的地址
+pub fn validate_email(email: &str) -> Option<String> {
+    let email = email.trim();
+    let (local, domain) = email.split_once('@')?;
+    let valid = !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && email.len() <= 254
+        && !email.chars().any(|c| c.is_whitespace() || c.is_control());
+    valid.then(|| email.to_string())
+}
+
 /// 简化的评论统计（用于模板渲染）
 #[derive(Debug, Serialize)]
 pub struct CommentCount {
     pub slug: String,
     pub count: i64,
 }
+
+/// 按 IP 限制评论提交频率：每个 IP 在一小时窗口内最多提交 `limit` 条
+#[derive(Debug)]
+pub struct CommentRateLimiter {
+    limit: u32,
+    /// IP -> (窗口内已提交数, 窗口开始时间)
+    entries: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl CommentRateLimiter {
+    /// `limit_per_hour` 为 0 时不限制
+    pub fn new(limit_per_hour: u32) -> Self {
+        Self {
+            limit: limit_per_hour,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 未超出限制时计数并返回 true；同时清理已过期的记录
+    pub fn check_and_increment(&self, ip: &str) -> bool {
+        self.check_and_increment_at(ip, Instant::now())
+    }
+
+    fn check_and_increment_at(&self, ip: &str, now: Instant) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries.retain(|_, (_, started)| now.saturating_duration_since(*started) < RATE_LIMIT_WINDOW);
+
+        let (count, _) = entries.entry(ip.to_string()).or_insert((0, now));
+        if *count >= self.limit {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+
+    /// 距该 IP 的窗口结束还有多少秒（用于 `Retry-After`）
+    pub fn retry_after_secs(&self, ip: &str) -> u64 {
+        let entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        entries
+            .get(ip)
+            .map(|(_, started)| RATE_LIMIT_WINDOW.saturating_sub(started.elapsed()).as_secs().max(1))
+            .unwrap_or(0)
+    }
+
+    /// 清除所有 IP 的限流记录，返回清除的条数
+    pub fn clear(&self) -> usize {
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let cleared = entries.len();
+        entries.clear();
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(db: &CommentDB, content: &str, parent_id: Option<&str>) -> Comment {
+        let comment = db
+            .add_comment(CreateCommentRequest {
+                slug: "fruit-apple".to_string(),
+                author: "lin".to_string(),
+                content: content.to_string(),
+                website: None,
+                parent_id: parent_id.map(String::from),
+                email: None,
+            })
+            .unwrap();
+        // created_at 精度有限，保证先后顺序
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        comment
+    }
+
+    #[test]
+    fn test_comments_threaded() {
+        let db_path = "/tmp/test_comments_threaded.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = CommentDB::new(db_path).unwrap();
+
+        let first = comment(&db, "first", None);
+        let second = comment(&db, "second", None);
+        comment(&db, "reply 1", Some(&first.id));
+        comment(&db, "reply 2", Some(&first.id));
+
+        let threads = db.get_comments_threaded("fruit-apple").unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].comment.id, second.id);
+        let replies: Vec<_> = threads[1].replies.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(replies, vec!["reply 1", "reply 2"]);
+
+        // 删除顶层评论时一并删除回复
+        assert!(db.delete_comment(&first.id).unwrap());
+        assert_eq!(db.get_comments_by_slug("fruit-apple").unwrap().len(), 1);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_comment_counts() {
+        let db_path = "/tmp/test_comment_counts.db";
+        let _ = std::fs::remove_file(db_path);
+        // 旧数据库：评论表已有数据，缓存表还不存在
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE comments (id TEXT PRIMARY KEY, slug TEXT NOT NULL, author TEXT NOT NULL,
+                content TEXT NOT NULL, website TEXT, created_at TEXT NOT NULL, parent_id TEXT);
+             INSERT INTO comments VALUES ('a', 'fruit-pear', 'lin', 'old', NULL, '2024-01-01', NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = CommentDB::new(db_path).unwrap();
+        assert_eq!(db.get_comment_count("fruit-pear").unwrap(), 1);
+        assert_eq!(db.get_comment_count("fruit-apple").unwrap(), 0);
+
+        let first = comment(&db, "first", None);
+        comment(&db, "reply", Some(&first.id));
+        comment(&db, "second", None);
+        assert_eq!(db.get_comment_count("fruit-apple").unwrap(), 3);
+
+        // 删除顶层评论时回复也不再计数
+        db.delete_comment(&first.id).unwrap();
+        let counts = db.get_comment_counts(&["fruit-apple", "fruit-pear", "fruit-none"]).unwrap();
+        assert_eq!(counts.get("fruit-apple"), Some(&1));
+        assert_eq!(counts.get("fruit-pear"), Some(&1));
+        assert!(!counts.contains_key("fruit-none"));
+        assert!(db.get_comment_counts(&[]).unwrap().is_empty());
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_comment_email_and_avatar() {
+        let db_path = "/tmp/test_comment_email.db";
+        let _ = std::fs::remove_file(db_path);
+        // 旧数据库：评论表还没有邮箱列
+        let conn = Connection::open(db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE comments (id TEXT PRIMARY KEY, slug TEXT NOT NULL, author TEXT NOT NULL,
+                content TEXT NOT NULL, website TEXT, created_at TEXT NOT NULL, parent_id TEXT);
+             INSERT INTO comments VALUES ('a', 'fruit-apple', 'lin', 'old', 'javascript:alert(1)', '2024-01-01', NULL);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let db = CommentDB::new(db_path).unwrap();
+        db.add_comment(CreateCommentRequest {
+            slug: "fruit-apple".to_string(),
+            author: "Ann".to_string(),
+            content: "new".to_string(),
+            website: Some("https://ann.example".to_string()),
+            parent_id: None,
+            email: Some("Ann@Example.com".to_string()),
+        })
+        .unwrap();
+
+        let comments = db.get_comments_by_slug("fruit-apple").unwrap();
+        assert_eq!(comments.len(), 2);
+        let (new, old) = (&comments[0], &comments[1]);
+        assert_eq!(new.email.as_deref(), Some("Ann@Example.com"));
+        assert_eq!(new.avatar_url, avatar_url(Some(" ann@example.com "), "someone else"));
+        assert_eq!(new.website.as_deref(), Some("https://ann.example"));
+        assert_eq!(old.email, None);
+        assert_eq!(old.website, None);
+        assert_eq!(old.avatar_url, avatar_url(None, "lin"));
+        assert_ne!(old.avatar_url, avatar_url(None, "Ann"));
+        assert!(old.avatar_url.starts_with("https://www.gravatar.com/avatar/"));
+        assert!(old.avatar_url.ends_with("?d=identicon&s=64"));
+
+        // 邮箱不出现在对外返回的数据中
+        let json = serde_json::to_value(new).unwrap();
+        assert!(json.get("email").is_none());
+        assert_eq!(json["avatar_url"], new.avatar_url.as_str());
+
+        assert_eq!(validate_website(" https://ann.example/blog "), Some("https://ann.example/blog".to_string()));
+        assert_eq!(validate_website("HTTP://ann.example"), Some("HTTP://ann.example".to_string()));
+        for invalid in ["javascript:alert(1)", "ftp://ann.example", "https://", "https://user@evil.example", "https://a b.example", "ann.example"] {
+            assert_eq!(validate_website(invalid), None, "{}", invalid);
+        }
+        assert_eq!(validate_email(" ann@example.com "), Some("ann@example.com".to_string()));
+        for invalid in ["ann", "@example.com", "ann@example", "ann@.com", "an n@example.com"] {
+            assert_eq!(validate_email(invalid), None, "{}", invalid);
+        }
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_comment_rate_limiter() {
+        let limiter = CommentRateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.check_and_increment_at("10.0.0.1", start));
+        assert!(limiter.check_and_increment_at("10.0.0.1", start));
+        assert!(!limiter.check_and_increment_at("10.0.0.1", start));
+        assert!(limiter.check_and_increment_at("10.0.0.2", start));
+        assert!(limiter.retry_after_secs("10.0.0.1") > 0);
+
+        // 窗口过期后重新计数，并清理过期记录
+        let later = start + RATE_LIMIT_WINDOW;
+        assert!(limiter.check_and_increment_at("10.0.0.1", later));
+        assert_eq!(limiter.clear(), 1);
+        assert_eq!(limiter.retry_after_secs("10.0.0.1"), 0);
+
+        let unlimited = CommentRateLimiter::new(0);
+        assert!((0..10).all(|_| unlimited.check_and_increment("10.0.0.1")));
+    }
+}
@@ -0,0 +1,281 @@
+//! GeoIP 查询
+//!
+//! 用 `maxminddb` 读取 MaxMind DB（`.mmdb`，如 GeoLite2-Country / GeoLite2-City）
+//! 格式的数据库，按访客 IP 查出国家代码和城市名。
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::Config;
+
+/// 城市名优先使用的语言
+const CITY_NAME_LANGUAGES: &[&str] = &["zh-CN", "en"];
+
+/// 查询结果中的国家代码（ISO 3166-1）和城市名；Country 数据库的记录没有城市
+pub fn location_from_record(record: &geoip2::City) -> (Option<String>, Option<String>) {
+    let country = record
+        .country
+        .as_ref()
+        .and_then(|country| country.iso_code)
+        .or_else(|| record.registered_country.as_ref().and_then(|country| country.iso_code))
+        .map(str::to_string);
+    let city = CITY_NAME_LANGUAGES
+        .iter()
+        .find_map(|lang| record.city.as_ref()?.names.as_ref()?.get(lang).copied())
+        .map(str::to_string);
+    (country, city)
+}
+
+/// 私有、回环等不会出现在 GeoIP 数据库中的地址
+fn is_non_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast()
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xfe00) == 0xfc00 // 唯一本地地址 fc00::/7
+                || (first & 0xffc0) == 0xfe80 // 链路本地地址 fe80::/10
+        }
+    }
+}
+
+/// 配置的 GeoIP 数据库，第一次查询时才加载，所有请求共享
+pub struct GeoIp {
+    path: Option<PathBuf>,
+    reader: OnceLock<Option<Reader<Vec<u8>>>>,
+}
+
+impl GeoIp {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            path: config.analytics.geoip_db_path.clone(),
+            reader: OnceLock::new(),
+        }
+    }
+
+    /// 使用已加载的数据库（测试用）
+    #[cfg(test)]
+    fn with_reader(reader: Reader<Vec<u8>>) -> Self {
+        Self {
+            path: None,
+            reader: OnceLock::from(Some(reader)),
+        }
+    }
+
+    fn reader(&self) -> Option<&Reader<Vec<u8>>> {
+        self.reader
+            .get_or_init(|| {
+                let path = self.path.as_ref()?;
+                match Reader::open_readfile(path) {
+                    Ok(reader) => {
+                        log::info!("Loaded GeoIP database {}", path.display());
+                        Some(reader)
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to load GeoIP database {}: {}", path.display(), e);
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    /// 查询 IP 的国家代码和城市名；未配置数据库、私有地址或查不到时返回 None
+    pub fn locate(&self, ip: &str) -> (Option<String>, Option<String>) {
+        let Ok(ip) = ip.trim().parse::<IpAddr>() else {
+            return (None, None);
+        };
+        if is_non_public(ip) {
+            return (None, None);
+        }
+        let Some(reader) = self.reader() else {
+            return (None, None);
+        };
+        match reader.lookup::<geoip2::City>(ip) {
+            Ok(record) => location_from_record(&record),
+            Err(MaxMindDBError::AddressNotFoundError(_)) => (None, None),
+            Err(e) => {
+                log::debug!("GeoIP lookup for {} failed: {}", ip, e);
+                (None, None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 元数据段开头的标记
+    const METADATA_MARKER: &[u8] = b"\xAB\xCD\xEFMaxMind.com";
+    /// 搜索树与数据段之间的 16 字节分隔
+    const DATA_SECTION_SEPARATOR: usize = 16;
+
+    /// 测试数据库中用到的 MaxMind DB 数据类型
+    enum Field {
+        Str(&'static str),
+        Uint(u64),
+        Map(Vec<(&'static str, Field)>),
+        Array(Vec<Field>),
+    }
+
+    fn map(entries: Vec<(&'static str, Field)>) -> Field {
+        Field::Map(entries)
+    }
+
+    /// 按 MaxMind DB 格式编码一个值
+    fn encode(value: &Field, out: &mut Vec<u8>) {
+        fn header(kind: u8, size: usize, out: &mut Vec<u8>) {
+            assert!(size < 29);
+            if kind <= 7 {
+                out.push(kind << 5 | size as u8);
+            } else {
+                out.push(size as u8);
+                out.push(kind - 7);
+            }
+        }
+        match value {
+            Field::Str(s) => {
+                header(2, s.len(), out);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Field::Uint(n) => {
+                let bytes: Vec<u8> = n.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+                header(9, bytes.len(), out);
+                out.extend(bytes);
+            }
+            Field::Map(entries) => {
+                header(7, entries.len(), out);
+                for (key, value) in entries {
+                    encode(&Field::Str(key), out);
+                    encode(value, out);
+                }
+            }
+            Field::Array(items) => {
+                header(11, items.len(), out);
+                for item in items {
+                    encode(item, out);
+                }
+            }
+        }
+    }
+
+    /// 生成只含 IPv4 网段的小型测试数据库（record_size 24）
+    fn build_test_mmdb(networks: &[(&str, u8, Field)]) -> Vec<u8> {
+        // 每个节点的左右记录，None 表示未使用
+        let mut nodes: Vec<[Option<usize>; 2]> = vec![[None, None]];
+        let mut data = Vec::new();
+        let mut leaves = Vec::new();
+        for (network, prefix, record) in networks {
+            let ip: u32 = network.parse::<std::net::Ipv4Addr>().unwrap().into();
+            let offset = data.len();
+            encode(record, &mut data);
+            let mut node = 0;
+            for i in 0..*prefix {
+                let bit = ((ip >> (31 - i)) & 1) as usize;
+                if i + 1 == *prefix {
+                    leaves.push((node, bit, offset));
+                } else {
+                    node = match nodes[node][bit] {
+                        Some(next) => next,
+                        None => {
+                            nodes.push([None, None]);
+                            let next = nodes.len() - 1;
+                            nodes[node][bit] = Some(next);
+                            next
+                        }
+                    };
+                }
+            }
+        }
+
+        let node_count = nodes.len();
+        let mut records: Vec<[usize; 2]> = nodes
+            .iter()
+            .map(|node| [node[0].unwrap_or(node_count), node[1].unwrap_or(node_count)])
+            .collect();
+        for (node, bit, offset) in leaves {
+            records[node][bit] = node_count + DATA_SECTION_SEPARATOR + offset;
+        }
+
+        let mut buf = Vec::new();
+        for record in records {
+            for side in record {
+                buf.extend_from_slice(&(side as u32).to_be_bytes()[1..]);
+            }
+        }
+        buf.extend_from_slice(&[0; DATA_SECTION_SEPARATOR]);
+        buf.extend(data);
+        buf.extend_from_slice(METADATA_MARKER);
+        encode(
+            &map(vec![
+                ("binary_format_major_version", Field::Uint(2)),
+                ("binary_format_minor_version", Field::Uint(0)),
+                ("build_epoch", Field::Uint(1_700_000_000)),
+                ("database_type", Field::Str("Test-City")),
+                ("description", map(vec![("en", Field::Str("Test database"))])),
+                ("ip_version", Field::Uint(4)),
+                ("languages", Field::Array(vec![Field::Str("en"), Field::Str("zh-CN")])),
+                ("node_count", Field::Uint(node_count as u64)),
+                ("record_size", Field::Uint(24)),
+            ]),
+            &mut buf,
+        );
+        buf
+    }
+
+    fn test_geoip() -> GeoIp {
+        let mmdb = build_test_mmdb(&[
+            (
+                "1.2.0.0",
+                16,
+                map(vec![
+                    ("country", map(vec![("iso_code", Field::Str("AU"))])),
+                    ("city", map(vec![("names", map(vec![("en", Field::Str("Sydney"))]))])),
+                ]),
+            ),
+            (
+                "8.8.8.0",
+                24,
+                map(vec![
+                    ("country", map(vec![("iso_code", Field::Str("US"))])),
+                    (
+                        "city",
+                        map(vec![("names", map(vec![("en", Field::Str("Mountain View")), ("zh-CN", Field::Str("芒廷维尤"))]))]),
+                    ),
+                ]),
+            ),
+            // Country 数据库的记录：没有城市，只有注册国家
+            ("9.9.9.0", 24, map(vec![("registered_country", map(vec![("iso_code", Field::Str("CH"))]))])),
+        ]);
+        GeoIp::with_reader(Reader::from_source(mmdb).unwrap())
+    }
+
+    #[test]
+    fn test_geoip_lookup() {
+        let geoip = test_geoip();
+        assert_eq!(geoip.locate("1.2.3.4"), (Some("AU".to_string()), Some("Sydney".to_string())));
+        assert_eq!(geoip.locate("8.8.8.8"), (Some("US".to_string()), Some("芒廷维尤".to_string())));
+        assert_eq!(geoip.locate("9.9.9.9"), (Some("CH".to_string()), None));
+        assert_eq!(geoip.locate("8.8.9.8"), (None, None));
+        assert_eq!(geoip.locate("2001:4860::8888"), (None, None));
+        assert_eq!(geoip.locate("::1"), (None, None));
+        assert_eq!(geoip.locate("unknown"), (None, None));
+
+        // 私有和回环地址不查询
+        for ip in ["10.0.0.1", "192.168.1.1", "127.0.0.1", "fe80::1"] {
+            assert!(is_non_public(ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(!is_non_public("8.8.8.8".parse().unwrap()));
+
+        // 未配置数据库时什么也不查
+        let disabled = GeoIp::new(&Config::default());
+        assert_eq!(disabled.locate("8.8.8.8"), (None, None));
+
+        assert!(Reader::from_source(b"not a database".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! 数据库位置迁移
+//!
+//! 旧版本只有 `paths.storage_database_path`（页面、附件和评论）与
+//! `paths.database_path`（访问统计，更早版本还有评论）两个配置项。
+//! 现在每类数据有各自的文件，启动时把旧位置中的数据移到新位置。
+use crate::analytics::AnalyticsDB;
+use crate::comments::CommentDB;
+use crate::config::PathConfig;
+use crate::error::BlogError;
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+/// 评论数据所在的表
+const COMMENT_TABLES: &[&str] = &["comments", "comment_counts"];
+/// 访问统计所在的表
+const ANALYTICS_TABLES: &[&str] = &["visits"];
+
+/// 创建数据库所在目录，并把旧配置项指向的数据迁移到新位置，返回每一步迁移的说明
+pub fn migrate_legacy_databases(paths: &PathConfig) -> Result<Vec<String>, BlogError> {
+    for db_path in [&paths.storage_db, &paths.comments_db, &paths.analytics_db] {
+        if let Some(parent) = db_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut migrated = Vec::new();
+
+    // 存储数据库整体搬到新位置
+    if let Some(legacy) = paths.storage_database_path.as_deref() {
+        if legacy != paths.storage_db && legacy.exists() {
+            if paths.storage_db.exists() {
+                migrated.push(format!(
+                    "Both {} and {} exist, keeping {} (remove paths.storage_database_path once the old file is no longer needed)",
+                    legacy.display(),
+                    paths.storage_db.display(),
+                    paths.storage_db.display()
+                ));
+            } else {
+                move_database_file(legacy, &paths.storage_db)?;
+                migrated.push(format!("Moved storage database {} to {}", legacy.display(), paths.storage_db.display()));
+            }
+        }
+    }
+
+    // 评论以前和页面放在存储数据库里，更早的版本放在 database_path 里
+    let mut comment_sources = vec![paths.storage_db.as_path()];
+    comment_sources.extend(paths.database_path.as_deref());
+    for source in comment_sources {
+        let moved = move_tables(source, &paths.comments_db, COMMENT_TABLES, |path| CommentDB::new(path).map(|_| ()))?;
+        if moved > 0 {
+            migrated.push(format!("Moved {} comment rows from {} to {}", moved, source.display(), paths.comments_db.display()));
+        }
+    }
+
+    if let Some(legacy) = paths.database_path.as_deref() {
+        let moved = move_tables(legacy, &paths.analytics_db, ANALYTICS_TABLES, |path| AnalyticsDB::new(path).map(|_| ()))?;
+        if moved > 0 {
+            migrated.push(format!("Moved {} visit rows from {} to {}", moved, legacy.display(), paths.analytics_db.display()));
+        }
+    }
+
+    Ok(migrated)
+}
+
+/// 先把 WAL 合并进主文件，再移动数据库文件
+fn move_database_file(from: &Path, to: &Path) -> Result<(), BlogError> {
+    Connection::open(from)?.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    if fs::rename(from, to).is_err() {
+        // 跨文件系统时无法重命名
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = from.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = fs::remove_file(sidecar);
+    }
+    Ok(())
+}
+
+/// 把 `from` 中的 `tables` 复制到 `to` 后从 `from` 删除，返回移动的行数。
+/// 目标表结构由 `init` 创建，只复制两边都有的列；源文件或表不存在时什么也不做
+fn move_tables(
+    from: &Path,
+    to: &Path,
+    tables: &[&str],
+    init: impl FnOnce(&str) -> rusqlite::Result<()>,
+) -> Result<usize, BlogError> {
+    if from == to || !from.exists() {
+        return Ok(0);
+    }
+    let conn = Connection::open(from)?;
+    let mut existing = Vec::new();
+    for table in tables {
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?",
+            [table],
+            |row| row.get(0),
+        )?;
+        if exists {
+            existing.push(*table);
+        }
+    }
+    if existing.is_empty() {
+        return Ok(0);
+    }
+
+    init(&to.to_string_lossy())?;
+    conn.execute("ATTACH DATABASE ? AS dest", [to.to_string_lossy()])?;
+    let result = copy_and_drop(&conn, &existing);
+    conn.execute_batch("DETACH DATABASE dest").ok();
+    result
+}
+
+fn copy_and_drop(conn: &Connection, tables: &[&str]) -> Result<usize, BlogError> {
+    conn.execute_batch("BEGIN IMMEDIATE")?;
+    let mut moved = 0;
+    for table in tables {
+        let dest_columns = table_columns(conn, "dest", table)?;
+        let columns: Vec<String> = table_columns(conn, "main", table)?
+            .into_iter()
+            .filter(|column| dest_columns.contains(column))
+            .collect();
+        let columns = columns.join(", ");
+        let copied = conn.execute(
+            &format!("INSERT OR IGNORE INTO dest.{table} ({columns}) SELECT {columns} FROM main.{table}"),
+            [],
+        );
+        match copied.and_then(|rows| conn.execute(&format!("DROP TABLE main.{table}"), []).map(|_| rows)) {
+            Ok(rows) if *table == tables[0] => moved += rows,
+            Ok(_) => {}
+            Err(e) => {
+                conn.execute_batch("ROLLBACK").ok();
+                return Err(e.into());
+            }
+        }
+    }
+    conn.execute_batch("COMMIT")?;
+    Ok(moved)
+}
+
+fn table_columns(conn: &Connection, schema: &str, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA {schema}.table_info({table})"))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?.collect();
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageDB;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_migrate_legacy_databases() {
+        let root = PathBuf::from("/tmp/lf_blog_migrate_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        // 旧布局：评论在存储数据库里，访问统计在 database_path 里
+        let legacy_storage = root.join("storage.db");
+        let legacy_analytics = root.join("comments.db");
+        StorageDB::new(&legacy_storage.to_string_lossy()).unwrap().update_compile_time().unwrap();
+        CommentDB::new(&legacy_storage.to_string_lossy()).unwrap();
+        Connection::open(&legacy_storage)
+            .unwrap()
+            .execute(
+                "INSERT INTO comments (id, slug, author, content, created_at) VALUES ('c1', 'wine-1', 'Ann', 'Nice', '2024-01-01T00:00:00Z')",
+                [],
+            )
+            .unwrap();
+        AnalyticsDB::new(&legacy_analytics.to_string_lossy()).unwrap();
+        Connection::open(&legacy_analytics)
+            .unwrap()
+            .execute(
+                "INSERT INTO visits (id, page_slug, ip_address, user_agent, referer, source, visit_time)
+                 VALUES ('v1', 'wine-1', '127.0.0.1', 'curl', '', 'direct', '2024-01-01T00:00:00Z')",
+                [],
+            )
+            .unwrap();
+
+        let mut paths = crate::config::Config::default().paths;
+        paths.data_dir = root.join("data");
+        paths.storage_db = PathBuf::new();
+        paths.comments_db = PathBuf::new();
+        paths.analytics_db = PathBuf::new();
+        paths.resolve_database_paths();
+        paths.storage_database_path = Some(legacy_storage.clone());
+        paths.database_path = Some(legacy_analytics.clone());
+
+        let migrated = migrate_legacy_databases(&paths).unwrap();
+        assert_eq!(migrated.len(), 3, "{:?}", migrated);
+        assert!(!legacy_storage.exists());
+        let storage = StorageDB::new(&paths.storage_db.to_string_lossy()).unwrap();
+        assert!(storage.get_stats().unwrap().last_compiled.is_some());
+        let comments = CommentDB::new(&paths.comments_db.to_string_lossy()).unwrap();
+        assert_eq!(comments.get_total_comments().unwrap(), 1);
+        let analytics = AnalyticsDB::new(&paths.analytics_db.to_string_lossy()).unwrap();
+        assert_eq!(analytics.get_total_visits(false).unwrap(), 1);
+
+        // 迁移后再次启动不再移动任何数据
+        assert!(migrate_legacy_databases(&paths).unwrap().is_empty());
+        assert_eq!(CommentDB::new(&paths.comments_db.to_string_lossy()).unwrap().get_total_comments().unwrap(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,6 +1,21 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, DatabaseName, OptionalExtension, Result};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use serde::{Deserialize, Serialize};
 
+/// 存储数据的修改代数：写入或删除页面和附件、提交事务、编译完成后递增，
+/// 内存中的页面缓存据此判断是否失效
+static CACHE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 当前的存储修改代数，须在读取数据之前取得
+pub fn cache_generation() -> u64 {
+    CACHE_GENERATION.load(Ordering::Acquire)
+}
+
+fn bump_cache_generation() {
+    CACHE_GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
 /// 页面类型枚举
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum PageType {
@@ -19,6 +34,67 @@ pub struct Page {
     pub content: String,          // HTML content
     pub category: Option<String>, // For items/categories
     pub updated_at: String,
+    #[serde(default)]
+    pub publish_at: Option<String>, // Scheduled publish time, UTC "YYYY-MM-DD HH:MM:SS"
+    #[serde(default)]
+    pub tags: Vec<String>, // Item tags, stored comma-separated
+    #[serde(default)]
+    pub raw_content: Option<String>, // Source Markdown (items only)
+    #[serde(default)]
+    pub series: Option<String>, // Series the item belongs to
+    #[serde(default)]
+    pub series_order: Option<u32>, // Position within the series
+    #[serde(default)]
+    pub item_name: Option<String>, // Item directory name under its category (items only)
+    #[serde(default)]
+    pub excerpt: Option<String>, // Summary for listings (items only)
+}
+
+/// 同一分类中相邻的文章
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdjacentItem {
+    pub slug: String,
+    pub title: String,
+}
+
+/// 页面历史版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageVersion {
+    pub id: i64,
+    pub slug: String,
+    pub page_type: PageType,
+    pub title: String,
+    pub content: String,
+    pub raw_content: Option<String>,
+    pub category: Option<String>,
+    pub version_number: u64,
+    pub created_at: String,
+}
+
+/// 搜索过滤条件，未设置的条件不参与过滤
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    pub limit: usize,
+    pub offset: usize,
+    pub category: Option<String>,
+    pub start_date: Option<String>, // 发布日期下限（含），YYYY-MM-DD
+    pub end_date: Option<String>,   // 发布日期上限（含），YYYY-MM-DD
+    pub page_type: Option<PageType>,
+    pub tags: Option<Vec<String>>, // 须同时包含的标签
+}
+
+/// 默认保留的历史版本数
+pub const DEFAULT_MAX_VERSIONS: usize = 10;
+
+/// 自动 VACUUM 的间隔（天）
+pub const AUTO_VACUUM_INTERVAL_DAYS: i64 = 7;
+
+impl Page {
+    /// 是否为尚未到发布时间的定时文章
+    pub fn is_scheduled(&self) -> bool {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.publish_at.as_deref().is_some_and(|at| at > now.as_str())
+    }
 }
 
 /// 附件结构
@@ -32,6 +108,52 @@ pub struct StoredAttachment {
     pub file_data: Vec<u8>, // Binary content
     pub file_size: usize,
     pub updated_at: String,
+    #[serde(default)]
+    pub content_hash: String, // SHA-256 of file_data, used as ETag
+}
+
+/// 附件元信息（不含文件内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentMeta {
+    pub rowid: i64, // Used for incremental blob reads
+    pub slug: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub file_size: usize,
+    pub updated_at: String,
+    pub content_hash: String,
+    pub file_path: Option<String>, // Set when the content lives on disk instead of in file_data
+}
+
+/// 附件列表项（不含文件内容），用于后台浏览附件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentMetadata {
+    pub id: String,
+    pub slug: String,
+    pub filename: String,
+    pub original_name: String,
+    pub mime_type: String,
+    pub file_size: usize,
+    pub updated_at: String,
+}
+
+/// 附件引用（记录引用同一附件的所有文章）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentRef {
+    pub slug: String,     // Referencing item slug
+    pub filename: String, // Stored attachment filename
+    pub source_path: String, // Source file path in the content directory
+}
+
+/// 重复的源文件（同一附件在多个文章目录中各存一份）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateSource {
+    pub filename: String,
+    pub original_name: String,
+    pub file_size: usize,
+    pub slugs: Vec<String>,
+    pub source_paths: Vec<String>,
+    pub potential_savings: usize, // Bytes freed by keeping a single copy on disk
 }
 
 /// 站点统计信息
@@ -46,6 +168,7 @@ pub struct SiteStats {
 /// 统一的存储数据库
 pub struct StorageDB {
     conn: Connection,
+    max_versions: usize,
 }
 
 impl StorageDB {
@@ -53,6 +176,15 @@ impl StorageDB {
     pub fn new(db_path: &str) -> Result<Self> {
         let conn = Connection::open(db_path)?;
 
+        // WAL 模式允许读写并发；其余为连接级设置，每次打开都需执行
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA cache_size = -64000;
+             PRAGMA foreign_keys = ON;
+             PRAGMA temp_store = MEMORY;",
+        )?;
+
         // 创建页面表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS pages (
@@ -62,11 +194,58 @@ impl StorageDB {
                 title TEXT NOT NULL,
                 content TEXT NOT NULL,
                 category TEXT,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                publish_at TEXT,
+                tags TEXT,
+                raw_content TEXT,
+                series TEXT,
+                series_order INTEGER,
+                item_name TEXT,
+                excerpt TEXT
             )",
             [],
         )?;
 
+        // 旧数据库迁移：添加定时发布列
+        if !column_exists(&conn, "pages", "publish_at")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN publish_at TEXT", [])?;
+        }
+
+        // 旧数据库迁移：添加标签列
+        if !column_exists(&conn, "pages", "tags")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN tags TEXT", [])?;
+        }
+
+        // 旧数据库迁移：添加 Markdown 原文列
+        if !column_exists(&conn, "pages", "raw_content")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN raw_content TEXT", [])?;
+        }
+        if !column_exists(&conn, "pages", "series")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN series TEXT", [])?;
+        }
+        if !column_exists(&conn, "pages", "series_order")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN series_order INTEGER", [])?;
+        }
+        // 旧数据库迁移：文章目录名，旧数据为空时由 slug 推断
+        if !column_exists(&conn, "pages", "item_name")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN item_name TEXT", [])?;
+        }
+        // 旧数据库迁移：文章摘要，下次编译时填充
+        if !column_exists(&conn, "pages", "excerpt")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN excerpt TEXT", [])?;
+        }
+        // 旧数据库迁移：编译时预先压缩的页面正文及其语言，下次编译时填充
+        if !column_exists(&conn, "pages", "content_gzip")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN content_gzip BLOB", [])?;
+        }
+        if !column_exists(&conn, "pages", "content_gzip_lang")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN content_gzip_lang TEXT", [])?;
+        }
+        // 旧数据库迁移：同一语言的 brotli 压缩正文
+        if !column_exists(&conn, "pages", "content_br")? {
+            conn.execute("ALTER TABLE pages ADD COLUMN content_br BLOB", [])?;
+        }
+
         // 创建索引以加速查询
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_pages_slug ON pages(slug)",
@@ -81,6 +260,23 @@ impl StorageDB {
             [],
         )?;
 
+        // 创建页面历史版本表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                slug TEXT NOT NULL,
+                page_type TEXT NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                raw_content TEXT,
+                category TEXT,
+                version_number INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (slug, version_number)
+            )",
+            [],
+        )?;
+
         // 创建附件表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS attachments (
@@ -91,11 +287,25 @@ impl StorageDB {
                 mime_type TEXT NOT NULL,
                 file_data BLOB NOT NULL,
                 file_size INTEGER NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                content_hash TEXT NOT NULL DEFAULT ''
             )",
             [],
         )?;
 
+        // 旧数据库迁移：添加内容哈希列
+        if !column_exists(&conn, "attachments", "content_hash")? {
+            conn.execute(
+                "ALTER TABLE attachments ADD COLUMN content_hash TEXT NOT NULL DEFAULT ''",
+                [],
+            )?;
+        }
+
+        // 旧数据库迁移：添加磁盘路径列（文件系统存储模式）
+        if !column_exists(&conn, "attachments", "file_path")? {
+            conn.execute("ALTER TABLE attachments ADD COLUMN file_path TEXT", [])?;
+        }
+
         // 创建索引
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_attachments_slug ON attachments(slug)",
@@ -106,6 +316,21 @@ impl StorageDB {
             [],
         )?;
 
+        // 创建附件引用表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS attachment_refs (
+                slug TEXT NOT NULL,
+                filename TEXT NOT NULL,
+                source_path TEXT NOT NULL,
+                PRIMARY KEY (slug, source_path)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_attachment_refs_filename ON attachment_refs(filename)",
+            [],
+        )?;
+
         // 创建站点元数据表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS site_metadata (
@@ -115,19 +340,50 @@ impl StorageDB {
             [],
         )?;
 
+        // 创建文章浏览量表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS page_views (
+                slug TEXT PRIMARY KEY,
+                view_count INTEGER NOT NULL DEFAULT 0,
+                last_viewed_at TEXT
+            )",
+            [],
+        )?;
+
+        // 创建管理员会话表（只保存令牌哈希）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS admin_sessions (
+                token_hash TEXT PRIMARY KEY,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
         // 初始化默认元数据
         conn.execute(
             "INSERT OR IGNORE INTO site_metadata (key, value) VALUES (?, ?)",
             ["last_compiled", ""],
         )?;
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            max_versions: DEFAULT_MAX_VERSIONS,
+        })
+    }
+
+    /// 设置每个页面保留的历史版本数
+    pub fn with_max_versions(mut self, max_versions: usize) -> Self {
+        self.max_versions = max_versions;
+        self
     }
 
     // ==================== 页面操作 ====================
 
-    /// 保存页面
+    /// 保存页面（覆盖前将旧内容存为历史版本）
     pub fn save_page(&self, page: &Page) -> Result<()> {
+        archive_page(&self.conn, page, self.max_versions)?;
+
         let now = chrono::Utc::now().to_rfc3339();
         let page_type_str = match page.page_type {
             PageType::Index => "index",
@@ -136,76 +392,127 @@ impl StorageDB {
         };
 
         self.conn.execute(
-            "INSERT OR REPLACE INTO pages (id, slug, page_type, title, content, category, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
-            params![page.id, page.slug, page_type_str, page.title, page.content, page.category, now],
+            "INSERT OR REPLACE INTO pages (id, slug, page_type, title, content, category, updated_at, publish_at, tags, raw_content, series, series_order, item_name, excerpt)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![page.id, page.slug, page_type_str, page.title, page.content, page.category, now, page.publish_at, join_tags(&page.tags), page.raw_content, page.series, page.series_order, page.item_name, page.excerpt],
         )?;
 
+        bump_cache_generation();
         Ok(())
     }
 
+    /// 页面的类型和更新时间，不读取正文，用于校验缓存；尚未到发布时间的页面视为不存在
+    pub fn get_page_version(&self, slug: &str) -> Result<Option<(PageType, String)>> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .query_row(
+                "SELECT page_type, updated_at FROM pages
+                 WHERE slug = ?1 AND (publish_at IS NULL OR publish_at <= ?2)",
+                params![slug, now],
+                |row| {
+                    let page_type = match row.get::<_, String>(0)?.as_str() {
+                        "index" => PageType::Index,
+                        "category" => PageType::Category,
+                        _ => PageType::Item,
+                    };
+                    Ok((page_type, row.get(1)?))
+                },
+            )
+            .optional()
+    }
+
     /// 获取页面
     pub fn get_page(&self, slug: &str) -> Result<Option<Page>> {
+        self.conn
+            .query_row(
+                &format!("SELECT {} FROM pages WHERE slug = ?", PAGE_COLUMNS),
+                params![slug],
+                row_to_page,
+            )
+            .optional()
+    }
+
+    /// 同一分类中按 `updated_at` 排列的上一篇和下一篇文章，首篇没有上一篇、末篇没有下一篇；
+    /// 尚未到发布时间的文章不参与排列
+    pub fn get_adjacent_items(&self, slug: &str, category: &str) -> Result<(Option<AdjacentItem>, Option<AdjacentItem>)> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn
+            .query_row(
+                "SELECT prev_slug, prev_title, next_slug, next_title FROM (
+                     SELECT slug,
+                            LAG(slug) OVER w AS prev_slug, LAG(title) OVER w AS prev_title,
+                            LEAD(slug) OVER w AS next_slug, LEAD(title) OVER w AS next_title
+                     FROM pages
+                     WHERE page_type = 'item' AND category = ?2 AND (publish_at IS NULL OR publish_at <= ?3)
+                     WINDOW w AS (ORDER BY updated_at, slug)
+                 ) WHERE slug = ?1",
+                params![slug, category, now],
+                |row| {
+                    let adjacent = |slug: Option<String>, title: Option<String>| {
+                        slug.map(|slug| AdjacentItem { slug, title: title.unwrap_or_default() })
+                    };
+                    Ok((adjacent(row.get(0)?, row.get(1)?), adjacent(row.get(2)?, row.get(3)?)))
+                },
+            )
+            .optional()
+            .map(Option::unwrap_or_default)
+    }
+
+    /// 获取页面的历史版本，按版本号从新到旧排列
+    pub fn get_page_versions(&self, slug: &str) -> Result<Vec<PageVersion>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, page_type, title, content, category, updated_at
-             FROM pages WHERE slug = ?",
+            "SELECT id, slug, page_type, title, content, raw_content, category, version_number, created_at
+             FROM page_versions WHERE slug = ? ORDER BY version_number DESC",
         )?;
+        let versions = stmt
+            .query_map(params![slug], row_to_page_version)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(versions)
+    }
 
-        let mut rows = stmt.query(params![slug])?;
-
-        if let Some(row) = rows.next()? {
-            let page_type_str: String = row.get(2)?;
-            let page_type = match page_type_str.as_str() {
-                "index" => PageType::Index,
-                "category" => PageType::Category,
-                "item" => PageType::Item,
-                _ => PageType::Item,
-            };
+    /// 将历史版本恢复为当前页面；当前内容会先存为新的历史版本
+    pub fn restore_page_version(&self, slug: &str, version: u64) -> Result<()> {
+        let restored = self.conn.query_row(
+            "SELECT id, slug, page_type, title, content, raw_content, category, version_number, created_at
+             FROM page_versions WHERE slug = ? AND version_number = ?",
+            params![slug, version],
+            row_to_page_version,
+        )?;
 
-            let page = Page {
-                id: row.get(0)?,
-                slug: row.get(1)?,
-                page_type,
-                title: row.get(3)?,
-                content: row.get(4)?,
-                category: row.get(5)?,
-                updated_at: row.get(6)?,
-            };
-            Ok(Some(page))
-        } else {
-            Ok(None)
-        }
+        let current = self.get_page(slug)?;
+        let page = Page {
+            id: current
+                .as_ref()
+                .map(|page| page.id.clone())
+                .unwrap_or_else(|| default_page_id(&restored.page_type, slug)),
+            slug: restored.slug,
+            page_type: restored.page_type,
+            title: restored.title,
+            content: restored.content,
+            category: restored.category,
+            updated_at: String::new(),
+            publish_at: current.as_ref().and_then(|page| page.publish_at.clone()),
+            tags: current.as_ref().map(|page| page.tags.clone()).unwrap_or_default(),
+            raw_content: restored.raw_content,
+            series: current.as_ref().and_then(|page| page.series.clone()),
+            series_order: current.as_ref().and_then(|page| page.series_order),
+            excerpt: current.as_ref().and_then(|page| page.excerpt.clone()),
+            item_name: current.and_then(|page| page.item_name),
+        };
+        self.save_page(&page)
     }
 
     /// 获取所有页面
     pub fn get_all_pages(&self) -> Result<Vec<Page>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, slug, page_type, title, content, category, updated_at
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM pages ORDER BY slug",
-        )?;
-
-        let mut rows = stmt.query([])?;
-        let mut pages = Vec::new();
-
-        while let Some(row) = rows.next()? {
-            let page_type_str: String = row.get(2)?;
-            let page_type = match page_type_str.as_str() {
-                "index" => PageType::Index,
-                "category" => PageType::Category,
-                "item" => PageType::Item,
-                _ => PageType::Item,
-            };
+            PAGE_COLUMNS
+        ))?;
 
-            pages.push(Page {
-                id: row.get(0)?,
-                slug: row.get(1)?,
-                page_type,
-                title: row.get(3)?,
-                content: row.get(4)?,
-                category: row.get(5)?,
-                updated_at: row.get(6)?,
-            });
-        }
+        let pages = stmt
+            .query_map([], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
         Ok(pages)
     }
 
@@ -217,50 +524,140 @@ impl StorageDB {
             PageType::Item => "item",
         };
 
-        let mut stmt = self.conn.prepare(
-            "SELECT id, slug, page_type, title, content, category, updated_at
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM pages WHERE page_type = ? ORDER BY slug",
-        )?;
+            PAGE_COLUMNS
+        ))?;
 
-        let mut rows = stmt.query(params![page_type_str])?;
-        let mut pages = Vec::new();
-        let page_type = page_type; // 重新绑定
+        let pages = stmt
+            .query_map(params![page_type_str], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pages)
+    }
 
-        while let Some(row) = rows.next()? {
-            pages.push(Page {
-                id: row.get(0)?,
-                slug: row.get(1)?,
-                page_type: page_type.clone(),
-                title: row.get(3)?,
-                content: row.get(4)?,
-                category: row.get(5)?,
-                updated_at: row.get(6)?,
-            });
-        }
+    /// 获取所有项目页面用于列表展示：只读取摘要等元数据，`content` 为空、不读取 Markdown 原文
+    pub fn get_pages_with_excerpts(&self) -> Result<Vec<Page>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM pages WHERE page_type = 'item' ORDER BY slug",
+            PAGE_SUMMARY_COLUMNS
+        ))?;
+
+        let pages = stmt
+            .query_map([], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pages)
+    }
+
+    /// 分页获取某分类中已发布的项目页面（最近更新的在前）
+    pub fn get_items_by_category_paginated(
+        &self,
+        category: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Page>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM pages
+             WHERE page_type = 'item' AND category = ?
+             AND (publish_at IS NULL OR publish_at <= datetime('now'))
+             ORDER BY updated_at DESC, slug
+             LIMIT ? OFFSET ?",
+            PAGE_COLUMNS
+        ))?;
+
+        let pages = stmt
+            .query_map(params![category, limit, offset], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
         Ok(pages)
     }
 
+    /// 后台文章列表：可按分类过滤，草稿（slug 带 `_draft` 后缀）仅在
+    /// `include_drafts` 时列出；返回当前页结果和总数
+    pub fn list_items(
+        &self,
+        category: Option<&str>,
+        include_drafts: bool,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<Page>, usize)> {
+        let mut conditions = vec!["page_type = 'item'".to_string()];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if let Some(category) = category {
+            conditions.push("category = ?".to_string());
+            values.push(category.to_string().into());
+        }
+        if !include_drafts {
+            conditions.push("slug NOT LIKE '%\\_draft' ESCAPE '\\'".to_string());
+        }
+        let where_clause = conditions.join(" AND ");
+
+        let total: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM pages WHERE {}", where_clause),
+            rusqlite::params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM pages
+             WHERE {}
+             ORDER BY updated_at DESC, slug
+             LIMIT ? OFFSET ?",
+            PAGE_COLUMNS, where_clause
+        ))?;
+        values.push((limit as i64).into());
+        values.push((offset as i64).into());
+
+        let pages = stmt
+            .query_map(rusqlite::params_from_iter(values.iter()), row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok((pages, total as usize))
+    }
+
     /// 获取某分类的所有项目页面
     pub fn get_items_by_category(&self, category: &str) -> Result<Vec<Page>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, slug, page_type, title, content, category, updated_at
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM pages WHERE page_type = 'item' AND category = ? ORDER BY slug",
-        )?;
+            PAGE_COLUMNS
+        ))?;
 
-        let mut rows = stmt.query(params![category])?;
-        let mut pages = Vec::new();
+        let pages = stmt
+            .query_map(params![category], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pages)
+    }
 
-        while let Some(row) = rows.next()? {
-            pages.push(Page {
-                id: row.get(0)?,
-                slug: row.get(1)?,
-                page_type: PageType::Item,
-                title: row.get(3)?,
-                content: row.get(4)?,
-                category: row.get(5)?,
-                updated_at: row.get(6)?,
-            });
-        }
+    /// 获取尚未到发布时间的定时文章
+    pub fn get_scheduled_items(&self) -> Result<Vec<Page>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM pages
+             WHERE page_type = 'item' AND publish_at IS NOT NULL AND publish_at > datetime('now')
+             ORDER BY publish_at",
+            PAGE_COLUMNS
+        ))?;
+
+        let pages = stmt
+            .query_map([], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
+        Ok(pages)
+    }
+
+    /// 获取某系列的所有文章，按系列序号升序排列（未设置序号的排在最后）
+    pub fn get_series_items(&self, series: &str) -> Result<Vec<Page>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
+             FROM pages WHERE page_type = 'item' AND series = ?
+             ORDER BY series_order IS NULL, series_order ASC, slug",
+            PAGE_COLUMNS
+        ))?;
+
+        let pages = stmt
+            .query_map(params![series], row_to_page)?
+            .collect::<Result<Vec<_>>>()?;
         Ok(pages)
     }
 
@@ -269,12 +666,48 @@ impl StorageDB {
         let rows = self
             .conn
             .execute("DELETE FROM pages WHERE slug = ?", params![slug])?;
+        bump_cache_generation();
         Ok(rows > 0)
     }
 
+    /// 在同一个事务中执行一组操作，闭包返回错误时回滚
+    pub fn in_transaction<T>(&self, f: impl FnOnce(&Self) -> Result<T>) -> Result<T> {
+        self.begin_transaction()?;
+        match f(self) {
+            Ok(value) => {
+                self.commit_transaction()?;
+                Ok(value)
+            }
+            Err(e) => {
+                self.rollback_transaction();
+                Err(e)
+            }
+        }
+    }
+
+    /// 开始一个事务。批量写入方法在事务内改用保存点，提交前其他连接看到的仍是旧数据
+    pub fn begin_transaction(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")
+    }
+
+    /// 提交 `begin_transaction` 开始的事务
+    pub fn commit_transaction(&self) -> Result<()> {
+        self.conn.execute_batch("COMMIT")?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 回滚 `begin_transaction` 开始的事务，没有进行中的事务时什么也不做
+    pub fn rollback_transaction(&self) {
+        if !self.conn.is_autocommit() {
+            self.conn.execute_batch("ROLLBACK").ok();
+        }
+    }
+
     /// 清除所有页面
     pub fn clear_pages(&self) -> Result<()> {
         self.conn.execute("DELETE FROM pages", [])?;
+        bump_cache_generation();
         Ok(())
     }
 
@@ -286,8 +719,8 @@ impl StorageDB {
 
         self.conn.execute(
             "INSERT OR REPLACE INTO attachments
-             (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+             (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 attachment.id,
                 attachment.slug,
@@ -296,17 +729,19 @@ impl StorageDB {
                 attachment.mime_type,
                 attachment.file_data,
                 attachment.file_size,
-                now
+                now,
+                attachment.content_hash
             ],
         )?;
 
+        bump_cache_generation();
         Ok(())
     }
 
     /// 获取附件
     pub fn get_attachment(&self, filename: &str) -> Result<Option<StoredAttachment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at
+            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash
              FROM attachments WHERE filename = ?",
         )?;
 
@@ -322,6 +757,7 @@ impl StorageDB {
                 file_data: row.get(5)?,
                 file_size: row.get(6)?,
                 updated_at: row.get(7)?,
+                content_hash: row.get(8)?,
             };
             Ok(Some(attachment))
         } else {
@@ -329,10 +765,142 @@ impl StorageDB {
         }
     }
 
+    /// 获取附件元信息（不读取文件内容）
+    pub fn get_attachment_meta(&self, filename: &str) -> Result<Option<AttachmentMeta>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT rowid, slug, filename, mime_type, file_size, updated_at, content_hash, file_path
+             FROM attachments WHERE filename = ?",
+        )?;
+
+        let mut rows = stmt.query(params![filename])?;
+
+        if let Some(row) = rows.next()? {
+            Ok(Some(Self::row_to_attachment_meta(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// 获取内容仍存放在数据库中的附件元信息
+    pub fn get_db_stored_attachments(&self) -> Result<Vec<AttachmentMeta>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT rowid, slug, filename, mime_type, file_size, updated_at, content_hash, file_path
+             FROM attachments WHERE file_path IS NULL ORDER BY slug, filename",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut attachments = Vec::new();
+        while let Some(row) = rows.next()? {
+            attachments.push(Self::row_to_attachment_meta(row)?);
+        }
+        Ok(attachments)
+    }
+
+    /// 所属页面已不存在的附件文件名
+    pub fn get_orphaned_attachments(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT filename FROM attachments
+             WHERE slug NOT IN (SELECT slug FROM pages) ORDER BY slug, filename",
+        )?;
+        let filenames = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        Ok(filenames)
+    }
+
+    /// 获取某项目存放在磁盘上的附件路径
+    pub fn get_attachment_file_paths(&self, slug: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT file_path FROM attachments WHERE slug = ? AND file_path IS NOT NULL",
+        )?;
+        let paths = stmt
+            .query_map(params![slug], |row| row.get(0))?
+            .collect::<Result<Vec<String>>>()?;
+        Ok(paths)
+    }
+
+    /// 将附件标记为存放在磁盘上，并清空数据库中的内容
+    pub fn move_attachment_to_file(&self, rowid: i64, file_path: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE attachments SET file_data = x'', file_path = ? WHERE rowid = ?",
+            params![file_path, rowid],
+        )?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 数据库文件当前占用的字节数（page_count * page_size）
+    fn database_size(&self) -> Result<u64> {
+        let page_count: i64 = self.conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+        Ok((page_count * page_size).max(0) as u64)
+    }
+
+    /// 回收已释放的数据库空间，返回释放的字节数，并记录 `last_vacuum_at`
+    pub fn vacuum(&self) -> Result<u64> {
+        let before = self.database_size()?;
+        self.conn.execute("VACUUM", [])?;
+        let after = self.database_size()?;
+        self.set_metadata("last_vacuum_at", &chrono::Utc::now().to_rfc3339())?;
+        Ok(before.saturating_sub(after))
+    }
+
+    /// 距上次 VACUUM 超过 `AUTO_VACUUM_INTERVAL_DAYS` 天（或从未执行）时执行一次；
+    /// 返回释放的字节数，未到期时返回 `None`
+    pub fn vacuum_if_due(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Option<u64>> {
+        let last_vacuum = self
+            .get_metadata("last_vacuum_at")?
+            .and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok());
+        if let Some(last) = last_vacuum {
+            if now.signed_duration_since(last) <= chrono::Duration::days(AUTO_VACUUM_INTERVAL_DAYS) {
+                return Ok(None);
+            }
+        }
+        self.vacuum().map(Some)
+    }
+
+    /// 执行 `PRAGMA integrity_check`，数据库完好时返回 true
+    pub fn integrity_check(&self) -> Result<bool> {
+        let result: String = self.conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        Ok(result == "ok")
+    }
+
+    /// 执行 ANALYZE，更新查询优化器的统计信息
+    pub fn analyze(&self) -> Result<()> {
+        self.conn.execute_batch("ANALYZE")
+    }
+
+    /// 将 WAL 日志写回主数据库并截断日志文件
+    pub fn checkpoint(&self) -> Result<()> {
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    }
+
+    fn row_to_attachment_meta(row: &rusqlite::Row) -> Result<AttachmentMeta> {
+        Ok(AttachmentMeta {
+            rowid: row.get(0)?,
+            slug: row.get(1)?,
+            filename: row.get(2)?,
+            mime_type: row.get(3)?,
+            file_size: row.get(4)?,
+            updated_at: row.get(5)?,
+            content_hash: row.get(6)?,
+            file_path: row.get(7)?,
+        })
+    }
+
+    /// 从 offset 处读取附件内容填充 buf（增量读取，不加载整个 BLOB），返回读取的字节数
+    pub fn read_attachment_chunk(&self, rowid: i64, offset: usize, buf: &mut [u8]) -> Result<usize> {
+        let blob = self
+            .conn
+            .blob_open(DatabaseName::Main, "attachments", "file_data", rowid, true)?;
+        blob.read_at(buf, offset)
+    }
+
     /// 获取某项目的所有附件
     pub fn get_attachments_by_slug(&self, slug: &str) -> Result<Vec<StoredAttachment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at
+            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash
              FROM attachments WHERE slug = ? ORDER BY filename",
         )?;
 
@@ -349,19 +917,20 @@ impl StorageDB {
                 file_data: row.get(5)?,
                 file_size: row.get(6)?,
                 updated_at: row.get(7)?,
+                content_hash: row.get(8)?,
             });
         }
         Ok(attachments)
     }
 
-    /// 获取所有附件
-    pub fn get_all_attachments(&self) -> Result<Vec<StoredAttachment>> {
+    /// 获取某项目的附件列表（不读取文件内容，file_data 为空）
+    pub fn get_attachment_list(&self, slug: &str) -> Result<Vec<StoredAttachment>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at
-             FROM attachments ORDER BY slug, filename",
+            "SELECT id, slug, filename, original_name, mime_type, file_size, updated_at, content_hash
+             FROM attachments WHERE slug = ? ORDER BY original_name, filename",
         )?;
 
-        let mut rows = stmt.query([])?;
+        let mut rows = stmt.query(params![slug])?;
         let mut attachments = Vec::new();
 
         while let Some(row) = rows.next()? {
@@ -371,20 +940,71 @@ impl StorageDB {
                 filename: row.get(2)?,
                 original_name: row.get(3)?,
                 mime_type: row.get(4)?,
-                file_data: row.get(5)?,
-                file_size: row.get(6)?,
-                updated_at: row.get(7)?,
+                file_data: Vec::new(),
+                file_size: row.get(5)?,
+                updated_at: row.get(6)?,
+                content_hash: row.get(7)?,
             });
         }
         Ok(attachments)
     }
 
-    /// 删除附件
-    pub fn delete_attachment(&self, filename: &str) -> Result<bool> {
-        let rows = self.conn.execute(
-            "DELETE FROM attachments WHERE filename = ?",
-            params![filename],
+    /// 获取附件列表（不读取文件内容），`slug` 为空时列出所有文章的附件
+    pub fn get_attachment_metadata_list(&self, slug: Option<&str>) -> Result<Vec<AttachmentMetadata>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, slug, filename, original_name, mime_type, file_size, updated_at
+             FROM attachments WHERE ?1 IS NULL OR slug = ?1 ORDER BY slug, original_name, filename",
+        )?;
+
+        let attachments = stmt
+            .query_map(params![slug], |row| {
+                Ok(AttachmentMetadata {
+                    id: row.get(0)?,
+                    slug: row.get(1)?,
+                    filename: row.get(2)?,
+                    original_name: row.get(3)?,
+                    mime_type: row.get(4)?,
+                    file_size: row.get(5)?,
+                    updated_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(attachments)
+    }
+
+    /// 获取所有附件（包含文件内容，只浏览列表时用 `get_attachment_metadata_list`）
+    pub fn get_all_attachments(&self) -> Result<Vec<StoredAttachment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash
+             FROM attachments ORDER BY slug, filename",
+        )?;
+
+        let mut rows = stmt.query([])?;
+        let mut attachments = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            attachments.push(StoredAttachment {
+                id: row.get(0)?,
+                slug: row.get(1)?,
+                filename: row.get(2)?,
+                original_name: row.get(3)?,
+                mime_type: row.get(4)?,
+                file_data: row.get(5)?,
+                file_size: row.get(6)?,
+                updated_at: row.get(7)?,
+                content_hash: row.get(8)?,
+            });
+        }
+        Ok(attachments)
+    }
+
+    /// 删除附件
+    pub fn delete_attachment(&self, filename: &str) -> Result<bool> {
+        let rows = self.conn.execute(
+            "DELETE FROM attachments WHERE filename = ?",
+            params![filename],
         )?;
+        bump_cache_generation();
         Ok(rows > 0)
     }
 
@@ -393,15 +1013,116 @@ impl StorageDB {
         let rows = self
             .conn
             .execute("DELETE FROM attachments WHERE slug = ?", params![slug])?;
+        bump_cache_generation();
         Ok(rows)
     }
 
     /// 清除所有附件
     pub fn clear_attachments(&self) -> Result<()> {
         self.conn.execute("DELETE FROM attachments", [])?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 替换全部附件引用（事务）
+    pub fn replace_attachment_refs(&mut self, refs: &[AttachmentRef]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        tx.execute("DELETE FROM attachment_refs", [])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO attachment_refs (slug, filename, source_path)
+                 VALUES (?, ?, ?)",
+            )?;
+            for attachment_ref in refs {
+                stmt.execute(params![
+                    attachment_ref.slug,
+                    attachment_ref.filename,
+                    attachment_ref.source_path
+                ])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }
 
+    /// 替换单篇文章的附件引用（单篇编译时使用）
+    pub fn replace_item_attachment_refs(&mut self, slug: &str, refs: &[AttachmentRef]) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM attachment_refs WHERE slug = ?", params![slug])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO attachment_refs (slug, filename, source_path)
+                 VALUES (?, ?, ?)",
+            )?;
+            for attachment_ref in refs {
+                stmt.execute(params![
+                    attachment_ref.slug,
+                    attachment_ref.filename,
+                    attachment_ref.source_path
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 获取引用某附件的所有文章
+    pub fn get_attachment_refs(&self, filename: &str) -> Result<Vec<AttachmentRef>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT slug, filename, source_path FROM attachment_refs
+             WHERE filename = ? ORDER BY slug",
+        )?;
+
+        let refs = stmt
+            .query_map(params![filename], |row| {
+                Ok(AttachmentRef {
+                    slug: row.get(0)?,
+                    filename: row.get(1)?,
+                    source_path: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(refs)
+    }
+
+    /// 获取在多个文章目录中重复存放的源文件
+    pub fn get_duplicate_sources(&self) -> Result<Vec<DuplicateSource>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT a.filename, a.original_name, a.file_size
+             FROM attachments a
+             JOIN attachment_refs r ON r.filename = a.filename
+             GROUP BY a.filename
+             HAVING COUNT(*) > 1
+             ORDER BY a.file_size * (COUNT(*) - 1) DESC",
+        )?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, usize>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut duplicates = Vec::new();
+        for (filename, original_name, file_size) in rows {
+            let refs = self.get_attachment_refs(&filename)?;
+            let mut slugs: Vec<String> = refs.iter().map(|r| r.slug.clone()).collect();
+            slugs.dedup();
+            duplicates.push(DuplicateSource {
+                potential_savings: file_size * (refs.len() - 1),
+                filename,
+                original_name,
+                file_size,
+                slugs,
+                source_paths: refs.into_iter().map(|r| r.source_path).collect(),
+            });
+        }
+        Ok(duplicates)
+    }
+
     // ==================== 元数据操作 ====================
 
     /// 设置元数据
@@ -431,7 +1152,9 @@ impl StorageDB {
     /// 更新编译时间
     pub fn update_compile_time(&self) -> Result<()> {
         let now = chrono::Utc::now().to_rfc3339();
-        self.set_metadata("last_compiled", &now)
+        self.set_metadata("last_compiled", &now)?;
+        bump_cache_generation();
+        Ok(())
     }
 
     /// 获取最后编译时间
@@ -439,17 +1162,28 @@ impl StorageDB {
         self.get_metadata("last_compiled")
     }
 
+    /// 记录最近一次全量编译所用模板的指纹
+    pub fn update_template_fingerprint(&self, fingerprint: &str) -> Result<()> {
+        self.set_metadata("template_fingerprint", fingerprint)
+    }
+
+    /// 获取最近一次全量编译所用模板的指纹
+    pub fn get_template_fingerprint(&self) -> Result<Option<String>> {
+        self.get_metadata("template_fingerprint")
+    }
+
     // ==================== 搜索功能 ====================
 
     /// 搜索页面
     pub fn search_pages(&self, query: &str, limit: usize) -> Result<Vec<Page>> {
         let search_pattern = format!("%{}%", query);
 
-        let mut stmt = self.conn.prepare(
-            "SELECT id, slug, page_type, title, content, category, updated_at
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}
              FROM pages
              WHERE (title LIKE ? OR content LIKE ?)
              AND page_type != 'index'
+             AND (publish_at IS NULL OR publish_at <= datetime('now'))
              ORDER BY
                 CASE
                     WHEN title LIKE ? THEN 2
@@ -457,39 +1191,16 @@ impl StorageDB {
                     ELSE 0
                 END DESC,
                 updated_at DESC
-             LIMIT ?"
-        )?;
-
-        let mut rows = stmt.query(params![
-            search_pattern,
-            search_pattern,
-            search_pattern,
-            format!("%{}%", query),
-            limit
-        ])?;
-
-        let mut pages = Vec::new();
-
-        while let Some(row) = rows.next()? {
-            let page_type_str: String = row.get(2)?;
-            let page_type = match page_type_str.as_str() {
-                "index" => PageType::Index,
-                "category" => PageType::Category,
-                "item" => PageType::Item,
-                _ => PageType::Item,
-            };
-
-            pages.push(Page {
-                id: row.get(0)?,
-                slug: row.get(1)?,
-                page_type,
-                title: row.get(3)?,
-                content: row.get(4)?,
-                category: row.get(5)?,
-                updated_at: row.get(6)?,
-            });
-        }
-
+             LIMIT ?",
+            PAGE_COLUMNS
+        ))?;
+
+        let pages = stmt
+            .query_map(
+                params![search_pattern, search_pattern, search_pattern, format!("%{}%", query), limit],
+                row_to_page,
+            )?
+            .collect::<Result<Vec<_>>>()?;
         Ok(pages)
     }
 
@@ -500,7 +1211,8 @@ impl StorageDB {
         let count: i64 = self.conn.query_row(
             "SELECT COUNT(*) FROM pages
              WHERE (title LIKE ? OR content LIKE ?)
-             AND page_type != 'index'",
+             AND page_type != 'index'
+             AND (publish_at IS NULL OR publish_at <= datetime('now'))",
             params![search_pattern, search_pattern],
             |row| row.get(0),
         )?;
@@ -508,8 +1220,107 @@ impl StorageDB {
         Ok(count as usize)
     }
 
+    /// 按 `opts` 过滤的分页搜索，返回当前页结果和总数；
+    /// 关键词为空时只按过滤条件列出
+    pub fn search_pages_filtered(&self, query: &str, opts: &SearchOptions) -> Result<(Vec<Page>, usize)> {
+        let mut conditions = vec![
+            "page_type != 'index'".to_string(),
+            "(publish_at IS NULL OR publish_at <= datetime('now'))".to_string(),
+        ];
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+
+        let search_pattern = format!("%{}%", query);
+        if !query.is_empty() {
+            conditions.push("(title LIKE ? OR content LIKE ?)".to_string());
+            values.push(search_pattern.clone().into());
+            values.push(search_pattern.clone().into());
+        }
+        if let Some(category) = &opts.category {
+            conditions.push("category = ?".to_string());
+            values.push(category.clone().into());
+        }
+        // 文章按发布时间，没有发布时间的页面按编译时间；两者都以 YYYY-MM-DD 开头
+        if let Some(start) = &opts.start_date {
+            conditions.push("substr(COALESCE(publish_at, updated_at), 1, 10) >= ?".to_string());
+            values.push(start.clone().into());
+        }
+        if let Some(end) = &opts.end_date {
+            conditions.push("substr(COALESCE(publish_at, updated_at), 1, 10) <= ?".to_string());
+            values.push(end.clone().into());
+        }
+        if let Some(page_type) = &opts.page_type {
+            conditions.push("page_type = ?".to_string());
+            values.push(page_type_str(page_type).to_string().into());
+        }
+        for tag in opts.tags.iter().flatten() {
+            // tags 列以逗号连接，首尾补逗号后按整词匹配
+            conditions.push("instr(',' || tags || ',', ?) > 0".to_string());
+            values.push(format!(",{},", tag).into());
+        }
+        let where_clause = conditions.join(" AND ");
+
+        // 结果和总数一次查询取得；每行都带 total 列
+        let mut stmt = self.conn.prepare(&format!(
+            "WITH matches AS (
+                SELECT {}
+                FROM pages
+                WHERE {}
+             )
+             SELECT *, (SELECT COUNT(*) FROM matches) AS total
+             FROM matches
+             ORDER BY
+                CASE WHEN title LIKE ? THEN 1 ELSE 0 END DESC,
+                updated_at DESC
+             LIMIT ? OFFSET ?",
+            PAGE_COLUMNS, where_clause
+        ))?;
+        let filter_values = values.len();
+        values.push(search_pattern.into());
+        values.push((opts.limit as i64).into());
+        values.push((opts.offset as i64).into());
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(values.iter()))?;
+        let mut pages = Vec::new();
+        let mut total: Option<i64> = None;
+        while let Some(row) = rows.next()? {
+            total = Some(row.get(14)?);
+            pages.push(row_to_page(row)?);
+        }
+        drop(rows);
+
+        // 超出最后一页时没有返回行，单独计数
+        let total = match total {
+            Some(total) => total,
+            None if opts.offset == 0 => 0,
+            None => self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM pages WHERE {}", where_clause),
+                rusqlite::params_from_iter(values[..filter_values].iter()),
+                |row| row.get(0),
+            )?,
+        };
+        Ok((pages, total as usize))
+    }
+
+    /// 按关键词分页搜索，返回跳过 `offset` 条后的至多 `limit` 条结果和结果总数
+    pub fn search_pages_paginated(&self, query: &str, limit: usize, offset: usize) -> Result<(Vec<Page>, usize)> {
+        self.search_pages_filtered(
+            query,
+            &SearchOptions {
+                limit,
+                offset,
+                ..Default::default()
+            },
+        )
+    }
+
     // ==================== 统计信息 ====================
 
+    /// 页面总数；查询失败说明数据库不可用，健康检查据此判断
+    pub fn count_pages(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM pages", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
     /// 获取站点统计信息
     pub fn get_stats(&self) -> Result<SiteStats> {
         let total_categories: i64 = self
@@ -545,6 +1356,89 @@ impl StorageDB {
         })
     }
 
+    // ==================== 浏览量 ====================
+
+    /// 浏览量加一
+    pub fn increment_view_count(&self, slug: &str) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO page_views (slug, view_count, last_viewed_at) VALUES (?, 1, ?)
+             ON CONFLICT(slug) DO UPDATE SET view_count = view_count + 1, last_viewed_at = excluded.last_viewed_at",
+            params![slug, now],
+        )?;
+        Ok(())
+    }
+
+    /// 获取浏览量
+    pub fn get_view_count(&self, slug: &str) -> Result<i64> {
+        let count: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT view_count FROM page_views WHERE slug = ?",
+                params![slug],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(count.unwrap_or(0))
+    }
+
+    /// 获取所有文章的浏览量
+    pub fn get_view_counts(&self) -> Result<HashMap<String, i64>> {
+        let mut stmt = self.conn.prepare("SELECT slug, view_count FROM page_views")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    // ==================== 管理员会话 ====================
+
+    /// 创建会话，超出并发上限时淘汰最早的会话
+    pub fn create_admin_session(
+        &mut self,
+        token_hash: &str,
+        expires_at: i64,
+        max_sessions: usize,
+    ) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM admin_sessions WHERE expires_at <= ?", params![now])?;
+        tx.execute(
+            "INSERT OR REPLACE INTO admin_sessions (token_hash, created_at, expires_at) VALUES (?, ?, ?)",
+            params![token_hash, now, expires_at],
+        )?;
+        tx.execute(
+            "DELETE FROM admin_sessions WHERE token_hash NOT IN (
+                SELECT token_hash FROM admin_sessions ORDER BY created_at DESC, rowid DESC LIMIT ?
+            )",
+            params![max_sessions.max(1) as i64],
+        )?;
+        tx.commit()
+    }
+
+    /// 检查会话是否存在且未过期
+    pub fn is_admin_session_valid(&self, token_hash: &str) -> Result<bool> {
+        let now = chrono::Utc::now().timestamp();
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM admin_sessions WHERE token_hash = ? AND expires_at > ?",
+            params![token_hash, now],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// 删除会话
+    pub fn delete_admin_session(&self, token_hash: &str) -> Result<bool> {
+        let affected = self.conn.execute(
+            "DELETE FROM admin_sessions WHERE token_hash = ?",
+            params![token_hash],
+        )?;
+        Ok(affected > 0)
+    }
+
+    /// 删除所有会话（修改密码后强制重新登录），返回删除的数量
+    pub fn delete_all_admin_sessions(&self) -> Result<usize> {
+        self.conn.execute("DELETE FROM admin_sessions", [])
+    }
+
     // ==================== 批量操作 ====================
 
     /// 清空所有存储的数据
@@ -559,17 +1453,128 @@ impl StorageDB {
         Ok(())
     }
 
+    /// 从磁盘文件批量保存附件（事务），内容分块写入 BLOB，不整体读入内存
+    pub fn save_attachments_from_files(
+        &mut self,
+        attachments: &[(StoredAttachment, std::path::PathBuf)],
+    ) -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let tx = self.conn.savepoint()?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        for (attachment, source_path) in attachments {
+            let mut file = std::fs::File::open(source_path)?;
+            let file_size = file.metadata()?.len();
+            if file_size > i32::MAX as u64 {
+                return Err(format!(
+                    "Attachment '{}' is too large to store ({} bytes)",
+                    source_path.display(),
+                    file_size
+                )
+                .into());
+            }
+
+            tx.execute(
+                "INSERT OR REPLACE INTO attachments
+                 (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash)
+                 VALUES (?, ?, ?, ?, ?, zeroblob(?), ?, ?, ?)",
+                params![
+                    attachment.id,
+                    attachment.slug,
+                    attachment.filename,
+                    attachment.original_name,
+                    attachment.mime_type,
+                    file_size as i64,
+                    file_size as i64,
+                    now,
+                    attachment.content_hash
+                ],
+            )?;
+
+            let rowid = tx.last_insert_rowid();
+            let mut blob = tx.blob_open(DatabaseName::Main, "attachments", "file_data", rowid, false)?;
+            std::io::copy(&mut file, &mut blob)?;
+        }
+
+        tx.commit()?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 批量保存存放在磁盘上的附件元信息（事务），file_path 为附件在磁盘上的位置
+    pub fn save_attachment_records(
+        &mut self,
+        attachments: &[(StoredAttachment, std::path::PathBuf)],
+    ) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO attachments
+                 (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash, file_path)
+                 VALUES (?, ?, ?, ?, ?, x'', ?, ?, ?, ?)",
+            )?;
+            let now = chrono::Utc::now().to_rfc3339();
+
+            for (attachment, file_path) in attachments {
+                stmt.execute(params![
+                    attachment.id,
+                    attachment.slug,
+                    attachment.filename,
+                    attachment.original_name,
+                    attachment.mime_type,
+                    attachment.file_size,
+                    now,
+                    attachment.content_hash,
+                    file_path.to_string_lossy()
+                ])?;
+            }
+        }
+        tx.commit()?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 保存页面正文的压缩版本，`bodies` 为 (slug, gzip 数据, brotli 数据)，`lang` 为压缩时使用的语言。
+    /// 页面被重新保存后压缩版本随之清空
+    pub fn save_compressed_pages(&mut self, lang: &str, bodies: &[(String, Vec<u8>, Vec<u8>)]) -> Result<()> {
+        let tx = self.conn.savepoint()?;
+        {
+            let mut stmt = tx.prepare(
+                "UPDATE pages SET content_gzip = ?, content_br = ?, content_gzip_lang = ? WHERE slug = ?",
+            )?;
+            for (slug, gzip, brotli) in bodies {
+                stmt.execute(params![gzip, brotli, lang, slug])?;
+            }
+        }
+        tx.commit()?;
+        bump_cache_generation();
+        Ok(())
+    }
+
+    /// 获取页面正文在 `lang` 语言下的 (gzip, brotli) 压缩版本，没有时返回 None；
+    /// 加入 brotli 之前压缩的页面只有 gzip 版本
+    pub fn get_compressed_page(&self, slug: &str, lang: &str) -> Result<Option<(Vec<u8>, Option<Vec<u8>>)>> {
+        self.conn
+            .query_row(
+                "SELECT content_gzip, content_br FROM pages
+                 WHERE slug = ? AND content_gzip_lang = ? AND content_gzip IS NOT NULL",
+                params![slug, lang],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+    }
+
     /// 执行批量保存（事务）
     pub fn save_pages_batch(&mut self, pages: &[Page]) -> Result<()> {
-        let tx = self.conn.transaction()?;
+        let tx = self.conn.savepoint()?;
         {
             let mut stmt = tx.prepare(
-                "INSERT OR REPLACE INTO pages (id, slug, page_type, title, content, category, updated_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?)"
+                "INSERT OR REPLACE INTO pages (id, slug, page_type, title, content, category, updated_at, publish_at, tags, raw_content, series, series_order, item_name, excerpt)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
             )?;
             let now = chrono::Utc::now().to_rfc3339();
 
             for page in pages {
+                archive_page(&tx, page, self.max_versions)?;
                 let page_type_str = match page.page_type {
                     PageType::Index => "index",
                     PageType::Category => "category",
@@ -582,22 +1587,30 @@ impl StorageDB {
                     page.title,
                     page.content,
                     page.category,
-                    now
+                    now,
+                    page.publish_at,
+                    join_tags(&page.tags),
+                    page.raw_content,
+                    page.series,
+                    page.series_order,
+                    page.item_name,
+                    page.excerpt
                 ])?;
             }
         }
         tx.commit()?;
+        bump_cache_generation();
         Ok(())
     }
 
     /// 批量保存附件（事务）
     pub fn save_attachments_batch(&mut self, attachments: &[StoredAttachment]) -> Result<()> {
-        let tx = self.conn.transaction()?;
+        let tx = self.conn.savepoint()?;
         {
             let mut stmt = tx.prepare(
                 "INSERT OR REPLACE INTO attachments
-                 (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at)
-                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                 (id, slug, filename, original_name, mime_type, file_data, file_size, updated_at, content_hash)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )?;
             let now = chrono::Utc::now().to_rfc3339();
 
@@ -610,39 +1623,244 @@ impl StorageDB {
                     attachment.mime_type,
                     attachment.file_data,
                     attachment.file_size,
-                    now
+                    now,
+                    attachment.content_hash
                 ])?;
             }
         }
         tx.commit()?;
+        bump_cache_generation();
         Ok(())
     }
 }
 
-/// 根据文件名获取 MIME 类型
-pub fn get_mime_type(filename: &str) -> &'static str {
-    if filename.ends_with(".jpg") || filename.ends_with(".jpeg") {
-        "image/jpeg"
-    } else if filename.ends_with(".png") {
-        "image/png"
-    } else if filename.ends_with(".gif") {
-        "image/gif"
-    } else if filename.ends_with(".svg") {
-        "image/svg+xml"
-    } else if filename.ends_with(".webp") {
-        "image/webp"
-    } else if filename.ends_with(".pdf") {
-        "application/pdf"
-    } else if filename.ends_with(".zip") {
-        "application/zip"
-    } else if filename.ends_with(".mp4") {
-        "video/mp4"
-    } else if filename.ends_with(".mp3") {
-        "audio/mpeg"
-    } else if filename.ends_with(".txt") {
-        "text/plain"
+fn page_type_str(page_type: &PageType) -> &'static str {
+    match page_type {
+        PageType::Index => "index",
+        PageType::Category => "category",
+        PageType::Item => "item",
+    }
+}
+
+fn page_type_from_str(value: &str) -> PageType {
+    match value {
+        "index" => PageType::Index,
+        "category" => PageType::Category,
+        _ => PageType::Item,
+    }
+}
+
+/// 与编译器生成的页面 ID 保持一致
+fn default_page_id(page_type: &PageType, slug: &str) -> String {
+    match page_type {
+        PageType::Index => "index".to_string(),
+        PageType::Category => format!("category-{}", slug),
+        PageType::Item => format!("item-{}", slug),
+    }
+}
+
+/// `row_to_page` 读取的页面列，顺序与其中的下标一致
+const PAGE_COLUMNS: &str =
+    "id, slug, page_type, title, content, category, updated_at, publish_at, tags, raw_content, series, series_order, item_name, excerpt";
+
+/// 列表用的页面列：不读取正文和 Markdown 原文，其余与 `PAGE_COLUMNS` 相同
+const PAGE_SUMMARY_COLUMNS: &str =
+    "id, slug, page_type, title, '', category, updated_at, publish_at, tags, NULL, series, series_order, item_name, excerpt";
+
+fn row_to_page(row: &rusqlite::Row) -> Result<Page> {
+    let page_type: String = row.get(2)?;
+    Ok(Page {
+        id: row.get(0)?,
+        slug: row.get(1)?,
+        page_type: page_type_from_str(&page_type),
+        title: row.get(3)?,
+        content: row.get(4)?,
+        category: row.get(5)?,
+        updated_at: row.get(6)?,
+        publish_at: row.get(7)?,
+        tags: split_tags(row.get(8)?),
+        raw_content: row.get(9)?,
+        series: row.get(10)?,
+        series_order: row.get(11)?,
+        item_name: row.get(12)?,
+        excerpt: row.get(13)?,
+    })
+}
+
+fn row_to_page_version(row: &rusqlite::Row) -> Result<PageVersion> {
+    let page_type: String = row.get(2)?;
+    Ok(PageVersion {
+        id: row.get(0)?,
+        slug: row.get(1)?,
+        page_type: page_type_from_str(&page_type),
+        title: row.get(3)?,
+        content: row.get(4)?,
+        raw_content: row.get(5)?,
+        category: row.get(6)?,
+        version_number: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+/// 页面内容将被 `page` 覆盖时，把当前内容存为历史版本并清理超出上限的旧版本。
+/// 有 Markdown 原文时按原文判断是否变化，避免重新编译产生的无意义版本。
+fn archive_page(conn: &Connection, page: &Page, max_versions: usize) -> Result<()> {
+    if max_versions == 0 {
+        return Ok(());
+    }
+
+    let current: Option<(String, Option<String>, String)> = conn
+        .query_row(
+            "SELECT content, raw_content, title FROM pages WHERE slug = ?",
+            params![page.slug],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    let Some((content, raw_content, title)) = current else {
+        return Ok(());
+    };
+
+    let unchanged = match (&raw_content, &page.raw_content) {
+        (Some(old), Some(new)) => old == new && title == page.title,
+        _ => content == page.content && title == page.title,
+    };
+    if unchanged {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO page_versions (slug, page_type, title, content, raw_content, category, version_number, created_at)
+         SELECT slug, page_type, title, content, raw_content, category,
+                COALESCE((SELECT MAX(version_number) FROM page_versions WHERE slug = ?1), 0) + 1, ?2
+         FROM pages WHERE slug = ?1",
+        params![page.slug, chrono::Utc::now().to_rfc3339()],
+    )?;
+    conn.execute(
+        "DELETE FROM page_versions
+         WHERE slug = ?1
+           AND version_number <= (SELECT MAX(version_number) FROM page_versions WHERE slug = ?1) - ?2",
+        params![page.slug, max_versions as i64],
+    )?;
+    Ok(())
+}
+
+/// 通过 `PRAGMA table_info` 判断表中是否已有某列
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(1)?;
+        if name == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// 标签以逗号分隔存储
+fn join_tags(tags: &[String]) -> Option<String> {
+    if tags.is_empty() {
+        None
     } else {
-        "application/octet-stream"
+        Some(tags.join(","))
+    }
+}
+
+fn split_tags(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 根据文件名的扩展名（不区分大小写）获取 MIME 类型，未知时返回 application/octet-stream
+pub fn get_mime_type(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "md" => "text/markdown",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "toml" => "application/toml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 常见文件格式的文件头签名：每项为 (偏移, 字节) 列表和对应的 MIME 类型，按顺序匹配
+const FILE_SIGNATURES: &[(&[(usize, &[u8])], &str)] = &[
+    (&[(0, b"\xFF\xD8\xFF")], "image/jpeg"),
+    (&[(0, b"\x89PNG\r\n\x1A\n")], "image/png"),
+    (&[(0, b"GIF87a")], "image/gif"),
+    (&[(0, b"GIF89a")], "image/gif"),
+    (&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    (&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    (&[(4, b"ftypavif")], "image/avif"),
+    (&[(4, b"ftyp")], "video/mp4"),
+    (&[(0, b"\x00\x00\x01\x00")], "image/x-icon"),
+    (&[(0, b"%PDF-")], "application/pdf"),
+    (&[(0, b"PK\x03\x04")], "application/zip"),
+    (&[(0, b"\x1A\x45\xDF\xA3")], "video/webm"),
+    (&[(0, b"ID3")], "audio/mpeg"),
+    (&[(0, b"\xFF\xFB")], "audio/mpeg"),
+    (&[(0, b"OggS")], "audio/ogg"),
+    (&[(0, b"fLaC")], "audio/flac"),
+    (&[(0, b"wOFF")], "font/woff"),
+    (&[(0, b"wOF2")], "font/woff2"),
+    (&[(0, b"\x00\x01\x00\x00\x00")], "font/ttf"),
+    (&[(0, b"<?xml")], "application/xml"),
+    (&[(0, b"<svg")], "image/svg+xml"),
+];
+
+/// 根据文件开头的字节识别 MIME 类型，不认识时返回 None
+pub fn sniff_mime_type(head: &[u8]) -> Option<&'static str> {
+    FILE_SIGNATURES
+        .iter()
+        .find(|(parts, _)| {
+            parts
+                .iter()
+                .all(|(offset, bytes)| head.get(*offset..offset + bytes.len()) == Some(*bytes))
+        })
+        .map(|(_, mime_type)| *mime_type)
+}
+
+/// 获取文件的 MIME 类型：先看扩展名，扩展名未知时读取文件开头的 16 字节识别
+pub fn detect_mime_type(path: &std::path::Path) -> &'static str {
+    use std::io::Read;
+
+    let by_extension = get_mime_type(&path.to_string_lossy());
+    if by_extension != "application/octet-stream" {
+        return by_extension;
+    }
+
+    let mut head = Vec::with_capacity(16);
+    match std::fs::File::open(path).and_then(|file| file.take(16).read_to_end(&mut head)) {
+        Ok(_) => sniff_mime_type(&head).unwrap_or(by_extension),
+        Err(_) => by_extension,
     }
 }
 
@@ -661,6 +1879,22 @@ mod tests {
         std::fs::remove_file(temp_path).ok();
     }
 
+    #[test]
+    fn test_wal_mode() {
+        let temp_path = "/tmp/test_storage_wal.db";
+        let _ = std::fs::remove_file(temp_path);
+
+        let db = StorageDB::new(temp_path).unwrap();
+        let mode: String = db
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+        assert!(db.checkpoint().is_ok());
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
     #[test]
     fn test_save_and_get_page() {
         let temp_path = "/tmp/test_storage_page.db";
@@ -676,10 +1910,233 @@ mod tests {
             content: "<h1>Hello</h1>".to_string(),
             category: Some("test".to_string()),
             updated_at: chrono::Utc::now().to_rfc3339(),
+            publish_at: None,
+            tags: vec!["fruit".to_string(), "红色".to_string()],
+            raw_content: Some("# Hello".to_string()),
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: Some("Hello".to_string()),
         };
 
+        let generation = cache_generation();
         assert!(db.save_page(&page).is_ok());
-        assert!(db.get_page("test-page").unwrap().is_some());
+        // 写入后内存缓存失效
+        assert!(cache_generation() > generation);
+        let loaded = db.get_page("test-page").unwrap().unwrap();
+        assert_eq!(loaded.tags, vec!["fruit".to_string(), "红色".to_string()]);
+        assert_eq!(loaded.raw_content.as_deref(), Some("# Hello"));
+        assert_eq!(loaded.excerpt.as_deref(), Some("Hello"));
+
+        // 列表只读取摘要，不读取正文
+        let listed = db.get_pages_with_excerpts().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].excerpt.as_deref(), Some("Hello"));
+        assert!(listed[0].content.is_empty() && listed[0].raw_content.is_none());
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_adjacent_items() {
+        let temp_path = "/tmp/test_storage_adjacent.db";
+        let _ = std::fs::remove_file(temp_path);
+        let db = StorageDB::new(temp_path).unwrap();
+
+        let item = |slug: &str, category: &str, publish_at: Option<&str>| Page {
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            page_type: PageType::Item,
+            title: slug.to_uppercase(),
+            content: String::new(),
+            category: Some(category.to_string()),
+            updated_at: String::new(),
+            publish_at: publish_at.map(str::to_string),
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        for (slug, category, publish_at, updated_at) in [
+            ("fruit-c", "fruit", None, "2024-01-03T00:00:00+00:00"),
+            ("fruit-a", "fruit", None, "2024-01-01T00:00:00+00:00"),
+            ("fruit-b", "fruit", None, "2024-01-02T00:00:00+00:00"),
+            ("fruit-later", "fruit", Some("2999-01-01 00:00:00"), "2024-01-04T00:00:00+00:00"),
+            ("veg-a", "veg", None, "2024-01-02T12:00:00+00:00"),
+        ] {
+            db.save_page(&item(slug, category, publish_at)).unwrap();
+            db.conn
+                .execute("UPDATE pages SET updated_at = ?1 WHERE slug = ?2", params![updated_at, slug])
+                .unwrap();
+        }
+
+        let adjacent = |slug: &str| {
+            let (prev, next) = db.get_adjacent_items(slug, "fruit").unwrap();
+            (prev.map(|item| item.slug), next.map(|item| item.slug))
+        };
+        assert_eq!(adjacent("fruit-a"), (None, Some("fruit-b".to_string())));
+        assert_eq!(adjacent("fruit-b"), (Some("fruit-a".to_string()), Some("fruit-c".to_string())));
+        // 定时发布的文章不算下一篇
+        assert_eq!(adjacent("fruit-c"), (Some("fruit-b".to_string()), None));
+        assert_eq!(adjacent("missing"), (None, None));
+
+        let (prev, _) = db.get_adjacent_items("fruit-b", "fruit").unwrap();
+        assert_eq!(prev, Some(AdjacentItem { slug: "fruit-a".to_string(), title: "FRUIT-A".to_string() }));
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        let temp_path = "/tmp/test_storage_transaction.db";
+        let _ = std::fs::remove_file(temp_path);
+
+        let mut db = StorageDB::new(temp_path).unwrap();
+        let page = |slug: &str, content: &str| Page {
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            page_type: PageType::Item,
+            title: slug.to_string(),
+            content: content.to_string(),
+            category: Some("test".to_string()),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        db.save_pages_batch(&[page("old", "old")]).unwrap();
+
+        // 批量写入在外层事务中使用保存点，回滚后保留旧内容
+        db.begin_transaction().unwrap();
+        db.save_pages_batch(&[page("old", "new"), page("added", "new")]).unwrap();
+        db.rollback_transaction();
+        assert_eq!(db.get_page("old").unwrap().unwrap().content, "old");
+        assert!(db.get_page("added").unwrap().is_none());
+
+        db.begin_transaction().unwrap();
+        db.save_pages_batch(&[page("old", "new")]).unwrap();
+        db.commit_transaction().unwrap();
+        assert_eq!(db.get_page("old").unwrap().unwrap().content, "new");
+        // 没有进行中的事务时回滚不报错
+        db.rollback_transaction();
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_page_versions() {
+        let temp_path = "/tmp/test_storage_versions.db";
+        let _ = std::fs::remove_file(temp_path);
+        let db = StorageDB::new(temp_path).unwrap().with_max_versions(3);
+
+        let page = |raw: &str| Page {
+            id: "item-fruit-apple".to_string(),
+            slug: "fruit-apple".to_string(),
+            page_type: PageType::Item,
+            title: "Apple".to_string(),
+            content: format!("<p>{}</p>", raw),
+            category: Some("fruit".to_string()),
+            updated_at: String::new(),
+            publish_at: None,
+            tags: vec!["red".to_string()],
+            raw_content: Some(raw.to_string()),
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+
+        for n in 1..=5 {
+            db.save_page(&page(&format!("v{}", n))).unwrap();
+        }
+        // 内容未变化时不产生新版本
+        db.save_page(&page("v5")).unwrap();
+
+        let versions = db.get_page_versions("fruit-apple").unwrap();
+        let numbers: Vec<u64> = versions.iter().map(|v| v.version_number).collect();
+        assert_eq!(numbers, vec![4, 3, 2]);
+        assert_eq!(versions[0].raw_content.as_deref(), Some("v4"));
+
+        db.restore_page_version("fruit-apple", 2).unwrap();
+        let current = db.get_page("fruit-apple").unwrap().unwrap();
+        assert_eq!(current.raw_content.as_deref(), Some("v2"));
+        assert_eq!(current.tags, vec!["red".to_string()]);
+
+        // 恢复前的内容成为最新版本
+        let latest = &db.get_page_versions("fruit-apple").unwrap()[0];
+        assert_eq!((latest.version_number, latest.raw_content.as_deref()), (5, Some("v5")));
+
+        assert!(matches!(
+            db.restore_page_version("fruit-apple", 1),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+        ));
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_raw_content_migration() {
+        let temp_path = "/tmp/test_storage_raw_content.db";
+        let _ = std::fs::remove_file(temp_path);
+
+        // 旧版本的页面表，没有 raw_content 列
+        Connection::open(temp_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE pages (
+                    id TEXT PRIMARY KEY,
+                    slug TEXT NOT NULL UNIQUE,
+                    page_type TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    category TEXT,
+                    updated_at TEXT NOT NULL
+                );
+                INSERT INTO pages VALUES ('item-a', 'a', 'item', 'A', '<p>a</p>', NULL, '');",
+            )
+            .unwrap();
+
+        let db = StorageDB::new(temp_path).unwrap();
+        assert!(column_exists(&db.conn, "pages", "raw_content").unwrap());
+        assert_eq!(db.get_page("a").unwrap().unwrap().raw_content, None);
+
+        // 再次打开时不会重复添加列
+        assert!(StorageDB::new(temp_path).is_ok());
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_compressed_columns_migrate_independently() {
+        let temp_path = "/tmp/test_storage_gzip_columns.db";
+        let _ = std::fs::remove_file(temp_path);
+
+        // 只有 content_gzip、缺少 content_gzip_lang 的页面表
+        Connection::open(temp_path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE pages (
+                    id TEXT PRIMARY KEY,
+                    slug TEXT NOT NULL UNIQUE,
+                    page_type TEXT NOT NULL,
+                    title TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    category TEXT,
+                    updated_at TEXT NOT NULL,
+                    content_gzip BLOB
+                );",
+            )
+            .unwrap();
+
+        let db = StorageDB::new(temp_path).unwrap();
+        assert!(column_exists(&db.conn, "pages", "content_gzip_lang").unwrap());
+        assert!(column_exists(&db.conn, "pages", "content_br").unwrap());
 
         std::fs::remove_file(temp_path).ok();
     }
@@ -700,11 +2157,445 @@ mod tests {
             file_data: vec![0x89, 0x50, 0x4E, 0x47], // PNG header
             file_size: 4,
             updated_at: chrono::Utc::now().to_rfc3339(),
+            content_hash: String::new(),
         };
 
         assert!(db.save_attachment(&attachment).is_ok());
         assert!(db.get_attachment("test_image.jpg").unwrap().is_some());
 
+        db.save_attachment(&StoredAttachment {
+            id: "att-2".to_string(),
+            slug: "other-item".to_string(),
+            filename: "notes.pdf".to_string(),
+            original_name: "notes.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            ..attachment.clone()
+        })
+        .unwrap();
+        let all = db.get_attachment_metadata_list(None).unwrap();
+        assert_eq!(all.iter().map(|a| a.filename.as_str()).collect::<Vec<_>>(), ["notes.pdf", "test_image.jpg"]);
+        let listed = db.get_attachment_metadata_list(Some("test-item")).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].original_name, "my_photo.jpg");
+        assert_eq!(listed[0].file_size, 4);
+        assert!(db.get_attachment_metadata_list(Some("missing")).unwrap().is_empty());
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_attachment_streamed_storage() {
+        let db_path = "/tmp/test_attachment_stream.db";
+        let source = std::path::PathBuf::from("/tmp/test_attachment_stream.bin");
+        let _ = std::fs::remove_file(db_path);
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&source, &data).unwrap();
+
+        let mut db = StorageDB::new(db_path).unwrap();
+        let attachment = StoredAttachment {
+            id: "attachment-clip_1.mp4".to_string(),
+            slug: "item".to_string(),
+            filename: "clip_1.mp4".to_string(),
+            original_name: "clip.mp4".to_string(),
+            mime_type: "video/mp4".to_string(),
+            file_data: Vec::new(),
+            file_size: data.len(),
+            updated_at: String::new(),
+            content_hash: "hash".to_string(),
+        };
+        db.save_attachments_from_files(&[(attachment, source.clone())]).unwrap();
+
+        let meta = db.get_attachment_meta("clip_1.mp4").unwrap().unwrap();
+        assert_eq!(meta.file_size, data.len());
+
+        let mut buf = vec![0u8; 1000];
+        let read = db.read_attachment_chunk(meta.rowid, 150_000, &mut buf).unwrap();
+        assert_eq!(read, 1000);
+        assert_eq!(&buf[..], &data[150_000..151_000]);
+
+        let read = db.read_attachment_chunk(meta.rowid, 199_500, &mut buf).unwrap();
+        assert_eq!(read, 500);
+
+        let full = db.get_attachment("clip_1.mp4").unwrap().unwrap();
+        assert_eq!(full.file_data, data);
+
+        let _ = std::fs::remove_file(db_path);
+        let _ = std::fs::remove_file(source);
+    }
+
+    #[test]
+    fn test_attachment_file_records() {
+        let db_path = "/tmp/test_attachment_files.db";
+        let _ = std::fs::remove_file(db_path);
+
+        let mut db = StorageDB::new(db_path).unwrap();
+        let attachment = |filename: &str| StoredAttachment {
+            id: format!("attachment-{}", filename),
+            slug: "item".to_string(),
+            filename: filename.to_string(),
+            original_name: filename.to_string(),
+            mime_type: "image/png".to_string(),
+            file_data: b"in-db".to_vec(),
+            file_size: 5,
+            updated_at: String::new(),
+            content_hash: String::new(),
+        };
+        db.save_attachment(&attachment("a_1.png")).unwrap();
+        db.save_attachment_records(&[(
+            attachment("b_1.png"),
+            std::path::PathBuf::from("generated/attachments/item/b_1.png"),
+        )])
+        .unwrap();
+
+        let pending = db.get_db_stored_attachments().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].filename, "a_1.png");
+
+        db.move_attachment_to_file(pending[0].rowid, "generated/attachments/item/a_1.png").unwrap();
+        assert!(db.get_db_stored_attachments().unwrap().is_empty());
+
+        let meta = db.get_attachment_meta("a_1.png").unwrap().unwrap();
+        assert_eq!(meta.file_path.as_deref(), Some("generated/attachments/item/a_1.png"));
+        assert!(db.get_attachment("a_1.png").unwrap().unwrap().file_data.is_empty());
+
+        let mut paths = db.get_attachment_file_paths("item").unwrap();
+        paths.sort();
+        assert_eq!(paths.len(), 2);
+        assert!(paths[1].ends_with("b_1.png"));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_scheduled_items() {
+        let db_path = "/tmp/test_scheduled_items.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = StorageDB::new(db_path).unwrap();
+
+        let fmt = "%Y-%m-%d %H:%M:%S";
+        let future = (chrono::Utc::now() + chrono::Duration::hours(2)).format(fmt).to_string();
+        let past = (chrono::Utc::now() - chrono::Duration::minutes(5)).format(fmt).to_string();
+        for (slug, publish_at) in [("future", Some(future)), ("past", Some(past)), ("plain", None)] {
+            db.save_page(&Page {
+                id: format!("item-{}", slug),
+                slug: slug.to_string(),
+                page_type: PageType::Item,
+                title: format!("Scheduled {}", slug),
+                content: "<p>body</p>".to_string(),
+                category: Some("test".to_string()),
+                updated_at: String::new(),
+                publish_at,
+                tags: Vec::new(),
+                raw_content: None,
+                series: None,
+                series_order: None,
+                item_name: None,
+                excerpt: None,
+            })
+            .unwrap();
+        }
+
+        let scheduled = db.get_scheduled_items().unwrap();
+        assert_eq!(scheduled.len(), 1);
+        assert_eq!(scheduled[0].slug, "future");
+
+        assert_eq!(db.search_pages_count("Scheduled").unwrap(), 2);
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_series_items() {
+        let db_path = "/tmp/test_series_items.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = StorageDB::new(db_path).unwrap();
+
+        for (slug, series, order) in [
+            ("part-two", Some("rust"), Some(2)),
+            ("extra", Some("rust"), None),
+            ("part-one", Some("rust"), Some(1)),
+            ("other", Some("go"), Some(1)),
+            ("single", None, None),
+        ] {
+            db.save_page(&Page {
+                id: format!("item-{}", slug),
+                slug: slug.to_string(),
+                page_type: PageType::Item,
+                title: slug.to_string(),
+                content: "<p>body</p>".to_string(),
+                category: Some("test".to_string()),
+                updated_at: String::new(),
+                publish_at: None,
+                tags: Vec::new(),
+                raw_content: None,
+                series: series.map(str::to_string),
+                series_order: order,
+                item_name: None,
+                excerpt: None,
+            })
+            .unwrap();
+        }
+
+        // 未设置序号的文章排在最后
+        let slugs: Vec<String> = db.get_series_items("rust").unwrap().into_iter().map(|p| p.slug).collect();
+        assert_eq!(slugs, vec!["part-one", "part-two", "extra"]);
+        assert_eq!(db.get_page("part-two").unwrap().unwrap().series_order, Some(2));
+        assert!(db.get_series_items("missing").unwrap().is_empty());
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_search_pages_filtered() {
+        let temp_path = "/tmp/test_storage_search_filtered.db";
+        let _ = std::fs::remove_file(temp_path);
+        let db = StorageDB::new(temp_path).unwrap();
+
+        let page = |slug: &str, page_type: PageType, category: &str, publish_at: Option<&str>, tags: &[&str]| Page {
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            page_type,
+            title: slug.to_string(),
+            content: "<p>grape season</p>".to_string(),
+            category: Some(category.to_string()),
+            updated_at: String::new(),
+            publish_at: publish_at.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        db.save_page(&page("fruit-apple", PageType::Item, "fruit", Some("2024-05-01 08:00:00"), &["red", "sweet"])).unwrap();
+        db.save_page(&page("fruit-pear", PageType::Item, "fruit", Some("2024-06-10 08:00:00"), &["green", "redish"])).unwrap();
+        db.save_page(&page("tools-knife", PageType::Item, "tools", Some("2024-05-20 08:00:00"), &["red"])).unwrap();
+        db.save_page(&page("fruit", PageType::Category, "fruit", None, &[])).unwrap();
+
+        let slugs = |opts: SearchOptions| {
+            let (pages, total) = db.search_pages_filtered("grape", &SearchOptions { limit: 10, ..opts }).unwrap();
+            let mut slugs: Vec<String> = pages.into_iter().map(|p| p.slug).collect();
+            slugs.sort();
+            assert_eq!(slugs.len(), total);
+            slugs
+        };
+
+        assert_eq!(slugs(SearchOptions::default()).len(), 4);
+        assert_eq!(
+            slugs(SearchOptions { category: Some("fruit".to_string()), page_type: Some(PageType::Item), ..Default::default() }),
+            vec!["fruit-apple", "fruit-pear"]
+        );
+        assert_eq!(
+            slugs(SearchOptions {
+                start_date: Some("2024-05-01".to_string()),
+                end_date: Some("2024-05-20".to_string()),
+                ..Default::default()
+            }),
+            vec!["fruit-apple", "tools-knife"]
+        );
+        // 标签按整词匹配，red 不匹配 redish
+        assert_eq!(
+            slugs(SearchOptions { tags: Some(vec!["red".to_string()]), ..Default::default() }),
+            vec!["fruit-apple", "tools-knife"]
+        );
+        assert_eq!(
+            slugs(SearchOptions { tags: Some(vec!["red".to_string(), "sweet".to_string()]), ..Default::default() }),
+            vec!["fruit-apple"]
+        );
+
+        let (pages, total) = db
+            .search_pages_filtered("", &SearchOptions { limit: 1, offset: 1, ..Default::default() })
+            .unwrap();
+        assert_eq!((pages.len(), total), (1, 4));
+
+        let (pages, total) = db.search_pages_paginated("grape", 3, 0).unwrap();
+        assert_eq!((pages.len(), total), (3, 4));
+        let (pages, total) = db.search_pages_paginated("grape", 3, 3).unwrap();
+        assert_eq!((pages.len(), total), (1, 4));
+        // 超出最后一页时仍返回总数
+        let (pages, total) = db.search_pages_paginated("grape", 3, 9).unwrap();
+        assert_eq!((pages.len(), total), (0, 4));
+        assert_eq!(db.search_pages_paginated("plum", 3, 0).unwrap().1, 0);
+
         std::fs::remove_file(temp_path).ok();
     }
+
+    #[test]
+    fn test_view_counts() {
+        let db_path = "/tmp/test_view_counts.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = StorageDB::new(db_path).unwrap();
+
+        assert_eq!(db.get_view_count("grape-tizi").unwrap(), 0);
+        db.increment_view_count("grape-tizi").unwrap();
+        db.increment_view_count("grape-tizi").unwrap();
+        db.increment_view_count("apple-fuji").unwrap();
+
+        assert_eq!(db.get_view_count("grape-tizi").unwrap(), 2);
+        let counts = db.get_view_counts().unwrap();
+        assert_eq!(counts.get("apple-fuji"), Some(&1));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_database_maintenance() {
+        let db_path = "/tmp/test_database_maintenance.db";
+        let _ = std::fs::remove_file(db_path);
+        let db = StorageDB::new(db_path).unwrap();
+
+        db.set_metadata("filler", &"x".repeat(1 << 20)).unwrap();
+        db.conn.execute("DELETE FROM site_metadata WHERE key = 'filler'", []).unwrap();
+        assert!(db.vacuum().unwrap() > 0);
+        assert!(db.integrity_check().unwrap());
+        assert!(db.analyze().is_ok());
+
+        // Due again only once the interval has passed
+        let now = chrono::Utc::now();
+        assert_eq!(db.vacuum_if_due(now).unwrap(), None);
+        assert!(db.vacuum_if_due(now + chrono::Duration::days(AUTO_VACUUM_INTERVAL_DAYS + 1)).unwrap().is_some());
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_admin_sessions() {
+        let db_path = "/tmp/test_admin_sessions.db";
+        let _ = std::fs::remove_file(db_path);
+        let mut db = StorageDB::new(db_path).unwrap();
+        let now = chrono::Utc::now().timestamp();
+
+        db.create_admin_session("expired", now - 1, 5).unwrap();
+        assert!(!db.is_admin_session_valid("expired").unwrap());
+
+        db.create_admin_session("a", now + 3600, 2).unwrap();
+        db.create_admin_session("b", now + 3600, 2).unwrap();
+        db.create_admin_session("c", now + 3600, 2).unwrap();
+        assert!(!db.is_admin_session_valid("a").unwrap());
+        assert!(db.is_admin_session_valid("b").unwrap());
+        assert!(db.is_admin_session_valid("c").unwrap());
+
+        assert!(db.delete_admin_session("c").unwrap());
+        assert!(!db.is_admin_session_valid("c").unwrap());
+        assert!(!db.is_admin_session_valid("forged_1700000000").unwrap());
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_duplicate_sources() {
+        let temp_path = "/tmp/test_storage_duplicates.db";
+        let _ = std::fs::remove_file(temp_path);
+
+        let mut db = StorageDB::new(temp_path).unwrap();
+
+        let attachment = StoredAttachment {
+            id: "attachment-diagram_1.png".to_string(),
+            slug: "docs-part1".to_string(),
+            filename: "diagram_1.png".to_string(),
+            original_name: "diagram.png".to_string(),
+            mime_type: "image/png".to_string(),
+            file_data: vec![0u8; 100],
+            file_size: 100,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            content_hash: String::new(),
+        };
+        db.save_attachment(&attachment).unwrap();
+
+        let refs: Vec<AttachmentRef> = (1..=3)
+            .map(|i| AttachmentRef {
+                slug: format!("docs-part{}", i),
+                filename: "diagram_1.png".to_string(),
+                source_path: format!("content/docs/part{}/attachment/diagram.png", i),
+            })
+            .collect();
+        db.replace_attachment_refs(&refs).unwrap();
+
+        let duplicates = db.get_duplicate_sources().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].slugs.len(), 3);
+        assert_eq!(duplicates[0].potential_savings, 200);
+
+        std::fs::remove_file(temp_path).ok();
+    }
+
+    #[test]
+    fn test_get_mime_type() {
+        let cases = [
+            ("photo.jpg", "image/jpeg"),
+            ("photo.jpeg", "image/jpeg"),
+            ("PHOTO.JPG", "image/jpeg"),
+            ("chart.png", "image/png"),
+            ("anim.gif", "image/gif"),
+            ("logo.svg", "image/svg+xml"),
+            ("photo.webp", "image/webp"),
+            ("photo.avif", "image/avif"),
+            ("favicon.ico", "image/x-icon"),
+            ("paper.pdf", "application/pdf"),
+            ("bundle.zip", "application/zip"),
+            ("clip.mp4", "video/mp4"),
+            ("clip.webm", "video/webm"),
+            ("song.mp3", "audio/mpeg"),
+            ("song.ogg", "audio/ogg"),
+            ("voice.wav", "audio/wav"),
+            ("album.flac", "audio/flac"),
+            ("font.woff", "font/woff"),
+            ("font.woff2", "font/woff2"),
+            ("font.ttf", "font/ttf"),
+            ("notes.txt", "text/plain"),
+            ("table.csv", "text/csv"),
+            ("readme.md", "text/markdown"),
+            ("data.json", "application/json"),
+            ("feed.xml", "application/xml"),
+            ("config.toml", "application/toml"),
+            ("archive.tar.zip", "application/zip"),
+            ("Song.Final.MP3", "audio/mpeg"),
+            ("blob.bin", "application/octet-stream"),
+            ("Makefile", "application/octet-stream"),
+            ("trailing.", "application/octet-stream"),
+        ];
+        for (filename, expected) in cases {
+            assert_eq!(get_mime_type(filename), expected, "{}", filename);
+        }
+    }
+
+    #[test]
+    fn test_sniff_mime_type() {
+        let cases: [(&[u8], &str); 20] = [
+            (b"\xFF\xD8\xFF\xE0\x00\x10JFIF", "image/jpeg"),
+            (b"\x89PNG\r\n\x1A\n\x00\x00", "image/png"),
+            (b"GIF89a\x01\x00", "image/gif"),
+            (b"RIFF\x24\x00\x00\x00WEBPVP8 ", "image/webp"),
+            (b"RIFF\x24\x00\x00\x00WAVEfmt ", "audio/wav"),
+            (b"\x00\x00\x00\x1CftypavifXX", "image/avif"),
+            (b"\x00\x00\x00\x18ftypisom", "video/mp4"),
+            (b"\x00\x00\x01\x00\x01\x00", "image/x-icon"),
+            (b"%PDF-1.7\n", "application/pdf"),
+            (b"PK\x03\x04\x14\x00", "application/zip"),
+            (b"\x1A\x45\xDF\xA3\x9F", "video/webm"),
+            (b"ID3\x04\x00", "audio/mpeg"),
+            (b"\xFF\xFB\x90\x64", "audio/mpeg"),
+            (b"OggS\x00\x02", "audio/ogg"),
+            (b"fLaC\x00\x00", "audio/flac"),
+            (b"wOFF\x00\x01", "font/woff"),
+            (b"wOF2\x00\x01", "font/woff2"),
+            (b"\x00\x01\x00\x00\x00\x0F", "font/ttf"),
+            (b"<?xml version=", "application/xml"),
+            (b"<svg xmlns=", "image/svg+xml"),
+        ];
+        for (head, expected) in cases {
+            assert_eq!(sniff_mime_type(head), Some(expected), "{:?}", head);
+        }
+        assert_eq!(sniff_mime_type(b"hello world"), None);
+        assert_eq!(sniff_mime_type(b"RIF"), None);
+        assert_eq!(sniff_mime_type(b""), None);
+
+        // 扩展名未知时读取文件头
+        let path = std::path::PathBuf::from("/tmp/test_detect_mime_type.dat");
+        std::fs::write(&path, b"\x89PNG\r\n\x1A\n\x00\x00\x00\x0DIHDR").unwrap();
+        assert_eq!(detect_mime_type(&path), "image/png");
+        std::fs::write(&path, b"plain bytes").unwrap();
+        assert_eq!(detect_mime_type(&path), "application/octet-stream");
+        assert_eq!(detect_mime_type(std::path::Path::new("/tmp/missing_detect_mime.dat")), "application/octet-stream");
+        assert_eq!(detect_mime_type(std::path::Path::new("/tmp/missing_detect_mime.json")), "application/json");
+        std::fs::remove_file(&path).ok();
+    }
 }
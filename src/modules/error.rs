@@ -0,0 +1,160 @@
+//! 统一错误类型
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use std::fmt;
+use utoipa::ToSchema;
+
+/// 内容编译和请求处理中的错误。作为 actix 的 `ResponseError` 时返回对应的
+/// HTTP 状态码和 `{status, code, message}` 格式的 JSON
+#[derive(Debug)]
+pub enum BlogError {
+    Io(std::io::Error),
+    Database(rusqlite::Error),
+    Template(tera::Error),
+    Markdown(String),
+    NotFound(String),
+    Unauthorized(String),
+    Forbidden(String),
+    TooManyRequests(String, u64), // 附带 `Retry-After` 秒数
+    Validation(String),
+    Internal(String), // 其他来源的错误，如压缩包解析、后台任务失败
+}
+
+impl BlogError {
+    /// JSON 响应中的 `code` 字段
+    pub fn code(&self) -> &'static str {
+        match self {
+            BlogError::Io(_) => "io",
+            BlogError::Database(_) => "database",
+            BlogError::Template(_) => "template",
+            BlogError::Markdown(_) => "markdown",
+            BlogError::NotFound(_) => "not_found",
+            BlogError::Unauthorized(_) => "unauthorized",
+            BlogError::Forbidden(_) => "forbidden",
+            BlogError::TooManyRequests(..) => "too_many_requests",
+            BlogError::Validation(_) => "validation",
+            BlogError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for BlogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlogError::Io(e) => write!(f, "{}", e),
+            BlogError::Database(e) => write!(f, "{}", e),
+            // tera 把具体原因（哪个变量、哪一行）放在 source 链里
+            BlogError::Template(e) => {
+                write!(f, "{}", e)?;
+                let mut source = std::error::Error::source(e);
+                while let Some(cause) = source {
+                    write!(f, ": {}", cause)?;
+                    source = cause.source();
+                }
+                Ok(())
+            }
+            BlogError::Markdown(message)
+            | BlogError::NotFound(message)
+            | BlogError::Unauthorized(message)
+            | BlogError::Forbidden(message)
+            | BlogError::TooManyRequests(message, _)
+            | BlogError::Validation(message)
+            | BlogError::Internal(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BlogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlogError::Io(e) => Some(e),
+            BlogError::Database(e) => Some(e),
+            BlogError::Template(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BlogError {
+    fn from(e: std::io::Error) -> Self {
+        BlogError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for BlogError {
+    fn from(e: rusqlite::Error) -> Self {
+        BlogError::Database(e)
+    }
+}
+
+impl From<tera::Error> for BlogError {
+    fn from(e: tera::Error) -> Self {
+        BlogError::Template(e)
+    }
+}
+
+/// 错误响应的 JSON 格式
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(example = json!({"status": "error", "code": "not_found", "message": "文章不存在"}))]
+pub struct ErrorBody {
+    pub status: &'static str, // 固定为 "error"
+    pub code: &'static str,   // 见 `BlogError::code`
+    pub message: String,
+}
+
+impl ResponseError for BlogError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            BlogError::NotFound(_) => StatusCode::NOT_FOUND,
+            BlogError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            BlogError::Forbidden(_) => StatusCode::FORBIDDEN,
+            BlogError::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+            BlogError::Validation(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let BlogError::TooManyRequests(_, retry_after) = self {
+            response.insert_header((actix_web::http::header::RETRY_AFTER, retry_after.to_string()));
+        }
+        response.json(ErrorBody {
+            status: "error",
+            code: self.code(),
+            message: self.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_response() {
+        let cases = [
+            (BlogError::Validation("分类不存在".to_string()), 400, "validation"),
+            (BlogError::NotFound("草稿文件不存在".to_string()), 404, "not_found"),
+            (BlogError::Unauthorized("未授权访问，请先登录".to_string()), 401, "unauthorized"),
+            (BlogError::Forbidden("当前密码错误".to_string()), 403, "forbidden"),
+            (BlogError::TooManyRequests("请稍后再试".to_string(), 60), 429, "too_many_requests"),
+            (BlogError::from(std::io::Error::other("disk full")), 500, "io"),
+            (BlogError::from(rusqlite::Error::InvalidQuery), 500, "database"),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code().as_u16(), status);
+            assert_eq!(error.code(), code);
+        }
+
+        let response = BlogError::TooManyRequests("请稍后再试".to_string(), 60).error_response();
+        assert_eq!(response.headers().get("retry-after").unwrap(), "60");
+
+        // 模板错误带上具体原因
+        let mut tera = tera::Tera::default();
+        tera.add_raw_template("page.html", "{{ missing }}").unwrap();
+        let error = BlogError::from(tera.render("page.html", &tera::Context::new()).unwrap_err());
+        assert!(error.to_string().contains("missing"));
+    }
+}
@@ -3,12 +3,15 @@
 //! 核心配置和工具
 pub mod config;
 pub mod cli;
+pub mod error;
 
 // 数据库相关模块
 pub mod database {
     pub mod storage;
     pub mod comments;
     pub mod analytics;
+    pub mod migrate;
+    pub mod geoip;
 }
 
 // 内容处理相关模块
@@ -18,6 +21,14 @@ pub mod content {
     pub mod markdown;
     pub mod templates;
     pub mod theme;
+    pub mod seo;
+    pub mod highlight;
+    pub mod tags;
+    pub mod series;
+    pub mod i18n;
+    pub mod images;
+    pub mod slug;
+    pub mod git_sync;
 }
 
 // Web 相关模块
@@ -25,4 +36,11 @@ pub mod web {
     pub mod admin;
     pub mod routes;
     pub mod recommender;
+    pub mod redirects;
+    pub mod ip_filter;
+    pub mod notifications;
+    pub mod openapi;
+    pub mod render_cache;
+    pub mod middleware;
+    pub mod live_config;
 }
\ No newline at end of file
@@ -1,25 +1,31 @@
-use crate::config::Config;
+use crate::config::{AttachmentBackend, AttachmentsConfig, Config, MarkdownConfig, MIN_ADMIN_PASSWORD_LEN};
+use crate::compiler::{CompileItemResult, CompileProgress, Compiler};
 use crate::scanner::{Category, ContentItem, Scanner, SiteContent};
-use crate::storage::{Page, PageType, StorageDB};
-use actix_multipart::Multipart;
+use crate::storage::{Page, PageType, StorageDB, StoredAttachment};
+use crate::redirects::RedirectStore;
+use crate::templates::template_fingerprint;
+use crate::error::BlogError;
+use actix_multipart::{Field, Multipart};
 use actix_web::{web, HttpResponse, Responder};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
 use uuid::Uuid;
 use chrono::Utc;
 
 /// 管理员登录请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub password: String,
 }
 
 /// 管理员登录响应
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
     pub success: bool,
     pub message: String,
@@ -27,14 +33,58 @@ pub struct LoginResponse {
 }
 
 /// 创建分类请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateCategoryRequest {
     pub name: String,
     pub description: Option<String>,
 }
 
-/// 创建文章请求
+/// 文章列表查询参数
 #[derive(Debug, Deserialize)]
+pub struct ItemsQuery {
+    pub drafts: Option<bool>,     // 为 true 时一并列出草稿
+    pub category: Option<String>, // 只列出指定分类
+    pub page: Option<usize>,      // 页码，从 1 开始
+    pub per_page: Option<usize>,  // 每页条数
+}
+
+/// 文章列表默认每页条数
+const DEFAULT_ITEMS_PER_PAGE: usize = 50;
+
+/// 文章列表每页条数上限
+const MAX_ITEMS_PER_PAGE: usize = 200;
+
+/// 重命名分类请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RenameCategoryRequest {
+    pub name: String,
+}
+
+/// 批量操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    Delete,
+    Publish,
+    Unpublish,
+}
+
+/// 批量操作文章请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkItemsRequest {
+    pub action: BulkAction,
+    pub slugs: Vec<String>,
+}
+
+/// 批量操作中失败的文章
+#[derive(Debug, Serialize)]
+pub struct BulkFailure {
+    pub slug: String,
+    pub error: String,
+}
+
+/// 创建文章请求
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateItemRequest {
     pub category: String,
     pub item_name: String,
@@ -48,7 +98,7 @@ pub struct CreateItemRequest {
 }
 
 /// 更新文章请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateItemRequest {
     pub category: String,
     pub item_name: String,
@@ -62,7 +112,7 @@ pub struct UpdateItemRequest {
 }
 
 /// 导入请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct ImportRequest {
     pub import_type: String, // "full" or "incremental"
     pub target_category: Option<String>, // For incremental import
@@ -78,75 +128,641 @@ pub struct ExportResponse {
 }
 
 /// 编译器状态
+#[derive(Debug, Default)]
 pub struct CompilerState {
     pub is_compiling: bool,
     pub last_compile_result: Option<String>,
+    /// 上一次编译是否成功
+    pub last_success: Option<bool>,
+    /// 当前（或最近一次）编译任务 ID
+    pub job_id: Option<String>,
+    /// 当前（或最近一次）编译开始时间
+    pub started_at: Option<String>,
+    /// 当前编译进度（阶段与已完成数量），空闲时为空
+    pub progress: Option<CompileProgress>,
 }
 
 impl CompilerState {
     pub fn new() -> Self {
-        Self {
-            is_compiling: false,
-            last_compile_result: None,
+        Self::default()
+    }
+
+    /// 开始一次编译并返回新任务 ID；已有编译在运行时返回其任务 ID
+    pub fn try_start(&mut self) -> Result<String, String> {
+        if self.is_compiling {
+            return Err(self.job_id.clone().unwrap_or_default());
+        }
+        let job_id = Uuid::new_v4().to_string();
+        self.is_compiling = true;
+        self.job_id = Some(job_id.clone());
+        self.started_at = Some(Utc::now().to_rfc3339());
+        self.progress = None;
+        Ok(job_id)
+    }
+
+    /// 记录编译进度；编译结束后到达的进度会被忽略
+    pub fn set_progress(&mut self, progress: CompileProgress) {
+        if self.is_compiling {
+            self.progress = Some(progress);
+        }
+    }
+
+    /// 记录编译结果并标记为空闲
+    pub fn finish(&mut self, result: String, success: bool) {
+        self.is_compiling = false;
+        self.progress = None;
+        self.last_compile_result = Some(result);
+        self.last_success = Some(success);
+    }
+}
+
+/// 在后台线程启动一次全量编译，返回任务 ID；已有编译在运行时返回 `Err(其任务 ID)`
+pub fn start_background_compile(
+    config: Config,
+    state: web::Data<Mutex<CompilerState>>,
+) -> Result<String, String> {
+    let job_id = match state.lock() {
+        Ok(mut state) => state.try_start(),
+        Err(poisoned) => poisoned.into_inner().try_start(),
+    }?;
+
+    let progress_state = state.clone();
+    let webhook_config = config.clone();
+    let started = std::time::Instant::now();
+    actix_web::rt::spawn(async move {
+        let result = tokio::task::spawn_blocking(move || {
+            Compiler::new(config)
+                .map(|compiler| {
+                    // 管理员手动触发的编译总是全量编译
+                    compiler.with_force_full(true).with_progress(move |progress| match progress_state.lock() {
+                        Ok(mut state) => state.set_progress(progress),
+                        Err(poisoned) => poisoned.into_inner().set_progress(progress),
+                    })
+                })
+                .and_then(|mut compiler| compiler.compile())
+                .map_err(|e| e.to_string())
+        })
+        .await;
+
+        let (message, success) = match result {
+            Ok(Ok(result)) => {
+                let message = format!("Compiled {} pages, {} attachments",
+                    result.total_categories + result.total_items + 1, result.total_attachments);
+                log::info!("Compilation successful: {}", message);
+                crate::notifications::notify_compile(&webhook_config, &result, started.elapsed());
+                (message, true)
+            }
+            Ok(Err(e)) => {
+                log::error!("Compilation failed: {}", e);
+                (e, false)
+            }
+            Err(e) => {
+                log::error!("Compilation task panicked: {}", e);
+                (format!("Compilation task panicked: {}", e), false)
+            }
+        };
+
+        match state.lock() {
+            Ok(mut state) => state.finish(message, success),
+            Err(poisoned) => poisoned.into_inner().finish(message, success),
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// 等待进行中的编译结束，最多等待 `timeout`；编译已结束（或没有编译）时返回 true
+pub async fn wait_for_compile(state: &web::Data<Mutex<CompilerState>>, timeout: std::time::Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let compiling = match state.lock() {
+            Ok(state) => state.is_compiling,
+            Err(poisoned) => poisoned.into_inner().is_compiling,
+        };
+        if !compiling {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        actix_web::rt::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+/// 模板目录的指纹与上次全量编译时不同（或从未记录）时返回 true
+pub fn templates_changed(config: &Config) -> bool {
+    let compiled = get_storage(config)
+        .ok()
+        .and_then(|storage| storage.get_template_fingerprint().ok().flatten());
+    compiled.as_deref() != Some(template_fingerprint(&config.paths.templates_dir).as_str())
+}
+
+/// 开启 `server.autoreload_templates` 时，模板改动后在后台启动一次全量编译；
+/// 当前请求仍返回已编译的页面
+pub fn reload_templates_if_changed(config: &Config, state: Option<&web::Data<Mutex<CompilerState>>>) {
+    let Some(state) = state.filter(|_| config.server.autoreload_templates) else {
+        return;
+    };
+    if !templates_changed(config) {
+        return;
+    }
+    if let Ok(job_id) = start_background_compile(config.clone(), state.clone()) {
+        log::info!("Templates changed, recompiling the site (job {})", job_id);
+    }
+}
+
+/// 登录失败次数上限，超过后锁定
+const MAX_LOGIN_FAILURES: u32 = 5;
+/// 锁定时长（秒）
+const LOGIN_LOCKOUT_SECS: u64 = 60;
+/// 最后一次失败超过这么久（秒）且未锁定时，失败次数作废
+const LOGIN_FAILURE_WINDOW_SECS: u64 = 15 * 60;
+
+/// 按 IP 记录的登录失败状态
+#[derive(Debug, Default)]
+struct LoginAttempts {
+    failures: u32,
+    last_failure: Option<std::time::Instant>,
+    locked_until: Option<std::time::Instant>,
+}
+
+impl LoginAttempts {
+    /// 锁定已到期，或未锁定且失败记录已超出有效期
+    fn is_expired(&self, now: std::time::Instant) -> bool {
+        match self.locked_until {
+            Some(until) => until <= now,
+            None => self.last_failure.is_none_or(|last| {
+                now.saturating_duration_since(last) >= std::time::Duration::from_secs(LOGIN_FAILURE_WINDOW_SECS)
+            }),
+        }
+    }
+}
+
+/// 登录限流器
+#[derive(Debug, Default)]
+pub struct LoginThrottle {
+    attempts: Mutex<HashMap<String, LoginAttempts>>,
+}
+
+impl LoginThrottle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 若 IP 处于锁定中，返回剩余秒数
+    pub fn locked_for(&self, ip: &str) -> Option<u64> {
+        let attempts = self.attempts.lock().ok()?;
+        let locked_until = attempts.get(ip)?.locked_until?;
+        let remaining = locked_until.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            None
+        } else {
+            Some(remaining.as_secs().max(1))
+        }
+    }
+
+    /// 记录一次失败，达到上限时开始锁定
+    pub fn record_failure(&self, ip: &str) {
+        self.record_failure_at(ip, std::time::Instant::now());
+    }
+
+    fn record_failure_at(&self, ip: &str, now: std::time::Instant) {
+        let Ok(mut attempts) = self.attempts.lock() else {
+            return;
+        };
+        // 顺便清理已过期的记录（包括本 IP 的，之后重新计数），避免表无限增长
+        attempts.retain(|_, entry| !entry.is_expired(now));
+        let entry = attempts.entry(ip.to_string()).or_default();
+        entry.failures += 1;
+        entry.last_failure = Some(now);
+        log::warn!("Failed admin login from {} ({} consecutive)", ip, entry.failures);
+        if entry.failures >= MAX_LOGIN_FAILURES {
+            entry.locked_until = Some(now + std::time::Duration::from_secs(LOGIN_LOCKOUT_SECS));
+            log::warn!("Admin login locked for {} for {}s", ip, LOGIN_LOCKOUT_SECS);
+        }
+    }
+
+    /// 登录成功后清除记录
+    pub fn record_success(&self, ip: &str) {
+        if let Ok(mut attempts) = self.attempts.lock() {
+            attempts.remove(ip);
         }
     }
 }
 
-/// 验证管理员密码
+/// 生成管理员密码的 argon2 哈希
+pub fn hash_admin_password(password: &str) -> Result<String, Box<dyn std::error::Error>> {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2::Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| e.to_string())?;
+    Ok(hash.to_string())
+}
+
+/// 常量时间比较
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// 验证管理员密码（优先使用哈希）
 fn verify_admin_password(config: &Config, password: &str) -> bool {
-    password == config.server.admin_password
+    if let Some(hash) = config.server.admin_password_hash.as_deref().filter(|h| !h.is_empty()) {
+        return verify_password_hash(hash, password);
+    }
+
+    !config.server.admin_password.is_empty()
+        && constant_time_eq(password.as_bytes(), config.server.admin_password.as_bytes())
+}
+
+/// 用 argon2 哈希验证密码
+fn verify_password_hash(hash: &str, password: &str) -> bool {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    match PasswordHash::new(hash) {
+        Ok(parsed) => argon2::Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(e) => {
+            log::error!("Invalid admin_password_hash in config: {}", e);
+            false
+        }
+    }
+}
+
+/// 检查新密码的长度和两次输入是否一致
+pub fn validate_new_password(new_password: &str, confirm_password: &str) -> Result<(), BlogError> {
+    if new_password.chars().count() < MIN_ADMIN_PASSWORD_LEN {
+        return Err(BlogError::Validation(format!("新密码至少需要 {} 个字符", MIN_ADMIN_PASSWORD_LEN)));
+    }
+    if new_password != confirm_password {
+        return Err(BlogError::Validation("两次输入的新密码不一致".to_string()));
+    }
+    Ok(())
+}
+
+/// 读取 `config_path` 的配置文件，修改后写回，返回修改后（已应用环境变量覆盖）的配置。
+/// 写回的只有文件中的配置，环境变量中的值不会写进文件
+fn update_config_file(config_path: &Path, update: impl FnOnce(&mut Config)) -> Result<Config, BlogError> {
+    let mut config = Config::load_file(config_path).map_err(|e| BlogError::Internal(e.to_string()))?;
+    update(&mut config);
+    config.save_to(config_path).map_err(|e| BlogError::Internal(e.to_string()))?;
+    config.apply_env_overrides();
+    Ok(config)
+}
+
+/// 把新密码的哈希写入 `config_path` 的配置文件（同时清除明文密码），
+/// 并注销所有管理员会话，返回新的哈希
+pub fn set_admin_password(config_path: &Path, new_password: &str) -> Result<String, BlogError> {
+    let hash = hash_admin_password(new_password).map_err(|e| BlogError::Internal(e.to_string()))?;
+    let config = update_config_file(config_path, |config| {
+        config.server.admin_password_hash = Some(hash.clone());
+        config.server.admin_password = String::new();
+    })?;
+
+    let removed = StorageDB::new(&config.paths.storage_db.to_string_lossy())?.delete_all_admin_sessions()?;
+    log::info!("Admin password changed, {} sessions signed out", removed);
+    Ok(hash)
+}
+
+/// API 密钥的前缀，便于在日志和配置中辨认
+const API_KEY_PREFIX: &str = "lfb_";
+/// 撤销 API 密钥时哈希前缀的最短长度
+const MIN_API_KEY_ID_LEN: usize = 8;
+/// 列表中展示的哈希前缀长度
+const API_KEY_ID_LEN: usize = 12;
+
+/// 生成新的 API 密钥，返回明文和写入配置的 SHA-256
+pub fn generate_api_key() -> (String, String) {
+    let key = format!("{}{}", API_KEY_PREFIX, generate_token());
+    let hash = hash_token(&key);
+    (key, hash)
+}
+
+/// 生成 API 密钥并把哈希追加到 `config_path` 的配置文件，返回明文和哈希
+pub fn add_api_key(config_path: &Path) -> Result<(String, String), BlogError> {
+    let (key, hash) = generate_api_key();
+    update_config_file(config_path, |config| config.server.api_keys.push(hash.clone()))?;
+    Ok((key, hash))
+}
+
+/// 检查 API 密钥的哈希是否在 `hashes` 中
+fn api_key_matches(hashes: &[String], key: &str) -> bool {
+    let hash = hash_token(key);
+    // 逐个比较完，不因提前命中泄露位置
+    hashes
+        .iter()
+        .fold(false, |found, stored| constant_time_eq(stored.as_bytes(), hash.as_bytes()) | found)
+}
+
+/// 运行中生效的管理员密码和 API 密钥。在后台修改后写回配置文件并更新这里，无需重启服务
+pub struct AdminCredentials {
+    config_path: PathBuf,
+    password_hash: RwLock<Option<String>>, // 启动后修改过的密码哈希，为空时使用配置中的密码
+    api_keys: RwLock<Vec<String>>,
+}
+
+impl AdminCredentials {
+    pub fn new(config_path: impl Into<PathBuf>, config: &Config) -> Self {
+        Self {
+            config_path: config_path.into(),
+            password_hash: RwLock::new(None),
+            api_keys: RwLock::new(config.server.api_keys.clone()),
+        }
+    }
+
+    fn api_key_matches(&self, key: &str) -> bool {
+        self.api_keys.read().is_ok_and(|hashes| api_key_matches(&hashes, key))
+    }
+
+    /// 生成 API 密钥，写入配置文件后立即生效，返回明文和哈希
+    fn add_api_key(&self) -> Result<(String, String), BlogError> {
+        let mut api_keys = self.api_keys.write().map_err(|e| BlogError::Internal(e.to_string()))?;
+        let (key, hash) = add_api_key(&self.config_path)?;
+        api_keys.push(hash.clone());
+        Ok((key, hash))
+    }
+
+    /// 撤销哈希以 `prefix` 开头的唯一一个 API 密钥，返回它的哈希
+    fn revoke_api_key(&self, prefix: &str) -> Result<String, BlogError> {
+        if prefix.len() < MIN_API_KEY_ID_LEN {
+            return Err(BlogError::Validation(format!("密钥前缀至少需要 {} 个字符", MIN_API_KEY_ID_LEN)));
+        }
+        let mut api_keys = self.api_keys.write().map_err(|e| BlogError::Internal(e.to_string()))?;
+        let matches: Vec<&String> = api_keys.iter().filter(|hash| hash.starts_with(prefix)).collect();
+        let hash = match matches.as_slice() {
+            [] => return Err(BlogError::NotFound("API 密钥不存在".to_string())),
+            [hash] => (*hash).clone(),
+            _ => return Err(BlogError::Validation("前缀匹配到多个密钥，请提供更长的前缀".to_string())),
+        };
+        update_config_file(&self.config_path, |config| config.server.api_keys.retain(|stored| *stored != hash))?;
+        api_keys.retain(|stored| *stored != hash);
+        Ok(hash)
+    }
+
+    /// 验证管理员密码，启动后修改过密码时只接受新密码
+    fn verify(&self, config: &Config, password: &str) -> bool {
+        match self.password_hash.read().ok().and_then(|hash| hash.clone()) {
+            Some(hash) => verify_password_hash(&hash, password),
+            None => verify_admin_password(config, password),
+        }
+    }
+
+    fn change_password(&self, new_password: &str) -> Result<(), BlogError> {
+        let hash = set_admin_password(&self.config_path, new_password)?;
+        if let Ok(mut current) = self.password_hash.write() {
+            *current = Some(hash);
+        }
+        Ok(())
+    }
 }
 
-/// 生成简单的会话令牌
+/// 生成随机会话令牌
 fn generate_token() -> String {
-    let uuid = Uuid::new_v4().to_string();
-    let timestamp = chrono::Utc::now().timestamp();
-    format!("{}_{}", uuid, timestamp)
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// 会话令牌哈希（数据库中只保存哈希）
+fn hash_token(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// 创建管理员会话，返回令牌
+fn create_admin_session(config: &Config) -> Result<String, Box<dyn std::error::Error>> {
+    let token = generate_token();
+    let expires_at = Utc::now().timestamp() + config.server.session_lifetime_hours * 3600;
+    let mut storage = StorageDB::new(&config.paths.storage_db.to_string_lossy())?;
+    storage.create_admin_session(&hash_token(&token), expires_at, config.server.max_sessions)?;
+    Ok(token)
+}
+
+/// 构建会话 cookie
+fn session_cookie<'a>(config: &Config, token: &'a str) -> actix_web::cookie::Cookie<'a> {
+    actix_web::cookie::Cookie::build("admin_token", token)
+        .path("/")
+        .http_only(true)
+        .same_site(actix_web::cookie::SameSite::Lax)
+        .max_age(actix_web::cookie::time::Duration::hours(config.server.session_lifetime_hours))
+        .finish()
 }
 
 /// 管理员登录
+#[utoipa::path(
+    post,
+    path = "/api/admin/login",
+    tag = "auth",
+    summary = "管理员登录，成功后设置 admin_token Cookie",
+    request_body(content = LoginRequest, example = json!({"password": "correct horse battery"})),
+    responses((status = 200, description = "成功", body = LoginResponse, example = json!({"success": true, "message": "登录成功", "token": "3f2a9c0e8b7d4f1a2c3e4d5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b"})))
+)]
 pub async fn admin_login(
+    req: actix_web::HttpRequest,
     body: web::Json<LoginRequest>,
     config: web::Data<Config>,
+    throttle: web::Data<LoginThrottle>,
+    credentials: web::Data<AdminCredentials>,
 ) -> impl Responder {
     let request = body.into_inner();
+    let ip = crate::ip_filter::client_ip(&req);
 
-    if verify_admin_password(&config, &request.password) {
-        let token = generate_token();
-        HttpResponse::Ok().json(LoginResponse {
-            success: true,
-            message: "登录成功".to_string(),
-            token: Some(token),
-        })
-    } else {
-        HttpResponse::Unauthorized().json(LoginResponse {
+    if let Some(secs) = throttle.locked_for(&ip) {
+        return HttpResponse::TooManyRequests().json(LoginResponse {
+            success: false,
+            message: format!("登录失败次数过多，请 {} 秒后再试", secs),
+            token: None,
+        });
+    }
+
+    if !credentials.verify(&config, &request.password) {
+        throttle.record_failure(&ip);
+        return HttpResponse::Unauthorized().json(LoginResponse {
             success: false,
             message: "密码错误".to_string(),
             token: None,
-        })
+        });
+    }
+    throttle.record_success(&ip);
+
+    match create_admin_session(&config) {
+        Ok(token) => HttpResponse::Ok()
+            .cookie(session_cookie(&config, &token))
+            .json(LoginResponse {
+                success: true,
+                message: "登录成功".to_string(),
+                token: Some(token.clone()),
+            }),
+        Err(e) => HttpResponse::InternalServerError().json(LoginResponse {
+            success: false,
+            message: format!("创建会话失败: {}", e),
+            token: None,
+        }),
     }
 }
 
-/// 验证管理员令牌
-fn verify_admin_token(config: &Config, token: &str) -> bool {
-    // 简单验证：令牌格式正确且未过期（24小时内）
-    if !token.contains('_') {
-        return false;
-    }
-    let parts: Vec<&str> = token.split('_').collect();
-    if parts.len() != 2 {
-        return false;
+/// 修改密码请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    pub confirm_password: String,
+}
+
+/// 修改管理员密码：新密码写入配置文件，所有会话失效，需要重新登录
+#[utoipa::path(
+    post,
+    path = "/api/admin/password",
+    tag = "auth",
+    summary = "修改管理员密码，所有会话随之失效",
+    request_body(content = ChangePasswordRequest, example = json!({"current_password": "correct horse battery", "new_password": "new horse battery", "confirm_password": "new horse battery"})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "密码已修改，请使用新密码重新登录"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn change_admin_password(
+    req: actix_web::HttpRequest,
+    body: web::Json<ChangePasswordRequest>,
+    config: web::Data<Config>,
+    throttle: web::Data<LoginThrottle>,
+    credentials: web::Data<AdminCredentials>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let request = body.into_inner();
+    let ip = crate::ip_filter::client_ip(&req);
+
+    // 当前密码错误与登录失败一起计数，防止借已登录的会话猜测密码
+    if let Some(secs) = throttle.locked_for(&ip) {
+        return Err(BlogError::TooManyRequests(format!("密码错误次数过多，请 {} 秒后再试", secs), secs));
     }
-    // 验证时间戳（24小时有效期）
-    if let Ok(timestamp) = parts[1].parse::<i64>() {
-        let now = chrono::Utc::now().timestamp();
-        let diff = now - timestamp;
-        return diff < 86400 && diff > -86400; // 24小时窗口
+    if !credentials.verify(&config, &request.current_password) {
+        throttle.record_failure(&ip);
+        return Err(BlogError::Forbidden("当前密码错误".to_string()));
     }
-    false
+    throttle.record_success(&ip);
+    validate_new_password(&request.new_password, &request.confirm_password)?;
+
+    let credentials = credentials.into_inner();
+    let new_password = request.new_password;
+    web::block(move || credentials.change_password(&new_password))
+        .await
+        .map_err(|e| BlogError::Internal(e.to_string()))??;
+
+    Ok(HttpResponse::Ok()
+        .cookie(
+            actix_web::cookie::Cookie::build("admin_token", "")
+                .path("/")
+                .max_age(actix_web::cookie::time::Duration::seconds(0))
+                .finish(),
+        )
+        .json(serde_json::json!({
+            "status": "success",
+            "message": "密码已修改，请使用新密码重新登录"
+        })))
+}
+
+/// 生成 API 密钥，明文只在响应中出现这一次
+#[utoipa::path(
+    post,
+    path = "/api/admin/api-keys",
+    tag = "auth",
+    summary = "生成 API 密钥，明文只返回这一次",
+    responses((status = 201, description = "已创建", body = serde_json::Value, example = json!({"status": "success", "message": "API 密钥已生成，请立即保存，之后无法再次查看", "key": "lfb_3f2a9c0e8b7d4f1a2c3e4d5f6a7b8c9d0e1f2a3b4c5d6e7f8a9b0c1d2e3f4a5b", "id": "9f86d081884c"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn create_api_key(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    credentials: web::Data<AdminCredentials>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let credentials = credentials.into_inner();
+    let (key, hash) = web::block(move || credentials.add_api_key())
+        .await
+        .map_err(|e| BlogError::Internal(e.to_string()))??;
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "status": "success",
+        "message": "API 密钥已生成，请立即保存，之后无法再次查看",
+        "key": key,
+        "id": &hash[..API_KEY_ID_LEN]
+    })))
+}
+
+/// 列出 API 密钥（只返回哈希前缀，用于撤销）
+#[utoipa::path(
+    get,
+    path = "/api/admin/api-keys",
+    tag = "auth",
+    summary = "列出 API 密钥（只返回哈希前缀）",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "keys": ["9f86d081884c"]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn list_api_keys(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    credentials: web::Data<AdminCredentials>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let ids: Vec<String> = credentials
+        .api_keys
+        .read()
+        .map_err(|e| BlogError::Internal(e.to_string()))?
+        .iter()
+        .map(|hash| hash.chars().take(API_KEY_ID_LEN).collect())
+        .collect();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "keys": ids
+    })))
+}
+
+/// 按哈希前缀撤销 API 密钥
+#[utoipa::path(
+    delete,
+    path = "/api/admin/api-keys/{prefix}",
+    tag = "auth",
+    summary = "按哈希前缀撤销 API 密钥",
+    params(("prefix" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "API 密钥已撤销", "id": "9f86d081884c"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn revoke_api_key(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+    credentials: web::Data<AdminCredentials>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let prefix = path.into_inner().to_ascii_lowercase();
+    let credentials = credentials.into_inner();
+    let hash = web::block(move || credentials.revoke_api_key(&prefix))
+        .await
+        .map_err(|e| BlogError::Internal(e.to_string()))??;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "API 密钥已撤销",
+        "id": &hash[..API_KEY_ID_LEN.min(hash.len())]
+    })))
+}
+
+/// 验证管理员令牌
+fn verify_admin_token(config: &Config, token: &str) -> bool {
+    StorageDB::new(&config.paths.storage_db.to_string_lossy())
+        .and_then(|storage| storage.is_admin_session_valid(&hash_token(token)))
+        .unwrap_or(false)
 }
 
 /// 获取站点概览
+#[utoipa::path(
+    get,
+    path = "/api/admin/overview",
+    tag = "admin",
+    summary = "后台概览",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "overview": {"total_categories": 3, "total_items": 12, "total_attachments": 20, "last_compiled": "2024-05-01 10:00:00", "total_comments": 5}}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn get_admin_overview(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
     // 验证管理员身份
     if !require_auth(&req, &config) {
@@ -157,7 +773,7 @@ pub async fn get_admin_overview(req: actix_web::HttpRequest, config: web::Data<C
     }
     let storage_path = config
         .paths
-        .storage_database_path
+        .storage_db
         .to_string_lossy()
         .to_string();
 
@@ -168,7 +784,7 @@ pub async fn get_admin_overview(req: actix_web::HttpRequest, config: web::Data<C
                     let last_compiled = storage.get_last_compiled().ok().flatten();
 
                     // 获取评论统计
-                    let comments_path = config.paths.storage_database_path.to_string_lossy().to_string();
+                    let comments_path = config.paths.comments_db.to_string_lossy().to_string();
                     let comment_stats = crate::comments::CommentDB::new(&comments_path)
                         .and_then(|db| db.get_comment_stats());
 
@@ -200,6 +816,14 @@ pub async fn get_admin_overview(req: actix_web::HttpRequest, config: web::Data<C
 }
 
 /// 获取所有分类
+#[utoipa::path(
+    get,
+    path = "/api/admin/categories",
+    tag = "admin",
+    summary = "列出分类",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "categories": [{"slug": "wine", "title": "Wine", "description": "Wines I have tried", "item_count": 4}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn get_categories(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
     // 验证管理员身份
     if !require_auth(&req, &config) {
@@ -210,7 +834,7 @@ pub async fn get_categories(req: actix_web::HttpRequest, config: web::Data<Confi
     }
     let storage_path = config
         .paths
-        .storage_database_path
+        .storage_db
         .to_string_lossy()
         .to_string();
 
@@ -251,8 +875,17 @@ pub async fn get_categories(req: actix_web::HttpRequest, config: web::Data<Confi
     }
 }
 
-/// 获取所有文章（包括草稿）
-pub async fn get_items(req: actix_web::HttpRequest, config: web::Data<Config>, query: web::Query<Option<bool>>) -> impl Responder {
+/// 获取文章列表：`?drafts=true` 时包括草稿，可按 `category` 过滤并用 `page`/`per_page` 分页
+#[utoipa::path(
+    get,
+    path = "/api/admin/items",
+    tag = "admin",
+    summary = "分页列出文章",
+    params(("drafts" = Option<bool>, Query, description = "一并列出草稿"), ("category" = Option<String>, Query, description = "只列出指定分类"), ("page" = Option<u32>, Query, description = "页码，从 1 开始"), ("per_page" = Option<u32>, Query, description = "每页条数")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "items": [{"slug": "wine-merlot", "title": "Merlot", "category": "wine", "is_draft": false, "updated_at": "2024-05-01 10:00:00"}], "count": 1, "total": 1, "page": 1, "per_page": 20}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_items(req: actix_web::HttpRequest, config: web::Data<Config>, query: web::Query<ItemsQuery>) -> impl Responder {
     // 验证管理员身份
     if !require_auth(&req, &config) {
         return HttpResponse::Unauthorized().json(serde_json::json!({
@@ -260,27 +893,30 @@ pub async fn get_items(req: actix_web::HttpRequest, config: web::Data<Config>, q
             "message": "未授权访问，请先登录"
         }));
     }
-    let show_drafts = query.into_inner().unwrap_or(false);
+    let query = query.into_inner();
+    let show_drafts = query.drafts.unwrap_or(false);
+    let category = query.category.as_deref().map(str::trim).filter(|c| !c.is_empty());
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_ITEMS_PER_PAGE).clamp(1, MAX_ITEMS_PER_PAGE);
     let storage_path = config
         .paths
-        .storage_database_path
+        .storage_db
         .to_string_lossy()
         .to_string();
 
     match StorageDB::new(&storage_path) {
         Ok(storage) => {
-            let all_pages = storage.get_all_pages().unwrap_or_default();
-            let items: Vec<serde_json::Value> = all_pages
+            let (pages, total) = match storage.list_items(category, show_drafts, per_page, (page - 1) * per_page) {
+                Ok(result) => result,
+                Err(e) => {
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
+                        "status": "error",
+                        "message": e.to_string()
+                    }));
+                }
+            };
+            let items: Vec<serde_json::Value> = pages
                 .iter()
-                .filter(|page| page.page_type == PageType::Item)
-                .filter(|page| {
-                    if show_drafts {
-                        true
-                    } else {
-                        // 默认不显示草稿（通过 slug 中的 _draft 后缀判断）
-                        !page.slug.ends_with("_draft")
-                    }
-                })
                 .map(|page| {
                     // 检查是否是草稿
                     let is_draft = page.slug.ends_with("_draft");
@@ -299,7 +935,10 @@ pub async fn get_items(req: actix_web::HttpRequest, config: web::Data<Config>, q
             HttpResponse::Ok().json(serde_json::json!({
                 "status": "success",
                 "items": items,
-                "count": items.len()
+                "count": items.len(),
+                "total": total,
+                "page": page,
+                "per_page": per_page
             }))
         }
         Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
@@ -310,6 +949,15 @@ pub async fn get_items(req: actix_web::HttpRequest, config: web::Data<Config>, q
 }
 
 /// 获取单篇文章
+#[utoipa::path(
+    get,
+    path = "/api/admin/items/{slug}",
+    tag = "admin",
+    summary = "读取文章",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "item": {"slug": "wine-merlot", "title": "Merlot", "content": "A soft red wine.", "category": "wine", "is_draft": false, "updated_at": "2024-05-01 10:00:00"}}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn get_item(
     req: actix_web::HttpRequest,
     path: web::Path<String>,
@@ -325,7 +973,7 @@ pub async fn get_item(
     let slug = path.into_inner();
     let storage_path = config
         .paths
-        .storage_database_path
+        .storage_db
         .to_string_lossy()
         .to_string();
 
@@ -363,50 +1011,27 @@ pub async fn get_item(
     }
 }
 
-/// 创建分类
-pub async fn create_category(
-    req: actix_web::HttpRequest,
-    body: web::Json<CreateCategoryRequest>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
+/// 创建分类目录和 index.md 并生成分类页，返回分类 slug
+fn add_category(config: &Config, name: &str, description: Option<&str>) -> Result<String, BlogError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(BlogError::Validation("分类名称不能为空".to_string()));
     }
-    let request = body.into_inner();
-    let slug = request.name.to_lowercase().replace(' ', "-");
-
-    let storage_path = config
-        .paths
-        .storage_database_path
-        .to_string_lossy()
-        .to_string();
-
-    match StorageDB::new(&storage_path) {
-        Ok(storage) => {
-            // 检查分类是否已存在
-            if storage.get_page(&slug).unwrap_or(None).is_some() {
-                return HttpResponse::BadRequest().json(serde_json::json!({
-                    "status": "error",
-                    "message": "分类已存在"
-                }));
-            }
+    let slug = crate::slug::slugify(name);
+    let storage = get_storage(config)?;
 
-            // 创建分类目录
-            let category_dir = config.paths.content_dir.join(&request.name);
-            if let Err(e) = fs::create_dir_all(&category_dir) {
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "status": "error",
-                    "message": format!("创建分类目录失败: {}", e)
-                }));
-            }
+    // 检查分类是否已存在（目录名即 slug，分类 URL 与之一致）
+    let category_dir = config.paths.content_dir.join(&slug);
+    if category_dir.exists() || storage.get_page(&slug).unwrap_or(None).is_some() {
+        return Err(BlogError::Validation("分类已存在".to_string()));
+    }
+    fs::create_dir_all(&category_dir)
+        .map_err(|e| BlogError::Internal(format!("创建分类目录失败: {}", e)))?;
 
-            // 创建 index.md
-            let index_content = format!(
-                r#"---
+    // 创建 index.md，显示名称保存在 title 中
+    let index_content = format!(
+        r#"---
+title: {}
 description: {}
 ---
 
@@ -414,136 +1039,133 @@ description: {}
 
 这里是 {} 的介绍内容。
 "#,
-                request.description.clone().unwrap_or_default(),
-                request.name,
-                request.name
-            );
-            let index_path = category_dir.join("index.md");
-            if let Err(e) = fs::write(&index_path, &index_content) {
-                return HttpResponse::InternalServerError().json(serde_json::json!({
-                    "status": "error",
-                    "message": format!("创建分类索引文件失败: {}", e)
-                }));
-            }
-
-            // 生成 HTML
-            let mut scanner = Scanner::new(config.paths.content_dir.clone());
-            let site_content = match scanner.scan() {
-                Ok(content) => content,
-                Err(e) => {
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("扫描内容失败: {}", e)
-                    }));
-                }
-            };
+        yaml_string(name),
+        description.unwrap_or_default(),
+        name,
+        name
+    );
+    fs::write(category_dir.join("index.md"), &index_content)
+        .map_err(|e| BlogError::Internal(format!("创建分类索引文件失败: {}", e)))?;
+
+    // 生成分类页
+    let mut scanner = Scanner::new(config.paths.content_dir.clone())
+        .with_markdown(config.markdown.clone())
+        .with_publish_dates(config.site.future_posts, config.site_timezone());
+    let site_content = scanner.scan()?;
+    if let Some(category) = site_content.categories.iter().find(|c| c.url == slug) {
+        let renderer = crate::templates::TemplateRenderer::new(config)?;
+        let category_html = renderer.render_category(category)?;
+
+        let category_page = Page {
+            id: Uuid::new_v4().to_string(),
+            slug: slug.clone(),
+            page_type: PageType::Category,
+            title: category.title.clone(),
+            content: category_html,
+            category: None,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        storage.save_page(&category_page)?;
+    }
 
-            // 找到新创建的分类
-            if let Some(category) = site_content.categories.iter().find(|c| c.url == slug) {
-                let renderer = match crate::templates::TemplateRenderer::new(&config) {
-                    Ok(r) => r,
-                    Err(e) => {
-                        return HttpResponse::InternalServerError().json(serde_json::json!({
-                            "status": "error",
-                            "message": format!("创建模板渲染器失败: {}", e)
-                        }));
-                    }
-                };
+    Ok(slug)
+}
 
-                let category_html = match renderer.render_category(category) {
-                    Ok(html) => html,
-                    Err(e) => {
-                        return HttpResponse::InternalServerError().json(serde_json::json!({
-                            "status": "error",
-                            "message": format!("渲染分类页面失败: {}", e)
-                        }));
-                    }
-                };
+/// 删除分类目录及数据库中的分类页和文章页
+fn remove_category(config: &Config, slug: &str) -> Result<(), BlogError> {
+    if !is_plain_name(slug) {
+        return Err(BlogError::Validation("无效的分类 slug".to_string()));
+    }
+    let storage = get_storage(config)?;
+    let category_dir = config.paths.content_dir.join(slug);
+    if !category_dir.exists() && storage.get_page(slug).unwrap_or(None).is_none() {
+        return Err(BlogError::NotFound("分类不存在".to_string()));
+    }
 
-                let category_page = Page {
-                    id: Uuid::new_v4().to_string(),
-                    slug: slug.clone(),
-                    page_type: PageType::Category,
-                    title: request.name.clone(),
-                    content: category_html,
-                    category: None,
-                    updated_at: chrono::Utc::now().to_rfc3339(),
-                };
+    delete_category_pages(&storage, slug);
+    if category_dir.exists() {
+        fs::remove_dir_all(&category_dir)
+            .map_err(|e| BlogError::Internal(format!("删除分类目录失败: {}", e)))?;
+    }
+    Ok(())
+}
 
-                if let Err(e) = storage.save_page(&category_page) {
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("保存分类页面失败: {}", e)
-                    }));
-                }
-            }
+/// 创建分类
+#[utoipa::path(
+    post,
+    path = "/api/admin/categories",
+    tag = "admin",
+    summary = "创建分类",
+    request_body(content = CreateCategoryRequest, example = json!({"name": "Wine", "description": "Wines I have tried"})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "分类创建成功", "slug": "wine"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn create_category(
+    req: actix_web::HttpRequest,
+    body: web::Json<CreateCategoryRequest>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let request = body.into_inner();
+    let slug = add_category(&config, &request.name, request.description.as_deref())?;
 
-            HttpResponse::Ok().json(serde_json::json!({
-                "status": "success",
-                "message": "分类创建成功",
-                "slug": slug
-            }))
-        }
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": e.to_string()
-        })),
-    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "分类创建成功",
+        "slug": slug
+    })))
 }
 
 /// 删除分类
+#[utoipa::path(
+    delete,
+    path = "/api/admin/categories/{slug}",
+    tag = "admin",
+    summary = "删除分类",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "分类删除成功"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn delete_category(
     req: actix_web::HttpRequest,
     path: web::Path<String>,
     config: web::Data<Config>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
-    }
-    let slug = path.into_inner();
-    let storage_path = config
-        .paths
-        .storage_database_path
-        .to_string_lossy()
-        .to_string();
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    remove_category(&config, &path.into_inner())?;
 
-    match StorageDB::new(&storage_path) {
-        Ok(storage) => {
-            // 删除数据库中的页面
-            storage.delete_page(&slug).ok();
-
-            // 删除文件系统中的内容
-            let category_dir = config.paths.content_dir.join(&slug);
-            if category_dir.exists() {
-                if let Err(e) = fs::remove_dir_all(&category_dir) {
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("删除分类目录失败: {}", e)
-                    }));
-                }
-            }
-
-            HttpResponse::Ok().json(serde_json::json!({
-                "status": "success",
-                "message": "分类删除成功"
-            }))
-        }
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": e.to_string()
-        })),
-    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "分类删除成功"
+    })))
 }
 
-/// 创建文章
-pub async fn create_item(
+/// 重命名分类：按新名称重新生成 slug，移动目录并更新 index.md 的 title，
+/// 删除旧 slug 下的页面后重新编译；旧 URL 会登记为永久重定向
+#[utoipa::path(
+    post,
+    path = "/api/admin/categories/{slug}/rename",
+    tag = "admin",
+    summary = "重命名分类，旧地址自动重定向",
+    params(("slug" = String, Path)),
+    request_body(content = RenameCategoryRequest, example = json!({"name": "Red Wine"})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "分类重命名成功，正在重新编译", "slug": "red-wine", "moved": [{"from": "/wine/merlot", "to": "/red-wine/merlot"}], "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn rename_category(
     req: actix_web::HttpRequest,
-    body: web::Json<CreateItemRequest>,
+    path: web::Path<String>,
+    body: web::Json<RenameCategoryRequest>,
     config: web::Data<Config>,
+    state: web::Data<Mutex<CompilerState>>,
+    redirects: Option<web::Data<RedirectStore>>,
 ) -> impl Responder {
     // 验证管理员身份
     if !require_auth(&req, &config) {
@@ -552,162 +1174,170 @@ pub async fn create_item(
             "message": "未授权访问，请先登录"
         }));
     }
-    let request = body.into_inner();
-
-    // 验证分类存在
-    let category_dir = config.paths.content_dir.join(&request.category);
-    if !category_dir.exists() {
+    let old_slug = path.into_inner();
+    let name = body.into_inner().name.trim().to_string();
+    if name.is_empty() {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "status": "error",
-            "message": "分类不存在"
+            "message": "分类名称不能为空"
         }));
     }
 
-    // 生成 slug
-    let slug = format!("{}-{}", request.category, request.item_name);
-
-    // 如果是草稿，添加 _draft 后缀
-    let is_draft = request.is_draft.unwrap_or(false);
-    let slug = if is_draft {
-        format!("{}_draft", slug)
-    } else {
-        slug
-    };
+    let old_dir = config.paths.content_dir.join(&old_slug);
+    if !is_plain_name(&old_slug) || !old_dir.is_dir() {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "分类不存在"
+        }));
+    }
 
-    // 创建文章目录
-    let item_dir = category_dir.join(&request.item_name);
-    if let Err(e) = fs::create_dir_all(&item_dir) {
-        return HttpResponse::InternalServerError().json(serde_json::json!({
+    let new_slug = crate::slug::slugify(&name);
+    let new_dir = config.paths.content_dir.join(&new_slug);
+    if new_slug != old_slug && new_dir.exists() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
             "status": "error",
-            "message": format!("创建文章目录失败: {}", e)
+            "message": "分类已存在"
         }));
     }
 
-    // 构建 Markdown 内容
-    let frontmatter = format!(
-        r#"---
-title: {}
-date: {}
-author: {}
-description: {}
-tags: {}
-draft: {}
----
+    let storage = match get_storage(&config) {
+        Ok(storage) => storage,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }));
+        }
+    };
 
-{}
-"#,
-        request.title,
-        request.date.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string()),
-        request.author.unwrap_or_else(|| config.site.author.clone()),
-        request.description.unwrap_or_default(),
-        request.tags.clone().unwrap_or_default().join(", "),
-        if is_draft { "true" } else { "false" },
-        request.content
-    );
+    if new_slug != old_slug {
+        if let Err(e) = fs::rename(&old_dir, &new_dir) {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("移动分类目录失败: {}", e)
+            }));
+        }
+    }
 
-    // 写入 Markdown 文件
-    let md_path = item_dir.join(format!("{}.md", request.item_name));
-    if let Err(e) = fs::write(&md_path, &frontmatter) {
+    let index_path = new_dir.join("index.md");
+    let index_content = fs::read_to_string(&index_path).unwrap_or_default();
+    if let Err(e) = fs::write(&index_path, set_frontmatter_title(&index_content, &name)) {
         return HttpResponse::InternalServerError().json(serde_json::json!({
             "status": "error",
-            "message": format!("写入文章文件失败: {}", e)
+            "message": format!("更新分类索引文件失败: {}", e)
         }));
     }
 
-    // 创建 attachment 目录
-    let attachment_dir = item_dir.join("attachment");
-    if let Err(e) = fs::create_dir_all(&attachment_dir) {
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": format!("创建附件目录失败: {}", e)
-        }));
+    let mut moved: Vec<(String, String)> = Vec::new();
+    if new_slug != old_slug {
+        // 文章 slug 以 `{分类}-` 开头，换上新分类前缀即为新的 URL
+        moved = delete_category_pages(&storage, &old_slug)
+            .into_iter()
+            .filter_map(|slug| {
+                let to = format!("{}{}", new_slug, slug.strip_prefix(old_slug.as_str())?);
+                Some((slug, to))
+            })
+            .collect();
+        if let Some(store) = &redirects {
+            for (from, to) in &moved {
+                let rule = crate::redirects::RedirectRule {
+                    from: format!("/{}", from),
+                    to: format!("/{}", to),
+                    permanent: true,
+                };
+                if let Err(e) = store.upsert(rule) {
+                    log::warn!("登记分类重定向失败 {} -> {}: {}", from, to, e);
+                }
+            }
+        }
     }
 
+    // 已在编译时返回当前任务 ID，新 slug 会在下次编译时生效
+    let job_id = match start_background_compile(config.get_ref().clone(), state) {
+        Ok(job_id) | Err(job_id) => job_id,
+    };
+
     HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
-        "message": "文章创建成功",
-        "slug": slug
+        "message": "分类重命名成功，正在重新编译",
+        "slug": new_slug,
+        "moved": moved
+            .iter()
+            .map(|(from, to)| serde_json::json!({ "from": from, "to": to }))
+            .collect::<Vec<_>>(),
+        "job_id": job_id
     }))
 }
 
-/// 更新文章
-pub async fn update_item(
-    req: actix_web::HttpRequest,
-    path: web::Path<String>,
-    body: web::Json<UpdateItemRequest>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
+/// 删除分类页及其下的全部文章页（含草稿），返回被删除的非草稿页面 slug
+fn delete_category_pages(storage: &StorageDB, category: &str) -> Vec<String> {
+    let pages = storage.get_all_pages().unwrap_or_default();
+    let mut removed = Vec::new();
+    for page in pages {
+        let in_category = page.slug == category
+            || page.category.as_deref() == Some(category);
+        if !in_category {
+            continue;
+        }
+        storage.delete_page(&page.slug).ok();
+        if !page.slug.ends_with("_draft") {
+            removed.push(page.slug);
+        }
     }
-    let old_slug = path.into_inner();
-    let request = body.into_inner();
+    removed
+}
 
-    // 解析旧 slug 获取分类和文章名
-    let parts: Vec<&str> = old_slug.splitn(2, '-').collect();
-    if parts.len() < 2 {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "status": "error",
-            "message": "无效的文章 slug"
-        }));
-    }
+/// 把字符串写成 YAML 双引号标量（JSON 字符串即合法的 YAML）
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+}
 
-    let old_category = parts[0];
-    let old_item_name = if old_slug.ends_with("_draft") {
-        let without_draft = &old_slug[..old_slug.len() - 6];
-        let parts2: Vec<&str> = without_draft.splitn(2, '-').collect();
-        if parts2.len() >= 2 {
-            parts2[1]
-        } else {
-            parts[1]
-        }
-    } else {
-        parts[1]
+/// 设置 Markdown 头信息中的 title，没有头信息时补上
+fn set_frontmatter_title(content: &str, title: &str) -> String {
+    let title_line = format!("title: {}", yaml_string(title));
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return format!("---\n{}\n---\n\n{}", title_line, content);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return format!("---\n{}\n---\n\n{}", title_line, content);
     };
 
-    // 确定是否需要移动目录
-    let new_category = &request.category;
-    let new_item_name = &request.item_name;
-    let should_move = old_category != new_category || old_item_name != new_item_name;
+    let (frontmatter, body) = rest.split_at(end);
+    let mut lines: Vec<String> = frontmatter
+        .lines()
+        .filter(|line| !line.starts_with("title:"))
+        .map(str::to_string)
+        .collect();
+    lines.insert(0, title_line);
+    format!("---\n{}{}", lines.join("\n"), body)
+}
 
-    // 如果需要移动，先删除旧目录
-    if should_move {
-        let old_item_dir = config.paths.content_dir.join(old_category).join(old_item_name);
-        if old_item_dir.exists() {
-            fs::remove_dir_all(&old_item_dir).ok();
-        }
+/// 写入文章目录和 Markdown 文件，返回文章 slug（草稿带 `_draft` 后缀）
+fn write_item(
+    config: &Config,
+    category: &str,
+    item_name: &str,
+    fields: ItemFields,
+) -> Result<String, BlogError> {
+    if !is_plain_name(category) || !is_plain_name(item_name) {
+        return Err(BlogError::Validation("无效的分类或文章名".to_string()));
     }
 
     // 验证分类存在
-    let category_dir = config.paths.content_dir.join(new_category);
-    if !category_dir.exists() {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "status": "error",
-            "message": "分类不存在"
-        }));
+    let category_dir = config.paths.content_dir.join(category);
+    if !category_dir.is_dir() {
+        return Err(BlogError::Validation("分类不存在".to_string()));
     }
 
-    // 生成新 slug
-    let slug = format!("{}-{}", new_category, new_item_name);
-    let is_draft = request.is_draft.unwrap_or(false);
-    let slug = if is_draft {
-        format!("{}_draft", slug)
-    } else {
-        slug
-    };
+    // 生成 slug，草稿添加 _draft 后缀
+    let is_draft = fields.is_draft.unwrap_or(false);
+    let slug = format!("{}-{}", category, item_name);
+    let slug = if is_draft { format!("{}_draft", slug) } else { slug };
 
     // 创建文章目录
-    let item_dir = category_dir.join(new_item_name);
-    if let Err(e) = fs::create_dir_all(&item_dir) {
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": format!("创建文章目录失败: {}", e)
-        }));
-    }
+    let item_dir = category_dir.join(item_name);
+    fs::create_dir_all(&item_dir)
+        .map_err(|e| BlogError::Internal(format!("创建文章目录失败: {}", e)))?;
 
     // 构建 Markdown 内容
     let frontmatter = format!(
@@ -722,166 +1352,556 @@ draft: {}
 
 {}
 "#,
-        request.title,
-        request.date.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string()),
-        request.author.unwrap_or_else(|| config.site.author.clone()),
-        request.description.unwrap_or_default(),
-        request.tags.clone().unwrap_or_default().join(", "),
+        fields.title,
+        fields.date.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m-%d").to_string()),
+        fields.author.unwrap_or_else(|| config.site.author.clone()),
+        fields.description.unwrap_or_default(),
+        fields.tags.unwrap_or_default().join(", "),
         if is_draft { "true" } else { "false" },
-        request.content
+        fields.content
     );
 
     // 写入 Markdown 文件
-    let md_path = item_dir.join(format!("{}.md", new_item_name));
-    if let Err(e) = fs::write(&md_path, &frontmatter) {
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": format!("写入文章文件失败: {}", e)
-        }));
-    }
+    let md_path = item_dir.join(format!("{}.md", item_name));
+    fs::write(&md_path, &frontmatter)
+        .map_err(|e| BlogError::Internal(format!("写入文章文件失败: {}", e)))?;
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "文章更新成功",
-        "slug": slug
-    }))
+    Ok(slug)
 }
 
-/// 删除文章
-pub async fn delete_item(
-    req: actix_web::HttpRequest,
-    path: web::Path<String>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
-    }
-    let slug = path.into_inner();
+/// 新建和编辑文章共用的字段
+struct ItemFields {
+    title: String,
+    content: String,
+    date: Option<String>,
+    author: Option<String>,
+    description: Option<String>,
+    tags: Option<Vec<String>>,
+    is_draft: Option<bool>,
+}
 
-    // 解析 slug
-    let is_draft = slug.ends_with("_draft");
-    let clean_slug = if is_draft {
-        &slug[..slug.len() - 6]
-    } else {
-        &slug
-    };
+/// 创建文章及其附件目录，返回文章 slug
+fn add_item(config: &Config, request: CreateItemRequest) -> Result<String, BlogError> {
+    let CreateItemRequest { category, item_name, title, content, date, author, description, tags, is_draft } = request;
+    let fields = ItemFields { title, content, date, author, description, tags, is_draft };
+    let slug = write_item(config, &category, &item_name, fields)?;
 
-    let parts: Vec<&str> = clean_slug.splitn(2, '-').collect();
-    if parts.len() < 2 {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "status": "error",
-            "message": "无效的文章 slug"
-        }));
-    }
+    // 创建 attachment 目录
+    let attachment_dir = config.paths.content_dir.join(&category).join(&item_name).join("attachment");
+    fs::create_dir_all(&attachment_dir)
+        .map_err(|e| BlogError::Internal(format!("创建附件目录失败: {}", e)))?;
 
-    let category = parts[0];
-    let item_name = parts[1];
+    Ok(slug)
+}
 
-    // 删除数据库中的页面
-    let storage_path = config
-        .paths
-        .storage_database_path
-        .to_string_lossy()
-        .to_string();
+/// 更新文章，分类或文章名改变时移动到新目录，返回新的文章 slug
+fn edit_item(config: &Config, old_slug: &str, request: UpdateItemRequest) -> Result<String, BlogError> {
+    // 获取旧的分类和文章目录名
+    let (old_category, old_item_name) = locate_item(config, old_slug).map_err(BlogError::Validation)?;
 
-    if let Ok(storage) = StorageDB::new(&storage_path) {
-        storage.delete_page(&slug).ok();
-        storage.delete_attachments_by_slug(&slug).ok();
+    let UpdateItemRequest { category, item_name, title, content, date, author, description, tags, is_draft } = request;
+    if !is_plain_name(&category) || !is_plain_name(&item_name) {
+        return Err(BlogError::Validation("无效的分类或文章名".to_string()));
+    }
+    if !config.paths.content_dir.join(&category).is_dir() {
+        return Err(BlogError::Validation("分类不存在".to_string()));
     }
 
-    // 删除文件系统中的内容
-    let item_dir = config.paths.content_dir.join(category).join(item_name);
-    if item_dir.exists() {
-        if let Err(e) = fs::remove_dir_all(&item_dir) {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "status": "error",
-                "message": format!("删除文章目录失败: {}", e)
-            }));
+    // 移动时先删除旧目录
+    if old_category != category || old_item_name != item_name {
+        let old_item_dir = config.paths.content_dir.join(&old_category).join(&old_item_name);
+        if old_item_dir.exists() {
+            fs::remove_dir_all(&old_item_dir)
+                .map_err(|e| BlogError::Internal(format!("删除旧文章目录失败: {}", e)))?;
         }
     }
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "文章删除成功"
-    }))
+    let fields = ItemFields { title, content, date, author, description, tags, is_draft };
+    write_item(config, &category, &item_name, fields)
 }
 
-/// 发布草稿
-pub async fn publish_draft(
-    req: actix_web::HttpRequest,
-    path: web::Path<String>,
-    config: web::Data<Config>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
-    }
-    let draft_slug = path.into_inner();
+/// 删除文章目录、页面和附件
+fn remove_item(config: &Config, slug: &str) -> Result<(), BlogError> {
+    let storage = get_storage(config)?;
+    remove_item_in(config, &storage, slug)
+}
 
-    if !draft_slug.ends_with("_draft") {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "status": "error",
-            "message": "不是草稿"
-        }));
+/// 使用已打开的数据库删除文章，供批量操作在同一事务中调用
+fn remove_item_in(config: &Config, storage: &StorageDB, slug: &str) -> Result<(), BlogError> {
+    // 获取分类和文章目录名（须在删除数据库记录之前）
+    let (category, item_name) = locate_item(config, slug).map_err(BlogError::Validation)?;
+    if !is_plain_name(&category) || !is_plain_name(&item_name) {
+        return Err(BlogError::Validation("无效的文章 slug".to_string()));
     }
 
-    let clean_slug = &draft_slug[..draft_slug.len() - 6];
+    // 删除数据库中的页面
+    storage.delete_page(slug)?;
 
-    // 解析 slug
-    let parts: Vec<&str> = clean_slug.splitn(2, '-').collect();
-    if parts.len() < 2 {
-        return HttpResponse::BadRequest().json(serde_json::json!({
-            "status": "error",
-            "message": "无效的文章 slug"
-        }));
+    // 文件系统存储模式下同时删除磁盘上的附件
+    for file_path in storage.get_attachment_file_paths(slug).unwrap_or_default() {
+        if let Err(e) = fs::remove_file(&file_path) {
+            log::warn!("Failed to remove attachment file {}: {}", file_path, e);
+        }
+    }
+    fs::remove_dir(config.attachments_dir().join(slug)).ok();
+    storage.delete_attachments_by_slug(slug).ok();
+
+    // 删除文件系统中的内容
+    let item_dir = config.paths.content_dir.join(&category).join(&item_name);
+    if item_dir.exists() {
+        fs::remove_dir_all(&item_dir)
+            .map_err(|e| BlogError::Internal(format!("删除文章目录失败: {}", e)))?;
     }
+    Ok(())
+}
 
-    let category = parts[0];
-    let item_name = parts[1];
+/// 把草稿的 `draft: true` 改为 false，返回发布后的 slug
+fn publish_item_draft(config: &Config, draft_slug: &str) -> Result<String, BlogError> {
+    let Some(clean_slug) = draft_slug.strip_suffix("_draft") else {
+        return Err(BlogError::Validation("不是草稿".to_string()));
+    };
+    let (category, item_name) = locate_item(config, draft_slug).map_err(BlogError::Validation)?;
 
     // 读取草稿文件
     let draft_path = config.paths.content_dir
-        .join(category)
-        .join(item_name)
+        .join(&category)
+        .join(&item_name)
         .join(format!("{}.md", item_name));
-
     if !draft_path.exists() {
-        return HttpResponse::NotFound().json(serde_json::json!({
-            "status": "error",
-            "message": "草稿文件不存在"
-        }));
+        return Err(BlogError::NotFound("草稿文件不存在".to_string()));
     }
 
-    // 读取并修改 frontmatter
-    if let Ok(content) = fs::read_to_string(&draft_path) {
-        // 将 draft: true 改为 false
-        let new_content = content.replace("draft: true", "draft: false");
+    // 将 draft: true 改为 false
+    let content = fs::read_to_string(&draft_path)
+        .map_err(|e| BlogError::Internal(format!("读取草稿失败: {}", e)))?;
+    fs::write(&draft_path, content.replace("draft: true", "draft: false"))
+        .map_err(|e| BlogError::Internal(format!("更新草稿失败: {}", e)))?;
 
-        if let Err(e) = fs::write(&draft_path, &new_content) {
-            return HttpResponse::InternalServerError().json(serde_json::json!({
-                "status": "error",
-                "message": format!("更新草稿失败: {}", e)
-            }));
-        }
+    Ok(clean_slug.to_string())
+}
+
+/// 把已发布文章改回草稿，返回草稿 slug
+fn unpublish_item(config: &Config, slug: &str) -> Result<String, BlogError> {
+    if slug.ends_with("_draft") {
+        return Err(BlogError::Validation("已经是草稿".to_string()));
     }
+    let (category, item_name) = locate_item(config, slug).map_err(BlogError::Validation)?;
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "草稿已发布",
-        "slug": clean_slug.to_string()
-    }))
+    let md_path = config.paths.content_dir
+        .join(&category)
+        .join(&item_name)
+        .join(format!("{}.md", item_name));
+    if !md_path.exists() {
+        return Err(BlogError::NotFound("文章文件不存在".to_string()));
+    }
+
+    let content = fs::read_to_string(&md_path)
+        .map_err(|e| BlogError::Internal(format!("读取文章失败: {}", e)))?;
+    let new_content = if content.contains("draft: false") {
+        content.replacen("draft: false", "draft: true", 1)
+    } else if let Some(rest) = content.strip_prefix("---\n") {
+        // frontmatter 中没有 draft 字段时补上
+        format!("---\ndraft: true\n{}", rest)
+    } else {
+        format!("---\ndraft: true\n---\n\n{}", content)
+    };
+    fs::write(&md_path, new_content)
+        .map_err(|e| BlogError::Internal(format!("更新文章失败: {}", e)))?;
+
+    Ok(format!("{}_draft", slug))
 }
 
-/// 导入内容（全量或增量）
-pub async fn import_content(
-    req: actix_web::HttpRequest,
+/// 批量删除、发布或撤回文章，逐篇记录结果；数据库改动在同一事务中提交
+fn bulk_update_items(
+    config: &Config,
+    action: BulkAction,
+    slugs: &[String],
+) -> Result<(Vec<String>, Vec<BulkFailure>), BlogError> {
+    let storage = get_storage(config)?;
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+    storage
+        .in_transaction(|storage| {
+            for slug in slugs {
+                let result = match action {
+                    BulkAction::Delete => remove_item_in(config, storage, slug),
+                    BulkAction::Publish => publish_item_draft(config, slug).map(|_| ()),
+                    BulkAction::Unpublish => unpublish_item(config, slug).map(|_| ()),
+                };
+                match result {
+                    Ok(()) => succeeded.push(slug.clone()),
+                    Err(e) => failed.push(BulkFailure { slug: slug.clone(), error: e.to_string() }),
+                }
+            }
+            Ok(())
+        })?;
+    Ok((succeeded, failed))
+}
+
+/// 批量操作文章，完成后在后台重新编译
+#[utoipa::path(
+    post,
+    path = "/api/admin/items/bulk",
+    tag = "admin",
+    summary = "批量删除、发布或撤回文章",
+    request_body(content = BulkItemsRequest, example = json!({"action": "publish", "slugs": ["wine-merlot", "wine-syrah"]})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "partial", "succeeded": ["wine-merlot"], "failed": [{"slug": "wine-syrah", "error": "文章不存在"}], "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn bulk_items(
+    req: actix_web::HttpRequest,
+    body: web::Json<BulkItemsRequest>,
+    config: web::Data<Config>,
+    state: web::Data<Mutex<CompilerState>>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+
+    let request = body.into_inner();
+    if request.slugs.is_empty() {
+        return Err(BlogError::Validation("未选择文章".to_string()));
+    }
+
+    let (succeeded, failed) = bulk_update_items(&config, request.action, &request.slugs)?;
+    let job_id = if succeeded.is_empty() {
+        None
+    } else {
+        match start_background_compile(config.get_ref().clone(), state) {
+            Ok(job_id) | Err(job_id) => Some(job_id),
+        }
+    };
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": if failed.is_empty() { "success" } else { "partial" },
+        "succeeded": succeeded,
+        "failed": failed,
+        "job_id": job_id
+    })))
+}
+
+/// 创建文章
+#[utoipa::path(
+    post,
+    path = "/api/admin/items",
+    tag = "admin",
+    summary = "创建文章",
+    request_body(content = CreateItemRequest, example = json!({"category": "wine", "item_name": "merlot", "title": "Merlot", "content": "A soft red wine.", "date": "2024-05-01", "author": "xiaolinfeng", "description": "Tasting notes", "tags": ["red"], "is_draft": false})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "文章创建成功", "slug": "wine-merlot"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn create_item(
+    req: actix_web::HttpRequest,
+    body: web::Json<CreateItemRequest>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let slug = add_item(&config, body.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "文章创建成功",
+        "slug": slug
+    })))
+}
+
+/// 更新文章
+#[utoipa::path(
+    put,
+    path = "/api/admin/items/{slug}",
+    tag = "admin",
+    summary = "更新文章",
+    params(("slug" = String, Path)),
+    request_body(content = UpdateItemRequest, example = json!({"category": "wine", "item_name": "merlot", "title": "Merlot", "content": "A soft red wine.", "date": "2024-05-01", "author": "xiaolinfeng", "description": "Tasting notes", "tags": ["red"], "is_draft": false})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "文章更新成功", "slug": "wine-merlot"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn update_item(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    body: web::Json<UpdateItemRequest>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let slug = edit_item(&config, &path.into_inner(), body.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "文章更新成功",
+        "slug": slug
+    })))
+}
+
+/// 删除文章
+#[utoipa::path(
+    delete,
+    path = "/api/admin/items/{slug}",
+    tag = "admin",
+    summary = "删除文章",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "文章删除成功"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn delete_item(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    remove_item(&config, &path.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "文章删除成功"
+    })))
+}
+
+/// 单篇编译文章（不触发全站重建）
+#[utoipa::path(
+    post,
+    path = "/api/admin/items/{slug}/compile",
+    tag = "admin",
+    summary = "只重新编译这篇文章",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "文章编译成功", "slug": "wine-merlot", "duration_ms": 35, "page_size": 10240, "attachments": 2}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn compile_item(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    // 验证管理员身份
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+    let slug = path.into_inner();
+
+    match compile_single_item(&config, &slug).await {
+        Ok(result) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "文章编译成功",
+            "slug": result.slug,
+            "duration_ms": result.duration_ms,
+            "page_size": result.page_size,
+            "attachments": result.total_attachments
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("编译文章失败: {}", e)
+        })),
+    }
+}
+
+/// 从 `[content.git]` 配置的仓库拉取内容，只重新编译变化的文件。
+/// 本地修改过（如在后台编辑器中）且远程也改动了的文件作为冲突返回 409，内容目录保持不变
+#[utoipa::path(
+    post,
+    path = "/api/admin/sync",
+    tag = "admin",
+    summary = "从 git 仓库拉取内容并重新编译变化的文章",
+    responses(
+        (status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "已同步到 9c1e2f4，重新编译了 2 篇文章", "previous_commit": "4b825dc642cb6eb9a060e54bf8d69288fbee4904", "commit": "9c1e2f4a7b3d5e6f8091a2b3c4d5e6f708192a3b", "changed_files": ["wine/malbec/malbec.md", "wine/merlot/merlot.md"], "changed_slugs": ["wine-malbec", "wine-merlot"], "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21"})),
+        (status = 409, description = "正在编译，或本地修改与远程更新冲突", body = serde_json::Value, example = json!({"status": "error", "message": "以下文件在本地有未提交的修改，远程也改动了它们，未做任何更改", "commit": "9c1e2f4a7b3d5e6f8091a2b3c4d5e6f708192a3b", "conflicts": ["wine/merlot/merlot.md"]}))
+    ),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn sync_content(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    state: web::Data<Mutex<CompilerState>>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+
+    // 同步期间占用编译状态，避免后台编译读到一半更新的内容
+    let started = match state.lock() {
+        Ok(mut state) => state.try_start(),
+        Err(poisoned) => poisoned.into_inner().try_start(),
+    };
+    let job_id = match started {
+        Ok(job_id) => job_id,
+        Err(running) => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "status": "error",
+                "message": "正在编译中，请在编译结束后再同步",
+                "job_id": running
+            })));
+        }
+    };
+
+    let sync_config = config.get_ref().clone();
+    let started_at = std::time::Instant::now();
+    let result = web::block(move || crate::git_sync::sync_and_compile(&sync_config))
+        .await
+        .map_err(|e| BlogError::Internal(e.to_string()))
+        .and_then(|result| result);
+
+    let (message, success) = match &result {
+        Ok((report, _)) if !report.is_applied() => ("同步冲突，内容未更新".to_string(), false),
+        Ok((report, Some(compiled))) => {
+            crate::notifications::notify_compile(&config, compiled, started_at.elapsed());
+            (format!("已同步到 {}，重新编译了 {} 篇文章", short_commit(&report.commit), compiled.total_items), true)
+        }
+        Ok((report, None)) => (format!("已是最新（{}）", short_commit(&report.commit)), true),
+        Err(e) => (format!("同步失败: {}", e), false),
+    };
+    match state.lock() {
+        Ok(mut state) => state.finish(message.clone(), success),
+        Err(poisoned) => poisoned.into_inner().finish(message.clone(), success),
+    }
+
+    let (report, compiled) = result?;
+    if !report.is_applied() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "以下文件在本地有未提交的修改，远程也改动了它们，未做任何更改",
+            "commit": report.commit,
+            "conflicts": report.conflicts
+        })));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": message,
+        "previous_commit": report.previous_commit,
+        "commit": report.commit,
+        "changed_files": report.changed_files,
+        "changed_slugs": compiled.and_then(|compiled| compiled.changed_slugs),
+        "job_id": job_id
+    })))
+}
+
+/// 提交 ID 的前 7 位
+fn short_commit(commit: &str) -> &str {
+    commit.get(..7).unwrap_or(commit)
+}
+
+/// 由文章 slug（可带 _draft 后缀）找出分类与文章目录名
+///
+/// 优先使用编译时记录在数据库中的值；旧数据没有记录时按 slug 拆分
+fn locate_item(config: &Config, slug: &str) -> Result<(String, String), String> {
+    let clean_slug = slug.strip_suffix("_draft").unwrap_or(slug);
+    let stored = StorageDB::new(&config.paths.storage_db.to_string_lossy())
+        .ok()
+        .and_then(|storage| {
+            [slug, clean_slug]
+                .iter()
+                .find_map(|slug| storage.get_page(slug).ok().flatten())
+        });
+    if let Some(Page { category: Some(category), item_name: Some(item_name), .. }) = stored {
+        return Ok((category, item_name));
+    }
+    split_item_slug(&config.paths.content_dir, slug)
+}
+
+/// 将 slug（category-item_name，可带 _draft 后缀）拆成分类与文章目录名
+///
+/// 分类名本身可能含 '-'，依次尝试每个 '-'：优先取文章目录存在的拆法，
+/// 其次取分类目录存在的最长分类名，都不存在时按第一个 '-' 拆分
+fn split_item_slug(content_dir: &std::path::Path, slug: &str) -> Result<(String, String), String> {
+    let clean_slug = slug.strip_suffix("_draft").unwrap_or(slug);
+    let splits: Vec<(&str, &str)> = clean_slug
+        .match_indices('-')
+        .map(|(index, _)| (&clean_slug[..index], &clean_slug[index + 1..]))
+        .filter(|(category, item_name)| !category.is_empty() && !item_name.is_empty())
+        .collect();
+    splits
+        .iter()
+        .find(|(category, item_name)| content_dir.join(category).join(item_name).is_dir())
+        .or_else(|| splits.iter().rev().find(|(category, _)| content_dir.join(category).is_dir()))
+        .or(splits.first())
+        .map(|(c, i)| (c.to_string(), i.to_string()))
+        .ok_or_else(|| "无效的文章 slug".to_string())
+}
+
+/// 按 slug 在阻塞线程中编译单篇文章
+async fn compile_single_item(config: &Config, slug: &str) -> Result<CompileItemResult, String> {
+    let (category, item_name) = locate_item(config, slug)?;
+
+    let config = config.clone();
+    web::block(move || {
+        Compiler::new(config)
+            .and_then(|mut compiler| compiler.compile_item(&category, &item_name))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 文章删除后在阻塞线程中刷新分类页、首页以及仍链接到它的文章页
+async fn compile_removed_single_item(config: &Config, category: String, slug: &str) -> Result<CompileItemResult, String> {
+    let clean_slug = slug.strip_suffix("_draft").unwrap_or(slug).to_string();
+
+    let config = config.clone();
+    web::block(move || {
+        Compiler::new(config)
+            .and_then(|mut compiler| compiler.compile_removed_item(&category, &clean_slug))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 拼出“操作结果 + 增量编译结果”的提示
+fn compile_outcome(done: &str, result: Result<CompileItemResult, String>) -> (String, bool) {
+    match result {
+        Ok(result) => (
+            format!("{}，已重新编译 {} 个页面（{} ms）", done, result.total_pages, result.duration_ms),
+            true,
+        ),
+        Err(e) => (format!("{}，但编译失败: {}", done, e), false),
+    }
+}
+
+/// 跳转回文章列表页并显示提示
+fn redirect_to_items(message: &str, success: bool) -> HttpResponse {
+    redirect_with_message("/admin/items", message, success)
+}
+
+/// 跳转到管理页面并通过 `message`、`success` 参数显示提示
+fn redirect_with_message(path: &str, message: &str, success: bool) -> HttpResponse {
+    let location = format!("{}?message={}&success={}", path, urlencoding::encode(message), success);
+    HttpResponse::SeeOther()
+        .append_header((actix_web::http::header::LOCATION, location))
+        .body(String::new())
+}
+
+/// 发布草稿
+#[utoipa::path(
+    post,
+    path = "/api/admin/items/{slug}/publish",
+    tag = "admin",
+    summary = "发布草稿",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "草稿已发布", "slug": "wine-merlot"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn publish_draft(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let slug = publish_item_draft(&config, &path.into_inner())?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "草稿已发布",
+        "slug": slug
+    })))
+}
+
+/// 导入内容（全量或增量）
+#[utoipa::path(
+    post,
+    path = "/api/admin/import",
+    tag = "admin",
+    summary = "返回导入内容包的上传地址",
+    request_body(content = ImportRequest, example = json!({"import_type": "full", "target_category": null})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "请使用 POST 上传文件到指定端点", "upload_url": "/api/admin/upload/full", "supported_formats": ["zip", "tar.gz"]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn import_content(
+    req: actix_web::HttpRequest,
     body: web::Json<ImportRequest>,
     config: web::Data<Config>,
 ) -> impl Responder {
@@ -913,20 +1933,335 @@ pub async fn import_content(
     }))
 }
 
-/// 处理全量上传
+/// 全量导入参数
+#[derive(Debug, Deserialize)]
+pub struct FullImportQuery {
+    /// `merge` 时保留压缩包中没有的现有文件，默认整体替换内容目录
+    pub mode: Option<String>,
+}
+
+/// 处理全量上传：压缩包内每个顶层目录为一个分类，导入后触发重新编译
+#[utoipa::path(
+    post,
+    path = "/api/admin/upload/full",
+    tag = "admin",
+    summary = "上传完整内容包（zip 或 tar.gz）并重新编译",
+    params(("mode" = Option<String>, Query, description = "merge 时保留压缩包中没有的现有文件")),
+    request_body(content = inline(crate::openapi::UploadForm), content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "已导入 2 个分类，正在重新编译", "mode": "replace", "categories": ["wine", "tea"], "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn upload_full_content(
+    req: actix_web::HttpRequest,
+    query: web::Query<FullImportQuery>,
+    mut payload: Multipart,
     config: web::Data<Config>,
+    state: web::Data<Mutex<CompilerState>>,
 ) -> impl Responder {
-    // 这里的实现需要 multipart 支持
-    // 简化版本：返回提示信息
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "info",
-        "message": "全量上传功能需要通过表单上传 ZIP 文件",
-        "endpoint": "POST /api/admin/upload/full",
-        "form_field": "file"
+    // 验证管理员身份
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+    let merge = query.mode.as_deref() == Some("merge");
+
+    // 编译期间替换内容目录会让编译读到一半旧、一半新的内容
+    let running_job = match state.lock() {
+        Ok(state) => state.is_compiling.then(|| state.job_id.clone().unwrap_or_default()),
+        Err(poisoned) => {
+            let state = poisoned.into_inner();
+            state.is_compiling.then(|| state.job_id.clone().unwrap_or_default())
+        }
+    };
+    if let Some(job_id) = running_job {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "正在编译中，请在编译结束后再导入",
+            "job_id": job_id
+        }));
+    }
+
+    while let Some(Ok(mut field)) = payload.next().await {
+        let name = field.name();
+        if name != "file" {
+            continue;
+        }
+
+        // 保存临时文件，保留原始文件扩展名以识别格式
+        let content_disposition = field.content_disposition();
+        let original_filename = content_disposition.get_filename().unwrap_or("upload").to_lowercase();
+        let extension = if original_filename.ends_with(".tar.gz") {
+            "tar.gz"
+        } else {
+            std::path::Path::new(&original_filename)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("")
+        };
+        let temp_file_path = std::env::temp_dir().join(format!("lf_blog_upload_{}.{}", Uuid::new_v4(), extension));
+
+        if let Err(e) = save_upload_field(&mut field, &temp_file_path, config.server.max_upload_bytes).await {
+            return upload_error_response(e);
+        }
+        if let Err(e) = check_upload_archive(&temp_file_path, &original_filename) {
+            let _ = fs::remove_file(&temp_file_path);
+            return upload_error_response(e);
+        }
+
+        let content_dir = config.paths.content_dir.clone();
+        let archive_path = temp_file_path.clone();
+        let limits = ExtractLimits::from_config(&config);
+        let result = web::block(move || import_full_package(&archive_path, &content_dir, merge, limits)).await;
+        let _ = fs::remove_file(&temp_file_path);
+
+        let categories = match result {
+            Ok(Ok(categories)) => categories,
+            // 压缩包本身有问题时为 400，解压、暂存或切换目录失败时为 500
+            Ok(Err(e)) => {
+                return HttpResponse::build(actix_web::ResponseError::status_code(&e)).json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("导入失败: {}", e)
+                }));
+            }
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("导入失败: {}", e)
+                }));
+            }
+        };
+
+        // 导入期间刚好有编译开始时，等它结束后再编译一次，保证新内容生效
+        let job_id = match start_background_compile(config.get_ref().clone(), state.clone()) {
+            Ok(job_id) => job_id,
+            Err(running_job) => {
+                log::warn!("Content imported while compile {} is running; recompiling once it finishes", running_job);
+                let config = config.get_ref().clone();
+                actix_web::rt::spawn(async move {
+                    while !wait_for_compile(&state, std::time::Duration::from_secs(60)).await {}
+                    if let Err(job_id) = start_background_compile(config, state) {
+                        log::warn!("Compile {} started before the queued recompile; it includes the imported content", job_id);
+                    }
+                });
+                running_job
+            }
+        };
+
+        return HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("已导入 {} 个分类，正在重新编译", categories.len()),
+            "mode": if merge { "merge" } else { "replace" },
+            "categories": categories,
+            "job_id": job_id
+        }));
+    }
+
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "status": "error",
+        "message": "未找到上传的文件"
     }))
 }
 
+/// 解压全站压缩包并替换（或合并进）内容目录，返回导入的分类名
+///
+/// 先解压到与内容目录同级的暂存目录，校验通过后再用重命名整体切换，
+/// 任何一步失败时原内容目录保持不变
+fn import_full_package(
+    archive_path: &PathBuf,
+    content_dir: &PathBuf,
+    merge: bool,
+    limits: ExtractLimits,
+) -> Result<Vec<String>, BlogError> {
+    let filename = archive_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let is_zip = filename.ends_with(".zip");
+    let is_tar = filename.ends_with(".tar.gz") || filename.ends_with(".tgz");
+    if !is_zip && !is_tar {
+        return Err(BlogError::Validation("不支持的文件格式，请上传 .zip 或 .tar.gz 文件".to_string()));
+    }
+
+    let parent = content_dir
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let dir_name = content_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("content");
+    let staging_dir = parent.join(format!(".{}_import_{}", dir_name, Uuid::new_v4()));
+
+    let result = stage_full_package(archive_path, &staging_dir, is_zip, content_dir, merge, limits)
+        .and_then(|categories| {
+            swap_content_dir(&staging_dir, content_dir, &parent, dir_name).map_err(BlogError::Internal)?;
+            Ok(categories)
+        });
+    if staging_dir.exists() {
+        let _ = fs::remove_dir_all(&staging_dir);
+    }
+    result
+}
+
+/// 解压到暂存目录并校验结构，合并模式下再补入现有内容中压缩包没有的文件
+fn stage_full_package(
+    archive_path: &PathBuf,
+    staging_dir: &PathBuf,
+    is_zip: bool,
+    content_dir: &PathBuf,
+    merge: bool,
+    limits: ExtractLimits,
+) -> Result<Vec<String>, BlogError> {
+    let extract_dir = staging_dir.with_extension("extract");
+    fs::create_dir_all(&extract_dir).map_err(|e| BlogError::Internal(format!("创建临时目录失败: {}", e)))?;
+
+    let result = (|| {
+        let extract_result = if is_zip {
+            extract_zip(archive_path, &extract_dir, limits)
+        } else {
+            extract_tar(archive_path, &extract_dir, limits)
+        };
+        extract_result.map_err(|e| match e {
+            ExtractError::Rejected(msg) => BlogError::Validation(format!("压缩包不合法: {}", msg)),
+            ExtractError::Io(msg) => BlogError::Internal(format!("解压失败: {}", msg)),
+        })?;
+
+        let root = find_import_root(&extract_dir);
+        let categories = validate_import_root(&root).map_err(BlogError::Validation)?;
+        fs::rename(&root, staging_dir).map_err(|e| BlogError::Internal(format!("移动暂存目录失败: {}", e)))?;
+
+        // 导出备份时附带的数据库快照不属于内容
+        let _ = fs::remove_dir_all(staging_dir.join("_data"));
+
+        if merge && content_dir.exists() {
+            merge_missing_files(content_dir, staging_dir)
+                .map_err(|e| BlogError::Internal(format!("合并现有内容失败: {}", e)))?;
+        }
+        Ok(categories)
+    })();
+
+    let _ = fs::remove_dir_all(&extract_dir);
+    result
+}
+
+/// 压缩包可能多包了一层目录（例如直接压缩了整个 content 目录），此时以该目录为根
+fn find_import_root(extract_dir: &PathBuf) -> PathBuf {
+    let dirs = import_category_dirs(extract_dir);
+    if dirs.len() == 1 && !is_valid_import_category(&dirs[0]) && !import_category_dirs(&dirs[0]).is_empty() {
+        return dirs[0].clone();
+    }
+    extract_dir.clone()
+}
+
+/// 顶层分类目录：忽略隐藏目录和 `_` 开头的目录
+fn import_category_dirs(dir: &PathBuf) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_dir())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .map(|n| !n.starts_with('.') && !n.starts_with('_'))
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// 分类目录需要包含 index.md，或至少一个带 .md 文件的文章目录
+fn is_valid_import_category(dir: &PathBuf) -> bool {
+    if dir.join("index.md").is_file() {
+        return true;
+    }
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.filter_map(|entry| entry.ok()).any(|entry| {
+                let path = entry.path();
+                path.is_dir()
+                    && fs::read_dir(&path)
+                        .map(|files| {
+                            files.filter_map(|f| f.ok()).any(|f| {
+                                f.path().extension().and_then(|ext| ext.to_str()) == Some("md")
+                            })
+                        })
+                        .unwrap_or(false)
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// 校验所有顶层目录，返回分类名
+fn validate_import_root(root: &PathBuf) -> Result<Vec<String>, String> {
+    let dirs = import_category_dirs(root);
+    if dirs.is_empty() {
+        return Err("压缩包中没有分类目录".to_string());
+    }
+
+    let invalid: Vec<String> = dirs
+        .iter()
+        .filter(|dir| !is_valid_import_category(dir))
+        .filter_map(|dir| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(format!(
+            "以下目录既没有 index.md 也没有包含 .md 文件的文章目录: {}",
+            invalid.join(", ")
+        ));
+    }
+
+    Ok(dirs
+        .iter()
+        .filter_map(|dir| dir.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect())
+}
+
+/// 把 `src` 中 `dst` 尚不存在的文件复制过去
+fn merge_missing_files(src: &PathBuf, dst: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        if src_path.is_dir() {
+            fs::create_dir_all(&dst_path)?;
+            merge_missing_files(&src_path, &dst_path)?;
+        } else if !dst_path.exists() {
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 用暂存目录替换内容目录；切换失败时恢复原目录
+fn swap_content_dir(staging_dir: &PathBuf, content_dir: &PathBuf, parent: &PathBuf, dir_name: &str) -> Result<(), String> {
+    if !content_dir.exists() {
+        return fs::rename(staging_dir, content_dir).map_err(|e| format!("替换内容目录失败: {}", e));
+    }
+
+    let backup_dir = parent.join(format!(".{}_old_{}", dir_name, Uuid::new_v4()));
+    fs::rename(content_dir, &backup_dir).map_err(|e| format!("替换内容目录失败: {}", e))?;
+    if let Err(e) = fs::rename(staging_dir, content_dir) {
+        if let Err(restore_err) = fs::rename(&backup_dir, content_dir) {
+            log::error!("恢复内容目录失败，原内容保存在 {:?}: {}", backup_dir, restore_err);
+        }
+        return Err(format!("替换内容目录失败: {}", e));
+    }
+
+    if let Err(e) = fs::remove_dir_all(&backup_dir) {
+        log::warn!("删除旧内容目录 {:?} 失败: {}", backup_dir, e);
+    }
+    Ok(())
+}
+
 /// 处理增量上传
 pub async fn upload_incremental_content(
     path: web::Path<String>,
@@ -952,6 +2287,15 @@ pub async fn upload_incremental_content(
 }
 
 /// 上传分类压缩包并创建分类
+#[utoipa::path(
+    post,
+    path = "/api/admin/upload/category",
+    tag = "admin",
+    summary = "上传单个分类的内容包",
+    request_body(content = inline(crate::openapi::UploadForm), content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "分类 'wine' 创建成功", "category": "wine", "optimized_images": 3}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn upload_category_package(
     req: actix_web::HttpRequest,
     mut payload: Multipart,
@@ -973,8 +2317,8 @@ pub async fn upload_category_package(
         if name == "file" {
             // 获取原始文件名以保留扩展名
             let content_disposition = field.content_disposition();
-            let original_filename = content_disposition.get_filename().unwrap_or("upload");
-            let extension = std::path::Path::new(original_filename)
+            let original_filename = content_disposition.get_filename().unwrap_or("upload").to_string();
+            let extension = std::path::Path::new(&original_filename)
                 .extension()
                 .and_then(|ext| ext.to_str())
                 .unwrap_or("");
@@ -988,30 +2332,17 @@ pub async fn upload_category_package(
             };
             let temp_file_path = temp_dir.join(temp_filename);
 
-            // 创建临时文件并写入
-            let mut temp_file = match fs::File::create(&temp_file_path) {
-                Ok(f) => f,
-                Err(e) => {
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("创建临时文件失败: {}", e)
-                    }));
-                }
-            };
-
-            // 读取并写入文件
-            while let Some(Ok(chunk)) = field.next().await {
-                if let Err(e) = temp_file.write_all(&chunk) {
-                    let _ = fs::remove_file(&temp_file_path);
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("写入临时文件失败: {}", e)
-                    }));
-                }
+            // 写入临时文件并按文件头校验格式
+            if let Err(e) = save_upload_field(&mut field, &temp_file_path, config.server.max_upload_bytes).await {
+                return upload_error_response(e);
+            }
+            if let Err(e) = check_upload_archive(&temp_file_path, &original_filename) {
+                let _ = fs::remove_file(&temp_file_path);
+                return upload_error_response(e);
             }
 
             // 解压缩文件
-            let result = extract_category_package(&temp_file_path, content_dir);
+            let result = extract_category_package(&temp_file_path, content_dir, ExtractLimits::from_config(&config), &config.attachments);
 
             // 删除临时文件
             let _ = fs::remove_file(&temp_file_path);
@@ -1031,6 +2362,8 @@ pub async fn upload_category_package(
 fn extract_category_package(
     archive_path: &PathBuf,
     content_dir: &PathBuf,
+    limits: ExtractLimits,
+    images: &AttachmentsConfig,
 ) -> HttpResponse {
     let filename = archive_path.file_name()
         .and_then(|n| n.to_str())
@@ -1063,17 +2396,14 @@ fn extract_category_package(
 
     // 解压缩
     let extract_result = if is_zip {
-        extract_zip(archive_path, &temp_extract_dir)
+        extract_zip(archive_path, &temp_extract_dir, limits)
     } else {
-        extract_tar(archive_path, &temp_extract_dir)
+        extract_tar(archive_path, &temp_extract_dir, limits)
     };
 
     if let Err(e) = extract_result {
         let _ = fs::remove_dir_all(&temp_extract_dir);
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": format!("解压失败: {}", e)
-        }));
+        return extract_error_response(e);
     }
 
     // 查找解压后的分类目录
@@ -1125,6 +2455,14 @@ fn extract_category_package(
 
     let target_dir = content_dir.join(&category_name);
 
+    // 移动前优化压缩包中的图片附件
+    let optimized_images = crate::images::optimize_attachments(&category_dir, images);
+    if !optimized_images.is_empty() {
+        let original: usize = optimized_images.iter().map(|o| o.original_size).sum();
+        let optimized: usize = optimized_images.iter().map(|o| o.optimized_size).sum();
+        log::info!("优化了 {} 张图片: {} -> {} 字节", optimized_images.len(), original, optimized);
+    }
+
     // 如果目标目录已存在，先删除
     if target_dir.exists() {
         if let Err(e) = fs::remove_dir_all(&target_dir) {
@@ -1161,11 +2499,22 @@ fn extract_category_package(
     HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
         "message": format!("分类 '{}' 创建成功", category_name),
-        "category": category_name
+        "category": category_name,
+        "optimized_images": optimized_images
     }))
 }
 
 /// 上传文章压缩包到指定分类
+#[utoipa::path(
+    post,
+    path = "/api/admin/upload/item/{category}",
+    tag = "admin",
+    summary = "上传单篇文章的内容包",
+    params(("category" = String, Path)),
+    request_body(content = inline(crate::openapi::UploadForm), content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "文章 'merlot' 创建成功", "item": "merlot", "optimized_images": 1}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
 pub async fn upload_item_package(
     req: actix_web::HttpRequest,
     path: web::Path<String>,
@@ -1198,8 +2547,8 @@ pub async fn upload_item_package(
         if name == "file" {
             // 获取原始文件名以保留扩展名
             let content_disposition = field.content_disposition();
-            let original_filename = content_disposition.get_filename().unwrap_or("upload");
-            let extension = std::path::Path::new(original_filename)
+            let original_filename = content_disposition.get_filename().unwrap_or("upload").to_string();
+            let extension = std::path::Path::new(&original_filename)
                 .extension()
                 .and_then(|ext| ext.to_str())
                 .unwrap_or("");
@@ -1213,30 +2562,17 @@ pub async fn upload_item_package(
             };
             let temp_file_path = temp_dir.join(temp_filename);
 
-            // 创建临时文件并写入
-            let mut temp_file = match fs::File::create(&temp_file_path) {
-                Ok(f) => f,
-                Err(e) => {
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("创建临时文件失败: {}", e)
-                    }));
-                }
-            };
-
-            // 读取并写入文件
-            while let Some(Ok(chunk)) = field.next().await {
-                if let Err(e) = temp_file.write_all(&chunk) {
-                    let _ = fs::remove_file(&temp_file_path);
-                    return HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": format!("写入临时文件失败: {}", e)
-                    }));
-                }
+            // 写入临时文件并按文件头校验格式
+            if let Err(e) = save_upload_field(&mut field, &temp_file_path, config.server.max_upload_bytes).await {
+                return upload_error_response(e);
+            }
+            if let Err(e) = check_upload_archive(&temp_file_path, &original_filename) {
+                let _ = fs::remove_file(&temp_file_path);
+                return upload_error_response(e);
             }
 
             // 解压缩文件
-            let result = extract_item_package(&temp_file_path, &category_dir);
+            let result = extract_item_package(&temp_file_path, &category_dir, ExtractLimits::from_config(&config), &config.attachments);
 
             // 删除临时文件
             let _ = fs::remove_file(&temp_file_path);
@@ -1254,6 +2590,8 @@ pub async fn upload_item_package(
 fn extract_item_package(
     archive_path: &PathBuf,
     category_dir: &PathBuf,
+    limits: ExtractLimits,
+    images: &AttachmentsConfig,
 ) -> HttpResponse {
     let filename = archive_path.file_name()
         .and_then(|n| n.to_str())
@@ -1286,17 +2624,14 @@ fn extract_item_package(
 
     // 解压缩
     let extract_result = if is_zip {
-        extract_zip(archive_path, &temp_extract_dir)
+        extract_zip(archive_path, &temp_extract_dir, limits)
     } else {
-        extract_tar(archive_path, &temp_extract_dir)
+        extract_tar(archive_path, &temp_extract_dir, limits)
     };
 
     if let Err(e) = extract_result {
         let _ = fs::remove_dir_all(&temp_extract_dir);
-        return HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": format!("解压失败: {}", e)
-        }));
+        return extract_error_response(e);
     }
 
     // 查找解压后的文章目录
@@ -1350,217 +2685,1522 @@ fn extract_item_package(
         }
     };
 
-    // 获取文章名称（目录名）
-    let item_name = item_dir.file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+    // 获取文章名称（目录名）
+    let item_name = item_dir.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    let target_dir = category_dir.join(&item_name);
+
+    // 移动前优化压缩包中的图片附件
+    let optimized_images = crate::images::optimize_attachments(&item_dir, images);
+    if !optimized_images.is_empty() {
+        let original: usize = optimized_images.iter().map(|o| o.original_size).sum();
+        let optimized: usize = optimized_images.iter().map(|o| o.optimized_size).sum();
+        log::info!("优化了 {} 张图片: {} -> {} 字节", optimized_images.len(), original, optimized);
+    }
+
+    // 如果目标目录已存在，先删除
+    if target_dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&target_dir) {
+            let _ = fs::remove_dir_all(&temp_extract_dir);
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": format!("目标目录已存在且无法删除: {}", e)
+            }));
+        }
+    }
+
+    // 移动解压后的内容到目标位置
+    if let Err(e) = fs::rename(&item_dir, &target_dir) {
+        // 如果重命名失败，尝试复制后删除
+        if let Err(e2) = copy_dir(&item_dir, &target_dir) {
+            let _ = fs::remove_dir_all(&temp_extract_dir);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("移动文件失败: {}，复制也失败: {}", e, e2)
+            }));
+        }
+        let _ = fs::remove_dir_all(&item_dir);
+    }
+
+    // 清理临时目录
+    let _ = fs::remove_dir_all(&temp_extract_dir);
+
+    // 确保 attachment 目录存在
+    let attachment_dir = target_dir.join("attachment");
+    if !attachment_dir.exists() {
+        let _ = fs::create_dir_all(&attachment_dir);
+    }
+
+    // 确保有 index.md 或同名的 .md 文件
+    let md_files: Vec<_> = match target_dir.read_dir() {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().map(|ex| ex.to_str()).flatten() == Some("md"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    if md_files.is_empty() {
+        // 创建默认的 index.md
+        let default_content = format!(
+            r#"---
+title: {}
+date: {}
+author: {}
+description: ""
+tags: []
+draft: false
+---
+
+# {}
+
+这里是文章的默认内容。
+"#,
+            item_name,
+            chrono::Utc::now().format("%Y-%m-%d").to_string(),
+            "Author",
+            item_name
+        );
+        let md_path = target_dir.join(format!("{}.md", item_name));
+        let _ = fs::write(&md_path, &default_content);
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": format!("文章 '{}' 创建成功", item_name),
+        "item": item_name,
+        "optimized_images": optimized_images
+    }))
+}
+
+/// 上传的压缩包格式（按文件头识别）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveType {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveType {
+    /// 按文件名扩展名推断的格式
+    fn from_filename(filename: &str) -> Option<Self> {
+        let filename = filename.to_lowercase();
+        if filename.ends_with(".zip") {
+            Some(ArchiveType::Zip)
+        } else if filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
+            Some(ArchiveType::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// 上传失败
+#[derive(Debug)]
+enum UploadError {
+    /// 超过 `server.max_upload_bytes`，对应 413
+    TooLarge(usize),
+    /// 文件内容不是支持的压缩包，对应 415
+    UnsupportedType(String),
+    /// 读写临时文件失败，对应 500
+    Io(String),
+}
+
+impl From<std::io::Error> for UploadError {
+    fn from(e: std::io::Error) -> Self {
+        UploadError::Io(e.to_string())
+    }
+}
+
+fn upload_error_response(e: UploadError) -> HttpResponse {
+    match e {
+        UploadError::TooLarge(max_bytes) => HttpResponse::PayloadTooLarge().json(serde_json::json!({
+            "status": "error",
+            "message": format!("上传文件超过大小上限（{} 字节）", max_bytes)
+        })),
+        UploadError::UnsupportedType(msg) => HttpResponse::UnsupportedMediaType().json(serde_json::json!({
+            "status": "error",
+            "message": msg
+        })),
+        UploadError::Io(msg) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("写入临时文件失败: {}", msg)
+        })),
+    }
+}
+
+/// 将上传的文件字段写入 `path`，累计超过 `max_bytes` 或出错时删除不完整的临时文件
+async fn save_upload_field(field: &mut Field, path: &PathBuf, max_bytes: usize) -> Result<(), UploadError> {
+    let result = async {
+        let mut file = fs::File::create(path)?;
+        let mut written: usize = 0;
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| UploadError::Io(e.to_string()))?;
+            written += chunk.len();
+            if written > max_bytes {
+                return Err(UploadError::TooLarge(max_bytes));
+            }
+            file.write_all(&chunk)?;
+        }
+        Ok(())
+    }
+    .await;
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+/// 按文件头识别压缩包格式：ZIP 以 `PK\x03\x04` 开头，gzip 以 `\x1f\x8b` 开头
+fn validate_archive_magic(path: &PathBuf) -> Result<ArchiveType, UploadError> {
+    let mut magic = [0u8; 4];
+    let mut file = fs::File::open(path)?;
+    let mut read = 0;
+    while read < magic.len() {
+        match std::io::Read::read(&mut file, &mut magic[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+
+    if read >= 4 && magic == *b"PK\x03\x04" {
+        Ok(ArchiveType::Zip)
+    } else if read >= 2 && magic[..2] == [0x1f, 0x8b] {
+        Ok(ArchiveType::TarGz)
+    } else {
+        Err(UploadError::UnsupportedType(
+            "文件内容不是 ZIP 或 gzip 压缩包".to_string(),
+        ))
+    }
+}
+
+/// 校验上传文件的文件头，并要求与扩展名一致（解压时按扩展名选择格式）
+fn check_upload_archive(path: &PathBuf, filename: &str) -> Result<ArchiveType, UploadError> {
+    let archive_type = validate_archive_magic(path)?;
+    if ArchiveType::from_filename(filename) != Some(archive_type) {
+        return Err(UploadError::UnsupportedType(format!(
+            "文件内容与扩展名不符: {}",
+            filename
+        )));
+    }
+    Ok(archive_type)
+}
+
+/// 解压限制
+#[derive(Debug, Clone, Copy)]
+struct ExtractLimits {
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl ExtractLimits {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            max_bytes: config.admin.max_extract_size_mb.saturating_mul(1024 * 1024),
+            max_files: config.admin.max_extract_files,
+        }
+    }
+}
+
+/// 解压错误
+#[derive(Debug)]
+enum ExtractError {
+    /// 压缩包内容不合法（路径越界、符号链接、超出限制），对应 400
+    Rejected(String),
+    /// 读写失败或压缩包损坏，对应 500
+    Io(String),
+}
+
+impl std::fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::Rejected(msg) | ExtractError::Io(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExtractError {
+    fn from(e: std::io::Error) -> Self {
+        ExtractError::Io(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for ExtractError {
+    fn from(e: zip::result::ZipError) -> Self {
+        ExtractError::Io(e.to_string())
+    }
+}
+
+/// 解压失败时的响应：不合法的压缩包返回 400
+fn extract_error_response(e: ExtractError) -> HttpResponse {
+    match e {
+        ExtractError::Rejected(msg) => HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": format!("压缩包不合法: {}", msg)
+        })),
+        ExtractError::Io(msg) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("解压失败: {}", msg)
+        })),
+    }
+}
+
+/// 条目路径只允许普通的相对路径，拒绝绝对路径和 `..`；`./` 之类的空路径返回 `Ok(None)`
+fn sanitize_entry_path(name: &str) -> Result<Option<PathBuf>, ExtractError> {
+    let mut clean = PathBuf::new();
+    // Windows 打包工具可能使用反斜杠分隔
+    for component in std::path::Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            std::path::Component::Normal(part) => clean.push(part),
+            std::path::Component::CurDir => {}
+            _ => return Err(ExtractError::Rejected(format!("条目路径不安全: {}", name))),
+        }
+    }
+    Ok(if clean.as_os_str().is_empty() { None } else { Some(clean) })
+}
+
+/// 统计已解压的条目数和字节数，超出限制时报错
+struct ExtractBudget {
+    limits: ExtractLimits,
+    files: usize,
+    bytes: u64,
+}
+
+impl ExtractBudget {
+    fn new(limits: ExtractLimits) -> Self {
+        Self { limits, files: 0, bytes: 0 }
+    }
+
+    fn add_entry(&mut self, name: &str) -> Result<(), ExtractError> {
+        self.files += 1;
+        if self.files > self.limits.max_files {
+            return Err(ExtractError::Rejected(format!(
+                "条目数超过上限 {}（{}）",
+                self.limits.max_files, name
+            )));
+        }
+        Ok(())
+    }
+
+    /// 按实际写出的字节计数，不信任压缩包中声明的大小
+    fn copy(&mut self, name: &str, reader: &mut impl std::io::Read, out_path: &PathBuf) -> Result<(), ExtractError> {
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let remaining = self.limits.max_bytes.saturating_sub(self.bytes);
+        let mut out_file = fs::File::create(out_path)?;
+        let written = std::io::copy(&mut std::io::Read::take(reader, remaining + 1), &mut out_file)?;
+        self.bytes += written;
+        if written > remaining {
+            return Err(ExtractError::Rejected(format!(
+                "解压后总大小超过上限 {} MB（{}）",
+                self.limits.max_bytes / (1024 * 1024),
+                name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// 解压 ZIP 文件
+fn extract_zip(archive_path: &PathBuf, dest_dir: &PathBuf, limits: ExtractLimits) -> Result<(), ExtractError> {
+    let file = fs::File::open(archive_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    let mut budget = ExtractBudget::new(limits);
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        budget.add_entry(&name)?;
+        let Some(rel_path) = sanitize_entry_path(&name)? else {
+            continue;
+        };
+        let out_path = dest_dir.join(rel_path);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&out_path)?;
+        } else {
+            budget.copy(&name, &mut entry, &out_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 解压 TAR.GZ 文件
+fn extract_tar(archive_path: &PathBuf, dest_dir: &PathBuf, limits: ExtractLimits) -> Result<(), ExtractError> {
+    let file = fs::File::open(archive_path)?;
+
+    // 解压 gzip
+    let gzip_decoder = flate2::read::GzDecoder::new(file);
+    let mut archive = tar::Archive::new(gzip_decoder);
+    let mut budget = ExtractBudget::new(limits);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        budget.add_entry(&name)?;
+        let Some(rel_path) = sanitize_entry_path(&name)? else {
+            continue;
+        };
+        let out_path = dest_dir.join(rel_path);
+
+        match entry.header().entry_type() {
+            tar::EntryType::Directory => fs::create_dir_all(&out_path)?,
+            tar::EntryType::Regular | tar::EntryType::Continuous => {
+                budget.copy(&name, &mut entry, &out_path)?;
+            }
+            tar::EntryType::Symlink | tar::EntryType::Link => {
+                return Err(ExtractError::Rejected(format!("不支持链接条目: {}", name)));
+            }
+            _ => {
+                return Err(ExtractError::Rejected(format!("不支持的条目类型: {}", name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 复制目录
+fn copy_dir(src: &PathBuf, dst: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    if !dst.exists() {
+        fs::create_dir_all(dst)?;
+    }
+
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        if src_path.is_dir() {
+            copy_dir(&src_path, &dst_path)?;
+        } else {
+            fs::copy(&src_path, &dst_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 导出参数
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// 只导出指定分类
+    pub category: Option<String>,
+    /// `generated` 时一并打包数据库，用于完整备份
+    pub include: Option<String>,
+}
+
+/// 导出内容为 zip 压缩包
+///
+/// 压缩包先写入临时文件，再分块流式返回，不会整体读入内存；
+/// 单个分类的压缩包可以直接通过分类上传接口重新导入
+#[utoipa::path(
+    get,
+    path = "/api/admin/export",
+    tag = "admin",
+    summary = "把内容目录导出为 zip",
+    params(("category" = Option<String>, Query, description = "只导出指定分类"), ("include" = Option<String>, Query, description = "generated 时一并打包数据库")),
+    responses((status = 200, description = "成功", body = String, content_type = "application/zip")),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn export_content(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    query: web::Query<ExportQuery>,
+) -> HttpResponse {
+    // 验证管理员身份
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let category = query
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::to_string);
+    if let Some(category) = &category {
+        if category.contains(['/', '\\']) || category.starts_with('.') {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "分类名称无效"
+            }));
+        }
+        if !config.paths.content_dir.join(category).is_dir() {
+            return HttpResponse::NotFound().json(serde_json::json!({
+                "status": "error",
+                "message": format!("分类 '{}' 不存在", category)
+            }));
+        }
+    }
+
+    let databases = if query.include.as_deref() == Some("generated") {
+        vec![
+            config.paths.storage_db.clone(),
+            config.paths.comments_db.clone(),
+            config.paths.analytics_db.clone(),
+        ]
+    } else {
+        Vec::new()
+    };
+
+    let content_dir = config.paths.content_dir.clone();
+    let archive_path = std::env::temp_dir().join(format!("lf_blog_export_{}.zip", Uuid::new_v4()));
+    let build_path = archive_path.clone();
+    let result = web::block(move || {
+        build_export_archive(&content_dir, category.as_deref(), &databases, &build_path)
+            .map_err(|e| e.to_string())
+    })
+    .await;
+
+    if let Err(e) = result.map_err(|e| e.to_string()).and_then(|r| r) {
+        let _ = fs::remove_file(&archive_path);
+        return HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("导出失败: {}", e)
+        }));
+    }
+
+    let file = match fs::File::open(&archive_path) {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = fs::remove_file(&archive_path);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("读取导出文件失败: {}", e)
+            }));
+        }
+    };
+    // 文件已打开，提前删除临时文件，响应结束后由系统回收
+    let _ = fs::remove_file(&archive_path);
+
+    let filename = format!("blog-export-{}.zip", Utc::now().format("%Y%m%d"));
+    match actix_files::NamedFile::from_file(file, &filename) {
+        Ok(named) => named
+            .set_content_type("application/zip".parse().unwrap())
+            .set_content_disposition(actix_web::http::header::ContentDisposition {
+                disposition: actix_web::http::header::DispositionType::Attachment,
+                parameters: vec![actix_web::http::header::DispositionParam::Filename(filename)],
+            })
+            .into_response(&req),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("读取导出文件失败: {}", e)
+        })),
+    }
+}
+
+/// 将内容目录（或其中一个分类）打包为 zip，条目路径相对于内容目录；
+/// 传入的数据库会以一致的快照形式放在 `_data/` 下
+fn build_export_archive(
+    content_dir: &PathBuf,
+    category: Option<&str>,
+    databases: &[PathBuf],
+    dest: &PathBuf,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = zip::ZipWriter::new(fs::File::create(dest)?);
+    let options = zip::write::FileOptions::default();
+
+    let source_dir = match category {
+        Some(category) => {
+            zip.add_directory(format!("{}/", category), options)?;
+            content_dir.join(category)
+        }
+        None => content_dir.clone(),
+    };
+    add_dir_to_zip(&mut zip, &source_dir, content_dir, options)?;
+
+    for db_path in databases {
+        if !db_path.exists() {
+            continue;
+        }
+        let name = db_path.file_name().and_then(|n| n.to_str()).unwrap_or("database.db");
+        // 直接复制正在使用的 WAL 数据库可能不完整，用 VACUUM INTO 生成快照
+        let snapshot = std::env::temp_dir().join(format!("lf_blog_snapshot_{}.db", Uuid::new_v4()));
+        let result = add_database_snapshot(&mut zip, db_path, &snapshot, &format!("_data/{}", name), options);
+        let _ = fs::remove_file(&snapshot);
+        result?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// 生成数据库快照并写入压缩包
+fn add_database_snapshot(
+    zip: &mut zip::ZipWriter<fs::File>,
+    db_path: &PathBuf,
+    snapshot: &PathBuf,
+    name: &str,
+    options: zip::write::FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = rusqlite::Connection::open(db_path)?;
+    conn.execute("VACUUM INTO ?1", [snapshot.to_string_lossy()])?;
+    drop(conn);
+
+    zip.start_file(name, options.large_file(true))?;
+    std::io::copy(&mut fs::File::open(snapshot)?, zip)?;
+    Ok(())
+}
+
+/// 递归写入目录内容，按名称排序保证压缩包内容稳定
+pub(crate) fn add_dir_to_zip(
+    zip: &mut zip::ZipWriter<fs::File>,
+    dir: &PathBuf,
+    content_dir: &PathBuf,
+    options: zip::write::FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .strip_prefix(content_dir)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.is_dir() {
+            zip.add_directory(format!("{}/", name), options)?;
+            add_dir_to_zip(zip, &path, content_dir, options)?;
+        } else if path.is_file() {
+            zip.start_file(name, options)?;
+            std::io::copy(&mut fs::File::open(&path)?, zip)?;
+        }
+    }
+    Ok(())
+}
+
+/// 检查内容中指向不存在页面的站内链接
+#[utoipa::path(
+    get,
+    path = "/api/admin/content/broken-links",
+    tag = "admin",
+    summary = "检查内容中的失效链接",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "count": 1, "broken_links": [{"source_file": "content/wine/merlot/index.md", "url": "/wine/missing", "line": 12}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_broken_links(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+
+    // 只需要文章列表，不读取附件
+    let scanner = Scanner::new(config.paths.content_dir.clone())
+        .with_markdown(config.markdown.clone())
+        .with_publish_dates(config.site.future_posts, config.site_timezone());
+    let site_content = scanner.scan_listing()?;
+    let broken_links: Vec<serde_json::Value> = scanner
+        .check_broken_links(&site_content)
+        .into_iter()
+        .map(|link| {
+            let source_file = link
+                .source_file
+                .strip_prefix(&config.paths.content_dir)
+                .unwrap_or(&link.source_file);
+            serde_json::json!({
+                "source_file": source_file.to_string_lossy(),
+                "url": link.url,
+                "line": link.line
+            })
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "count": broken_links.len(),
+        "broken_links": broken_links
+    })))
+}
+
+/// 检查内容目录与数据库是否一致：孤立的附件、源文件已删除的页面和未编译的文章
+#[utoipa::path(
+    get,
+    path = "/api/admin/content/integrity",
+    tag = "admin",
+    summary = "检查内容目录与数据库是否一致",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "clean": false, "orphaned_attachments": ["3f2a9c.jpg"], "ghost_pages": ["wine-rose"], "uncompiled_items": ["wine/syrah"]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_content_integrity(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+
+    let report = Compiler::check_integrity(&config)?;
+    let uncompiled_items: Vec<String> = report
+        .uncompiled_items
+        .iter()
+        .map(|dir| dir.strip_prefix(&config.paths.content_dir).unwrap_or(dir).to_string_lossy().to_string())
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "clean": report.is_clean(),
+        "orphaned_attachments": report.orphaned_attachments,
+        "ghost_pages": report.ghost_pages,
+        "uncompiled_items": uncompiled_items
+    })))
+}
+
+/// 存储报告（包含重复的源文件）
+#[utoipa::path(
+    get,
+    path = "/api/admin/storage",
+    tag = "admin",
+    summary = "附件存储报告",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "report": {"total_attachments": 20, "duplicated_sources": [{"filename": "9f86d081_label.jpg", "original_name": "label.jpg", "file_size": 20480, "slugs": ["wine-merlot", "wine-syrah"], "source_paths": ["content/wine/merlot/label.jpg", "content/wine/syrah/label.jpg"], "potential_savings": 20480}], "potential_savings": 20480}}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_storage_report(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    // 验证管理员身份
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+    let storage_path = config
+        .paths
+        .storage_db
+        .to_string_lossy()
+        .to_string();
+
+    match StorageDB::new(&storage_path) {
+        Ok(storage) => match (storage.get_stats(), storage.get_duplicate_sources()) {
+            (Ok(stats), Ok(duplicates)) => {
+                let potential_savings: usize = duplicates.iter().map(|d| d.potential_savings).sum();
+                HttpResponse::Ok().json(serde_json::json!({
+                    "status": "success",
+                    "report": {
+                        "total_attachments": stats.total_attachments,
+                        "duplicated_sources": duplicates,
+                        "potential_savings": potential_savings
+                    }
+                }))
+            }
+            (Err(e), _) | (_, Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("获取存储报告失败: {}", e)
+            })),
+        },
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 获取文章的历史版本
+#[utoipa::path(
+    get,
+    path = "/api/admin/items/{slug}/versions",
+    tag = "admin",
+    summary = "文章的历史版本",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "slug": "wine-merlot", "versions": [{"id": 7, "slug": "wine-merlot", "page_type": "Item", "title": "Merlot", "content": "<p>A soft red wine.</p>", "raw_content": "A soft red wine.", "category": "wine", "version_number": 2, "created_at": "2024-05-01 10:00:00"}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_item_versions(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let slug = path.into_inner();
+    let result = get_storage(&config)
+        .map_err(|e| e.to_string())
+        .and_then(|storage| storage.get_page_versions(&slug).map_err(|e| e.to_string()));
+
+    match result {
+        Ok(versions) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "slug": slug,
+            "versions": versions
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("获取历史版本失败: {}", e)
+        })),
+    }
+}
+
+/// 恢复文章的历史版本
+#[utoipa::path(
+    post,
+    path = "/api/admin/items/{slug}/versions/{num}/restore",
+    tag = "admin",
+    summary = "恢复到历史版本",
+    params(("slug" = String, Path), ("num" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "已恢复到版本 2", "slug": "wine-merlot"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn restore_item_version(
+    req: actix_web::HttpRequest,
+    path: web::Path<(String, u64)>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let (slug, version) = path.into_inner();
+    let storage = match get_storage(&config) {
+        Ok(storage) => storage,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }));
+        }
+    };
+
+    match storage.restore_page_version(&slug, version) {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("已恢复到版本 {}", version),
+            "slug": slug
+        })),
+        Err(rusqlite::Error::QueryReturnedNoRows) => HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "版本不存在"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("恢复版本失败: {}", e)
+        })),
+    }
+}
+
+/// 手动执行 WAL checkpoint
+#[utoipa::path(
+    get,
+    path = "/api/admin/db/checkpoint",
+    tag = "maintenance",
+    summary = "把 WAL 合并进数据库文件",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "数据库 checkpoint 完成"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn db_checkpoint(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let result = web::block(move || StorageDB::new(&storage_path)?.checkpoint()).await;
+
+    match result {
+        Ok(Ok(())) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "数据库 checkpoint 完成"
+        })),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("数据库 checkpoint 失败: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 执行 VACUUM 整理数据库
+#[utoipa::path(
+    post,
+    path = "/api/admin/db/vacuum",
+    tag = "maintenance",
+    summary = "整理数据库文件",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "数据库整理完成，释放 4096 字节", "bytes_freed": 4096}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn db_vacuum(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let result = web::block(move || StorageDB::new(&storage_path)?.vacuum()).await;
+
+    match result {
+        Ok(Ok(freed)) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("数据库整理完成，释放 {} 字节", freed),
+            "bytes_freed": freed
+        })),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("数据库整理失败: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 执行数据库完整性检查
+#[utoipa::path(
+    get,
+    path = "/api/admin/db/integrity",
+    tag = "maintenance",
+    summary = "检查数据库完整性",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "数据库完整性检查通过", "ok": true}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn db_integrity(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let result = web::block(move || StorageDB::new(&storage_path)?.integrity_check()).await;
+
+    match result {
+        Ok(Ok(ok)) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": if ok { "数据库完整性检查通过" } else { "数据库完整性检查发现问题" },
+            "ok": ok
+        })),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("数据库完整性检查失败: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 执行 ANALYZE，更新查询优化器统计信息
+#[utoipa::path(
+    post,
+    path = "/api/admin/db/analyze",
+    tag = "maintenance",
+    summary = "更新查询统计信息",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "查询统计信息已更新"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn db_analyze(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let result = web::block(move || StorageDB::new(&storage_path)?.analyze()).await;
+
+    match result {
+        Ok(Ok(())) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "查询统计信息已更新"
+        })),
+        Ok(Err(e)) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("更新统计信息失败: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 清除所有评论限流记录
+#[utoipa::path(
+    post,
+    path = "/api/admin/comments/rate-limits/clear",
+    tag = "maintenance",
+    summary = "清除评论限流记录",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "已清除 2 个 IP 的评论限流记录", "cleared": 2}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn clear_comment_rate_limits(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    rate_limiter: web::Data<crate::comments::CommentRateLimiter>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let cleared = rate_limiter.clear();
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": format!("已清除 {} 个 IP 的评论限流记录", cleared),
+        "cleared": cleared
+    }))
+}
+
+/// Markdown 预览请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PreviewRequest {
+    pub content: String,
+    #[serde(default)]
+    pub frontmatter: Option<String>, // 不含 `---` 分隔线的 frontmatter
+    #[serde(default)]
+    pub slug: Option<String>, // 已有文章的 slug，用于解析 attachment/ 链接
+}
+
+/// 编辑器实时预览：与编译使用同一套 Markdown 解析和附件链接改写
+#[utoipa::path(
+    post,
+    path = "/api/admin/preview",
+    tag = "admin",
+    summary = "渲染 Markdown 预览",
+    request_body(content = PreviewRequest, example = json!({"content": "A **soft** red wine.", "frontmatter": "title: Merlot", "slug": "wine-merlot"})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "title": "Merlot", "html": "<p>A <strong>soft</strong> red wine.</p>", "missing_attachments": []}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn preview_markdown(
+    req: actix_web::HttpRequest,
+    body: web::Json<PreviewRequest>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let request = body.into_inner();
+    let source = match request.frontmatter.as_deref().map(str::trim) {
+        Some(frontmatter) if !frontmatter.is_empty() => format!("---\n{}\n---\n\n{}", frontmatter, request.content),
+        _ => request.content,
+    };
+    let config = config.get_ref().clone();
+
+    let result = web::block(move || {
+        // 已上传或已编译的附件：源文件名 -> 存储文件名
+        let attachment_map: Vec<(String, String)> = request
+            .slug
+            .as_deref()
+            .and_then(|slug| resolve_item_dir(&config, slug))
+            .and_then(|(item_slug, _)| {
+                let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+                StorageDB::new(&storage_path)
+                    .and_then(|storage| storage.get_attachment_list(&item_slug))
+                    .ok()
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(|attachment| (attachment.original_name, attachment.filename))
+            .collect();
+        render_preview(&config.markdown, &source, &attachment_map)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(preview)) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "title": preview.title,
+            "html": preview.html,
+            "missing_attachments": preview.missing_attachments
+        })),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": format!("Markdown 解析失败: {}", e)
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
+    }
+}
+
+/// 预览结果
+#[derive(Debug)]
+struct Preview {
+    title: String,
+    html: String,
+    missing_attachments: Vec<String>,
+}
+
+fn render_preview(
+    options: &MarkdownConfig,
+    source: &str,
+    attachment_map: &[(String, String)],
+) -> Result<Preview, String> {
+    let parsed = crate::markdown::parse_markdown_with_options(source, options).map_err(|e| e.to_string())?;
+    let (html, missing_attachments) = crate::markdown::rewrite_attachment_links(&parsed.html_content, attachment_map);
+    Ok(Preview {
+        title: parsed.get_title(),
+        html,
+        missing_attachments,
+    })
+}
+
+/// 上传文章附件（编辑器插图）
+#[utoipa::path(
+    post,
+    path = "/api/admin/items/{slug}/attachments",
+    tag = "admin",
+    summary = "上传附件",
+    params(("slug" = String, Path)),
+    request_body(content = inline(crate::openapi::UploadForm), content_type = "multipart/form-data"),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "附件上传成功", "attachment": {"filename": "9f86d081_label.jpg", "original_name": "label.jpg", "mime_type": "image/jpeg", "file_size": 20480, "url": "/attachment/9f86d081_label.jpg", "markdown": "![label](attachment/label.jpg)"}}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn upload_item_attachment(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    mut payload: Multipart,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let slug = path.into_inner();
+    let Some((item_slug, item_dir)) = resolve_item_dir(&config, &slug) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "文章不存在"
+        }));
+    };
 
-    let target_dir = category_dir.join(&item_name);
+    while let Some(Ok(mut field)) = payload.next().await {
+        let name = field.name();
+        if name != "file" {
+            continue;
+        }
 
-    // 如果目标目录已存在，先删除
-    if target_dir.exists() {
-        if let Err(e) = fs::remove_dir_all(&target_dir) {
-            let _ = fs::remove_dir_all(&temp_extract_dir);
+        let content_disposition = field.content_disposition();
+        let original_filename = content_disposition.get_filename().unwrap_or("upload").to_string();
+        let extension = std::path::Path::new(&original_filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        if !is_allowed_attachment_extension(&config.admin.attachment_extensions, &extension) {
             return HttpResponse::BadRequest().json(serde_json::json!({
                 "status": "error",
-                "message": format!("目标目录已存在且无法删除: {}", e)
+                "message": format!(
+                    "不支持的附件类型: {}（允许: {}）",
+                    original_filename,
+                    config.admin.attachment_extensions.join(", ")
+                )
             }));
         }
-    }
 
-    // 移动解压后的内容到目标位置
-    if let Err(e) = fs::rename(&item_dir, &target_dir) {
-        // 如果重命名失败，尝试复制后删除
-        if let Err(e2) = copy_dir(&item_dir, &target_dir) {
-            let _ = fs::remove_dir_all(&temp_extract_dir);
+        let attachment_dir = item_dir.join("attachment");
+        if let Err(e) = fs::create_dir_all(&attachment_dir) {
             return HttpResponse::InternalServerError().json(serde_json::json!({
                 "status": "error",
-                "message": format!("移动文件失败: {}，复制也失败: {}", e, e2)
+                "message": format!("创建附件目录失败: {}", e)
             }));
         }
-        let _ = fs::remove_dir_all(&item_dir);
-    }
 
-    // 清理临时目录
-    let _ = fs::remove_dir_all(&temp_extract_dir);
-
-    // 确保 attachment 目录存在
-    let attachment_dir = target_dir.join("attachment");
-    if !attachment_dir.exists() {
-        let _ = fs::create_dir_all(&attachment_dir);
-    }
+        // 生成不与已有文件重名的附件名
+        let item_name = item_dir.file_name().and_then(|n| n.to_str()).unwrap_or("item");
+        let stored_name = loop {
+            let candidate = format!("{}_{}.{}", item_name, &Uuid::new_v4().simple().to_string()[..8], extension);
+            if !attachment_dir.join(&candidate).exists() {
+                break candidate;
+            }
+        };
 
-    // 确保有 index.md 或同名的 .md 文件
-    let md_files: Vec<_> = match target_dir.read_dir() {
-        Ok(entries) => entries
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map(|ex| ex.to_str()).flatten() == Some("md"))
-            .collect(),
-        Err(_) => Vec::new(),
-    };
+        // 先写入隐藏的临时文件（扫描时会跳过），完成后再重命名
+        let temp_path = attachment_dir.join(format!(".{}.part", stored_name));
+        let mut temp_file = match fs::File::create(&temp_path) {
+            Ok(f) => f,
+            Err(e) => {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("创建临时文件失败: {}", e)
+                }));
+            }
+        };
+
+        use sha2::{Digest, Sha256};
+        let max_bytes = config.server.max_attachment_size_mb * 1024 * 1024;
+        let mut hasher = Sha256::new();
+        let mut file_size: u64 = 0;
+        while let Some(chunk) = field.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let _ = fs::remove_file(&temp_path);
+                    return HttpResponse::BadRequest().json(serde_json::json!({
+                        "status": "error",
+                        "message": format!("读取上传数据失败: {}", e)
+                    }));
+                }
+            };
+            file_size += chunk.len() as u64;
+            if file_size > max_bytes {
+                let _ = fs::remove_file(&temp_path);
+                return HttpResponse::PayloadTooLarge().json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("附件超过大小上限（{} MB）", config.server.max_attachment_size_mb)
+                }));
+            }
+            hasher.update(&chunk);
+            if let Err(e) = temp_file.write_all(&chunk) {
+                let _ = fs::remove_file(&temp_path);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("写入附件失败: {}", e)
+                }));
+            }
+        }
+        drop(temp_file);
 
-    if md_files.is_empty() {
-        // 创建默认的 index.md
-        let default_content = format!(
-            r#"---
-title: {}
-date: {}
-author: {}
-description: ""
-tags: []
-draft: false
----
+        let dest = attachment_dir.join(&stored_name);
+        if let Err(e) = fs::rename(&temp_path, &dest) {
+            let _ = fs::remove_file(&temp_path);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("保存附件失败: {}", e)
+            }));
+        }
 
-# {}
+        // 源文件名即生成的附件名，编译时 `attachment/{name}` 链接会被正确改写
+        let attachment = StoredAttachment {
+            id: format!("attachment-{}", stored_name),
+            slug: item_slug,
+            filename: stored_name.clone(),
+            original_name: stored_name,
+            mime_type: crate::scanner::get_mime_type(&extension),
+            file_data: Vec::new(),
+            file_size: file_size as usize,
+            updated_at: Utc::now().to_rfc3339(),
+            content_hash: format!("{:x}", hasher.finalize()),
+        };
+        if let Err(e) = store_uploaded_attachment(&config, &attachment, &dest) {
+            let _ = fs::remove_file(&dest);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": format!("保存附件失败: {}", e)
+            }));
+        }
 
-这里是文章的默认内容。
-"#,
-            item_name,
-            chrono::Utc::now().format("%Y-%m-%d").to_string(),
-            "Author",
-            item_name
-        );
-        let md_path = target_dir.join(format!("{}.md", item_name));
-        let _ = fs::write(&md_path, &default_content);
+        return HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "附件上传成功",
+            "attachment": attachment_json(&attachment)
+        }));
     }
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": format!("文章 '{}' 创建成功", item_name),
-        "item": item_name
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "status": "error",
+        "message": "未找到上传的文件"
     }))
 }
 
-/// 解压 ZIP 文件
-fn extract_zip(archive_path: &PathBuf, dest_dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let file = fs::File::open(archive_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
+/// 获取文章的附件列表
+#[utoipa::path(
+    get,
+    path = "/api/admin/items/{slug}/attachments",
+    tag = "admin",
+    summary = "文章的附件",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "count": 1, "attachments": [{"filename": "9f86d081_label.jpg", "original_name": "label.jpg", "mime_type": "image/jpeg", "file_size": 20480, "url": "/attachment/9f86d081_label.jpg", "markdown": "![label](attachment/label.jpg)"}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_item_attachments(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
 
-    for i in 0..archive.len() {
-        let mut entry = archive.by_index(i)?;
-        let out_path = dest_dir.join(entry.name());
+    let slug = path.into_inner();
+    let Some((item_slug, _)) = resolve_item_dir(&config, &slug) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "文章不存在"
+        }));
+    };
 
-        if entry.name().ends_with('/') {
-            fs::create_dir_all(&out_path)?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)?;
-                }
-            }
-            let mut out_file = fs::File::create(&out_path)?;
-            std::io::copy(&mut entry, &mut out_file)?;
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    match StorageDB::new(&storage_path).and_then(|storage| storage.get_attachment_list(&item_slug)) {
+        Ok(mut attachments) => {
+            // 上传记录与编译生成的记录指向同一个源文件，只列出一次
+            attachments.dedup_by(|a, b| a.original_name == b.original_name);
+            let attachments: Vec<serde_json::Value> = attachments.iter().map(attachment_json).collect();
+            HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "count": attachments.len(),
+                "attachments": attachments
+            }))
         }
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": e.to_string()
+        })),
     }
-    Ok(())
 }
 
-/// 解压 TAR.GZ 文件
-fn extract_tar(archive_path: &PathBuf, dest_dir: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let file = fs::File::open(archive_path)?;
+/// 删除文章附件：源文件以及数据库中指向它的所有记录
+#[utoipa::path(
+    delete,
+    path = "/api/admin/items/{slug}/attachments/{filename}",
+    tag = "admin",
+    summary = "删除附件",
+    params(("slug" = String, Path), ("filename" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "附件已删除", "removed": 1}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn delete_item_attachment(
+    req: actix_web::HttpRequest,
+    path: web::Path<(String, String)>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
 
-    // 解压 gzip
-    let gzip_decoder = flate2::read::GzDecoder::new(file);
-    let mut archive = tar::Archive::new(gzip_decoder);
+    let (slug, filename) = path.into_inner();
+    let Some((item_slug, item_dir)) = resolve_item_dir(&config, &slug) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "文章不存在"
+        }));
+    };
 
-    for entry in archive.entries()? {
-        let mut entry = entry?;
-        let out_path = dest_dir.join(entry.path()?);
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = match StorageDB::new(&storage_path) {
+        Ok(storage) => storage,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }));
+        }
+    };
+    let attachments = storage.get_attachment_list(&item_slug).unwrap_or_default();
+    let Some(target) = attachments.iter().find(|a| a.filename == filename) else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "附件不存在"
+        }));
+    };
 
-        if entry.header().entry_type() == tar::EntryType::Directory {
-            fs::create_dir_all(&out_path)?;
-        } else {
-            if let Some(parent) = out_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)?;
+    // 源文件名来自数据库，仍只接受单层文件名
+    if is_plain_name(&target.original_name) {
+        let source = item_dir.join("attachment").join(&target.original_name);
+        if let Err(e) = fs::remove_file(&source) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "status": "error",
+                    "message": format!("删除附件文件失败: {}", e)
+                }));
+            }
+        }
+    }
+
+    let mut removed = 0;
+    for copy in attachments.iter().filter(|a| a.original_name == target.original_name) {
+        if let Ok(Some(meta)) = storage.get_attachment_meta(&copy.filename) {
+            if let Some(file_path) = meta.file_path {
+                if let Err(e) = fs::remove_file(&file_path) {
+                    log::warn!("Failed to remove attachment file {}: {}", file_path, e);
                 }
             }
-            entry.unpack(&out_path)?;
+        }
+        if storage.delete_attachment(&copy.filename).unwrap_or(false) {
+            removed += 1;
         }
     }
-    Ok(())
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "附件已删除",
+        "removed": removed
+    }))
 }
 
-/// 复制目录
-fn copy_dir(src: &PathBuf, dst: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    if !dst.exists() {
-        fs::create_dir_all(dst)?;
-    }
+/// 附件列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct AttachmentsQuery {
+    pub slug: Option<String>, // 只列出指定文章的附件
+}
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+/// 列出附件元信息（不含文件内容）
+#[utoipa::path(
+    get,
+    path = "/api/admin/attachments",
+    tag = "admin",
+    summary = "列出所有附件的元信息（不含文件内容）",
+    params(("slug" = Option<String>, Query, description = "只列出指定文章的附件")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "count": 1, "attachments": [{"id": "wine-merlot-label.jpg", "slug": "wine-merlot", "filename": "9f86d081_label.jpg", "original_name": "label.jpg", "mime_type": "image/jpeg", "file_size": 20480, "updated_at": "2024-05-01T10:00:00+00:00"}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn list_attachments(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    query: web::Query<AttachmentsQuery>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let slug = query.slug.as_deref().filter(|slug| !slug.is_empty());
+    let attachments = get_storage(&config)?.get_attachment_metadata_list(slug)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "count": attachments.len(),
+        "attachments": attachments
+    })))
+}
 
-        if src_path.is_dir() {
-            copy_dir(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)?;
+/// 删除附件：数据库记录、文件系统存储模式下的副本，以及内容目录中引用它的源文件，
+/// 否则下次编译会重新导入
+#[utoipa::path(
+    delete,
+    path = "/api/admin/attachments/{filename}",
+    tag = "admin",
+    summary = "删除附件及内容目录中引用它的源文件",
+    params(("filename" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "附件已删除", "filename": "9f86d081_label.jpg"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn delete_attachment(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, BlogError> {
+    require_admin(&req, &config)?;
+    let filename = path.into_inner();
+    let storage = get_storage(&config)?;
+    let meta = storage
+        .get_attachment_meta(&filename)?
+        .ok_or_else(|| BlogError::NotFound("附件不存在".to_string()))?;
+
+    let mut files: Vec<String> = storage
+        .get_attachment_refs(&filename)?
+        .into_iter()
+        .map(|r| r.source_path)
+        .collect();
+    files.extend(meta.file_path);
+    for file in &files {
+        if let Err(e) = fs::remove_file(file) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(BlogError::Internal(format!("删除附件文件失败: {}", e)));
+            }
         }
     }
-    Ok(())
+    storage.delete_attachment(&filename)?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "附件已删除",
+        "filename": filename
+    })))
 }
 
-/// 导出内容
-pub async fn export_content(
-    req: actix_web::HttpRequest,
-    config: web::Data<Config>,
-    query: web::Query<Option<String>>,
-) -> impl Responder {
-    // 验证管理员身份
-    if !require_auth(&req, &config) {
-        return HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "未授权访问，请先登录"
-        }));
+/// 按 slug（category-item_name，可带 _draft 后缀）定位已存在的文章目录，
+/// 返回去掉草稿后缀的 slug 和目录路径
+fn resolve_item_dir(config: &Config, slug: &str) -> Option<(String, PathBuf)> {
+    let clean_slug = slug.strip_suffix("_draft").unwrap_or(slug);
+    let (category, item_name) = locate_item(config, slug).ok()?;
+    if !is_plain_name(&category) || !is_plain_name(&item_name) {
+        return None;
     }
-    let category_filter = query.into_inner();
+    let item_dir = config.paths.content_dir.join(category).join(item_name);
+    item_dir.is_dir().then(|| (clean_slug.to_string(), item_dir))
+}
 
-    // 收集要导出的文件
-    let mut files = Vec::new();
-    let mut total_size = 0;
+/// 单层、非隐藏的文件或目录名
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
 
-    if let Some(category) = category_filter {
-        // 导出指定分类
-        let category_dir = config.paths.content_dir.join(&category);
-        if category_dir.exists() {
-            collect_files(&category_dir, &mut files, &mut total_size, &config.paths.content_dir);
-        }
+/// 扩展名是否在允许上传的列表中（不区分大小写）
+fn is_allowed_attachment_extension(allowed: &[String], extension: &str) -> bool {
+    !extension.is_empty()
+        && allowed
+            .iter()
+            .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(extension))
+}
+
+/// 插入编辑器的 Markdown 片段：图片用图片语法，其他文件用链接
+fn attachment_markdown(name: &str, mime_type: &str) -> String {
+    let link = format!("attachment/{}", urlencoding::encode(name));
+    if mime_type.starts_with("image/") {
+        let alt = std::path::Path::new(name)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(name);
+        format!("![{}]({})", alt, link)
     } else {
-        // 导出全部内容
-        collect_files(&config.paths.content_dir, &mut files, &mut total_size, &config.paths.content_dir);
+        format!("[{}]({})", name, link)
     }
+}
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "内容导出信息",
-        "file_count": files.len(),
-        "data_size": total_size,
-        "files": files.iter().take(50).collect::<Vec<_>>(), // 只返回前50个文件信息
-        "truncated": files.len() > 50
-    }))
+fn attachment_json(attachment: &StoredAttachment) -> serde_json::Value {
+    serde_json::json!({
+        "filename": attachment.filename,
+        "original_name": attachment.original_name,
+        "mime_type": attachment.mime_type,
+        "file_size": attachment.file_size,
+        "url": format!("/attachment/{}", urlencoding::encode(&attachment.filename)),
+        "markdown": attachment_markdown(&attachment.original_name, &attachment.mime_type)
+    })
 }
 
-/// 收集目录中的文件
-fn collect_files(dir: &PathBuf, files: &mut Vec<String>, total_size: &mut usize, content_dir: &PathBuf) {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() {
-                    if let Ok(metadata) = fs::metadata(&path) {
-                        *total_size += metadata.len() as usize;
-                    }
-                    if let Some(rel_path) = path.strip_prefix(content_dir).ok() {
-                        files.push(rel_path.to_string_lossy().to_string());
-                    }
-                } else if path.is_dir() {
-                    collect_files(&path, files, total_size, content_dir);
-                }
-            }
+/// 按配置的存储后端保存上传的附件，与编译时的处理方式一致
+fn store_uploaded_attachment(
+    config: &Config,
+    attachment: &StoredAttachment,
+    source: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut storage = StorageDB::new(&config.paths.storage_db.to_string_lossy())?;
+    match config.storage.attachment_backend {
+        AttachmentBackend::Db => {
+            storage.save_attachments_from_files(&[(attachment.clone(), source.to_path_buf())])?;
+        }
+        AttachmentBackend::Filesystem => {
+            let dir = config.attachments_dir().join(&attachment.slug);
+            fs::create_dir_all(&dir)?;
+            let dest = dir.join(&attachment.filename);
+            fs::copy(source, &dest)?;
+            storage.save_attachment_records(&[(attachment.clone(), dest)])?;
         }
     }
+    Ok(())
 }
 
 /// 配置管理员路由
@@ -1570,20 +4210,48 @@ pub fn configure_admin_routes(cfg: &mut web::ServiceConfig) {
 
     // 需要认证的路由（使用简单 token 验证）
     cfg.route("/api/admin/overview", web::get().to(get_admin_overview))
+        .route("/api/admin/password", web::post().to(change_admin_password))
+        .route("/api/admin/api-keys", web::get().to(list_api_keys))
+        .route("/api/admin/api-keys", web::post().to(create_api_key))
+        .route("/api/admin/api-keys/{prefix}", web::delete().to(revoke_api_key))
         .route("/api/admin/categories", web::get().to(get_categories))
         .route("/api/admin/items", web::get().to(get_items))
         .route("/api/admin/items/{slug}", web::get().to(get_item))
         .route("/api/admin/categories", web::post().to(create_category))
         .route("/api/admin/categories/{slug}", web::delete().to(delete_category))
+        .route("/api/admin/categories/{slug}/rename", web::post().to(rename_category))
         .route("/api/admin/items", web::post().to(create_item))
+        .route("/api/admin/items/bulk", web::post().to(bulk_items))
         .route("/api/admin/items/{slug}", web::put().to(update_item))
         .route("/api/admin/items/{slug}", web::delete().to(delete_item))
         .route("/api/admin/items/{slug}/publish", web::post().to(publish_draft))
+        .route("/api/admin/items/{slug}/compile", web::post().to(compile_item))
+        .route("/api/admin/items/{slug}/versions", web::get().to(get_item_versions))
+        .route("/api/admin/items/{slug}/versions/{num}/restore", web::post().to(restore_item_version))
+        .route("/api/admin/items/{slug}/attachments", web::get().to(get_item_attachments))
+        .route("/api/admin/items/{slug}/attachments", web::post().to(upload_item_attachment))
+        .route("/api/admin/items/{slug}/attachments/{filename}", web::delete().to(delete_item_attachment))
+        .route("/api/admin/attachments", web::get().to(list_attachments))
+        .route("/api/admin/attachments/{filename}", web::delete().to(delete_attachment))
+        .route("/api/admin/preview", web::post().to(preview_markdown))
         .route("/api/admin/import", web::post().to(import_content))
         .route("/api/admin/export", web::get().to(export_content))
+        .route("/api/admin/content/broken-links", web::get().to(get_broken_links))
+        .route("/api/admin/content/integrity", web::get().to(get_content_integrity))
+        .route("/api/admin/storage", web::get().to(get_storage_report))
+        .route("/api/admin/db/checkpoint", web::get().to(db_checkpoint))
+        .route("/api/admin/db/vacuum", web::post().to(db_vacuum))
+        .route("/api/admin/db/integrity", web::get().to(db_integrity))
+        .route("/api/admin/db/analyze", web::post().to(db_analyze))
+        .route("/api/admin/comments/rate-limits/clear", web::post().to(clear_comment_rate_limits))
+        .route("/api/admin/compile/status", web::get().to(get_compile_status))
+        // 旧地址，与上面相同
+        .route("/api/recompile/status", web::get().to(get_compile_status))
         // 上传压缩包路由
+        .route("/api/admin/upload/full", web::post().to(upload_full_content))
         .route("/api/admin/upload/category", web::post().to(upload_category_package))
-        .route("/api/admin/upload/item/{category}", web::post().to(upload_item_package));
+        .route("/api/admin/upload/item/{category}", web::post().to(upload_item_package))
+        .route("/api/admin/sync", web::post().to(sync_content));
 
 // 页面路由
 cfg.route("/admin/login", web::get().to(admin_login_page))
@@ -1601,45 +4269,66 @@ cfg.route("/admin/login", web::get().to(admin_login_page))
 .route("/admin/items/new", web::get().to(admin_new_item_page))
 .route("/admin/items/new", web::post().to(admin_create_item_handler))
 .route("/admin/items/{slug}/edit", web::get().to(admin_edit_item_page))
+.route("/admin/items/{slug}/stats", web::get().to(admin_item_stats_page))
 .route("/admin/items/{slug}", web::post().to(admin_update_item_handler))
 .route("/admin/items/{slug}/edit", web::post().to(admin_update_item_handler))
-        .route("/admin/items/{slug}/publish", web::post().to(admin_publish_draft_handler))
-        .route("/admin/items/{slug}/delete", web::post().to(admin_delete_item_handler))
-        .route("/admin/analytics", web::get().to(admin_analytics_page))
-        .route("/admin/compile", web::get().to(admin_compile_page));
+.route("/admin/items/{slug}/publish", web::post().to(admin_publish_draft_handler))
+.route("/admin/items/{slug}/delete", web::post().to(admin_delete_item_handler))
+.route("/admin/analytics", web::get().to(admin_analytics_page))
+.route("/admin/attachments", web::get().to(admin_attachments_page))
+.route("/admin/comments", web::get().to(admin_comments_page))
+.route("/admin/comments/{id}/delete", web::post().to(admin_delete_comment_handler))
+.route("/admin/compile", web::get().to(admin_compile_page));
 }
 
 // ==================== 页面路由处理器 ====================
 
-/// 获取登录 cookie 中的 token
+/// 获取登录 cookie（或 Authorization: Bearer 头）中的 token
 fn get_admin_token(req: &actix_web::HttpRequest) -> Option<String> {
-    req.cookie("admin_token").map(|c| c.value().to_string())
+    if let Some(cookie) = req.cookie("admin_token") {
+        return Some(cookie.value().to_string());
+    }
+    req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
 }
 
 /// 验证并获取存储数据库连接
-fn get_storage(config: &Config) -> Result<StorageDB, actix_web::Error> {
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
-    StorageDB::new(&storage_path).map_err(|e| {
-        actix_web::error::ErrorInternalServerError(e.to_string())
-    })
+fn get_storage(config: &Config) -> Result<StorageDB, BlogError> {
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = StorageDB::new(&storage_path)?;
+    Ok(storage.with_max_versions(config.admin.max_versions))
 }
 
 /// 创建模板渲染器
-fn get_renderer(config: &Config) -> Result< crate::templates::TemplateRenderer, actix_web::Error> {
-    crate::templates::TemplateRenderer::new(config).map_err(|e| {
-        actix_web::error::ErrorInternalServerError(e.to_string())
-    })
+fn get_renderer(config: &Config) -> Result<crate::templates::TemplateRenderer, BlogError> {
+    crate::templates::TemplateRenderer::new(config)
 }
 
-/// 检查是否已登录
-fn require_auth(req: &actix_web::HttpRequest, config: &Config) -> bool {
-    if let Some(token) = get_admin_token(req) {
-        verify_admin_token(config, &token)
+/// 未登录时返回 [`BlogError::Unauthorized`]，供返回 `Result` 的接口直接使用 `?`
+fn require_admin(req: &actix_web::HttpRequest, config: &Config) -> Result<(), BlogError> {
+    if require_auth(req, config) {
+        Ok(())
     } else {
-        false
+        Err(BlogError::Unauthorized("未授权访问，请先登录".to_string()))
     }
 }
 
+/// 检查是否已登录，或请求带有有效的 API 密钥
+pub(crate) fn require_auth(req: &actix_web::HttpRequest, config: &Config) -> bool {
+    let Some(token) = get_admin_token(req) else {
+        return false;
+    };
+    // 运行中新增或撤销的密钥只记录在 AdminCredentials 里
+    let api_key_valid = match req.app_data::<web::Data<AdminCredentials>>() {
+        Some(credentials) => credentials.api_key_matches(&token),
+        None => api_key_matches(&config.server.api_keys, &token),
+    };
+    api_key_valid || verify_admin_token(config, &token)
+}
+
 /// 管理后台登录页面
 pub async fn admin_login_page(
     config: web::Data<Config>,
@@ -1653,14 +4342,28 @@ pub async fn admin_login_page(
 
 /// 管理后台登录处理
 pub async fn admin_login_handler(
+    req: actix_web::HttpRequest,
     form: actix_web::web::Form<std::collections::HashMap<String, String>>,
     config: web::Data<Config>,
+    throttle: web::Data<LoginThrottle>,
+    credentials: web::Data<AdminCredentials>,
 ) -> actix_web::Result<HttpResponse> {
     let password = form.get("password").cloned().unwrap_or_default();
     let renderer = get_renderer(&config)?;
+    let ip = crate::ip_filter::client_ip(&req);
+
+    if let Some(secs) = throttle.locked_for(&ip) {
+        let message = format!("登录失败次数过多，请 {} 秒后再试", secs);
+        let html = renderer.render_admin_login(Some(&message))?;
+        return Ok(HttpResponse::TooManyRequests()
+            .content_type("text/html; charset=utf-8")
+            .body(html));
+    }
 
-    if verify_admin_password(&config, &password) {
-        let token = generate_token();
+    if credentials.verify(&config, &password) {
+        throttle.record_success(&ip);
+        let token = create_admin_session(&config)
+            .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
         let html = renderer.render_admin_overview(
             &serde_json::json!({
                 "total_categories": 0,
@@ -1674,12 +4377,10 @@ pub async fn admin_login_handler(
         )?;
         Ok(HttpResponse::Ok()
             .content_type("text/html; charset=utf-8")
-            .cookie(actix_web::cookie::Cookie::build("admin_token", &token)
-                .path("/")
-                .max_age(actix_web::cookie::time::Duration::hours(24))
-                .finish())
+            .cookie(session_cookie(&config, &token))
             .body(html))
     } else {
+        throttle.record_failure(&ip);
         let html = renderer.render_admin_login(Some("密码错误"))?;
         Ok(HttpResponse::Ok()
             .content_type("text/html; charset=utf-8")
@@ -1688,7 +4389,18 @@ pub async fn admin_login_handler(
 }
 
 /// 退出登录
-pub async fn admin_logout(config: web::Data<Config>) -> actix_web::Result<HttpResponse> {
+pub async fn admin_logout(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some(token) = get_admin_token(&req) {
+        if let Ok(storage) = get_storage(&config) {
+            if let Err(e) = storage.delete_admin_session(&hash_token(&token)) {
+                log::warn!("Failed to delete admin session: {}", e);
+            }
+        }
+    }
+
     let renderer = get_renderer(&config)?;
     let html = renderer.render_admin_login(None)?;
     Ok(HttpResponse::Ok()
@@ -1731,7 +4443,7 @@ pub async fn admin_overview_page(
             .body(html));
     }
 
-    let storage = get_storage(&config).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let storage = get_storage(&config)?;
     let stats = storage.get_stats().unwrap_or_else(|_| crate::storage::SiteStats {
         total_categories: 0,
         total_items: 0,
@@ -1741,20 +4453,25 @@ pub async fn admin_overview_page(
     let last_compiled = storage.get_last_compiled().ok().flatten();
 
     // 获取评论统计
-    let comments_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let comments_path = config.paths.comments_db.to_string_lossy().to_string();
     let comment_stats = crate::comments::CommentDB::new(&comments_path)
         .and_then(|db| db.get_comment_stats())
         .unwrap_or_default();
     let total_comments = comment_stats.iter().map(|(_, count)| count).sum::<i64>();
 
+    let renderer = get_renderer(&config)?;
+    let compiled_fingerprint = storage.get_template_fingerprint().ok().flatten();
     let overview = serde_json::json!({
         "total_categories": stats.total_categories,
         "total_items": stats.total_items,
         "total_attachments": stats.total_attachments,
-        "total_comments": total_comments
+        "total_comments": total_comments,
+        "template_source": renderer.source(),
+        "template_fingerprint": renderer.fingerprint(),
+        "templates_stale": compiled_fingerprint.as_deref() != Some(renderer.fingerprint()),
+        "last_vacuum_at": storage.get_metadata("last_vacuum_at").ok().flatten()
     });
 
-    let renderer = get_renderer(&config)?;
     let html = renderer.render_admin_overview(&overview, last_compiled.as_deref(), None, true)?;
     Ok(HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
@@ -1765,6 +4482,7 @@ pub async fn admin_overview_page(
 pub async fn admin_categories_page(
     req: actix_web::HttpRequest,
     config: web::Data<Config>,
+    query: web::Query<std::collections::HashMap<String, String>>,
 ) -> actix_web::Result<HttpResponse> {
     if !require_auth(&req, &config) {
         return Ok(HttpResponse::SeeOther()
@@ -1772,8 +4490,8 @@ pub async fn admin_categories_page(
             .body(String::new()));
     }
 
-    let storage = get_storage(&config).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-    let category_pages = storage.get_pages_by_type(PageType::Category).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let storage = get_storage(&config)?;
+    let category_pages = storage.get_pages_by_type(PageType::Category).map_err(BlogError::from)?;
     let categories: Vec<serde_json::Value> = category_pages
         .iter()
         .map(|page| {
@@ -1787,7 +4505,9 @@ pub async fn admin_categories_page(
         .collect();
 
     let renderer = get_renderer(&config)?;
-    let html = renderer.render_admin_categories(&categories, None, true)?;
+    let message = query.get("message").map(String::as_str).filter(|m| !m.is_empty());
+    let success = query.get("success").map_or(true, |v| v == "true");
+    let html = renderer.render_admin_categories(&categories, message, success)?;
     Ok(HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
         .body(html))
@@ -1826,60 +4546,10 @@ pub async fn admin_create_category_handler(
     let name = form.get("name").cloned().unwrap_or_default();
     let description = form.get("description").cloned();
 
-    if name.is_empty() {
-        let renderer = get_renderer(&config)?;
-        let html = renderer.render_admin_categories(&[], Some("分类名称不能为空"), false)?;
-        return Ok(HttpResponse::Ok()
-            .content_type("text/html; charset=utf-8")
-            .body(html));
-    }
-
-    // 创建分类目录
-    let slug = name.to_lowercase().replace(' ', "-");
-    let category_dir = config.paths.content_dir.join(&name);
-
-    if category_dir.exists() {
-        let renderer = get_renderer(&config)?;
-        let html = renderer.render_admin_categories(&[], Some("分类已存在"), false)?;
-        return Ok(HttpResponse::Ok()
-            .content_type("text/html; charset=utf-8")
-            .body(html));
-    }
-
-    if let Err(e) = std::fs::create_dir_all(&category_dir) {
-        let renderer = get_renderer(&config)?;
-        let html = renderer.render_admin_categories(&[], Some(&format!("创建分类目录失败: {}", e)), false)?;
-        return Ok(HttpResponse::Ok()
-            .content_type("text/html; charset=utf-8")
-            .body(html));
-    }
-
-    // 创建 index.md
-    let index_content = format!(
-        r#"---
-description: {}
----
-
-# {}
-
-这里是 {} 的介绍内容.
-"#,
-        description.clone().unwrap_or_default(),
-        name,
-        name
-    );
-    let index_path = category_dir.join("index.md");
-    if let Err(e) = std::fs::write(&index_path, &index_content) {
-        let renderer = get_renderer(&config)?;
-        let html = renderer.render_admin_categories(&[], Some(&format!("创建分类索引文件失败: {}", e)), false)?;
-        return Ok(HttpResponse::Ok()
-            .content_type("text/html; charset=utf-8")
-            .body(html));
+    match add_category(&config, &name, description.as_deref()) {
+        Ok(_) => Ok(redirect_with_message("/admin/categories", "分类创建成功", true)),
+        Err(e) => Ok(redirect_with_message("/admin/categories", &e.to_string(), false)),
     }
-
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/categories"))
-        .body(String::new()))
 }
 
 /// 删除分类处理
@@ -1894,12 +4564,10 @@ pub async fn admin_delete_category_handler(
             .body(String::new()));
     }
 
-    let slug = path.into_inner();
-    let _ = delete_category(req, web::Path::from(slug), config).await;
-
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/categories"))
-        .body(String::new()))
+    match remove_category(&config, &path.into_inner()) {
+        Ok(()) => Ok(redirect_with_message("/admin/categories", "分类删除成功", true)),
+        Err(e) => Ok(redirect_with_message("/admin/categories", &e.to_string(), false)),
+    }
 }
 
 /// 管理后台文章列表页面
@@ -1915,8 +4583,12 @@ pub async fn admin_items_page(
     }
 
     let show_drafts = query.get("drafts").map(|v| v == "true").unwrap_or(false);
+    let message = query.get("message").map(String::as_str).filter(|m| !m.is_empty());
+    let success = query.get("success").map_or(true, |v| v == "true");
     let storage = get_storage(&config)?;
     let all_pages = storage.get_all_pages().unwrap_or_default();
+    let view_counts = storage.get_view_counts().unwrap_or_default();
+    let timezone = config.site_timezone();
 
     let items: Vec<serde_json::Value> = all_pages
         .iter()
@@ -1936,18 +4608,33 @@ pub async fn admin_items_page(
                 "title": page.title,
                 "category": category,
                 "is_draft": is_draft,
-                "updated_at": page.updated_at
+                "updated_at": page.updated_at,
+                "is_scheduled": page.is_scheduled(),
+                "publish_at": page.publish_at,
+                "publish_at_local": page.publish_at.as_deref().and_then(|at| format_publish_time(at, timezone)),
+                "view_count": view_counts.get(&page.slug).copied().unwrap_or(0)
             })
         })
         .collect();
 
     let renderer = get_renderer(&config)?;
-    let html = renderer.render_admin_items(&items, show_drafts, None, true)?;
+    let html = renderer.render_admin_items(&items, show_drafts, message, success)?;
     Ok(HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
         .body(html))
 }
 
+/// 将数据库中的 UTC 发布时间转换为站点时区显示
+fn format_publish_time(publish_at: &str, timezone: chrono_tz::Tz) -> Option<String> {
+    let utc = chrono::NaiveDateTime::parse_from_str(publish_at, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some(
+        utc.and_utc()
+            .with_timezone(&timezone)
+            .format("%Y-%m-%d %H:%M %Z")
+            .to_string(),
+    )
+}
+
 /// 新建文章页面
 pub async fn admin_new_item_page(
     req: actix_web::HttpRequest,
@@ -1959,8 +4646,8 @@ pub async fn admin_new_item_page(
             .body(String::new()));
     }
 
-    let storage = get_storage(&config).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
-    let category_pages = storage.get_pages_by_type(PageType::Category).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let storage = get_storage(&config)?;
+    let category_pages = storage.get_pages_by_type(PageType::Category).map_err(BlogError::from)?;
     let categories: Vec<serde_json::Value> = category_pages
         .iter()
         .map(|page| {
@@ -2001,12 +4688,13 @@ pub async fn admin_create_item_handler(
         tags: form.get("tags").map(|t| t.split(',').map(|s| s.trim().to_string()).collect()),
         is_draft: form.get("is_draft").map(|v| v == "true"),
     };
+    let slug = match add_item(&config, request) {
+        Ok(slug) => slug,
+        Err(e) => return Ok(redirect_to_items(&e.to_string(), false)),
+    };
 
-    let _ = create_item(req, web::Json(request), config).await;
-
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/items"))
-        .body(String::new()))
+    let (message, success) = compile_outcome("文章创建成功", compile_single_item(&config, &slug).await);
+    Ok(redirect_to_items(&message, success))
 }
 
 /// 编辑文章页面
@@ -2022,14 +4710,15 @@ pub async fn admin_edit_item_page(
     }
 
     let slug = path.into_inner();
-    let storage = get_storage(&config).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let storage = get_storage(&config)?;
 
     match storage.get_page(&slug) {
         Ok(Some(page)) => {
             let is_draft = slug.ends_with("_draft");
-            let clean_slug = if is_draft { &slug[..slug.len() - 6] } else { &slug };
-            let parts: Vec<&str> = clean_slug.splitn(2, '-').collect();
-            let item_name = if parts.len() >= 2 { parts[1].to_string() } else { slug.clone() };
+            let item_name = match &page.item_name {
+                Some(item_name) => item_name.clone(),
+                None => locate_item(&config, &slug).map(|(_, item_name)| item_name).unwrap_or_else(|_| slug.clone()),
+            };
 
             let category = page.category.clone().unwrap_or_default();
 
@@ -2041,7 +4730,8 @@ pub async fn admin_edit_item_page(
                 "title": page.title,
                 "category": category,
                 "item_name": item_name,
-                "content": page.content,
+                // 旧数据库中没有原文时退回到编译后的 HTML
+                "content": page.raw_content.as_deref().unwrap_or(&page.content),
                 "is_draft": is_draft,
                 "date": "",
                 "author": "",
@@ -2050,7 +4740,7 @@ pub async fn admin_edit_item_page(
                 "updated_at": page.updated_at
             });
 
-            let category_pages = storage.get_pages_by_type(PageType::Category).map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+            let category_pages = storage.get_pages_by_type(PageType::Category).map_err(BlogError::from)?;
             let categories: Vec<serde_json::Value> = category_pages
                 .iter()
                 .map(|page| {
@@ -2072,10 +4762,38 @@ pub async fn admin_edit_item_page(
                 .content_type("text/html; charset=utf-8")
                 .body("文章不存在"))
         }
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError(e.to_string()))
-        }
+        Err(e) => Err(BlogError::from(e).into()),
+    }
+}
+
+/// 管理后台单篇文章访问统计页面，`?days=` 指定统计天数
+pub async fn admin_item_stats_page(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<crate::analytics::PageAnalyticsQuery>,
+    config: web::Data<Config>,
+) -> actix_web::Result<HttpResponse> {
+    if !require_auth(&req, &config) {
+        return Ok(HttpResponse::SeeOther()
+            .append_header((actix_web::http::header::LOCATION, "/admin/login"))
+            .body(String::new()));
     }
+
+    let stats = match crate::analytics::load_page_analytics(&config, &path.into_inner(), query.days) {
+        Ok(stats) => stats,
+        Err(BlogError::NotFound(_)) => {
+            return Ok(HttpResponse::NotFound()
+                .content_type("text/html; charset=utf-8")
+                .body("文章不存在"))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let renderer = get_renderer(&config)?;
+    let html = renderer.render_admin_item_stats(&stats)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
 }
 
 /// 更新文章处理
@@ -2102,12 +4820,14 @@ pub async fn admin_update_item_handler(
         tags: form.get("tags").map(|t| t.split(',').map(|s| s.trim().to_string()).collect()),
         is_draft: form.get("is_draft").map(|v| v == "true"),
     };
+    let new_slug = match edit_item(&config, &path.into_inner(), request) {
+        Ok(slug) => slug,
+        Err(e) => return Ok(redirect_to_items(&e.to_string(), false)),
+    };
 
-    let _ = update_item(req, path, web::Json(request), config).await;
-
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/items"))
-        .body(String::new()))
+    // 保存后立即重新生成该文章及列出它的页面
+    let (message, success) = compile_outcome("文章更新成功", compile_single_item(&config, &new_slug).await);
+    Ok(redirect_to_items(&message, success))
 }
 
 /// 发布草稿处理
@@ -2122,11 +4842,13 @@ pub async fn admin_publish_draft_handler(
             .body(String::new()));
     }
 
-    let _ = publish_draft(req, path, config).await;
+    let slug = path.into_inner();
+    if let Err(e) = publish_item_draft(&config, &slug) {
+        return Ok(redirect_to_items(&e.to_string(), false));
+    }
 
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/items"))
-        .body(String::new()))
+    let (message, success) = compile_outcome("草稿已发布", compile_single_item(&config, &slug).await);
+    Ok(redirect_to_items(&message, success))
 }
 
 /// 删除文章处理
@@ -2141,11 +4863,166 @@ pub async fn admin_delete_item_handler(
             .body(String::new()));
     }
 
-    let _ = delete_item(req, path, config).await;
+    let slug = path.into_inner();
+    // 删除后数据库中就没有记录了，先取出分类
+    let location = locate_item(&config, &slug);
+    if let Err(e) = remove_item(&config, &slug) {
+        return Ok(redirect_to_items(&e.to_string(), false));
+    }
+
+    let result = match location {
+        Ok((category, _)) => compile_removed_single_item(&config, category, &slug).await,
+        Err(e) => Err(e),
+    };
+    let (message, success) = compile_outcome("文章删除成功", result);
+    Ok(redirect_to_items(&message, success))
+}
+
+/// 编译进度 API
+#[utoipa::path(
+    get,
+    path = "/api/admin/compile/status",
+    tag = "system",
+    summary = "查询编译进度和上次编译结果（旧地址 /api/recompile/status）",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"running": true, "is_compiling": true, "phase": "rendering", "done": 4, "total": 12, "last_result": "Compiled 12 pages", "last_success": true, "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21", "started_at": "2024-05-01T10:00:00+00:00"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_compile_status(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    state: web::Data<Mutex<CompilerState>>,
+) -> impl Responder {
+    if !require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let state = match state.lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let progress = state.progress.as_ref();
+    HttpResponse::Ok().json(serde_json::json!({
+        "running": state.is_compiling,
+        "is_compiling": state.is_compiling,
+        "phase": progress.map(|p| p.phase),
+        "done": progress.map(|p| p.done),
+        "total": progress.map(|p| p.total),
+        "last_result": state.last_compile_result,
+        "last_success": state.last_success,
+        "job_id": state.job_id,
+        "started_at": state.started_at,
+    }))
+}
+
+/// 附件管理页面
+pub async fn admin_attachments_page(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    query: web::Query<AttachmentsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    if !require_auth(&req, &config) {
+        return Ok(HttpResponse::SeeOther()
+            .append_header((actix_web::http::header::LOCATION, "/admin/login"))
+            .body(String::new()));
+    }
+
+    let slug = query.slug.as_deref().filter(|slug| !slug.is_empty());
+    let storage = get_storage(&config)?;
+    let attachments = storage.get_attachment_metadata_list(slug).map_err(BlogError::from)?;
+    // 筛选下拉框列出所有带附件的文章
+    let mut slugs: Vec<String> = match slug {
+        Some(_) => storage.get_attachment_metadata_list(None).map_err(BlogError::from)?,
+        None => attachments.clone(),
+    }
+    .into_iter()
+    .map(|a| a.slug)
+    .collect();
+    slugs.dedup();
+
+    let renderer = get_renderer(&config)?;
+    let html = renderer.render_admin_attachments(&attachments, &slugs, slug)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
+}
+
+/// 评论管理页面查询参数
+#[derive(Debug, Deserialize)]
+pub struct CommentsQuery {
+    pub slug: Option<String>, // 只列出指定文章的评论
+    pub message: Option<String>,
+    pub success: Option<bool>,
+}
+
+/// 评论管理页面：按时间倒序列出评论，新评论通知中的链接指向这里
+pub async fn admin_comments_page(
+    req: actix_web::HttpRequest,
+    config: web::Data<Config>,
+    query: web::Query<CommentsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    if !require_auth(&req, &config) {
+        return Ok(HttpResponse::SeeOther()
+            .append_header((actix_web::http::header::LOCATION, "/admin/login"))
+            .body(String::new()));
+    }
+
+    let slug = query.slug.as_deref().filter(|slug| !slug.is_empty());
+    let comments_db = crate::comments::CommentDB::new(&config.paths.comments_db.to_string_lossy()).map_err(BlogError::from)?;
+    let all_comments = comments_db.get_all_comments().map_err(BlogError::from)?;
+    let titles: HashMap<String, String> = get_storage(&config)?
+        .get_pages_by_type(PageType::Item)
+        .map_err(BlogError::from)?
+        .into_iter()
+        .map(|page| (page.slug, page.title))
+        .collect();
+
+    // 筛选下拉框列出所有有评论的文章
+    let mut slugs: Vec<String> = all_comments.iter().map(|comment| comment.slug.clone()).collect();
+    slugs.sort();
+    slugs.dedup();
+    let comments: Vec<serde_json::Value> = all_comments
+        .iter()
+        .filter(|comment| slug.map_or(true, |slug| comment.slug == slug))
+        .map(|comment| {
+            serde_json::json!({
+                "comment": comment,
+                "title": titles.get(&comment.slug).unwrap_or(&comment.slug),
+                "muted": config.notifications.muted_slugs.contains(&comment.slug),
+                "email": comment.email // 评论接口不返回邮箱，只在后台显示
+            })
+        })
+        .collect();
+
+    let message = query.message.as_deref().filter(|message| !message.is_empty());
+    let renderer = get_renderer(&config)?;
+    let html = renderer.render_admin_comments(&comments, &slugs, slug, message, query.success.unwrap_or(true))?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html))
+}
+
+/// 删除评论（连同其回复）后返回评论管理页面
+pub async fn admin_delete_comment_handler(
+    req: actix_web::HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+) -> actix_web::Result<HttpResponse> {
+    if !require_auth(&req, &config) {
+        return Ok(HttpResponse::SeeOther()
+            .append_header((actix_web::http::header::LOCATION, "/admin/login"))
+            .body(String::new()));
+    }
 
-    Ok(HttpResponse::SeeOther()
-        .append_header((actix_web::http::header::LOCATION, "/admin/items"))
-        .body(String::new()))
+    let deleted = crate::comments::CommentDB::new(&config.paths.comments_db.to_string_lossy())
+        .and_then(|db| db.delete_comment(&path.into_inner()));
+    Ok(match deleted {
+        Ok(true) => redirect_with_message("/admin/comments", "评论已删除", true),
+        Ok(false) => redirect_with_message("/admin/comments", "评论不存在", false),
+        Err(e) => redirect_with_message("/admin/comments", &format!("删除评论失败: {}", e), false),
+    })
 }
 
 /// 编译发布页面
@@ -2164,4 +5041,1195 @@ pub async fn admin_compile_page(
     Ok(HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
         .body(html))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_admin_password_hash() {
+        let mut config = Config::default();
+        config.server.admin_password = String::new();
+        config.server.admin_password_hash = Some(hash_admin_password("s3cret").unwrap());
+
+        assert!(verify_admin_password(&config, "s3cret"));
+        assert!(!verify_admin_password(&config, "admin"));
+        assert!(!verify_admin_password(&config, ""));
+
+        config.server.admin_password_hash = None;
+        config.server.admin_password = "plain".to_string();
+        assert!(verify_admin_password(&config, "plain"));
+        assert!(!verify_admin_password(&config, "plai"));
+    }
+
+    #[actix_web::test]
+    async fn test_change_admin_password() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_change_password_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let config_path = root.join("lf_blog.toml");
+        let mut config = Config::default();
+        config.server.admin_password = "old-password".to_string();
+        config.paths.storage_db = root.join("storage.db");
+        config.save_to(&config_path).unwrap();
+        let token = create_admin_session(&config).unwrap();
+        let other_token = create_admin_session(&config).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(LoginThrottle::new()))
+                .app_data(web::Data::new(AdminCredentials::new(&config_path, &config)))
+                .route("/api/admin/login", web::post().to(admin_login))
+                .route("/api/admin/password", web::post().to(change_admin_password)),
+        )
+        .await;
+        let change = |token: &str, current: &str, new: &str, confirm: &str| {
+            actix_test::TestRequest::post()
+                .uri("/api/admin/password")
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .set_json(serde_json::json!({
+                    "current_password": current,
+                    "new_password": new,
+                    "confirm_password": confirm
+                }))
+                .to_request()
+        };
+        let login = |password: &str| {
+            actix_test::TestRequest::post()
+                .uri("/api/admin/login")
+                .set_json(serde_json::json!({ "password": password }))
+                .to_request()
+        };
+
+        let resp = actix_test::call_service(&app, change(&token, "wrong-password", "new-password", "new-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        for (current, new, confirm) in [
+            ("old-password", "short", "short"),
+            ("old-password", "new-password", "new-passw0rd"),
+        ] {
+            let resp = actix_test::call_service(&app, change(&token, current, new, confirm)).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        }
+        let resp = actix_test::call_service(&app, change("bogus", "old-password", "new-password", "new-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let resp = actix_test::call_service(&app, change(&token, "old-password", "new-password", "new-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // 所有会话失效，只能用新密码登录
+        assert!(!verify_admin_token(&config, &token));
+        assert!(!verify_admin_token(&config, &other_token));
+        let resp = actix_test::call_service(&app, login("old-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        let resp = actix_test::call_service(&app, login("new-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // 配置文件中只保存哈希
+        let saved = Config::load_from(&config_path).unwrap();
+        assert!(saved.server.admin_password.is_empty());
+        assert!(verify_admin_password(&saved, "new-password"));
+
+        // 连续输错当前密码后锁定，返回 429 和 Retry-After
+        let token = create_admin_session(&config).unwrap();
+        for _ in 0..MAX_LOGIN_FAILURES {
+            let resp = actix_test::call_service(&app, change(&token, "wrong-password", "x-password", "x-password")).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        }
+        let resp = actix_test::call_service(&app, change(&token, "new-password", "x-password", "x-password")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers().contains_key(actix_web::http::header::RETRY_AFTER));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_api_keys() {
+        use actix_web::http::{Method, StatusCode};
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_api_keys_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let config_path = root.join("lf_blog.toml");
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        config.save_to(&config_path).unwrap();
+        // 命令行生成的密钥在启动时从配置读取
+        let (cli_key, _) = add_api_key(&config_path).unwrap();
+        let config = Config::load_from(&config_path).unwrap();
+        let token = create_admin_session(&config).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(AdminCredentials::new(&config_path, &config)))
+                .route("/api/admin/api-keys", web::get().to(list_api_keys))
+                .route("/api/admin/api-keys", web::post().to(create_api_key))
+                .route("/api/admin/api-keys/{prefix}", web::delete().to(revoke_api_key)),
+        )
+        .await;
+        let request = |method: actix_web::http::Method, uri: &str, key: &str| {
+            actix_test::TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", key)))
+                .to_request()
+        };
+
+        let resp = actix_test::call_service(&app, request(Method::GET, "/api/admin/api-keys", &cli_key)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = actix_test::call_service(&app, request(Method::GET, "/api/admin/api-keys", "lfb_bogus")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // 新密钥只在响应中出现一次，配置中只保存哈希
+        let resp = actix_test::call_service(&app, request(Method::POST, "/api/admin/api-keys", &token)).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        let key = body["key"].as_str().unwrap().to_string();
+        let id = body["id"].as_str().unwrap().to_string();
+        assert!(key.starts_with(API_KEY_PREFIX));
+        let saved = Config::load_from(&config_path).unwrap();
+        assert_eq!(saved.server.api_keys.len(), 2);
+        assert!(saved.server.api_keys.contains(&hash_token(&key)));
+        assert!(!fs::read_to_string(&config_path).unwrap().contains(&key));
+
+        let resp = actix_test::call_service(&app, request(Method::GET, "/api/admin/api-keys", &key)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert!(body["keys"].as_array().unwrap().contains(&serde_json::json!(id)));
+
+        // 前缀太短或不存在
+        let resp = actix_test::call_service(&app, request(Method::DELETE, "/api/admin/api-keys/abc", &key)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let resp = actix_test::call_service(&app, request(Method::DELETE, "/api/admin/api-keys/zzzzzzzz", &key)).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        // 撤销后立即失效，另一个密钥不受影响
+        let resp = actix_test::call_service(&app, request(Method::DELETE, &format!("/api/admin/api-keys/{}", id), &cli_key)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = actix_test::call_service(&app, request(Method::GET, "/api/admin/api-keys", &key)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = actix_test::call_service(&app, request(Method::GET, "/api/admin/api-keys", &cli_key)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(Config::load_from(&config_path).unwrap().server.api_keys.len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_login_throttle_lockout() {
+        let throttle = LoginThrottle::new();
+        for _ in 0..MAX_LOGIN_FAILURES - 1 {
+            throttle.record_failure("10.0.0.1");
+        }
+        assert!(throttle.locked_for("10.0.0.1").is_none());
+
+        throttle.record_failure("10.0.0.1");
+        assert!(throttle.locked_for("10.0.0.1").is_some());
+        assert!(throttle.locked_for("10.0.0.2").is_none());
+
+        throttle.record_success("10.0.0.1");
+        assert!(throttle.locked_for("10.0.0.1").is_none());
+    }
+
+    #[test]
+    fn test_login_throttle_evicts_expired_entries() {
+        use std::time::{Duration, Instant};
+
+        let throttle = LoginThrottle::new();
+        let start = Instant::now();
+        for _ in 0..MAX_LOGIN_FAILURES {
+            throttle.record_failure_at("10.0.0.1", start);
+        }
+        throttle.record_failure_at("10.0.0.2", start);
+        assert_eq!(throttle.attempts.lock().unwrap().len(), 2);
+
+        // 锁定到期后 10.0.0.1 被清除，10.0.0.2 的失败仍在有效期内
+        let later = start + Duration::from_secs(LOGIN_LOCKOUT_SECS);
+        throttle.record_failure_at("10.0.0.3", later);
+        let attempts = throttle.attempts.lock().unwrap();
+        assert!(!attempts.contains_key("10.0.0.1"));
+        assert_eq!(attempts["10.0.0.2"].failures, 1);
+        drop(attempts);
+
+        // 超出有效期后失败次数作废，重新计数
+        let expired = start + Duration::from_secs(LOGIN_FAILURE_WINDOW_SECS);
+        throttle.record_failure_at("10.0.0.3", expired);
+        let attempts = throttle.attempts.lock().unwrap();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts["10.0.0.3"].failures, 2);
+    }
+
+    #[test]
+    fn test_format_publish_time() {
+        let shanghai: chrono_tz::Tz = "Asia/Shanghai".parse().unwrap();
+        assert_eq!(
+            format_publish_time("2025-11-30 16:00:00", shanghai).as_deref(),
+            Some("2025-12-01 00:00 CST")
+        );
+        assert!(format_publish_time("soon", shanghai).is_none());
+    }
+
+    #[test]
+    fn test_compiler_state_single_job() {
+        use crate::compiler::CompilePhase;
+
+        let mut state = CompilerState::new();
+        let job_id = state.try_start().unwrap();
+        assert!(state.is_compiling);
+        assert!(state.started_at.is_some());
+
+        // 运行中再次请求只返回当前任务
+        assert_eq!(state.try_start(), Err(job_id.clone()));
+
+        let progress = CompileProgress { phase: CompilePhase::Rendering, done: 3, total: 10 };
+        state.set_progress(progress.clone());
+        assert_eq!(state.progress, Some(progress.clone()));
+
+        state.finish("done".to_string(), true);
+        assert!(!state.is_compiling);
+        assert_eq!(state.last_compile_result.as_deref(), Some("done"));
+        // 结束后迟到的进度不再显示
+        state.set_progress(progress);
+        assert!(state.progress.is_none());
+        assert_ne!(state.try_start().unwrap(), job_id);
+    }
+
+    #[actix_web::test]
+    async fn test_compile_status_routes() {
+        use actix_web::{test as actix_test, App};
+
+        let db_path = PathBuf::from("/tmp/test_compile_status_routes.db");
+        let _ = fs::remove_file(&db_path);
+        let mut config = Config::default();
+        config.paths.storage_db = db_path.clone();
+        let token = create_admin_session(&config).unwrap();
+        let state = web::Data::new(Mutex::new(CompilerState::new()));
+        let job_id = state.lock().unwrap().try_start().unwrap();
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(state.clone())
+                .configure(configure_admin_routes),
+        )
+        .await;
+
+        for uri in ["/api/admin/compile/status", "/api/recompile/status"] {
+            let req = actix_test::TestRequest::get().uri(uri).to_request();
+            let resp = actix_test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED, "{}", uri);
+
+            let req = actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request();
+            let body: serde_json::Value = actix_test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["running"], true, "{}", uri);
+            assert_eq!(body["is_compiling"], true, "{}", uri);
+            assert_eq!(body["job_id"], job_id.as_str());
+            assert!(body["started_at"].is_string());
+        }
+        let _ = fs::remove_file(&db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_wait_for_compile() {
+        use std::time::Duration;
+
+        let state = web::Data::new(Mutex::new(CompilerState::new()));
+        assert!(wait_for_compile(&state, Duration::ZERO).await);
+
+        state.lock().unwrap().try_start().unwrap();
+        assert!(!wait_for_compile(&state, Duration::from_millis(150)).await);
+
+        let finishing = state.clone();
+        actix_web::rt::spawn(async move {
+            actix_web::rt::time::sleep(Duration::from_millis(150)).await;
+            finishing.lock().unwrap().finish("done".to_string(), true);
+        });
+        assert!(wait_for_compile(&state, Duration::from_secs(5)).await);
+    }
+
+    #[test]
+    fn test_export_archive_round_trip() {
+        let root = PathBuf::from("/tmp/lf_blog_export_test");
+        let _ = fs::remove_dir_all(&root);
+        let content_dir = root.join("content");
+        fs::create_dir_all(content_dir.join("fruit/apple/attachment")).unwrap();
+        fs::write(content_dir.join("fruit/index.md"), "# 水果").unwrap();
+        fs::write(content_dir.join("fruit/apple/apple.md"), "# 苹果").unwrap();
+        fs::create_dir_all(content_dir.join("other")).unwrap();
+        fs::write(content_dir.join("other/index.md"), "# 其他").unwrap();
+
+        let db_path = root.join("storage.db");
+        StorageDB::new(&db_path.to_string_lossy()).unwrap();
+
+        // 完整备份包含所有分类和数据库快照
+        let full = root.join("full.zip");
+        build_export_archive(&content_dir, None, &[db_path], &full).unwrap();
+        let archive = zip::ZipArchive::new(fs::File::open(&full).unwrap()).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&"other/index.md"));
+        assert!(names.contains(&"_data/storage.db"));
+
+        // 单个分类的导出可以通过分类上传接口重新导入
+        let single = root.join("fruit.zip");
+        build_export_archive(&content_dir, Some("fruit"), &[], &single).unwrap();
+        let restored_dir = root.join("restored");
+        fs::create_dir_all(&restored_dir).unwrap();
+        let response = extract_category_package(&single, &restored_dir, ExtractLimits::from_config(&Config::default()), &AttachmentsConfig::default());
+        assert!(response.status().is_success());
+        assert_eq!(fs::read_to_string(restored_dir.join("fruit/apple/apple.md")).unwrap(), "# 苹果");
+        assert!(restored_dir.join("fruit/apple/attachment").is_dir());
+        assert!(!restored_dir.join("other").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_full_import_conflicts_with_running_compile() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_full_import_conflict_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        let token = create_admin_session(&config).unwrap();
+        let state = web::Data::new(Mutex::new(CompilerState::new()));
+        let job_id = state.lock().unwrap().try_start().unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(state.clone())
+                .route("/api/admin/upload/full", web::post().to(upload_full_content)),
+        )
+        .await;
+        let req = actix_test::TestRequest::post()
+            .uri("/api/admin/upload/full")
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .insert_header(("Content-Type", "multipart/form-data; boundary=x"))
+            .set_payload("--x--\r\n")
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["job_id"], job_id.as_str());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_sync_content_compiles_changed_items() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_git_sync_test");
+        let _ = fs::remove_dir_all(&root);
+        let upstream = git2::Repository::init(root.join("upstream")).unwrap();
+        upstream.set_head("refs/heads/main").unwrap();
+        let commit = |files: &[(&str, &str)]| {
+            let mut index = upstream.index().unwrap();
+            for (path, content) in files {
+                let file = root.join("upstream").join(path);
+                fs::create_dir_all(file.parent().unwrap()).unwrap();
+                fs::write(file, content).unwrap();
+                index.add_path(Path::new(path)).unwrap();
+            }
+            index.write().unwrap();
+            let tree = upstream.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+            let parent = upstream.head().ok().and_then(|head| head.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit> = parent.iter().collect();
+            upstream.commit(Some("HEAD"), &signature, &signature, "update", &tree, &parents).unwrap();
+        };
+        commit(&[("fruit/apple/apple.md", "---\ntitle: Apple\ndate: 2024-01-01\n---\n\n正文")]);
+
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        config.content.git.url = root.join("upstream").to_string_lossy().into_owned();
+        let token = create_admin_session(&config).unwrap();
+        let state = web::Data::new(Mutex::new(CompilerState::new()));
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(state.clone())
+                .route("/api/admin/sync", web::post().to(sync_content)),
+        )
+        .await;
+        let sync = || {
+            actix_test::TestRequest::post()
+                .uri("/api/admin/sync")
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        // 首次同步克隆仓库并全量编译
+        let resp = actix_test::call_service(&app, sync()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert!(body["previous_commit"].is_null());
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        assert!(storage.get_page("fruit-apple").unwrap().is_some());
+
+        // 之后只编译变化的文章
+        commit(&[("fruit/banana/banana.md", "---\ntitle: Banana\ndate: 2024-02-01\n---\n\n正文")]);
+        let resp = actix_test::call_service(&app, sync()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["changed_files"], serde_json::json!(["fruit/banana/banana.md"]));
+        assert_eq!(body["changed_slugs"], serde_json::json!(["fruit-banana"]));
+        assert!(storage.get_page("fruit-banana").unwrap().is_some());
+        assert!(storage.get_page("fruit-apple").unwrap().unwrap().content.contains("href=\"/fruit-banana\""));
+
+        // 后台编辑过的文件远程也改动了：返回冲突，不覆盖本地修改
+        fs::write(config.paths.content_dir.join("fruit/apple/apple.md"), "---\ntitle: Green apple\n---\n").unwrap();
+        commit(&[("fruit/apple/apple.md", "---\ntitle: Red apple\n---\n")]);
+        let resp = actix_test::call_service(&app, sync()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["conflicts"], serde_json::json!(["fruit/apple/apple.md"]));
+        assert!(fs::read_to_string(config.paths.content_dir.join("fruit/apple/apple.md")).unwrap().contains("Green apple"));
+        let state = state.lock().unwrap();
+        assert!(!state.is_compiling);
+        assert_eq!(state.last_success, Some(false));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_full_import_replace_and_merge() {
+        let root = PathBuf::from("/tmp/lf_blog_full_import_test");
+        let _ = fs::remove_dir_all(&root);
+        let source_dir = root.join("source");
+        fs::create_dir_all(source_dir.join("fruit/apple")).unwrap();
+        fs::write(source_dir.join("fruit/apple/apple.md"), "# 苹果").unwrap();
+        fs::create_dir_all(source_dir.join("veg")).unwrap();
+        fs::write(source_dir.join("veg/index.md"), "# 蔬菜").unwrap();
+        let archive = root.join("site.zip");
+        build_export_archive(&source_dir, None, &[], &archive).unwrap();
+
+        let content_dir = root.join("content");
+        let limits = ExtractLimits::from_config(&Config::default());
+        fs::create_dir_all(content_dir.join("old")).unwrap();
+        fs::write(content_dir.join("old/index.md"), "# 旧分类").unwrap();
+
+        // 合并模式保留现有分类
+        let categories = import_full_package(&archive, &content_dir, true, limits).unwrap();
+        assert_eq!(categories, vec!["fruit", "veg"]);
+        assert!(content_dir.join("old/index.md").exists());
+        assert_eq!(fs::read_to_string(content_dir.join("fruit/apple/apple.md")).unwrap(), "# 苹果");
+
+        // 结构不合法时原目录不变
+        let bad_source = root.join("bad");
+        fs::create_dir_all(bad_source.join("empty")).unwrap();
+        let bad_archive = root.join("bad.zip");
+        build_export_archive(&bad_source, None, &[], &bad_archive).unwrap();
+        let result = import_full_package(&bad_archive, &content_dir, false, limits);
+        assert!(matches!(result, Err(BlogError::Validation(_))), "{:?}", result);
+        assert!(content_dir.join("old/index.md").exists());
+
+        // 暂存目录无法创建属于服务器错误
+        fs::write(root.join("not-a-dir"), "").unwrap();
+        let result = import_full_package(&archive, &root.join("not-a-dir/content"), false, limits);
+        assert!(matches!(result, Err(BlogError::Internal(_))), "{:?}", result);
+        fs::remove_file(root.join("not-a-dir")).unwrap();
+
+        // 替换模式只保留压缩包中的内容
+        import_full_package(&archive, &content_dir, false, limits).unwrap();
+        assert!(!content_dir.join("old").exists());
+        assert!(content_dir.join("veg/index.md").exists());
+
+        // 不留下暂存目录
+        let leftovers: Vec<_> = fs::read_dir(&root)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with('.'))
+            .collect();
+        assert!(leftovers.is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    fn write_zip(path: &PathBuf, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, data) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// `tar::Header::set_path` refuses `..`, so write the raw name like a hostile archiver would
+    fn tar_header(name: &str, entry_type: tar::EntryType, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header.set_cksum();
+        header
+    }
+
+    fn write_tar(path: &PathBuf, headers: Vec<(tar::Header, &[u8])>) {
+        let encoder = flate2::write::GzEncoder::new(fs::File::create(path).unwrap(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (header, data) in headers {
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_extract_rejects_malicious_archives() {
+        let root = PathBuf::from("/tmp/lf_blog_extract_malicious_test");
+        let _ = fs::remove_dir_all(&root);
+        let dest = root.join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        let limits = ExtractLimits { max_bytes: 1024, max_files: 3 };
+        let rejected = |result: Result<(), ExtractError>, needle: &str| match result {
+            Err(ExtractError::Rejected(msg)) => assert!(msg.contains(needle), "{}", msg),
+            other => panic!("expected rejection, got {:?}", other),
+        };
+
+        let zip_slip = root.join("slip.zip");
+        write_zip(&zip_slip, &[("fruit/index.md", b"ok"), ("../evil.txt", b"pwned")]);
+        rejected(extract_zip(&zip_slip, &dest, limits), "../evil.txt");
+        assert!(!root.join("evil.txt").exists());
+
+        let zip_abs = root.join("abs.zip");
+        write_zip(&zip_abs, &[("/tmp/evil.txt", b"pwned")]);
+        rejected(extract_zip(&zip_abs, &dest, limits), "/tmp/evil.txt");
+
+        let zip_bomb = root.join("bomb.zip");
+        write_zip(&zip_bomb, &[("fruit/big.md", &[b'a'; 2048])]);
+        rejected(extract_zip(&zip_bomb, &dest, limits), "fruit/big.md");
+
+        let zip_many = root.join("many.zip");
+        write_zip(&zip_many, &[("a.md", b""), ("b.md", b""), ("c.md", b""), ("d.md", b"")]);
+        rejected(extract_zip(&zip_many, &dest, limits), "d.md");
+
+        let tar_slip = root.join("slip.tar.gz");
+        write_tar(&tar_slip, vec![(tar_header("../../evil.txt", tar::EntryType::Regular, 5), b"pwned")]);
+        rejected(extract_tar(&tar_slip, &dest, limits), "../../evil.txt");
+
+        let tar_link = root.join("link.tar.gz");
+        let mut link = tar_header("fruit/passwd", tar::EntryType::Symlink, 0);
+        link.set_link_name("/etc/passwd").unwrap();
+        link.set_cksum();
+        write_tar(&tar_link, vec![(link, b"")]);
+        rejected(extract_tar(&tar_link, &dest, limits), "fruit/passwd");
+        assert!(fs::symlink_metadata(dest.join("fruit/passwd")).is_err());
+
+        let tar_ok = root.join("ok.tar.gz");
+        write_tar(&tar_ok, vec![(tar_header("./fruit/index.md", tar::EntryType::Regular, 2), b"ok")]);
+        extract_tar(&tar_ok, &dest, limits).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("fruit/index.md")).unwrap(), "ok");
+
+        // 上传接口对不合法的压缩包返回 400
+        let response = extract_category_package(&zip_slip, &root.join("content"), limits, &AttachmentsConfig::default());
+        assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_validate_archive_magic() {
+        let root = PathBuf::from("/tmp/lf_blog_archive_magic_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let zip = root.join("fruit.zip");
+        write_zip(&zip, &[("fruit/index.md", b"ok")]);
+        let tar = root.join("fruit.tar.gz");
+        write_tar(&tar, vec![(tar_header("fruit/index.md", tar::EntryType::Regular, 2), b"ok")]);
+        assert_eq!(validate_archive_magic(&zip).unwrap(), ArchiveType::Zip);
+        assert_eq!(validate_archive_magic(&tar).unwrap(), ArchiveType::TarGz);
+
+        // 只看文件内容，扩展名伪装的文件会被拒绝
+        let fake = root.join("fake.zip");
+        fs::write(&fake, b"<html>not an archive</html>").unwrap();
+        let empty = root.join("empty.zip");
+        fs::write(&empty, b"").unwrap();
+        for path in [&fake, &empty] {
+            assert!(matches!(validate_archive_magic(path), Err(UploadError::UnsupportedType(_))));
+        }
+        assert!(matches!(check_upload_archive(&tar, "fruit.zip"), Err(UploadError::UnsupportedType(_))));
+        assert_eq!(check_upload_archive(&tar, "Fruit.TGZ").unwrap(), ArchiveType::TarGz);
+
+        let response = upload_error_response(validate_archive_magic(&fake).unwrap_err());
+        assert_eq!(response.status(), actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let response = upload_error_response(UploadError::TooLarge(1024));
+        assert_eq!(response.status(), actix_web::http::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_item_attachment_helpers() {
+        let root = PathBuf::from("/tmp/lf_blog_attachment_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.clone();
+        config.paths.storage_db = root.join("storage.db");
+        fs::create_dir_all(root.join("fruit/apple")).unwrap();
+
+        let (slug, dir) = resolve_item_dir(&config, "fruit-apple_draft").unwrap();
+        assert_eq!(slug, "fruit-apple");
+        assert_eq!(dir, root.join("fruit/apple"));
+        assert!(resolve_item_dir(&config, "fruit-pear").is_none());
+        assert!(resolve_item_dir(&config, "..-fruit").is_none());
+        assert!(resolve_item_dir(&config, "fruit").is_none());
+
+        let allowed = vec!["png".to_string(), ".PDF".to_string()];
+        assert!(is_allowed_attachment_extension(&allowed, "png"));
+        assert!(is_allowed_attachment_extension(&allowed, "pdf"));
+        assert!(!is_allowed_attachment_extension(&allowed, "exe"));
+        assert!(!is_allowed_attachment_extension(&allowed, ""));
+
+        assert_eq!(attachment_markdown("苹果_1a2b.png", "image/png"), "![苹果_1a2b](attachment/%E8%8B%B9%E6%9E%9C_1a2b.png)");
+        assert_eq!(attachment_markdown("notes.pdf", "application/pdf"), "[notes.pdf](attachment/notes.pdf)");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_locate_item_with_hyphenated_names() {
+        let root = PathBuf::from("/tmp/lf_blog_locate_item_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        let content_dir = config.paths.content_dir.clone();
+
+        // 旧数据：按目录是否存在拆分，分类和文章名都可以含 '-' 和 '_'
+        fs::create_dir_all(content_dir.join("my-notes/hello_world-2")).unwrap();
+        fs::create_dir_all(content_dir.join("my")).unwrap();
+        assert_eq!(locate_item(&config, "my-notes-hello_world-2_draft").unwrap(),
+            ("my-notes".to_string(), "hello_world-2".to_string()));
+        assert_eq!(split_item_slug(&content_dir, "my-notes-gone").unwrap(),
+            ("my-notes".to_string(), "gone".to_string()));
+        assert_eq!(split_item_slug(&content_dir, "other-cat-item").unwrap(),
+            ("other".to_string(), "cat-item".to_string()));
+        assert!(split_item_slug(&content_dir, "nohyphen").is_err());
+
+        // 编译后数据库记录了分类和目录名，目录名不必与 slug 一致
+        let item_dir = content_dir.join("my-notes").join("Hello World");
+        fs::create_dir_all(&item_dir).unwrap();
+        fs::write(item_dir.join("post.md"), "---\ntitle: Hello\ndate: 2024-01-01\nslug: hello-world\n---\n\n正文").unwrap();
+        Compiler::new(config.clone()).unwrap().compile().unwrap();
+        assert_eq!(locate_item(&config, "my-notes-hello-world").unwrap(),
+            ("my-notes".to_string(), "Hello World".to_string()));
+        assert_eq!(resolve_item_dir(&config, "my-notes-hello-world").unwrap().1, item_dir);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_content_integrity() {
+        let root = PathBuf::from("/tmp/lf_blog_integrity_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        let write_item = |name: &str| {
+            let item_dir = config.paths.content_dir.join("fruit").join(name);
+            fs::create_dir_all(&item_dir).unwrap();
+            fs::write(item_dir.join(format!("{}.md", name)), format!("---\ntitle: {}\n---\n\n正文", name)).unwrap();
+        };
+        write_item("apple");
+        write_item("pear");
+        Compiler::new(config.clone()).unwrap().compile().unwrap();
+        assert!(Compiler::check_integrity(&config).unwrap().is_clean());
+
+        // 删除源文件、新增未编译的文章、附件所属页面不存在
+        fs::remove_dir_all(config.paths.content_dir.join("fruit/pear")).unwrap();
+        write_item("plum");
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        storage
+            .save_attachment(&StoredAttachment {
+                id: "attachment-old.png".to_string(),
+                slug: "fruit-gone".to_string(),
+                filename: "old.png".to_string(),
+                original_name: "old.png".to_string(),
+                mime_type: "image/png".to_string(),
+                file_data: vec![1, 2, 3],
+                file_size: 3,
+                updated_at: String::new(),
+                content_hash: String::new(),
+            })
+            .unwrap();
+
+        let report = Compiler::check_integrity(&config).unwrap();
+        assert_eq!(report.orphaned_attachments, vec!["old.png"]);
+        assert_eq!(report.ghost_pages, vec!["fruit-pear"]);
+        assert_eq!(report.uncompiled_items, vec![config.paths.content_dir.join("fruit/plum")]);
+        assert!(!report.is_clean());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_admin_core_errors() {
+        let root = PathBuf::from("/tmp/lf_blog_admin_core_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        fs::create_dir_all(&config.paths.content_dir).unwrap();
+
+        // 分类：空名称、重复名称、删除不存在的分类
+        assert!(matches!(add_category(&config, "  ", None), Err(BlogError::Validation(_))));
+        assert_eq!(add_category(&config, "Fruit", None).unwrap(), "fruit");
+        assert_eq!(add_category(&config, "Fruit", None).unwrap_err().to_string(), "分类已存在");
+        assert!(matches!(remove_category(&config, "vegetable"), Err(BlogError::NotFound(_))));
+
+        // 文章：分类不存在、非法名称、非草稿发布
+        let request = |category: &str, item_name: &str| CreateItemRequest {
+            category: category.to_string(),
+            item_name: item_name.to_string(),
+            title: "Grape".to_string(),
+            content: "正文".to_string(),
+            date: None,
+            author: None,
+            description: None,
+            tags: None,
+            is_draft: Some(true),
+        };
+        assert_eq!(add_item(&config, request("vegetable", "grape")).unwrap_err().to_string(), "分类不存在");
+        assert!(matches!(add_item(&config, request("fruit", "..")), Err(BlogError::Validation(_))));
+        assert_eq!(add_item(&config, request("fruit", "grape")).unwrap(), "fruit-grape_draft");
+        assert!(matches!(publish_item_draft(&config, "fruit-grape"), Err(BlogError::Validation(_))));
+        assert_eq!(publish_item_draft(&config, "fruit-grape_draft").unwrap(), "fruit-grape");
+        let markdown = fs::read_to_string(config.paths.content_dir.join("fruit/grape/grape.md")).unwrap();
+        assert!(markdown.contains("draft: false"));
+
+        // 编辑到不存在的分类时保留原目录
+        let update = UpdateItemRequest {
+            category: "vegetable".to_string(),
+            item_name: "grape".to_string(),
+            title: "Grape".to_string(),
+            content: "正文".to_string(),
+            date: None,
+            author: None,
+            description: None,
+            tags: None,
+            is_draft: None,
+        };
+        assert!(edit_item(&config, "fruit-grape", update).is_err());
+        assert!(config.paths.content_dir.join("fruit/grape/grape.md").exists());
+
+        remove_item(&config, "fruit-grape").unwrap();
+        assert!(!config.paths.content_dir.join("fruit/grape").exists());
+        remove_category(&config, "fruit").unwrap();
+        assert!(!config.paths.content_dir.join("fruit").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_bulk_update_items() {
+        let root = PathBuf::from("/tmp/lf_blog_bulk_items_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        for (name, draft) in [("apple", false), ("grape", false), ("melon", true)] {
+            let item_dir = config.paths.content_dir.join("fruit").join(name);
+            fs::create_dir_all(&item_dir).unwrap();
+            fs::write(item_dir.join(format!("{}.md", name)), format!("---\ntitle: {}\ndraft: {}\n---\n\n正文", name, draft)).unwrap();
+        }
+        let slugs = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let read = |name: &str| fs::read_to_string(config.paths.content_dir.join(format!("fruit/{0}/{0}.md", name))).unwrap();
+
+        // 撤回：已是草稿的条目单独记为失败，其余照常处理
+        let (succeeded, failed) = bulk_update_items(&config, BulkAction::Unpublish, &slugs(&["fruit-apple", "fruit-melon_draft"])).unwrap();
+        assert_eq!(succeeded, vec!["fruit-apple"]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].slug, "fruit-melon_draft");
+        assert!(read("apple").contains("draft: true"));
+
+        let (succeeded, failed) = bulk_update_items(&config, BulkAction::Publish, &slugs(&["fruit-apple_draft", "fruit-melon_draft"])).unwrap();
+        assert_eq!(succeeded.len(), 2);
+        assert!(failed.is_empty());
+        assert!(read("melon").contains("draft: false"));
+
+        let (succeeded, failed) = bulk_update_items(&config, BulkAction::Delete, &slugs(&["fruit-grape", "nohyphen"])).unwrap();
+        assert_eq!(succeeded, vec!["fruit-grape"]);
+        assert_eq!(failed[0].slug, "nohyphen");
+        assert!(!config.paths.content_dir.join("fruit/grape").exists());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_items_and_export_query_params() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_items_query_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("content/fruit")).unwrap();
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.storage_db = root.join("storage.db");
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        for (slug, category) in [
+            ("fruit-apple", "fruit"),
+            ("fruit-banana", "fruit"),
+            ("fruit-cherry_draft", "fruit"),
+            ("my-notes-hello", "my-notes"),
+        ] {
+            storage
+                .save_page(&Page {
+                    id: slug.to_string(),
+                    slug: slug.to_string(),
+                    page_type: PageType::Item,
+                    title: slug.to_string(),
+                    content: String::new(),
+                    category: Some(category.to_string()),
+                    updated_at: String::new(),
+                    publish_at: None,
+                    tags: Vec::new(),
+                    raw_content: None,
+                    series: None,
+                    series_order: None,
+                    item_name: None,
+                    excerpt: None,
+                })
+                .unwrap();
+        }
+        let token = create_admin_session(&config).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .route("/api/admin/items", web::get().to(get_items))
+                .route("/api/admin/export", web::get().to(export_content)),
+        )
+        .await;
+        let get = |uri: &str| {
+            actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, get("/api/admin/items")).await;
+        assert_eq!(body["total"], 3);
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, get("/api/admin/items?drafts=true")).await;
+        assert_eq!(body["total"], 4);
+        assert!(body["items"].as_array().unwrap().iter().any(|item| item["is_draft"] == true));
+
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, get("/api/admin/items?drafts=true&category=fruit&page=2&per_page=2")).await;
+        assert_eq!(body["total"], 3);
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["page"], 2);
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, get("/api/admin/items?category=my-notes")).await;
+        assert_eq!(body["items"][0]["slug"], "my-notes-hello");
+
+        let resp = actix_test::call_service(&app, get("/api/admin/items?drafts=maybe")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let resp = actix_test::call_service(&app, get("/api/admin/export?category=vegetable")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let resp = actix_test::call_service(&app, get("/api/admin/export?category=..%2Fetc")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let resp = actix_test::call_service(&app, get("/api/admin/export?category=fruit")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_comments_page() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_admin_comments_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        config.paths.comments_db = root.join("comments.db");
+        config.paths.templates_dir = root.join("templates");
+        config.notifications.muted_slugs = vec!["fruit-pear".to_string()];
+        let comments_db = crate::comments::CommentDB::new(&config.paths.comments_db.to_string_lossy()).unwrap();
+        let add = |slug: &str, content: &str, parent_id: Option<String>| {
+            comments_db
+                .add_comment(crate::comments::CreateCommentRequest {
+                    slug: slug.to_string(),
+                    author: "lin".to_string(),
+                    content: content.to_string(),
+                    website: None,
+                    parent_id,
+                    email: None,
+                })
+                .unwrap()
+        };
+        let first = add("fruit-apple", "first apple comment", None);
+        add("fruit-apple", "a reply", Some(first.id.clone()));
+        add("fruit-pear", "pear comment", None);
+        let token = create_admin_session(&config).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .route("/admin/comments", web::get().to(admin_comments_page))
+                .route("/admin/comments/{id}/delete", web::post().to(admin_delete_comment_handler)),
+        )
+        .await;
+        let page = |uri: &str| {
+            let request = actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request();
+            async { String::from_utf8(actix_test::call_and_read_body(&app, request).await.to_vec()).unwrap() }
+        };
+
+        let html = page("/admin/comments").await;
+        assert!(html.contains(&format!(r#"id="comment-{}""#, first.id)));
+        assert!(html.contains("pear comment") && html.contains("已静音"));
+        let html = page("/admin/comments?slug=fruit-apple").await;
+        assert!(html.contains("first apple comment") && !html.contains("pear comment"));
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get().uri("/admin/comments").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        // 删除评论时连同回复一起删除
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri(&format!("/admin/comments/{}/delete", first.id))
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(comments_db.get_comments_by_slug("fruit-apple").unwrap().len(), 0);
+        assert_eq!(comments_db.get_total_comments().unwrap(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_page_analytics_endpoints() {
+        use actix_web::{test as actix_test, App};
+
+        let root = PathBuf::from("/tmp/lf_blog_page_analytics_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        config.paths.analytics_db = root.join("analytics.db");
+        config.paths.templates_dir = root.join("templates");
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        storage
+            .save_page(&Page {
+                id: "fruit-apple".to_string(),
+                slug: "fruit-apple".to_string(),
+                page_type: PageType::Item,
+                title: "Apple".to_string(),
+                content: String::new(),
+                category: Some("fruit".to_string()),
+                updated_at: String::new(),
+                publish_at: None,
+                tags: Vec::new(),
+                raw_content: None,
+                series: None,
+                series_order: None,
+                item_name: None,
+                excerpt: None,
+            })
+            .unwrap();
+        let visit = crate::analytics::VisitRecord {
+            id: "v1".to_string(),
+            page_slug: "fruit-apple".to_string(),
+            ip_address: "127.0.0.1".to_string(),
+            user_agent: "curl/8.0".to_string(),
+            referer: "https://www.google.com/search?q=apple".to_string(),
+            source: crate::analytics::VisitSource::SearchEngine,
+            search_engine: Some("Google".to_string()),
+            search_keyword: Some("apple".to_string()),
+            visit_time: Utc::now().to_rfc3339(),
+            country: None,
+            city: None,
+            redirected_from: None,
+            device_type: Default::default(),
+            is_bot: false,
+        };
+        crate::analytics::AnalyticsDB::new(&config.paths.analytics_db.to_string_lossy())
+            .unwrap()
+            .record_visit(&visit)
+            .unwrap();
+        let token = create_admin_session(&config).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .route("/api/analytics/page/{slug}", web::get().to(crate::analytics::get_page_analytics))
+                .route("/api/analytics/realtime", web::get().to(crate::analytics::get_realtime_visitors))
+                .route("/api/analytics/realtime/stream", web::get().to(crate::analytics::stream_realtime_visitors))
+                .route("/admin/items/{slug}/stats", web::get().to(admin_item_stats_page)),
+        )
+        .await;
+        let get = |uri: &str| {
+            actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, get("/api/analytics/page/fruit-apple?days=7")).await;
+        assert_eq!(body["stats"]["title"], "Apple");
+        assert_eq!(body["stats"]["total_visits"], 1);
+        assert_eq!(body["stats"]["daily"].as_array().unwrap().len(), 7);
+        assert_eq!(body["stats"]["keywords"][0][0], "apple");
+        // 超出范围的天数被限制
+        let body: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, get("/api/analytics/page/fruit-apple?days=5000")).await;
+        assert_eq!(body["stats"]["daily"].as_array().unwrap().len(), 365);
+
+        let resp = actix_test::call_service(&app, get("/api/analytics/page/fruit-appel")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get().uri("/api/analytics/page/fruit-apple").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        // 实时访客
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, get("/api/analytics/realtime")).await;
+        assert_eq!(body["active_visitors"], 1);
+        assert_eq!(body["within_minutes"], 5);
+        assert_eq!(body["top_pages"][0]["slug"], "fruit-apple");
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get().uri("/api/analytics/realtime/stream").to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        // 第一条事件立即发送
+        let resp = actix_test::call_service(&app, get("/api/analytics/realtime/stream?minutes=10")).await;
+        assert_eq!(resp.headers().get("Content-Type").unwrap(), "text/event-stream");
+        use actix_web::body::MessageBody;
+        let mut stream = resp.into_body();
+        let event = std::future::poll_fn(|cx| std::pin::Pin::new(&mut stream).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        let event = std::str::from_utf8(&event).unwrap();
+        let data: serde_json::Value = serde_json::from_str(event.strip_prefix("data: ").unwrap().trim_end()).unwrap();
+        assert_eq!(data["active_visitors"], 1);
+        assert_eq!(data["within_minutes"], 10);
+
+        let resp = actix_test::call_service(&app, get("/admin/items/fruit-apple/stats?days=30")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let html = String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap();
+        assert!(html.contains("Apple") && html.contains("<polyline points="));
+        assert!(html.contains(r#"<option value="30" selected>"#));
+        let resp = actix_test::call_service(&app, get("/admin/items/fruit-appel/stats")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_item_recompile_refreshes_listings() {
+        let root = PathBuf::from("/tmp/lf_blog_item_recompile_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        for (name, title, date) in [("apple", "Apple", "2024-01-01"), ("banana", "Banana", "2024-02-01")] {
+            let dir = config.paths.content_dir.join("fruit").join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(format!("{}.md", name)), format!("---\ntitle: {}\ndate: {}\n---\n\n正文", title, date)).unwrap();
+        }
+
+        let mut compiler = Compiler::new(config.clone()).unwrap();
+        compiler.compile_item("fruit", "apple").unwrap();
+        let result = compiler.compile_item("fruit", "banana").unwrap();
+        // 文章页、分类页、首页、404 页和相邻的 apple
+        assert_eq!(result.total_pages, 5);
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        assert!(crate::i18n::localize(&storage.get_page("index").unwrap().unwrap().content, "zh-CN", "zh-CN").contains("共 2 篇"));
+        assert!(storage.get_page("fruit-apple").unwrap().unwrap().content.contains("href=\"/fruit-banana\""));
+
+        // 删除后首页、分类页和相邻文章不再链接到它
+        fs::remove_dir_all(config.paths.content_dir.join("fruit/banana")).unwrap();
+        let result = compiler.compile_removed_item("fruit", "fruit-banana").unwrap();
+        assert_eq!(result.total_pages, 4);
+        assert!(storage.get_page("fruit-banana").unwrap().is_none());
+        assert!(crate::i18n::localize(&storage.get_page("index").unwrap().unwrap().content, "zh-CN", "zh-CN").contains("共 1 篇"));
+        assert!(!storage.get_page("fruit").unwrap().unwrap().content.contains("Banana"));
+        assert!(!storage.get_page("fruit-apple").unwrap().unwrap().content.contains("fruit-banana"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_category_title_and_pages() {
+        assert_eq!(set_frontmatter_title("---\ntitle: 旧\ndescription: d\n---\n\n# 正文", "技术 文章"),
+            "---\ntitle: \"技术 文章\"\ndescription: d\n---\n\n# 正文");
+        assert_eq!(set_frontmatter_title("# 正文", "A: b"), "---\ntitle: \"A: b\"\n---\n\n# 正文");
+
+        let root = PathBuf::from("/tmp/lf_blog_category_rename_test");
+        let _ = fs::remove_dir_all(&root);
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.storage_db = root.join("storage.db");
+        let category_dir = config.paths.content_dir.join("ji-shu-wen-zhang");
+        fs::create_dir_all(category_dir.join("apple")).unwrap();
+        fs::write(category_dir.join("index.md"), set_frontmatter_title("# 介绍", "技术 文章")).unwrap();
+        fs::write(category_dir.join("apple/apple.md"), "---\ntitle: Apple\ndate: 2024-01-01\n---\n\n正文").unwrap();
+
+        Compiler::new(config.clone()).unwrap().compile().unwrap();
+        let storage = StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        let category_page = storage.get_page("ji-shu-wen-zhang").unwrap().unwrap();
+        assert_eq!(category_page.title, "技术 文章");
+        assert!(storage.get_page("ji-shu-wen-zhang-apple").unwrap().is_some());
+
+        let mut removed = delete_category_pages(&storage, "ji-shu-wen-zhang");
+        removed.sort();
+        assert_eq!(removed, vec!["ji-shu-wen-zhang".to_string(), "ji-shu-wen-zhang-apple".to_string()]);
+        assert!(storage.get_page("ji-shu-wen-zhang").unwrap().is_none());
+        assert!(storage.get_page("ji-shu-wen-zhang-apple").unwrap().is_none());
+        assert!(storage.get_page("index").unwrap().is_some());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_render_preview_matches_compile() {
+        let options = MarkdownConfig::default();
+        let source = "---\ntitle: 预览\n---\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n![图](attachment/cover.png) ![缺](attachment/gone.png)\n";
+        let map = vec![("cover.png".to_string(), "apple_1.png".to_string())];
+
+        let preview = render_preview(&options, source, &map).unwrap();
+        let compiled = crate::markdown::parse_markdown_with_options(source, &options).unwrap();
+        assert_eq!(preview.title, "预览");
+        assert_eq!(
+            preview.html,
+            crate::markdown::rewrite_attachment_links(&compiled.html_content, &map).0
+        );
+        assert!(preview.html.contains("<table>"));
+        assert!(preview.html.contains("src=\"/attachment/apple_1.png\""));
+        assert_eq!(preview.missing_attachments, vec!["gone.png".to_string()]);
+    }
+}
@@ -0,0 +1,216 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use ipnet::IpNet;
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::rc::Rc;
+
+/// 解析 IP 列表条目，支持 CIDR（127.0.0.0/8）和单个 IP，遇到无效条目时返回错误
+pub fn parse_allowlist(entries: &[String]) -> Result<Vec<IpNet>, String> {
+    entries
+        .iter()
+        .map(|entry| {
+            let entry = entry.trim();
+            entry
+                .parse::<IpNet>()
+                .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| format!("'{}' is not a valid IP address or CIDR range", entry))
+        })
+        .collect()
+}
+
+/// 可信反向代理（`server.trusted_proxies`），作为 app_data 注册
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub fn new(proxies: Vec<IpNet>) -> Self {
+        Self(proxies)
+    }
+}
+
+/// 获取客户端 IP：只有直接连接方是可信代理时才采用 X-Forwarded-For / X-Real-IP
+pub fn client_ip(req: &HttpRequest) -> String {
+    let trusted = req
+        .app_data::<web::Data<TrustedProxies>>()
+        .map(|proxies| proxies.0.as_slice())
+        .unwrap_or(&[]);
+    let header = |name: &str| req.headers().get(name).and_then(|value| value.to_str().ok());
+    resolve_client_ip(
+        req.peer_addr().map(|addr| addr.ip()),
+        header("x-forwarded-for"),
+        header("x-real-ip"),
+        trusted,
+    )
+}
+
+/// 从连接地址和代理头推出客户端 IP
+fn resolve_client_ip(
+    peer: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    real_ip: Option<&str>,
+    trusted: &[IpNet],
+) -> String {
+    let Some(peer) = peer else {
+        return "0.0.0.0".to_string();
+    };
+    let is_trusted = |ip: &IpAddr| trusted.iter().any(|net| net.contains(ip));
+    if !is_trusted(&peer) {
+        return peer.to_string();
+    }
+
+    let mut client = peer;
+    if let Some(forwarded) = forwarded_for {
+        // 从右往左跳过可信代理，第一个不可信的地址就是客户端
+        for hop in forwarded.rsplit(',') {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    client = ip;
+                    if !is_trusted(&ip) {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    } else if let Some(ip) = real_ip.and_then(|ip| ip.trim().parse::<IpAddr>().ok()) {
+        client = ip;
+    }
+    client.to_string()
+}
+
+/// 是否为管理路径
+fn is_admin_path(path: &str) -> bool {
+    path == "/admin"
+        || path.starts_with("/admin/")
+        || path == "/api/admin"
+        || path.starts_with("/api/admin/")
+        || path == "/api/recompile"
+        || path.starts_with("/api/recompile/")
+}
+
+/// IP 是否在白名单内（白名单为空时全部允许）
+fn is_allowed(allowlist: &[IpNet], ip: &str) -> bool {
+    if allowlist.is_empty() {
+        return true;
+    }
+    match ip.parse::<IpAddr>() {
+        Ok(addr) => allowlist.iter().any(|net| net.contains(&addr)),
+        Err(_) => false,
+    }
+}
+
+/// 管理后台 IP 白名单中间件
+pub struct AdminIpFilter {
+    allowlist: Rc<Vec<IpNet>>,
+}
+
+impl AdminIpFilter {
+    pub fn new(allowlist: Vec<IpNet>) -> Self {
+        Self {
+            allowlist: Rc::new(allowlist),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AdminIpFilter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AdminIpFilterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminIpFilterMiddleware {
+            service,
+            allowlist: self.allowlist.clone(),
+        }))
+    }
+}
+
+pub struct AdminIpFilterMiddleware<S> {
+    service: S,
+    allowlist: Rc<Vec<IpNet>>,
+}
+
+impl<S, B> Service<ServiceRequest> for AdminIpFilterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if is_admin_path(req.path()) {
+            let ip = client_ip(req.request());
+            if !is_allowed(&self.allowlist, &ip) {
+                log::warn!("Blocked admin request from {} to {}", ip, req.path());
+                let response = HttpResponse::Forbidden()
+                    .json(serde_json::json!({
+                        "status": "error",
+                        "message": "禁止访问"
+                    }))
+                    .map_into_right_body();
+                return Box::pin(async move { Ok(req.into_response(response)) });
+            }
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_allowlist() {
+        let allowlist = parse_allowlist(&["127.0.0.1/8".to_string(), "192.168.1.10".to_string()]).unwrap();
+        assert_eq!(allowlist.len(), 2);
+        assert!(parse_allowlist(&["127.0.0.1".to_string(), "not-an-ip".to_string()]).is_err());
+
+        assert!(is_allowed(&allowlist, "127.0.0.1"));
+        assert!(is_allowed(&allowlist, "127.20.3.4"));
+        assert!(is_allowed(&allowlist, "192.168.1.10"));
+        assert!(!is_allowed(&allowlist, "192.168.1.11"));
+        assert!(!is_allowed(&allowlist, "unknown"));
+        assert!(is_allowed(&[], "10.0.0.1"));
+
+        assert!(is_admin_path("/admin"));
+        assert!(is_admin_path("/api/admin/items"));
+        assert!(is_admin_path("/api/recompile"));
+        assert!(is_admin_path("/api/recompile/status"));
+        assert!(!is_admin_path("/api/recompiler"));
+        assert!(!is_admin_path("/administration-tips"));
+    }
+
+    #[test]
+    fn test_client_ip_trusts_only_configured_proxies() {
+        let trusted = parse_allowlist(&["10.0.0.0/8".to_string()]).unwrap();
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let direct: IpAddr = "203.0.113.7".parse().unwrap();
+
+        // 直接连接时忽略伪造的代理头
+        assert_eq!(resolve_client_ip(Some(direct), Some("127.0.0.1"), Some("127.0.0.1"), &trusted), "203.0.113.7");
+        assert_eq!(resolve_client_ip(Some(direct), Some("127.0.0.1"), None, &[]), "203.0.113.7");
+
+        // 经可信代理时取最右侧的不可信地址
+        assert_eq!(
+            resolve_client_ip(Some(proxy), Some("127.0.0.1, 198.51.100.4, 10.0.0.9"), None, &trusted),
+            "198.51.100.4"
+        );
+        assert_eq!(resolve_client_ip(Some(proxy), None, Some("198.51.100.4"), &trusted), "198.51.100.4");
+        assert_eq!(resolve_client_ip(Some(proxy), None, None, &trusted), "10.0.0.2");
+        assert_eq!(resolve_client_ip(None, Some("198.51.100.4"), None, &trusted), "0.0.0.0");
+    }
+}
@@ -0,0 +1,234 @@
+//! 运行时重新加载配置
+//!
+//! 处理函数照常通过 `web::Data<Config>` 读取配置；`CurrentConfig` 中间件在每个请求
+//! 开始时放入最新加载的配置，所以 SIGHUP 之后的新请求会看到新配置，
+//! 进行中的请求仍使用开始时的配置。
+use crate::admin::{self, CompilerState};
+use crate::config::Config;
+use actix_web::dev::{forward_ready, Extensions, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{web, Error};
+use std::future::{ready, Ready};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Mutex, RwLock};
+
+/// 当前生效的配置及其配置文件
+pub struct LiveConfig {
+    path: PathBuf,
+    current: RwLock<web::Data<Config>>,
+}
+
+/// 一次重新加载的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigReload {
+    pub warnings: Vec<String>,
+    pub site_changed: bool, // 页面中包含站点信息，需要重新编译
+}
+
+impl LiveConfig {
+    pub fn new(path: PathBuf, config: Config) -> Self {
+        Self {
+            path,
+            current: RwLock::new(web::Data::new(config)),
+        }
+    }
+
+    /// 当前配置
+    pub fn current(&self) -> web::Data<Config> {
+        match self.current.read() {
+            Ok(current) => current.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// 重新读取配置文件并更新可热加载的配置项（见 `Config::apply_reload`）；
+    /// 文件无法解析或合并后的配置有错误时保留当前配置
+    pub fn reload(&self) -> Result<ConfigReload, String> {
+        let reloaded = Config::load_from(&self.path).map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        let mut config = Config::clone(&self.current());
+        let site_changed = config.site != reloaded.site;
+        let mut warnings = config.apply_reload(reloaded);
+        match config.validate() {
+            Ok(validated) => warnings.extend(validated.iter().map(ToString::to_string)),
+            Err(errors) => {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                return Err(errors.join("; "));
+            }
+        }
+
+        let config = web::Data::new(config);
+        match self.current.write() {
+            Ok(mut current) => *current = config,
+            Err(poisoned) => *poisoned.into_inner() = config,
+        }
+        Ok(ConfigReload { warnings, site_changed })
+    }
+}
+
+/// 监听 SIGHUP，每次收到时重新加载配置；站点信息变化时在后台重新编译全站。
+/// 须在运行时中调用，返回前信号处理已注册
+#[cfg(unix)]
+pub fn spawn_reload_on_hangup(
+    live: web::Data<LiveConfig>,
+    compiler_state: web::Data<Mutex<CompilerState>>,
+) -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = signal(SignalKind::hangup())?;
+    actix_web::rt::spawn(async move {
+        while hangup.recv().await.is_some() {
+            log::info!("Received SIGHUP, reloading {}", live.path.display());
+            reload_and_recompile(&live, &compiler_state);
+        }
+    });
+    Ok(())
+}
+
+/// 重新加载配置并记录结果；站点信息变化时在后台重新编译全站
+fn reload_and_recompile(live: &LiveConfig, compiler_state: &web::Data<Mutex<CompilerState>>) {
+    let reload = match live.reload() {
+        Ok(reload) => reload,
+        Err(e) => {
+            log::error!("Config reload failed, keeping the current configuration: {}", e);
+            return;
+        }
+    };
+    for warning in &reload.warnings {
+        log::warn!("Config: {}", warning);
+    }
+    log::info!("Configuration reloaded");
+
+    if reload.site_changed {
+        let config = Config::clone(&live.current());
+        match admin::start_background_compile(config, compiler_state.clone()) {
+            Ok(job_id) => log::info!("Site settings changed, recompiling (job {})", job_id),
+            Err(job_id) => log::warn!(
+                "Site settings changed while compile {} is running; recompile once it finishes",
+                job_id
+            ),
+        }
+    }
+}
+
+/// 让请求中的 `web::Data<Config>` 取到最新加载的配置
+pub struct CurrentConfig {
+    live: web::Data<LiveConfig>,
+}
+
+impl CurrentConfig {
+    pub fn new(live: web::Data<LiveConfig>) -> Self {
+        Self { live }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for CurrentConfig
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = CurrentConfigMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CurrentConfigMiddleware {
+            service,
+            live: self.live.clone(),
+        }))
+    }
+}
+
+pub struct CurrentConfigMiddleware<S> {
+    service: S,
+    live: web::Data<LiveConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for CurrentConfigMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        // 后加入的数据优先于应用启动时注册的配置
+        let mut data = Extensions::new();
+        data.insert(self.live.current());
+        req.add_data_container(Rc::new(data));
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as actix_test, App};
+
+    #[actix_web::test]
+    async fn test_reload_updates_site_title() {
+        let root = PathBuf::from("/tmp/lf_blog_live_config_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("content")).unwrap();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        let config_path = root.join("lf_blog.toml");
+
+        let mut config = Config::default();
+        config.paths.content_dir = root.join("content");
+        config.paths.templates_dir = root.join("templates");
+        config.paths.generated_dir = root.join("generated");
+        config.paths.data_dir = root.join("data");
+        config.paths.storage_db = root.join("data/storage.db");
+        config.paths.comments_db = root.join("data/comments.db");
+        config.paths.analytics_db = root.join("data/analytics.db");
+        config.server.admin_password = "correct horse".to_string();
+        config.site.title = "Old Title".to_string();
+        config.save_to(&config_path).unwrap();
+
+        let live = web::Data::new(LiveConfig::new(config_path.clone(), config.clone()));
+        let compiler_state = web::Data::new(Mutex::new(CompilerState::new()));
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .wrap(CurrentConfig::new(live.clone()))
+                .route("/title", web::get().to(|config: web::Data<Config>| async move { config.site.title.clone() })),
+        )
+        .await;
+        let title = || async { actix_test::call_and_read_body(&app, actix_test::TestRequest::get().uri("/title").to_request()).await };
+        assert_eq!(title().await.as_ref(), b"Old Title");
+
+        // 站点信息没变时不需要重新编译
+        let reload = live.reload().unwrap();
+        assert!(!reload.site_changed);
+
+        // 只有可热加载的配置项会更新
+        config.site.title = "New Title".to_string();
+        config.server.port = 9090;
+        config.server.render_cache_size = 1;
+        config.save_to(&config_path).unwrap();
+        reload_and_recompile(&live, &compiler_state);
+
+        assert_eq!(title().await.as_ref(), b"New Title");
+        assert_eq!(live.current().server.port, 9090);
+        assert_eq!(live.current().server.render_cache_size, Config::default().server.render_cache_size);
+
+        // 站点信息变化后在后台重新编译
+        assert!(admin::wait_for_compile(&compiler_state, std::time::Duration::from_secs(30)).await);
+        let state = compiler_state.lock().unwrap();
+        assert_eq!(state.last_success, Some(true), "{:?}", state.last_compile_result);
+        drop(state);
+
+        // 有错误的配置不会生效
+        std::fs::write(&config_path, "not = [valid toml").unwrap();
+        assert!(live.reload().is_err());
+        reload_and_recompile(&live, &compiler_state);
+        assert_eq!(live.current().site.title, "New Title");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,152 @@
+//! 为所有响应添加安全相关的响应头
+use crate::config::Config;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::Error;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+/// HTTPS 站点的 Strict-Transport-Security（一年，包含子域名）
+const HSTS: &str = "max-age=31536000; includeSubDomains";
+
+/// 安全响应头中间件；处理函数已设置的同名响应头保持不变
+pub struct SecurityHeaders {
+    headers: Rc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl SecurityHeaders {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            headers: Rc::new(security_headers(config)),
+        }
+    }
+}
+
+/// 按配置生成的响应头：固定的几项，加上 CSP（配置了指令时）和 HSTS（`server.use_https` 时）
+pub fn security_headers(config: &Config) -> Vec<(HeaderName, HeaderValue)> {
+    let mut headers = vec![
+        ("x-content-type-options", "nosniff".to_string()),
+        ("x-frame-options", "SAMEORIGIN".to_string()),
+        ("referrer-policy", "strict-origin-when-cross-origin".to_string()),
+        ("permissions-policy", "camera=(), microphone=()".to_string()),
+    ];
+    if let Some(csp) = config.security.csp_header() {
+        headers.push(("content-security-policy", csp));
+    }
+    if config.server.use_https {
+        headers.push(("strict-transport-security", HSTS.to_string()));
+    }
+
+    headers
+        .into_iter()
+        .filter_map(|(name, value)| match HeaderValue::from_str(&value) {
+            Ok(value) => Some((HeaderName::from_static(name), value)),
+            Err(_) => {
+                log::warn!("Ignoring invalid {} header: {}", name, value);
+                None
+            }
+        })
+        .collect()
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersMiddleware {
+            service,
+            headers: self.headers.clone(),
+        }))
+    }
+}
+
+pub struct SecurityHeadersMiddleware<S> {
+    service: S,
+    headers: Rc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let headers = self.headers.clone();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let mut res = fut.await?;
+            let response_headers = res.headers_mut();
+            for (name, value) in headers.iter() {
+                if !response_headers.contains_key(name) {
+                    response_headers.insert(name.clone(), value.clone());
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test as actix_test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn test_security_headers() {
+        let mut config = Config::default();
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(SecurityHeaders::new(&config))
+                .route("/", web::get().to(HttpResponse::Ok))
+                .route(
+                    "/embed",
+                    web::get().to(|| async { HttpResponse::Ok().insert_header(("X-Frame-Options", "DENY")).finish() }),
+                ),
+        )
+        .await;
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/").to_request()).await;
+        let header = |name: &str| resp.headers().get(name).map(|v| v.to_str().unwrap().to_string());
+        assert_eq!(header("x-content-type-options").as_deref(), Some("nosniff"));
+        assert_eq!(header("x-frame-options").as_deref(), Some("SAMEORIGIN"));
+        assert_eq!(header("referrer-policy").as_deref(), Some("strict-origin-when-cross-origin"));
+        assert_eq!(header("permissions-policy").as_deref(), Some("camera=(), microphone=()"));
+        let csp = header("content-security-policy").unwrap();
+        assert!(csp.starts_with("base-uri 'self'; connect-src 'self'; default-src 'self';"), "{}", csp);
+        assert!(csp.contains("script-src 'self' https://cdn.jsdelivr.net 'unsafe-inline'"), "{}", csp);
+        assert!(header("strict-transport-security").is_none());
+
+        // 处理函数设置的响应头优先
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/embed").to_request()).await;
+        assert_eq!(resp.headers().get("x-frame-options").unwrap(), "DENY");
+
+        // 严格模式不允许内联脚本；HTTPS 站点发送 HSTS；CSP 为空时不发送
+        config.security.allow_inline_scripts = false;
+        config.server.use_https = true;
+        let headers = security_headers(&config);
+        let csp = header_value(&headers, "content-security-policy").unwrap();
+        assert!(csp.contains("script-src 'self' https://cdn.jsdelivr.net;"), "{}", csp);
+        assert_eq!(header_value(&headers, "strict-transport-security").as_deref(), Some(HSTS));
+        config.security.csp.clear();
+        assert!(header_value(&security_headers(&config), "content-security-policy").is_none());
+    }
+
+    fn header_value(headers: &[(HeaderName, HeaderValue)], name: &str) -> Option<String> {
+        headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.to_str().unwrap().to_string())
+    }
+}
@@ -1,3 +1,5 @@
 pub mod admin;
 pub mod routes;
-pub mod recommender;
\ No newline at end of file
+pub mod recommender;
+pub mod redirects;
+pub mod ip_filter;
\ No newline at end of file
@@ -0,0 +1,456 @@
+use crate::comments::Comment;
+use crate::compiler::CompileResult;
+use crate::config::{Config, NotificationConfig, WebhooksConfig};
+use crate::storage::StorageDB;
+use actix_web::{web, HttpResponse, Responder};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+
+/// webhook 请求超时时间
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// 编译 webhook 失败后的重试次数，以及第一次重试前的等待时间（之后每次翻倍）
+const COMPILE_WEBHOOK_RETRIES: u32 = 3;
+const COMPILE_WEBHOOK_BACKOFF: Duration = Duration::from_secs(2);
+/// 评论 webhook 失败后（开启 `retry` 时）重试前的等待时间
+const COMMENT_WEBHOOK_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// 通知中评论摘录的最大字符数
+const EXCERPT_CHARS: usize = 200;
+
+/// 测试中 webhook 地址以此开头时不发送请求，请求体记录在内存中，用 `take_captured` 取出
+#[cfg(test)]
+pub const CAPTURE_URL_PREFIX: &str = "capture://";
+#[cfg(test)]
+static CAPTURED: std::sync::Mutex<Vec<(String, serde_json::Value)>> = std::sync::Mutex::new(Vec::new());
+
+/// 取出发往 `url` 的已记录请求体（按发送顺序）
+#[cfg(test)]
+pub fn take_captured(url: &str) -> Vec<serde_json::Value> {
+    let mut captured = match CAPTURED.lock() {
+        Ok(captured) => captured,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let (taken, rest) = std::mem::take(&mut *captured)
+        .into_iter()
+        .partition::<Vec<_>, _>(|(target, _)| target == url);
+    *captured = rest;
+    taken.into_iter().map(|(_, body)| body).collect()
+}
+
+/// 评论 webhook 请求体：`{event, comment, title, excerpt, page_url, admin_url}`，
+/// `admin_url` 指向后台评论管理页中的这条评论
+pub fn webhook_payload(event: &str, comment: &Comment, title: &str, site_url: &str) -> serde_json::Value {
+    let site_url = site_url.trim_end_matches('/');
+    serde_json::json!({
+        "event": event,
+        "comment": comment,
+        "title": title,
+        "excerpt": comment_excerpt(&comment.content),
+        "page_url": format!("{}/{}", site_url, comment.slug),
+        "admin_url": format!(
+            "{}/admin/comments?slug={}#comment-{}",
+            site_url,
+            urlencoding::encode(&comment.slug),
+            comment.id
+        )
+    })
+}
+
+/// 评论内容的前 `EXCERPT_CHARS` 个字符
+fn comment_excerpt(content: &str) -> String {
+    let content = content.trim();
+    match content.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+/// 对请求体计算 HMAC-SHA256，返回十六进制字符串
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 发送评论 webhook，配置了密钥时附带 `X-Signature` 头；返回响应状态码
+pub async fn send_webhook(settings: &NotificationConfig, body: Vec<u8>) -> Result<u16, String> {
+    let url = settings
+        .webhook_url
+        .as_deref()
+        .filter(|url| !url.trim().is_empty())
+        .ok_or_else(|| "未配置 webhook_url".to_string())?;
+    post_webhook(url, settings.webhook_secret.as_deref(), body).await
+}
+
+/// 向 `url` POST JSON 请求体，`secret` 不为空时附带 `X-Signature` 头；返回响应状态码
+pub async fn post_webhook(url: &str, secret: Option<&str>, body: Vec<u8>) -> Result<u16, String> {
+    #[cfg(test)]
+    if url.starts_with(CAPTURE_URL_PREFIX) {
+        let body = serde_json::from_slice(&body).unwrap_or_else(|_| String::from_utf8_lossy(&body).into());
+        match CAPTURED.lock() {
+            Ok(mut captured) => captured.push((url.to_string(), body)),
+            Err(poisoned) => poisoned.into_inner().push((url.to_string(), body)),
+        }
+        return Ok(200);
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut request = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json");
+    if let Some(secret) = secret.filter(|s| !s.is_empty()) {
+        request = request.header("X-Signature", sign_payload(secret, &body));
+    }
+
+    let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(status.as_u16())
+    } else {
+        Err(format!("webhook 返回 HTTP {}", status))
+    }
+}
+
+/// 新评论通知：在后台任务中读取文章标题并发送，失败只记录日志（开启 `retry` 时先重试一次），
+/// 不影响评论提交；`muted_slugs` 中的文章不通知
+pub fn notify_new_comment(config: &Config, comment: &Comment) {
+    if config.notifications.webhook_url.as_deref().map_or(true, |url| url.trim().is_empty()) {
+        return;
+    }
+    if config.notifications.muted_slugs.iter().any(|slug| slug == &comment.slug) {
+        log::debug!("Comment notifications for {} are muted", comment.slug);
+        return;
+    }
+
+    let settings = config.notifications.clone();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let site_url = config.site.url.clone();
+    let comment = comment.clone();
+
+    tokio::task::spawn(async move {
+        // 页面还未编译时用 slug 代替标题
+        let title = StorageDB::new(&storage_path)
+            .ok()
+            .and_then(|storage| storage.get_page(&comment.slug).ok().flatten())
+            .map_or_else(|| comment.slug.clone(), |page| page.title);
+        let body = match serde_json::to_vec(&webhook_payload("new_comment", &comment, &title, &site_url)) {
+            Ok(body) => body,
+            Err(e) => {
+                log::error!("Failed to serialize comment webhook payload: {}", e);
+                return;
+            }
+        };
+
+        let mut result = send_webhook(&settings, body.clone()).await;
+        if let (Err(e), true) = (&result, settings.retry) {
+            log::warn!("Comment webhook for {} failed, retrying: {}", comment.id, e);
+            tokio::time::sleep(COMMENT_WEBHOOK_RETRY_DELAY).await;
+            result = send_webhook(&settings, body).await;
+        }
+        match result {
+            Ok(status) => log::debug!("Comment webhook for {} delivered (HTTP {})", comment.id, status),
+            Err(e) => log::warn!("Comment webhook for {} failed: {}", comment.id, e),
+        }
+    });
+}
+
+/// 编译完成的 webhook 请求体；`changed_slugs` 只在增量编译时不为 null
+pub fn compile_payload(site_url: &str, result: &CompileResult, duration: Duration) -> serde_json::Value {
+    serde_json::json!({
+        "event": "compile",
+        "site_url": site_url,
+        "incremental": result.changed_slugs.is_some(),
+        "total_categories": result.total_categories,
+        "total_items": result.total_items,
+        "total_attachments": result.total_attachments,
+        "duration_ms": duration.as_millis() as u64,
+        "changed_slugs": result.changed_slugs,
+        "compiled_at": chrono::Utc::now().to_rfc3339()
+    })
+}
+
+/// 配置中不为空的编译 webhook 地址
+fn compile_webhook_urls(settings: &WebhooksConfig) -> Vec<String> {
+    settings
+        .urls
+        .iter()
+        .map(|url| url.trim())
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 编译成功通知：在后台任务中发送到每个地址，失败时重试，最终失败只记录日志
+pub fn notify_compile(config: &Config, result: &CompileResult, duration: Duration) {
+    let urls = compile_webhook_urls(&config.webhooks);
+    if urls.is_empty() {
+        return;
+    }
+
+    let body = match serde_json::to_vec(&compile_payload(&config.site.url, result, duration)) {
+        Ok(body) => body,
+        Err(e) => {
+            log::error!("Failed to serialize compile webhook payload: {}", e);
+            return;
+        }
+    };
+    for url in urls {
+        let secret = config.webhooks.secret.clone();
+        let body = body.clone();
+        tokio::task::spawn(async move {
+            let mut delay = COMPILE_WEBHOOK_BACKOFF;
+            for attempt in 0..=COMPILE_WEBHOOK_RETRIES {
+                match post_webhook(&url, secret.as_deref(), body.clone()).await {
+                    Ok(status) => {
+                        log::debug!("Compile webhook {} delivered (HTTP {})", url, status);
+                        return;
+                    }
+                    Err(e) if attempt < COMPILE_WEBHOOK_RETRIES => {
+                        log::warn!("Compile webhook {} failed, retrying in {}s: {}", url, delay.as_secs(), e);
+                        tokio::time::sleep(delay).await;
+                        delay *= 2;
+                    }
+                    Err(e) => log::error!("Compile webhook {} failed after {} attempts: {}", url, attempt + 1, e),
+                }
+            }
+        });
+    }
+}
+
+/// 向每个编译 webhook 发送一条测试通知（event 为 "test"），不重试
+#[utoipa::path(
+    post,
+    path = "/api/admin/webhooks/test",
+    tag = "maintenance",
+    summary = "向每个编译 webhook 发送测试通知",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "测试通知已发送到 1 个地址", "results": [{"url": "https://cdn.example/purge", "success": true, "message": "HTTP 200"}]}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn test_compile_webhooks(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let urls = compile_webhook_urls(&config.webhooks);
+    if urls.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "未配置 webhooks.urls"
+        }));
+    }
+
+    let body = serde_json::json!({
+        "event": "test",
+        "site_url": config.site.url,
+        "sent_at": chrono::Utc::now().to_rfc3339()
+    })
+    .to_string()
+    .into_bytes();
+    let mut results = Vec::new();
+    for url in &urls {
+        let result = post_webhook(url, config.webhooks.secret.as_deref(), body.clone()).await;
+        results.push(serde_json::json!({
+            "url": url,
+            "success": result.is_ok(),
+            "message": match result {
+                Ok(status) => format!("HTTP {}", status),
+                Err(e) => e,
+            }
+        }));
+    }
+
+    let failed = results.iter().filter(|result| result["success"] == false).count();
+    if failed == 0 {
+        HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("测试通知已发送到 {} 个地址", urls.len()),
+            "results": results
+        }))
+    } else {
+        HttpResponse::BadGateway().json(serde_json::json!({
+            "status": "error",
+            "message": format!("{} 个地址中有 {} 个发送失败", urls.len(), failed),
+            "results": results
+        }))
+    }
+}
+
+/// 发送一条测试通知（event 为 "test"）
+#[utoipa::path(
+    post,
+    path = "/api/admin/notifications/test",
+    tag = "maintenance",
+    summary = "向配置的 webhook 发送测试通知",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "测试通知已发送（HTTP 200）"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn test_notification(req: actix_web::HttpRequest, config: web::Data<Config>) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    if config.notifications.webhook_url.as_deref().map_or(true, |url| url.trim().is_empty()) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "未配置 webhook_url"
+        }));
+    }
+
+    let comment = Comment {
+        id: "test".to_string(),
+        slug: String::new(),
+        author: config.site.author.clone(),
+        content: "这是一条测试通知".to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        website: None,
+        parent_id: None,
+        email: None,
+        avatar_url: String::new(),
+    };
+    let body = match serde_json::to_vec(&webhook_payload("test", &comment, &config.site.title, &config.site.url)) {
+        Ok(body) => body,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": e.to_string()
+            }));
+        }
+    };
+
+    match send_webhook(&config.notifications, body).await {
+        Ok(status) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("测试通知已发送（HTTP {}）", status)
+        })),
+        Err(e) => HttpResponse::BadGateway().json(serde_json::json!({
+            "status": "error",
+            "message": format!("测试通知发送失败: {}", e)
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let comment = Comment {
+            id: "c1".to_string(),
+            slug: "fruit-apple".to_string(),
+            author: "lin".to_string(),
+            content: "好文".to_string(),
+            created_at: "2024-05-01T00:00:00Z".to_string(),
+            website: None,
+            parent_id: None,
+            email: None,
+            avatar_url: String::new(),
+        };
+        let payload = webhook_payload("new_comment", &comment, "Apple", "https://example.com/");
+        assert_eq!(payload["event"], "new_comment");
+        assert_eq!(payload["comment"]["author"], "lin");
+        assert_eq!(payload["title"], "Apple");
+        assert_eq!(payload["excerpt"], "好文");
+        assert_eq!(payload["page_url"], "https://example.com/fruit-apple");
+        assert_eq!(payload["admin_url"], "https://example.com/admin/comments?slug=fruit-apple#comment-c1");
+
+        let long = "长".repeat(EXCERPT_CHARS + 10);
+        assert_eq!(comment_excerpt(&long), format!("{}…", "长".repeat(EXCERPT_CHARS)));
+    }
+
+    #[actix_web::test]
+    async fn test_new_comment_notifications_captured() {
+        let root = std::path::PathBuf::from("/tmp/lf_blog_notifications_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let url = format!("{}new-comment", CAPTURE_URL_PREFIX);
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        config.site.url = "https://example.com".to_string();
+        config.notifications.webhook_url = Some(url.clone());
+        config.notifications.muted_slugs = vec!["fruit-popular".to_string()];
+
+        let comment = |id: &str, slug: &str| Comment {
+            id: id.to_string(),
+            slug: slug.to_string(),
+            author: "lin".to_string(),
+            content: "好文".to_string(),
+            created_at: "2024-05-01T00:00:00Z".to_string(),
+            website: None,
+            parent_id: None,
+            email: None,
+            avatar_url: String::new(),
+        };
+        notify_new_comment(&config, &comment("c1", "fruit-apple"));
+        notify_new_comment(&config, &comment("c2", "fruit-popular"));
+
+        let mut sent = Vec::new();
+        for _ in 0..50 {
+            sent.extend(take_captured(&url));
+            if !sent.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        // 静音文章的评论不通知；页面不存在时标题为 slug
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        sent.extend(take_captured(&url));
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0]["comment"]["id"], "c1");
+        assert_eq!(sent[0]["title"], "fruit-apple");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_compile_payload() {
+        let mut result = CompileResult {
+            success: true,
+            generated_files: Vec::new(),
+            total_categories: 2,
+            total_items: 5,
+            total_attachments: 3,
+            broken_links: Vec::new(),
+            changed_slugs: None,
+        };
+        let payload = compile_payload("https://example.com", &result, Duration::from_millis(1234));
+        assert_eq!(payload["event"], "compile");
+        assert_eq!(payload["incremental"], false);
+        assert_eq!(payload["total_items"], 5);
+        assert_eq!(payload["duration_ms"], 1234);
+        assert!(payload["changed_slugs"].is_null());
+
+        result.changed_slugs = Some(vec!["fruit-apple".to_string()]);
+        let payload = compile_payload("https://example.com", &result, Duration::from_millis(10));
+        assert_eq!(payload["incremental"], true);
+        assert_eq!(payload["changed_slugs"][0], "fruit-apple");
+
+        let settings = WebhooksConfig {
+            urls: vec![" https://cdn.example/purge ".to_string(), "  ".to_string()],
+            secret: None,
+        };
+        assert_eq!(compile_webhook_urls(&settings), vec!["https://cdn.example/purge"]);
+    }
+}
@@ -0,0 +1,357 @@
+//! API 文档
+//!
+//! OpenAPI 文档（`/api/openapi.json`）由 utoipa 根据各接口处理函数上的
+//! `#[utoipa::path]` 标注和请求、响应类型生成，`/api/docs` 提供浏览页面。
+//! 新增接口时在处理函数上补充标注，并加入下面 `ApiDoc` 的 `paths`，
+//! 测试会检查文档与 `configure_routes`、`configure_admin_routes` 是否一致。
+use actix_web::{web, HttpResponse};
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Deprecated, OpenApi as OpenApiDoc, Ref, ResponseBuilder, ServerBuilder};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::config::Config;
+use crate::error::ErrorBody;
+
+/// 接口文档页面
+const API_DOCS_HTML: &str = include_str!("../../../assets/api-docs.html");
+
+/// 上传压缩包或附件的表单，只用于生成文档
+#[derive(ToSchema)]
+pub struct UploadForm {
+    #[allow(dead_code)] // 处理函数从 multipart 流中读取，不会构造这个结构
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "LF Blog API",
+        description = "需要登录的接口接受 admin_token Cookie，或 Authorization: Bearer 后跟会话令牌或 API 密钥"
+    ),
+    paths(
+        // system
+        crate::routes::health_check,
+        crate::routes::recompile,
+        crate::admin::get_compile_status,
+        openapi_json,
+        api_docs,
+        // content
+        crate::routes::get_recommendations,
+        crate::routes::get_popular_content,
+        crate::routes::get_latest_content,
+        crate::routes::get_view_count,
+        crate::routes::search_content,
+        crate::routes::search_api,
+        crate::routes::search_api_preflight,
+        crate::routes::get_tags,
+        crate::routes::get_tag_cloud,
+        crate::routes::get_items_by_tag,
+        // comments
+        crate::routes::get_comments,
+        crate::routes::add_comment,
+        // analytics
+        crate::analytics::get_daily_stats,
+        crate::analytics::get_source_stats,
+        crate::analytics::get_popular_pages,
+        crate::analytics::get_total_stats,
+        crate::analytics::get_search_keywords,
+        crate::analytics::get_device_stats,
+        crate::analytics::get_country_stats,
+        crate::analytics::get_page_analytics,
+        crate::analytics::get_hourly_heatmap,
+        crate::analytics::get_hourly_breakdown,
+        crate::analytics::get_realtime_visitors,
+        crate::analytics::stream_realtime_visitors,
+        crate::analytics::record_visit,
+        crate::analytics::export_analytics,
+        // auth
+        crate::admin::admin_login,
+        crate::admin::change_admin_password,
+        crate::admin::list_api_keys,
+        crate::admin::create_api_key,
+        crate::admin::revoke_api_key,
+        // admin
+        crate::admin::get_admin_overview,
+        crate::admin::get_categories,
+        crate::admin::create_category,
+        crate::admin::delete_category,
+        crate::admin::rename_category,
+        crate::admin::get_items,
+        crate::admin::create_item,
+        crate::admin::bulk_items,
+        crate::admin::get_item,
+        crate::admin::update_item,
+        crate::admin::delete_item,
+        crate::admin::publish_draft,
+        crate::admin::compile_item,
+        crate::admin::get_item_versions,
+        crate::admin::restore_item_version,
+        crate::admin::get_item_attachments,
+        crate::admin::upload_item_attachment,
+        crate::admin::delete_item_attachment,
+        crate::admin::list_attachments,
+        crate::admin::delete_attachment,
+        crate::admin::preview_markdown,
+        crate::admin::import_content,
+        crate::admin::export_content,
+        crate::admin::get_broken_links,
+        crate::admin::get_content_integrity,
+        crate::admin::get_storage_report,
+        crate::admin::upload_full_content,
+        crate::admin::upload_category_package,
+        crate::admin::upload_item_package,
+        crate::admin::sync_content,
+        // maintenance
+        crate::admin::db_checkpoint,
+        crate::admin::db_vacuum,
+        crate::admin::db_integrity,
+        crate::admin::db_analyze,
+        crate::admin::clear_comment_rate_limits,
+        crate::redirects::get_redirects,
+        crate::redirects::add_redirect,
+        crate::redirects::delete_redirect,
+        crate::notifications::test_notification,
+        crate::notifications::test_compile_webhooks,
+    ),
+    components(schemas(ErrorBody, crate::comments::Comment, crate::analytics::VisitRecord)),
+    tags(
+        (name = "system", description = "健康检查、编译和文档"),
+        (name = "content", description = "公开的内容、搜索和标签"),
+        (name = "comments", description = "评论"),
+        (name = "analytics", description = "访问统计"),
+        (name = "auth", description = "管理员登录、密码和 API 密钥"),
+        (name = "admin", description = "后台内容管理"),
+        (name = "maintenance", description = "数据库维护、重定向和通知"),
+    ),
+    modifiers(&AdminAuth, &LegacyAliases)
+)]
+struct ApiDoc;
+
+/// 注册 Cookie 和 Bearer 两种认证方式，并为需要登录的接口补充 401 响应
+struct AdminAuth;
+
+impl Modify for AdminAuth {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "cookieAuth",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("admin_token"))),
+        );
+        components.add_security_scheme(
+            "bearerAuth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+
+        let unauthorized = ResponseBuilder::new()
+            .description("未登录或 API 密钥无效")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("ErrorBody")))
+                    .example(Some(serde_json::json!({
+                        "status": "error",
+                        "code": "unauthorized",
+                        "message": "未授权访问，请先登录"
+                    })))
+                    .build(),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            let operations: [&mut Option<Operation>; 5] =
+                [&mut item.get, &mut item.post, &mut item.put, &mut item.delete, &mut item.options];
+            for operation in operations.into_iter().flatten() {
+                if operation.security.is_some() {
+                    operation.responses.responses.insert("401".to_string(), unauthorized.clone().into());
+                }
+            }
+        }
+    }
+}
+
+/// 仍然注册的旧地址及其对应的新地址
+const LEGACY_ALIASES: &[(&str, &str)] = &[("/api/recompile/status", "/api/admin/compile/status")];
+
+/// 旧地址复制新地址的文档，并标记为已弃用
+struct LegacyAliases;
+
+impl Modify for LegacyAliases {
+    fn modify(&self, openapi: &mut OpenApiDoc) {
+        for (alias, path) in LEGACY_ALIASES {
+            let Some(mut item) = openapi.paths.paths.get(*path).cloned() else {
+                continue;
+            };
+            let operations: [&mut Option<Operation>; 5] =
+                [&mut item.get, &mut item.post, &mut item.put, &mut item.delete, &mut item.options];
+            for operation in operations.into_iter().flatten() {
+                operation.operation_id = operation.operation_id.take().map(|id| format!("{}_legacy", id));
+                operation.deprecated = Some(Deprecated::True);
+            }
+            openapi.paths.paths.insert(alias.to_string(), item);
+        }
+    }
+}
+
+/// 生成完整的 OpenAPI 文档，标题和服务器地址取自站点配置
+pub fn build_spec(config: &Config) -> OpenApiDoc {
+    let mut spec = ApiDoc::openapi();
+    if !config.site.title.trim().is_empty() {
+        spec.info.title = format!("{} API", config.site.title);
+    }
+    let site_url = config.site.url.trim().trim_end_matches('/');
+    if !site_url.is_empty() {
+        spec.servers = Some(vec![ServerBuilder::new().url(site_url).build()]);
+    }
+    spec
+}
+
+/// 返回 OpenAPI 文档
+#[utoipa::path(
+    get,
+    path = "/api/openapi.json",
+    tag = "system",
+    summary = "本文档（OpenAPI 3.1）",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"openapi": "3.1.0", "info": {"title": "LF Blog API", "version": "0.1.0"}, "paths": {}})))
+)]
+pub async fn openapi_json(config: web::Data<Config>) -> HttpResponse {
+    HttpResponse::Ok().json(build_spec(&config))
+}
+
+/// 接口文档浏览页面，从 `/api/openapi.json` 读取文档
+#[utoipa::path(
+    get,
+    path = "/api/docs",
+    tag = "system",
+    summary = "接口文档浏览页面",
+    responses((status = 200, description = "成功", body = String, content_type = "text/html"))
+)]
+pub async fn api_docs() -> HttpResponse {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(API_DOCS_HTML)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::BTreeSet;
+
+    /// 把 actix 路由写法转为 OpenAPI 路径：`{path:.*}` -> `{path}`
+    fn openapi_path(route: &str) -> String {
+        let mut path = String::with_capacity(route.len());
+        let mut rest = route;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+                break;
+            };
+            let name = rest[start + 1..end].split(':').next().unwrap_or_default();
+            path.push_str(&rest[..start]);
+            path.push_str(&format!("{{{}}}", name));
+            rest = &rest[end + 1..];
+        }
+        path.push_str(rest);
+        path
+    }
+
+    /// 从路由配置函数的源码中找出 `/api` 接口和 `/health`，返回 (方法, OpenAPI 路径)
+    fn registered_routes(source: &str, function: &str) -> BTreeSet<(String, String)> {
+        let start = source.find(&format!("pub fn {}(", function)).unwrap();
+        let end = start + source[start..].find("\n}\n").unwrap();
+        let body = &source[start..end];
+
+        let mut routes = BTreeSet::new();
+        for (index, _) in body.match_indices("route(\"") {
+            let rest = &body[index + "route(\"".len()..];
+            let path = &rest[..rest.find('"').unwrap()];
+            if !(path.starts_with("/api/") || path == "/health") {
+                continue;
+            }
+            let handler = &rest[path.len()..];
+            let method = ["get", "post", "put", "delete"]
+                .into_iter()
+                .find(|method| handler.trim_start_matches(['"', ',', ' ']).starts_with(&format!("web::{}()", method)))
+                .or_else(|| handler.contains("Method::OPTIONS").then_some("options"))
+                .unwrap_or_else(|| panic!("unknown method for {}", path));
+            routes.insert((method.to_string(), openapi_path(path)));
+        }
+        routes
+    }
+
+    /// 文档中的全部 (方法, 路径)
+    fn documented_routes(spec: &Value) -> BTreeSet<(String, String)> {
+        let mut routes = BTreeSet::new();
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                routes.insert((method.clone(), path.clone()));
+            }
+        }
+        routes
+    }
+
+    fn spec_json(config: &Config) -> Value {
+        serde_json::from_str(&build_spec(config).to_json().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_spec_covers_registered_routes() {
+        let mut registered = registered_routes(include_str!("routes.rs"), "configure_routes");
+        registered.extend(registered_routes(include_str!("admin.rs"), "configure_admin_routes"));
+        assert!(registered.len() > 50, "{:?}", registered);
+
+        let documented = documented_routes(&spec_json(&Config::default()));
+        let missing: Vec<_> = registered.difference(&documented).collect();
+        let stale: Vec<_> = documented.difference(&registered).collect();
+        assert!(missing.is_empty(), "routes missing from the OpenAPI spec: {:?}", missing);
+        assert!(stale.is_empty(), "documented routes that are not registered: {:?}", stale);
+    }
+
+    #[test]
+    fn test_build_spec() {
+        let mut config = Config::default();
+        config.site.url = "https://blog.example.com/".to_string();
+        config.site.title = "Wine Notes".to_string();
+        let spec = spec_json(&config);
+
+        assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+        assert_eq!(spec["info"]["title"], "Wine Notes API");
+        assert_eq!(spec["info"]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(spec["servers"][0]["url"], "https://blog.example.com");
+        let tags: BTreeSet<&str> = spec["tags"].as_array().unwrap().iter().map(|tag| tag["name"].as_str().unwrap()).collect();
+        let mut operation_ids = BTreeSet::new();
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            assert!(path.starts_with('/') && !path.contains(':'), "{}", path);
+            for (method, operation) in item.as_object().unwrap() {
+                assert!(tags.contains(operation["tags"][0].as_str().unwrap()), "{} {}", method, path);
+                assert!(operation_ids.insert(operation["operationId"].as_str().unwrap().to_string()));
+                assert!(!operation["summary"].as_str().unwrap_or_default().is_empty(), "{} {}", method, path);
+                // JSON 响应都带示例
+                for (status, response) in operation["responses"].as_object().unwrap() {
+                    if let Some(media) = response.get("content").and_then(|content| content.get("application/json")) {
+                        assert!(media.get("example").is_some(), "{} {} {}", method, path, status);
+                    }
+                }
+                // 路径参数都已声明
+                let declared = operation["parameters"].as_array().map_or(0, |params| params.iter().filter(|p| p["in"] == "path").count());
+                assert_eq!(declared, path.matches('{').count(), "{} {}", method, path);
+                // 需要登录的接口都有 401 响应
+                assert_eq!(operation.get("security").is_some(), operation["responses"].get("401").is_some(), "{} {}", method, path);
+            }
+        }
+
+        let item = &spec["paths"]["/api/admin/items/{slug}"]["put"];
+        assert_eq!(item["requestBody"]["content"]["application/json"]["example"]["item_name"], "merlot");
+        assert_eq!(item["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/UpdateItemRequest");
+        assert_eq!(item["security"][1]["bearerAuth"], json!([]));
+        assert_eq!(item["responses"]["401"]["content"]["application/json"]["example"]["status"], "error");
+        assert!(spec["paths"]["/api/admin/api-keys"]["post"]["responses"]["201"].is_object());
+        assert!(spec["paths"]["/api/analytics/record-visit/{page_slug}"]["post"].is_object());
+        assert!(spec["paths"]["/api/search"]["get"].get("security").is_none());
+        let upload = &spec["paths"]["/api/admin/upload/full"]["post"]["requestBody"]["content"]["multipart/form-data"];
+        assert_eq!(upload["schema"]["properties"]["file"]["format"], "binary");
+        let schemas = &spec["components"]["schemas"];
+        for name in ["ErrorBody", "Comment", "VisitRecord", "CreateCategoryRequest", "LoginResponse"] {
+            assert!(schemas[name].is_object(), "{}", name);
+        }
+        assert_eq!(spec["components"]["securitySchemes"]["cookieAuth"]["name"], "admin_token");
+    }
+}
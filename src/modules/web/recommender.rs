@@ -10,7 +10,9 @@ pub struct RecommendedItem {
     pub category: String,
     pub category_name: String,
     pub description: Option<String>,
+    pub excerpt: Option<String>,
     pub score: f64,
+    pub comment_count: u64, // 由接口按评论数缓存填充
 }
 
 /// 推荐系统
@@ -18,6 +20,8 @@ pub struct Recommender {
     site_content: SiteContent,
     /// 关键词到文章索引的映射
     keyword_index: HashMap<String, Vec<usize>>,
+    /// 文章浏览量（slug -> 次数）
+    view_counts: HashMap<String, i64>,
 }
 
 impl Recommender {
@@ -39,9 +43,16 @@ impl Recommender {
         Self {
             site_content,
             keyword_index,
+            view_counts: HashMap::new(),
         }
     }
 
+    /// 设置文章浏览量，用于热门排序
+    pub fn with_view_counts(mut self, view_counts: HashMap<String, i64>) -> Self {
+        self.view_counts = view_counts;
+        self
+    }
+
     /// 提取文章的关键词
     fn extract_keywords(item: &ContentItem) -> Vec<String> {
         let mut keywords = Vec::new();
@@ -127,7 +138,9 @@ impl Recommender {
                     category: item.category.clone(),
                     category_name: self.get_category_name(&item.category),
                     description: item.description.clone(),
+                    excerpt: item.excerpt.clone(),
                     score: *score,
+                    comment_count: 0,
                 })
             })
             .collect();
@@ -158,9 +171,9 @@ impl Recommender {
         deduped.into_iter().take(limit).collect()
     }
 
-    /// 获取热门文章
+    /// 获取热门文章（按浏览量排序）
     pub fn get_popular_items(&self, limit: usize) -> Vec<RecommendedItem> {
-        let items: Vec<_> = self
+        let mut items: Vec<_> = self
             .site_content
             .all_items()
             .into_iter()
@@ -170,11 +183,19 @@ impl Recommender {
                 category: item.category.clone(),
                 category_name: self.get_category_name(&item.category),
                 description: item.description.clone(),
-                score: 0.0,
+                excerpt: item.excerpt.clone(),
+                score: self.view_counts.get(&item.url).copied().unwrap_or(0) as f64,
+                comment_count: 0,
             })
-            .take(limit * 2)
             .collect();
 
+        // 浏览量相同时保持原有顺序
+        items.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
         // 按分类去重
         let mut seen_categories = std::collections::HashSet::new();
         items
@@ -198,7 +219,9 @@ impl Recommender {
                 category: item.category.clone(),
                 category_name: self.get_category_name(&item.category),
                 description: item.description.clone(),
+                excerpt: item.excerpt.clone(),
                 score: 0.0,
+                comment_count: 0,
             })
             .collect();
 
@@ -228,7 +251,7 @@ impl Recommender {
             .categories
             .iter()
             .find(|cat| cat.url == category_url)
-            .map(|cat| cat.name.clone())
+            .map(|cat| cat.title.clone())
             .unwrap_or_else(|| category_url.to_string())
     }
 
@@ -259,7 +282,9 @@ impl Recommender {
                 category: item.category.clone(),
                 category_name: self.get_category_name(&item.category),
                 description: item.description.clone(),
+                excerpt: item.excerpt.clone(),
                 score: 0.0,
+                comment_count: 0,
             })
             .take(limit)
             .collect();
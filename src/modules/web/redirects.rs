@@ -0,0 +1,348 @@
+use crate::analytics;
+use crate::config::Config;
+use actix_web::guard::GuardContext;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// 重定向规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct RedirectRule {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+/// redirects.toml 文件结构
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RedirectsFile {
+    #[serde(default, rename = "redirect")]
+    redirects: Vec<RedirectRule>,
+}
+
+/// 删除重定向请求
+#[derive(Debug, Deserialize)]
+pub struct DeleteRedirectQuery {
+    pub from: String,
+}
+
+/// 运行时共享的重定向规则
+pub struct RedirectStore {
+    path: PathBuf,
+    rules: RwLock<Vec<RedirectRule>>,
+}
+
+impl RedirectStore {
+    /// 创建空的规则集
+    pub fn empty(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            rules: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 从文件加载重定向规则（文件不存在时为空）
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rules = if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            let file: RedirectsFile = toml::from_str(&content)?;
+            file.redirects
+        } else {
+            Vec::new()
+        };
+
+        for rule in &mut rules {
+            rule.from = normalize_path(&rule.from);
+        }
+        warn_redirect_loops(&rules);
+
+        let store = Self::empty(path);
+        *store.rules.write().map_err(|_| "Redirect rules lock poisoned")? = rules;
+        Ok(store)
+    }
+
+    /// 获取所有规则
+    pub fn rules(&self) -> Vec<RedirectRule> {
+        self.rules.read().map(|rules| rules.clone()).unwrap_or_default()
+    }
+
+    /// 查找匹配路径的规则
+    pub fn find(&self, path: &str) -> Option<RedirectRule> {
+        let path = normalize_path(path);
+        self.rules
+            .read()
+            .ok()?
+            .iter()
+            .find(|rule| rule.from == path)
+            .cloned()
+    }
+
+    /// 添加或替换规则并写回文件
+    pub fn upsert(&self, mut rule: RedirectRule) -> Result<(), Box<dyn std::error::Error>> {
+        rule.from = normalize_path(&rule.from);
+        let mut rules = self.rules.write().map_err(|_| "Redirect rules lock poisoned")?;
+        rules.retain(|r| r.from != rule.from);
+        rules.push(rule);
+        warn_redirect_loops(&rules);
+        self.save(&rules)
+    }
+
+    /// 删除规则并写回文件
+    pub fn remove(&self, from: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        let from = normalize_path(from);
+        let mut rules = self.rules.write().map_err(|_| "Redirect rules lock poisoned")?;
+        let before = rules.len();
+        rules.retain(|r| r.from != from);
+        if rules.len() == before {
+            return Ok(false);
+        }
+        self.save(&rules)?;
+        Ok(true)
+    }
+
+    fn save(&self, rules: &[RedirectRule]) -> Result<(), Box<dyn std::error::Error>> {
+        let file = RedirectsFile {
+            redirects: rules.to_vec(),
+        };
+        std::fs::write(&self.path, toml::to_string_pretty(&file)?)?;
+        Ok(())
+    }
+}
+
+/// 统一路径格式：以 / 开头，不以 / 结尾
+fn normalize_path(path: &str) -> String {
+    format!("/{}", path.trim_matches('/'))
+}
+
+/// 查找重定向循环（如 A -> B -> A）
+pub fn find_redirect_loops(rules: &[RedirectRule]) -> Vec<Vec<String>> {
+    let targets: HashMap<String, String> = rules
+        .iter()
+        .map(|rule| (normalize_path(&rule.from), normalize_path(&rule.to)))
+        .collect();
+
+    let mut loops = Vec::new();
+    let mut reported: HashSet<String> = HashSet::new();
+
+    for start in targets.keys() {
+        if reported.contains(start) {
+            continue;
+        }
+        let mut chain = vec![start.clone()];
+        let mut current = start;
+        while let Some(next) = targets.get(current) {
+            if let Some(pos) = chain.iter().position(|p| p == next) {
+                let mut cycle = chain[pos..].to_vec();
+                if cycle.iter().all(|p| !reported.contains(p)) {
+                    reported.extend(cycle.iter().cloned());
+                    cycle.push(next.clone());
+                    loops.push(cycle);
+                }
+                break;
+            }
+            chain.push(next.clone());
+            current = next;
+        }
+    }
+    loops
+}
+
+fn warn_redirect_loops(rules: &[RedirectRule]) {
+    for cycle in find_redirect_loops(rules) {
+        log::warn!("Redirect loop detected: {}", cycle.join(" -> "));
+    }
+}
+
+/// 路由守卫：请求路径是否命中重定向规则
+pub fn matches_redirect(ctx: &GuardContext) -> bool {
+    ctx.app_data::<web::Data<RedirectStore>>()
+        .map(|store| store.find(ctx.head().uri.path()).is_some())
+        .unwrap_or(false)
+}
+
+/// 执行重定向并记录访问
+pub async fn redirect(
+    req: HttpRequest,
+    store: web::Data<RedirectStore>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let path = req.path().to_string();
+    let rule = match store.find(&path) {
+        Some(rule) => rule,
+        None => return HttpResponse::NotFound().body("Page not found"),
+    };
+
+    let target_slug = rule.to.trim_start_matches('/').to_string();
+    if !analytics::do_not_track(&req) {
+        let record = analytics::build_visit_record(&req, target_slug, Some(rule.from.clone()), &config);
+        analytics::save_visit_record(&record, &config);
+    }
+
+    let mut response = if rule.permanent {
+        HttpResponse::MovedPermanently()
+    } else {
+        HttpResponse::Found()
+    };
+    response
+        .append_header((actix_web::http::header::LOCATION, rule.to))
+        .finish()
+}
+
+// ==================== 管理 API ====================
+
+/// 获取所有重定向规则
+#[utoipa::path(
+    get,
+    path = "/api/admin/redirects",
+    tag = "maintenance",
+    summary = "列出重定向规则",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "redirects": [{"from": "/old-post", "to": "/wine/merlot", "permanent": true}], "count": 1}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn get_redirects(
+    req: HttpRequest,
+    store: web::Data<RedirectStore>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let rules = store.rules();
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "redirects": rules,
+        "count": rules.len()
+    }))
+}
+
+/// 添加或更新重定向规则
+#[utoipa::path(
+    post,
+    path = "/api/admin/redirects",
+    tag = "maintenance",
+    summary = "添加或替换重定向规则",
+    request_body(content = RedirectRule, example = json!({"from": "/old-post", "to": "/wine/merlot", "permanent": true})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "重定向规则已保存"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn add_redirect(
+    req: HttpRequest,
+    body: web::Json<RedirectRule>,
+    store: web::Data<RedirectStore>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    let rule = body.into_inner();
+    if rule.from.trim_matches('/').is_empty() || rule.to.trim().is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "from 和 to 不能为空"
+        }));
+    }
+
+    match store.upsert(rule) {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "重定向规则已保存"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("保存重定向规则失败: {}", e)
+        })),
+    }
+}
+
+/// 删除重定向规则
+#[utoipa::path(
+    delete,
+    path = "/api/admin/redirects",
+    tag = "maintenance",
+    summary = "删除重定向规则",
+    params(("from" = Option<String>, Query, description = "规则的来源路径")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "重定向规则已删除"}))),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn delete_redirect(
+    req: HttpRequest,
+    query: web::Query<DeleteRedirectQuery>,
+    store: web::Data<RedirectStore>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    if !crate::admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
+    match store.remove(&query.from) {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "重定向规则已删除"
+        })),
+        Ok(false) => HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "重定向规则不存在"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("删除重定向规则失败: {}", e)
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(from: &str, to: &str) -> RedirectRule {
+        RedirectRule {
+            from: from.to_string(),
+            to: to.to_string(),
+            permanent: true,
+        }
+    }
+
+    #[test]
+    fn test_find_redirect_loops() {
+        let rules = vec![rule("/a", "/b"), rule("/b", "/a"), rule("/c", "/d")];
+        let loops = find_redirect_loops(&rules);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 3);
+
+        let rules = vec![rule("/old", "/new"), rule("/new/", "/newest")];
+        assert!(find_redirect_loops(&rules).is_empty());
+    }
+
+    #[test]
+    fn test_parse_redirects_file() {
+        let content = r#"
+[[redirect]]
+from = "/old-slug"
+to = "/new-slug"
+permanent = true
+
+[[redirect]]
+from = "/temp"
+to = "/elsewhere"
+"#;
+        let file: RedirectsFile = toml::from_str(content).unwrap();
+        assert_eq!(file.redirects.len(), 2);
+        assert!(file.redirects[0].permanent);
+        assert!(!file.redirects[1].permanent);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::storage::{AttachmentMeta, PageType};
+use actix_web::web::Bytes;
+use lru::LruCache;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+
+/// 已编译页面和小附件的内存缓存，命中时不访问 SQLite
+///
+/// 条目按写入时的存储修改代数（`storage::cache_generation`）区分：页面或附件
+/// 被编译、编辑或删除后代数递增，下一次访问缓存时清空所有旧条目。
+pub struct RenderCache {
+    pages: Option<LruCache<String, CachedPage>>, // 容量为 0 时不缓存
+    attachments: Option<LruCache<String, CachedAttachment>>,
+    generation: u64,
+    hits: u64,
+    misses: u64,
+    attachment_hits: u64,
+    attachment_misses: u64,
+}
+
+/// 缓存的页面：HTML、默认语言下的 gzip / brotli 压缩正文、校验请求所需的更新时间以及相邻文章的 Link 头
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedPage {
+    pub html: String,
+    pub gzip: Option<Bytes>,
+    pub brotli: Option<Bytes>,
+    pub updated_at: String,
+    pub page_type: PageType,
+    pub links: Option<String>,
+}
+
+/// 缓存的附件：元信息和完整内容
+#[derive(Debug, Clone)]
+pub struct CachedAttachment {
+    pub meta: AttachmentMeta,
+    pub data: Bytes,
+}
+
+/// 缓存命中统计，显示在健康检查中
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+    pub attachment_hits: u64,
+    pub attachment_misses: u64,
+    pub attachment_entries: usize,
+    pub generation: u64,
+}
+
+impl RenderCache {
+    /// 创建最多缓存 `capacity` 个页面（以及同样数量的附件）的缓存
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            pages: NonZeroUsize::new(capacity).map(LruCache::new),
+            attachments: NonZeroUsize::new(capacity).map(LruCache::new),
+            generation: 0,
+            hits: 0,
+            misses: 0,
+            attachment_hits: 0,
+            attachment_misses: 0,
+        }
+    }
+
+    /// 取出当前代数下缓存的页面，并记录命中或未命中
+    pub fn get(&mut self, slug: &str, generation: u64) -> Option<CachedPage> {
+        self.sync(generation);
+        let page = self.pages.as_mut().and_then(|pages| pages.get(slug)).cloned();
+        if page.is_some() {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+        page
+    }
+
+    /// 缓存页面，替换同一 slug 的旧版本。`generation` 须在读取页面之前取得，
+    /// 读取期间数据被修改时这次写入会被丢弃
+    pub fn insert(&mut self, slug: &str, page: CachedPage, generation: u64) {
+        if generation < self.generation {
+            return;
+        }
+        self.sync(generation);
+        if let Some(pages) = self.pages.as_mut() {
+            pages.put(slug.to_string(), page);
+        }
+    }
+
+    /// 取出当前代数下缓存的附件
+    pub fn get_attachment(&mut self, filename: &str, generation: u64) -> Option<CachedAttachment> {
+        self.sync(generation);
+        let attachment = self.attachments.as_mut().and_then(|attachments| attachments.get(filename)).cloned();
+        if attachment.is_some() {
+            self.attachment_hits += 1;
+        } else {
+            self.attachment_misses += 1;
+        }
+        attachment
+    }
+
+    /// 缓存附件，是否缓存（大小上限）由调用方决定
+    pub fn insert_attachment(&mut self, attachment: CachedAttachment, generation: u64) {
+        if generation < self.generation {
+            return;
+        }
+        self.sync(generation);
+        if let Some(attachments) = self.attachments.as_mut() {
+            attachments.put(attachment.meta.filename.clone(), attachment);
+        }
+    }
+
+    /// 代数变化后清空旧条目
+    fn sync(&mut self, generation: u64) {
+        if generation != self.generation {
+            self.clear();
+            self.generation = generation;
+        }
+    }
+
+    /// 清空所有缓存条目，统计数据保留
+    pub fn clear(&mut self) {
+        if let Some(pages) = self.pages.as_mut() {
+            pages.clear();
+        }
+        if let Some(attachments) = self.attachments.as_mut() {
+            attachments.clear();
+        }
+    }
+
+    pub fn stats(&self) -> RenderCacheStats {
+        RenderCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.pages.as_ref().map_or(0, |pages| pages.len()),
+            capacity: self.pages.as_ref().map_or(0, |pages| pages.cap().get()),
+            attachment_hits: self.attachment_hits,
+            attachment_misses: self.attachment_misses,
+            attachment_entries: self.attachments.as_ref().map_or(0, |attachments| attachments.len()),
+            generation: self.generation,
+        }
+    }
+}
+
+/// 页面版本的 etag
+pub fn page_etag(slug: &str, updated_at: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(slug.as_bytes())
+        .chain_update([0])
+        .chain_update(updated_at.as_bytes())
+        .finalize();
+    digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(html: &str) -> CachedPage {
+        CachedPage {
+            html: html.to_string(),
+            gzip: None,
+            brotli: None,
+            updated_at: "2024-05-01T00:00:00Z".to_string(),
+            page_type: PageType::Item,
+            links: None,
+        }
+    }
+
+    #[test]
+    fn test_render_cache() {
+        let mut cache = RenderCache::new(2);
+        assert_ne!(
+            page_etag("grape-tizi", "2024-05-01T00:00:00Z"),
+            page_etag("grape-tizi", "2024-05-02T00:00:00Z")
+        );
+
+        assert_eq!(cache.get("grape-tizi", 1), None);
+        cache.insert("grape-tizi", page("<p>v1</p>"), 1);
+        assert_eq!(cache.get("grape-tizi", 1).map(|p| p.html).as_deref(), Some("<p>v1</p>"));
+        // 数据修改后旧条目全部失效
+        assert_eq!(cache.get("grape-tizi", 2), None);
+        // 修改前开始的读取不会写回旧内容
+        cache.insert("grape-tizi", page("<p>v1</p>"), 1);
+        assert_eq!(cache.get("grape-tizi", 2), None);
+
+        // 超出容量时淘汰最久未使用的页面
+        cache.insert("grape-tizi", page("<p>v2</p>"), 2);
+        cache.insert("a", page("a"), 2);
+        cache.insert("b", page("b"), 2);
+        assert_eq!(cache.get("grape-tizi", 2), None);
+
+        let meta = AttachmentMeta {
+            rowid: 1,
+            slug: "grape-tizi".to_string(),
+            filename: "abc.jpg".to_string(),
+            mime_type: "image/jpeg".to_string(),
+            file_size: 3,
+            updated_at: String::new(),
+            content_hash: String::new(),
+            file_path: None,
+        };
+        assert!(cache.get_attachment("abc.jpg", 2).is_none());
+        cache.insert_attachment(CachedAttachment { meta, data: Bytes::from_static(b"abc") }, 2);
+        assert_eq!(cache.get_attachment("abc.jpg", 2).unwrap().data, Bytes::from_static(b"abc"));
+        assert_eq!(
+            cache.stats(),
+            RenderCacheStats {
+                hits: 1,
+                misses: 4,
+                entries: 2,
+                capacity: 2,
+                attachment_hits: 1,
+                attachment_misses: 1,
+                attachment_entries: 1,
+                generation: 2,
+            }
+        );
+
+        cache.clear();
+        assert_eq!(cache.get("a", 2), None);
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().attachment_entries, 0);
+
+        let mut disabled = RenderCache::new(0);
+        disabled.insert("a", page("a"), 0);
+        assert_eq!(disabled.get("a", 0), None);
+        assert_eq!(disabled.stats().capacity, 0);
+    }
+}
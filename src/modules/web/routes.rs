@@ -1,24 +1,65 @@
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
 use crate::config::Config;
-use crate::compiler::Compiler;
-use crate::storage::StorageDB;
+use crate::compiler::{Compiler, NOT_FOUND_SLUG};
+use crate::storage::{self, AttachmentMeta, PageType, SearchOptions, StorageDB};
 use crate::admin;
 use crate::analytics;
 use crate::comments;
 use crate::recommender;
+use crate::redirects;
+use crate::notifications;
+use crate::openapi;
+use crate::render_cache::{self, CachedAttachment, CachedPage, RenderCache};
+use crate::tags::TagFilter;
+use crate::i18n;
 use crate::templates::TemplateRenderer;
 use crate::scanner::{SiteContent, Category, ContentItem};
+use crate::markdown::html_to_text;
 use serde_json;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// When the routes were first configured, for the uptime in `/health`
+static STARTED_AT: OnceLock<Instant> = OnceLock::new();
+
+/// Count an article view off the request path
+fn count_view(storage_path: &str, slug: &str) {
+    let storage_path = storage_path.to_string();
+    let slug = slug.to_string();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = StorageDB::new(&storage_path).and_then(|db| db.increment_view_count(&slug)) {
+            log::warn!("Failed to record view for {}: {}", slug, e);
+        }
+    });
+}
 
 /// Serve pages from database
-pub async fn serve_page(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
+pub async fn serve_page(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+    compiler_state: Option<web::Data<Mutex<admin::CompilerState>>>,
+    render_cache: Option<web::Data<Mutex<RenderCache>>>,
+) -> impl Responder {
     let requested_path = path.into_inner();
 
     // Skip attachment paths (they should be handled by serve_attachment)
     if requested_path.starts_with("attachment/") {
-        return HttpResponse::NotFound().body("Attachment not found");
+        return not_found_page(&req, &config, "Attachment not found");
+    }
+
+    // Unknown API endpoints answer in JSON like the rest of the API
+    if requested_path.trim_start_matches('/').starts_with("api/") {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": "Not found"
+        }));
     }
 
+    admin::reload_templates_if_changed(&config, compiler_state.as_ref());
+
     // Clean the path - remove leading/trailing slashes and prevent directory traversal
     let clean_path = requested_path
         .trim_start_matches('/')
@@ -32,8 +73,29 @@ pub async fn serve_page(path: web::Path<String>, config: web::Data<Config>) -> i
         clean_path
     };
 
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+
+    // Hot pages are answered from memory without touching SQLite
+    let generation = storage::cache_generation();
+    let cached = render_cache
+        .as_ref()
+        .filter(|_| slug != NOT_FOUND_SLUG)
+        .and_then(|cache| cache.lock().ok().and_then(|mut cache| cache.get(&slug, generation)));
+    if let Some(page) = cached {
+        if page.page_type == PageType::Item {
+            count_view(&storage_path, &slug);
+        }
+        let (mut response, not_modified) = page_response(&req, &config, &slug, &page.updated_at);
+        if not_modified {
+            return response.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+        }
+        if let Some(links) = &page.links {
+            response.insert_header((header::LINK, links.as_str()));
+        }
+        return page_body(&req, &config, response, &page.html, page.gzip, page.brotli);
+    }
+
     // Create storage connection
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -42,16 +104,70 @@ pub async fn serve_page(path: web::Path<String>, config: web::Data<Config>) -> i
         }
     };
 
+    // Answer conditional requests without loading the page body
+    if slug != NOT_FOUND_SLUG {
+        if let Ok(Some((page_type, updated_at))) = storage.get_page_version(&slug) {
+            let (mut response, not_modified) = page_response(&req, &config, &slug, &updated_at);
+            if not_modified {
+                if page_type == PageType::Item {
+                    count_view(&storage_path, &slug);
+                }
+                return response.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+            }
+        }
+    }
+
     // Try to get page from database
     match storage.get_page(&slug) {
         Ok(Some(page)) => {
-            HttpResponse::Ok()
-                .content_type("text/html; charset=utf-8")
-                .body(page.content)
+            // Scheduled articles stay hidden until their publish time
+            if page.is_scheduled() {
+                return not_found_page(&req, &config, "Page not found");
+            }
+            if page.slug == NOT_FOUND_SLUG {
+                return HttpResponse::NotFound()
+                    .content_type("text/html; charset=utf-8")
+                    .body(localized(&req, &config, &page.content));
+            }
+
+            if page.page_type == PageType::Item {
+                count_view(&storage_path, &slug);
+            }
+            let (mut response, not_modified) = page_response(&req, &config, &slug, &page.updated_at);
+            if not_modified {
+                return response.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+            }
+            let links = adjacent_links(&storage, &page);
+            if let Some(links) = &links {
+                response.insert_header((header::LINK, links.as_str()));
+            }
+            let (gzip, brotli) = match storage.get_compressed_page(&slug, default_language(&config)) {
+                Ok(Some((gzip, brotli))) => (Some(web::Bytes::from(gzip)), brotli.map(web::Bytes::from)),
+                Ok(None) => (None, None),
+                Err(e) => {
+                    log::warn!("Failed to load compressed page {}: {}", slug, e);
+                    (None, None)
+                }
+            };
+            let body = page_body(&req, &config, response, &page.content, gzip.clone(), brotli.clone());
+            if let Some(cache) = &render_cache {
+                if let Ok(mut cache) = cache.lock() {
+                    let cached = CachedPage {
+                        html: page.content,
+                        gzip,
+                        brotli,
+                        updated_at: page.updated_at,
+                        page_type: page.page_type,
+                        links,
+                    };
+                    cache.insert(&slug, cached, generation);
+                }
+            }
+            body
         }
         Ok(None) => {
             log::debug!("Page not found in database: {}", slug);
-            HttpResponse::NotFound().body("Page not found")
+            not_found_page(&req, &config, "Page not found")
         }
         Err(e) => {
             log::error!("Failed to get page from database: {}", e);
@@ -60,13 +176,176 @@ pub async fn serve_page(path: web::Path<String>, config: web::Data<Config>) -> i
     }
 }
 
+/// `Link` header pointing at the previous and next item of the same category,
+/// from `StorageDB::get_adjacent_items`; `None` for other pages and for an
+/// item that is alone in its category
+fn adjacent_links(storage: &StorageDB, page: &storage::Page) -> Option<String> {
+    let category = page.category.as_deref().filter(|_| page.page_type == PageType::Item)?;
+    let (prev, next) = storage.get_adjacent_items(&page.slug, category).unwrap_or_else(|e| {
+        log::warn!("Failed to look up items next to {}: {}", page.slug, e);
+        (None, None)
+    });
+    let links: Vec<String> = [(prev, "prev"), (next, "next")]
+        .into_iter()
+        .filter_map(|(item, rel)| item.map(|item| format!("</{}>; rel=\"{}\"", item.slug, rel)))
+        .collect();
+    (!links.is_empty()).then(|| links.join(", "))
+}
+
+/// `200 OK` builder with the caching headers of a stored page, and whether the
+/// request's validators still match it. The HTML is localized per request, so
+/// the weak ETag includes the language and responses vary on its sources.
+/// Every compile rewrites `updated_at`, which changes the ETag.
+fn page_response(req: &HttpRequest, config: &Config, slug: &str, updated_at: &str) -> (HttpResponseBuilder, bool) {
+    let etag = format!("\"{}-{}\"", render_cache::page_etag(slug, updated_at), request_language(req, config));
+    let last_modified = parse_page_time(updated_at);
+    let cache_control = match config.server.page_cache_seconds {
+        0 => "public, no-cache".to_string(),
+        seconds => format!("public, max-age={}", seconds),
+    };
+
+    let mut builder = HttpResponse::Ok();
+    builder
+        .insert_header((header::ETAG, format!("W/{}", etag)))
+        .insert_header((header::CACHE_CONTROL, cache_control))
+        .insert_header((header::VARY, "Cookie, X-Language, Accept-Encoding"));
+    if let Some(modified) = last_modified {
+        builder.insert_header((header::LAST_MODIFIED, format_http_date(&modified)));
+    }
+    let not_modified = is_not_modified(req, &etag, last_modified.as_ref());
+    (builder, not_modified)
+}
+
+/// Page `updated_at`, stored as RFC 3339 by `StorageDB::save_page`
+fn parse_page_time(updated_at: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(updated_at)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+}
+
+/// 404 response with the compiled `404.html` page, or `fallback` as plain
+/// text before the site has been compiled
+fn not_found_page(req: &HttpRequest, config: &Config, fallback: &'static str) -> HttpResponse {
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    match StorageDB::new(&storage_path).and_then(|storage| storage.get_page(NOT_FOUND_SLUG)) {
+        Ok(Some(page)) => HttpResponse::NotFound()
+            .content_type("text/html; charset=utf-8")
+            .body(localized(req, config, &page.content)),
+        _ => HttpResponse::NotFound().body(fallback),
+    }
+}
+
+/// Language for this request: `?lang=`, the `X-Language` header, then the
+/// `language` cookie set by the language switcher, else `site.default_language`
+fn request_language(req: &HttpRequest, config: &Config) -> &'static str {
+    let query_lang = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.get("lang").cloned());
+    let header_lang = req
+        .headers()
+        .get("x-language")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let cookie_lang = req.cookie("language").map(|cookie| cookie.value().to_string());
+
+    [query_lang, header_lang, cookie_lang]
+        .into_iter()
+        .flatten()
+        .find_map(|lang| i18n::supported_language(&lang))
+        .unwrap_or_else(|| default_language(config))
+}
+
+fn default_language(config: &Config) -> &'static str {
+    i18n::supported_language(&config.site.default_language).unwrap_or(i18n::FALLBACK_LANGUAGE)
+}
+
+/// Send a stored page body. The brotli or gzip copy made at compile time is
+/// used when the client accepts it (brotli preferred) and reads the default
+/// language; setting `Content-Encoding` keeps the `Compress` middleware from
+/// encoding it again. Other responses are still compressed on the fly by the
+/// middleware.
+fn page_body(
+    req: &HttpRequest,
+    config: &Config,
+    mut response: HttpResponseBuilder,
+    html: &str,
+    gzip: Option<web::Bytes>,
+    brotli: Option<web::Bytes>,
+) -> HttpResponse {
+    response.content_type("text/html; charset=utf-8");
+    if request_language(req, config) == default_language(config) {
+        if let Some(brotli) = brotli.filter(|_| accepts_encoding(req, "br")) {
+            return response.insert_header((header::CONTENT_ENCODING, "br")).body(brotli);
+        }
+        if let Some(gzip) = gzip.filter(|_| accepts_encoding(req, "gzip")) {
+            return response.insert_header((header::CONTENT_ENCODING, "gzip")).body(gzip);
+        }
+    }
+    response.body(localized(req, config, html))
+}
+
+/// Whether the request's `Accept-Encoding` allows `coding` (`gzip` also
+/// matches the legacy `x-gzip`)
+fn accepts_encoding(req: &HttpRequest, coding: &str) -> bool {
+    req.headers()
+        .get_all(header::ACCEPT_ENCODING)
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|accepted| {
+            let mut parts = accepted.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let refused = parts.any(|param| {
+                param.trim().strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()) == Some(0.0)
+            });
+            let matches = name.eq_ignore_ascii_case(coding)
+                || (coding == "gzip" && name.eq_ignore_ascii_case("x-gzip"))
+                || name == "*";
+            matches && !refused
+        })
+}
+
+/// `html` with its template strings in the request's language
+fn localized(req: &HttpRequest, config: &Config, html: &str) -> String {
+    i18n::localize(html, request_language(req, config), default_language(config))
+}
+
+/// Stored page HTML in the default language, for search snippets
+fn page_html(config: &Config, content: &str) -> String {
+    let lang = default_language(config);
+    i18n::localize(content, lang, lang)
+}
+
 /// Serve attachments from database
-pub async fn serve_attachment(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
+///
+/// Supports conditional requests (`ETag` / `Last-Modified`) and, for seekable
+/// media, single `Range` requests. Metadata is checked first so that a 304
+/// never touches the blob column.
+pub async fn serve_attachment(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+    config: web::Data<Config>,
+    render_cache: Option<web::Data<Mutex<RenderCache>>>,
+) -> impl Responder {
     let filename = path.into_inner();
     log::debug!("Trying to serve attachment: {}", filename);
 
+    // Small attachments are answered from memory without touching SQLite
+    let generation = storage::cache_generation();
+    let cached = render_cache
+        .as_ref()
+        .and_then(|cache| cache.lock().ok().and_then(|mut cache| cache.get_attachment(&filename, generation)));
+    if let Some(attachment) = cached {
+        let cache_control = attachment_cache_control(&query, &attachment.meta);
+        let (mut builder, not_modified) = attachment_response(&req, &attachment.meta, cache_control);
+        if not_modified {
+            return builder.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+        }
+        return builder.content_type(attachment.meta.mime_type.as_str()).body(attachment.data);
+    }
+
     // Create storage connection
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -75,30 +354,299 @@ pub async fn serve_attachment(path: web::Path<String>, config: web::Data<Config>
         }
     };
 
-    // Try to get attachment from database
-    match storage.get_attachment(&filename) {
-        Ok(Some(attachment)) => {
-            let mime_type = attachment.mime_type.as_str();
-            log::debug!("Serving attachment: {} ({}, {} bytes)", filename, mime_type, attachment.file_size);
-            HttpResponse::Ok()
-                .content_type(mime_type)
-                .body(attachment.file_data)
-        }
+    let meta = match storage.get_attachment_meta(&filename) {
+        Ok(Some(meta)) => meta,
         Ok(None) => {
             log::debug!("Attachment not found in database: {}", filename);
-            HttpResponse::NotFound().body("Attachment not found")
+            return not_found_page(&req, &config, "Attachment not found");
         }
         Err(e) => {
             log::error!("Failed to get attachment from database: {}", e);
-            HttpResponse::InternalServerError().finish()
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let cache_control = attachment_cache_control(&query, &meta);
+
+    // Attachments kept on disk: NamedFile handles conditional and range requests itself
+    if let Some(file_path) = &meta.file_path {
+        return serve_attachment_file(&req, file_path, &meta.mime_type, cache_control);
+    }
+
+    let (mut builder, not_modified) = attachment_response(&req, &meta, cache_control);
+    if not_modified {
+        return builder.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+    }
+
+    let seekable = matches!(meta.mime_type.as_str(), "video/mp4" | "audio/mpeg");
+
+    // Load small attachments whole so the next request is served from memory
+    let cache_limit = config.server.attachment_cache_max_bytes;
+    if let Some(cache) = render_cache.as_ref().filter(|_| !seekable && meta.file_size <= cache_limit) {
+        let mut data = vec![0u8; meta.file_size];
+        match storage.read_attachment_chunk(meta.rowid, 0, &mut data) {
+            Ok(read) if read == data.len() => {}
+            Ok(read) => {
+                log::error!("Attachment {} is truncated: expected {} bytes, read {}", filename, meta.file_size, read);
+                return HttpResponse::InternalServerError().finish();
+            }
+            Err(e) => {
+                log::error!("Failed to read attachment {}: {}", filename, e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        }
+        let data = web::Bytes::from(data);
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert_attachment(CachedAttachment { meta: meta.clone(), data: data.clone() }, generation);
+        }
+        log::debug!("Serving attachment: {} ({}, {} bytes)", filename, meta.mime_type, meta.file_size);
+        return builder.content_type(meta.mime_type.as_str()).body(data);
+    }
+
+    let mut range = None;
+    if seekable {
+        builder.insert_header((header::ACCEPT_RANGES, "bytes"));
+
+        if let Some(value) = req.headers().get(header::RANGE).and_then(|v| v.to_str().ok()) {
+            match parse_byte_range(value, meta.file_size) {
+                Some(Ok(r)) => range = Some(r),
+                Some(Err(())) => {
+                    return HttpResponse::RangeNotSatisfiable()
+                        .insert_header((header::CONTENT_RANGE, format!("bytes */{}", meta.file_size)))
+                        .finish();
+                }
+                // Multi-range or malformed header: fall back to the full body
+                None => {}
+            }
         }
     }
+
+    if let Some((start, end)) = range {
+        builder
+            .status(actix_web::http::StatusCode::PARTIAL_CONTENT)
+            .insert_header((
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, meta.file_size),
+            ));
+    }
+    // Half-open byte span to send
+    let (start, end) = match range {
+        Some((start, last)) => (start, last + 1),
+        None => (0, meta.file_size),
+    };
+
+    // Read the first chunk up front so storage errors still produce a 500
+    let mut first = vec![0u8; ATTACHMENT_CHUNK_SIZE.min(end - start)];
+    if !first.is_empty() {
+        match storage.read_attachment_chunk(meta.rowid, start, &mut first) {
+            Ok(read) if read == first.len() => {}
+            Ok(read) => {
+                log::error!(
+                    "Attachment {} is truncated: expected {} bytes, read {}",
+                    filename, meta.file_size, start + read
+                );
+                return HttpResponse::InternalServerError().finish();
+            }
+            Err(e) => {
+                log::error!("Failed to read attachment {}: {}", filename, e);
+                return HttpResponse::InternalServerError().finish();
+            }
+        }
+    }
+
+    log::debug!("Serving attachment: {} ({}, {} bytes)", filename, meta.mime_type, end - start);
+    builder
+        .content_type(meta.mime_type.as_str())
+        .no_chunking((end - start) as u64)
+        .streaming(attachment_stream(storage, meta.rowid, start + first.len(), end, first))
+}
+
+/// Stored filenames are reused across compiles, so only a URL pinned to the
+/// content hash (`?v=<hash prefix>`) may be cached without revalidation
+fn attachment_cache_control(query: &HashMap<String, String>, meta: &AttachmentMeta) -> &'static str {
+    let pinned = query
+        .get("v")
+        .is_some_and(|v| !v.is_empty() && !meta.content_hash.is_empty() && meta.content_hash.starts_with(v.as_str()));
+    if pinned {
+        "public, max-age=31536000, immutable"
+    } else {
+        "public, no-cache"
+    }
+}
+
+/// Response builder with the validators of an attachment stored in SQLite,
+/// and whether the request's validators still match
+fn attachment_response(
+    req: &HttpRequest,
+    meta: &AttachmentMeta,
+    cache_control: &'static str,
+) -> (HttpResponseBuilder, bool) {
+    let etag = attachment_etag(meta);
+    let last_modified = chrono::DateTime::parse_from_rfc3339(&meta.updated_at)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc));
+
+    let mut builder = HttpResponse::Ok();
+    builder
+        .insert_header((header::ETAG, etag.clone()))
+        .insert_header((header::CACHE_CONTROL, cache_control));
+    if let Some(modified) = last_modified {
+        builder.insert_header((header::LAST_MODIFIED, format_http_date(&modified)));
+    }
+    let not_modified = is_not_modified(req, &etag, last_modified.as_ref());
+    (builder, not_modified)
+}
+
+/// Serve an attachment stored under `generated_dir/attachments`
+fn serve_attachment_file(
+    req: &HttpRequest,
+    file_path: &str,
+    mime_type: &str,
+    cache_control: &'static str,
+) -> HttpResponse {
+    let file = match actix_files::NamedFile::open(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            log::error!("Failed to open attachment file {}: {}", file_path, e);
+            return HttpResponse::NotFound().body("Attachment not found");
+        }
+    };
+
+    let mut file = file.disable_content_disposition();
+    if let Ok(mime) = mime_type.parse() {
+        file = file.set_content_type(mime);
+    }
+
+    let mut response = file.into_response(req);
+    response.headers_mut().insert(
+        header::CACHE_CONTROL,
+        header::HeaderValue::from_static(cache_control),
+    );
+    response
+}
+
+/// Size of each read when streaming attachments out of SQLite
+const ATTACHMENT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Stream `[offset, end)` of an attachment blob, starting with an already-read chunk
+fn attachment_stream(
+    storage: StorageDB,
+    rowid: i64,
+    offset: usize,
+    end: usize,
+    first: Vec<u8>,
+) -> impl futures::Stream<Item = Result<web::Bytes, actix_web::Error>> {
+    let first = futures::stream::once(std::future::ready(Ok(web::Bytes::from(first))));
+    let rest = futures::stream::unfold((storage, offset), move |(storage, offset)| async move {
+        if offset >= end {
+            return None;
+        }
+        let mut buf = vec![0u8; ATTACHMENT_CHUNK_SIZE.min(end - offset)];
+        match storage.read_attachment_chunk(rowid, offset, &mut buf) {
+            Ok(0) => None,
+            Ok(read) => {
+                buf.truncate(read);
+                Some((Ok(web::Bytes::from(buf)), (storage, offset + read)))
+            }
+            Err(e) => {
+                log::error!("Failed to stream attachment: {}", e);
+                Some((
+                    Err(actix_web::error::ErrorInternalServerError(e.to_string())),
+                    (storage, end),
+                ))
+            }
+        }
+    });
+    futures::StreamExt::chain(first, rest)
+}
+
+/// Strong ETag for an attachment, falling back to size + mtime for rows stored before hashing
+fn attachment_etag(meta: &AttachmentMeta) -> String {
+    if meta.content_hash.is_empty() {
+        format!("\"{}-{}\"", meta.file_size, meta.updated_at)
+    } else {
+        format!("\"{}\"", meta.content_hash)
+    }
+}
+
+/// Format a timestamp as an HTTP-date (RFC 7231)
+fn format_http_date(dt: &chrono::DateTime<chrono::Utc>) -> String {
+    dt.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Evaluate `If-None-Match` (preferred) or `If-Modified-Since`
+fn is_not_modified(
+    req: &HttpRequest,
+    etag: &str,
+    last_modified: Option<&chrono::DateTime<chrono::Utc>>,
+) -> bool {
+    if let Some(if_none_match) = req.headers().get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+
+    if let (Some(since), Some(modified)) = (
+        req.headers().get(header::IF_MODIFIED_SINCE).and_then(|v| v.to_str().ok()),
+        last_modified,
+    ) {
+        if let Ok(since) = chrono::DateTime::parse_from_rfc2822(since) {
+            return modified.timestamp() <= since.timestamp();
+        }
+    }
+
+    false
+}
+
+/// Parse a single `bytes=` range against a body of `total` bytes.
+///
+/// Returns `None` when the header should be ignored, `Some(Err(()))` when it is unsatisfiable.
+fn parse_byte_range(header: &str, total: usize) -> Option<Result<(usize, usize), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    if total == 0 {
+        return Some(Err(()));
+    }
+
+    let range = if start.is_empty() {
+        // Suffix range: last N bytes
+        let len: usize = end.parse().ok()?;
+        if len == 0 {
+            return Some(Err(()));
+        }
+        (total.saturating_sub(len), total - 1)
+    } else {
+        let start: usize = start.parse().ok()?;
+        let end = if end.is_empty() {
+            total - 1
+        } else {
+            end.parse::<usize>().ok()?.min(total - 1)
+        };
+        if start > end || start >= total {
+            return Some(Err(()));
+        }
+        (start, end)
+    };
+
+    Some(Ok(range))
 }
 
 /// Serve index page from database
-pub async fn index(config: web::Data<Config>) -> impl Responder {
+pub async fn index(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    compiler_state: Option<web::Data<Mutex<admin::CompilerState>>>,
+) -> impl Responder {
+    admin::reload_templates_if_changed(&config, compiler_state.as_ref());
+
     // Create storage connection
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -107,16 +655,22 @@ pub async fn index(config: web::Data<Config>) -> impl Responder {
         }
     };
 
+    if let Ok(Some((_, updated_at))) = storage.get_page_version("index") {
+        let (mut response, not_modified) = page_response(&req, &config, "index", &updated_at);
+        if not_modified {
+            return response.status(actix_web::http::StatusCode::NOT_MODIFIED).finish();
+        }
+    }
+
     // Try to get index page from database
     match storage.get_page("index") {
         Ok(Some(page)) => {
-            HttpResponse::Ok()
+            let (mut response, _) = page_response(&req, &config, "index", &page.updated_at);
+            response
                 .content_type("text/html; charset=utf-8")
-                .body(page.content)
-        }
-        Ok(None) => {
-            HttpResponse::NotFound().body("Index page not found. Please compile the site first.")
+                .body(localized(&req, &config, &page.content))
         }
+        Ok(None) => not_found_page(&req, &config, "Index page not found. Please compile the site first."),
         Err(e) => {
             log::error!("Failed to get index page from database: {}", e);
             HttpResponse::InternalServerError().body("Database error")
@@ -125,63 +679,240 @@ pub async fn index(config: web::Data<Config>) -> impl Responder {
 }
 
 /// Serve content files based on URL pattern
-pub async fn serve_content(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
-    serve_page(path, config).await
+pub async fn serve_content(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<Config>,
+    compiler_state: Option<web::Data<Mutex<admin::CompilerState>>>,
+    render_cache: Option<web::Data<Mutex<RenderCache>>>,
+) -> impl Responder {
+    serve_page(req, path, config, compiler_state, render_cache).await
 }
 
-/// Trigger recompilation
-pub async fn recompile(config: web::Data<Config>) -> impl Responder {
+/// Trigger recompilation in the background
+///
+/// Compilation runs on the blocking pool; progress is reported by
+/// `GET /api/admin/compile/status`. A request made while a compilation is
+/// running gets `202 Accepted` with the running job's ID. Requires an admin
+/// session or API key, like the status endpoint.
+///
+/// Every page is re-rendered, so template edits since the last compile
+/// (reported as `templates_changed`) are picked up as well.
+#[utoipa::path(
+    post,
+    path = "/api/recompile",
+    tag = "system",
+    summary = "在后台重新编译全部内容",
+    responses(
+        (status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "Compilation started", "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21", "templates_changed": false})),
+        (status = 202, description = "已有编译在运行", body = serde_json::Value, example = json!({"status": "running", "message": "Compilation already in progress", "job_id": "5f0c6a1e-8d4b-4c1e-9a57-2d8e0b3f6c21"}))
+    ),
+    security(("cookieAuth" = []), ("bearerAuth" = []))
+)]
+pub async fn recompile(
+    req: HttpRequest,
+    config: web::Data<Config>,
+    state: web::Data<Mutex<admin::CompilerState>>,
+    render_cache: Option<web::Data<Mutex<RenderCache>>>,
+) -> impl Responder {
+    if !admin::require_auth(&req, &config) {
+        return HttpResponse::Unauthorized().json(serde_json::json!({
+            "status": "error",
+            "message": "未授权访问，请先登录"
+        }));
+    }
+
     log::info!("Received recompile request");
+    let templates_changed = admin::templates_changed(&config);
+    if templates_changed {
+        log::info!("Templates changed since the last compile");
+    }
 
-    match Compiler::new(config.get_ref().clone()) {
-        Ok(mut compiler) => {
-            match compiler.compile() {
-                Ok(result) => {
-                    log::info!("Compilation successful: {} pages, {} attachments",
-                        result.total_categories + result.total_items + 1, result.total_attachments);
-                    HttpResponse::Ok().json(serde_json::json!({
-                        "status": "success",
-                        "message": format!("Compiled {} pages, {} attachments",
-                            result.total_categories + result.total_items + 1, result.total_attachments),
-                        "categories": result.total_categories,
-                        "items": result.total_items,
-                        "attachments": result.total_attachments
-                    }))
-                }
-                Err(e) => {
-                    log::error!("Compilation failed: {}", e);
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "status": "error",
-                        "message": e.to_string()
-                    }))
+    match admin::start_background_compile(config.get_ref().clone(), state) {
+        Ok(job_id) => {
+            // Recompiled pages get a new updated_at anyway; drop everything at once
+            if let Some(cache) = &render_cache {
+                if let Ok(mut cache) = cache.lock() {
+                    cache.clear();
                 }
             }
+            HttpResponse::Ok().json(serde_json::json!({
+                "status": "success",
+                "message": "Compilation started",
+                "job_id": job_id,
+                "templates_changed": templates_changed
+            }))
         }
+        Err(running_job) => HttpResponse::Accepted().json(serde_json::json!({
+            "status": "running",
+            "message": "Compilation already in progress",
+            "job_id": running_job
+        })),
+    }
+}
+
+/// Get the view count of an article
+#[utoipa::path(
+    get,
+    path = "/api/stats/views/{slug}",
+    tag = "content",
+    summary = "页面访问量",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "slug": "wine-merlot", "views": 42})))
+)]
+pub async fn get_view_count(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
+    let slug = path.into_inner();
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = match StorageDB::new(&storage_path) {
+        Ok(s) => s,
         Err(e) => {
-            log::error!("Failed to create compiler: {}", e);
+            log::error!("Failed to open storage database: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": "Database error"
+            }));
+        }
+    };
+
+    match storage.get_view_count(&slug) {
+        Ok(views) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "slug": slug,
+            "views": views
+        })),
+        Err(e) => {
+            log::error!("Failed to get view count: {}", e);
             HttpResponse::InternalServerError().json(serde_json::json!({
                 "status": "error",
-                "message": e.to_string()
+                "message": "Failed to get view count"
             }))
         }
     }
 }
 
-/// Health check endpoint
-pub async fn health_check() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
+#[derive(Debug, serde::Deserialize)]
+pub struct HealthQuery {
+    verbose: Option<bool>,
+}
+
+/// Health check endpoint. `privacy_mode` tells clients how visits are recorded:
+/// requests sent with `DNT: 1` are never recorded.
+///
+/// By default the storage, comments and analytics databases are opened and
+/// queried, and any failure turns the response into a 503 naming the
+/// database. `?verbose=false` skips the checks for high-frequency probes.
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "system",
+    summary = "健康检查，默认包含各数据库的状态",
+    params(("verbose" = Option<bool>, Query, description = "为 false 时不检查数据库")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "ok", "service": "lf_blog", "version": "0.1.0", "uptime_secs": 3600, "render_cache": {"hits": 10, "misses": 2, "entries": 2, "capacity": 100, "attachment_hits": 4, "attachment_misses": 1, "attachment_entries": 1, "generation": 3}, "privacy_mode": {"anonymize_ip": true, "respect_dnt": true}, "databases": {"storage": {"ok": true, "pages": 12, "categories": 3, "items": 9, "attachments": 20, "last_compiled": "2024-05-01 10:00:00"}, "comments": {"ok": true, "comments": 3}, "analytics": {"ok": true, "visits": 120}}})))
+)]
+pub async fn health_check(
+    query: web::Query<HealthQuery>,
+    config: web::Data<Config>,
+    render_cache: Option<web::Data<Mutex<RenderCache>>>,
+) -> impl Responder {
+    let render_cache = render_cache
+        .as_ref()
+        .and_then(|cache| cache.lock().ok().map(|cache| cache.stats()));
+    let mut body = serde_json::json!({
         "status": "ok",
-        "service": "lf_blog"
-    }))
+        "service": "lf_blog",
+        "version": env!("CARGO_PKG_VERSION"),
+        "render_cache": render_cache,
+        "privacy_mode": {
+            "anonymize_ip": config.analytics.anonymize_ip,
+            "respect_dnt": true
+        }
+    });
+    if !query.verbose.unwrap_or(true) {
+        return HttpResponse::Ok().json(body);
+    }
+
+    let storage_path = config.paths.storage_db.clone();
+    let comments_path = config.paths.comments_db.clone();
+    let analytics_path = config.paths.analytics_db.clone();
+    let checks = tokio::task::spawn_blocking(move || {
+        serde_json::json!({
+            "storage": check_storage_db(&storage_path),
+            "comments": check_db(&comments_path, |path| {
+                let total = comments::CommentDB::new(path)?.get_total_comments()?;
+                Ok(serde_json::json!({ "comments": total }))
+            }),
+            "analytics": check_db(&analytics_path, |path| {
+                let total = analytics::AnalyticsDB::new(path)?.get_total_visits(false)?;
+                Ok(serde_json::json!({ "visits": total }))
+            })
+        })
+    })
+    .await
+    .unwrap_or_else(|e| serde_json::json!({ "storage": { "ok": false, "error": e.to_string() } }));
+
+    let healthy = checks
+        .as_object()
+        .is_some_and(|checks| checks.values().all(|check| check["ok"] == true));
+    body["status"] = serde_json::json!(if healthy { "ok" } else { "error" });
+    body["uptime_secs"] = serde_json::json!(STARTED_AT.get().map_or(0, |started| started.elapsed().as_secs()));
+    body["databases"] = checks;
+    if healthy {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+/// Storage database check. The file is created by the startup compile, so
+/// unlike the others it must already exist.
+fn check_storage_db(path: &std::path::Path) -> serde_json::Value {
+    if !path.exists() {
+        return serde_json::json!({ "ok": false, "error": format!("{} does not exist", path.display()) });
+    }
+    check_db(path, |path| {
+        let storage = StorageDB::new(path)?;
+        let pages = storage.count_pages()?;
+        let stats = storage.get_stats()?;
+        Ok(serde_json::json!({
+            "pages": pages,
+            "categories": stats.total_categories,
+            "items": stats.total_items,
+            "attachments": stats.total_attachments,
+            "last_compiled": stats.last_compiled.filter(|at| !at.is_empty())
+        }))
+    })
+}
+
+/// Run `query` against the database at `path`, adding `"ok"` and the error if any
+fn check_db(
+    path: &std::path::Path,
+    query: impl FnOnce(&str) -> rusqlite::Result<serde_json::Value>,
+) -> serde_json::Value {
+    match query(&path.to_string_lossy()) {
+        Ok(mut details) => {
+            details["ok"] = serde_json::json!(true);
+            details
+        }
+        Err(e) => serde_json::json!({ "ok": false, "error": e.to_string() }),
+    }
 }
 
 /// Get comments for a specific page
+#[utoipa::path(
+    get,
+    path = "/api/comments/{slug}",
+    tag = "comments",
+    summary = "页面的评论（按楼层分组）",
+    params(("slug" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "comments": [{"comment": {"id": "c1", "slug": "wine-merlot", "author": "Ann", "content": "Nice", "created_at": "2024-05-01T10:00:00Z", "website": null, "parent_id": null, "avatar_url": "https://www.gravatar.com/avatar/71d4f55f72fa128dfb468a1a3901507c804b74316488744d769d7f4b16696476?d=identicon&s=64"}, "replies": []}], "count": 1})))
+)]
 pub async fn get_comments(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
     let slug = path.into_inner();
     log::debug!("Getting comments for slug: {}", slug);
 
     // Create comments database connection
-    let comments_db_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let comments_db_path = config.paths.comments_db.to_string_lossy().to_string();
     let comments_db = match comments::CommentDB::new(&comments_db_path) {
         Ok(db) => db,
         Err(e) => {
@@ -193,12 +924,13 @@ pub async fn get_comments(path: web::Path<String>, config: web::Data<Config>) ->
         }
     };
 
-    match comments_db.get_comments_by_slug(&slug) {
-        Ok(comments) => {
+    match comments_db.get_comments_threaded(&slug) {
+        Ok(threads) => {
+            let count: usize = threads.iter().map(|t| 1 + t.replies.len()).sum();
             HttpResponse::Ok().json(serde_json::json!({
                 "status": "success",
-                "comments": comments,
-                "count": comments.len()
+                "comments": threads,
+                "count": count
             }))
         }
         Err(e) => {
@@ -212,11 +944,35 @@ pub async fn get_comments(path: web::Path<String>, config: web::Data<Config>) ->
 }
 
 /// Add a new comment
+///
+/// Each client IP may post `comments.rate_limit_per_hour` comments per hour;
+/// beyond that the request gets `429 Too Many Requests` with `Retry-After`.
+#[utoipa::path(
+    post,
+    path = "/api/comments",
+    tag = "comments",
+    summary = "发表评论或回复",
+    request_body(content = comments::CreateCommentRequest, example = json!({"slug": "wine-merlot", "author": "Ann", "content": "Nice", "website": "https://example.com", "email": "ann@example.com", "parent_id": null})),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "message": "Comment added successfully", "comment": {"id": "c1", "slug": "wine-merlot", "author": "Ann", "content": "Nice", "created_at": "2024-05-01T10:00:00Z", "website": "https://example.com", "parent_id": null, "avatar_url": "https://www.gravatar.com/avatar/71d4f55f72fa128dfb468a1a3901507c804b74316488744d769d7f4b16696476?d=identicon&s=64"}})))
+)]
 pub async fn add_comment(
+    req: HttpRequest,
     body: web::Json<comments::CreateCommentRequest>,
-    config: web::Data<Config>
+    config: web::Data<Config>,
+    rate_limiter: web::Data<comments::CommentRateLimiter>,
 ) -> impl Responder {
-    let request = body.into_inner();
+    let ip = crate::ip_filter::client_ip(&req);
+    if !rate_limiter.check_and_increment(&ip) {
+        log::warn!("Comment rate limit exceeded for {}", ip);
+        return HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, rate_limiter.retry_after_secs(&ip).to_string()))
+            .json(serde_json::json!({
+                "status": "error",
+                "message": "Too many comments, please try again later"
+            }));
+    }
+
+    let mut request = body.into_inner();
     log::debug!("Adding comment for slug: {}", request.slug);
 
     // Validate input
@@ -226,9 +982,30 @@ pub async fn add_comment(
             "message": "Author and content are required"
         }));
     }
+    // Blank optional fields are dropped; anything else must be well-formed
+    let website = request.website.take().filter(|w| !w.trim().is_empty());
+    request.website = match website.as_deref().map(comments::validate_website) {
+        Some(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "Website must be an http or https URL"
+            }));
+        }
+        validated => validated.flatten(),
+    };
+    let email = request.email.take().filter(|e| !e.trim().is_empty());
+    request.email = match email.as_deref().map(comments::validate_email) {
+        Some(None) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": "Invalid email address"
+            }));
+        }
+        validated => validated.flatten(),
+    };
 
     // Create comments database connection
-    let comments_db_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let comments_db_path = config.paths.comments_db.to_string_lossy().to_string();
     let comments_db = match comments::CommentDB::new(&comments_db_path) {
         Ok(db) => db,
         Err(e) => {
@@ -240,9 +1017,42 @@ pub async fn add_comment(
         }
     };
 
+    // Replies go one level deep, and only within the same article
+    if let Some(parent_id) = &request.parent_id {
+        match comments_db.get_comment(parent_id) {
+            Ok(Some(parent)) if parent.slug != request.slug => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "status": "error",
+                    "message": "Parent comment belongs to another article"
+                }));
+            }
+            Ok(Some(parent)) if parent.parent_id.is_some() => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "status": "error",
+                    "message": "Cannot reply to a reply"
+                }));
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return HttpResponse::BadRequest().json(serde_json::json!({
+                    "status": "error",
+                    "message": "Parent comment not found"
+                }));
+            }
+            Err(e) => {
+                log::error!("Failed to look up parent comment: {}", e);
+                return HttpResponse::InternalServerError().json(serde_json::json!({
+                    "status": "error",
+                    "message": "Database error"
+                }));
+            }
+        }
+    }
+
     match comments_db.add_comment(request) {
         Ok(comment) => {
             log::info!("Comment added successfully: {}", comment.id);
+            notifications::notify_new_comment(&config, &comment);
             HttpResponse::Ok().json(serde_json::json!({
                 "status": "success",
                 "message": "Comment added successfully",
@@ -260,6 +1070,14 @@ pub async fn add_comment(
 }
 
 /// Get content recommendations
+#[utoipa::path(
+    get,
+    path = "/api/recommendations/{url}",
+    tag = "content",
+    summary = "与指定页面相关的推荐内容",
+    params(("url" = String, Path)),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "recommendations": [{"url": "/wine/merlot", "title": "Merlot", "category": "wine", "category_name": "Wine", "description": "Tasting notes", "excerpt": "A soft red wine.", "score": 0.82, "comment_count": 3}], "count": 1})))
+)]
 pub async fn get_recommendations(
     path: web::Path<String>,
     config: web::Data<Config>
@@ -268,7 +1086,7 @@ pub async fn get_recommendations(
     log::debug!("Getting recommendations for URL: {}", current_url);
 
     // Create storage connection to get site content
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -307,11 +1125,18 @@ pub async fn get_recommendations(
 }
 
 /// Get popular content
+#[utoipa::path(
+    get,
+    path = "/api/popular-content",
+    tag = "content",
+    summary = "访问最多的内容",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "popular_items": [{"url": "/wine/merlot", "title": "Merlot", "category": "wine", "category_name": "Wine", "description": "Tasting notes", "excerpt": "A soft red wine.", "score": 0.82, "comment_count": 3}], "count": 1})))
+)]
 pub async fn get_popular_content(config: web::Data<Config>) -> impl Responder {
     log::debug!("Getting popular content");
 
     // Create storage connection to get site content
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -339,8 +1164,13 @@ pub async fn get_popular_content(config: web::Data<Config>) -> impl Responder {
     let site_content = convert_pages_to_site_content(&pages);
 
     // Create recommender and get popular items
-    let recommender = recommender::Recommender::new(site_content);
-    let popular_items = recommender.get_popular_items(10);
+    let view_counts = storage.get_view_counts().unwrap_or_else(|e| {
+        log::warn!("Failed to load view counts: {}", e);
+        HashMap::new()
+    });
+    let recommender = recommender::Recommender::new(site_content).with_view_counts(view_counts);
+    let mut popular_items = recommender.get_popular_items(10);
+    fill_comment_counts(&config, &mut popular_items);
 
     HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
@@ -350,11 +1180,18 @@ pub async fn get_popular_content(config: web::Data<Config>) -> impl Responder {
 }
 
 /// Get latest content
+#[utoipa::path(
+    get,
+    path = "/api/latest-content",
+    tag = "content",
+    summary = "最新发布的内容",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "latest_items": [{"url": "/wine/merlot", "title": "Merlot", "category": "wine", "category_name": "Wine", "description": "Tasting notes", "excerpt": "A soft red wine.", "score": 0.82, "comment_count": 3}], "count": 1})))
+)]
 pub async fn get_latest_content(config: web::Data<Config>) -> impl Responder {
     log::debug!("Getting latest content");
 
     // Create storage connection to get site content
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -383,7 +1220,8 @@ pub async fn get_latest_content(config: web::Data<Config>) -> impl Responder {
 
     // Create recommender and get latest items
     let recommender = recommender::Recommender::new(site_content);
-    let latest_items = recommender.get_latest_items(10);
+    let mut latest_items = recommender.get_latest_items(10);
+    fill_comment_counts(&config, &mut latest_items);
 
     HttpResponse::Ok().json(serde_json::json!({
         "status": "success",
@@ -392,23 +1230,128 @@ pub async fn get_latest_content(config: web::Data<Config>) -> impl Responder {
     }))
 }
 
-/// Search content API endpoint
+/// Set each item's `comment_count` from the cached per-article counts
+fn fill_comment_counts(config: &Config, items: &mut [recommender::RecommendedItem]) {
+    let comments_db_path = config.paths.comments_db.to_string_lossy().to_string();
+    let slugs: Vec<&str> = items.iter().map(|item| item.url.as_str()).collect();
+    let counts = match comments::CommentDB::new(&comments_db_path).and_then(|db| db.get_comment_counts(&slugs)) {
+        Ok(counts) => counts,
+        Err(e) => {
+            log::warn!("Failed to load comment counts: {}", e);
+            return;
+        }
+    };
+    for item in items.iter_mut() {
+        item.comment_count = counts.get(&item.url).copied().unwrap_or(0) as u64;
+    }
+}
+
+/// Build search filters from the `category`, `type`, `tag`, `start` and `end` query parameters
+///
+/// Dates must be `YYYY-MM-DD`; an invalid date is reported back to the caller.
+fn search_filters(
+    query: &std::collections::HashMap<String, String>,
+    limit: usize,
+) -> Result<SearchOptions, String> {
+    let param = |name: &str| {
+        query
+            .get(name)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let date = |name: &str| match param(name) {
+        Some(value) => chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+            .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+            .map_err(|_| format!("Invalid {} date: {}", name, value)),
+        None => Ok(None),
+    };
+    let page_type = match param("type").as_deref() {
+        Some("item") => Some(PageType::Item),
+        Some("category") => Some(PageType::Category),
+        Some(other) => return Err(format!("Invalid page type: {}", other)),
+        None => None,
+    };
+
+    Ok(SearchOptions {
+        limit,
+        offset: 0,
+        category: param("category"),
+        start_date: date("start")?,
+        end_date: date("end")?,
+        page_type,
+        tags: param("tag").map(|tag| vec![tag]),
+    })
+}
+
+/// True when any filter narrows the search, so an empty query is allowed
+fn has_search_filters(opts: &SearchOptions) -> bool {
+    opts.category.is_some()
+        || opts.start_date.is_some()
+        || opts.end_date.is_some()
+        || opts.page_type.is_some()
+        || opts.tags.is_some()
+}
+
+/// Page number and page size from the `page` and `limit` query parameters,
+/// falling back to the first page of `SEARCH_API_DEFAULT_LIMIT` results
+fn search_paging(query: &std::collections::HashMap<String, String>) -> (usize, usize) {
+    let page = query.get("page").and_then(|p| p.trim().parse::<usize>().ok()).unwrap_or(1).max(1);
+    let limit = query
+        .get("limit")
+        .and_then(|l| l.trim().parse::<usize>().ok())
+        .unwrap_or(SEARCH_API_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_API_MAX_LIMIT);
+    (page, limit)
+}
+
+/// Pagination metadata for a page of search results
+fn search_pagination(page: usize, limit: usize, total_count: usize) -> serde_json::Value {
+    let total_pages = total_count.div_ceil(limit);
+    serde_json::json!({
+        "current_page": page,
+        "total_pages": total_pages,
+        "total_count": total_count,
+        "has_next": page < total_pages,
+        "has_prev": page > 1
+    })
+}
+
+/// Search content API endpoint: `/api/search?q=&page=&limit=`
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "content",
+    summary = "站内搜索",
+    params(("q" = Option<String>, Query, description = "关键词"), ("page" = Option<u32>, Query, description = "页码，从 1 开始"), ("limit" = Option<u32>, Query, description = "每页条数，默认 10，最多 50"), ("category" = Option<String>, Query, description = "只搜索指定分类"), ("start_date" = Option<String>, Query, description = "发布日期不早于（YYYY-MM-DD）"), ("end_date" = Option<String>, Query, description = "发布日期不晚于（YYYY-MM-DD）")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "query": "merlot", "results": [{"slug": "wine-merlot", "title": "Merlot", "category": "wine", "excerpt": "A soft red wine.", "snippet": "...soft <mark>Merlot</mark>...", "page_type": "item"}], "count": 1, "total": 1, "pagination": {"current_page": 1, "total_pages": 1, "total_count": 1, "has_next": false, "has_prev": false}})))
+)]
 pub async fn search_content(
     query: web::Query<std::collections::HashMap<String, String>>,
     config: web::Data<Config>
 ) -> impl Responder {
     let search_query = query.get("q").cloned().unwrap_or_default();
+    let (page, limit) = search_paging(&query);
+
+    let opts = match search_filters(&query, limit) {
+        Ok(opts) => SearchOptions { offset: (page - 1) * limit, ..opts },
+        Err(message) => {
+            return HttpResponse::BadRequest().json(serde_json::json!({
+                "status": "error",
+                "message": message
+            }));
+        }
+    };
 
-    if search_query.trim().is_empty() {
+    if search_query.trim().is_empty() && !has_search_filters(&opts) {
         return HttpResponse::BadRequest().json(serde_json::json!({
             "status": "error",
             "message": "Search query is empty"
         }));
     }
 
-    log::debug!("Searching for: {}", search_query);
+    log::debug!("Searching for: {} ({:?})", search_query, opts);
 
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -420,9 +1363,8 @@ pub async fn search_content(
         }
     };
 
-    let limit = query.get("limit").and_then(|s| s.parse().ok()).unwrap_or(20);
-    let pages = match storage.search_pages(&search_query, limit) {
-        Ok(pages) => pages,
+    let (pages, total_count) = match storage.search_pages_filtered(search_query.trim(), &opts) {
+        Ok(result) => result,
         Err(e) => {
             log::error!("Search failed: {}", e);
             return HttpResponse::InternalServerError().json(serde_json::json!({
@@ -432,17 +1374,13 @@ pub async fn search_content(
         }
     };
 
-    let total_count = match storage.search_pages_count(&search_query) {
-        Ok(count) => count,
-        Err(_) => pages.len(),
-    };
-
     let results: Vec<serde_json::Value> = pages.iter().map(|page| {
-        let snippet = extract_snippet(&page.content, &search_query);
+        let snippet = extract_snippet(&page_html(&config, &page.content), &search_query);
         serde_json::json!({
             "slug": page.slug,
             "title": page.title,
             "category": page.category,
+            "excerpt": page.excerpt,
             "snippet": snippet,
             "page_type": match page.page_type {
                 crate::storage::PageType::Category => "category",
@@ -457,10 +1395,156 @@ pub async fn search_content(
         "query": search_query,
         "results": results,
         "count": results.len(),
-        "total": total_count
+        "total": total_count,
+        "pagination": search_pagination(page, limit, total_count)
+    }))
+}
+
+/// Default and maximum page size of the public search API
+const SEARCH_API_DEFAULT_LIMIT: usize = 10;
+const SEARCH_API_MAX_LIMIT: usize = 50;
+
+/// Public search API for other sites: `/api/v1/search?q=&category=&tag=&page=&limit=`
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "content",
+    summary = "分页搜索接口（支持跨域）",
+    params(("q" = Option<String>, Query, description = "关键词"), ("category" = Option<String>, Query, description = "只搜索指定分类"), ("tag" = Option<String>, Query, description = "只搜索带有该标签的文章"), ("page" = Option<u32>, Query, description = "页码，从 1 开始"), ("limit" = Option<u32>, Query, description = "每页条数")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "query": "merlot", "results": [{"slug": "wine-merlot", "title": "Merlot", "category": "wine", "tags": ["red"], "description": "A soft red wine.", "excerpt": "A soft red wine.", "snippet": "...soft Merlot...", "score": 3.5}], "pagination": {"page": 1, "limit": 10, "total_pages": 1, "total_count": 1}})))
+)]
+pub async fn search_api(
+    req: HttpRequest,
+    query: web::Query<std::collections::HashMap<String, String>>,
+    config: web::Data<Config>
+) -> HttpResponse {
+    let param = |name: &str| {
+        query
+            .get(name)
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let search_query = param("q").unwrap_or_default();
+    let category = param("category");
+    let tag = param("tag");
+    let page = param("page").and_then(|p| p.parse::<usize>().ok()).unwrap_or(1).max(1);
+    let limit = param("limit")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(SEARCH_API_DEFAULT_LIMIT)
+        .clamp(1, SEARCH_API_MAX_LIMIT);
+
+    if search_query.is_empty() && category.is_none() && tag.is_none() {
+        return with_cors(&req, &config, HttpResponse::BadRequest()).json(serde_json::json!({
+            "status": "error",
+            "message": "Search query is empty"
+        }));
+    }
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let searched = StorageDB::new(&storage_path).and_then(|storage| {
+        storage.search_pages_filtered(
+            &search_query,
+            &SearchOptions {
+                limit,
+                offset: (page - 1) * limit,
+                category,
+                tags: tag.map(|tag| vec![tag]),
+                ..Default::default()
+            },
+        )
+    });
+    let (pages, total_count) = match searched {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("Search failed: {}", e);
+            return with_cors(&req, &config, HttpResponse::InternalServerError()).json(serde_json::json!({
+                "status": "error",
+                "message": "Search failed"
+            }));
+        }
+    };
+
+    let results: Vec<serde_json::Value> = pages.iter().map(|page| {
+        let text = html_to_text(&page_html(&config, &page.content));
+        serde_json::json!({
+            "slug": page.slug,
+            "title": page.title,
+            "category": page.category,
+            "tags": page.tags,
+            "description": extract_snippet(&text, ""),
+            "excerpt": page.excerpt,
+            "snippet": extract_snippet(&text, &search_query),
+            "score": search_score(page, &text, &search_query)
+        })
+    }).collect();
+
+    with_cors(&req, &config, HttpResponse::Ok()).json(serde_json::json!({
+        "status": "success",
+        "query": search_query,
+        "results": results,
+        "pagination": {
+            "page": page,
+            "limit": limit,
+            "total_pages": total_count.div_ceil(limit),
+            "total_count": total_count
+        }
     }))
 }
 
+/// CORS preflight for the public search API
+#[utoipa::path(
+    options,
+    path = "/api/v1/search",
+    tag = "content",
+    summary = "跨域预检",
+    responses((status = 204, description = "允许跨域请求"))
+)]
+pub async fn search_api_preflight(req: HttpRequest, config: web::Data<Config>) -> HttpResponse {
+    with_cors(&req, &config, HttpResponse::NoContent())
+        .insert_header((header::ACCESS_CONTROL_ALLOW_METHODS, "GET, OPTIONS"))
+        .insert_header((header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type"))
+        .insert_header((header::ACCESS_CONTROL_MAX_AGE, "86400"))
+        .finish()
+}
+
+/// Add `Access-Control-Allow-Origin` when the request origin is allowed by `server.cors_origins`
+fn with_cors(
+    req: &HttpRequest,
+    config: &Config,
+    mut builder: actix_web::HttpResponseBuilder,
+) -> actix_web::HttpResponseBuilder {
+    let origins = &config.server.cors_origins;
+    if origins.iter().any(|o| o == "*") {
+        builder.insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
+    } else if let Some(origin) = req.headers().get(header::ORIGIN).and_then(|o| o.to_str().ok()) {
+        if origins.iter().any(|o| o.trim_end_matches('/') == origin) {
+            builder
+                .insert_header((header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.to_string()))
+                .insert_header((header::VARY, "Origin"));
+        }
+    }
+    builder
+}
+
+/// Rough relevance: title matches weigh most, then tag matches and body occurrences
+fn search_score(page: &crate::storage::Page, text: &str, query: &str) -> f64 {
+    let query = query.to_lowercase();
+    if query.is_empty() {
+        return 0.0;
+    }
+    let title = page.title.to_lowercase();
+    let mut score = 0.0;
+    if title == query {
+        score += 10.0;
+    } else if title.contains(&query) {
+        score += 5.0;
+    }
+    if page.tags.iter().any(|t| t.to_lowercase() == query) {
+        score += 3.0;
+    }
+    score + (text.to_lowercase().matches(&query).count() as f64).min(10.0) * 0.5
+}
+
 /// Extract a snippet from content around the search term
 fn extract_snippet(content: &str, query: &str) -> String {
     let content_lower = content.to_lowercase();
@@ -508,14 +1592,33 @@ fn extract_snippet(content: &str, query: &str) -> String {
     }
 }
 
+/// Search page link without the page number: keeps every other query
+/// parameter and ends with `page=` so the template can append the number
+fn search_page_url(query: &std::collections::HashMap<String, String>) -> String {
+    let mut params: Vec<(&String, &String)> = query.iter().filter(|(name, _)| name.as_str() != "page").collect();
+    params.sort();
+    let mut url = String::from("/search?");
+    for (name, value) in params {
+        url.push_str(&format!("{}={}&", urlencoding::encode(name), urlencoding::encode(value)));
+    }
+    url.push_str("page=");
+    url
+}
+
 /// Serve search page
 pub async fn search_page(
+    req: HttpRequest,
     query: web::Query<std::collections::HashMap<String, String>>,
     config: web::Data<Config>
 ) -> impl Responder {
     let search_query = query.get("q").cloned().unwrap_or_default();
+    let (page, limit) = search_paging(&query);
+    let opts = match search_filters(&query, limit) {
+        Ok(opts) => SearchOptions { offset: (page - 1) * limit, ..opts },
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
 
-    let storage_path = config.paths.storage_database_path.to_string_lossy().to_string();
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
     let storage = match StorageDB::new(&storage_path) {
         Ok(s) => s,
         Err(e) => {
@@ -524,25 +1627,25 @@ pub async fn search_page(
         }
     };
 
-    let pages = match storage.search_pages(&search_query, 50) {
-        Ok(pages) => pages,
-        Err(e) => {
-            log::error!("Search failed: {}", e);
-            return HttpResponse::InternalServerError().body("Search failed");
+    let (pages, total_count) = if search_query.trim().is_empty() && !has_search_filters(&opts) {
+        (Vec::new(), 0)
+    } else {
+        match storage.search_pages_filtered(search_query.trim(), &opts) {
+            Ok(result) => result,
+            Err(e) => {
+                log::error!("Search failed: {}", e);
+                return HttpResponse::InternalServerError().body("Search failed");
+            }
         }
     };
 
-    let total_count = match storage.search_pages_count(&search_query) {
-        Ok(count) => count,
-        Err(_) => pages.len(),
-    };
-
     let results: Vec<serde_json::Value> = pages.iter().map(|page| {
-        let snippet = extract_snippet(&page.content, &search_query);
+        let snippet = extract_snippet(&page_html(&config, &page.content), &search_query);
         serde_json::json!({
             "slug": page.slug,
             "title": page.title,
             "category": page.category,
+            "excerpt": page.excerpt,
             "snippet": snippet,
             "page_type": match page.page_type {
                 crate::storage::PageType::Category => "category",
@@ -561,15 +1664,20 @@ pub async fn search_page(
     ctx.insert("config", &config);
     ctx.insert("site_content", &site_content);
     ctx.insert("search_query", &search_query);
+    ctx.insert("search_category", &opts.category);
+    ctx.insert("search_start", &opts.start_date);
+    ctx.insert("search_end", &opts.end_date);
     ctx.insert("search_results", &results);
     ctx.insert("total_count", &total_count);
+    ctx.insert("pagination", &search_pagination(page, limit, total_count));
+    ctx.insert("search_page_url", &search_page_url(&query));
 
     match TemplateRenderer::new(&config) {
         Ok(renderer) => {
             match renderer.render_with_context("search.html", &ctx) {
                 Ok(html) => HttpResponse::Ok()
                     .content_type("text/html; charset=utf-8")
-                    .body(html),
+                    .body(localized(&req, &config, &html)),
                 Err(e) => {
                     log::error!("Failed to render search template: {}", e);
                     HttpResponse::InternalServerError().body("Template error")
@@ -583,15 +1691,262 @@ pub async fn search_page(
     }
 }
 
+/// Serve the RSS 2.0 feed of a single category at `/feed/{category_slug}.xml`
+pub async fn category_feed(path: web::Path<String>, config: web::Data<Config>) -> impl Responder {
+    let category_slug = path.into_inner();
+
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = match StorageDB::new(&storage_path) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Failed to open storage database: {}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let category_page = match storage.get_page(&category_slug) {
+        Ok(Some(page)) if page.page_type == PageType::Category => page,
+        Ok(_) => return HttpResponse::NotFound().body("Feed not found"),
+        Err(e) => {
+            log::error!("Failed to get category from database: {}", e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let items = match storage.get_items_by_category_paginated(&category_slug, config.site.feed_max_items, 0) {
+        Ok(items) => items,
+        Err(e) => {
+            log::error!("Failed to get items for feed {}: {}", category_slug, e);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    let channel = build_category_feed(&config, &category_page, &items);
+    HttpResponse::Ok()
+        .content_type("application/rss+xml; charset=utf-8")
+        .body(channel.to_string())
+}
+
+/// robots.txt built from the `[seo]` config
+pub async fn robots_txt(config: web::Data<Config>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; charset=utf-8")
+        .insert_header((header::CACHE_CONTROL, "public, max-age=86400"))
+        .body(crate::seo::render_robots_txt(&config.seo, &config.site.url))
+}
+
+/// Assemble the RSS channel for a category page and its items
+fn build_category_feed(
+    config: &Config,
+    category: &crate::storage::Page,
+    items: &[crate::storage::Page],
+) -> rss::Channel {
+    let base_url = config.site.url.trim_end_matches('/');
+
+    // The description only lives in the category's index.md frontmatter
+    let index_path = config.paths.content_dir.join(&category.slug).join("index.md");
+    let description = std::fs::read_to_string(index_path)
+        .ok()
+        .and_then(|content| crate::markdown::parse_markdown(&content).ok())
+        .and_then(|parsed| parsed.frontmatter.description)
+        .unwrap_or_else(|| config.site.description.clone());
+
+    let feed_items: Vec<rss::Item> = items
+        .iter()
+        .map(|page| {
+            let link = format!("{}/{}", base_url, page.slug);
+            rss::ItemBuilder::default()
+                .title(Some(page.title.clone()))
+                .link(Some(link.clone()))
+                .guid(Some(rss::GuidBuilder::default().value(link).permalink(true).build()))
+                .pub_date(
+                    chrono::DateTime::parse_from_rfc3339(&page.updated_at)
+                        .ok()
+                        .map(|dt| dt.to_rfc2822()),
+                )
+                .categories(
+                    page.tags
+                        .iter()
+                        .map(|tag| rss::CategoryBuilder::default().name(tag.clone()).build())
+                        .collect::<Vec<_>>(),
+                )
+                .build()
+        })
+        .collect();
+
+    rss::ChannelBuilder::default()
+        .title(format!("{} - {}", category.title, config.site.title))
+        .link(format!("{}/{}", base_url, category.slug))
+        .description(description)
+        .last_build_date(Some(chrono::Utc::now().to_rfc2822()))
+        .items(feed_items)
+        .build()
+}
+
+/// Build the tag index from stored item pages
+fn load_tag_filter(config: &Config) -> Result<TagFilter, rusqlite::Error> {
+    let storage_path = config.paths.storage_db.to_string_lossy().to_string();
+    let storage = StorageDB::new(&storage_path)?;
+    let pages = storage.get_pages_with_excerpts()?;
+    Ok(TagFilter::from_pages(&pages))
+}
+
+/// Get all tags with their item counts, most used first
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    tag = "content",
+    summary = "所有标签及文章数",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!([{"tag": "red", "count": 4}])))
+)]
+pub async fn get_tags(config: web::Data<Config>) -> impl Responder {
+    match load_tag_filter(&config) {
+        Ok(filter) => HttpResponse::Ok().json(filter.tag_counts()),
+        Err(e) => {
+            log::error!("Failed to load tags: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": "Database error"
+            }))
+        }
+    }
+}
+
+/// Get tags with a normalized weight for tag clouds
+#[utoipa::path(
+    get,
+    path = "/api/tags/cloud",
+    tag = "content",
+    summary = "标签云（带 0 到 1 的权重）",
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!([{"tag": "red", "count": 4, "weight": 1.0}])))
+)]
+pub async fn get_tag_cloud(config: web::Data<Config>) -> impl Responder {
+    match load_tag_filter(&config) {
+        Ok(filter) => HttpResponse::Ok().json(filter.tag_cloud()),
+        Err(e) => {
+            log::error!("Failed to load tags: {}", e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": "Database error"
+            }))
+        }
+    }
+}
+
+/// Items per page for `/api/items`
+const ITEMS_PER_PAGE: usize = 20;
+
+/// List items sharing a tag, paginated (`?tag=foo&page=1`)
+#[utoipa::path(
+    get,
+    path = "/api/items",
+    tag = "content",
+    summary = "带有指定标签的文章",
+    params(("tag" = Option<String>, Query, description = "标签"), ("page" = Option<u32>, Query, description = "页码，从 1 开始")),
+    responses((status = 200, description = "成功", body = serde_json::Value, example = json!({"status": "success", "tag": "red", "page": 1, "per_page": 20, "total": 1, "total_pages": 1, "items": [{"slug": "wine/merlot", "title": "Merlot", "category": "wine", "tags": ["red"], "excerpt": "A soft red wine."}]})))
+)]
+pub async fn get_items_by_tag(
+    query: web::Query<std::collections::HashMap<String, String>>,
+    config: web::Data<Config>
+) -> impl Responder {
+    let tag = query.get("tag").map(|t| t.trim().to_string()).unwrap_or_default();
+    if tag.is_empty() {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "Missing tag parameter"
+        }));
+    }
+    let page = query.get("page").and_then(|p| p.parse::<usize>().ok()).unwrap_or(1).max(1);
+
+    let filter = match load_tag_filter(&config) {
+        Ok(filter) => filter,
+        Err(e) => {
+            log::error!("Failed to load tags: {}", e);
+            return HttpResponse::InternalServerError().json(serde_json::json!({
+                "status": "error",
+                "message": "Database error"
+            }));
+        }
+    };
+
+    let tagged = filter.items_with_tag(&tag);
+    let total = tagged.len();
+    let items: Vec<_> = tagged
+        .into_iter()
+        .skip((page - 1) * ITEMS_PER_PAGE)
+        .take(ITEMS_PER_PAGE)
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "tag": tag,
+        "page": page,
+        "per_page": ITEMS_PER_PAGE,
+        "total": total,
+        "total_pages": total.div_ceil(ITEMS_PER_PAGE),
+        "items": items
+    }))
+}
+
+/// Serve the tag cloud page, optionally listing the items of `?tag=`
+pub async fn tags_page(
+    req: HttpRequest,
+    query: web::Query<std::collections::HashMap<String, String>>,
+    config: web::Data<Config>
+) -> impl Responder {
+    let tag = query.get("tag").map(|t| t.trim().to_string()).unwrap_or_default();
+
+    let filter = match load_tag_filter(&config) {
+        Ok(filter) => filter,
+        Err(e) => {
+            log::error!("Failed to load tags: {}", e);
+            return HttpResponse::InternalServerError().body("Database error");
+        }
+    };
+
+    let mut ctx = tera::Context::new();
+    ctx.insert("config", &config);
+    ctx.insert("cloud", &filter.tag_cloud());
+    ctx.insert("tag", &tag);
+
+    match TemplateRenderer::new(&config) {
+        Ok(mut renderer) => {
+            renderer.set_tag_filter(filter);
+            match renderer.render_with_context("tags.html", &ctx) {
+                Ok(html) => HttpResponse::Ok()
+                    .content_type("text/html; charset=utf-8")
+                    .body(localized(&req, &config, &html)),
+                Err(e) => {
+                    log::error!("Failed to render tags template: {}", e);
+                    HttpResponse::InternalServerError().body("Template error")
+                }
+            }
+        }
+        Err(e) => {
+            log::error!("Failed to create template renderer: {}", e);
+            HttpResponse::InternalServerError().body("Template error")
+        }
+    }
+}
+
 /// Helper function to convert StorageDB pages to SiteContent for recommender
 fn convert_pages_to_site_content(pages: &[crate::storage::Page]) -> SiteContent {
     use crate::storage::PageType;
     
     let mut categories: std::collections::HashMap<String, Vec<&crate::storage::Page>> = std::collections::HashMap::new();
+    // Category pages carry the display name from index.md
+    let titles: std::collections::HashMap<&str, &str> = pages
+        .iter()
+        .filter(|page| page.page_type == PageType::Category)
+        .map(|page| (page.slug.as_str(), page.title.as_str()))
+        .collect();
     
     // Group pages by category
     for page in pages {
         if let PageType::Item = page.page_type {
+            if page.is_scheduled() {
+                continue;
+            }
             if let Some(category) = &page.category {
                 categories.entry(category.clone()).or_default().push(page);
             }
@@ -615,14 +1970,21 @@ fn convert_pages_to_site_content(pages: &[crate::storage::Page]) -> SiteContent
                 date: None, // Could extract from updated_at if needed
                 author: None,
                 description: None, // Could be extracted from content
+                excerpt: page.excerpt.clone(),
                 html_content: page.content.clone(),
                 attachments: Vec::new(),
-                tags: Vec::new(),
+                tags: page.tags.clone(),
+                publish_at: None,
+                raw_content: String::new(),
+                series: None,
+                series_order: None,
+                cover: None,
             };
             items.push(item);
         }
         
         let category = Category {
+            title: titles.get(category_name.as_str()).map_or_else(|| category_name.clone(), |t| t.to_string()),
             name: category_name.clone(),
             url: category_name.clone(),
             index_path: std::path::PathBuf::new(),
@@ -640,6 +2002,8 @@ fn convert_pages_to_site_content(pages: &[crate::storage::Page]) -> SiteContent
 
 /// Configure routes
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
+    STARTED_AT.get_or_init(Instant::now);
+
     // Configure admin routes first to avoid being matched by wildcard route
     admin::configure_admin_routes(cfg);
 
@@ -653,7 +2017,15 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         .route("/api/analytics/popular-pages", web::get().to(analytics::get_popular_pages))
         .route("/api/analytics/total-stats", web::get().to(analytics::get_total_stats))
         .route("/api/analytics/search-keywords", web::get().to(analytics::get_search_keywords))
+        .route("/api/analytics/device-stats", web::get().to(analytics::get_device_stats))
+        .route("/api/analytics/countries", web::get().to(analytics::get_country_stats))
+        .route("/api/analytics/page/{slug}", web::get().to(analytics::get_page_analytics))
+        .route("/api/analytics/heatmap", web::get().to(analytics::get_hourly_heatmap))
+        .route("/api/analytics/hourly", web::get().to(analytics::get_hourly_breakdown))
+        .route("/api/analytics/realtime", web::get().to(analytics::get_realtime_visitors))
+        .route("/api/analytics/realtime/stream", web::get().to(analytics::stream_realtime_visitors))
         .route("/api/analytics/record-visit/{page_slug:.*}", web::post().to(analytics::record_visit))
+        .route("/api/admin/analytics/export", web::get().to(analytics::export_analytics))
         // Comments API endpoints
         .route("/api/comments/{slug}", web::get().to(get_comments))
         .route("/api/comments", web::post().to(add_comment))
@@ -661,11 +2033,721 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         .route("/api/recommendations/{url:.*}", web::get().to(get_recommendations))
         .route("/api/popular-content", web::get().to(get_popular_content))
         .route("/api/latest-content", web::get().to(get_latest_content))
+        // View count API endpoint
+        .route("/api/stats/views/{slug}", web::get().to(get_view_count))
         // Search API endpoint
         .route("/api/search", web::get().to(search_content))
+        .route("/api/v1/search", web::get().to(search_api))
+        .route("/api/v1/search", web::method(actix_web::http::Method::OPTIONS).to(search_api_preflight))
+        // Tag API endpoints
+        .route("/api/tags", web::get().to(get_tags))
+        .route("/api/tags/cloud", web::get().to(get_tag_cloud))
+        .route("/api/items", web::get().to(get_items_by_tag))
+        // API documentation
+        .route("/api/openapi.json", web::get().to(openapi::openapi_json))
+        .route("/api/docs", web::get().to(openapi::api_docs))
+        // Redirect management API
+        .route("/api/admin/redirects", web::get().to(redirects::get_redirects))
+        .route("/api/admin/redirects", web::post().to(redirects::add_redirect))
+        .route("/api/admin/redirects", web::delete().to(redirects::delete_redirect))
+        .route("/api/admin/notifications/test", web::post().to(notifications::test_notification))
+        .route("/api/admin/webhooks/test", web::post().to(notifications::test_compile_webhooks))
         // Main site routes
         .route("/", web::get().to(index))
+        .route("/robots.txt", web::get().to(robots_txt))
+        .route("/feed/{category_slug}.xml", web::get().to(category_feed))
         .route("/search", web::get().to(search_page))
+        .route("/tags", web::get().to(tags_page))
         .route("/attachment/{filename:.*}", web::get().to(serve_attachment))
+        .route(
+            "/{path:.*}",
+            web::get()
+                .guard(actix_web::guard::fn_guard(redirects::matches_redirect))
+                .to(redirects::redirect),
+        )
         .route("/{path:.*}", web::get().to(serve_content));
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StoredAttachment;
+    use actix_web::{test as actix_test, App};
+
+    fn setup(db_path: &str, filename: &str, mime_type: &str, data: &[u8]) -> Config {
+        let _ = std::fs::remove_file(db_path);
+        let storage = StorageDB::new(db_path).unwrap();
+        storage
+            .save_attachment(&StoredAttachment {
+                id: format!("attachment-{}", filename),
+                slug: "item".to_string(),
+                filename: filename.to_string(),
+                original_name: filename.to_string(),
+                mime_type: mime_type.to_string(),
+                file_data: data.to_vec(),
+                file_size: data.len(),
+                updated_at: chrono::Utc::now().to_rfc3339(),
+                content_hash: "abc123".to_string(),
+            })
+            .unwrap();
+
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config
+    }
+
+    #[actix_web::test]
+    async fn test_attachment_etag_returns_304_without_blob() {
+        let db_path = "/tmp/test_attachment_etag.db";
+        let config = setup(db_path, "photo_1.jpg", "image/jpeg", b"jpegdata");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/attachment/{filename:.*}", web::get().to(serve_attachment)),
+        )
+        .await;
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/attachment/photo_1.jpg").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let etag = resp.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+        assert_eq!(etag, "\"abc123\"");
+        assert_eq!(actix_test::read_body(resp).await.as_ref(), b"jpegdata");
+
+        // Empty the blob: any read of the content now fails with a 500
+        rusqlite::Connection::open(db_path)
+            .unwrap()
+            .execute("UPDATE attachments SET file_data = x''", [])
+            .unwrap();
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri("/attachment/photo_1.jpg")
+                .insert_header((header::IF_NONE_MATCH, etag))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 304);
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/attachment/photo_1.jpg").to_request()).await;
+        assert_eq!(resp.status(), 500);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_health_check_databases() {
+        let root = std::path::PathBuf::from("/tmp/test_health_check");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let mut config = Config::default();
+        config.paths.storage_db = root.join("storage.db");
+        config.paths.comments_db = root.join("comments.db");
+        config.paths.analytics_db = root.join("analytics.db");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .route("/health", web::get().to(health_check)),
+        )
+        .await;
+        let get = |uri: &str| actix_test::TestRequest::get().uri(uri).to_request();
+
+        // Missing storage database
+        let resp = actix_test::call_service(&app, get("/health")).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["databases"]["storage"]["ok"], false);
+
+        // The cheap probe skips the checks
+        let body: serde_json::Value = actix_test::call_and_read_body_json(&app, get("/health?verbose=false")).await;
+        assert_eq!(body["status"], "ok");
+        assert!(body.get("databases").is_none());
+
+        StorageDB::new(&config.paths.storage_db.to_string_lossy()).unwrap();
+        let resp = actix_test::call_service(&app, get("/health")).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(body["databases"]["storage"]["pages"], 0);
+        assert_eq!(body["databases"]["comments"]["comments"], 0);
+        assert_eq!(body["databases"]["analytics"]["ok"], true);
+
+        // A corrupt file is reported instead of a static ok
+        std::fs::write(&config.paths.analytics_db, "not a database").unwrap();
+        let resp = actix_test::call_service(&app, get("/health")).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["databases"]["analytics"]["ok"], false);
+        assert_eq!(body["databases"]["storage"]["ok"], true);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[actix_web::test]
+    async fn test_missing_pages_use_not_found_template() {
+        let db_path = "/tmp/test_not_found_page.db";
+        let _ = std::fs::remove_file(db_path);
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config.paths.templates_dir = std::path::PathBuf::from("/tmp/test_not_found_page_templates");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .route("/attachment/{filename:.*}", web::get().to(serve_attachment))
+                .route("/{path:.*}", web::get().to(serve_content)),
+        )
+        .await;
+
+        // Before the first compile there is only the plain-text fallback
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/nowhere").to_request()).await;
+        assert_eq!(resp.status(), 404);
+        assert_eq!(actix_test::read_body(resp).await.as_ref(), b"Page not found");
+
+        let recent = vec![crate::scanner::ItemLink { title: "Apple".to_string(), url: "fruit-apple".to_string() }];
+        let html = TemplateRenderer::new(&config).unwrap().render_not_found(&recent).unwrap();
+        StorageDB::new(db_path)
+            .unwrap()
+            .save_page(&crate::storage::Page {
+                id: NOT_FOUND_SLUG.to_string(),
+                slug: NOT_FOUND_SLUG.to_string(),
+                page_type: PageType::Index,
+                title: String::new(),
+                content: html,
+                category: None,
+                updated_at: String::new(),
+                publish_at: None,
+                tags: Vec::new(),
+                raw_content: None,
+                series: None,
+                series_order: None,
+                item_name: None,
+                excerpt: None,
+            })
+            .unwrap();
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/api/nowhere").to_request()).await;
+        assert_eq!(resp.status(), 404);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["status"], "error");
+
+        for uri in ["/nowhere", "/attachment/missing.png", "/404"] {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(resp.status(), 404, "{}", uri);
+            assert!(resp.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("text/html"));
+            let body = String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap();
+            assert!(body.contains("action=\"/search\""));
+            assert!(body.contains("<a href=\"/fruit-apple\">Apple</a>"));
+        }
+
+        // The page language follows ?lang=, then the language cookie
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/nowhere?lang=en").to_request()).await;
+        let body = String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("<html lang=\"en\">"));
+        assert!(body.contains("<h1>Page not found</h1>"));
+        let request = actix_test::TestRequest::get()
+            .uri("/nowhere")
+            .cookie(actix_web::cookie::Cookie::new("language", "en"))
+            .to_request();
+        let body = String::from_utf8(actix_test::read_body(actix_test::call_service(&app, request).await).await.to_vec()).unwrap();
+        assert!(body.contains("<h1>Page not found</h1>"));
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/nowhere").to_request()).await;
+        let body = String::from_utf8(actix_test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("<h1>页面不存在</h1>"));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_page_conditional_get() {
+        let db_path = "/tmp/test_page_conditional_get.db";
+        let _ = std::fs::remove_file(db_path);
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config.server.page_cache_seconds = 300;
+        let storage = StorageDB::new(db_path).unwrap();
+        let save = |slug: &str, page_type: PageType| {
+            storage
+                .save_page(&crate::storage::Page {
+                    id: slug.to_string(),
+                    slug: slug.to_string(),
+                    page_type,
+                    title: slug.to_string(),
+                    content: format!("<p>{}</p>", slug),
+                    category: None,
+                    updated_at: String::new(),
+                    publish_at: None,
+                    tags: Vec::new(),
+                    raw_content: None,
+                    series: None,
+                    series_order: None,
+                    item_name: None,
+                    excerpt: None,
+                })
+                .unwrap()
+        };
+        save("index", PageType::Index);
+        save("fruit-apple", PageType::Item);
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(Mutex::new(RenderCache::new(10))))
+                .route("/", web::get().to(index))
+                .route("/{path:.*}", web::get().to(serve_content)),
+        )
+        .await;
+        let get = |uri: &str, etag: Option<&str>| {
+            let mut request = actix_test::TestRequest::get().uri(uri);
+            if let Some(etag) = etag {
+                request = request.insert_header((header::IF_NONE_MATCH, etag.to_string()));
+            }
+            request.to_request()
+        };
+
+        for uri in ["/", "/fruit-apple"] {
+            let resp = actix_test::call_service(&app, get(uri, None)).await;
+            assert_eq!(resp.status(), 200, "{}", uri);
+            assert_eq!(resp.headers().get(header::CACHE_CONTROL).unwrap(), "public, max-age=300");
+            assert!(resp.headers().contains_key(header::LAST_MODIFIED), "{}", uri);
+            let etag = resp.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+            assert!(etag.starts_with("W/\""), "{}", etag);
+
+            // Twice, so the second round trip is answered next to the render cache
+            for _ in 0..2 {
+                let resp = actix_test::call_service(&app, get(uri, Some(&etag))).await;
+                assert_eq!(resp.status(), 304, "{}", uri);
+                assert_eq!(resp.headers().get(header::ETAG).unwrap().to_str().unwrap(), etag);
+                assert!(actix_test::read_body(resp).await.is_empty());
+            }
+
+            // The HTML differs per language, and so does the ETag
+            let resp = actix_test::call_service(&app, get(&format!("{}?lang=en", uri), Some(&etag))).await;
+            assert_eq!(resp.status(), 200, "{}", uri);
+        }
+
+        // A recompile rewrites updated_at and busts the ETag
+        let resp = actix_test::call_service(&app, get("/fruit-apple", None)).await;
+        let etag = resp.headers().get(header::ETAG).unwrap().to_str().unwrap().to_string();
+        save("fruit-apple", PageType::Item);
+        let resp = actix_test::call_service(&app, get("/fruit-apple", Some(&etag))).await;
+        assert_eq!(resp.status(), 200);
+        assert_ne!(resp.headers().get(header::ETAG).unwrap().to_str().unwrap(), etag);
+        assert_eq!(actix_test::read_body(resp).await.as_ref(), b"<p>fruit-apple</p>");
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_precompressed_pages() {
+        use std::io::{Read, Write};
+
+        let db_path = "/tmp/test_precompressed_pages.db";
+        let _ = std::fs::remove_file(db_path);
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        let mut storage = StorageDB::new(db_path).unwrap();
+        let page = crate::storage::Page {
+            id: "fruit-apple".to_string(),
+            slug: "fruit-apple".to_string(),
+            page_type: PageType::Item,
+            title: "Apple".to_string(),
+            content: "<p>apple</p>".to_string(),
+            category: None,
+            updated_at: String::new(),
+            publish_at: None,
+            tags: Vec::new(),
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        storage.save_page(&page).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"<p>apple</p>").unwrap();
+        let gzip = encoder.finish().unwrap();
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder.write_all(b"<p>apple</p>").unwrap();
+        encoder.flush().unwrap();
+        let lang = default_language(&config);
+        storage.save_compressed_pages(lang, &[("fruit-apple".to_string(), gzip, encoder.into_inner())]).unwrap();
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(Mutex::new(RenderCache::new(10))))
+                .route("/{path:.*}", web::get().to(serve_content)),
+        )
+        .await;
+        let get = |uri: &str, encoding: &str| {
+            actix_test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT_ENCODING, encoding.to_string()))
+                .to_request()
+        };
+
+        // Twice: from storage, then from the render cache
+        for _ in 0..2 {
+            let resp = actix_test::call_service(&app, get("/fruit-apple", "br;q=1.0, gzip;q=0.8")).await;
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "br");
+            let body = actix_test::read_body(resp).await;
+            let mut html = String::new();
+            brotli::Decompressor::new(body.as_ref(), 4096).read_to_string(&mut html).unwrap();
+            assert_eq!(html, "<p>apple</p>");
+
+            for encoding in ["gzip", "br;q=0, gzip"] {
+                let resp = actix_test::call_service(&app, get("/fruit-apple", encoding)).await;
+                assert_eq!(resp.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip", "{}", encoding);
+                let body = actix_test::read_body(resp).await;
+                let mut html = String::new();
+                flate2::read::GzDecoder::new(body.as_ref()).read_to_string(&mut html).unwrap();
+                assert_eq!(html, "<p>apple</p>");
+            }
+        }
+
+        // Clients without br or gzip, and other languages, get the plain HTML
+        let other = if lang == "en" { "zh-CN" } else { "en" };
+        for (uri, encoding) in [("/fruit-apple", "identity"), ("/fruit-apple", "gzip;q=0"), (&format!("/fruit-apple?lang={}", other)[..], "br, gzip")] {
+            let resp = actix_test::call_service(&app, get(uri, encoding)).await;
+            assert!(!resp.headers().contains_key(header::CONTENT_ENCODING), "{} {}", uri, encoding);
+            assert_eq!(actix_test::read_body(resp).await.as_ref(), b"<p>apple</p>");
+        }
+
+        // Saving the page again drops the stale compressed copy
+        storage.save_page(&crate::storage::Page { content: "<p>apple v2</p>".to_string(), ..page }).unwrap();
+        assert_eq!(storage.get_compressed_page("fruit-apple", lang).unwrap(), None);
+        let resp = actix_test::call_service(&app, get("/fruit-apple", "gzip")).await;
+        assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(actix_test::read_body(resp).await.as_ref(), b"<p>apple v2</p>");
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_recompile_while_running_returns_accepted() {
+        let (key, hash) = admin::generate_api_key();
+        let mut config = Config::default();
+        config.server.api_keys = vec![hash];
+        let state = web::Data::new(Mutex::new(admin::CompilerState::new()));
+        let job_id = state.lock().unwrap().try_start().unwrap();
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .app_data(state.clone())
+                .route("/api/recompile", web::post().to(recompile)),
+        )
+        .await;
+
+        // Anonymous clients cannot start compiles
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::post().uri("/api/recompile").to_request()).await;
+        assert_eq!(resp.status(), 401);
+
+        let req = actix_test::TestRequest::post()
+            .uri("/api/recompile")
+            .insert_header(("Authorization", format!("Bearer {}", key)))
+            .to_request();
+        let resp = actix_test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["job_id"], job_id);
+        assert!(state.lock().unwrap().is_compiling);
+    }
+
+    #[actix_web::test]
+    async fn test_tag_endpoints() {
+        let db_path = "/tmp/test_tag_endpoints.db";
+        let _ = std::fs::remove_file(db_path);
+        let storage = StorageDB::new(db_path).unwrap();
+        for (slug, tags) in [("fruit-apple", vec!["red", "sweet"]), ("fruit-cherry", vec!["red"])] {
+            storage
+                .save_page(&crate::storage::Page {
+                    id: format!("item-{}", slug),
+                    slug: slug.to_string(),
+                    page_type: PageType::Item,
+                    title: slug.to_string(),
+                    content: String::new(),
+                    category: Some("fruit".to_string()),
+                    updated_at: String::new(),
+                    publish_at: None,
+                    tags: tags.into_iter().map(String::from).collect(),
+                    raw_content: None,
+                    series: None,
+                    series_order: None,
+                    item_name: None,
+                    excerpt: None,
+                })
+                .unwrap();
+        }
+
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config.paths.templates_dir = std::path::PathBuf::from("/tmp/test_tag_endpoints_templates");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/api/tags", web::get().to(get_tags))
+                .route("/api/tags/cloud", web::get().to(get_tag_cloud))
+                .route("/api/items", web::get().to(get_items_by_tag))
+                .route("/tags", web::get().to(tags_page)),
+        )
+        .await;
+
+        let tags: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, actix_test::TestRequest::get().uri("/api/tags").to_request()).await;
+        assert_eq!(tags, serde_json::json!([{"tag": "red", "count": 2}, {"tag": "sweet", "count": 1}]));
+
+        let cloud: serde_json::Value =
+            actix_test::call_and_read_body_json(&app, actix_test::TestRequest::get().uri("/api/tags/cloud").to_request()).await;
+        assert_eq!(cloud[0]["weight"], 1.0);
+        assert_eq!(cloud[1]["weight"], 0.0);
+
+        let items: serde_json::Value = actix_test::call_and_read_body_json(
+            &app,
+            actix_test::TestRequest::get().uri("/api/items?tag=sweet&page=1").to_request(),
+        )
+        .await;
+        assert_eq!(items["total"], 1);
+        assert_eq!(items["items"][0]["slug"], "fruit-apple");
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/tags?tag=red").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let body = actix_test::read_body(resp).await;
+        let html = std::str::from_utf8(&body).unwrap();
+        assert!(html.contains("/tags?tag=sweet"));
+        assert!(html.contains("href=\"/fruit-cherry\""));
+
+        let _ = std::fs::remove_file(db_path);
+        let _ = std::fs::remove_dir_all("/tmp/test_tag_endpoints_templates");
+    }
+
+    #[actix_web::test]
+    async fn test_search_api_v1() {
+        let db_path = "/tmp/test_search_api_v1.db";
+        let _ = std::fs::remove_file(db_path);
+        let storage = StorageDB::new(db_path).unwrap();
+        for (slug, category, tags) in [
+            ("rust-intro", "tech", vec!["rust", "web"]),
+            ("rust-async", "tech", vec!["rust"]),
+            ("rust-garden", "life", vec!["garden"]),
+        ] {
+            storage
+                .save_page(&crate::storage::Page {
+                    id: format!("item-{}", slug),
+                    slug: slug.to_string(),
+                    page_type: PageType::Item,
+                    title: slug.to_string(),
+                    content: format!("<p>All about <strong>rust</strong> &amp; {}</p>", slug),
+                    category: Some(category.to_string()),
+                    updated_at: String::new(),
+                    publish_at: None,
+                    tags: tags.into_iter().map(String::from).collect(),
+                    raw_content: None,
+                    series: None,
+                    series_order: None,
+                    item_name: None,
+                    excerpt: None,
+                })
+                .unwrap();
+        }
+
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config.server.cors_origins = vec!["https://example.com".to_string()];
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/api/search", web::get().to(search_content))
+                .route("/api/v1/search", web::get().to(search_api))
+                .route("/api/v1/search", web::method(actix_web::http::Method::OPTIONS).to(search_api_preflight)),
+        )
+        .await;
+
+        let body: serde_json::Value = actix_test::call_and_read_body_json(
+            &app,
+            actix_test::TestRequest::get().uri("/api/search?q=rust&limit=2&page=2").to_request(),
+        )
+        .await;
+        assert_eq!(body["count"], 1);
+        assert_eq!(
+            body["pagination"],
+            serde_json::json!({"current_page": 2, "total_pages": 2, "total_count": 3, "has_next": false, "has_prev": true})
+        );
+        assert_eq!(
+            search_page_url(&[("q", "a b"), ("page", "2"), ("category", "tech")].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+            "/search?category=tech&q=a%20b&page="
+        );
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri("/api/v1/search?q=rust&category=tech&limit=1&page=2")
+                .insert_header((header::ORIGIN, "https://example.com"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "https://example.com");
+        let body: serde_json::Value = actix_test::read_body_json(resp).await;
+        assert_eq!(body["pagination"], serde_json::json!({"page": 2, "limit": 1, "total_pages": 2, "total_count": 2}));
+        assert_eq!(body["results"].as_array().unwrap().len(), 1);
+        assert!(body["results"][0]["snippet"].as_str().unwrap().contains("All about rust &"));
+        assert!(body["results"][0]["score"].as_f64().unwrap() > 0.0);
+
+        let body: serde_json::Value = actix_test::call_and_read_body_json(
+            &app,
+            actix_test::TestRequest::get().uri("/api/v1/search?tag=web").to_request(),
+        )
+        .await;
+        assert_eq!(body["pagination"]["total_count"], 1);
+        assert_eq!(body["results"][0]["slug"], "rust-intro");
+        assert_eq!(body["results"][0]["tags"], serde_json::json!(["rust", "web"]));
+
+        // Origins outside the allowlist get no CORS header
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri("/api/v1/search?q=rust")
+                .insert_header((header::ORIGIN, "https://evil.example"))
+                .to_request(),
+        )
+        .await;
+        assert!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::default()
+                .method(actix_web::http::Method::OPTIONS)
+                .uri("/api/v1/search")
+                .insert_header((header::ORIGIN, "https://example.com"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 204);
+        assert_eq!(resp.headers().get(header::ACCESS_CONTROL_ALLOW_METHODS).unwrap(), "GET, OPTIONS");
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/api/v1/search").to_request()).await;
+        assert_eq!(resp.status(), 400);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[actix_web::test]
+    async fn test_category_feed() {
+        let db_path = "/tmp/test_category_feed.db";
+        let content_dir = std::path::PathBuf::from("/tmp/test_category_feed_content");
+        let _ = std::fs::remove_file(db_path);
+        let _ = std::fs::remove_dir_all(&content_dir);
+        std::fs::create_dir_all(content_dir.join("fruit")).unwrap();
+        std::fs::write(
+            content_dir.join("fruit/index.md"),
+            "---\ntitle: Fruit\ndescription: All about <fruit> & more\n---\n",
+        )
+        .unwrap();
+
+        let storage = StorageDB::new(db_path).unwrap();
+        let page = |slug: &str, page_type: PageType, category: Option<&str>, publish_at: Option<String>| crate::storage::Page {
+            id: slug.to_string(),
+            slug: slug.to_string(),
+            page_type,
+            title: format!("Title {}", slug),
+            content: String::new(),
+            category: category.map(String::from),
+            updated_at: String::new(),
+            publish_at,
+            tags: vec!["red".to_string()],
+            raw_content: None,
+            series: None,
+            series_order: None,
+            item_name: None,
+            excerpt: None,
+        };
+        let later = (chrono::Utc::now() + chrono::Duration::hours(1)).format("%Y-%m-%d %H:%M:%S").to_string();
+        storage.save_page(&page("fruit", PageType::Category, None, None)).unwrap();
+        storage.save_page(&page("fruit-apple", PageType::Item, Some("fruit"), None)).unwrap();
+        storage.save_page(&page("fruit-cherry", PageType::Item, Some("fruit"), None)).unwrap();
+        storage.save_page(&page("fruit-plum", PageType::Item, Some("fruit"), Some(later))).unwrap();
+        storage.save_page(&page("veg-leek", PageType::Item, Some("veg"), None)).unwrap();
+
+        let mut config = Config::default();
+        config.paths.storage_db = std::path::PathBuf::from(db_path);
+        config.paths.content_dir = content_dir.clone();
+        config.site.url = "https://example.com/".to_string();
+        config.site.feed_max_items = 1;
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/feed/{category_slug}.xml", web::get().to(category_feed)),
+        )
+        .await;
+
+        let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/feed/fruit.xml").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().get(header::CONTENT_TYPE).unwrap().to_str().unwrap().starts_with("application/rss+xml"));
+
+        let body = actix_test::read_body(resp).await;
+        let channel = rss::Channel::read_from(&body[..]).unwrap();
+        assert_eq!(channel.link(), "https://example.com/fruit");
+        assert_eq!(channel.description(), "All about <fruit> & more");
+        // Limited by feed_max_items; scheduled and other-category items never appear
+        assert_eq!(channel.items().len(), 1);
+        let item = &channel.items()[0];
+        assert!(item.link().unwrap().starts_with("https://example.com/fruit-"));
+        assert_eq!(item.categories()[0].name(), "red");
+        assert!(item.pub_date().is_some());
+
+        for uri in ["/feed/veg.xml", "/feed/fruit-apple.xml"] {
+            let resp = actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(resp.status(), 404, "{}", uri);
+        }
+
+        let _ = std::fs::remove_file(db_path);
+        let _ = std::fs::remove_dir_all(&content_dir);
+    }
+
+    #[actix_web::test]
+    async fn test_attachment_range_request() {
+        let db_path = "/tmp/test_attachment_range.db";
+        let config = setup(db_path, "clip_1.mp4", "video/mp4", b"0123456789");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/attachment/{filename:.*}", web::get().to(serve_attachment)),
+        )
+        .await;
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri("/attachment/clip_1.mp4")
+                .insert_header((header::RANGE, "bytes=2-5"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 206);
+        assert_eq!(resp.headers().get(header::CONTENT_RANGE).unwrap(), "bytes 2-5/10");
+        assert_eq!(actix_test::read_body(resp).await.as_ref(), b"2345");
+
+        let resp = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::get()
+                .uri("/attachment/clip_1.mp4")
+                .insert_header((header::RANGE, "bytes=20-"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 416);
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn test_parse_byte_range() {
+        assert_eq!(parse_byte_range("bytes=0-", 10), Some(Ok((0, 9))));
+        assert_eq!(parse_byte_range("bytes=-3", 10), Some(Ok((7, 9))));
+        assert_eq!(parse_byte_range("bytes=5-100", 10), Some(Ok((5, 9))));
+        assert_eq!(parse_byte_range("bytes=10-", 10), Some(Err(())));
+        assert_eq!(parse_byte_range("bytes=0-1,4-5", 10), None);
+        assert_eq!(parse_byte_range("items=0-1", 10), None);
+    }
+}